@@ -0,0 +1,1107 @@
+//! ACME签发模块
+//! 将生成结果CSV中的CSR提交给ACME CA（RFC 8555）签发证书：为每个CSR创建订单，通过DNS-01验证域名，
+//! 以预先生成的CSR完成订单，输出在原有列之后增加certificate列（证书）和chain列（中间证书）的CSV文件
+//!
+//! DNS-01验证需要在 `_acme-challenge.<域名>` 添加TXT记录，由 [`DnsProvider`] 完成：内置执行命令（command）
+//! 和HTTP请求（webhook）两种方式，其他程序可实现该trait接入自己的DNS服务。
+//! 所有订单的TXT记录先一并添加，等待生效后统一验证，验证结束后（包括失败时）删除
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::base64;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::{hash, MessageDigest};
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::sign::Signer;
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::hook::shell_command;
+use crate::inspect::dns_names;
+use crate::output::{derived_csv_path, write_atomic, ResultCsv};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 查询订单和授权状态的间隔和次数
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const POLL_ATTEMPTS: usize = 60;
+
+/// ACME签发参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct AcmeParams {
+    /// 待签发的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _acme 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// ACME目录地址，如 https://acme.example.com/directory
+    pub directory_url: String,
+    /// ACME服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+    /// 账户私钥路径 (PEM，EC P-256)，文件不存在时生成并保存
+    pub account_key_path: String,
+    /// 账户联系邮箱 (可选)
+    #[serde(default)]
+    pub contact_email: String,
+    /// 外部账户绑定(EAB)的密钥ID (CA要求时填写)
+    #[serde(default)]
+    pub eab_kid: String,
+    /// 外部账户绑定的HMAC密钥 (base64url)
+    #[serde(default)]
+    pub eab_hmac_key: String,
+    /// DNS服务商: command (执行命令), webhook (HTTP请求)
+    pub dns_provider: String,
+    /// command时执行的命令，环境变量 CSR_DNS_ACTION (add/remove)、CSR_DNS_NAME、CSR_DNS_VALUE
+    /// 为操作、记录名和记录值
+    #[serde(default)]
+    pub dns_command: String,
+    /// webhook时的地址，以POST发送 {"action", "name", "value"} JSON
+    #[serde(default)]
+    pub dns_webhook_url: String,
+    /// 添加TXT记录后等待DNS生效的秒数
+    #[serde(default)]
+    pub propagation_seconds: u64,
+}
+
+/// ACME签发结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct AcmeResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// DNS-01验证使用的DNS服务商
+pub trait DnsProvider {
+    /// 添加TXT记录；同一记录名可能有多个值（如同时验证 example.com 和 *.example.com），须追加而不是替换
+    fn add_txt(&self, name: &str, value: &str) -> Result<(), BatchError>;
+    /// 删除添加的TXT记录
+    fn remove_txt(&self, name: &str, value: &str) -> Result<(), BatchError>;
+}
+
+/// 执行命令管理TXT记录
+struct CommandDns(String);
+
+impl CommandDns {
+    fn run(&self, action: &str, name: &str, value: &str) -> Result<(), BatchError> {
+        let output = shell_command(&self.0)
+            .env("CSR_DNS_ACTION", action)
+            .env("CSR_DNS_NAME", name)
+            .env("CSR_DNS_VALUE", value)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| BatchError::parameter("dns_command", format!("无法执行命令: {}", e)))?;
+        if !output.status.success() {
+            let message = format!(
+                "{} {} 失败（{}）: {}",
+                action,
+                name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return Err(BatchError::Other { message });
+        }
+        Ok(())
+    }
+}
+
+impl DnsProvider for CommandDns {
+    fn add_txt(&self, name: &str, value: &str) -> Result<(), BatchError> {
+        self.run("add", name, value)
+    }
+
+    fn remove_txt(&self, name: &str, value: &str) -> Result<(), BatchError> {
+        self.run("remove", name, value)
+    }
+}
+
+/// 向HTTP接口发送请求管理TXT记录
+struct WebhookDns(String);
+
+impl WebhookDns {
+    fn send(&self, action: &str, name: &str, value: &str) -> Result<(), BatchError> {
+        let body = json!({ "action": action, "name": name, "value": value });
+        ureq::post(&self.0)
+            .timeout(HTTP_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(|e| BatchError::Other {
+                message: format!("{} {} 失败: {}", action, name, e),
+            })?;
+        Ok(())
+    }
+}
+
+impl DnsProvider for WebhookDns {
+    fn add_txt(&self, name: &str, value: &str) -> Result<(), BatchError> {
+        self.send("add", name, value)
+    }
+
+    fn remove_txt(&self, name: &str, value: &str) -> Result<(), BatchError> {
+        self.send("remove", name, value)
+    }
+}
+
+/// 按参数创建内置的DNS服务商
+fn dns_provider(params: &AcmeParams) -> Result<Box<dyn DnsProvider>, BatchError> {
+    let required = |field: &str, value: &str| {
+        if value.trim().is_empty() {
+            Err(BatchError::parameter(field, "未填写"))
+        } else {
+            Ok(value.trim().to_string())
+        }
+    };
+    match params.dns_provider.as_str() {
+        "command" => Ok(Box::new(CommandDns(required(
+            "dns_command",
+            &params.dns_command,
+        )?))),
+        "webhook" => Ok(Box::new(WebhookDns(required(
+            "dns_webhook_url",
+            &params.dns_webhook_url,
+        )?))),
+        other => Err(BatchError::parameter(
+            "dns_provider",
+            format!("不支持的DNS服务商: {}", other),
+        )),
+    }
+}
+
+/// 不带填充的base64url编码（RFC 7515）
+fn b64url(data: &[u8]) -> String {
+    base64::encode_block(data)
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_")
+}
+
+/// base64url解码，接受带或不带填充的输入
+fn b64url_decode(input: &str) -> Result<Vec<u8>> {
+    let mut standard = input.trim().replace('-', "+").replace('_', "/");
+    while !standard.len().is_multiple_of(4) {
+        standard.push('=');
+    }
+    Ok(base64::decode_block(&standard)?)
+}
+
+/// ACME账户密钥（EC P-256，使用ES256签名）
+struct AccountKey {
+    key: PKey<Private>,
+    /// 公钥的JWK
+    jwk: Value,
+    /// JWK指纹（RFC 7638），用于计算DNS-01的记录值
+    thumbprint: String,
+}
+
+impl AccountKey {
+    /// 读取账户私钥，文件不存在时生成并保存
+    fn load_or_create(path: &str) -> Result<Self> {
+        if path.trim().is_empty() {
+            return Err(BatchError::parameter("account_key_path", "未填写").into());
+        }
+        let key = if Path::new(path).exists() {
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            PKey::private_key_from_pem(&pem)
+                .map_err(|e| BatchError::parameter("account_key_path", e))?
+        } else {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+            let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+            write_atomic(Path::new(path), &key.private_key_to_pem_pkcs8()?)?;
+            key
+        };
+        Self::new(key)
+    }
+
+    fn new(key: PKey<Private>) -> Result<Self> {
+        let ec = key
+            .ec_key()
+            .ok()
+            .filter(|ec| ec.group().curve_name() == Some(Nid::X9_62_PRIME256V1))
+            .ok_or_else(|| BatchError::parameter("account_key_path", "账户私钥须为EC P-256"))?;
+        let mut ctx = BigNumContext::new()?;
+        let (mut x, mut y) = (BigNum::new()?, BigNum::new()?);
+        ec.public_key()
+            .affine_coordinates(ec.group(), &mut x, &mut y, &mut ctx)?;
+        let (x, y) = (b64url(&x.to_vec_padded(32)?), b64url(&y.to_vec_padded(32)?));
+        // 指纹按RFC 7638的字段顺序（字典序）计算
+        let canonical = format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y);
+        let thumbprint = b64url(&hash(MessageDigest::sha256(), canonical.as_bytes())?);
+        Ok(Self {
+            key,
+            jwk: json!({ "crv": "P-256", "kty": "EC", "x": x, "y": y }),
+            thumbprint,
+        })
+    }
+
+    /// ES256签名：DER编码的ECDSA签名转换为定长的 r||s
+    fn sign(&self, data: &[u8]) -> Result<String> {
+        let mut signer = Signer::new(MessageDigest::sha256(), &self.key)?;
+        signer.update(data)?;
+        let signature = EcdsaSig::from_der(&signer.sign_to_vec()?)?;
+        let mut raw = signature.r().to_vec_padded(32)?;
+        raw.extend(signature.s().to_vec_padded(32)?);
+        Ok(b64url(&raw))
+    }
+
+    /// 以JWS平铺格式签名，payload为None时为POST-as-GET
+    fn jws(&self, protected: &Value, payload: Option<&Value>) -> Result<String> {
+        let protected = b64url(protected.to_string().as_bytes());
+        let payload = payload.map_or(String::new(), |p| b64url(p.to_string().as_bytes()));
+        let signature = self.sign(format!("{}.{}", protected, payload).as_bytes())?;
+        Ok(
+            json!({ "protected": protected, "payload": payload, "signature": signature })
+                .to_string(),
+        )
+    }
+}
+
+/// ACME目录
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+/// ACME响应
+struct AcmeResponse {
+    location: Option<String>,
+    body: String,
+}
+
+impl AcmeResponse {
+    fn json(&self) -> Result<Value> {
+        serde_json::from_str(&self.body).map_err(|e| anyhow!("ACME服务器返回的JSON无效: {}", e))
+    }
+}
+
+/// ACME错误（RFC 7807）中的说明
+fn problem_detail(problem: &Value) -> String {
+    let detail = problem["detail"].as_str().unwrap_or("");
+    let kind = problem["type"].as_str().unwrap_or("");
+    match (detail.is_empty(), kind.is_empty()) {
+        (false, false) => format!("{} ({})", detail, kind),
+        (false, true) => detail.to_string(),
+        (true, false) => kind.to_string(),
+        (true, true) => problem.to_string(),
+    }
+}
+
+struct AcmeClient {
+    agent: ureq::Agent,
+    directory: Directory,
+    key: AccountKey,
+    /// 账户地址，创建账户前为空（使用jwk签名）
+    kid: String,
+    nonce: Option<String>,
+}
+
+impl AcmeClient {
+    /// 读取目录并创建（或找回）账户
+    fn connect(params: &AcmeParams) -> Result<Self> {
+        let mut builder = ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT);
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            let connector = native_tls::TlsConnector::builder()
+                .add_root_certificate(cert)
+                .build()?;
+            builder = builder.tls_connector(Arc::new(connector));
+        }
+        let agent = builder.build();
+        let directory: Directory = serde_json::from_str(
+            &agent
+                .get(&params.directory_url)
+                .call()
+                .map_err(|e| anyhow!("无法读取ACME目录 {}: {}", params.directory_url, e))?
+                .into_string()?,
+        )
+        .map_err(|e| anyhow!("ACME目录格式错误: {}", e))?;
+
+        let mut client = Self {
+            agent,
+            directory,
+            key: AccountKey::load_or_create(&params.account_key_path)?,
+            kid: String::new(),
+            nonce: None,
+        };
+        client.register(params)?;
+        Ok(client)
+    }
+
+    fn register(&mut self, params: &AcmeParams) -> Result<()> {
+        let mut payload = json!({ "termsOfServiceAgreed": true });
+        if !params.contact_email.trim().is_empty() {
+            payload["contact"] = json!([format!("mailto:{}", params.contact_email.trim())]);
+        }
+        if !params.eab_kid.trim().is_empty() {
+            payload["externalAccountBinding"] = self.external_account_binding(params)?;
+        }
+        let url = self.directory.new_account.clone();
+        let response = self.post(&url, Some(&payload))?;
+        self.kid = response
+            .location
+            .ok_or_else(|| anyhow!("ACME服务器未返回账户地址"))?;
+        Ok(())
+    }
+
+    /// 外部账户绑定：以HMAC密钥对账户公钥签名（RFC 8555 7.3.4）
+    fn external_account_binding(&self, params: &AcmeParams) -> Result<Value> {
+        let hmac_key = b64url_decode(&params.eab_hmac_key)
+            .map_err(|e| BatchError::parameter("eab_hmac_key", format!("base64url无效: {}", e)))?;
+        let protected = json!({
+            "alg": "HS256",
+            "kid": params.eab_kid.trim(),
+            "url": self.directory.new_account,
+        });
+        let protected = b64url(protected.to_string().as_bytes());
+        let payload = b64url(self.key.jwk.to_string().as_bytes());
+        let pkey = PKey::hmac(&hmac_key)?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+        signer.update(format!("{}.{}", protected, payload).as_bytes())?;
+        let signature = b64url(&signer.sign_to_vec()?);
+        Ok(json!({ "protected": protected, "payload": payload, "signature": signature }))
+    }
+
+    fn nonce(&mut self) -> Result<String> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let response = self
+            .agent
+            .head(&self.directory.new_nonce)
+            .call()
+            .map_err(|e| anyhow!("无法获取nonce: {}", e))?;
+        response
+            .header("Replay-Nonce")
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("ACME服务器未返回nonce"))
+    }
+
+    /// 发送签名的POST请求，payload为None时为POST-as-GET；nonce失效时重试一次
+    fn post(&mut self, url: &str, payload: Option<&Value>) -> Result<AcmeResponse> {
+        for attempt in 0..2 {
+            let mut protected = json!({ "alg": "ES256", "nonce": self.nonce()?, "url": url });
+            if self.kid.is_empty() {
+                protected["jwk"] = self.key.jwk.clone();
+            } else {
+                protected["kid"] = json!(self.kid);
+            }
+            let body = self.key.jws(&protected, payload)?;
+            let result = self
+                .agent
+                .post(url)
+                .set("Content-Type", "application/jose+json")
+                .set(
+                    "Accept",
+                    "application/json, application/pem-certificate-chain",
+                )
+                .send_string(&body);
+            let (response, failed) = match result {
+                Ok(response) => (response, false),
+                Err(ureq::Error::Status(_, response)) => (response, true),
+                Err(e) => return Err(anyhow!("请求 {} 失败: {}", url, e)),
+            };
+            self.nonce = response.header("Replay-Nonce").map(str::to_string);
+            let response = AcmeResponse {
+                location: response.header("Location").map(str::to_string),
+                body: response.into_string()?,
+            };
+            if !failed {
+                return Ok(response);
+            }
+            let problem: Value = serde_json::from_str(&response.body).unwrap_or(Value::Null);
+            let bad_nonce = problem["type"]
+                .as_str()
+                .is_some_and(|kind| kind.ends_with(":badNonce"));
+            if !(bad_nonce && attempt == 0) {
+                return Err(anyhow!("ACME服务器拒绝请求: {}", problem_detail(&problem)));
+            }
+        }
+        unreachable!("第二次请求总是返回")
+    }
+
+    /// 查询对象直到状态不再是pending中的状态，返回最后一次的对象
+    fn poll(&mut self, url: &str, pending: &[&str]) -> Result<Value> {
+        for attempt in 0..POLL_ATTEMPTS {
+            let object = self.post(url, None)?.json()?;
+            let status = object["status"].as_str().unwrap_or("");
+            if !pending.contains(&status) {
+                return Ok(object);
+            }
+            if attempt + 1 < POLL_ATTEMPTS {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+        let seconds = POLL_INTERVAL.as_secs() * POLL_ATTEMPTS as u64;
+        Err(anyhow!("等待 {} 超时（{}秒）", url, seconds))
+    }
+}
+
+/// 需要完成的DNS-01验证
+struct DnsChallenge {
+    /// 所属记录的序号（从1开始）
+    number: usize,
+    /// TXT记录名，如 _acme-challenge.example.com
+    name: String,
+    /// TXT记录值
+    value: String,
+    challenge_url: String,
+    authorization_url: String,
+}
+
+/// 待签发的一条记录
+struct PendingOrder {
+    record: StringRecord,
+    csr: X509Req,
+    order_url: String,
+    finalize_url: String,
+}
+
+/// 订单的域名：CSR备用名称中的DNS名称，没有时使用通用名称
+fn order_identifiers(req: &X509Req) -> Result<Vec<String>> {
+    let mut names = dns_names(req)?;
+    if names.is_empty() {
+        let cn = req
+            .subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .next()
+            .and_then(|entry| entry.data().as_utf8().ok())
+            .map(|cn| cn.to_string())
+            .filter(|cn| cn.contains('.'));
+        names.extend(cn);
+    }
+    if names.is_empty() {
+        return Err(anyhow!("CSR中没有可验证的DNS名称（备用名称或通用名称）"));
+    }
+    Ok(names)
+}
+
+/// 通过ACME CA为CSV中的每个CSR签发证书，DNS-01验证使用参数中的内置DNS服务商
+pub fn issue_acme(params: AcmeParams) -> Result<AcmeResult, BatchError> {
+    let provider = dns_provider(&params)?;
+    issue_acme_with(&params, provider.as_ref())
+}
+
+/// 通过ACME CA为CSV中的每个CSR签发证书，DNS-01验证使用指定的DNS服务商
+pub fn issue_acme_with(
+    params: &AcmeParams,
+    provider: &dyn DnsProvider,
+) -> Result<AcmeResult, BatchError> {
+    issue_records(params, provider).map_err(BatchError::from)
+}
+
+fn issue_records(params: &AcmeParams, provider: &dyn DnsProvider) -> Result<AcmeResult> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let csr_col = headers
+        .iter()
+        .position(|h| h == "csr")
+        .ok_or_else(|| BatchError::header("缺少csr列"))?;
+    // 重复签发时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "acme")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+
+    let mut client = AcmeClient::connect(params)?;
+
+    // 创建订单并收集待完成的验证
+    let mut orders = Vec::new();
+    let mut challenges = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let number = index + 1;
+        let record = record.map_err(|e| BatchError::record(number, format!("格式错误: {}", e)))?;
+        let csr = parse_csr(record.get(csr_col).unwrap_or(""))
+            .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+        let identifiers = order_identifiers(&csr).map_err(|e| BatchError::record(number, e))?;
+        let payload = json!({
+            "identifiers": identifiers
+                .iter()
+                .map(|name| json!({ "type": "dns", "value": name }))
+                .collect::<Vec<_>>(),
+        });
+        let url = client.directory.new_order.clone();
+        let response = client
+            .post(&url, Some(&payload))
+            .map_err(|e| BatchError::record(number, format!("创建订单失败: {}", e)))?;
+        let order = response.json()?;
+        let order_url = response
+            .location
+            .ok_or_else(|| anyhow!("ACME服务器未返回订单地址"))?;
+
+        for authorization_url in order["authorizations"].as_array().into_iter().flatten() {
+            let authorization_url = authorization_url.as_str().unwrap_or("").to_string();
+            let authorization = client.post(&authorization_url, None)?.json()?;
+            if authorization["status"] == "valid" {
+                continue;
+            }
+            let domain = authorization["identifier"]["value"].as_str().unwrap_or("");
+            let challenge = authorization["challenges"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|challenge| challenge["type"] == "dns-01")
+                .ok_or_else(|| {
+                    BatchError::record(number, format!("{} 不支持DNS-01验证", domain))
+                })?;
+            let token = challenge["token"].as_str().unwrap_or("");
+            let key_authorization = format!("{}.{}", token, client.key.thumbprint);
+            challenges.push(DnsChallenge {
+                number,
+                name: format!("_acme-challenge.{}", domain.trim_start_matches("*.")),
+                value: b64url(&hash(
+                    MessageDigest::sha256(),
+                    key_authorization.as_bytes(),
+                )?),
+                challenge_url: challenge["url"].as_str().unwrap_or("").to_string(),
+                authorization_url: authorization_url.clone(),
+            });
+        }
+        orders.push(PendingOrder {
+            record,
+            csr,
+            order_url,
+            finalize_url: order["finalize"].as_str().unwrap_or("").to_string(),
+        });
+    }
+
+    // 添加TXT记录并完成验证，之后删除已添加的记录
+    let mut added = Vec::new();
+    let validated = validate(&mut client, provider, params, &challenges, &mut added);
+    let cleanup_failed = added
+        .iter()
+        .filter(|challenge| {
+            provider
+                .remove_txt(&challenge.name, &challenge.value)
+                .is_err()
+        })
+        .count();
+    validated?;
+
+    // 中途签发失败时，已签发的证书和其余行照常写入输出文件
+    for (index, order) in orders.iter().enumerate() {
+        let number = index + 1;
+        let chain = match finalize(&mut client, order) {
+            Ok(chain) => chain,
+            Err(e) => {
+                let error = BatchError::record(number, format!("签发失败: {}", e)).into();
+                let remaining = orders[index..].iter().map(|order| &order.record);
+                return Err(output.abort(error, remaining, index));
+            }
+        };
+        let mut fields: Vec<&str> = order.record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &chain.0;
+        fields[chain_col] = &chain.1;
+        output.write_record(&fields)?;
+    }
+    output.finish()?;
+
+    let total = orders.len();
+    let mut message = format!("ACME CA签发 {} 个证书", total);
+    if cleanup_failed > 0 {
+        message.push_str(&format!(
+            "，{}条TXT记录删除失败，请手动删除",
+            cleanup_failed
+        ));
+    }
+    Ok(AcmeResult {
+        success: true,
+        message,
+        total,
+        output_path,
+    })
+}
+
+/// 添加所有TXT记录，等待生效后通知CA验证并等待验证结果；已添加的记录放入added以便删除
+fn validate<'a>(
+    client: &mut AcmeClient,
+    provider: &dyn DnsProvider,
+    params: &AcmeParams,
+    challenges: &'a [DnsChallenge],
+    added: &mut Vec<&'a DnsChallenge>,
+) -> Result<()> {
+    if challenges.is_empty() {
+        return Ok(());
+    }
+    for challenge in challenges {
+        provider
+            .add_txt(&challenge.name, &challenge.value)
+            .map_err(|e| anyhow!("添加TXT记录失败: {}", e))?;
+        added.push(challenge);
+    }
+    std::thread::sleep(Duration::from_secs(params.propagation_seconds));
+    for challenge in challenges {
+        client
+            .post(&challenge.challenge_url, Some(&json!({})))
+            .map_err(|e| BatchError::record(challenge.number, e))?;
+    }
+    for challenge in challenges {
+        let authorization = client.poll(&challenge.authorization_url, &["pending"])?;
+        if authorization["status"] != "valid" {
+            let error = authorization["challenges"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find_map(|c| c.get("error").map(problem_detail))
+                .unwrap_or_else(|| format!("状态为 {}", authorization["status"]));
+            let message = format!("{} 验证失败: {}", challenge.name, error);
+            return Err(BatchError::record(challenge.number, message).into());
+        }
+    }
+    Ok(())
+}
+
+/// 以CSR完成订单并下载证书，返回证书和中间证书的PEM
+fn finalize(client: &mut AcmeClient, order: &PendingOrder) -> Result<(String, String)> {
+    let mut state = client.poll(&order.order_url, &["pending"])?;
+    if state["status"] == "ready" {
+        let payload = json!({ "csr": b64url(&order.csr.to_der()?) });
+        client.post(&order.finalize_url, Some(&payload))?;
+        state = client.poll(&order.order_url, &["ready", "processing"])?;
+    }
+    if state["status"] != "valid" {
+        let error = state
+            .get("error")
+            .map(problem_detail)
+            .unwrap_or_else(|| format!("订单状态为 {}", state["status"]));
+        return Err(anyhow!(error));
+    }
+    let url = state["certificate"]
+        .as_str()
+        .ok_or_else(|| anyhow!("订单中没有证书地址"))?;
+    let pem = client.post(url, None)?.body;
+    let certs = X509::stack_from_pem(pem.as_bytes())?;
+    let (leaf, chain) = certs
+        .split_first()
+        .ok_or_else(|| anyhow!("下载的证书为空"))?;
+    let csr_key = order.csr.public_key()?;
+    if !leaf.public_key()?.public_eq(&csr_key) {
+        return Err(anyhow!("签发的证书与CSR的公钥不一致"));
+    }
+    let mut chain_pem = String::new();
+    for cert in chain {
+        chain_pem.push_str(&String::from_utf8(cert.to_pem()?)?);
+    }
+    Ok((String::from_utf8(leaf.to_pem()?)?, chain_pem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use tiny_http::{Header, Method, Response, Server};
+
+    /// 记录TXT记录的DNS服务商，corrupt时写入错误的值
+    #[derive(Default)]
+    struct MemoryDns {
+        records: Arc<Mutex<BTreeMap<String, Vec<String>>>>,
+        corrupt: bool,
+    }
+
+    impl DnsProvider for MemoryDns {
+        fn add_txt(&self, name: &str, value: &str) -> Result<(), BatchError> {
+            let value = if self.corrupt { "invalid" } else { value };
+            let mut records = self.records.lock().unwrap();
+            records
+                .entry(name.to_string())
+                .or_default()
+                .push(value.to_string());
+            Ok(())
+        }
+
+        fn remove_txt(&self, name: &str, _value: &str) -> Result<(), BatchError> {
+            self.records.lock().unwrap().remove(name);
+            Ok(())
+        }
+    }
+
+    struct MockOrder {
+        domains: Vec<String>,
+        valid: Vec<Option<bool>>,
+        certificate: Option<String>,
+    }
+
+    /// 测试用的ACME服务器：校验JWS签名，按DNS服务商中记录的TXT值判断验证结果，以测试CA签发证书
+    struct MockAcme {
+        base: String,
+        records: Arc<Mutex<BTreeMap<String, Vec<String>>>>,
+        jwk: Option<Value>,
+        nonce: u64,
+        bad_nonce_sent: bool,
+        orders: Vec<MockOrder>,
+        ca_key: PKey<Private>,
+        ca_cert: X509,
+    }
+
+    impl MockAcme {
+        /// 校验JWS签名，返回保护头和载荷
+        fn verify(&mut self, body: &str) -> (Value, Value) {
+            let jws: Value = serde_json::from_str(body).unwrap();
+            let decode = |field: &str| b64url_decode(jws[field].as_str().unwrap()).unwrap();
+            let protected: Value = serde_json::from_slice(&decode("protected")).unwrap();
+            if let Some(jwk) = protected.get("jwk") {
+                self.jwk = Some(jwk.clone());
+            }
+            let jwk = self.jwk.clone().expect("账户已创建");
+            let coordinate = |field: &str| {
+                BigNum::from_slice(&b64url_decode(jwk[field].as_str().unwrap()).unwrap()).unwrap()
+            };
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+            let key = EcKey::from_public_key_affine_coordinates(
+                &group,
+                &coordinate("x"),
+                &coordinate("y"),
+            )
+            .unwrap();
+            let signature = decode("signature");
+            let signature = EcdsaSig::from_private_components(
+                BigNum::from_slice(&signature[..32]).unwrap(),
+                BigNum::from_slice(&signature[32..]).unwrap(),
+            )
+            .unwrap();
+            let signed = format!(
+                "{}.{}",
+                jws["protected"].as_str().unwrap(),
+                jws["payload"].as_str().unwrap()
+            );
+            let digest = hash(MessageDigest::sha256(), signed.as_bytes()).unwrap();
+            assert!(signature.verify(&digest, &key).unwrap(), "JWS签名无效");
+            let payload = decode("payload");
+            let payload = if payload.is_empty() {
+                Value::Null
+            } else {
+                serde_json::from_slice(&payload).unwrap()
+            };
+            (protected, payload)
+        }
+
+        fn thumbprint(&self) -> String {
+            let jwk = self.jwk.as_ref().unwrap();
+            let canonical = format!(
+                r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+                jwk["x"].as_str().unwrap(),
+                jwk["y"].as_str().unwrap()
+            );
+            b64url(&hash(MessageDigest::sha256(), canonical.as_bytes()).unwrap())
+        }
+
+        fn order_json(&self, id: usize) -> Value {
+            let order = &self.orders[id];
+            let status = if order.certificate.is_some() {
+                "valid"
+            } else if order.valid.iter().all(|v| *v == Some(true)) {
+                "ready"
+            } else if order.valid.contains(&Some(false)) {
+                "invalid"
+            } else {
+                "pending"
+            };
+            json!({
+                "status": status,
+                "authorizations": (0..order.domains.len())
+                    .map(|j| format!("{}/authz/{}/{}", self.base, id, j))
+                    .collect::<Vec<_>>(),
+                "finalize": format!("{}/finalize/{}", self.base, id),
+                "certificate": format!("{}/cert/{}", self.base, id),
+            })
+        }
+
+        fn sign(&self, csr: &X509Req) -> String {
+            let mut builder = X509Builder::new().unwrap();
+            builder.set_version(2).unwrap();
+            builder.set_subject_name(csr.subject_name()).unwrap();
+            builder
+                .set_issuer_name(self.ca_cert.subject_name())
+                .unwrap();
+            builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(90).unwrap())
+                .unwrap();
+            builder.sign(&self.ca_key, MessageDigest::sha256()).unwrap();
+            let mut pem = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+            pem.push_str(&String::from_utf8(self.ca_cert.to_pem().unwrap()).unwrap());
+            pem
+        }
+
+        /// 处理POST请求，返回状态码、响应体和Location
+        fn handle(&mut self, path: &str, body: &str) -> (u16, Value, Option<String>) {
+            let (_, payload) = self.verify(body);
+            let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+            let index = |i: usize| segments[i].parse::<usize>().unwrap();
+            match segments.as_slice() {
+                ["account"] if !self.bad_nonce_sent => {
+                    self.bad_nonce_sent = true;
+                    let problem = json!({ "type": "urn:ietf:params:acme:error:badNonce" });
+                    (400, problem, None)
+                }
+                ["account"] => {
+                    let location = format!("{}/acct/1", self.base);
+                    (201, json!({ "status": "valid" }), Some(location))
+                }
+                ["order"] => {
+                    let domains: Vec<String> = payload["identifiers"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|id| id["value"].as_str().unwrap().to_string())
+                        .collect();
+                    self.orders.push(MockOrder {
+                        valid: vec![None; domains.len()],
+                        domains,
+                        certificate: None,
+                    });
+                    let id = self.orders.len() - 1;
+                    let location = format!("{}/order/{}", self.base, id);
+                    (201, self.order_json(id), Some(location))
+                }
+                ["order", _] => (200, self.order_json(index(1)), None),
+                ["authz", _, _] | ["chall", _, _] => {
+                    let (i, j) = (index(1), index(2));
+                    let token = format!("token-{}-{}", i, j);
+                    if segments[0] == "chall" {
+                        let expected = format!("{}.{}", token, self.thumbprint());
+                        let expected =
+                            b64url(&hash(MessageDigest::sha256(), expected.as_bytes()).unwrap());
+                        let domain = self.orders[i].domains[j].trim_start_matches("*.");
+                        let name = format!("_acme-challenge.{}", domain);
+                        let records = self.records.lock().unwrap();
+                        let found = records.get(&name).is_some_and(|v| v.contains(&expected));
+                        self.orders[i].valid[j] = Some(found);
+                    }
+                    let status = match self.orders[i].valid[j] {
+                        Some(true) => "valid",
+                        Some(false) => "invalid",
+                        None => "pending",
+                    };
+                    let mut challenge = json!({
+                        "type": "dns-01",
+                        "url": format!("{}/chall/{}/{}", self.base, i, j),
+                        "token": token,
+                    });
+                    if status == "invalid" {
+                        challenge["error"] = json!({ "detail": "TXT记录不正确" });
+                    }
+                    let authorization = json!({
+                        "status": status,
+                        "identifier": { "type": "dns", "value": self.orders[i].domains[j] },
+                        "challenges": [challenge],
+                    });
+                    (200, authorization, None)
+                }
+                ["finalize", _] => {
+                    let der = b64url_decode(payload["csr"].as_str().unwrap()).unwrap();
+                    let csr = X509Req::from_der(&der).unwrap();
+                    self.orders[index(1)].certificate = Some(self.sign(&csr));
+                    (200, self.order_json(index(1)), None)
+                }
+                _ => (404, Value::Null, None),
+            }
+        }
+    }
+
+    /// 启动测试ACME服务器，返回目录地址
+    fn start_mock(records: Arc<Mutex<BTreeMap<String, Vec<String>>>>) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test ACME CA").unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(90).unwrap())
+            .unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let mut mock = MockAcme {
+            base: base.clone(),
+            records,
+            jwk: None,
+            nonce: 0,
+            bad_nonce_sent: false,
+            orders: Vec::new(),
+            ca_key,
+            ca_cert: builder.build(),
+        };
+
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                mock.nonce += 1;
+                let nonce =
+                    Header::from_bytes("Replay-Nonce", format!("nonce-{}", mock.nonce)).unwrap();
+                let url = request.url().to_string();
+                let (status, body, location) = match (request.method(), url.as_str()) {
+                    (Method::Get, "/directory") => {
+                        let directory = json!({
+                            "newNonce": format!("{}/nonce", mock.base),
+                            "newAccount": format!("{}/account", mock.base),
+                            "newOrder": format!("{}/order", mock.base),
+                        });
+                        (200, directory.to_string(), None)
+                    }
+                    (Method::Head, "/nonce") => (200, String::new(), None),
+                    (Method::Post, path) if path.starts_with("/cert/") => {
+                        let id: usize = path[6..].parse().unwrap();
+                        let pem = mock.orders[id].certificate.clone().unwrap();
+                        (200, pem, None)
+                    }
+                    (Method::Post, path) => {
+                        let mut body = String::new();
+                        request.as_reader().read_to_string(&mut body).unwrap();
+                        let (status, body, location) = mock.handle(path, &body);
+                        (status, body.to_string(), location)
+                    }
+                    _ => (404, String::new(), None),
+                };
+                let mut response = Response::from_string(body)
+                    .with_status_code(status)
+                    .with_header(nonce);
+                if let Some(location) = location {
+                    response.add_header(Header::from_bytes("Location", location).unwrap());
+                }
+                let _ = request.respond(response);
+            }
+        });
+        format!("{}/directory", base)
+    }
+
+    #[test]
+    fn test_acme_dns01_issue() {
+        let dir = std::env::temp_dir().join("csr_batch_acme_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2".to_string(),
+            subject_template: "CN=[{CN}.example.com]".to_string(),
+            sans: "dNSName=[{CN}.example.com,*.{CN}.example.com]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let dns = MemoryDns::default();
+        let params = AcmeParams {
+            input_path: input.to_string_lossy().to_string(),
+            directory_url: start_mock(Arc::clone(&dns.records)),
+            account_key_path: dir.join("account.key").to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let result = issue_acme_with(&params, &dns).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.output_path.ends_with("out_acme.csv"));
+        // 验证结束后TXT记录已删除，账户私钥已保存
+        assert!(dns.records.lock().unwrap().is_empty());
+        assert!(dir.join("account.key").exists());
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (csr_col, cert_col, chain_col) =
+            (column("csr"), column("certificate"), column("chain"));
+        for record in reader.records() {
+            let record = record.unwrap();
+            let csr = parse_csr(&record[csr_col]).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert
+                .public_key()
+                .unwrap()
+                .public_eq(&csr.public_key().unwrap()));
+            assert!(record[chain_col].contains("BEGIN CERTIFICATE"));
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let error = issue_acme_with(&params, &dns).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_acme_challenge_failure() {
+        let dir = std::env::temp_dir().join("csr_batch_acme_failure_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1".to_string(),
+            subject_template: "CN=[{CN}.example.com]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        // TXT记录不正确时验证失败，返回CA的错误说明，已添加的记录同样被删除
+        let dns = MemoryDns {
+            corrupt: true,
+            ..Default::default()
+        };
+        let params = AcmeParams {
+            input_path: input.to_string_lossy().to_string(),
+            directory_url: start_mock(Arc::clone(&dns.records)),
+            account_key_path: dir.join("account.key").to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let error = issue_acme_with(&params, &dns).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidRecord { index: 1, ref message }
+                if message.contains("_acme-challenge.dev1.example.com 验证失败: TXT记录不正确")),
+            "{}",
+            error
+        );
+        assert!(dns.records.lock().unwrap().is_empty());
+        // 没有签发任何证书时不写入结果文件
+        assert!(!dir.join("out_acme.csv").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+}
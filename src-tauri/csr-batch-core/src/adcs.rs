@@ -0,0 +1,687 @@
+//! AD CS注册模块
+//! 将生成结果CSV中的CSR按指定的证书模板提交给Microsoft AD CS企业CA，输出在原有列之后增加certificate列
+//! （证书）、chain列（证书链）和requestId列（CA的请求ID）的CSV文件
+//!
+//! 支持两种提交方式：证书服务Web注册页面（certsrv，HTTP Basic认证），以及在Windows上调用 `certreq`
+//! 经DCOM提交（使用当前Windows账户的身份）。需要CA管理员审批的请求在CA上挂起，certificate列留空；
+//! 再次处理输出的CSV时，已有requestId但没有证书的行按请求ID取回证书，其余已签发的行保持不变
+
+use anyhow::{anyhow, Result};
+use csv::ReaderBuilder;
+use openssl::base64;
+use openssl::pkcs7::Pkcs7;
+use openssl::x509::{X509Req, X509};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::output::{derived_csv_path, ResultCsv};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// AD CS注册参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct AdcsParams {
+    /// 待注册的CSV文件路径（批量生成的输出需包含csr列；之前的注册输出按requestId列取回挂起的证书）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _adcs 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// 提交方式: web (证书服务Web注册，默认), certreq (Windows上经DCOM提交)
+    #[serde(default)]
+    pub method: String,
+    /// 证书服务Web注册地址，如 https://ca.example.com/certsrv (web方式)
+    #[serde(default)]
+    pub server_url: String,
+    /// CA配置字符串，如 ca.example.com\Example Issuing CA (certreq方式)
+    #[serde(default)]
+    pub config: String,
+    /// 证书模板名称（模板的名称，而非显示名称）
+    pub template: String,
+    /// HTTP Basic认证用户名，如 EXAMPLE\user (web方式，IIS须启用Basic认证)
+    #[serde(default)]
+    pub username: String,
+    /// HTTP Basic认证密码
+    #[serde(default)]
+    pub password: String,
+    /// 证书服务Web服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+}
+
+/// AD CS注册结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct AdcsResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发（含取回）的证书总数
+    pub total: usize,
+    /// 在CA上挂起、等待审批的请求数
+    pub pending: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 一次提交或取回的结果
+enum Outcome {
+    /// 已签发：请求ID、证书和证书链 (PEM)
+    Issued(String, String, String),
+    /// 挂起：请求ID
+    Pending(String),
+}
+
+/// PKCS#7证书链（DER，或base64编码且可带PEM头尾行）中除签发证书外的证书 (PEM)
+fn chain_pem(bytes: &[u8], leaf: &X509) -> Result<String> {
+    let pkcs7 = match Pkcs7::from_der(bytes) {
+        Ok(pkcs7) => pkcs7,
+        Err(_) => {
+            let text = String::from_utf8_lossy(bytes);
+            let compact: String = text
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .flat_map(|line| line.split_whitespace())
+                .collect();
+            let der = base64::decode_block(&compact).map_err(|_| anyhow!("无效的证书链"))?;
+            Pkcs7::from_der(&der).map_err(|e| anyhow!("无效的证书链: {}", e))?
+        }
+    };
+    let leaf = leaf.to_der()?;
+    let mut chain = String::new();
+    if let Some(certs) = pkcs7.signed().and_then(|signed| signed.certificates()) {
+        for cert in certs {
+            if cert.to_der()? != leaf {
+                chain.push_str(&String::from_utf8(cert.to_pem()?)?);
+            }
+        }
+    }
+    Ok(chain)
+}
+
+/// 证书服务页面的文本（去除HTML标签并合并空白）
+fn page_text(html: &str) -> Result<String> {
+    let tags = Regex::new(r"(?s)<[^>]*>")?;
+    let text = tags.replace_all(html, " ").replace("&quot;", "\"");
+    Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// 从证书服务页面中提取拒绝或错误的说明
+fn page_error(html: &str) -> Result<String> {
+    let text = page_text(html)?;
+    let disposition = Regex::new(r#"disposition message is "([^"]*)""#)?;
+    if let Some(captures) = disposition.captures(&text) {
+        return Ok(captures[1].trim().to_string());
+    }
+    let mut text: String = text.chars().take(200).collect();
+    if text.is_empty() {
+        text = "证书服务返回空页面".to_string();
+    }
+    Ok(text)
+}
+
+/// 证书服务Web注册（certsrv）
+struct WebEnrollment {
+    agent: ureq::Agent,
+    base_url: String,
+    authorization: Option<String>,
+}
+
+impl WebEnrollment {
+    fn connect(params: &AdcsParams) -> Result<Self> {
+        if params.server_url.trim().is_empty() {
+            return Err(BatchError::parameter("server_url", "未填写").into());
+        }
+        let mut builder = ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT);
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            let connector = native_tls::TlsConnector::builder()
+                .add_root_certificate(cert)
+                .build()?;
+            builder = builder.tls_connector(Arc::new(connector));
+        }
+        let authorization = (!params.username.is_empty()).then(|| {
+            let credentials = format!("{}:{}", params.username, params.password);
+            format!("Basic {}", base64::encode_block(credentials.as_bytes()))
+        });
+        Ok(Self {
+            agent: builder.build(),
+            base_url: params.server_url.trim().trim_end_matches('/').to_string(),
+            authorization,
+        })
+    }
+
+    fn request(&self, method: &str, page: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, &format!("{}/{}", self.base_url, page));
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    fn read(response: Result<ureq::Response, ureq::Error>) -> Result<String> {
+        match response {
+            Ok(response) => Ok(response.into_string()?),
+            Err(ureq::Error::Status(401, _)) => Err(anyhow!(
+                "证书服务返回 401，请检查用户名和密码，并确认IIS已启用Basic认证"
+            )),
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                Err(anyhow!("证书服务返回 {}: {}", code, page_error(&text)?))
+            }
+            Err(e) => Err(anyhow!("连接失败: {}", e)),
+        }
+    }
+
+    fn submit(&self, csr: &X509Req, template: &str) -> Result<Outcome> {
+        let csr_pem = String::from_utf8(csr.to_pem()?)?;
+        let attributes = format!("CertificateTemplate:{}", template);
+        let page = Self::read(self.request("POST", "certfnsh.asp").send_form(&[
+            ("Mode", "newreq"),
+            ("CertRequest", &csr_pem),
+            ("CertAttrib", &attributes),
+            ("TargetStoreFlags", "0"),
+            ("SaveCert", "yes"),
+        ]))?;
+
+        let issued = Regex::new(r"certnew\.cer\?ReqID=(\d+)")?;
+        if let Some(captures) = issued.captures(&page) {
+            return self.retrieve(&captures[1]);
+        }
+        let text = page_text(&page)?;
+        let request_id = Regex::new(r"Request Id is (\d+)")?;
+        match request_id.captures(&text) {
+            Some(captures) if text.to_lowercase().contains("pending") => {
+                Ok(Outcome::Pending(captures[1].to_string()))
+            }
+            _ => Err(anyhow!("CA拒绝签发: {}", page_error(&page)?)),
+        }
+    }
+
+    fn retrieve(&self, request_id: &str) -> Result<Outcome> {
+        let query = format!("ReqID={}&Enc=b64", request_id);
+        let page = Self::read(
+            self.request("GET", &format!("certnew.cer?{}", query))
+                .call(),
+        )?;
+        if !page.contains("-----BEGIN CERTIFICATE-----") {
+            let text = page_text(&page)?.to_lowercase();
+            if text.contains("pending") || text.contains("taken under submission") {
+                return Ok(Outcome::Pending(request_id.to_string()));
+            }
+            return Err(anyhow!(
+                "无法取回请求 {}: {}",
+                request_id,
+                page_error(&page)?
+            ));
+        }
+        let cert = X509::from_pem(page.as_bytes()).map_err(|e| anyhow!("无效的证书: {}", e))?;
+        let p7b = Self::read(
+            self.request("GET", &format!("certnew.p7b?{}", query))
+                .call(),
+        )?;
+        let chain = chain_pem(p7b.as_bytes(), &cert)?;
+        Ok(Outcome::Issued(
+            request_id.to_string(),
+            String::from_utf8(cert.to_pem()?)?,
+            chain,
+        ))
+    }
+}
+
+/// 在Windows上通过 certreq 经DCOM提交和取回
+struct CertreqEnrollment {
+    config: String,
+}
+
+impl CertreqEnrollment {
+    fn new(params: &AdcsParams) -> Result<Self> {
+        if params.config.trim().is_empty() {
+            return Err(BatchError::parameter("config", "未填写CA配置字符串").into());
+        }
+        Ok(Self {
+            config: params.config.trim().to_string(),
+        })
+    }
+
+    /// 执行certreq，返回请求ID和输出的证书及证书链文件内容（挂起时证书为None）
+    fn run(&self, args: &[&str], name: &str) -> Result<Outcome> {
+        let dir = std::env::temp_dir();
+        let cert_path = dir.join(format!("{}.cer", name));
+        let chain_path = dir.join(format!("{}.p7b", name));
+        let output = Command::new("certreq")
+            .args(["-q", "-f", "-config", &self.config])
+            .args(args)
+            .arg(&cert_path)
+            .arg(&chain_path)
+            .output()
+            .map_err(|e| BatchError::io("certreq", e))?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let cert = fs::read(&cert_path).ok();
+        let chain = fs::read(&chain_path).ok();
+        fs::remove_file(&cert_path).ok();
+        fs::remove_file(&chain_path).ok();
+        if !output.status.success() {
+            return Err(anyhow!("certreq 执行失败: {}", stdout.trim()));
+        }
+
+        let request_id = Regex::new(r"RequestId:\s*(\d+)")?
+            .captures(&stdout)
+            .map(|captures| captures[1].to_string());
+        match (request_id, cert) {
+            (Some(request_id), Some(cert)) => {
+                let cert = X509::from_pem(&cert)
+                    .or_else(|_| X509::from_der(&cert))
+                    .map_err(|e| anyhow!("无效的证书: {}", e))?;
+                let chain = match chain {
+                    Some(chain) => chain_pem(&chain, &cert)?,
+                    None => String::new(),
+                };
+                Ok(Outcome::Issued(
+                    request_id,
+                    String::from_utf8(cert.to_pem()?)?,
+                    chain,
+                ))
+            }
+            (Some(request_id), None) => Ok(Outcome::Pending(request_id)),
+            (None, _) => Err(anyhow!("CA拒绝签发: {}", stdout.trim())),
+        }
+    }
+
+    fn submit(&self, csr: &X509Req, template: &str, number: usize) -> Result<Outcome> {
+        let name = format!("csr-batch-adcs-{}-{}", std::process::id(), number);
+        let req_path = std::env::temp_dir().join(format!("{}.req", name));
+        fs::write(&req_path, csr.to_pem()?).map_err(|e| BatchError::io(&req_path, e))?;
+        let attributes = format!("CertificateTemplate:{}", template);
+        let req_arg = req_path.to_string_lossy().to_string();
+        let outcome = self.run(&["-submit", "-attrib", &attributes, &req_arg], &name);
+        fs::remove_file(&req_path).ok();
+        outcome
+    }
+
+    fn retrieve(&self, request_id: &str, number: usize) -> Result<Outcome> {
+        let name = format!("csr-batch-adcs-{}-{}", std::process::id(), number);
+        self.run(&["-retrieve", request_id], &name)
+    }
+}
+
+/// 提交方式
+enum Enrollment {
+    Web(WebEnrollment),
+    Certreq(CertreqEnrollment),
+}
+
+impl Enrollment {
+    fn from_params(params: &AdcsParams) -> Result<Self> {
+        match params.method.as_str() {
+            "" | "web" => Ok(Self::Web(WebEnrollment::connect(params)?)),
+            "certreq" => Ok(Self::Certreq(CertreqEnrollment::new(params)?)),
+            other => {
+                Err(BatchError::parameter("method", format!("不支持的提交方式: {}", other)).into())
+            }
+        }
+    }
+
+    fn submit(&self, csr: &X509Req, template: &str, number: usize) -> Result<Outcome> {
+        match self {
+            Self::Web(web) => web.submit(csr, template),
+            Self::Certreq(certreq) => certreq.submit(csr, template, number),
+        }
+    }
+
+    fn retrieve(&self, request_id: &str, number: usize) -> Result<Outcome> {
+        match self {
+            Self::Web(web) => web.retrieve(request_id),
+            Self::Certreq(certreq) => certreq.retrieve(request_id, number),
+        }
+    }
+}
+
+/// 通过AD CS为CSV中的每个CSR签发证书，或取回之前挂起的请求
+pub fn adcs_enroll(params: AdcsParams) -> Result<AdcsResult, BatchError> {
+    enroll_records(&params).map_err(BatchError::from)
+}
+
+/// 提交一行的CSR，已有请求ID时按请求ID取回
+fn enroll_record(
+    enrollment: &Enrollment,
+    csr_pem: &str,
+    request_id: &str,
+    template: &str,
+    number: usize,
+) -> Result<Outcome> {
+    let outcome = if request_id.is_empty() {
+        let csr = parse_csr(csr_pem)
+            .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+        enrollment.submit(&csr, template, number)
+    } else {
+        enrollment.retrieve(request_id, number)
+    }
+    .map_err(|e| BatchError::record(number, format!("注册失败: {}", e)))?;
+    Ok(outcome)
+}
+
+fn enroll_records(params: &AdcsParams) -> Result<AdcsResult> {
+    let template = params.template.trim();
+    if template.is_empty() {
+        return Err(BatchError::parameter("template", "未填写证书模板").into());
+    }
+
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let csr_col = headers
+        .iter()
+        .position(|h| h == "csr")
+        .ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+    let request_col = ensure_column(&mut headers, "requestId");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "adcs")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let enrollment = Enrollment::from_params(params)?;
+    let mut total = 0;
+    let mut pending = 0;
+    for (index, record) in records.iter().enumerate() {
+        let number = index + 1;
+        let mut fields: Vec<String> = record.iter().map(str::to_string).collect();
+        fields.resize(headers.len(), String::new());
+        let request_id = fields[request_col].trim().to_string();
+
+        // 之前已签发的行保持不变；已提交但挂起的行按请求ID取回
+        if !request_id.is_empty() && !fields[cert_col].trim().is_empty() {
+            output.write_record(&fields)?;
+            continue;
+        }
+        // 中途失败时，已签发或已挂起的请求和其余行照常写入输出文件，挂起的请求ID不会丢失
+        let outcome =
+            match enroll_record(&enrollment, &fields[csr_col], &request_id, template, number) {
+                Ok(outcome) => outcome,
+                Err(error) => return Err(output.abort(error, &records[index..], total + pending)),
+            };
+
+        match outcome {
+            Outcome::Issued(request_id, cert_pem, chain) => {
+                fields[request_col] = request_id;
+                fields[cert_col] = cert_pem;
+                fields[chain_col] = chain;
+                total += 1;
+            }
+            Outcome::Pending(request_id) => {
+                fields[request_col] = request_id;
+                fields[cert_col].clear();
+                fields[chain_col].clear();
+                pending += 1;
+            }
+        }
+        output.write_record(&fields)?;
+    }
+    output.finish()?;
+
+    let mut message = format!("AD CS签发 {} 个证书", total);
+    if pending > 0 {
+        message.push_str(&format!(
+            "，{} 个请求等待CA审批，审批后再次处理输出文件即可取回",
+            pending
+        ));
+    }
+    Ok(AdcsResult {
+        success: true,
+        message,
+        total,
+        pending,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::pkcs7::Pkcs7Flags;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::stack::Stack;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use std::collections::HashMap;
+    use tiny_http::{Method, Response, Server};
+
+    /// 解码application/x-www-form-urlencoded的值
+    fn form_value(body: &str, name: &str) -> String {
+        let value = body
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(&format!("{}=", name)))
+            .unwrap_or_default()
+            .replace('+', " ");
+        let mut bytes = Vec::new();
+        let mut rest = value.as_bytes();
+        while let Some((&b, tail)) = rest.split_first() {
+            if b == b'%' {
+                let hex = std::str::from_utf8(&tail[..2]).unwrap();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap());
+                rest = &tail[2..];
+            } else {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// 启动测试证书服务：第一个请求立即签发，其余请求挂起，取回时视为已审批；模板Denied被拒绝
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}/certsrv", server.server_addr().to_ip().unwrap());
+        let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test Issuing CA").unwrap();
+        let name = name.build();
+        let issuer = name.to_owned().unwrap();
+        let validity = move |builder: &mut X509Builder| {
+            builder.set_version(2).unwrap();
+            builder.set_issuer_name(&issuer).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                .unwrap();
+        };
+        let mut builder = X509Builder::new().unwrap();
+        validity(&mut builder);
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+
+        std::thread::spawn(move || {
+            let expected = format!("Basic {}", base64::encode_block(b"EXAMPLE\\user:secret"));
+            let mut requests: HashMap<String, X509Req> = HashMap::new();
+            let mut issued: HashMap<String, X509> = HashMap::new();
+            for mut request in server.incoming_requests() {
+                let authorized = request
+                    .headers()
+                    .iter()
+                    .any(|h| h.field.equiv("Authorization") && h.value.as_str() == expected);
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let url = request.url().to_string();
+                let request_id = url
+                    .split_once("ReqID=")
+                    .map(|(_, rest)| rest.split('&').next().unwrap().to_string());
+                let page = match (request.method(), url.as_str(), request_id) {
+                    _ if !authorized => {
+                        let _ = request.respond(Response::from_string("").with_status_code(401));
+                        continue;
+                    }
+                    (Method::Post, "/certsrv/certfnsh.asp", _) => {
+                        assert_eq!(form_value(&body, "Mode"), "newreq");
+                        let id = (requests.len() + 1).to_string();
+                        let csr = parse_csr(&form_value(&body, "CertRequest")).unwrap();
+                        requests.insert(id.clone(), csr);
+                        match form_value(&body, "CertAttrib").as_str() {
+                            "CertificateTemplate:Denied" => format!(
+                                "<P>Your certificate request was denied.<P>Your Request Id is {}.  \
+                                 The disposition message is &quot;Denied by Policy Module&quot;.",
+                                id
+                            ),
+                            "CertificateTemplate:WebServer" if id == "1" => format!(
+                                "<A Href=\"certnew.cer?ReqID={}&amp;Enc=b64\">Download certificate</A>",
+                                id
+                            ),
+                            _ => format!(
+                                "<P>Your certificate request is pending.<P>Your Request Id is {}.",
+                                id
+                            ),
+                        }
+                    }
+                    (Method::Get, page, Some(id)) => {
+                        let cert = issued.entry(id.clone()).or_insert_with(|| {
+                            let csr = &requests[&id];
+                            let mut builder = X509Builder::new().unwrap();
+                            validity(&mut builder);
+                            builder.set_subject_name(csr.subject_name()).unwrap();
+                            builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                            builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                            builder.build()
+                        });
+                        if page.starts_with("/certsrv/certnew.cer") {
+                            String::from_utf8(cert.to_pem().unwrap()).unwrap()
+                        } else {
+                            let mut certs = Stack::new().unwrap();
+                            certs.push(cert.clone()).unwrap();
+                            let pkcs7 =
+                                Pkcs7::sign(&ca_cert, &ca_key, &certs, &[], Pkcs7Flags::BINARY)
+                                    .unwrap();
+                            let encoded = base64::encode_block(&pkcs7.to_der().unwrap());
+                            format!(
+                                "-----BEGIN CERTIFICATE-----\r\n{}\r\n-----END CERTIFICATE-----\r\n",
+                                encoded
+                            )
+                        }
+                    }
+                    _ => {
+                        let _ = request.respond(Response::from_string("").with_status_code(404));
+                        continue;
+                    }
+                };
+                let _ = request.respond(Response::from_string(page));
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_adcs_enroll() {
+        let dir = std::env::temp_dir().join("csr_batch_adcs_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "web1,web2".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        // 第一次提交：web1立即签发，web2挂起
+        let server_url = start_mock();
+        let params = AdcsParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            template: "WebServer".to_string(),
+            username: "EXAMPLE\\user".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        };
+        let result = adcs_enroll(params).unwrap();
+        assert_eq!((result.total, result.pending), (1, 1));
+        assert!(result.output_path.ends_with("out_adcs.csv"));
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let records: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(&records[1][column("requestId")], "2");
+        assert!(records[1][column("certificate")].is_empty());
+
+        // 再次处理输出文件时取回审批后的证书
+        let params = AdcsParams {
+            input_path: result.output_path.clone(),
+            output_path: dir.join("retrieved.csv").to_string_lossy().to_string(),
+            server_url,
+            template: "WebServer".to_string(),
+            username: "EXAMPLE\\user".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        };
+        let result = adcs_enroll(params).unwrap();
+        assert_eq!((result.total, result.pending), (1, 0));
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        for record in reader.records() {
+            let record = record.unwrap();
+            let key = PKey::private_key_from_pem(record[column("privateKey")].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[column("certificate")].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::from_pem(record[column("chain")].as_bytes()).unwrap();
+            assert_eq!(cert.issuer_name_hash(), chain.subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let server_url = start_mock();
+        let params = |overwrite: bool| AdcsParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            template: "Denied".to_string(),
+            username: "EXAMPLE\\user".to_string(),
+            password: "secret".to_string(),
+            overwrite,
+            ..Default::default()
+        };
+        let error = adcs_enroll(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 模板被拒绝时返回CA的说明
+        let error = adcs_enroll(params(true)).unwrap_err();
+        assert!(
+            error.to_string().contains("Denied by Policy Module"),
+            "{}",
+            error
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,264 @@
+//! 审计日志模块
+//! 以JSON Lines格式追加写入生成事件（任务开始、条目生成、文件写入、私钥导出、任务结束），
+//! 每条记录包含上一条记录的哈希，并以自身内容（不含hash字段）的SHA-256作为哈希，形成哈希链：
+//! 修改、删除或插入中间的任意记录都会使之后的哈希校验失败，可用 [`verify_audit_log`] 检查
+//!
+//! 哈希链无法发现截断末尾的记录，需要时可将校验报告中的最后一个哈希另行保存；
+//! 同一进程中的多个任务可同时写入同一日志，但同一日志文件不应由多个进程同时写入
+
+use anyhow::{anyhow, Result};
+use openssl::hash::{hash, Hasher, MessageDigest};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::BatchError;
+use crate::job_db::now;
+use crate::job_log::current_user;
+
+/// 第一条记录的上一条哈希
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 审计日志中的一条记录
+#[derive(Debug, Serialize, Deserialize)]
+struct AuditEntry {
+    /// 序号，从1开始连续递增
+    seq: u64,
+    time: String,
+    /// 事件类型：job_started、item_generated、file_written、key_exported、job_finished
+    event: String,
+    job_id: String,
+    user: String,
+    /// 事件详情
+    detail: Value,
+    prev_hash: String,
+    /// 本条记录的哈希，计算时为空（不参与序列化）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    hash: String,
+}
+
+impl AuditEntry {
+    /// 不含hash字段的JSON序列化结果的SHA-256
+    fn compute_hash(&self) -> Result<String> {
+        let mut content = serde_json::to_value(self)?;
+        if let Value::Object(map) = &mut content {
+            map.remove("hash");
+        }
+        let digest = hash(MessageDigest::sha256(), content.to_string().as_bytes())?;
+        Ok(hex(&digest))
+    }
+}
+
+/// 小写十六进制
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 文件内容的SHA-256
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path).map_err(|e| BatchError::io(path, e))?;
+    let mut hasher = Hasher::new(MessageDigest::sha256())?;
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex(&hasher.finish()?))
+}
+
+/// 进程内各审计日志的链尾（序号和哈希），同一进程中并发执行的任务依次追加，哈希链保持连续
+static CHAIN_TAILS: Mutex<BTreeMap<PathBuf, (u64, String)>> = Mutex::new(BTreeMap::new());
+
+/// 读取文件中最后一条记录的序号和哈希，文件不存在或为空时从起点开始
+fn read_tail(path: &Path) -> Result<(u64, String)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(BatchError::io(path, e).into()),
+    };
+    let Some(line) = content.lines().rev().find(|line| !line.trim().is_empty()) else {
+        return Ok((0, GENESIS_HASH.to_string()));
+    };
+    let entry: AuditEntry = serde_json::from_str(line).map_err(|e| {
+        BatchError::parameter("audit_log_path", format!("最后一条记录格式错误: {}", e))
+    })?;
+    Ok((entry.seq, entry.hash))
+}
+
+/// 追加写入审计日志，记录写入后立即落盘
+pub(crate) struct AuditLog {
+    file: File,
+    path: PathBuf,
+    job_id: String,
+    user: String,
+}
+
+impl AuditLog {
+    /// 打开（或创建）审计日志并从最后一条记录继续哈希链，path为空时返回None
+    pub(crate) fn open(path: &str, job_id: &str) -> Result<Option<Self>> {
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let path = PathBuf::from(path);
+        {
+            let mut tails = CHAIN_TAILS.lock().unwrap();
+            let tail = read_tail(&path)?;
+            tails.insert(path.clone(), tail);
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| BatchError::io(&path, e))?;
+        Ok(Some(Self {
+            file,
+            path,
+            job_id: job_id.to_string(),
+            user: current_user(),
+        }))
+    }
+
+    /// 追加一条记录
+    pub(crate) fn record(&mut self, event: &str, detail: Value) -> Result<()> {
+        let mut tails = CHAIN_TAILS.lock().unwrap();
+        let (seq, last_hash) = tails
+            .entry(self.path.clone())
+            .or_insert_with(|| (0, GENESIS_HASH.to_string()));
+        let mut entry = AuditEntry {
+            seq: *seq + 1,
+            time: now(),
+            event: event.to_string(),
+            job_id: self.job_id.clone(),
+            user: self.user.clone(),
+            detail,
+            prev_hash: last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash()?;
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        *seq = entry.seq;
+        *last_hash = entry.hash;
+        Ok(())
+    }
+
+    /// 记录写入的文件及其SHA-256，路径为空或不是文件（如目录）时跳过
+    pub(crate) fn record_file(&mut self, path: &str) -> Result<()> {
+        let file_path = Path::new(path);
+        if path.is_empty() || !file_path.is_file() {
+            return Ok(());
+        }
+        let sha256 = file_sha256(file_path)?;
+        self.record(
+            "file_written",
+            serde_json::json!({ "path": path, "sha256": sha256 }),
+        )
+    }
+}
+
+/// 审计日志校验报告
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLogReport {
+    /// 哈希链是否完整
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 校验通过的记录数
+    pub entries: usize,
+    /// 最后一条校验通过的记录的哈希
+    pub last_hash: String,
+    /// 第一条未通过校验的记录所在行号（从1开始，全部通过时为0）
+    pub broken_line: usize,
+}
+
+/// 校验审计日志的哈希链：逐条检查序号连续、prev_hash与上一条记录一致、hash与记录内容一致
+pub fn verify_audit_log(path: &str) -> Result<AuditLogReport, BatchError> {
+    let content = fs::read_to_string(path).map_err(|e| BatchError::io(path, e))?;
+    let mut entries = 0;
+    let mut last_hash = GENESIS_HASH.to_string();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = match check_entry(line, entries as u64 + 1, &last_hash) {
+            Ok(entry) => entry,
+            Err(e) => {
+                return Ok(AuditLogReport {
+                    success: false,
+                    message: format!("第{}行校验失败: {}", index + 1, e),
+                    entries,
+                    last_hash,
+                    broken_line: index + 1,
+                })
+            }
+        };
+        entries += 1;
+        last_hash = entry.hash;
+    }
+    Ok(AuditLogReport {
+        success: true,
+        message: format!("审计日志完整，共 {} 条记录", entries),
+        entries,
+        last_hash,
+        broken_line: 0,
+    })
+}
+
+/// 校验单条记录，通过时返回该记录
+fn check_entry(line: &str, seq: u64, prev_hash: &str) -> Result<AuditEntry> {
+    let entry: AuditEntry =
+        serde_json::from_str(line).map_err(|e| anyhow!("记录格式错误: {}", e))?;
+    if entry.seq != seq {
+        return Err(anyhow!("序号应为 {}，实际为 {}", seq, entry.seq));
+    }
+    if entry.prev_hash != prev_hash {
+        return Err(anyhow!("prev_hash与上一条记录的哈希不一致"));
+    }
+    if entry.compute_hash()? != entry.hash {
+        return Err(anyhow!("记录内容与hash不一致"));
+    }
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_audit_log_chain() {
+        let path = std::env::temp_dir().join("csr_batch_audit_test.log");
+        fs::remove_file(&path).ok();
+        let path_str = path.to_string_lossy().to_string();
+        assert!(AuditLog::open("", "job-1").unwrap().is_none());
+
+        let mut log = AuditLog::open(&path_str, "job-1").unwrap().unwrap();
+        log.record("job_started", json!({ "total": 2 })).unwrap();
+        log.record("item_generated", json!({ "cn": "YDL0001" }))
+            .unwrap();
+        // 重新打开后从最后一条记录继续哈希链
+        let mut log = AuditLog::open(&path_str, "job-2").unwrap().unwrap();
+        log.record("item_generated", json!({ "cn": "YDL0002" }))
+            .unwrap();
+        let report = verify_audit_log(&path_str).unwrap();
+        assert!(report.success, "{}", report.message);
+        assert_eq!(report.entries, 3);
+
+        // 修改中间记录的内容
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("YDL0001", "YDL0009")).unwrap();
+        let report = verify_audit_log(&path_str).unwrap();
+        assert!(!report.success);
+        assert_eq!((report.entries, report.broken_line), (1, 2));
+
+        // 删除中间记录
+        let lines: Vec<&str> = content.lines().collect();
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let report = verify_audit_log(&path_str).unwrap();
+        assert_eq!(report.broken_line, 2);
+        assert!(report.message.contains("序号"));
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,623 @@
+//! 批量CSR生成器 - 命令行工具
+//! 与桌面应用共用生成逻辑，适用于CI流水线和无图形界面的服务器
+//!
+//! 示例: `csrgen --cn-range YDL0001-YDL0100 --key-type EC_P256 --out out.csv`
+
+use chrono::{Local, Months, SecondsFormat};
+use clap::Parser;
+use csr_batch_core::{
+    resume_checkpoint, run_batch, verify_audit_log, verify_batch, BatchError, BatchRequest,
+    CancelToken, CompletionHooks, CsrServer, CustomExtension, OcspConfig, OcspResponder, Profile,
+    ServerConfig, VerifyParams,
+};
+use std::io::Write;
+use std::process::ExitCode;
+
+/// 批量生成CSR和私钥并输出到CSV文件
+#[derive(Debug, Parser)]
+#[command(name = "csrgen", version)]
+struct Args {
+    /// 通用名称范围，如 YDL0001-YDL0100，可用逗号分隔多个范围和单个通用名称
+    #[arg(long, required_unless_present_any = ["input_csv", "mac_range", "cn_mode", "verify", "verify_audit_log", "resume", "serve", "ocsp"])]
+    cn_range: Option<String>,
+    /// 通用名称范围计数器的进制: 10, 16, 36
+    #[arg(long, default_value_t = 10)]
+    range_radix: u32,
+    /// 通用名称模式: uuid, hex，按数量生成随机通用名称（代替CN范围）
+    #[arg(long, requires = "count")]
+    cn_mode: Option<String>,
+    /// 随机通用名称数量
+    #[arg(long, default_value_t = 0)]
+    count: usize,
+    /// 随机十六进制通用名称的长度
+    #[arg(long, default_value_t = 16)]
+    hex_length: usize,
+    /// MAC地址范围，如 "00:1A:2B:00:00:01 - 00:1A:2B:00:01:00"，按MAC地址生成通用名称
+    #[arg(long)]
+    mac_range: Option<String>,
+    /// MAC地址分隔符: colon, hyphen, dot, none
+    #[arg(long, default_value = "colon")]
+    mac_separator: String,
+    /// MAC地址大小写: upper, lower
+    #[arg(long, default_value = "upper")]
+    mac_case: String,
+    /// 输入CSV或Excel（.xlsx）文件，按文件逐行生成（必须包含cn列）
+    #[arg(long)]
+    input_csv: Option<String>,
+    /// 排除的通用名称，逗号分隔，可使用范围写法
+    #[arg(long, default_value = "")]
+    exclude: String,
+    /// 排除列表文件，每行一个通用名称或范围
+    #[arg(long)]
+    exclude_file: Option<String>,
+    /// Subject主题模板，可使用{CN}、{INDEX}、{DATE}、{UUID}、{RAND:n}模板变量
+    #[arg(long, default_value = "CN=[{CN}]")]
+    subject: String,
+    /// 按DN属性强制字符串类型，如 C=printable,O=utf8
+    #[arg(long, default_value = "")]
+    dn_string_types: String,
+    /// 密钥类型: RSA_2048, RSA_3072, RSA_4096, EC_P256, EC_P384, EC_P521, EC_SECP256K1,
+    /// EC_BRAINPOOL_P256R1, EC_BRAINPOOL_P384R1, EC_BRAINPOOL_P512R1, ED25519, ED448, SM2,
+    /// ML_DSA_65, ML_DSA_87 (需以ml-dsa特性构建)；默认RSA_2048，或按 --profile 的模板
+    #[arg(long, default_value = "")]
+    key_type: String,
+    /// 按通用名称前缀指定密钥类型，如 "GW=EC_P256, LEGACY=RSA_2048"，未匹配的使用 --key-type
+    #[arg(long, default_value = "")]
+    key_type_map: String,
+    /// 签名哈希算法: SHA256, SHA384, SHA512, SHA1, MatchIssuer (与签发者证书一致)；默认SHA256，或按 --profile 的模板
+    #[arg(long, default_value = "")]
+    sign_hash_alg: String,
+    /// 签发者证书路径（PEM或DER），签名哈希算法为MatchIssuer时必填
+    #[arg(long, default_value = "")]
+    issuer_cert: String,
+    /// 允许SHA1等遗留算法 (默认拒绝，使用时在报告中记录警告)
+    #[arg(long)]
+    allow_legacy_algorithms: bool,
+    /// 配置模板: tls_server, tls_client, smime, code_signing, iot_device, sm2_dual，
+    /// 预设密钥类型、签名哈希算法、密钥用途和备用名称，命令行中显式指定的参数优先
+    #[arg(long)]
+    profile: Option<String>,
+    /// 配置模板文件（JSON），不存在时写入内置模板以供编辑；默认使用内置模板
+    #[arg(long, default_value = "", requires = "profile")]
+    profiles_file: String,
+    /// 混合模式的后量子密钥类型: ML_DSA_65, ML_DSA_87，每个CN额外生成一对后量子密钥和CSR
+    #[arg(long, default_value = "")]
+    pq_key_type: String,
+    /// 签名方案: pkcs1, pss (RSASSA-PSS，仅RSA密钥)
+    #[arg(long, default_value = "pkcs1")]
+    signature_scheme: String,
+    /// PSS盐长度，单位字节，默认与摘要长度相同
+    #[arg(long)]
+    pss_salt_length: Option<u32>,
+    /// PSS的MGF1哈希算法，默认与签名哈希算法相同
+    #[arg(long, default_value = "")]
+    pss_mgf1_hash: String,
+    /// 有效期开始时间 (ISO8601格式，默认为当前时间)
+    #[arg(long)]
+    not_before: Option<String>,
+    /// 有效期结束时间 (ISO8601格式，默认为开始时间后10年)
+    #[arg(long)]
+    not_after: Option<String>,
+    /// 有效期时长，如 397d、2y、1y6m (从开始时间起算，代替 --not-after)
+    #[arg(long, conflicts_with = "not_after")]
+    validity: Option<String>,
+    /// notBefore偏移模板，如 {INDEX}h，按条目错开生效时间（按时长计算的notAfter随之后移）
+    #[arg(long, default_value = "")]
+    not_before_offset: String,
+    /// notAfter偏移模板，如 {INDEX}h，按条目错开到期时间
+    #[arg(long, default_value = "")]
+    not_after_offset: String,
+    /// 唯一ID
+    #[arg(long, default_value = "")]
+    unique_id: String,
+    /// 将唯一ID写入Subject末尾的serialNumber属性
+    #[arg(long, requires = "unique_id")]
+    unique_id_serial_number: bool,
+    /// 备用名称，如 dNSName=[a.com,b.com];iPAddress=[127.0.0.1]
+    #[arg(long, default_value = "")]
+    sans: String,
+    /// 主题CN为国际化域名时保留UTF-8，不转换为punycode（备用名称中的DNS名称始终转换）
+    #[arg(long)]
+    idn_keep_utf8: bool,
+    /// 密钥用途，逗号分隔
+    #[arg(long, default_value = "")]
+    key_usage: String,
+    /// 扩展密钥用途，逗号分隔
+    #[arg(long, default_value = "")]
+    extended_key_usage: String,
+    /// AD CS证书模板名称或OID（OID后可追加 :主版本[:次版本]），写入CSR请求扩展
+    #[arg(long, default_value = "")]
+    certificate_template: String,
+    /// 自定义扩展，格式同 openssl req -addext：OID=[critical,]值，值为十六进制DER或 UTF8:、IA5: 开头的文本，可重复
+    #[arg(long = "extension")]
+    extensions: Vec<CustomExtension>,
+    /// 输出CSV文件路径
+    #[arg(long, required_unless_present_any = ["verify", "verify_audit_log", "resume", "serve", "ocsp"], default_value = "")]
+    out: String,
+    /// 输出方式: csv, files, both, zip, xlsx, json, ndjson, sqlite
+    #[arg(long, default_value = "csv")]
+    output_mode: String,
+    /// SQLite任务数据库路径，设置后同时写入该数据库（输出方式为sqlite时默认为与输出文件同名的 .db 文件）
+    #[arg(long, default_value = "")]
+    sqlite_path: String,
+    /// CSV分隔符: 单个ASCII字符（如 ; ）或 tab，默认为逗号
+    #[arg(long, default_value = "")]
+    csv_delimiter: String,
+    /// CSV引号方式: necessary, always, non_numeric, never
+    #[arg(long, default_value = "")]
+    csv_quote_style: String,
+    /// CSV换行符: lf, crlf
+    #[arg(long, default_value = "")]
+    csv_line_ending: String,
+    /// CSV文件编码，如 utf-8, gbk, gb18030
+    #[arg(long, default_value = "")]
+    csv_encoding: String,
+    /// 在UTF-8编码的CSV开头写入BOM
+    #[arg(long)]
+    csv_bom: bool,
+    /// CSR编码: pem, base64 (单行Base64), der (单独的CSR文件为二进制DER)
+    #[arg(long, default_value = "pem")]
+    csr_encoding: String,
+    /// 每个CSV文件的最大行数，超过时拆分为 <名称>_001.csv、<名称>_002.csv ……，0表示不拆分
+    #[arg(long, default_value_t = 0)]
+    max_rows_per_file: usize,
+    /// 追加到已有的输出CSV：校验表头后在末尾追加，跳过已有的CN
+    #[arg(long)]
+    append: bool,
+    /// 覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[arg(long)]
+    overwrite: bool,
+    /// 输出公钥SHA-256指纹 (publicKeySha256) 和主题密钥标识符 (subjectKeyId) 列
+    #[arg(long)]
+    fingerprint_columns: bool,
+    /// 生成结束后写入清单 <输出文件名>_manifest.json（各输出文件和CSR的SHA-256）
+    #[arg(long)]
+    manifest: bool,
+    /// 清单签名私钥路径（PEM格式），设置后写入清单并生成分离签名 .sig 文件
+    #[arg(long, default_value = "")]
+    manifest_signing_key: String,
+    /// 清单签名私钥的口令
+    #[arg(
+        long,
+        env = "CSRGEN_MANIFEST_KEY_PASSWORD",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    manifest_key_password: String,
+    /// 输出的列及顺序，逗号分隔，可用 列名=表头 改写表头，如 cn=设备编号,csr,keyPairType
+    #[arg(long, default_value = "")]
+    columns: String,
+    /// PEM文件输出目录
+    #[arg(long, default_value = "")]
+    output_dir: String,
+    /// PEM文件名模板，如 {CN}_{KEYTYPE}_{DATE}.csr
+    #[arg(long, default_value = "")]
+    file_name_template: String,
+    /// 并行生成的线程数，0表示使用CPU核心数
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+    /// 私钥加密口令（建议通过环境变量传入）
+    #[arg(
+        long,
+        env = "CSRGEN_KEY_PASSPHRASE",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    key_passphrase: String,
+    /// CSR质询密码，可使用模板变量
+    #[arg(
+        long,
+        env = "CSRGEN_CHALLENGE_PASSWORD",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    challenge_password: String,
+    /// ZIP压缩包密码
+    #[arg(
+        long,
+        env = "CSRGEN_ZIP_PASSWORD",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    zip_password: String,
+    /// 拆分私钥：输出中不含私钥，私钥另存为 <输出文件名>_keys.zip（使用ZIP密码加密）
+    #[arg(long)]
+    split_keys: bool,
+    /// 密钥存储: file, cng_user, cng_machine (仅Windows), keychain, secure_enclave (仅macOS),
+    /// tpm (Linux/Windows)；
+    /// 非file时密钥不可导出，CSV中不含私钥
+    #[arg(long, default_value = "file")]
+    key_store: String,
+    /// 已有私钥目录，按 <CN>.key 或 <CN>.pem 读取私钥并仅签名CSR（续期时保持密钥不变）
+    #[arg(long, default_value = "")]
+    existing_key_dir: String,
+    /// 【仅用于测试】确定性模式的主种子，每个CN的密钥由 HKDF-SHA256(种子, CN) 派生，
+    /// 相同参数多次运行得到相同的CSR（ECDSA需要OpenSSL 3.2及以上）；派生的密钥切勿用于生产
+    #[arg(
+        long,
+        env = "CSRGEN_DETERMINISTIC_SEED",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    deterministic_seed: String,
+    /// 筛查弱密钥（ROCA特征、小素因子、黑名单），命中的条目按生成失败处理
+    #[arg(long)]
+    screen_weak_keys: bool,
+    /// 弱密钥黑名单文件（openssl-blacklist格式，如 /usr/share/openssl-blacklist/blacklist.RSA-2048）
+    #[arg(long, default_value = "", requires = "screen_weak_keys")]
+    weak_key_blocklist: String,
+    /// 历史索引数据库路径（SQLite），检查并记录各批次生成的CN和公钥指纹
+    #[arg(long, default_value = "")]
+    history_db: String,
+    /// 与历史批次重叠时的处理方式: warn, fail
+    #[arg(long, default_value = "warn", requires = "history_db")]
+    history_conflict: String,
+    /// 审计日志路径，追加记录任务开始、每个生成的条目、写入的文件和任务结束，记录间以哈希链相连
+    #[arg(long, default_value = "")]
+    audit_log: String,
+    /// 断点文件路径，每写完一块后记录进度，中断（如Ctrl+C）后可用 --resume 继续（仅不拆分的csv/both输出）
+    #[arg(long, default_value = "")]
+    checkpoint: String,
+    /// 从断点文件继续中断的生成，其余生成参数取自断点文件（私钥口令等机密参数须重新指定）
+    #[arg(long, value_name = "CHECKPOINT")]
+    resume: Option<String>,
+    /// 条目生成失败时跳过并继续，存在失败条目时退出码为2
+    #[arg(long)]
+    continue_on_error: bool,
+    /// 将失败条目写入 <输出文件名>_errors.csv
+    #[arg(long, requires = "continue_on_error")]
+    error_report: bool,
+    /// 完成回调命令，结束后通过系统shell执行，任务结果JSON写入标准输入，
+    /// 环境变量 CSR_JOB_ID、CSR_JOB_STATE、CSR_OUTPUT_PATH 为任务ID、结束状态和输出路径
+    #[arg(long, default_value = "")]
+    on_complete: String,
+    /// 完成回调Webhook地址，结束后以POST发送任务结果JSON
+    #[arg(long, default_value = "")]
+    webhook: String,
+    /// 不生成，校验已生成的CSV：CSR自签名、私钥与CSR公钥是否匹配及通用名称是否重复，发现问题时退出码为2
+    /// （私钥口令、分隔符和编码沿用 --key-passphrase、--csv-delimiter 和 --csv-encoding）
+    #[arg(long, value_name = "CSV")]
+    verify: Option<String>,
+    /// 不生成，校验审计日志的哈希链，发现被修改、删除或插入的记录时退出码为2
+    #[arg(long, value_name = "LOG")]
+    verify_audit_log: Option<String>,
+    /// 不生成，以本地REST服务运行：POST /jobs 提交任务，GET /jobs/{id} 查询状态，
+    /// GET /jobs/{id}/files/{name} 下载结果
+    #[arg(long)]
+    serve: bool,
+    /// 服务监听的地址
+    #[arg(long, default_value = "127.0.0.1", requires = "serve")]
+    host: String,
+    /// 服务监听的端口
+    #[arg(long, default_value_t = 8443, requires = "serve")]
+    port: u16,
+    /// 服务的输出目录，每个任务写入以任务ID命名的子目录
+    #[arg(long, default_value = "csrgen-jobs", requires = "serve")]
+    serve_dir: String,
+    /// 服务的访问令牌，设置后请求须带 "Authorization: Bearer <令牌>" 头；--host 不是回环地址时必须设置
+    #[arg(long, env = "CSRGEN_TOKEN", default_value = "", hide_env_values = true)]
+    token: String,
+    /// 服务的TLS证书（PEM），与 --tls-key 同时设置时使用HTTPS
+    #[arg(long, default_value = "", requires_all = ["serve", "tls_key"])]
+    tls_cert: String,
+    /// 服务的TLS私钥（PEM）
+    #[arg(long, default_value = "", requires_all = ["serve", "tls_cert"])]
+    tls_key: String,
+    /// 不生成，以OCSP响应器运行并监听该地址（如 127.0.0.1:8888）：按任务历史数据库中的吊销记录
+    /// 回答内置CA所签发证书的OCSP查询（POST / 或 GET /{Base64请求}）
+    #[arg(long, value_name = "ADDR")]
+    ocsp: Option<String>,
+    /// OCSP响应器使用的任务历史数据库（桌面应用配置目录下的 job_history.db）
+    #[arg(long, default_value = "", requires = "ocsp")]
+    ocsp_db: String,
+    /// 签发OCSP响应的CA证书（PEM，或包含证书和私钥的 .p12/.pfx 文件）
+    #[arg(long, default_value = "", requires = "ocsp")]
+    ca_cert: String,
+    /// CA私钥（PEM），CA证书为PKCS#12文件时不需要
+    #[arg(long, default_value = "", requires = "ocsp")]
+    ca_key: String,
+    /// CA私钥口令或PKCS#12密码
+    #[arg(
+        long,
+        env = "CSRGEN_CA_PASSWORD",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    ca_password: String,
+    /// OCSP响应的有效小时数（nextUpdate），0表示不设置
+    #[arg(long, default_value_t = 1, requires = "ocsp")]
+    ocsp_next_update_hours: u32,
+    /// 不输出进度
+    #[arg(long, short)]
+    quiet: bool,
+}
+
+impl Args {
+    /// 转换为生成参数并应用配置模板，模板未预设的密钥类型和签名哈希算法使用默认值
+    fn into_params_with_profile(self) -> Result<BatchRequest, BatchError> {
+        let profile = match &self.profile {
+            Some(id) => Some(Profile::find(&self.profiles_file, id)?),
+            None => None,
+        };
+        let mut params = self.into_params();
+        if let Some(profile) = profile {
+            profile.apply(&mut params);
+        }
+        for (field, default) in [
+            (&mut params.key_type, "RSA_2048"),
+            (&mut params.sign_hash_alg, "SHA256"),
+        ] {
+            if field.is_empty() {
+                *field = default.to_string();
+            }
+        }
+        Ok(params)
+    }
+
+    /// 转换为生成参数，未指定有效期时与桌面应用默认值一致；指定时长时由生成器计算起止时间
+    fn into_params(self) -> BatchRequest {
+        let now = Local::now();
+        let (not_before, not_after) = if self.validity.is_some() {
+            (self.not_before.unwrap_or_default(), String::new())
+        } else {
+            let not_before = self
+                .not_before
+                .unwrap_or_else(|| now.to_rfc3339_opts(SecondsFormat::Secs, false));
+            let not_after = self.not_after.unwrap_or_else(|| {
+                now.checked_add_months(Months::new(120))
+                    .unwrap_or(now)
+                    .to_rfc3339_opts(SecondsFormat::Secs, false)
+            });
+            (not_before, not_after)
+        };
+
+        BatchRequest {
+            cn_range: self.cn_range.unwrap_or_default(),
+            range_radix: self.range_radix,
+            cn_mode: self.cn_mode.unwrap_or_default(),
+            count: self.count,
+            hex_length: self.hex_length,
+            mac_range: self.mac_range.unwrap_or_default(),
+            mac_separator: self.mac_separator,
+            mac_case: self.mac_case,
+            input_csv_path: self.input_csv.unwrap_or_default(),
+            exclude: self.exclude,
+            exclude_path: self.exclude_file.unwrap_or_default(),
+            subject_template: self.subject,
+            dn_string_types: self.dn_string_types,
+            key_type: self.key_type,
+            key_type_map: self.key_type_map,
+            sign_hash_alg: self.sign_hash_alg,
+            issuer_cert_path: self.issuer_cert,
+            allow_legacy_algorithms: self.allow_legacy_algorithms,
+            pq_key_type: self.pq_key_type,
+            signature_scheme: self.signature_scheme,
+            pss_salt_length: self.pss_salt_length,
+            pss_mgf1_hash: self.pss_mgf1_hash,
+            not_before,
+            not_after,
+            validity: self.validity.unwrap_or_default(),
+            not_before_offset: self.not_before_offset,
+            not_after_offset: self.not_after_offset,
+            unique_id: self.unique_id,
+            unique_id_serial_number: self.unique_id_serial_number,
+            sans: self.sans,
+            idn_keep_utf8: self.idn_keep_utf8,
+            output_path: self.out,
+            output_mode: self.output_mode,
+            output_dir: self.output_dir,
+            sqlite_path: self.sqlite_path,
+            csv_delimiter: self.csv_delimiter,
+            csv_quote_style: self.csv_quote_style,
+            csv_line_ending: self.csv_line_ending,
+            csv_encoding: self.csv_encoding,
+            csv_bom: self.csv_bom,
+            max_rows_per_file: self.max_rows_per_file,
+            append: self.append,
+            overwrite: self.overwrite,
+            columns: self.columns,
+            csr_encoding: self.csr_encoding,
+            fingerprint_columns: self.fingerprint_columns,
+            manifest: self.manifest,
+            manifest_signing_key: self.manifest_signing_key,
+            manifest_key_password: self.manifest_key_password,
+            file_name_template: self.file_name_template,
+            zip_password: self.zip_password,
+            split_keys: self.split_keys,
+            thread_count: self.threads,
+            progress_interval_ms: 200,
+            key_passphrase: self.key_passphrase,
+            challenge_password: self.challenge_password,
+            key_usage: self.key_usage,
+            extended_key_usage: self.extended_key_usage,
+            certificate_template: self.certificate_template,
+            extensions: self.extensions,
+            key_store: self.key_store,
+            existing_key_dir: self.existing_key_dir,
+            deterministic_seed: self.deterministic_seed,
+            screen_weak_keys: self.screen_weak_keys,
+            weak_key_blocklist: self.weak_key_blocklist,
+            history_db_path: self.history_db,
+            history_conflict: self.history_conflict,
+            audit_log_path: self.audit_log,
+            checkpoint_path: self.checkpoint,
+            continue_on_error: self.continue_on_error,
+            error_report: self.error_report,
+            on_complete_command: self.on_complete,
+            webhook_url: self.webhook,
+            ..Default::default()
+        }
+    }
+}
+
+/// 校验已生成的CSV并输出结果
+fn verify(args: Args, output_path: String) -> ExitCode {
+    let params = VerifyParams {
+        output_path,
+        key_passphrase: args.key_passphrase,
+        csv_delimiter: args.csv_delimiter,
+        csv_encoding: args.csv_encoding,
+    };
+    match verify_batch(params) {
+        Ok(report) => {
+            for failure in &report.failures {
+                eprintln!(
+                    "第 {} 条 {}: {}",
+                    failure.record, failure.cn, failure.message
+                );
+            }
+            println!("{}", report.message);
+            if report.success {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(2)
+            }
+        }
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 校验审计日志并输出结果
+fn verify_log(path: &str) -> ExitCode {
+    match verify_audit_log(path) {
+        Ok(report) => {
+            println!("{}", report.message);
+            println!("最后一条记录的哈希: {}", report.last_hash);
+            if report.success {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(2)
+            }
+        }
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 以REST服务运行，直到进程退出
+fn serve(args: Args) -> ExitCode {
+    let config = ServerConfig {
+        addr: format!("{}:{}", args.host, args.port),
+        output_dir: args.serve_dir,
+        token: args.token,
+        tls_cert: args.tls_cert,
+        tls_key: args.tls_key,
+    };
+    let server = match CsrServer::bind(&config) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let scheme = if config.tls_cert.is_empty() {
+        "http"
+    } else {
+        "https"
+    };
+    eprintln!("服务已启动: {}://{}", scheme, config.addr);
+    if config.token.is_empty() {
+        eprintln!("警告: 未设置访问令牌（--token），本机的任何用户都可提交任务和下载私钥（只接受Host为本机地址的请求）");
+    }
+    server.run();
+    ExitCode::SUCCESS
+}
+
+/// 以OCSP响应器运行，直到进程退出
+fn ocsp(args: Args, addr: String) -> ExitCode {
+    let config = OcspConfig {
+        addr,
+        db_path: args.ocsp_db,
+        ca_cert_path: args.ca_cert,
+        ca_key_path: args.ca_key,
+        ca_password: args.ca_password,
+        next_update_hours: args.ocsp_next_update_hours,
+    };
+    let responder = match OcspResponder::bind(&config) {
+        Ok(responder) => responder,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!("OCSP响应器已启动: http://{}", config.addr);
+    responder.run();
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Some(addr) = args.ocsp.clone() {
+        return ocsp(args, addr);
+    }
+    if args.serve {
+        return serve(args);
+    }
+    if let Some(path) = &args.verify_audit_log {
+        return verify_log(path);
+    }
+    if let Some(output_path) = args.verify.clone() {
+        return verify(args, output_path);
+    }
+    let quiet = args.quiet;
+    let params = match args.resume.clone() {
+        Some(path) => resume_checkpoint(&path, &args.into_params()),
+        None => args.into_params_with_profile(),
+    };
+    let params = match params {
+        Ok(params) => params,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let hooks = CompletionHooks::from_request(&params);
+    let mut result = run_batch(params, &CancelToken::default(), &|progress| {
+        if !quiet {
+            eprint!("\r生成中 {}/{}", progress.done, progress.total);
+            let _ = std::io::stderr().flush();
+        }
+    });
+    if !quiet {
+        eprintln!();
+    }
+    let hook_warnings = hooks.run(&result);
+    match &mut result {
+        Ok(report) => report.warnings.extend(hook_warnings),
+        Err(_) => hook_warnings.iter().for_each(|w| eprintln!("警告: {}", w)),
+    }
+
+    match result {
+        Ok(result) => {
+            println!("{}", result.message);
+            println!("输出文件: {}", result.output_path);
+            for failure in &result.failures {
+                eprintln!("失败: {}: {}", failure.cn, failure.error);
+            }
+            for warning in &result.warnings {
+                eprintln!("警告: {}", warning);
+            }
+            if !result.error_report_path.is_empty() {
+                println!("错误报告: {}", result.error_report_path);
+            }
+            if !result.manifest_path.is_empty() {
+                println!("清单: {}", result.manifest_path);
+            }
+            if result.failed > 0 {
+                ExitCode::from(2)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
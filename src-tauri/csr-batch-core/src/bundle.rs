@@ -0,0 +1,282 @@
+//! PKCS#12打包模块
+//! 将已签发CSV中每条记录的私钥、证书（及证书链文件和该行chain列中的证书链）打包为单独的 .p12 文件，
+//! 并在输出CSV中记录打包文件路径和证书SHA-256指纹
+
+use anyhow::Result;
+use csv::{ReaderBuilder, StringRecord, Writer};
+use openssl::hash::MessageDigest;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::stack::Stack;
+use openssl::x509::{X509Ref, X509};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audit_log::AuditLog;
+use crate::error::BatchError;
+use crate::fingerprint::colon_hex;
+use crate::output::{derived_csv_path, sanitize_file_name};
+use crate::subject::parse_subject;
+
+/// PKCS#12导出参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct Pkcs12Params {
+    /// 已签发的CSV文件路径（需包含subject、privateKey和certificate列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _p12 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// .p12文件输出目录 (可选，默认为与输出CSV同名的目录)
+    #[serde(default)]
+    pub output_dir: String,
+    /// PKCS#12文件密码
+    #[serde(default)]
+    pub password: String,
+    /// 私钥加密口令 (CSV中的私钥为加密PKCS#8格式时需要)
+    #[serde(default)]
+    pub key_passphrase: String,
+    /// 证书链文件路径 (可选，PEM格式，可包含多个证书)
+    #[serde(default)]
+    pub chain_path: String,
+    /// 审计日志路径 (可选，每导出一个私钥追加一条key_exported记录，格式同生成时的审计日志)
+    #[serde(default)]
+    pub audit_log_path: String,
+}
+
+/// PKCS#12导出结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct Pkcs12Result {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 导出的.p12文件总数
+    pub total: usize,
+    /// 输出CSV文件路径
+    pub output_path: String,
+    /// .p12文件输出目录
+    pub output_dir: String,
+}
+
+/// 证书的SHA-256指纹，格式为冒号分隔的大写十六进制
+pub(crate) fn sha256_fingerprint(cert: &X509Ref) -> Result<String> {
+    Ok(colon_hex(&cert.digest(MessageDigest::sha256())?))
+}
+
+/// 取Subject中的CN作为文件名和友好名称，没有CN时使用记录序号
+fn bundle_name(subject: &str, number: usize) -> String {
+    parse_subject(subject)
+        .ok()
+        .and_then(|attrs| attrs.into_iter().find(|a| a.attr == "CN"))
+        .map(|a| a.value)
+        .unwrap_or_else(|| format!("record{}", number))
+}
+
+/// 在表头中查找列，不存在时追加，返回列位置
+pub(crate) fn ensure_column(headers: &mut StringRecord, name: &str) -> usize {
+    match headers.iter().position(|h| h == name) {
+        Some(col) => col,
+        None => {
+            headers.push_field(name);
+            headers.len() - 1
+        }
+    }
+}
+
+/// 将CSV中的每条记录导出为PKCS#12文件
+pub fn export_pkcs12_bundles(params: Pkcs12Params) -> Result<Pkcs12Result, BatchError> {
+    export_records(&params).map_err(BatchError::from)
+}
+
+fn export_records(params: &Pkcs12Params) -> Result<Pkcs12Result> {
+    let chain = if params.chain_path.is_empty() {
+        Vec::new()
+    } else {
+        let pem =
+            fs::read(&params.chain_path).map_err(|e| BatchError::io(&params.chain_path, e))?;
+        X509::stack_from_pem(&pem).map_err(|e| BatchError::parameter("chain_path", e))?
+    };
+
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let subject_col = column("subject").ok_or_else(|| BatchError::header("缺少subject列"))?;
+    let key_col = column("privateKey").ok_or_else(|| BatchError::header("缺少privateKey列"))?;
+    let cert_col = column("certificate")
+        .ok_or_else(|| BatchError::header("缺少certificate列，请先签发或导入证书"))?;
+    let chain_col = column("chain");
+    let path_col = ensure_column(&mut headers, "pkcs12Path");
+    let fingerprint_col = ensure_column(&mut headers, "certificateSha256");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "p12")
+    } else {
+        params.output_path.clone()
+    };
+    let output_dir = if params.output_dir.is_empty() {
+        Path::new(&output_path).with_extension("")
+    } else {
+        PathBuf::from(&params.output_dir)
+    };
+    fs::create_dir_all(&output_dir).map_err(|e| BatchError::io(&output_dir, e))?;
+    let mut writer =
+        Writer::from_path(&output_path).map_err(|e| BatchError::io(&output_path, e))?;
+    writer.write_record(&headers)?;
+    let mut audit = AuditLog::open(&params.audit_log_path, "")?;
+
+    let mut total = 0;
+    for (index, record) in reader.records().enumerate() {
+        let number = index + 1;
+        let record = record.map_err(|e| BatchError::record(number, format!("格式错误: {}", e)))?;
+        let cell = |col: usize| record.get(col).unwrap_or("").trim();
+
+        if cell(cert_col).is_empty() {
+            return Err(BatchError::record(number, "缺少证书").into());
+        }
+        let cert = X509::from_pem(cell(cert_col).as_bytes())
+            .map_err(|e| BatchError::record(number, format!("证书无效: {}", e)))?;
+        let key = if params.key_passphrase.is_empty() {
+            PKey::private_key_from_pem(cell(key_col).as_bytes())
+        } else {
+            PKey::private_key_from_pem_passphrase(
+                cell(key_col).as_bytes(),
+                params.key_passphrase.as_bytes(),
+            )
+        }
+        .map_err(|e| {
+            BatchError::record(number, format!("私钥无法读取，加密私钥需提供口令: {}", e))
+        })?;
+        if !cert.public_key()?.public_eq(&key) {
+            return Err(BatchError::record(number, "私钥与证书不匹配").into());
+        }
+
+        // 签发时写入的证书链，与证书链文件中的证书去重合并
+        let row_chain = match chain_col.map(cell).unwrap_or("") {
+            "" => Vec::new(),
+            pem => X509::stack_from_pem(pem.as_bytes())
+                .map_err(|e| BatchError::record(number, format!("证书链无效: {}", e)))?,
+        };
+        let name = bundle_name(cell(subject_col), number);
+        let mut ca = Stack::new()?;
+        let mut seen = Vec::new();
+        for chain_cert in chain.iter().chain(&row_chain) {
+            let der = chain_cert.to_der()?;
+            if !seen.contains(&der) {
+                ca.push(chain_cert.clone())?;
+                seen.push(der);
+            }
+        }
+        let pkcs12 = Pkcs12::builder()
+            .name(&name)
+            .pkey(&key)
+            .cert(&cert)
+            .ca(ca)
+            .build2(&params.password)
+            .map_err(|e| BatchError::record(number, format!("打包失败: {}", e)))?;
+        let bundle_path = output_dir.join(format!("{}.p12", sanitize_file_name(&name)));
+        fs::write(&bundle_path, pkcs12.to_der()?)?;
+
+        let bundle_path = bundle_path.to_string_lossy().to_string();
+        let fingerprint = sha256_fingerprint(&cert)?;
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[path_col] = &bundle_path;
+        fields[fingerprint_col] = &fingerprint;
+        writer.write_record(&fields)?;
+        if let Some(audit) = &mut audit {
+            audit.record(
+                "key_exported",
+                json!({
+                    "cn": name,
+                    "path": bundle_path,
+                    "certificate_sha256": fingerprint,
+                }),
+            )?;
+        }
+        total += 1;
+    }
+    writer.flush()?;
+    if let Some(audit) = &mut audit {
+        audit.record_file(&output_path)?;
+    }
+
+    Ok(Pkcs12Result {
+        success: true,
+        message: format!("成功导出 {} 个PKCS#12文件", total),
+        total,
+        output_path,
+        output_dir: output_dir.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+
+    #[test]
+    fn test_export_pkcs12() {
+        // 自签名证书作为已签发的记录
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "YDL0001").unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = builder.build();
+
+        let input = std::env::temp_dir().join("csr_batch_p12_test.csv");
+        let mut writer = Writer::from_path(&input).unwrap();
+        writer
+            .write_record(["subject", "privateKey", "certificate"])
+            .unwrap();
+        writer
+            .write_record([
+                "CN=[YDL0001]",
+                std::str::from_utf8(&key.private_key_to_pem_pkcs8().unwrap()).unwrap(),
+                std::str::from_utf8(&cert.to_pem().unwrap()).unwrap(),
+            ])
+            .unwrap();
+        writer.flush().unwrap();
+
+        let result = export_pkcs12_bundles(Pkcs12Params {
+            input_path: input.to_string_lossy().to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(result.total, 1);
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[4], sha256_fingerprint(&cert).unwrap());
+        let der = fs::read(&record[3]).unwrap();
+        let parsed = Pkcs12::from_der(&der).unwrap().parse2("secret").unwrap();
+        assert_eq!(
+            parsed.cert.unwrap().to_der().unwrap(),
+            cert.to_der().unwrap()
+        );
+        assert!(parsed.pkey.unwrap().public_eq(&key));
+
+        fs::remove_dir_all(&result.output_dir).ok();
+        fs::remove_file(&result.output_path).ok();
+        fs::remove_file(input).ok();
+    }
+}
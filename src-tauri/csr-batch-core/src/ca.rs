@@ -0,0 +1,534 @@
+//! 内置简易CA模块
+//! 使用提供的CA证书和私钥为生成结果CSV中的每个CSR签发证书，
+//! 输出在原有列之后增加certificate列的CSV文件
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, Writer};
+use openssl::asn1::Asn1Time;
+use openssl::base64;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::x509::extension::{AuthorityKeyIdentifier, BasicConstraints, SubjectKeyIdentifier};
+use openssl::x509::{X509Builder, X509Req, X509};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::csr_generator::message_digest;
+use crate::error::BatchError;
+use crate::inspect::{extension_object, request_extensions};
+use crate::output::derived_csv_path;
+use crate::policy::check_hash_alg;
+use crate::signature::issuer_hash_alg;
+use crate::subject::oid_text;
+use crate::validity::parse_datetime;
+
+/// 签发参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct SignParams {
+    /// 待签发的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _signed 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// CA证书路径 (PEM格式，或扩展名为 .p12/.pfx 的PKCS#12文件)
+    pub ca_cert_path: String,
+    /// CA私钥路径 (PEM格式，CA证书为PKCS#12文件时不需要)
+    #[serde(default)]
+    pub ca_key_path: String,
+    /// CA私钥口令或PKCS#12密码 (可选)
+    #[serde(default)]
+    pub ca_password: String,
+    /// 证书有效天数 (可选，0表示使用CSV中的notBefore/notAfter)
+    #[serde(default)]
+    pub validity_days: u32,
+    /// 序列号策略: random (默认，159位随机数), sequential (从serial_start开始递增)
+    #[serde(default)]
+    pub serial_policy: String,
+    /// 顺序序列号的起始值 (可选，默认为1)
+    #[serde(default)]
+    pub serial_start: u64,
+    /// 签名哈希算法: SHA256 (默认), SHA384, SHA512, SHA1, MatchIssuer (与CA证书一致)
+    #[serde(default)]
+    pub sign_hash_alg: String,
+    /// 是否允许SHA1等遗留算法 (默认拒绝；允许并实际使用时在结果消息中记录)
+    #[serde(default)]
+    pub allow_legacy_algorithms: bool,
+}
+
+/// 签发结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct SignResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 证书序列号策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SerialPolicy {
+    /// 随机序列号
+    Random,
+    /// 从指定值开始递增的序列号
+    Sequential(u64),
+}
+
+impl SerialPolicy {
+    /// 从签发参数解析序列号策略，空字符串为随机
+    fn from_params(params: &SignParams) -> Result<Self> {
+        match params.serial_policy.as_str() {
+            "" | "random" => Ok(SerialPolicy::Random),
+            "sequential" => Ok(SerialPolicy::Sequential(params.serial_start.max(1))),
+            other => Err(BatchError::parameter(
+                "serial_policy",
+                format!("不支持的序列号策略: {}", other),
+            )
+            .into()),
+        }
+    }
+
+    /// 第index个证书（从0开始）的序列号
+    fn serial(&self, index: usize) -> Result<BigNum> {
+        match self {
+            SerialPolicy::Random => {
+                // 最高位置1保证序列号为固定长度的正整数
+                let mut serial = BigNum::new()?;
+                serial.rand(159, MsbOption::ONE, false)?;
+                Ok(serial)
+            }
+            SerialPolicy::Sequential(start) => {
+                let serial = start
+                    .checked_add(index as u64)
+                    .ok_or_else(|| anyhow!("序列号超出范围"))?;
+                Ok(BigNum::from_dec_str(&serial.to_string())?)
+            }
+        }
+    }
+}
+
+/// 由CA设置、不从CSR复制的扩展（否则证书中的扩展重复，CSR可借此把自己声明为CA）
+const CA_EXTENSIONS: [Nid; 3] = [
+    Nid::BASIC_CONSTRAINTS,
+    Nid::SUBJECT_KEY_IDENTIFIER,
+    Nid::AUTHORITY_KEY_IDENTIFIER,
+];
+
+/// 签发用的CA证书和私钥
+pub(crate) struct CertificateAuthority {
+    pub(crate) cert: X509,
+    pub(crate) key: PKey<Private>,
+    pub(crate) digest: MessageDigest,
+    /// 已允许使用的遗留算法记录
+    legacy_algorithm: Option<String>,
+}
+
+impl CertificateAuthority {
+    /// 加载CA证书和私钥，并检查二者是否匹配
+    pub(crate) fn load(params: &SignParams) -> Result<Self> {
+        let cert_path = Path::new(&params.ca_cert_path);
+        let cert_bytes =
+            fs::read(cert_path).map_err(|e| BatchError::io(&params.ca_cert_path, e))?;
+
+        let is_pkcs12 = cert_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("p12") || e.eq_ignore_ascii_case("pfx"));
+
+        let (cert, key) = if is_pkcs12 {
+            let parsed = Pkcs12::from_der(&cert_bytes)?
+                .parse2(&params.ca_password)
+                .map_err(|e| anyhow!("无法解析PKCS#12文件，请检查密码: {}", e))?;
+            let cert = parsed
+                .cert
+                .ok_or_else(|| anyhow!("PKCS#12文件中缺少证书"))?;
+            let key = parsed
+                .pkey
+                .ok_or_else(|| anyhow!("PKCS#12文件中缺少私钥"))?;
+            (cert, key)
+        } else {
+            let cert = X509::from_pem(&cert_bytes).map_err(|e| anyhow!("无效的CA证书: {}", e))?;
+            if params.ca_key_path.is_empty() {
+                return Err(anyhow!("未指定CA私钥"));
+            }
+            let key_bytes = fs::read(&params.ca_key_path)
+                .map_err(|e| BatchError::io(&params.ca_key_path, e))?;
+            let key = if params.ca_password.is_empty() {
+                PKey::private_key_from_pem(&key_bytes)
+            } else {
+                PKey::private_key_from_pem_passphrase(&key_bytes, params.ca_password.as_bytes())
+            }
+            .map_err(|e| anyhow!("无法读取CA私钥，请检查口令: {}", e))?;
+            (cert, key)
+        };
+
+        if !cert.public_key()?.public_eq(&key) {
+            return Err(anyhow!("CA私钥与CA证书不匹配"));
+        }
+
+        // MatchIssuer时与CA证书自身的签名哈希算法一致
+        let sign_hash_alg = if params.sign_hash_alg == "MatchIssuer" {
+            issuer_hash_alg(&cert)?
+        } else {
+            &params.sign_hash_alg
+        };
+        let legacy_algorithm = check_hash_alg(
+            "sign_hash_alg",
+            sign_hash_alg,
+            params.allow_legacy_algorithms,
+        )?;
+        let digest = ca_digest(&key, sign_hash_alg);
+        Ok(Self {
+            cert,
+            key,
+            digest,
+            legacy_algorithm,
+        })
+    }
+
+    /// 为CSR签发证书，复制CSR中的请求扩展；基本约束和密钥标识符由CA设置，忽略CSR中的同类扩展
+    fn sign(
+        &self,
+        req: &X509Req,
+        serial: &BigNum,
+        not_before: &Asn1Time,
+        not_after: &Asn1Time,
+    ) -> Result<X509> {
+        let pubkey = req.public_key()?;
+        if !req.verify(&pubkey)? {
+            return Err(anyhow!("CSR签名无效"));
+        }
+
+        let mut builder = X509Builder::new()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(serial.to_asn1_integer()?.as_ref())?;
+        builder.set_subject_name(req.subject_name())?;
+        builder.set_issuer_name(self.cert.subject_name())?;
+        builder.set_pubkey(&pubkey)?;
+        builder.set_not_before(not_before)?;
+        builder.set_not_after(not_after)?;
+
+        let mut seen = HashSet::new();
+        for extension in request_extensions(req)? {
+            let object = extension_object(&extension);
+            if CA_EXTENSIONS.contains(&object.nid()) {
+                continue;
+            }
+            let oid = oid_text(object);
+            if !seen.insert(oid.clone()) {
+                return Err(anyhow!("CSR中的扩展重复: {}", oid));
+            }
+            builder.append_extension(extension)?;
+        }
+        builder.append_extension(BasicConstraints::new().build()?)?;
+        let subject_key_id =
+            SubjectKeyIdentifier::new().build(&builder.x509v3_context(Some(&self.cert), None))?;
+        builder.append_extension(subject_key_id)?;
+        // CA证书不含主体密钥标识符时无法生成颁发机构密钥标识符
+        if self.cert.subject_key_id().is_some() {
+            let authority_key_id = AuthorityKeyIdentifier::new()
+                .keyid(false)
+                .build(&builder.x509v3_context(Some(&self.cert), None))?;
+            builder.append_extension(authority_key_id)?;
+        }
+
+        builder.sign(&self.key, self.digest)?;
+        Ok(builder.build())
+    }
+}
+
+/// 根据CA私钥类型选择签名摘要：EdDSA不使用独立摘要，SM2固定使用SM3
+fn ca_digest(key: &PKey<Private>, sign_hash_alg: &str) -> MessageDigest {
+    if matches!(key.id(), Id::ED25519 | Id::ED448) {
+        return MessageDigest::null();
+    }
+    let is_sm2 = key
+        .ec_key()
+        .ok()
+        .and_then(|ec| ec.group().curve_name())
+        .is_some_and(|nid| nid == Nid::SM2);
+    if is_sm2 {
+        MessageDigest::sm3()
+    } else {
+        message_digest(sign_hash_alg)
+    }
+}
+
+/// 将ISO8601时间（如 2025-01-01T00:00:00+08:00）或其他常见写法解析为证书时间
+fn parse_time(value: &str) -> Result<Asn1Time> {
+    let time = parse_datetime(value).ok_or_else(|| anyhow!("无效的时间 {}", value))?;
+    Ok(Asn1Time::from_unix(time.timestamp())?)
+}
+
+/// 解析CSV中的CSR，支持PEM和单行Base64（以base64或der编码生成时的csr列）
+pub(crate) fn parse_csr(csr: &str) -> Result<X509Req> {
+    let csr = csr.trim();
+    if csr.starts_with("-----") {
+        return Ok(X509Req::from_pem(csr.as_bytes())?);
+    }
+    Ok(X509Req::from_der(&base64::decode_block(csr)?)?)
+}
+
+/// 使用CA为CSV中的每个CSR签发证书
+pub fn sign_batch(params: SignParams) -> Result<SignResult, BatchError> {
+    sign_records(&params).map_err(BatchError::from)
+}
+
+fn sign_records(params: &SignParams) -> Result<SignResult> {
+    let ca = CertificateAuthority::load(params)?;
+    let serial_policy = SerialPolicy::from_params(params)?;
+
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let csr_col = column("csr").ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let not_before_col = column("notBefore");
+    let not_after_col = column("notAfter");
+    // 重复签发时覆盖已有的certificate列
+    let cert_col = column("certificate");
+    if cert_col.is_none() {
+        headers.push_field("certificate");
+    }
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "signed")
+    } else {
+        params.output_path.clone()
+    };
+    let mut writer =
+        Writer::from_path(&output_path).map_err(|e| BatchError::io(&output_path, e))?;
+    writer.write_record(&headers)?;
+
+    let mut total = 0;
+    for (index, record) in reader.records().enumerate() {
+        // 以数据条目计数，PEM字段跨多行，行号无意义
+        let number = index + 1;
+        let record = record.map_err(|e| BatchError::record(number, format!("格式错误: {}", e)))?;
+        let cell = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("");
+
+        let req = parse_csr(cell(Some(csr_col)))
+            .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+        let (not_before, not_after) = if params.validity_days > 0 {
+            (
+                Asn1Time::days_from_now(0)?,
+                Asn1Time::days_from_now(params.validity_days)?,
+            )
+        } else {
+            let parse = |col: Option<usize>, name: &str| {
+                parse_time(cell(col))
+                    .map_err(|e| BatchError::record(number, format!("{}无效: {}", name, e)))
+            };
+            (
+                parse(not_before_col, "notBefore")?,
+                parse(not_after_col, "notAfter")?,
+            )
+        };
+
+        let cert = ca
+            .sign(&req, &serial_policy.serial(index)?, &not_before, &not_after)
+            .map_err(|e| BatchError::record(number, format!("签发失败: {}", e)))?;
+        let cert_pem = String::from_utf8(cert.to_pem()?)?;
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        match cert_col {
+            Some(col) => fields[col] = &cert_pem,
+            None => fields.push(&cert_pem),
+        }
+        writer.write_record(&fields)?;
+        total += 1;
+    }
+    writer.flush()?;
+
+    let mut message = format!("成功签发 {} 个证书", total);
+    if let Some(record) = &ca.legacy_algorithm {
+        message.push_str(&format!("，{}", record));
+    }
+    Ok(SignResult {
+        success: true,
+        message,
+        total,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::stack::Stack;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509Extension, X509NameBuilder, X509ReqBuilder};
+
+    /// 生成自签名测试CA，返回证书和私钥的PEM
+    fn test_ca() -> (Vec<u8>, Vec<u8>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test CA").unwrap();
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        builder
+            .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = builder.build();
+        (
+            cert.to_pem().unwrap(),
+            key.private_key_to_pem_pkcs8().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_sign_csr_batch() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("csr_batch_sign_test.csv");
+        let ca_cert_path = dir.join("csr_batch_sign_test_ca.crt");
+        let ca_key_path = dir.join("csr_batch_sign_test_ca.key");
+        let (ca_cert, ca_key) = test_ca();
+        fs::write(&ca_cert_path, &ca_cert).unwrap();
+        fs::write(&ca_key_path, &ca_key).unwrap();
+
+        let generate = BatchRequest {
+            cn_range: "YDL0001-YDL0003".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            not_before: "2025-01-01T00:00:00+08:00".to_string(),
+            not_after: "2026-01-01T00:00:00+08:00".to_string(),
+            extended_key_usage: "clientAuth".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let result = sign_batch(SignParams {
+            input_path: input.to_string_lossy().to_string(),
+            ca_cert_path: ca_cert_path.to_string_lossy().to_string(),
+            ca_key_path: ca_key_path.to_string_lossy().to_string(),
+            serial_policy: "sequential".to_string(),
+            serial_start: 100,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(result.total, 3);
+        assert!(result
+            .output_path
+            .ends_with("csr_batch_sign_test_signed.csv"));
+
+        let ca = X509::from_pem(&ca_cert).unwrap();
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap().iter().next_back(),
+            Some("certificate")
+        );
+        let certs: Vec<X509> = reader
+            .records()
+            .map(|r| X509::from_pem(r.unwrap().iter().next_back().unwrap().as_bytes()).unwrap())
+            .collect();
+        assert_eq!(certs.len(), 3);
+        assert!(certs[0].verify(&ca.public_key().unwrap()).unwrap());
+        assert_eq!(
+            certs[2]
+                .serial_number()
+                .to_bn()
+                .unwrap()
+                .to_dec_str()
+                .unwrap()
+                .to_string(),
+            "102"
+        );
+        // 有效期来自CSV中的notBefore
+        assert_eq!(
+            certs[0].not_before().to_string(),
+            parse_time("2025-01-01T00:00:00+08:00").unwrap().to_string()
+        );
+
+        for path in [&input, &ca_cert_path, &ca_key_path] {
+            fs::remove_file(path).ok();
+        }
+        fs::remove_file(&result.output_path).ok();
+    }
+
+    #[test]
+    fn test_sign_ignores_ca_extensions() {
+        let (ca_cert, ca_key) = test_ca();
+        let cert = X509::from_pem(&ca_cert).unwrap();
+        let key = PKey::private_key_from_pem(&ca_key).unwrap();
+        let ca = CertificateAuthority {
+            cert,
+            key,
+            digest: MessageDigest::sha256(),
+            legacy_algorithm: None,
+        };
+        let request = |extensions: Vec<X509Extension>| {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+            let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+            let mut builder = X509ReqBuilder::new().unwrap();
+            builder.set_pubkey(&key).unwrap();
+            let mut stack = Stack::new().unwrap();
+            for extension in extensions {
+                stack.push(extension).unwrap();
+            }
+            builder.add_extensions(&stack).unwrap();
+            builder.sign(&key, MessageDigest::sha256()).unwrap();
+            builder.build()
+        };
+        let san = || {
+            let context = X509ReqBuilder::new().unwrap();
+            SubjectAlternativeName::new()
+                .dns("dev01.example.com")
+                .build(&context.x509v3_context(None))
+                .unwrap()
+        };
+        let serial = BigNum::from_u32(1).unwrap();
+        let (not_before, not_after) = (
+            Asn1Time::days_from_now(0).unwrap(),
+            Asn1Time::days_from_now(1).unwrap(),
+        );
+
+        // CSR中声明为CA的基本约束被忽略，证书中只有CA设置的一个
+        let ca_constraints = BasicConstraints::new().critical().ca().build().unwrap();
+        let req = request(vec![ca_constraints, san()]);
+        let cert = ca.sign(&req, &serial, &not_before, &not_after).unwrap();
+        let text = String::from_utf8(cert.to_text().unwrap()).unwrap();
+        assert_eq!(text.matches("Basic Constraints").count(), 1, "{}", text);
+        assert!(
+            text.contains("CA:FALSE") && !text.contains("CA:TRUE"),
+            "{}",
+            text
+        );
+        assert_eq!(
+            text.matches("Subject Key Identifier").count(),
+            1,
+            "{}",
+            text
+        );
+        assert!(text.contains("DNS:dev01.example.com"));
+
+        // 其他扩展重复时拒绝签发
+        let req = request(vec![san(), san()]);
+        let error = ca.sign(&req, &serial, &not_before, &not_after).unwrap_err();
+        assert!(error.to_string().contains("扩展重复"), "{}", error);
+    }
+}
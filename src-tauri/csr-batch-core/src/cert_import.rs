@@ -0,0 +1,633 @@
+//! 证书导入模块
+//! CA离线签发后返回一个目录或ZIP包的证书文件，按公钥指纹（或通用名称）将每个证书对应回生成结果CSV中的行，
+//! 校验证书链后输出在原有列之后增加certificate列和chain列的CSV文件，可选再导出为每行一个PKCS#12文件
+//!
+//! 证书文件可为PEM（可含多个证书）、DER或PKCS#7（.p7b/.p7c），无法解析的文件被忽略。
+//! 未与任何行对应的证书作为中间CA和根CA，用于构建证书链
+
+use anyhow::{anyhow, Result};
+use csv::ReaderBuilder;
+use openssl::hash::{hash, MessageDigest};
+use openssl::nid::Nid;
+use openssl::pkcs7::Pkcs7;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509Ref, X509StoreContext, X509VerifyResult, X509};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::bundle::{ensure_column, export_pkcs12_bundles, Pkcs12Params};
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::fingerprint::colon_hex;
+use crate::output::{derived_csv_path, ResultCsv};
+use crate::subject::parse_subject;
+
+/// 证书链的最大深度
+const MAX_CHAIN_DEPTH: usize = 10;
+
+/// 证书导入参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportParams {
+    /// 生成结果CSV文件路径（需包含csr列，或publicKeySha256列，或cn/subject列）
+    pub input_path: String,
+    /// 签发的证书所在的目录或ZIP文件
+    pub source_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _imported 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// 匹配方式：auto (默认，按公钥指纹，CSV中没有公钥时按CN), public_key, cn
+    #[serde(default)]
+    pub match_by: String,
+    /// 受信任的CA证书 (PEM，可选，设置后按其完整校验证书链)
+    #[serde(default)]
+    pub ca_bundle_path: String,
+    /// 是否同时导出PKCS#12文件 (所有行都须找到证书)
+    #[serde(default)]
+    pub export_pkcs12: bool,
+    /// PKCS#12文件密码
+    #[serde(default)]
+    pub pkcs12_password: String,
+    /// 私钥加密口令 (导出PKCS#12且CSV中的私钥为加密PKCS#8格式时需要)
+    #[serde(default)]
+    pub key_passphrase: String,
+}
+
+/// 证书导入结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// CSV中的行数
+    pub total: usize,
+    /// 找到证书的行数
+    pub matched: usize,
+    /// 未找到证书的行数
+    pub unmatched: usize,
+    /// 读取的证书文件中的证书数（去重后）
+    pub certificates: usize,
+    /// 输出CSV文件路径
+    pub output_path: String,
+    /// PKCS#12文件输出目录 (未导出时为空)
+    pub pkcs12_dir: String,
+}
+
+/// 匹配方式
+#[derive(Clone, Copy, PartialEq)]
+enum MatchBy {
+    Auto,
+    PublicKey,
+    Cn,
+}
+
+impl MatchBy {
+    fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "" | "auto" => Ok(Self::Auto),
+            "public_key" => Ok(Self::PublicKey),
+            "cn" => Ok(Self::Cn),
+            other => {
+                let message = format!("不支持的匹配方式: {}", other);
+                Err(BatchError::parameter("match_by", message).into())
+            }
+        }
+    }
+}
+
+/// 公钥（SubjectPublicKeyInfo）的SHA-256指纹，与publicKeySha256列的格式相同
+fn key_fingerprint(cert: &X509Ref) -> Result<String> {
+    let spki = cert.public_key()?.public_key_to_der()?;
+    Ok(colon_hex(&hash(MessageDigest::sha256(), &spki)?))
+}
+
+fn common_name(cert: &X509Ref) -> Option<String> {
+    cert.subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().as_utf8().ok())
+        .map(|cn| cn.to_string())
+}
+
+/// 解析一个证书文件：PEM证书、PEM或DER的PKCS#7、DER证书；不是证书时返回空
+fn parse_certificate_file(bytes: &[u8]) -> Vec<X509> {
+    let pkcs7_certs = |pkcs7: Pkcs7| -> Vec<X509> {
+        pkcs7
+            .signed()
+            .and_then(|signed| signed.certificates())
+            .map(|certs| certs.iter().map(|cert| cert.to_owned()).collect())
+            .unwrap_or_default()
+    };
+    let text = String::from_utf8_lossy(bytes);
+    if text.contains("-----BEGIN CERTIFICATE-----") {
+        X509::stack_from_pem(bytes).unwrap_or_default()
+    } else if text.contains("-----BEGIN PKCS7-----") {
+        Pkcs7::from_pem(bytes).map(pkcs7_certs).unwrap_or_default()
+    } else if let Ok(cert) = X509::from_der(bytes) {
+        vec![cert]
+    } else {
+        Pkcs7::from_der(bytes).map(pkcs7_certs).unwrap_or_default()
+    }
+}
+
+/// 读取目录（含子目录）或ZIP文件中的所有证书，按DER去重
+fn read_certificates(source: &str) -> Result<Vec<X509>> {
+    let path = Path::new(source);
+    let mut files = Vec::new();
+    if path.is_dir() {
+        let mut dirs = vec![path.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir).map_err(|e| BatchError::io(&dir, e))? {
+                let entry_path = entry?.path();
+                if entry_path.is_dir() {
+                    dirs.push(entry_path);
+                } else {
+                    files.push(fs::read(&entry_path).map_err(|e| BatchError::io(&entry_path, e))?);
+                }
+            }
+        }
+    } else {
+        let file = File::open(path).map_err(|e| BatchError::io(path, e))?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
+            BatchError::parameter("source_path", format!("不是目录或ZIP文件: {}", e))
+        })?;
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            if entry.is_file() {
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                files.push(bytes);
+            }
+        }
+    }
+
+    let mut certs: Vec<X509> = Vec::new();
+    let mut seen = Vec::new();
+    for bytes in files {
+        for cert in parse_certificate_file(&bytes) {
+            let der = cert.to_der()?;
+            if !seen.contains(&der) {
+                seen.push(der);
+                certs.push(cert);
+            }
+        }
+    }
+    Ok(certs)
+}
+
+/// 按受信任的CA完整校验证书链（含有效期），返回除证书本身外的链
+fn verify_chain(cert: &X509, pool: &[&X509], trusted: &[X509]) -> Result<Vec<X509>> {
+    let mut store = X509StoreBuilder::new()?;
+    for ca in trusted {
+        store.add_cert(ca.clone())?;
+    }
+    let store = store.build();
+    let mut untrusted = Stack::new()?;
+    for ca in pool {
+        untrusted.push((*ca).clone())?;
+    }
+    let mut context = X509StoreContext::new()?;
+    context.init(&store, cert, &untrusted, |context| {
+        if !context.verify_cert()? {
+            return Ok(Err(anyhow!(
+                "证书链校验失败: {}",
+                context.error().error_string()
+            )));
+        }
+        let chain = context
+            .chain()
+            .map(|chain| chain.iter().skip(1).map(|ca| ca.to_owned()).collect())
+            .unwrap_or_default();
+        Ok(Ok(chain))
+    })?
+}
+
+/// 在证书文件中按签发者逐级查找证书链，每一级都校验签名；找不到签发者时链到此为止
+fn build_chain(cert: &X509, pool: &[&X509]) -> Result<Vec<X509>> {
+    let mut chain: Vec<X509> = Vec::new();
+    let mut current = cert.clone();
+    while chain.len() < MAX_CHAIN_DEPTH {
+        // 自签名证书为链的终点
+        if current.issued(&current) == X509VerifyResult::OK {
+            break;
+        }
+        let candidates: Vec<&&X509> = pool
+            .iter()
+            .filter(|ca| ca.issued(&current) == X509VerifyResult::OK)
+            .collect();
+        if candidates.is_empty() {
+            break;
+        }
+        let issuer = candidates
+            .into_iter()
+            .find(|ca| {
+                ca.public_key()
+                    .and_then(|key| current.verify(&key))
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| {
+                let subject = common_name(&current).unwrap_or_default();
+                anyhow!("{} 的签发者证书签名校验失败", subject)
+            })?;
+        chain.push((*issuer).clone());
+        current = (*issuer).clone();
+    }
+    Ok(chain)
+}
+
+/// 将签发的证书导入生成结果CSV
+pub fn import_certificates(params: ImportParams) -> Result<ImportResult, BatchError> {
+    import_records(&params).map_err(BatchError::from)
+}
+
+fn import_records(params: &ImportParams) -> Result<ImportResult> {
+    let match_by = MatchBy::parse(&params.match_by)?;
+    if params.source_path.trim().is_empty() {
+        return Err(BatchError::parameter("source_path", "未填写").into());
+    }
+    let certs = read_certificates(params.source_path.trim())?;
+    if certs.is_empty() {
+        return Err(BatchError::parameter("source_path", "没有找到证书文件").into());
+    }
+    let trusted = if params.ca_bundle_path.is_empty() {
+        Vec::new()
+    } else {
+        let path = &params.ca_bundle_path;
+        let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+        X509::stack_from_pem(&pem).map_err(|e| BatchError::parameter("ca_bundle_path", e))?
+    };
+
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let csr_col = column("csr");
+    let key_id_col = column("publicKeySha256");
+    let cn_col = column("cn");
+    let subject_col = column("subject");
+    let has_key = csr_col.is_some() || key_id_col.is_some();
+    if match_by == MatchBy::PublicKey && !has_key {
+        return Err(BatchError::header("按公钥匹配需要csr列或publicKeySha256列").into());
+    }
+    if match_by == MatchBy::Cn && cn_col.is_none() && subject_col.is_none() && csr_col.is_none() {
+        return Err(BatchError::header("按CN匹配需要cn列、subject列或csr列").into());
+    }
+    let by_key = match_by == MatchBy::PublicKey || (match_by == MatchBy::Auto && has_key);
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // 每行的公钥指纹和CN
+    let mut rows = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        let number = index + 1;
+        let cell = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim();
+        let csr = match cell(csr_col) {
+            "" => None,
+            pem => Some(
+                parse_csr(pem)
+                    .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?,
+            ),
+        };
+        let fingerprint = match &csr {
+            Some(csr) => {
+                let spki = csr.public_key()?.public_key_to_der()?;
+                colon_hex(&hash(MessageDigest::sha256(), &spki)?)
+            }
+            None => cell(key_id_col).to_uppercase(),
+        };
+        let cn = match cell(cn_col) {
+            "" => parse_subject(cell(subject_col))
+                .ok()
+                .and_then(|attrs| attrs.into_iter().find(|a| a.attr == "CN"))
+                .map(|a| a.value)
+                .or_else(|| {
+                    let csr = csr.as_ref()?;
+                    let entry = csr.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+                    entry.data().as_utf8().ok().map(|cn| cn.to_string())
+                })
+                .unwrap_or_default(),
+            cn => cn.to_string(),
+        };
+        rows.push((fingerprint, cn));
+    }
+
+    // 按公钥指纹和CN索引证书；同一CN有多个证书时取到期最晚的
+    let mut by_fingerprint: HashMap<String, usize> = HashMap::new();
+    let mut by_cn: HashMap<String, usize> = HashMap::new();
+    for (index, cert) in certs.iter().enumerate() {
+        by_fingerprint.insert(key_fingerprint(cert)?, index);
+        if let Some(cn) = common_name(cert) {
+            match by_cn.get(&cn) {
+                Some(&other) if certs[other].not_after() >= cert.not_after() => {}
+                _ => {
+                    by_cn.insert(cn, index);
+                }
+            }
+        }
+    }
+    let matches: Vec<Option<usize>> = rows
+        .iter()
+        .map(|(fingerprint, cn)| {
+            if by_key {
+                by_fingerprint.get(fingerprint).copied()
+            } else {
+                by_cn.get(cn).copied()
+            }
+        })
+        .collect();
+    // 未与任何行对应的证书用于构建证书链
+    let pool: Vec<&X509> = certs
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !matches.contains(&Some(*index)))
+        .map(|(_, cert)| cert)
+        .collect();
+
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "imported")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+
+    let mut matched = 0;
+    for (index, record) in records.iter().enumerate() {
+        let number = index + 1;
+        let mut cert_pem = String::new();
+        let mut chain_pem = String::new();
+        if let Some(cert) = matches[index].map(|i| &certs[i]) {
+            let (fingerprint, _) = &rows[index];
+            // 按CN匹配时确认证书属于该行的密钥
+            if !by_key && !fingerprint.is_empty() && key_fingerprint(cert)? != *fingerprint {
+                return Err(BatchError::record(number, "按CN找到的证书与该行的公钥不一致").into());
+            }
+            let chain = if trusted.is_empty() {
+                build_chain(cert, &pool)
+            } else {
+                verify_chain(cert, &pool, &trusted)
+            }
+            .map_err(|e| BatchError::record(number, e))?;
+            cert_pem = String::from_utf8(cert.to_pem()?)?;
+            for ca in chain {
+                chain_pem.push_str(&String::from_utf8(ca.to_pem()?)?);
+            }
+            matched += 1;
+        }
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+    }
+    output.finish()?;
+
+    let total = records.len();
+    let unmatched = total - matched;
+    let mut message = format!("导入 {} 个证书", matched);
+    if unmatched > 0 {
+        message.push_str(&format!("，{} 行未找到证书", unmatched));
+    }
+    let mut pkcs12_dir = String::new();
+    if params.export_pkcs12 {
+        if unmatched > 0 {
+            let message = format!(
+                "{}，请补齐证书后再导出PKCS#12（已写入 {}）",
+                message, output_path
+            );
+            return Err(BatchError::parameter("export_pkcs12", message).into());
+        }
+        let exported = export_pkcs12_bundles(Pkcs12Params {
+            input_path: output_path.clone(),
+            password: params.pkcs12_password.clone(),
+            key_passphrase: params.key_passphrase.clone(),
+            ..Default::default()
+        })?;
+        message.push_str(&format!("，导出 {} 个PKCS#12文件", exported.total));
+        pkcs12_dir = exported.output_dir;
+    }
+
+    Ok(ImportResult {
+        success: true,
+        message,
+        total,
+        matched,
+        unmatched,
+        certificates: certs.len(),
+        output_path,
+        pkcs12_dir,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::pkcs12::Pkcs12;
+    use openssl::pkcs7::Pkcs7Flags;
+    use openssl::pkey::{PKey, PKeyRef, Private};
+    use openssl::x509::extension::BasicConstraints;
+    use openssl::x509::{X509Builder, X509NameBuilder, X509Req};
+    use std::io::Write;
+
+    fn issue(
+        cn: &str,
+        key: &PKeyRef<openssl::pkey::Public>,
+        issuer: Option<(&X509, &PKeyRef<Private>)>,
+        ca: bool,
+        signer: &PKeyRef<Private>,
+    ) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", cn).unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        match issuer {
+            Some((issuer, _)) => builder.set_issuer_name(issuer.subject_name()).unwrap(),
+            None => builder.set_issuer_name(&name).unwrap(),
+        }
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        if ca {
+            let constraints = BasicConstraints::new().critical().ca().build().unwrap();
+            builder.append_extension(constraints).unwrap();
+        }
+        let signer = issuer.map(|(_, key)| key).unwrap_or(signer);
+        builder.sign(signer, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn public(key: &PKey<Private>) -> PKey<openssl::pkey::Public> {
+        PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_import_certificates() {
+        let dir = std::env::temp_dir().join("csr_batch_import_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(dir.join("certs/ca")).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2,dev3".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        // 根CA -> 中间CA -> 各行的证书
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let root_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let root = issue("Test Root", &public(&root_key), None, true, &root_key);
+        let int_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let intermediate = issue(
+            "Test Issuing",
+            &public(&int_key),
+            Some((&root, &root_key)),
+            true,
+            &root_key,
+        );
+        let mut reader = csv::Reader::from_path(&input).unwrap();
+        let csr_col = reader.headers().unwrap().iter().position(|h| h == "csr");
+        let leaves: Vec<X509> = reader
+            .records()
+            .enumerate()
+            .map(|(index, record)| {
+                let csr = X509Req::from_pem(record.unwrap()[csr_col.unwrap()].as_bytes()).unwrap();
+                let cn = format!("dev{}", index + 1);
+                let key = csr.public_key().unwrap();
+                issue(&cn, &key, Some((&intermediate, &int_key)), false, &int_key)
+            })
+            .collect();
+
+        // ZIP中为PEM、DER和PKCS#7格式，dev3未签发
+        let zip_path = dir.join("issued.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("dev1.pem", options).unwrap();
+        zip.write_all(&leaves[0].to_pem().unwrap()).unwrap();
+        zip.start_file("nested/dev2.cer", options).unwrap();
+        zip.write_all(&leaves[1].to_der().unwrap()).unwrap();
+        let mut chain = Stack::new().unwrap();
+        chain.push(root.clone()).unwrap();
+        let pkcs7 = Pkcs7::sign(&intermediate, &int_key, &chain, &[], Pkcs7Flags::BINARY).unwrap();
+        zip.start_file("chain.p7b", options).unwrap();
+        zip.write_all(&pkcs7.to_der().unwrap()).unwrap();
+        zip.start_file("readme.txt", options).unwrap();
+        zip.write_all(b"issued by test CA").unwrap();
+        zip.finish().unwrap();
+
+        let params = ImportParams {
+            input_path: input.to_string_lossy().to_string(),
+            source_path: zip_path.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let result = import_certificates(params).unwrap();
+        assert_eq!(
+            (result.matched, result.unmatched, result.certificates),
+            (2, 1, 4)
+        );
+        assert!(result.output_path.ends_with("out_imported.csv"));
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (key_col, cert_col, chain_col) =
+            (column("privateKey"), column("certificate"), column("chain"));
+        let records: Vec<_> = reader.records().map(|r| r.unwrap()).collect();
+        for record in &records[..2] {
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::stack_from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(chain.len(), 2);
+            assert_eq!(chain[1].to_der().unwrap(), root.to_der().unwrap());
+        }
+        assert!(records[2][cert_col].is_empty());
+
+        // 结果文件已存在时默认拒绝覆盖
+        let params = |overwrite: bool| ImportParams {
+            input_path: input.to_string_lossy().to_string(),
+            source_path: zip_path.to_string_lossy().to_string(),
+            export_pkcs12: true,
+            overwrite,
+            ..Default::default()
+        };
+        let error = import_certificates(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 缺少证书时不能导出PKCS#12
+        let error = import_certificates(params(true)).unwrap_err();
+        assert!(error.to_string().contains("1 行未找到证书"), "{}", error);
+
+        // 目录中按CN匹配，以根CA完整校验证书链后导出PKCS#12
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = dir.join(format!("certs/dev{}.crt", index + 1));
+            fs::write(path, leaf.to_pem().unwrap()).unwrap();
+        }
+        fs::write(
+            dir.join("certs/ca/issuing.pem"),
+            intermediate.to_pem().unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.join("root.pem"), root.to_pem().unwrap()).unwrap();
+        let params = ImportParams {
+            input_path: input.to_string_lossy().to_string(),
+            source_path: dir.join("certs").to_string_lossy().to_string(),
+            output_path: dir.join("complete.csv").to_string_lossy().to_string(),
+            match_by: "cn".to_string(),
+            ca_bundle_path: dir.join("root.pem").to_string_lossy().to_string(),
+            export_pkcs12: true,
+            pkcs12_password: "secret".to_string(),
+            ..Default::default()
+        };
+        let result = import_certificates(params).unwrap();
+        assert_eq!((result.matched, result.unmatched), (3, 0));
+        let bundle = fs::read(Path::new(&result.pkcs12_dir).join("dev3.p12")).unwrap();
+        let parsed = Pkcs12::from_der(&bundle).unwrap().parse2("secret").unwrap();
+        assert_eq!(
+            parsed.cert.unwrap().to_der().unwrap(),
+            leaves[2].to_der().unwrap()
+        );
+        assert_eq!(parsed.ca.unwrap().len(), 2);
+
+        // 不受信任的根CA
+        let other_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let other = issue("Other Root", &public(&other_key), None, true, &other_key);
+        fs::write(dir.join("root.pem"), other.to_pem().unwrap()).unwrap();
+        let params = ImportParams {
+            input_path: input.to_string_lossy().to_string(),
+            source_path: dir.join("certs").to_string_lossy().to_string(),
+            ca_bundle_path: dir.join("root.pem").to_string_lossy().to_string(),
+            overwrite: true,
+            ..Default::default()
+        };
+        let error = import_certificates(params).unwrap_err();
+        assert!(error.to_string().contains("证书链校验失败"), "{}", error);
+        fs::remove_dir_all(dir).ok();
+    }
+}
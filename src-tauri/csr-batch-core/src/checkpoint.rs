@@ -0,0 +1,262 @@
+//! 断点续传模块
+//! 设置断点文件后，生成过程中每写完一块并刷新CSV输出，就把本次已写入的行数和CSV文件长度记入断点文件；
+//! 任务被暂停（取消）或程序中途退出后，可用 [`resume_checkpoint`] 从最后写入的行继续，
+//! 已写入的条目不再重新生成，最后一次刷新之后写了一半的内容被截掉
+//!
+//! 仅支持不拆分的CSV输出（csv/both输出方式，不拆分私钥、不同时写入SQLite任务数据库）；
+//! 断点文件不保存机密参数，续传时须重新提供。任务正常完成后删除断点文件
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::csr_generator::BatchRequest;
+use crate::error::BatchError;
+use crate::job_db::now;
+use crate::output::{write_atomic, OutputWriter};
+
+/// 断点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// 任务ID
+    pub job_id: String,
+    /// 更新时间
+    pub saved_at: String,
+    /// 生成参数（不含机密参数）
+    pub params: BatchRequest,
+    /// 正在写入的CSV文件，新建输出时为同目录的临时文件
+    pub csv_path: String,
+    /// 最后一次刷新后CSV文件的长度（字节）
+    pub offset: u64,
+    /// 本次运行已写入的行数
+    pub written: usize,
+    /// 本次运行待生成的条目数
+    pub total: usize,
+}
+
+impl BatchRequest {
+    /// 输出方式是否支持断点续传
+    pub fn supports_checkpoint(&self) -> bool {
+        matches!(self.output_mode.as_str(), "" | "csv" | "both")
+            && self.max_rows_per_file == 0
+            && !self.split_keys
+            && self.sqlite_path.is_empty()
+    }
+}
+
+/// 文件长度
+fn file_len(path: &Path) -> Result<u64> {
+    Ok(fs::metadata(path)
+        .map_err(|e| BatchError::io(path, e))?
+        .len())
+}
+
+/// 生成过程中更新的断点文件
+pub(crate) struct CheckpointFile {
+    path: PathBuf,
+    checkpoint: Checkpoint,
+}
+
+impl CheckpointFile {
+    /// 刷新表头后创建断点文件，未设置checkpoint_path时返回None
+    pub(crate) fn create(
+        request: &BatchRequest,
+        writer: &mut OutputWriter,
+        total: usize,
+    ) -> Result<Option<Self>> {
+        if request.checkpoint_path.is_empty() {
+            return Ok(None);
+        }
+        let Some(csv_path) = writer.csv_file() else {
+            return Ok(None);
+        };
+        writer.flush()?;
+        let path = PathBuf::from(&request.checkpoint_path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| BatchError::io(dir, e))?;
+        }
+        let mut file = Self {
+            path,
+            checkpoint: Checkpoint {
+                job_id: request.job_id.clone(),
+                saved_at: String::new(),
+                params: request.clone().without_secrets(),
+                csv_path: csv_path.to_string_lossy().to_string(),
+                offset: 0,
+                written: 0,
+                total,
+            },
+        };
+        file.save(0)?;
+        Ok(Some(file))
+    }
+
+    /// 输出刷新后记录已写入的行数和CSV文件长度
+    pub(crate) fn save(&mut self, written: usize) -> Result<()> {
+        let checkpoint = &mut self.checkpoint;
+        checkpoint.offset = file_len(Path::new(&checkpoint.csv_path))?;
+        checkpoint.written = written;
+        checkpoint.saved_at = now();
+        write_atomic(&self.path, &serde_json::to_vec_pretty(checkpoint)?)
+    }
+
+    /// 任务结束：完成时删除断点文件，取消时改为指向已提交的输出文件以便续传
+    pub(crate) fn finish(mut self, written: usize, cancelled: bool) -> Result<()> {
+        if cancelled {
+            self.checkpoint.csv_path = self.checkpoint.params.output_path.clone();
+            return self.save(written);
+        }
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                Err(BatchError::io(&self.path, e).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 读取断点文件
+pub fn load_checkpoint(path: &str) -> Result<Checkpoint, BatchError> {
+    let content = fs::read_to_string(path).map_err(|e| BatchError::io(path, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| BatchError::parameter("checkpoint_path", format!("断点文件格式错误: {}", e)))
+}
+
+/// 列出目录中的断点（按更新时间倒序），目录不存在时为空，无法解析的文件被跳过
+pub fn list_checkpoints(dir: &str) -> Result<Vec<Checkpoint>, BatchError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(BatchError::io(dir, e)),
+    };
+    let mut checkpoints: Vec<Checkpoint> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| load_checkpoint(&path.to_string_lossy()).ok())
+        .collect();
+    checkpoints.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+    Ok(checkpoints)
+}
+
+/// 准备从断点续传：将CSV截断到最后一次刷新的位置（新建输出时把临时文件重命名为输出文件），
+/// 返回追加到该文件的生成参数，机密参数取自secrets；
+/// 随机通用名称（uuid/hex）无法按已有条目跳过，数量减去已写入的行数
+pub fn resume_checkpoint(path: &str, secrets: &BatchRequest) -> Result<BatchRequest, BatchError> {
+    let checkpoint = load_checkpoint(path)?;
+    resume(path, checkpoint, secrets).map_err(BatchError::from)
+}
+
+fn resume(path: &str, checkpoint: Checkpoint, secrets: &BatchRequest) -> Result<BatchRequest> {
+    let csv_path = Path::new(&checkpoint.csv_path);
+    if !csv_path.exists() {
+        let message = format!("断点对应的CSV文件不存在: {}", csv_path.display());
+        return Err(BatchError::parameter("checkpoint_path", message).into());
+    }
+    if file_len(csv_path)? < checkpoint.offset {
+        let message = format!(
+            "CSV文件比断点记录的短，可能已被修改: {}",
+            csv_path.display()
+        );
+        return Err(BatchError::parameter("checkpoint_path", message).into());
+    }
+    OpenOptions::new()
+        .write(true)
+        .open(csv_path)
+        .and_then(|file| file.set_len(checkpoint.offset))
+        .map_err(|e| BatchError::io(csv_path, e))?;
+
+    let mut request = checkpoint.params.with_secrets_of(secrets);
+    let output_path = Path::new(&request.output_path);
+    if csv_path != output_path {
+        if output_path.exists() && !request.overwrite {
+            let message = format!("输出文件已存在: {}", output_path.display());
+            return Err(BatchError::parameter("overwrite", message).into());
+        }
+        fs::rename(csv_path, output_path).map_err(|e| BatchError::io(output_path, e))?;
+    }
+    if matches!(request.cn_mode.as_str(), "uuid" | "hex") {
+        request.count = request.count.saturating_sub(checkpoint.written);
+    }
+    request.append = true;
+    request.job_id = checkpoint.job_id;
+    request.checkpoint_path = path.to_string();
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, CancelToken};
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let dir = std::env::temp_dir().join("csr_batch_checkpoint_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("out.csv");
+        let checkpoint = dir.join("checkpoints/job-1.json");
+        let checkpoint_str = checkpoint.to_string_lossy().to_string();
+        let request = BatchRequest {
+            cn_range: "YDL0001-YDL0006".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: output.to_string_lossy().to_string(),
+            key_passphrase: "secret".to_string(),
+            checkpoint_path: checkpoint_str.clone(),
+            job_id: "job-1".to_string(),
+            ..Default::default()
+        };
+
+        // 模拟程序在写入第三行时退出：临时文件中有两行完整数据和半行
+        let report = run_batch(request.clone(), &CancelToken::default(), &|_| {}).unwrap();
+        assert_eq!(report.total, 6);
+        assert!(!checkpoint.exists());
+        let content = fs::read_to_string(&output).unwrap();
+        fs::remove_file(&output).unwrap();
+        let offset = content.find("CN=[YDL0003]").unwrap();
+        let temp = dir.join(".out.csv.partial");
+        fs::write(&temp, &content[..offset + 20]).unwrap();
+        let saved = Checkpoint {
+            job_id: "job-1".to_string(),
+            saved_at: now(),
+            params: request.clone().without_secrets(),
+            csv_path: temp.to_string_lossy().to_string(),
+            offset: offset as u64,
+            written: 2,
+            total: 6,
+        };
+        fs::create_dir_all(checkpoint.parent().unwrap()).unwrap();
+        fs::write(&checkpoint, serde_json::to_string(&saved).unwrap()).unwrap();
+        assert!(!fs::read_to_string(&checkpoint).unwrap().contains("secret"));
+        assert_eq!(
+            list_checkpoints(&dir.join("checkpoints").to_string_lossy())
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let secrets = BatchRequest {
+            key_passphrase: "secret".to_string(),
+            ..Default::default()
+        };
+        let resumed = resume_checkpoint(&checkpoint_str, &secrets).unwrap();
+        assert!(resumed.append && !temp.exists());
+        assert_eq!(resumed.job_id, "job-1");
+        let report = run_batch(resumed, &CancelToken::default(), &|_| {}).unwrap();
+        assert_eq!(report.total, 4);
+        assert!(!checkpoint.exists());
+
+        let mut reader = csv::Reader::from_path(&output).unwrap();
+        let subjects: Vec<String> = reader
+            .records()
+            .map(|record| record.unwrap()[0].to_string())
+            .collect();
+        assert_eq!(subjects.len(), 6);
+        assert_eq!(subjects[2], "CN=[YDL0003]");
+        assert!(resume_checkpoint(&checkpoint_str, &secrets).is_err());
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,1139 @@
+//! CMP注册模块
+//! 通过CMP（RFC 4210，HTTP传输见RFC 6712）将生成结果CSV中的CSR提交给CA（如EJBCA、Insta Certifier）：
+//! p10cr直接提交PKCS#10 CSR；ir/cr将CSR的Subject、公钥和请求扩展转为CRMF证书模板，并以CSR的私钥签名
+//! 作为私钥持有证明（POP）。消息保护支持共享密钥（PasswordBasedMac）和签名（客户端证书和私钥）两种方式。
+//! CA返回waiting时以pollReq查询，收到证书后发送certConf确认；签发的证书写入在原有列之后增加的certificate列，
+//! CA证书（caPubs和extraCerts）写入chain列，输出新的CSV文件
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::bn::BigNum;
+use openssl::hash::{hash, MessageDigest};
+use openssl::memcmp;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::rand::rand_bytes;
+use openssl::sign::{Signer, Verifier};
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::csr_generator::{message_digest, read_private_key};
+use crate::error::BatchError;
+use crate::output::{derived_csv_path, ResultCsv};
+use crate::signature::{
+    children, der, external_signature_oid, integer, issuer_hash_alg, oid, read_tlv, sequence,
+};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// pollRep中checkAfter的最长等待时间
+const MAX_CHECK_AFTER: Duration = Duration::from_secs(300);
+
+/// PasswordBasedMac的迭代次数；验证响应时接受的最大迭代次数
+const PBM_ITERATIONS: u32 = 1000;
+const MAX_PBM_ITERATIONS: u32 = 100_000;
+
+const OID_PASSWORD_BASED_MAC: &str = "1.2.840.113533.7.66.13";
+const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const OID_HMAC_SHA256: &str = "1.2.840.113549.2.9";
+const OID_EXTENSION_REQUEST: &str = "1.2.840.113549.1.9.14";
+
+/// PKIBody的类型（上下文标签号）
+const BODY_IR: u8 = 0;
+const BODY_IP: u8 = 1;
+const BODY_CR: u8 = 2;
+const BODY_CP: u8 = 3;
+const BODY_P10CR: u8 = 4;
+const BODY_PKICONF: u8 = 19;
+const BODY_ERROR: u8 = 23;
+const BODY_CERT_CONF: u8 = 24;
+const BODY_POLL_REQ: u8 = 25;
+const BODY_POLL_REP: u8 = 26;
+
+/// PKIStatus
+const STATUS_ACCEPTED: u32 = 0;
+const STATUS_GRANTED_WITH_MODS: u32 = 1;
+const STATUS_WAITING: u32 = 3;
+
+/// PKIFailureInfo各位的名称
+const FAIL_INFO: [&str; 27] = [
+    "badAlg",
+    "badMessageCheck",
+    "badRequest",
+    "badTime",
+    "badCertId",
+    "badDataFormat",
+    "wrongAuthority",
+    "incorrectData",
+    "missingTimeStamp",
+    "badPOP",
+    "certRevoked",
+    "certConfirmed",
+    "wrongIntegrity",
+    "badRecipientNonce",
+    "timeNotAvailable",
+    "unacceptedPolicy",
+    "unacceptedExtension",
+    "addInfoNotAvailable",
+    "badSenderNonce",
+    "badCertTemplate",
+    "signerNotTrusted",
+    "transactionIdInUse",
+    "unsupportedVersion",
+    "notAuthorized",
+    "systemUnavail",
+    "systemFailure",
+    "duplicateCertReq",
+];
+
+/// CMP注册参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct CmpParams {
+    /// 待注册的CSV文件路径（批量生成的输出，需包含csr列；ir/cr还需要privateKey列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _cmp 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// CMP服务地址，如EJBCA的 http://ejbca.example.com/ejbca/publicweb/cmp/<别名>
+    pub server_url: String,
+    /// 请求类型: p10cr (提交PKCS#10，默认), cr (证书请求), ir (初始化请求)
+    #[serde(default)]
+    pub request_type: String,
+    /// 消息保护: mac (共享密钥，默认), signature (客户端证书签名)
+    #[serde(default)]
+    pub protection: String,
+    /// 共享密钥的引用值，作为senderKID发送（EJBCA为终端实体用户名或别名配置的引用）
+    #[serde(default)]
+    pub reference: String,
+    /// 共享密钥
+    #[serde(default)]
+    pub shared_secret: String,
+    /// 签名保护使用的客户端证书 (PEM)
+    #[serde(default)]
+    pub client_cert_path: String,
+    /// 签名保护使用的客户端私钥 (PEM)
+    #[serde(default)]
+    pub client_key_path: String,
+    /// 客户端私钥口令
+    #[serde(default)]
+    pub client_key_password: String,
+    /// CA证书 (PEM，可选)，作为消息的接收方名称，并用于验证签名保护的响应
+    #[serde(default)]
+    pub ca_cert_path: String,
+    /// CSV中私钥的加密口令 (ir/cr且私钥已加密时填写)
+    #[serde(default)]
+    pub key_passphrase: String,
+    /// HTTPS服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+    /// CA返回waiting时查询的间隔秒数（pollRep中的checkAfter优先）
+    #[serde(default = "default_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// CA返回waiting时的最大查询次数
+    #[serde(default = "default_max_polls")]
+    pub max_polls: u32,
+}
+
+/// 默认查询间隔
+fn default_poll_interval_seconds() -> u64 {
+    10
+}
+
+/// 默认最大查询次数
+fn default_max_polls() -> u32 {
+    30
+}
+
+/// CMP注册结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct CmpResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 上下文特定的构造类型标签
+fn context(number: u8) -> u8 {
+    0xA0 | number
+}
+
+fn small_integer(value: u32) -> Result<Vec<u8>> {
+    Ok(integer(&BigNum::from_u32(value)?))
+}
+
+/// 读取非负INTEGER的值
+fn read_integer(content: &[u8]) -> u32 {
+    content
+        .iter()
+        .fold(0u32, |value, b| value.saturating_mul(256) | *b as u32)
+}
+
+/// 签名算法标识，RSA为PKCS#1 v1.5，EC为ECDSA，摘要均为SHA-256
+fn signature_algorithm(key: &PKey<Private>) -> Result<Vec<u8>> {
+    let rsa = match key.id() {
+        Id::RSA => true,
+        Id::EC => false,
+        _ => return Err(anyhow!("CMP签名仅支持RSA和EC密钥")),
+    };
+    let mut algorithm = oid(external_signature_oid(rsa, "SHA256"))?;
+    if rsa {
+        algorithm.extend_from_slice(&[0x05, 0x00]);
+    }
+    Ok(der(0x30, &algorithm))
+}
+
+fn sign(key: &PKey<Private>, data: &[u8]) -> Result<Vec<u8>> {
+    let mut signer = Signer::new(MessageDigest::sha256(), key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// BIT STRING（无未用位）
+fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    let mut content = vec![0u8];
+    content.extend_from_slice(bytes);
+    der(0x03, &content)
+}
+
+/// PasswordBasedMac的密钥：对 共享密钥||salt 迭代SHA-256
+fn pbm_key(secret: &[u8], salt: &[u8], iterations: u32) -> Result<Vec<u8>> {
+    let mut key = [secret, salt].concat();
+    for _ in 0..iterations {
+        key = hash(MessageDigest::sha256(), &key)?.to_vec();
+    }
+    Ok(key)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// PasswordBasedMac的算法标识（含PBMParameter）
+fn pbm_algorithm(salt: &[u8], iterations: u32) -> Result<Vec<u8>> {
+    let parameter = sequence(&[
+        &der(0x04, salt),
+        &sequence(&[&oid(OID_SHA256)?]),
+        &small_integer(iterations)?,
+        &sequence(&[&oid(OID_HMAC_SHA256)?]),
+    ]);
+    Ok(sequence(&[&oid(OID_PASSWORD_BASED_MAC)?, &parameter]))
+}
+
+/// 消息保护方式
+enum Protection {
+    Mac { reference: Vec<u8>, secret: Vec<u8> },
+    Signature { cert: X509, key: PKey<Private> },
+}
+
+impl Protection {
+    fn from_params(params: &CmpParams) -> Result<Self> {
+        match params.protection.as_str() {
+            "" | "mac" => {
+                if params.shared_secret.is_empty() {
+                    return Err(BatchError::parameter("shared_secret", "未填写").into());
+                }
+                Ok(Self::Mac {
+                    reference: params.reference.as_bytes().to_vec(),
+                    secret: params.shared_secret.as_bytes().to_vec(),
+                })
+            }
+            "signature" => {
+                let read = |field: &str, path: &str| -> Result<Vec<u8>> {
+                    if path.is_empty() {
+                        return Err(BatchError::parameter(field, "未填写").into());
+                    }
+                    Ok(fs::read(path).map_err(|e| BatchError::io(path, e))?)
+                };
+                let cert = X509::from_pem(&read("client_cert_path", &params.client_cert_path)?)
+                    .map_err(|e| anyhow!("无效的客户端证书: {}", e))?;
+                let key = read_private_key(
+                    &read("client_key_path", &params.client_key_path)?,
+                    &params.client_key_password,
+                )?;
+                if !cert.public_key()?.public_eq(&key) {
+                    return Err(anyhow!("客户端证书与私钥不匹配"));
+                }
+                Ok(Self::Signature { cert, key })
+            }
+            other => Err(BatchError::parameter(
+                "protection",
+                format!("不支持的消息保护方式: {}", other),
+            )
+            .into()),
+        }
+    }
+
+    fn algorithm(&self, salt: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Mac { .. } => pbm_algorithm(salt, PBM_ITERATIONS),
+            Self::Signature { key, .. } => signature_algorithm(key),
+        }
+    }
+
+    fn protect(&self, salt: &[u8], protected_part: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Mac { secret, .. } => {
+                hmac_sha256(&pbm_key(secret, salt, PBM_ITERATIONS)?, protected_part)
+            }
+            Self::Signature { key, .. } => sign(key, protected_part),
+        }
+    }
+}
+
+/// 解析后的CA响应
+struct CmpResponse {
+    body_type: u8,
+    body: Vec<u8>,
+    sender_nonce: Vec<u8>,
+    extra_certs: Vec<X509>,
+}
+
+/// PKIStatusInfo的状态和说明
+fn status_info(content: &[u8]) -> Result<(u32, String)> {
+    let mut status = 0;
+    let mut texts = Vec::new();
+    for (tag, value) in children(content)? {
+        match tag {
+            0x02 => status = read_integer(value),
+            0x30 => {
+                for (_, text) in children(value)? {
+                    texts.push(String::from_utf8_lossy(text).into_owned());
+                }
+            }
+            0x03 if !value.is_empty() => {
+                for (index, name) in FAIL_INFO.iter().enumerate() {
+                    let set = value
+                        .get(1 + index / 8)
+                        .is_some_and(|b| b & (0x80 >> (index % 8)) != 0);
+                    if set {
+                        texts.push(name.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((status, texts.join("; ")))
+}
+
+/// 按签名算法OID找到摘要算法
+fn signature_digest(algorithm_oid: &[u8]) -> Result<MessageDigest> {
+    for hash_alg in ["SHA256", "SHA384", "SHA512", "SHA1"] {
+        for rsa in [true, false] {
+            if oid(external_signature_oid(rsa, hash_alg))? == algorithm_oid {
+                return Ok(message_digest(hash_alg));
+            }
+        }
+    }
+    Err(anyhow!("不支持响应的签名算法"))
+}
+
+struct CmpClient {
+    agent: ureq::Agent,
+    url: String,
+    protection: Protection,
+    /// 接收方名称（CA证书的Subject，未指定时为空名称）
+    recipient: Vec<u8>,
+    ca_cert: Option<X509>,
+    poll_interval: Duration,
+    max_polls: u32,
+}
+
+impl CmpClient {
+    fn connect(params: &CmpParams) -> Result<Self> {
+        if params.server_url.trim().is_empty() {
+            return Err(BatchError::parameter("server_url", "未填写").into());
+        }
+        let protection = Protection::from_params(params)?;
+        let ca_cert = if params.ca_cert_path.is_empty() {
+            None
+        } else {
+            let path = &params.ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            Some(X509::from_pem(&pem).map_err(|e| anyhow!("无效的CA证书: {}", e))?)
+        };
+        let recipient = match &ca_cert {
+            Some(cert) => cert.subject_name().to_der()?,
+            None => sequence(&[]),
+        };
+
+        let mut builder = ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT);
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            let connector = native_tls::TlsConnector::builder()
+                .add_root_certificate(cert)
+                .build()?;
+            builder = builder.tls_connector(Arc::new(connector));
+        }
+        Ok(Self {
+            agent: builder.build(),
+            url: params.server_url.trim().to_string(),
+            protection,
+            recipient,
+            ca_cert,
+            poll_interval: Duration::from_secs(params.poll_interval_seconds),
+            max_polls: params.max_polls,
+        })
+    }
+
+    /// 发送一个PKIMessage并解析响应
+    fn exchange(
+        &self,
+        sender: &[u8],
+        transaction_id: &[u8],
+        recip_nonce: Option<&[u8]>,
+        body: Vec<u8>,
+    ) -> Result<CmpResponse> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 16];
+        rand_bytes(&mut salt)?;
+        rand_bytes(&mut nonce)?;
+        let now = chrono::Utc::now().format("%Y%m%d%H%M%SZ").to_string();
+
+        let sender = match &self.protection {
+            Protection::Signature { cert, .. } => cert.subject_name().to_der()?,
+            Protection::Mac { .. } => sender.to_vec(),
+        };
+        let mut fields = vec![
+            small_integer(2)?,
+            der(context(4), &sender),
+            der(context(4), &self.recipient),
+            der(context(0), &der(0x18, now.as_bytes())),
+            der(context(1), &self.protection.algorithm(&salt)?),
+        ];
+        if let Protection::Mac { reference, .. } = &self.protection {
+            fields.push(der(context(2), &der(0x04, reference)));
+        }
+        fields.push(der(context(4), &der(0x04, transaction_id)));
+        fields.push(der(context(5), &der(0x04, &nonce)));
+        if let Some(recip_nonce) = recip_nonce {
+            fields.push(der(context(6), &der(0x04, recip_nonce)));
+        }
+        let header = der(0x30, &fields.concat());
+        let protection = self
+            .protection
+            .protect(&salt, &sequence(&[&header, &body]))?;
+        let mut message = vec![header, body, der(context(0), &bit_string(&protection))];
+        if let Protection::Signature { cert, .. } = &self.protection {
+            message.push(der(context(1), &sequence(&[&cert.to_der()?])));
+        }
+
+        let response = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/pkixcmp")
+            .send_bytes(&der(0x30, &message.concat()))
+            .map_err(|e| anyhow!("CMP请求失败: {}", e))?;
+        let mut reply = Vec::new();
+        response.into_reader().read_to_end(&mut reply)?;
+        self.parse_response(&reply, transaction_id, &nonce)
+    }
+
+    /// 解析响应，验证transactionID、recipNonce和消息保护
+    fn parse_response(
+        &self,
+        reply: &[u8],
+        transaction_id: &[u8],
+        nonce: &[u8],
+    ) -> Result<CmpResponse> {
+        let (_, message, _) = read_tlv(reply)?;
+        let (_, header, rest) = read_tlv(message)?;
+        let header_raw = &message[..message.len() - rest.len()];
+        let (body_tag, body, tail) = read_tlv(rest)?;
+        let body_raw = &rest[..rest.len() - tail.len()];
+        let body_type = body_tag & 0x1f;
+
+        let mut protection_algorithm = None;
+        let mut response_tid = None;
+        let mut sender_nonce = Vec::new();
+        let mut recip_nonce = None;
+        // 前三项为pvno、sender和recipient，之后为带标签的可选字段
+        for (tag, value) in children(header)?.into_iter().skip(3) {
+            let inner = || read_tlv(value).map(|(_, content, _)| content);
+            match tag {
+                t if t == context(1) => protection_algorithm = Some(inner()?),
+                t if t == context(4) => response_tid = Some(inner()?),
+                t if t == context(5) => sender_nonce = inner()?.to_vec(),
+                t if t == context(6) => recip_nonce = Some(inner()?),
+                _ => {}
+            }
+        }
+
+        let mut protection = None;
+        let mut extra_certs = Vec::new();
+        for (tag, value) in children(tail)? {
+            if tag == context(0) {
+                protection = Some(read_tlv(value)?.1.get(1..).unwrap_or_default());
+            } else if tag == context(1) {
+                for (_, cert) in children(read_tlv(value)?.1)? {
+                    extra_certs.push(X509::from_der(&der(0x30, cert))?);
+                }
+            }
+        }
+
+        let response = CmpResponse {
+            body_type,
+            body: body.to_vec(),
+            sender_nonce,
+            extra_certs,
+        };
+        // 认证失败时CA可能返回未受保护的错误消息
+        let (Some(algorithm), Some(protection)) = (protection_algorithm, protection) else {
+            if body_type == BODY_ERROR {
+                return Ok(response);
+            }
+            return Err(anyhow!("CA响应未受保护"));
+        };
+        if response_tid.is_some_and(|tid| tid != transaction_id) {
+            return Err(anyhow!("响应的transactionID与请求不一致"));
+        }
+        if recip_nonce != Some(nonce) {
+            return Err(anyhow!("响应的recipNonce与请求的senderNonce不一致"));
+        }
+        self.verify(algorithm, protection, &sequence(&[header_raw, body_raw]))?;
+        Ok(response)
+    }
+
+    /// 验证响应的保护：共享密钥按响应中的PBM参数重新计算MAC，签名在指定CA证书时验证
+    fn verify(&self, algorithm: &[u8], protection: &[u8], protected_part: &[u8]) -> Result<()> {
+        let algorithm = children(algorithm)?;
+        let (_, algorithm_oid) = algorithm.first().ok_or_else(|| anyhow!("DER格式错误"))?;
+        let algorithm_oid = der(0x06, algorithm_oid);
+        if algorithm_oid == oid(OID_PASSWORD_BASED_MAC)? {
+            let Protection::Mac { secret, .. } = &self.protection else {
+                return Err(anyhow!("CA响应使用了共享密钥保护"));
+            };
+            let parameter = algorithm.get(1).ok_or_else(|| anyhow!("缺少PBM参数"))?;
+            let parameter = children(parameter.1)?;
+            let [(_, salt), (_, owf), (_, iterations), (_, mac)] = parameter.as_slice() else {
+                return Err(anyhow!("PBM参数格式错误"));
+            };
+            let iterations = read_integer(iterations);
+            if read_tlv(owf)?.1 != &oid(OID_SHA256)?[2..]
+                || read_tlv(mac)?.1 != &oid(OID_HMAC_SHA256)?[2..]
+                || iterations > MAX_PBM_ITERATIONS
+            {
+                return Err(anyhow!("不支持响应的PBM参数"));
+            }
+            let expected = hmac_sha256(&pbm_key(secret, salt, iterations)?, protected_part)?;
+            if expected.len() != protection.len() || !memcmp::eq(&expected, protection) {
+                return Err(anyhow!("CA响应的MAC验证失败，请检查共享密钥"));
+            }
+            return Ok(());
+        }
+
+        // 签名保护：未指定CA证书时无法验证签名者，只检查签发的证书与CSR匹配
+        let Some(ca_cert) = &self.ca_cert else {
+            return Ok(());
+        };
+        let digest = signature_digest(&algorithm_oid)?;
+        let key = ca_cert.public_key()?;
+        let mut verifier = Verifier::new(digest, &key)?;
+        verifier.update(protected_part)?;
+        if !verifier.verify(protection)? {
+            return Err(anyhow!("CA响应的签名验证失败"));
+        }
+        Ok(())
+    }
+
+    /// 提交请求并等待签发，发送certConf后返回签发的证书和CA证书（PEM）
+    fn enroll(&self, csr: &X509Req, body: Vec<u8>) -> Result<(String, String)> {
+        let sender = csr.subject_name().to_der()?;
+        let mut transaction_id = [0u8; 16];
+        rand_bytes(&mut transaction_id)?;
+
+        let mut response = self.exchange(&sender, &transaction_id, None, body)?;
+        let poll_req = || {
+            let content = sequence(&[&sequence(&[&small_integer(0)?])]);
+            Ok::<_, anyhow::Error>(der(context(BODY_POLL_REQ), &content))
+        };
+        let mut polls = 0;
+        let (cert, ca_pubs) = loop {
+            let wait = match response.body_type {
+                BODY_IP | BODY_CP => {
+                    let (status, text, issued) = cert_response(&response.body)?;
+                    match status {
+                        STATUS_ACCEPTED | STATUS_GRANTED_WITH_MODS => {
+                            break issued.ok_or_else(|| anyhow!("响应中没有证书"))?;
+                        }
+                        STATUS_WAITING => self.poll_interval,
+                        _ => return Err(anyhow!("CA拒绝签发: {}", text)),
+                    }
+                }
+                BODY_POLL_REP => {
+                    let (_, entry, _) = read_tlv(read_tlv(&response.body)?.1)?;
+                    let entry = children(entry)?;
+                    let check_after = entry
+                        .get(1)
+                        .map(|(_, value)| read_integer(value))
+                        .unwrap_or_default();
+                    Duration::from_secs(check_after as u64).min(MAX_CHECK_AFTER)
+                }
+                BODY_ERROR => {
+                    let (_, status, _) = read_tlv(read_tlv(&response.body)?.1)?;
+                    let (_, text) = status_info(status)?;
+                    return Err(anyhow!("CA返回错误: {}", text));
+                }
+                other => return Err(anyhow!("意外的CMP响应类型: {}", other)),
+            };
+            if polls >= self.max_polls {
+                return Err(anyhow!("查询{}次后仍未签发（waiting）", polls));
+            }
+            std::thread::sleep(wait);
+            polls += 1;
+            response = self.exchange(
+                &sender,
+                &transaction_id,
+                Some(&response.sender_nonce),
+                poll_req()?,
+            )?;
+        };
+
+        if !cert.public_key()?.public_eq(&*csr.public_key()?) {
+            return Err(anyhow!("签发的证书与CSR的公钥不一致"));
+        }
+        // certConf：证书哈希使用证书签名算法的摘要
+        let digest = message_digest(issuer_hash_alg(&cert)?);
+        let cert_hash = hash(digest, &cert.to_der()?)?;
+        let cert_status = sequence(&[&der(0x04, &cert_hash), &small_integer(0)?]);
+        let confirm = der(context(BODY_CERT_CONF), &sequence(&[&cert_status]));
+        let confirmed = self.exchange(
+            &sender,
+            &transaction_id,
+            Some(&response.sender_nonce),
+            confirm,
+        )?;
+        if confirmed.body_type != BODY_PKICONF {
+            return Err(anyhow!("CA未确认certConf"));
+        }
+
+        let leaf = cert.to_der()?;
+        let mut seen = vec![leaf];
+        let mut chain = String::new();
+        for ca in ca_pubs.iter().chain(&response.extra_certs) {
+            let encoded = ca.to_der()?;
+            if !seen.contains(&encoded) {
+                chain.push_str(&String::from_utf8(ca.to_pem()?)?);
+                seen.push(encoded);
+            }
+        }
+        Ok((String::from_utf8(cert.to_pem()?)?, chain))
+    }
+}
+
+/// 解析CertRepMessage中的第一个CertResponse，返回状态、说明和签发的证书及caPubs
+#[allow(clippy::type_complexity)]
+fn cert_response(body: &[u8]) -> Result<(u32, String, Option<(X509, Vec<X509>)>)> {
+    let content = read_tlv(body)?.1;
+    let mut ca_pubs = Vec::new();
+    let mut responses = None;
+    for (tag, value) in children(content)? {
+        if tag == context(1) {
+            for (_, cert) in children(read_tlv(value)?.1)? {
+                ca_pubs.push(X509::from_der(&der(0x30, cert))?);
+            }
+        } else if tag == 0x30 {
+            responses = Some(value);
+        }
+    }
+    let responses = responses.ok_or_else(|| anyhow!("响应中没有CertResponse"))?;
+    let (_, first, _) = read_tlv(responses)?;
+    let fields = children(first)?;
+    let (_, status) = fields.get(1).ok_or_else(|| anyhow!("缺少PKIStatusInfo"))?;
+    let (status, text) = status_info(status)?;
+    let cert = match fields.get(2) {
+        Some((0x30, key_pair)) => {
+            let (tag, cert, _) = read_tlv(key_pair)?;
+            if tag != context(0) {
+                return Err(anyhow!("不支持加密的证书"));
+            }
+            Some(X509::from_der(cert)?)
+        }
+        _ => None,
+    };
+    Ok((status, text, cert.map(|cert| (cert, ca_pubs))))
+}
+
+/// CSR中extensionRequest属性的Extensions内容
+fn requested_extensions(csr: &X509Req) -> Result<Option<Vec<u8>>> {
+    let encoded = csr.to_der()?;
+    let request = read_tlv(&encoded)?.1;
+    let (_, info, _) = read_tlv(request)?;
+    let extension_request = oid(OID_EXTENSION_REQUEST)?;
+    for (tag, attributes) in children(info)? {
+        if tag != context(0) {
+            continue;
+        }
+        for (_, attribute) in children(attributes)? {
+            let (_, attr_oid, values) = read_tlv(attribute)?;
+            if der(0x06, attr_oid) == extension_request {
+                let (_, values, _) = read_tlv(values)?;
+                let (_, extensions, _) = read_tlv(values)?;
+                return Ok(Some(extensions.to_vec()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// ir/cr的CertReqMessages：CRMF证书模板取自CSR，以私钥签名CertRequest作为POP
+fn cert_req_messages(csr: &X509Req, key: &PKey<Private>) -> Result<Vec<u8>> {
+    let public_key = csr.public_key()?.public_key_to_der()?;
+    let mut template = vec![
+        der(context(5), &csr.subject_name().to_der()?),
+        der(context(6), read_tlv(&public_key)?.1),
+    ];
+    if let Some(extensions) = requested_extensions(csr)? {
+        template.push(der(context(9), &extensions));
+    }
+    let cert_request = sequence(&[&small_integer(0)?, &der(0x30, &template.concat())]);
+    let popo = [
+        signature_algorithm(key)?,
+        bit_string(&sign(key, &cert_request)?),
+    ]
+    .concat();
+    let message = sequence(&[&cert_request, &der(context(1), &popo)]);
+    Ok(sequence(&[&message]))
+}
+
+/// 注册一行的CSR，返回证书和证书链；pop_key_col为ir/cr签名POP所用的私钥列
+fn enroll_record(
+    client: &CmpClient,
+    params: &CmpParams,
+    record: &StringRecord,
+    csr_col: usize,
+    pop_key_col: Option<usize>,
+    body_type: u8,
+    number: usize,
+) -> Result<(String, String)> {
+    let csr = parse_csr(record.get(csr_col).unwrap_or(""))
+        .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+    let body = match pop_key_col {
+        Some(key_col) => {
+            let pem = record.get(key_col).unwrap_or("");
+            let key = read_private_key(pem.as_bytes(), &params.key_passphrase)
+                .map_err(|e| BatchError::record(number, e))?;
+            let messages =
+                cert_req_messages(&csr, &key).map_err(|e| BatchError::record(number, e))?;
+            der(context(body_type), &messages)
+        }
+        None => der(context(BODY_P10CR), &csr.to_der()?),
+    };
+    let enrolled = client
+        .enroll(&csr, body)
+        .map_err(|e| BatchError::record(number, format!("注册失败: {}", e)))?;
+    Ok(enrolled)
+}
+
+/// 通过CMP为CSV中的每个CSR签发证书
+pub fn cmp_enroll(params: CmpParams) -> Result<CmpResult, BatchError> {
+    enroll_records(&params).map_err(BatchError::from)
+}
+
+fn enroll_records(params: &CmpParams) -> Result<CmpResult> {
+    let body_type = match params.request_type.as_str() {
+        "" | "p10cr" => BODY_P10CR,
+        "cr" => BODY_CR,
+        "ir" => BODY_IR,
+        other => {
+            return Err(BatchError::parameter(
+                "request_type",
+                format!("不支持的请求类型: {}", other),
+            )
+            .into())
+        }
+    };
+
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let csr_col = column("csr").ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let key_col = column("privateKey");
+    if body_type != BODY_P10CR && key_col.is_none() {
+        return Err(BatchError::header("缺少privateKey列，ir/cr须使用CSR的私钥签名POP").into());
+    }
+    // 重复注册时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "cmp")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = CmpClient::connect(params)?;
+    // ir/cr使用该行的私钥签名POP
+    let pop_key_col = key_col.filter(|_| body_type != BODY_P10CR);
+    let mut total = 0;
+    for (index, record) in records.iter().enumerate() {
+        let number = index + 1;
+        // 中途注册失败时，已签发的证书和其余行照常写入输出文件
+        let enrolled = enroll_record(
+            &client,
+            params,
+            record,
+            csr_col,
+            pop_key_col,
+            body_type,
+            number,
+        );
+        let (cert_pem, chain_pem) = match enrolled {
+            Ok(enrolled) => enrolled,
+            Err(error) => return Err(output.abort(error, &records[index..], total)),
+        };
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    Ok(CmpResult {
+        success: true,
+        message: format!("CMP签发 {} 个证书", total),
+        total,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subject::from_x509_name;
+    use crate::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::rsa::Rsa;
+    use openssl::x509::{X509Builder, X509Name, X509NameBuilder};
+    use std::collections::HashMap;
+    use tiny_http::{Response, Server};
+
+    /// 测试CA的共享密钥
+    const SECRET: &[u8] = b"secret";
+
+    /// 测试CA的响应：以共享密钥保护，密钥错误时返回未受保护的错误消息
+    fn reply(
+        request_header: &[(u8, &[u8])],
+        body: Vec<u8>,
+        protected: bool,
+        ca_name: &[u8],
+    ) -> Vec<u8> {
+        let field = |number: u8| {
+            request_header
+                .iter()
+                .find(|(tag, _)| *tag == context(number))
+                .map(|(_, value)| read_tlv(value).unwrap().1.to_vec())
+                .unwrap()
+        };
+        let salt = [7u8; 16];
+        let mut fields = vec![
+            small_integer(2).unwrap(),
+            der(context(4), ca_name),
+            der(context(4), &sequence(&[])),
+        ];
+        if protected {
+            fields.push(der(context(1), &pbm_algorithm(&salt, 10).unwrap()));
+        }
+        fields.push(der(context(4), &der(0x04, &field(4))));
+        fields.push(der(context(5), &der(0x04, &[9u8; 16])));
+        fields.push(der(context(6), &der(0x04, &field(5))));
+        let header = der(0x30, &fields.concat());
+        let mut message = vec![header.clone(), body.clone()];
+        if protected {
+            let key = pbm_key(SECRET, &salt, 10).unwrap();
+            let mac = hmac_sha256(&key, &sequence(&[&header, &body])).unwrap();
+            message.push(der(context(0), &bit_string(&mac)));
+        }
+        der(0x30, &message.concat())
+    }
+
+    /// PKIStatusInfo
+    fn status(value: u32, text: &str) -> Vec<u8> {
+        let mut fields = vec![small_integer(value).unwrap()];
+        if !text.is_empty() {
+            fields.push(sequence(&[&der(0x0C, text.as_bytes())]));
+        }
+        der(0x30, &fields.concat())
+    }
+
+    /// 启动测试CMP服务器：cr验证POP后返回waiting，pollReq时签发，certConf时确认
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/ejbca/publicweb/cmp/test",
+            server.server_addr().to_ip().unwrap()
+        );
+        let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test CMP CA").unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+        let ca_name = name.to_der().unwrap();
+
+        std::thread::spawn(move || {
+            // transactionID对应的待签发请求（Subject和公钥）和已签发证书
+            let mut pending: HashMap<Vec<u8>, (Vec<u8>, Vec<u8>)> = HashMap::new();
+            let mut issued: HashMap<Vec<u8>, X509> = HashMap::new();
+            for mut request in server.incoming_requests() {
+                let mut raw = Vec::new();
+                request.as_reader().read_to_end(&mut raw).unwrap();
+                let (_, message, _) = read_tlv(&raw).unwrap();
+                let (_, header, rest) = read_tlv(message).unwrap();
+                let header_raw = &message[..message.len() - rest.len()];
+                let (body_tag, body, tail) = read_tlv(rest).unwrap();
+                let body_raw = &rest[..rest.len() - tail.len()];
+                let fields = children(header).unwrap();
+                // 前三项为pvno、sender和recipient
+                let fields = fields.into_iter().skip(3).collect::<Vec<_>>();
+                let transaction_id = fields
+                    .iter()
+                    .find(|(tag, _)| *tag == context(4))
+                    .map(|(_, value)| read_tlv(value).unwrap().1.to_vec())
+                    .unwrap();
+
+                // 按请求的PBM参数验证MAC
+                let (_, algorithm) = fields.iter().find(|(tag, _)| *tag == context(1)).unwrap();
+                let algorithm = children(read_tlv(algorithm).unwrap().1).unwrap();
+                let parameter = children(algorithm[1].1).unwrap();
+                let key = pbm_key(SECRET, parameter[0].1, read_integer(parameter[2].1)).unwrap();
+                let expected = hmac_sha256(&key, &sequence(&[header_raw, body_raw])).unwrap();
+                let (_, protection) = children(tail)
+                    .unwrap()
+                    .into_iter()
+                    .find(|(tag, _)| *tag == context(0))
+                    .unwrap();
+                let protection = &read_tlv(protection).unwrap().1[1..];
+                if protection != expected.as_slice() {
+                    let error = sequence(&[&status(2, "wrong secret")]);
+                    let body = der(context(BODY_ERROR), &error);
+                    let _ =
+                        request.respond(Response::from_data(reply(&fields, body, false, &ca_name)));
+                    continue;
+                }
+
+                let body = match body_tag & 0x1f {
+                    BODY_CR => {
+                        // CertReqMsg：以模板中的公钥验证POP签名
+                        let (_, message, _) = read_tlv(read_tlv(body).unwrap().1).unwrap();
+                        let (_, _, popo) = read_tlv(message).unwrap();
+                        let cert_request = &message[..message.len() - popo.len()];
+                        let request_fields = children(read_tlv(cert_request).unwrap().1).unwrap();
+                        let template = children(request_fields[1].1).unwrap();
+                        let subject = template[0].1.to_vec();
+                        let public_key = der(0x30, template[1].1);
+                        let pop = children(read_tlv(popo).unwrap().1).unwrap();
+                        let key = PKey::public_key_from_der(&public_key).unwrap();
+                        let mut verifier = Verifier::new(MessageDigest::sha256(), &key).unwrap();
+                        verifier.update(cert_request).unwrap();
+                        assert!(verifier.verify(&pop[1].1[1..]).unwrap());
+                        pending.insert(transaction_id, (subject, public_key));
+                        let response = sequence(&[&small_integer(0).unwrap(), &status(3, "")]);
+                        let content = sequence(&[&sequence(&[&response])]);
+                        der(context(BODY_CP), &content)
+                    }
+                    BODY_POLL_REQ => {
+                        let (subject, public_key) = pending.remove(&transaction_id).unwrap();
+                        let mut builder = X509Builder::new().unwrap();
+                        builder.set_version(2).unwrap();
+                        let subject = X509Name::from_der(&subject).unwrap();
+                        builder.set_subject_name(&subject).unwrap();
+                        builder.set_issuer_name(&name).unwrap();
+                        let key = PKey::public_key_from_der(&public_key).unwrap();
+                        builder.set_pubkey(&key).unwrap();
+                        builder
+                            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                            .unwrap();
+                        builder
+                            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                            .unwrap();
+                        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                        let cert = builder.build();
+                        let key_pair = sequence(&[&der(context(0), &cert.to_der().unwrap())]);
+                        let response =
+                            sequence(&[&small_integer(0).unwrap(), &status(0, ""), &key_pair]);
+                        let ca_pubs = der(context(1), &sequence(&[&ca_cert.to_der().unwrap()]));
+                        issued.insert(transaction_id, cert);
+                        let content = sequence(&[&ca_pubs, &sequence(&[&response])]);
+                        der(context(BODY_CP), &content)
+                    }
+                    BODY_CERT_CONF => {
+                        let cert = issued.remove(&transaction_id).unwrap();
+                        let (_, cert_status, _) = read_tlv(read_tlv(body).unwrap().1).unwrap();
+                        let cert_hash = children(cert_status).unwrap()[0].1.to_vec();
+                        let expected = hash(MessageDigest::sha256(), &cert.to_der().unwrap());
+                        assert_eq!(cert_hash, expected.unwrap().to_vec());
+                        der(context(BODY_PKICONF), &[0x05, 0x00])
+                    }
+                    other => panic!("unexpected body {}", other),
+                };
+                let _ = request.respond(Response::from_data(reply(&fields, body, true, &ca_name)));
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_cmp_enroll() {
+        let dir = std::env::temp_dir().join("csr_batch_cmp_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "device1,device2".to_string(),
+            subject_template: "CN=[{CN}];O=[Example]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let params = CmpParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: start_mock(),
+            request_type: "cr".to_string(),
+            reference: "device".to_string(),
+            shared_secret: "secret".to_string(),
+            poll_interval_seconds: 0,
+            max_polls: 1,
+            ..Default::default()
+        };
+        let result = cmp_enroll(params).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.output_path.ends_with("out_cmp.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (csr_col, key_col, cert_col, chain_col) = (
+            column("csr"),
+            column("privateKey"),
+            column("certificate"),
+            column("chain"),
+        );
+        for record in reader.records() {
+            let record = record.unwrap();
+            let req = X509Req::from_pem(record[csr_col].as_bytes()).unwrap();
+            let subject = from_x509_name(req.subject_name()).unwrap();
+            assert_eq!(subject.len(), 2);
+            assert_eq!(subject[1].attr, "O");
+            assert_eq!(subject[1].value, "Example");
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(cert.issuer_name_hash(), chain.subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let server_url = start_mock();
+        let params = |overwrite: bool| CmpParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            shared_secret: "wrong".to_string(),
+            overwrite,
+            ..Default::default()
+        };
+        let error = cmp_enroll(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 共享密钥错误时CA返回未受保护的错误消息
+        let error = cmp_enroll(params(true)).unwrap_err();
+        assert!(error.to_string().contains("wrong secret"), "{}", error);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_response_mac_mismatch() {
+        let (transaction_id, nonce) = ([1u8; 16], [2u8; 16]);
+        let (tid_field, nonce_field) = (der(0x04, &transaction_id), der(0x04, &nonce));
+        let request_header = [(context(4), &tid_field[..]), (context(5), &nonce_field[..])];
+        let body = der(context(BODY_PKICONF), &[0x05, 0x00]);
+        let ca_name = sequence(&[]);
+        let client = |secret: &str| {
+            CmpClient::connect(&CmpParams {
+                server_url: "http://127.0.0.1:1/cmp".to_string(),
+                reference: "device".to_string(),
+                shared_secret: secret.to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        let message = reply(&request_header, body.clone(), true, &ca_name);
+        let response = client("secret")
+            .parse_response(&message, &transaction_id, &nonce)
+            .unwrap();
+        assert_eq!(response.body_type, BODY_PKICONF);
+
+        // 共享密钥不一致或响应被篡改时MAC验证失败
+        let error = client("wrong")
+            .parse_response(&message, &transaction_id, &nonce)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("MAC验证失败"), "{}", error);
+        let mut tampered = message.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        let error = client("secret")
+            .parse_response(&tampered, &transaction_id, &nonce)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("MAC验证失败"), "{}", error);
+
+        // 错误消息之外的响应未受保护时拒绝
+        let message = reply(&request_header, body, false, &ca_name);
+        let error = client("secret")
+            .parse_response(&message, &transaction_id, &nonce)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("未受保护"), "{}", error);
+    }
+}
@@ -0,0 +1,331 @@
+//! Windows CNG密钥存储模块
+//! 通过 certreq 在CNG密钥存储（Microsoft Software Key Storage Provider）中生成不可导出的密钥对并创建CSR，
+//! 私钥不离开密钥存储，CSV中仅包含CSR和密钥容器名称
+//!
+//! certreq 同时将请求登记在证书存储的"证书注册请求"中，证书签发后使用 `certreq -accept` 即可安装到原有密钥上
+
+use anyhow::{anyhow, Result};
+use openssl::asn1::Asn1Object;
+use openssl::rand::rand_bytes;
+use openssl::x509::X509Req;
+use std::fmt::Write as _;
+use std::fs;
+use std::process::Command;
+
+use crate::csr_generator::{BatchRequest, KeyType};
+use crate::error::BatchError;
+use crate::extensions::{split_usages, ExtensionOptions};
+use crate::output::sanitize_file_name;
+use crate::san::{parse_sans, SubjectAltName};
+use crate::signature::SignatureScheme;
+use crate::subject::{oid_text, DnAttribute};
+
+/// CNG密钥存储提供程序
+const KEY_STORAGE_PROVIDER: &str = "Microsoft Software Key Storage Provider";
+
+/// 密钥类型对应的certreq密钥算法和长度，CNG不支持的类型返回None
+fn key_algorithm(key_type: KeyType) -> Option<(&'static str, u32)> {
+    match key_type {
+        KeyType::Rsa2048 => Some(("RSA", 2048)),
+        KeyType::Rsa3072 => Some(("RSA", 3072)),
+        KeyType::Rsa4096 => Some(("RSA", 4096)),
+        KeyType::EcP256 => Some(("ECDSA_P256", 256)),
+        KeyType::EcP384 => Some(("ECDSA_P384", 384)),
+        KeyType::EcP521 => Some(("ECDSA_P521", 521)),
+        _ => None,
+    }
+}
+
+/// 校验生成参数是否可用于CNG密钥存储
+pub(crate) fn check_request(
+    request: &BatchRequest,
+    key_type: KeyType,
+    signature_scheme: SignatureScheme,
+) -> Result<()> {
+    let unsupported = if key_algorithm(key_type).is_none() {
+        (
+            "key_type",
+            format!("CNG不支持密钥类型 {}", key_type.display_name()),
+        )
+    } else if !request.pq_key_type.is_empty() {
+        ("pq_key_type", "CNG密钥存储不支持混合模式".to_string())
+    } else if signature_scheme != SignatureScheme::Pkcs1 {
+        (
+            "signature_scheme",
+            "CNG密钥存储仅支持PKCS#1 v1.5签名".to_string(),
+        )
+    } else if !request.challenge_password.is_empty() {
+        (
+            "challenge_password",
+            "CNG密钥存储不支持challengePassword属性".to_string(),
+        )
+    } else if !request.extensions.is_empty() {
+        ("extensions", "CNG密钥存储不支持自定义扩展".to_string())
+    } else if !request.dn_string_types.is_empty() {
+        (
+            "dn_string_types",
+            "CNG密钥存储不支持指定DN字符串类型".to_string(),
+        )
+    } else {
+        return Ok(());
+    };
+    Err(BatchError::parameter(unsupported.0, unsupported.1).into())
+}
+
+/// 密钥用途名称对应的certreq标志
+fn key_usage_flag(usage: &str) -> Result<&'static str> {
+    Ok(match usage.to_ascii_lowercase().as_str() {
+        "digitalsignature" => "CERT_DIGITAL_SIGNATURE_KEY_USAGE",
+        "nonrepudiation" | "contentcommitment" => "CERT_NON_REPUDIATION_KEY_USAGE",
+        "keyencipherment" => "CERT_KEY_ENCIPHERMENT_KEY_USAGE",
+        "dataencipherment" => "CERT_DATA_ENCIPHERMENT_KEY_USAGE",
+        "keyagreement" => "CERT_KEY_AGREEMENT_KEY_USAGE",
+        "keycertsign" => "CERT_KEY_CERT_SIGN_KEY_USAGE",
+        "crlsign" => "CERT_CRL_SIGN_KEY_USAGE",
+        "encipheronly" => "CERT_ENCIPHER_ONLY_KEY_USAGE",
+        "decipheronly" => "CERT_DECIPHER_ONLY_KEY_USAGE",
+        _ => {
+            let message = format!("不支持的密钥用途: {}", usage);
+            return Err(BatchError::parameter("key_usage", message).into());
+        }
+    })
+}
+
+/// 扩展密钥用途名称对应的OID，点分OID原样返回
+fn extended_key_usage_oid(usage: &str) -> Result<&str> {
+    Ok(match usage.to_ascii_lowercase().as_str() {
+        "serverauth" => "1.3.6.1.5.5.7.3.1",
+        "clientauth" => "1.3.6.1.5.5.7.3.2",
+        "codesigning" => "1.3.6.1.5.5.7.3.3",
+        "emailprotection" => "1.3.6.1.5.5.7.3.4",
+        "timestamping" => "1.3.6.1.5.5.7.3.8",
+        "ocspsigning" => "1.3.6.1.5.5.7.3.9",
+        "smartcardlogon" => "1.3.6.1.4.1.311.20.2.2",
+        _ if usage.contains('.') && usage.split('.').all(|n| n.parse::<u32>().is_ok()) => usage,
+        _ => {
+            let message = format!("不支持的扩展密钥用途: {}", usage);
+            return Err(BatchError::parameter("extended_key_usage", message).into());
+        }
+    })
+}
+
+/// 按CertStrToName格式转换Subject，属性值统一加双引号，多值RDN的属性以 `+` 连接；
+/// CertStrToName不识别的属性写作 `OID.点分OID`
+fn certreq_subject(subject: &[DnAttribute]) -> Result<String> {
+    let mut text = String::new();
+    for (i, attr) in subject.iter().enumerate() {
+        if i > 0 {
+            text.push_str(if attr.multi_valued { " + " } else { ", " });
+        }
+        let name = match attr.attr.as_str() {
+            "emailAddress" => "E".to_string(),
+            "ST" => "S".to_string(),
+            "street" => "STREET".to_string(),
+            "title" => "T".to_string(),
+            "GN" => "G".to_string(),
+            "initials" => "I".to_string(),
+            "serialNumber" => "SERIALNUMBER".to_string(),
+            "postalCode" => "PostalCode".to_string(),
+            "description" => "Description".to_string(),
+            name @ ("CN" | "O" | "OU" | "C" | "L" | "SN" | "DC" | "dnQualifier") => {
+                name.to_string()
+            }
+            name if name.contains('.') => format!("OID.{}", name),
+            name => format!("OID.{}", oid_text(&*Asn1Object::from_str(name)?)),
+        };
+        write!(text, "{}=\"{}\"", name, attr.value.replace('"', "\"\""))?;
+    }
+    Ok(text)
+}
+
+/// 备用名称的certreq文本格式，如 `dns=a.example.com`
+fn certreq_san(name: &SubjectAltName) -> Result<String> {
+    let (kind, value) = match name {
+        SubjectAltName::Dns(dns) => ("dns", dns.clone()),
+        SubjectAltName::Ip(ip) => ("ipaddress", ip.to_string()),
+        SubjectAltName::Email(email) => ("email", email.clone()),
+        SubjectAltName::Uri(uri) => ("url", uri.clone()),
+        SubjectAltName::Upn(upn) => ("upn", upn.clone()),
+    };
+    if value.contains('&') {
+        let message = format!("CNG密钥存储的备用名称不能包含&: {}", value);
+        return Err(BatchError::parameter("sans", message).into());
+    }
+    Ok(format!("{}={}", kind, value))
+}
+
+/// 生成certreq的INF请求文件内容
+fn certreq_inf(
+    subject: &[DnAttribute],
+    key_type: KeyType,
+    sign_hash_alg: &str,
+    key_container: &str,
+    machine: bool,
+    extensions: &ExtensionOptions,
+) -> Result<String> {
+    let (algorithm, length) = key_algorithm(key_type)
+        .ok_or_else(|| anyhow!("CNG不支持密钥类型 {}", key_type.display_name()))?;
+    // INF字符串中的双引号需写两次
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+
+    let mut inf = String::from("[Version]\r\nSignature = \"$Windows NT$\"\r\n\r\n[NewRequest]\r\n");
+    writeln!(inf, "Subject = {}\r", quote(&certreq_subject(subject)?))?;
+    writeln!(inf, "KeyAlgorithm = {}\r", algorithm)?;
+    writeln!(inf, "KeyLength = {}\r", length)?;
+    writeln!(inf, "HashAlgorithm = {}\r", sign_hash_alg)?;
+    writeln!(inf, "ProviderName = {}\r", quote(KEY_STORAGE_PROVIDER))?;
+    writeln!(inf, "KeyContainer = {}\r", quote(key_container))?;
+    writeln!(
+        inf,
+        "MachineKeySet = {}\r",
+        if machine { "TRUE" } else { "FALSE" }
+    )?;
+    inf.push_str("Exportable = FALSE\r\nRequestType = PKCS10\r\nSMIME = FALSE\r\n");
+
+    // 密钥用途按RFC 5280建议标记为关键扩展
+    let key_usage_flags = split_usages(extensions.key_usage)
+        .map(key_usage_flag)
+        .collect::<Result<Vec<_>>>()?;
+    if !key_usage_flags.is_empty() {
+        writeln!(inf, "KeyUsage = {}\r", quote(&key_usage_flags.join(" | ")))?;
+    }
+    let eku_oids = split_usages(extensions.extended_key_usage)
+        .map(extended_key_usage_oid)
+        .collect::<Result<Vec<_>>>()?;
+    if !eku_oids.is_empty() {
+        inf.push_str("\r\n[EnhancedKeyUsageExtension]\r\n");
+        for oid in eku_oids {
+            writeln!(inf, "OID = {}\r", oid)?;
+        }
+    }
+    let sans = parse_sans(extensions.sans)?;
+    if !key_usage_flags.is_empty() || !sans.is_empty() {
+        inf.push_str("\r\n[Extensions]\r\n");
+    }
+    if !sans.is_empty() {
+        // 备用名称以certreq的文本格式逐项续写，各项以&结尾
+        inf.push_str("2.5.29.17 = \"{text}\"\r\n");
+        for name in &sans {
+            writeln!(
+                inf,
+                "_continue_ = {}\r",
+                quote(&format!("{}&", certreq_san(name)?))
+            )?;
+        }
+    }
+    if !key_usage_flags.is_empty() {
+        inf.push_str("Critical = 2.5.29.15\r\n");
+    }
+    // certreq以CertificateTemplate请求属性写入模板，模板OID后的版本号不适用
+    let template = extensions.certificate_template.trim();
+    if !template.is_empty() {
+        let template = template.split(':').next().unwrap_or(template);
+        inf.push_str("\r\n[RequestAttributes]\r\n");
+        writeln!(inf, "CertificateTemplate = {}\r", quote(template))?;
+    }
+    Ok(inf)
+}
+
+/// 在CNG密钥存储中生成密钥对和CSR，返回CSR PEM和密钥容器名称
+pub(crate) fn generate_csr(
+    subject: &[DnAttribute],
+    cn: &str,
+    key_type: KeyType,
+    sign_hash_alg: &str,
+    machine: bool,
+    extensions: &ExtensionOptions,
+) -> Result<(String, String)> {
+    // 密钥容器名称：通用名称加随机后缀，避免与已有容器重名
+    let mut suffix = [0u8; 8];
+    rand_bytes(&mut suffix)?;
+    let suffix: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+    let key_container = format!("csr-batch-{}-{}", sanitize_file_name(cn), suffix);
+
+    let inf = certreq_inf(
+        subject,
+        key_type,
+        sign_hash_alg,
+        &key_container,
+        machine,
+        extensions,
+    )?;
+    let inf_path = std::env::temp_dir().join(format!("{}.inf", key_container));
+    let req_path = inf_path.with_extension("req");
+    fs::write(&inf_path, inf).map_err(|e| BatchError::io(&inf_path, e))?;
+
+    let output = Command::new("certreq")
+        .arg("-new")
+        .arg("-q")
+        .arg("-f")
+        .arg(&inf_path)
+        .arg(&req_path)
+        .output();
+    fs::remove_file(&inf_path).ok();
+    let output = output.map_err(|e| BatchError::io("certreq", e))?;
+    if !output.status.success() {
+        fs::remove_file(&req_path).ok();
+        return Err(anyhow!(
+            "certreq 执行失败: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        ));
+    }
+
+    // certreq输出 NEW CERTIFICATE REQUEST 格式，转换为标准PEM
+    let csr = fs::read(&req_path).map_err(|e| BatchError::io(&req_path, e))?;
+    fs::remove_file(&req_path).ok();
+    let req = X509Req::from_pem(&csr)?;
+    Ok((String::from_utf8(req.to_pem()?)?, key_container))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subject::parse_subject;
+
+    #[test]
+    fn test_certreq_inf() {
+        let subject = parse_subject(
+            "CN=YDL0001,OU=Dev+OU=Ops,O=Example\\, Inc,E=a@example.com,serialNumber=SN1,UID=u1",
+        )
+        .unwrap();
+        let inf = certreq_inf(
+            &subject,
+            KeyType::EcP256,
+            "SHA256",
+            "csr-batch-YDL0001",
+            true,
+            &ExtensionOptions {
+                key_usage: "digitalSignature",
+                sans: "DNS:ydl0001.example.com,UPN:YDL0001@corp.example.com",
+                certificate_template: "WebServer",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(inf.contains(
+            "Subject = \"CN=\"\"YDL0001\"\", OU=\"\"Dev\"\" + OU=\"\"Ops\"\", O=\"\"Example, Inc\"\", E=\"\"a@example.com\"\", SERIALNUMBER=\"\"SN1\"\", OID.0.9.2342.19200300.100.1.1=\"\"u1\"\"\"\r\n"
+        ));
+        assert!(inf.contains("KeyAlgorithm = ECDSA_P256\r\n"));
+        assert!(inf.contains("MachineKeySet = TRUE\r\n"));
+        assert!(inf.contains("Exportable = FALSE\r\n"));
+        assert!(inf.contains("KeyUsage = \"CERT_DIGITAL_SIGNATURE_KEY_USAGE\"\r\n"));
+        assert!(inf.contains(concat!(
+            "[Extensions]\r\n2.5.29.17 = \"{text}\"\r\n",
+            "_continue_ = \"dns=ydl0001.example.com&\"\r\n",
+            "_continue_ = \"upn=YDL0001@corp.example.com&\"\r\n",
+            "Critical = 2.5.29.15\r\n"
+        )));
+        assert!(inf.contains("[RequestAttributes]\r\nCertificateTemplate = \"WebServer\"\r\n"));
+
+        let extensions = ExtensionOptions::default();
+        assert!(certreq_inf(
+            &subject,
+            KeyType::Ed25519,
+            "SHA256",
+            "k",
+            false,
+            &extensions
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,479 @@
+//! 证书吊销列表模块
+//! 在本地任务历史数据库的revocations表中记录吊销的证书序列号和原因，
+//! 使用内置CA的证书和私钥签发v2 CRL（含CRL编号和颁发机构密钥标识符），写入PEM或DER文件
+//!
+//! 序列号在certificates表（证书到期跟踪）中能查到唯一的签发者时，只列入该签发者的CRL；
+//! 查不到时列入所有CA的CRL
+
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::error::ErrorStack;
+use openssl::x509::{X509Crl, X509Extension, X509Revoked};
+use openssl_sys as ffi;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::ca::{CertificateAuthority, SignParams};
+use crate::error::BatchError;
+use crate::expiry;
+use crate::extensions::der_extension;
+use crate::job_db::now;
+use crate::signature::{der, integer, sequence};
+use crate::subject::{format_subject, from_x509_name};
+
+/// 建表语句，已存在时不重复创建；crls表记录每次签发的CRL，number即CRL编号
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS revocations (
+    serial TEXT PRIMARY KEY,
+    issuer TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    revoked_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS crls (
+    number INTEGER PRIMARY KEY AUTOINCREMENT,
+    issuer TEXT NOT NULL,
+    this_update TEXT NOT NULL,
+    next_update TEXT NOT NULL,
+    revoked INTEGER NOT NULL
+);
+";
+
+/// 吊销原因及其CRLReason编码（RFC 5280 5.3.1）
+const REASONS: &[(&str, u8)] = &[
+    ("unspecified", 0),
+    ("keyCompromise", 1),
+    ("cACompromise", 2),
+    ("affiliationChanged", 3),
+    ("superseded", 4),
+    ("cessationOfOperation", 5),
+    ("certificateHold", 6),
+    ("privilegeWithdrawn", 9),
+    ("aACompromise", 10),
+];
+
+/// 吊销记录
+#[derive(Debug, Clone, Serialize)]
+pub struct Revocation {
+    /// 证书序列号（大写十六进制，不含前导0）
+    pub serial: String,
+    /// 签发者主题，未知时为空
+    pub issuer: String,
+    /// 吊销原因，如 keyCompromise
+    pub reason: String,
+    /// 吊销时间（RFC 3339格式）
+    pub revoked_at: String,
+}
+
+/// CRL签发参数结构体
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CrlParams {
+    /// CA证书路径 (PEM格式，或扩展名为 .p12/.pfx 的PKCS#12文件)
+    pub ca_cert_path: String,
+    /// CA私钥路径 (PEM格式，CA证书为PKCS#12文件时不需要)
+    pub ca_key_path: String,
+    /// CA私钥口令或PKCS#12密码 (可选)
+    pub ca_password: String,
+    /// 签名哈希算法: SHA256 (默认), SHA384, SHA512, MatchIssuer (与CA证书一致)
+    pub sign_hash_alg: String,
+    /// 距下次更新的天数 (可选，默认为7)
+    pub next_update_days: u32,
+    /// 输出格式: PEM (默认), DER
+    pub format: String,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// CRL签发结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct CrlResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 列入CRL的吊销证书数
+    pub total: usize,
+    /// CRL编号
+    pub crl_number: i64,
+    /// 下次更新时间（RFC 3339格式）
+    pub next_update: String,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 打开数据库并确保certificates、revocations和crls表存在
+pub(crate) fn open(path: &Path) -> Result<Connection> {
+    let conn = expiry::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// 规范化序列号：允许冒号、空格分隔和0x前缀，输出大写十六进制
+pub(crate) fn normalize_serial(serial: &str) -> Result<String> {
+    let hex: String = serial
+        .trim()
+        .trim_start_matches("0x")
+        .trim_start_matches("0X")
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .collect();
+    let invalid = || BatchError::parameter("serial", format!("无效的序列号: {}", serial));
+    if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid().into());
+    }
+    let serial = BigNum::from_hex_str(&hex).map_err(|_| invalid())?;
+    Ok(serial.to_hex_str()?.to_string())
+}
+
+/// 吊销原因的规范名称和编码，空字符串为unspecified
+pub(crate) fn parse_reason(reason: &str) -> Result<(&'static str, u8)> {
+    let reason = if reason.is_empty() {
+        "unspecified"
+    } else {
+        reason
+    };
+    REASONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(reason))
+        .copied()
+        .ok_or_else(|| {
+            let names: Vec<&str> = REASONS.iter().map(|(name, _)| *name).collect();
+            BatchError::parameter(
+                "reason",
+                format!("不支持的吊销原因: {}，可选 {}", reason, names.join(", ")),
+            )
+            .into()
+        })
+}
+
+/// 在数据库中吊销指定序列号的证书，reason为RFC 5280的吊销原因（如 keyCompromise，空为unspecified）；
+/// 已吊销的证书更新吊销原因，吊销时间不变
+pub fn revoke(db_path: &str, serial: &str, reason: &str) -> Result<Revocation, BatchError> {
+    revoke_serial(Path::new(db_path), serial, reason).map_err(BatchError::from)
+}
+
+fn revoke_serial(path: &Path, serial: &str, reason: &str) -> Result<Revocation> {
+    let serial = normalize_serial(serial)?;
+    let (reason, _) = parse_reason(reason)?;
+    let conn = open(path)?;
+    let issuers: Vec<String> = conn
+        .prepare("SELECT DISTINCT issuer FROM certificates WHERE serial = ?1")?
+        .query_map(params![serial], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let issuer = match issuers.as_slice() {
+        [issuer] => issuer.clone(),
+        _ => String::new(),
+    };
+    conn.execute(
+        "INSERT INTO revocations (serial, issuer, reason, revoked_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(serial) DO UPDATE SET reason = ?3",
+        params![serial, issuer, reason, now()],
+    )?;
+    let revocation = conn.query_row(
+        "SELECT serial, issuer, reason, revoked_at FROM revocations WHERE serial = ?1",
+        params![serial],
+        revocation_row,
+    )?;
+    Ok(revocation)
+}
+
+pub(crate) fn revocation_row(row: &rusqlite::Row) -> rusqlite::Result<Revocation> {
+    Ok(Revocation {
+        serial: row.get(0)?,
+        issuer: row.get(1)?,
+        reason: row.get(2)?,
+        revoked_at: row.get(3)?,
+    })
+}
+
+/// 按吊销时间列出数据库中的吊销记录，issuer非空时只列出该签发者（及签发者未知）的记录；数据库不存在时返回空
+pub fn list_revocations(db_path: &str, issuer: &str) -> Result<Vec<Revocation>, BatchError> {
+    revocations(Path::new(db_path), issuer).map_err(BatchError::from)
+}
+
+fn revocations(path: &Path, issuer: &str) -> Result<Vec<Revocation>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let conn = open(path)?;
+    let list = conn
+        .prepare(
+            "SELECT serial, issuer, reason, revoked_at FROM revocations
+             WHERE ?1 = '' OR issuer = '' OR issuer = ?1 ORDER BY revoked_at, serial",
+        )?
+        .query_map(params![issuer], revocation_row)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(list)
+}
+
+/// 吊销记录的时间转换为证书时间
+pub(crate) fn asn1_time(time: &str) -> Result<Asn1Time> {
+    let time =
+        DateTime::parse_from_rfc3339(time).map_err(|e| anyhow!("无效的时间 {}: {}", time, e))?;
+    Ok(Asn1Time::from_unix(time.timestamp())?)
+}
+
+fn check(ret: i32, action: &str) -> Result<()> {
+    if ret <= 0 {
+        return Err(anyhow!("{}失败: {}", action, ErrorStack::get()));
+    }
+    Ok(())
+}
+
+/// 构建CRL吊销条目：序列号、吊销时间和吊销原因（unspecified时按RFC 5280省略）
+fn revoked_entry(revocation: &Revocation) -> Result<X509Revoked> {
+    let serial = BigNum::from_hex_str(&revocation.serial)?.to_asn1_integer()?;
+    let revoked_at = asn1_time(&revocation.revoked_at)?;
+    let (_, code) = parse_reason(&revocation.reason)?;
+    // SAFETY: X509_REVOKED_new返回的指针非空时由X509Revoked负责释放，各set函数复制传入的值
+    unsafe {
+        let ptr = ffi::X509_REVOKED_new();
+        if ptr.is_null() {
+            return Err(ErrorStack::get().into());
+        }
+        let entry = X509Revoked::from_ptr(ptr);
+        check(
+            ffi::X509_REVOKED_set_serialNumber(ptr, serial.as_ptr()),
+            "设置吊销序列号",
+        )?;
+        check(
+            ffi::X509_REVOKED_set_revocationDate(ptr, revoked_at.as_ptr()),
+            "设置吊销时间",
+        )?;
+        if code != 0 {
+            let reason = der_extension("2.5.29.21", false, &der(0x0a, &[code]))?;
+            check(
+                ffi::X509_REVOKED_add_ext(ptr, reason.as_ptr(), -1),
+                "添加吊销原因",
+            )?;
+        }
+        Ok(entry)
+    }
+}
+
+/// 向CRL添加扩展，OpenSSL复制扩展内容
+fn add_crl_extension(crl: &X509Crl, extension: &X509Extension) -> Result<()> {
+    // SAFETY: 两个指针均来自有效的OpenSSL对象
+    check(
+        unsafe { ffi::X509_CRL_add_ext(crl.as_ptr(), extension.as_ptr(), -1) },
+        "添加CRL扩展",
+    )
+}
+
+/// 使用内置CA签发包含数据库中所有吊销记录的CRL，CRL编号在每次签发后递增
+pub fn generate_crl(db_path: &str, params: CrlParams) -> Result<CrlResult, BatchError> {
+    issue_crl(Path::new(db_path), &params).map_err(BatchError::from)
+}
+
+fn issue_crl(db_path: &Path, params: &CrlParams) -> Result<CrlResult> {
+    if params.output_path.is_empty() {
+        return Err(BatchError::parameter("output_path", "未指定CRL输出路径").into());
+    }
+    let pem = match params.format.to_uppercase().as_str() {
+        "" | "PEM" => true,
+        "DER" => false,
+        other => {
+            return Err(
+                BatchError::parameter("format", format!("不支持的输出格式: {}", other)).into(),
+            )
+        }
+    };
+    let ca = CertificateAuthority::load(&SignParams {
+        ca_cert_path: params.ca_cert_path.clone(),
+        ca_key_path: params.ca_key_path.clone(),
+        ca_password: params.ca_password.clone(),
+        sign_hash_alg: params.sign_hash_alg.clone(),
+        ..Default::default()
+    })?;
+    let issuer = format_subject(&from_x509_name(ca.cert.subject_name())?);
+    let revoked = revocations(db_path, &issuer)?;
+
+    let days = if params.next_update_days == 0 {
+        7
+    } else {
+        params.next_update_days
+    };
+    let this_update = Asn1Time::days_from_now(0)?;
+    let next_update = Asn1Time::days_from_now(days)?;
+    let next_update_text = expiry::rfc3339(expiry::utc_time(&next_update)?);
+
+    // 先占用CRL编号，签发失败时回滚
+    let mut conn = open(db_path)?;
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO crls (issuer, this_update, next_update, revoked) VALUES (?1, ?2, ?3, ?4)",
+        params![issuer, now(), next_update_text, revoked.len()],
+    )?;
+    let crl_number = tx.last_insert_rowid();
+
+    // SAFETY: X509_CRL_new返回的指针非空时由X509Crl负责释放，add0_revoked接管吊销条目的所有权，
+    // 其余set函数复制传入的值
+    let crl = unsafe {
+        let ptr = ffi::X509_CRL_new();
+        if ptr.is_null() {
+            return Err(ErrorStack::get().into());
+        }
+        let crl = X509Crl::from_ptr(ptr);
+        check(ffi::X509_CRL_set_version(ptr, 1), "设置CRL版本")?;
+        check(
+            ffi::X509_CRL_set_issuer_name(ptr, ca.cert.subject_name().as_ptr()),
+            "设置CRL签发者",
+        )?;
+        check(
+            ffi::X509_CRL_set1_lastUpdate(ptr, this_update.as_ptr()),
+            "设置CRL本次更新时间",
+        )?;
+        check(
+            ffi::X509_CRL_set1_nextUpdate(ptr, next_update.as_ptr()),
+            "设置CRL下次更新时间",
+        )?;
+        for revocation in &revoked {
+            let entry = revoked_entry(revocation)?;
+            check(
+                ffi::X509_CRL_add0_revoked(ptr, entry.as_ptr()),
+                "添加吊销条目",
+            )?;
+            std::mem::forget(entry);
+        }
+        check(ffi::X509_CRL_sort(ptr), "排序吊销条目")?;
+        crl
+    };
+
+    let number = BigNum::from_dec_str(&crl_number.to_string())?;
+    add_crl_extension(&crl, &der_extension("2.5.29.20", false, &integer(&number))?)?;
+    // CA证书不含主体密钥标识符时省略颁发机构密钥标识符
+    if let Some(key_id) = ca.cert.subject_key_id() {
+        let authority_key_id = sequence(&[&der(0x80, key_id.as_slice())]);
+        add_crl_extension(&crl, &der_extension("2.5.29.35", false, &authority_key_id)?)?;
+    }
+    // SAFETY: 三个指针均来自有效的OpenSSL对象
+    check(
+        unsafe { ffi::X509_CRL_sign(crl.as_ptr(), ca.key.as_ptr(), ca.digest.as_ptr()) },
+        "CRL签名",
+    )?;
+
+    let output = if pem { crl.to_pem()? } else { crl.to_der()? };
+    let output_path = Path::new(&params.output_path);
+    if let Some(dir) = output_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        fs::create_dir_all(dir).map_err(|e| BatchError::io(dir, e))?;
+    }
+    fs::write(output_path, output).map_err(|e| BatchError::io(output_path, e))?;
+    tx.commit()?;
+
+    Ok(CrlResult {
+        success: true,
+        message: format!(
+            "已签发第{}号CRL，包含{}个吊销的证书，下次更新时间 {}",
+            crl_number,
+            revoked.len(),
+            next_update_text
+        ),
+        total: revoked.len(),
+        crl_number,
+        next_update: next_update_text,
+        output_path: params.output_path.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::extension::SubjectKeyIdentifier;
+    use openssl::x509::{CrlStatus, X509Builder, X509NameBuilder};
+
+    #[test]
+    fn test_generate_crl() {
+        let dir = std::env::temp_dir().join("csr_batch_crl_test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test CA").unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(365).unwrap())
+            .unwrap();
+        let key_id = SubjectKeyIdentifier::new()
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(key_id).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+        let ca_cert_path = dir.join("ca.pem");
+        let ca_key_path = dir.join("ca.key");
+        std::fs::write(&ca_cert_path, ca_cert.to_pem().unwrap()).unwrap();
+        std::fs::write(&ca_key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+
+        let db = dir.join("job_history.db").to_string_lossy().to_string();
+        assert!(revoke(&db, "01:A2", "unknown").is_err());
+        assert!(revoke(&db, "xyz", "").is_err());
+        assert_eq!(revoke(&db, "01:a2", "").unwrap().serial, "01A2");
+        revoke(&db, "0x3f", "certificateHold").unwrap();
+        // 再次吊销更新原因
+        let revocation = revoke(&db, "3F", "keycompromise").unwrap();
+        assert_eq!(revocation.reason, "keyCompromise");
+        assert_eq!(list_revocations(&db, "").unwrap().len(), 2);
+
+        let params = |format: &str, output: &str| CrlParams {
+            ca_cert_path: ca_cert_path.to_string_lossy().to_string(),
+            ca_key_path: ca_key_path.to_string_lossy().to_string(),
+            format: format.to_string(),
+            output_path: dir.join(output).to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let result = generate_crl(&db, params("", "ca.crl.pem")).unwrap();
+        assert_eq!((result.total, result.crl_number), (2, 1));
+        let crl = X509Crl::from_pem(&std::fs::read(&result.output_path).unwrap()).unwrap();
+        assert!(crl.verify(&key).unwrap());
+        assert_eq!(crl.issuer_name().to_der().unwrap(), name.to_der().unwrap());
+        assert!(crl.next_update().is_some());
+        let serial = BigNum::from_hex_str("3F")
+            .unwrap()
+            .to_asn1_integer()
+            .unwrap();
+        match crl.get_by_serial(&serial) {
+            CrlStatus::Revoked(entry) => {
+                let (_, reason) = entry
+                    .extension::<openssl::x509::ReasonCode>()
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(reason.get_i64().unwrap(), 1);
+            }
+            _ => panic!("序列号3F应在CRL中"),
+        }
+        let serial = BigNum::from_hex_str("3E")
+            .unwrap()
+            .to_asn1_integer()
+            .unwrap();
+        assert!(matches!(crl.get_by_serial(&serial), CrlStatus::NotRevoked));
+
+        // CRL编号递增，DER格式可直接解析
+        let result = generate_crl(&db, params("DER", "ca.crl")).unwrap();
+        assert_eq!(result.crl_number, 2);
+        let crl = X509Crl::from_der(&std::fs::read(&result.output_path).unwrap()).unwrap();
+        assert_eq!(crl.get_revoked().unwrap().len(), 2);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
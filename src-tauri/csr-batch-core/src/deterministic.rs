@@ -0,0 +1,149 @@
+//! 确定性密钥模块（仅用于测试）
+//! 由主种子按 HKDF-SHA256(种子, CN) 派生每个通用名称的密钥对，相同的种子和CN总是得到相同的密钥，
+//! 用于生成可在多次运行间保持一致的测试夹具。派生的密钥强度取决于种子，切勿用于生产证书
+//!
+//! - RSA: 由派生字节确定起点，向上搜索素数p、q（公钥指数65537）
+//! - EC/SM2: 派生字节模 (n-1) 加1作为私钥标量
+//! - ED25519/ED448: 派生字节直接作为私钥
+
+use anyhow::{anyhow, Result};
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+use openssl::md::Md;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::rsa::Rsa;
+
+use crate::csr_generator::KeyType;
+use crate::error::BatchError;
+
+/// RSA公钥指数
+const RSA_EXPONENT: u32 = 65537;
+
+/// HKDF-SHA256(种子, CN) 派生指定长度的字节
+fn hkdf(seed: &[u8], cn: &str, len: usize) -> Result<Vec<u8>> {
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha256())?;
+    ctx.set_hkdf_key(seed)?;
+    ctx.add_hkdf_info(cn.as_bytes())?;
+    let mut out = vec![0u8; len];
+    ctx.derive(Some(&mut out))?;
+    Ok(out)
+}
+
+/// 由通用名称确定性地派生密钥对
+pub(crate) fn derive_key_pair(seed: &[u8], cn: &str, key_type: KeyType) -> Result<PKey<Private>> {
+    match key_type {
+        KeyType::Rsa2048 | KeyType::Rsa3072 | KeyType::Rsa4096 => {
+            derive_rsa(seed, cn, key_type.rsa_bits())
+        }
+        KeyType::Ed25519 => Ok(PKey::private_key_from_raw_bytes(
+            &hkdf(seed, cn, 32)?,
+            Id::ED25519,
+        )?),
+        KeyType::Ed448 => Ok(PKey::private_key_from_raw_bytes(
+            &hkdf(seed, cn, 57)?,
+            Id::ED448,
+        )?),
+        _ => {
+            let curve = key_type.ec_curve().ok_or_else(|| {
+                let message = format!("确定性模式不支持密钥类型 {}", key_type.display_name());
+                BatchError::parameter("key_type", message)
+            })?;
+            derive_ec(seed, cn, &EcGroup::from_curve_name(curve)?)
+        }
+    }
+}
+
+/// 派生EC私钥：取比阶多8字节的派生值模 (n-1) 加1，偏差可忽略
+fn derive_ec(seed: &[u8], cn: &str, group: &EcGroup) -> Result<PKey<Private>> {
+    let mut ctx = BigNumContext::new()?;
+    let mut order = BigNum::new()?;
+    group.order(&mut order, &mut ctx)?;
+    let mut modulus = order.to_owned()?;
+    modulus.sub_word(1)?;
+
+    let derived = BigNum::from_slice(&hkdf(seed, cn, order.num_bytes() as usize + 8)?)?;
+    let mut private = BigNum::new()?;
+    private.nnmod(&derived, &modulus, &mut ctx)?;
+    private.add_word(1)?;
+
+    let mut public = EcPoint::new(group)?;
+    public.mul_generator(group, &private, &ctx)?;
+    let ec_key = EcKey::from_private_components(group, &private, &public)?;
+    Ok(PKey::from_ec_key(ec_key)?)
+}
+
+/// 从派生字节开始向上搜索素数，最高两位置1以保证模数长度，且 p-1 与公钥指数互素
+fn next_prime(bytes: &[u8], bits: i32, ctx: &mut BigNumContext) -> Result<BigNum> {
+    let mut candidate = BigNum::from_slice(bytes)?;
+    candidate.set_bit(bits - 1)?;
+    candidate.set_bit(bits - 2)?;
+    candidate.set_bit(0)?;
+    while candidate.mod_word(RSA_EXPONENT)? == 1 || !candidate.is_prime_fasttest(64, ctx, true)? {
+        candidate.add_word(2)?;
+    }
+    Ok(candidate)
+}
+
+/// 派生RSA私钥
+fn derive_rsa(seed: &[u8], cn: &str, bits: usize) -> Result<PKey<Private>> {
+    let mut ctx = BigNumContext::new()?;
+    let half = bits / 2;
+    let bytes = hkdf(seed, cn, half / 8 * 2)?;
+    let (p_bytes, q_bytes) = bytes.split_at(half / 8);
+    let p = next_prime(p_bytes, half as i32, &mut ctx)?;
+    let q = next_prime(q_bytes, half as i32, &mut ctx)?;
+    if p == q {
+        return Err(anyhow!("派生的RSA素数相同，请更换种子"));
+    }
+
+    let minus_one = |n: &BigNumRef| -> Result<BigNum> {
+        let mut n = n.to_owned()?;
+        n.sub_word(1)?;
+        Ok(n)
+    };
+    let (p1, q1) = (minus_one(&p)?, minus_one(&q)?);
+    let e = BigNum::from_u32(RSA_EXPONENT)?;
+    let mut n = BigNum::new()?;
+    n.checked_mul(&p, &q, &mut ctx)?;
+    let mut phi = BigNum::new()?;
+    phi.checked_mul(&p1, &q1, &mut ctx)?;
+    let mut d = BigNum::new()?;
+    d.mod_inverse(&e, &phi, &mut ctx)?;
+    let mut dmp1 = BigNum::new()?;
+    dmp1.nnmod(&d, &p1, &mut ctx)?;
+    let mut dmq1 = BigNum::new()?;
+    dmq1.nnmod(&d, &q1, &mut ctx)?;
+    let mut iqmp = BigNum::new()?;
+    iqmp.mod_inverse(&q, &p, &mut ctx)?;
+
+    let rsa = Rsa::from_private_components(n, e, d, p, q, dmp1, dmq1, iqmp)?;
+    rsa.check_key()?;
+    Ok(PKey::from_rsa(rsa)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key_pair_is_stable() {
+        for key_type in [
+            KeyType::Rsa2048,
+            KeyType::EcP256,
+            KeyType::Sm2,
+            KeyType::Ed25519,
+        ] {
+            let key = derive_key_pair(b"fixture-seed", "YDL0001", key_type).unwrap();
+            let again = derive_key_pair(b"fixture-seed", "YDL0001", key_type).unwrap();
+            let other = derive_key_pair(b"fixture-seed", "YDL0002", key_type).unwrap();
+            let der = |k: &PKey<Private>| k.private_key_to_der().unwrap();
+            assert_eq!(der(&key), der(&again));
+            assert_ne!(der(&key), der(&other));
+        }
+        let rsa = derive_key_pair(b"fixture-seed", "YDL0001", KeyType::Rsa2048).unwrap();
+        assert_eq!(rsa.bits(), 2048);
+    }
+}
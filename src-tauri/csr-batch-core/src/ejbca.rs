@@ -0,0 +1,542 @@
+//! EJBCA REST注册模块
+//! 将生成结果CSV中的CSR逐个提交到EJBCA REST API的 `v1/certificate/pkcs10enroll` 接口，按批次指定的
+//! 证书配置（certificate profile）、终端实体配置（end entity profile）和CA签发，输出在原有列之后
+//! 增加certificate列（证书）和chain列（证书链）的CSV文件
+//!
+//! EJBCA REST API通常要求TLS客户端证书认证（经反向代理认证时可不设置）。服务器返回429（超出速率限制）
+//! 或5xx、连接失败时，按 Retry-After 或指数退避重试；也可设置请求间隔主动限速
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::base64;
+use openssl::nid::Nid;
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::est::{client_identity, retry_after};
+use crate::output::{derived_csv_path, ResultCsv};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 未返回 Retry-After 时首次重试的等待时间，之后每次加倍
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// EJBCA REST注册参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct EjbcaParams {
+    /// 待注册的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _ejbca 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// EJBCA REST API地址，如 https://ejbca.example.com/ejbca/ejbca-rest-api
+    pub server_url: String,
+    /// 证书配置名称
+    pub certificate_profile_name: String,
+    /// 终端实体配置名称
+    pub end_entity_profile_name: String,
+    /// 签发CA名称
+    pub certificate_authority_name: String,
+    /// 终端实体用户名，{CN} 替换为CSR的通用名称 (默认 {CN})
+    #[serde(default)]
+    pub username: String,
+    /// 终端实体的注册码（密码）
+    #[serde(default)]
+    pub enrollment_code: String,
+    /// 终端实体的电子邮件 (可选)
+    #[serde(default)]
+    pub email: String,
+    /// EJBCA服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+    /// TLS客户端证书 (PEM，或包含证书和私钥的 .p12/.pfx 文件)
+    #[serde(default)]
+    pub client_cert_path: String,
+    /// TLS客户端私钥 (PEM，客户端证书为PKCS#12文件时无需填写)
+    #[serde(default)]
+    pub client_key_path: String,
+    /// 客户端私钥口令或PKCS#12密码
+    #[serde(default)]
+    pub client_key_password: String,
+    /// 两次请求之间的最小间隔毫秒数 (0表示不限速)
+    #[serde(default)]
+    pub request_interval_ms: u64,
+    /// 超出速率限制或暂时性失败的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+/// 默认最大重试次数
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// EJBCA REST注册结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct EjbcaResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 重试的请求次数
+    pub retries: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// pkcs10enroll的请求体
+#[derive(Serialize)]
+struct EnrollRequest<'a> {
+    certificate_request: String,
+    certificate_profile_name: &'a str,
+    end_entity_profile_name: &'a str,
+    certificate_authority_name: &'a str,
+    username: String,
+    password: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    email: &'a str,
+    include_chain: bool,
+}
+
+/// pkcs10enroll的响应，证书为base64编码的DER
+#[derive(Deserialize)]
+struct EnrollResponse {
+    certificate: String,
+    #[serde(default)]
+    certificate_chain: Vec<String>,
+}
+
+/// EJBCA的错误响应
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error_message: String,
+}
+
+/// 一次注册请求的结果
+enum Attempt {
+    Issued(String),
+    /// 暂时性失败，附带服务器要求的等待时间
+    Retry(String, Option<Duration>),
+    Failed(String),
+}
+
+/// 解析base64编码的证书（EJBCA按response_format返回DER，部分版本为PEM）
+fn decode_certificate(encoded: &str) -> Result<X509> {
+    let compact: String = encoded.split_whitespace().collect();
+    let bytes = base64::decode_block(&compact).map_err(|_| anyhow!("证书不是base64编码"))?;
+    X509::from_der(&bytes)
+        .or_else(|_| X509::from_pem(&bytes))
+        .map_err(|e| anyhow!("无效的证书: {}", e))
+}
+
+/// 终端实体用户名：{CN} 替换为CSR的通用名称
+fn username(template: &str, csr: &X509Req) -> Result<String> {
+    let template = if template.trim().is_empty() {
+        "{CN}"
+    } else {
+        template.trim()
+    };
+    if !template.contains("{CN}") {
+        return Ok(template.to_string());
+    }
+    let cn = csr
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().as_utf8().ok())
+        .map(|cn| cn.to_string())
+        .ok_or_else(|| anyhow!("CSR中没有通用名称，无法生成用户名"))?;
+    Ok(template.replace("{CN}", &cn))
+}
+
+struct EjbcaClient<'a> {
+    agent: ureq::Agent,
+    enroll_url: String,
+    params: &'a EjbcaParams,
+    /// 上一次请求的时间，用于请求间隔限速
+    last_request: Option<Instant>,
+}
+
+impl<'a> EjbcaClient<'a> {
+    fn connect(params: &'a EjbcaParams) -> Result<Self> {
+        for (field, value) in [
+            ("server_url", &params.server_url),
+            ("certificate_profile_name", &params.certificate_profile_name),
+            ("end_entity_profile_name", &params.end_entity_profile_name),
+            (
+                "certificate_authority_name",
+                &params.certificate_authority_name,
+            ),
+        ] {
+            if value.trim().is_empty() {
+                return Err(BatchError::parameter(field, "未填写").into());
+            }
+        }
+        let mut tls = native_tls::TlsConnector::builder();
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            tls.add_root_certificate(cert);
+        }
+        if !params.client_cert_path.is_empty() {
+            tls.identity(client_identity(
+                &params.client_cert_path,
+                &params.client_key_path,
+                &params.client_key_password,
+            )?);
+        }
+        let agent = ureq::AgentBuilder::new()
+            .timeout(HTTP_TIMEOUT)
+            .tls_connector(Arc::new(tls.build()?))
+            .build();
+        Ok(Self {
+            agent,
+            enroll_url: format!(
+                "{}/v1/certificate/pkcs10enroll",
+                params.server_url.trim().trim_end_matches('/')
+            ),
+            params,
+            last_request: None,
+        })
+    }
+
+    fn attempt(&mut self, body: &str) -> Attempt {
+        let interval = Duration::from_millis(self.params.request_interval_ms);
+        if let Some(elapsed) = self.last_request.map(|last| last.elapsed()) {
+            if elapsed < interval {
+                std::thread::sleep(interval - elapsed);
+            }
+        }
+        self.last_request = Some(Instant::now());
+
+        let result = self
+            .agent
+            .post(&self.enroll_url)
+            .set("Content-Type", "application/json")
+            .set("Accept", "application/json")
+            .send_string(body);
+        match result {
+            Ok(response) => match response.into_string() {
+                Ok(body) => Attempt::Issued(body),
+                Err(e) => Attempt::Retry(format!("读取响应失败: {}", e), None),
+            },
+            Err(ureq::Error::Status(code, response)) => {
+                let delay = retry_after(&response);
+                let text = response.into_string().unwrap_or_default();
+                let text = serde_json::from_str::<ErrorResponse>(&text)
+                    .map(|error| error.error_message)
+                    .unwrap_or(text);
+                let message = format!("EJBCA返回 {}: {}", code, text.trim());
+                if code >= 500 || code == 429 {
+                    Attempt::Retry(message, delay)
+                } else {
+                    Attempt::Failed(message)
+                }
+            }
+            Err(ureq::Error::Transport(e)) => Attempt::Retry(format!("连接失败: {}", e), None),
+        }
+    }
+
+    /// 提交CSR，超出速率限制或暂时性失败时重试；返回证书、证书链和重试次数
+    fn enroll(&mut self, csr: &X509Req) -> Result<(String, String, usize)> {
+        let request = EnrollRequest {
+            certificate_request: String::from_utf8(csr.to_pem()?)?,
+            certificate_profile_name: self.params.certificate_profile_name.trim(),
+            end_entity_profile_name: self.params.end_entity_profile_name.trim(),
+            certificate_authority_name: self.params.certificate_authority_name.trim(),
+            username: username(&self.params.username, csr)?,
+            password: &self.params.enrollment_code,
+            email: self.params.email.trim(),
+            include_chain: true,
+        };
+        let body = serde_json::to_string(&request)?;
+        let mut delay = RETRY_DELAY;
+        let mut retry = 0;
+        let response = loop {
+            let (message, wait) = match self.attempt(&body) {
+                Attempt::Issued(response) => break response,
+                Attempt::Failed(message) => return Err(anyhow!(message)),
+                Attempt::Retry(message, wait) => (message, wait),
+            };
+            if retry >= self.params.max_retries as usize {
+                return Err(anyhow!("{}（已重试{}次）", message, retry));
+            }
+            std::thread::sleep(wait.unwrap_or(delay));
+            delay *= 2;
+            retry += 1;
+        };
+
+        let response: EnrollResponse =
+            serde_json::from_str(&response).map_err(|e| anyhow!("无法解析EJBCA响应: {}", e))?;
+        let cert = decode_certificate(&response.certificate)?;
+        if !cert.public_key()?.public_eq(&*csr.public_key()?) {
+            return Err(anyhow!("签发的证书与CSR的公钥不一致"));
+        }
+        let leaf = cert.to_der()?;
+        let mut chain = String::new();
+        for encoded in &response.certificate_chain {
+            let ca = decode_certificate(encoded)?;
+            // 部分版本的证书链包含签发的证书本身
+            if ca.to_der()? != leaf {
+                chain.push_str(&String::from_utf8(ca.to_pem()?)?);
+            }
+        }
+        Ok((String::from_utf8(cert.to_pem()?)?, chain, retry))
+    }
+}
+
+/// 通过EJBCA REST API为CSV中的每个CSR签发证书
+pub fn ejbca_enroll(params: EjbcaParams) -> Result<EjbcaResult, BatchError> {
+    enroll_records(&params).map_err(BatchError::from)
+}
+
+/// 注册一行的CSR，返回证书、证书链和重试次数
+fn enroll_record(
+    client: &mut EjbcaClient,
+    record: &StringRecord,
+    csr_col: usize,
+    number: usize,
+) -> Result<(String, String, usize)> {
+    let csr = parse_csr(record.get(csr_col).unwrap_or(""))
+        .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+    let enrolled = client
+        .enroll(&csr)
+        .map_err(|e| BatchError::record(number, format!("注册失败: {}", e)))?;
+    Ok(enrolled)
+}
+
+fn enroll_records(params: &EjbcaParams) -> Result<EjbcaResult> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let csr_col = headers
+        .iter()
+        .position(|h| h == "csr")
+        .ok_or_else(|| BatchError::header("缺少csr列"))?;
+    // 重复注册时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "ejbca")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut client = EjbcaClient::connect(params)?;
+    let mut total = 0;
+    let mut retries = 0;
+    for (index, record) in records.iter().enumerate() {
+        // 中途注册失败时，已签发的证书和其余行照常写入输出文件
+        let (cert_pem, chain_pem, retried) =
+            match enroll_record(&mut client, record, csr_col, index + 1) {
+                Ok(enrolled) => enrolled,
+                Err(error) => return Err(output.abort(error, &records[index..], total)),
+            };
+        retries += retried;
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    let mut message = format!("EJBCA签发 {} 个证书", total);
+    if retries > 0 {
+        message.push_str(&format!("，重试{}次", retries));
+    }
+    Ok(EjbcaResult {
+        success: true,
+        message,
+        total,
+        retries,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use tiny_http::{Header, Response, Server};
+
+    /// 启动测试EJBCA服务器：第一个请求返回429，之后按请求中的配置名称以测试CA签发证书
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!(
+            "http://{}/ejbca/ejbca-rest-api",
+            server.server_addr().to_ip().unwrap()
+        );
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test EJBCA CA").unwrap();
+        let name = name.build();
+        let issuer = name.to_owned().unwrap();
+        let validity = move |builder: &mut X509Builder| {
+            builder.set_version(2).unwrap();
+            builder.set_issuer_name(&issuer).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                .unwrap();
+        };
+        let mut builder = X509Builder::new().unwrap();
+        validity(&mut builder);
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+
+        std::thread::spawn(move || {
+            for (index, mut request) in server.incoming_requests().enumerate() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let response = if request.url()
+                    != "/ejbca/ejbca-rest-api/v1/certificate/pkcs10enroll"
+                {
+                    Response::from_string("").with_status_code(404)
+                } else if body["certificate_profile_name"] != "ENDUSER" {
+                    Response::from_string(
+                        r#"{"error_code":400,"error_message":"Certificate profile does not exist"}"#,
+                    )
+                    .with_status_code(400)
+                } else if index == 0 {
+                    Response::from_string(
+                        r#"{"error_code":429,"error_message":"Too many requests"}"#,
+                    )
+                    .with_status_code(429)
+                    .with_header(Header::from_bytes("Retry-After", "0").unwrap())
+                } else {
+                    let csr = parse_csr(body["certificate_request"].as_str().unwrap()).unwrap();
+                    assert_eq!(
+                        body["username"].as_str().unwrap(),
+                        format!("user-{}", username("{CN}", &csr).unwrap())
+                    );
+                    assert_eq!(body["password"], "foo123");
+                    let mut builder = X509Builder::new().unwrap();
+                    validity(&mut builder);
+                    builder.set_subject_name(csr.subject_name()).unwrap();
+                    builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                    builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                    let encode = |cert: &X509| base64::encode_block(&cert.to_der().unwrap());
+                    let reply = serde_json::json!({
+                        "certificate": encode(&builder.build()),
+                        "serial_number": "01",
+                        "response_format": "DER",
+                        "certificate_chain": [encode(&ca_cert)],
+                    });
+                    Response::from_string(reply.to_string()).with_status_code(201)
+                };
+                let _ = request.respond(response);
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_ejbca_enroll() {
+        let dir = std::env::temp_dir().join("csr_batch_ejbca_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let params = EjbcaParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: start_mock(),
+            certificate_profile_name: "ENDUSER".to_string(),
+            end_entity_profile_name: "DEVICES".to_string(),
+            certificate_authority_name: "ManagementCA".to_string(),
+            username: "user-{CN}".to_string(),
+            enrollment_code: "foo123".to_string(),
+            max_retries: 1,
+            ..Default::default()
+        };
+        let result = ejbca_enroll(params).unwrap();
+        assert_eq!((result.total, result.retries), (2, 1));
+        assert!(result.output_path.ends_with("out_ejbca.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (key_col, cert_col, chain_col) =
+            (column("privateKey"), column("certificate"), column("chain"));
+        for record in reader.records() {
+            let record = record.unwrap();
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(cert.issuer_name_hash(), chain.subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let server_url = start_mock();
+        let params = |overwrite: bool| EjbcaParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            certificate_profile_name: "MISSING".to_string(),
+            end_entity_profile_name: "DEVICES".to_string(),
+            certificate_authority_name: "ManagementCA".to_string(),
+            overwrite,
+            ..Default::default()
+        };
+        let error = ejbca_enroll(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 配置名称错误时不重试，返回EJBCA的错误说明
+        let error = ejbca_enroll(params(true)).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Certificate profile does not exist"),
+            "{}",
+            error
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,149 @@
+//! 错误类型模块
+//! 对外接口返回可序列化的结构化错误，前端可按 kind 字段显示本地化的提示
+//!
+//! 库内部仍使用 anyhow 传递错误，在公共接口处通过 `From<anyhow::Error>` 转换：
+//! 错误链中已有 `BatchError` 时原样取出，OpenSSL错误归为 `Openssl`，其余归为 `Other`
+
+use openssl::error::ErrorStack;
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::Path;
+
+/// 批量操作的结构化错误
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BatchError {
+    /// 通用名称范围格式错误
+    #[error("无法解析通用名称范围 {input}，正确格式示例: YDL0001-YDL0010")]
+    InvalidCnRange { input: String },
+    /// 不支持的密钥类型
+    #[error("不支持的密钥类型: {key_type}")]
+    UnsupportedKeyType { key_type: String },
+    /// 参数取值无效
+    #[error("参数 {field} 无效: {message}")]
+    InvalidParameter { field: String, message: String },
+    /// 没有需要生成的条目
+    #[error("没有需要生成的条目")]
+    NoItems,
+    /// 输入CSV中某一行无效（row为文件行号，表头为第1行）
+    #[error("输入CSV第{row}行无效: {message}")]
+    InvalidInput { row: usize, message: String },
+    /// 待签发或待导出CSV中某条记录无效（index从1开始）
+    #[error("第{index}条记录无效: {message}")]
+    InvalidRecord { index: usize, message: String },
+    /// Subject模板代入通用名称后无效
+    #[error("通用名称 {cn} 的Subject无效: {message}")]
+    InvalidSubject { cn: String, message: String },
+    /// 文件读写失败
+    #[error("文件读写失败 {path}: {message}")]
+    Io { path: String, message: String },
+    /// OpenSSL操作失败
+    #[error("{context}: {message}")]
+    Openssl { context: String, message: String },
+    /// 单个条目生成失败（index为条目序号，从0开始）
+    #[error("通用名称 {cn} 生成失败: {source}")]
+    Item {
+        index: usize,
+        cn: String,
+        source: Box<BatchError>,
+    },
+    /// 与历史索引中的批次重叠（count为重叠的条目数，example为第一个重叠条目的说明）
+    #[error("{count}个条目与历史批次重叠，如 {example}")]
+    HistoryConflict { count: usize, example: String },
+    /// 其他错误
+    #[error("{message}")]
+    Other { message: String },
+}
+
+impl BatchError {
+    /// 文件读写错误
+    pub(crate) fn io(path: impl AsRef<Path>, error: impl Display) -> Self {
+        BatchError::Io {
+            path: path.as_ref().display().to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    /// 参数取值错误
+    pub(crate) fn parameter(field: &str, message: impl Display) -> Self {
+        BatchError::InvalidParameter {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// CSV表头错误（表头为第1行）
+    pub(crate) fn header(message: impl Display) -> Self {
+        BatchError::InvalidInput {
+            row: 1,
+            message: message.to_string(),
+        }
+    }
+
+    /// CSV记录错误
+    pub(crate) fn record(index: usize, message: impl Display) -> Self {
+        BatchError::InvalidRecord {
+            index,
+            message: message.to_string(),
+        }
+    }
+
+    /// 为单个条目的错误附加条目序号和通用名称
+    pub(crate) fn item(index: usize, cn: &str, error: anyhow::Error) -> Self {
+        BatchError::Item {
+            index,
+            cn: cn.to_string(),
+            source: Box::new(error.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for BatchError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(batch_error) = error.chain().find_map(|e| e.downcast_ref::<BatchError>()) {
+            return batch_error.clone();
+        }
+        if let Some(stack) = error.chain().find_map(|e| e.downcast_ref::<ErrorStack>()) {
+            // 最外层不是OpenSSL错误本身时，将其作为上下文
+            let outermost = error.chain().next();
+            let context = if outermost.is_some_and(|e| e.is::<ErrorStack>()) {
+                "OpenSSL操作失败".to_string()
+            } else {
+                error.to_string()
+            };
+            return BatchError::Openssl {
+                context,
+                message: stack.to_string(),
+            };
+        }
+        BatchError::Other {
+            message: format!("{:#}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_convert_from_anyhow() {
+        let typed: anyhow::Error = BatchError::NoItems.into();
+        assert_eq!(BatchError::from(typed.context("外层")), BatchError::NoItems);
+
+        let openssl = openssl::pkey::PKey::private_key_from_pem(b"invalid")
+            .context("读取私钥失败")
+            .unwrap_err();
+        assert!(matches!(
+            BatchError::from(openssl),
+            BatchError::Openssl { context, .. } if context == "读取私钥失败"
+        ));
+
+        let item = BatchError::item(2, "YDL0003", anyhow::anyhow!("失败"));
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["kind"], "item");
+        assert_eq!(json["cn"], "YDL0003");
+        assert_eq!(json["source"]["kind"], "other");
+    }
+}
@@ -0,0 +1,495 @@
+//! EST注册模块
+//! 将生成结果CSV中的CSR逐个提交到EST服务器（RFC 7030）的 `/simpleenroll` 接口，
+//! 输出在原有列之后增加certificate列（证书）和chain列（返回的其他证书）的CSV文件，证书与私钥保存在同一行
+//!
+//! 客户端身份认证支持TLS客户端证书和HTTP Basic认证（可同时使用）。连接失败、服务器5xx/429错误和
+//! 202（请求待处理）视为暂时性失败，按 Retry-After 或指数退避重试
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::base64;
+use openssl::pkcs7::Pkcs7;
+use openssl::pkey::PKey;
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::output::{derived_csv_path, ResultCsv};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 未返回 Retry-After 时首次重试的等待时间，之后每次加倍
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Retry-After 的最长等待时间
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// EST注册参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct EstParams {
+    /// 待注册的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _est 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// EST服务地址，如 https://est.example.com/.well-known/est 或带CA标签的
+    /// https://est.example.com/.well-known/est/devices，请求时追加 /simpleenroll
+    pub server_url: String,
+    /// EST服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+    /// TLS客户端证书 (PEM，或包含证书和私钥的 .p12/.pfx 文件，可选)
+    #[serde(default)]
+    pub client_cert_path: String,
+    /// TLS客户端私钥 (PEM，客户端证书为PKCS#12文件时无需填写)
+    #[serde(default)]
+    pub client_key_path: String,
+    /// 客户端私钥口令或PKCS#12密码
+    #[serde(default)]
+    pub client_key_password: String,
+    /// HTTP Basic认证用户名 (可选)
+    #[serde(default)]
+    pub username: String,
+    /// HTTP Basic认证密码
+    #[serde(default)]
+    pub password: String,
+    /// 暂时性失败的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+/// 默认最大重试次数
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// EST注册结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct EstResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 重试的请求次数
+    pub retries: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 一次注册请求的结果
+enum Attempt {
+    Issued(String),
+    /// 暂时性失败，附带服务器要求的等待时间
+    Retry(String, Option<Duration>),
+    Failed(String),
+}
+
+/// 读取 Retry-After 头（秒数）
+pub(crate) fn retry_after(response: &ureq::Response) -> Option<Duration> {
+    response
+        .header("Retry-After")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(|secs| Duration::from_secs(secs).min(MAX_RETRY_DELAY))
+}
+
+struct EstClient {
+    agent: ureq::Agent,
+    enroll_url: String,
+    authorization: Option<String>,
+    max_retries: u32,
+}
+
+impl EstClient {
+    fn connect(params: &EstParams) -> Result<Self> {
+        if params.server_url.trim().is_empty() {
+            return Err(BatchError::parameter("server_url", "未填写").into());
+        }
+        let mut tls = native_tls::TlsConnector::builder();
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            tls.add_root_certificate(cert);
+        }
+        if !params.client_cert_path.is_empty() {
+            tls.identity(client_identity(
+                &params.client_cert_path,
+                &params.client_key_path,
+                &params.client_key_password,
+            )?);
+        }
+        let agent = ureq::AgentBuilder::new()
+            .timeout(HTTP_TIMEOUT)
+            .tls_connector(Arc::new(tls.build()?))
+            .build();
+
+        let authorization = (!params.username.is_empty()).then(|| {
+            let credentials = format!("{}:{}", params.username, params.password);
+            format!("Basic {}", base64::encode_block(credentials.as_bytes()))
+        });
+        Ok(Self {
+            agent,
+            enroll_url: format!(
+                "{}/simpleenroll",
+                params.server_url.trim().trim_end_matches('/')
+            ),
+            authorization,
+            max_retries: params.max_retries,
+        })
+    }
+
+    fn attempt(&self, body: &str) -> Attempt {
+        let mut request = self
+            .agent
+            .post(&self.enroll_url)
+            .set("Content-Type", "application/pkcs10")
+            .set("Content-Transfer-Encoding", "base64");
+        if let Some(authorization) = &self.authorization {
+            request = request.set("Authorization", authorization);
+        }
+        match request.send_string(body) {
+            // 202表示请求已接受但尚未签发，稍后重新提交同一CSR
+            Ok(response) if response.status() == 202 => {
+                let delay = retry_after(&response);
+                Attempt::Retry("请求待处理".to_string(), delay)
+            }
+            Ok(response) => match response.into_string() {
+                Ok(body) => Attempt::Issued(body),
+                Err(e) => Attempt::Retry(format!("读取响应失败: {}", e), None),
+            },
+            Err(ureq::Error::Status(code, response)) => {
+                let delay = retry_after(&response);
+                let text = response.into_string().unwrap_or_default();
+                let message = format!("EST服务器返回 {}: {}", code, text.trim());
+                if code >= 500 || code == 429 {
+                    Attempt::Retry(message, delay)
+                } else {
+                    Attempt::Failed(message)
+                }
+            }
+            Err(ureq::Error::Transport(e)) => Attempt::Retry(format!("连接失败: {}", e), None),
+        }
+    }
+
+    /// 提交CSR，暂时性失败时重试；返回响应内容和重试次数
+    fn enroll(&self, csr: &X509Req) -> Result<(String, usize)> {
+        let body = base64::encode_block(&csr.to_der()?);
+        let mut delay = RETRY_DELAY;
+        let mut retry = 0;
+        loop {
+            let (message, wait) = match self.attempt(&body) {
+                Attempt::Issued(response) => return Ok((response, retry)),
+                Attempt::Failed(message) => return Err(anyhow!(message)),
+                Attempt::Retry(message, wait) => (message, wait),
+            };
+            if retry >= self.max_retries as usize {
+                return Err(anyhow!("{}（已重试{}次）", message, retry));
+            }
+            std::thread::sleep(wait.unwrap_or(delay));
+            delay *= 2;
+            retry += 1;
+        }
+    }
+}
+
+/// 读取TLS客户端证书和私钥（证书为 .p12/.pfx 文件时私钥路径留空，口令为PKCS#12密码）
+pub(crate) fn client_identity(
+    cert_path: &str,
+    key_path: &str,
+    password: &str,
+) -> Result<native_tls::Identity> {
+    let bytes = fs::read(cert_path).map_err(|e| BatchError::io(cert_path, e))?;
+    let is_pkcs12 = Path::new(cert_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("p12") || e.eq_ignore_ascii_case("pfx"));
+    if is_pkcs12 {
+        return native_tls::Identity::from_pkcs12(&bytes, password)
+            .map_err(|e| anyhow!("无法解析PKCS#12文件，请检查密码: {}", e));
+    }
+
+    if key_path.is_empty() {
+        return Err(BatchError::parameter("client_key_path", "未指定客户端私钥").into());
+    }
+    let key_bytes = fs::read(key_path).map_err(|e| BatchError::io(key_path, e))?;
+    let key = if password.is_empty() {
+        PKey::private_key_from_pem(&key_bytes)
+    } else {
+        PKey::private_key_from_pem_passphrase(&key_bytes, password.as_bytes())
+    }
+    .map_err(|e| anyhow!("无法读取客户端私钥，请检查口令: {}", e))?;
+    // native-tls只接受未加密的PKCS#8私钥
+    native_tls::Identity::from_pkcs8(&bytes, &key.private_key_to_pem_pkcs8()?)
+        .map_err(|e| anyhow!("无效的客户端证书: {}", e))
+}
+
+/// 解析 simpleenroll 的响应（base64编码的certs-only PKCS#7），返回与CSR公钥匹配的证书和其余证书
+fn parse_certs_only(body: &str, csr: &X509Req) -> Result<(String, String)> {
+    let compact: String = body.split_whitespace().collect();
+    let der = base64::decode_block(&compact).map_err(|_| anyhow!("响应不是base64编码"))?;
+    let pkcs7 = Pkcs7::from_der(&der).map_err(|e| anyhow!("响应不是PKCS#7: {}", e))?;
+    let certs: Vec<X509> = pkcs7
+        .signed()
+        .and_then(|signed| signed.certificates())
+        .map(|stack| stack.iter().map(|cert| cert.to_owned()).collect())
+        .unwrap_or_default();
+
+    let csr_key = csr.public_key()?;
+    let mut leaf = None;
+    let mut chain = String::new();
+    for cert in certs {
+        if leaf.is_none() && cert.public_key()?.public_eq(&csr_key) {
+            leaf = Some(String::from_utf8(cert.to_pem()?)?);
+        } else {
+            chain.push_str(&String::from_utf8(cert.to_pem()?)?);
+        }
+    }
+    let leaf = leaf.ok_or_else(|| anyhow!("响应中没有与CSR公钥匹配的证书"))?;
+    Ok((leaf, chain))
+}
+
+/// 通过EST服务器为CSV中的每个CSR签发证书
+pub fn est_enroll(params: EstParams) -> Result<EstResult, BatchError> {
+    enroll_records(&params).map_err(BatchError::from)
+}
+
+/// 注册一行的CSR，返回证书、证书链和重试次数
+fn enroll_record(
+    client: &EstClient,
+    record: &StringRecord,
+    csr_col: usize,
+    number: usize,
+) -> Result<(String, String, usize)> {
+    let csr = parse_csr(record.get(csr_col).unwrap_or(""))
+        .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+    let (response, retried) = client
+        .enroll(&csr)
+        .map_err(|e| BatchError::record(number, format!("注册失败: {}", e)))?;
+    let (cert_pem, chain_pem) = parse_certs_only(&response, &csr)
+        .map_err(|e| BatchError::record(number, format!("注册失败: {}", e)))?;
+    Ok((cert_pem, chain_pem, retried))
+}
+
+fn enroll_records(params: &EstParams) -> Result<EstResult> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let csr_col = headers
+        .iter()
+        .position(|h| h == "csr")
+        .ok_or_else(|| BatchError::header("缺少csr列"))?;
+    // 重复注册时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "est")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = EstClient::connect(params)?;
+    let mut total = 0;
+    let mut retries = 0;
+    for (index, record) in records.iter().enumerate() {
+        // 中途注册失败时，已签发的证书和其余行照常写入输出文件
+        let (cert_pem, chain_pem, retried) =
+            match enroll_record(&client, record, csr_col, index + 1) {
+                Ok(enrolled) => enrolled,
+                Err(error) => return Err(output.abort(error, &records[index..], total)),
+            };
+        retries += retried;
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    let mut message = format!("EST服务器签发 {} 个证书", total);
+    if retries > 0 {
+        message.push_str(&format!("，重试{}次", retries));
+    }
+    Ok(EstResult {
+        success: true,
+        message,
+        total,
+        retries,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkcs7::Pkcs7Flags;
+    use openssl::stack::Stack;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use tiny_http::{Header, Response, Server};
+
+    /// 启动测试EST服务器：校验Basic认证，第一个请求返回503，之后以测试CA签发证书
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!(
+            "http://{}/.well-known/est",
+            server.server_addr().to_ip().unwrap()
+        );
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test EST CA").unwrap();
+        let name = name.build();
+        let issuer = name.to_owned().unwrap();
+        let validity = move |builder: &mut X509Builder| {
+            builder.set_version(2).unwrap();
+            builder.set_issuer_name(&issuer).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                .unwrap();
+        };
+        let mut builder = X509Builder::new().unwrap();
+        validity(&mut builder);
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+
+        std::thread::spawn(move || {
+            let expected = format!("Basic {}", base64::encode_block(b"device:secret"));
+            for (index, mut request) in server.incoming_requests().enumerate() {
+                let authorized = request
+                    .headers()
+                    .iter()
+                    .any(|h| h.field.equiv("Authorization") && h.value.as_str() == expected);
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let response = if request.url() != "/.well-known/est/simpleenroll" {
+                    Response::from_string("").with_status_code(404)
+                } else if !authorized {
+                    Response::from_string("unauthorized").with_status_code(401)
+                } else if index == 0 {
+                    Response::from_string("busy")
+                        .with_status_code(503)
+                        .with_header(Header::from_bytes("Retry-After", "0").unwrap())
+                } else {
+                    let der = base64::decode_block(&body).unwrap();
+                    let csr = X509Req::from_der(&der).unwrap();
+                    let mut builder = X509Builder::new().unwrap();
+                    validity(&mut builder);
+                    builder.set_subject_name(csr.subject_name()).unwrap();
+                    builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                    builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                    let mut certs = Stack::new().unwrap();
+                    certs.push(builder.build()).unwrap();
+                    // 签发的证书和CA证书都放在PKCS#7的证书列表中
+                    let pkcs7 =
+                        Pkcs7::sign(&ca_cert, &ca_key, &certs, &[], Pkcs7Flags::BINARY).unwrap();
+                    let encoded = base64::encode_block(&pkcs7.to_der().unwrap());
+                    Response::from_string(encoded).with_header(
+                        Header::from_bytes("Content-Type", "application/pkcs7-mime").unwrap(),
+                    )
+                };
+                let _ = request.respond(response);
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_est_enroll() {
+        let dir = std::env::temp_dir().join("csr_batch_est_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let params = EstParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: start_mock(),
+            username: "device".to_string(),
+            password: "secret".to_string(),
+            max_retries: 1,
+            ..Default::default()
+        };
+        let result = est_enroll(params).unwrap();
+        assert_eq!((result.total, result.retries), (2, 1));
+        assert!(result.output_path.ends_with("out_est.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (key_col, cert_col, chain_col) =
+            (column("privateKey"), column("certificate"), column("chain"));
+        for record in reader.records() {
+            let record = record.unwrap();
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(cert.issuer_name_hash(), chain.subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let server_url = start_mock();
+        let params = |overwrite: bool| EstParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            username: "device".to_string(),
+            password: "wrong".to_string(),
+            max_retries: 3,
+            overwrite,
+            ..Default::default()
+        };
+        let error = est_enroll(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 认证失败不重试，直接返回错误，已有的结果文件保持不变
+        let issued = fs::read_to_string(&result.output_path).unwrap();
+        let error = est_enroll(params(true)).unwrap_err();
+        assert!(error.to_string().contains("401"), "{}", error);
+        assert_eq!(fs::read_to_string(&result.output_path).unwrap(), issued);
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,251 @@
+//! 证书到期跟踪模块
+//! 将签发结果CSV中的证书记入本地任务历史数据库的certificates表（按证书SHA-256指纹去重），
+//! 可查询指定天数内到期（含已过期）的证书，用于续期看板和发起续期
+//!
+//! 每个证书记录其来源CSV，续期时以该CSV作为证书续期的输入即可沿用原私钥
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use csv::ReaderBuilder;
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::x509::{X509Ref, X509};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::bundle::sha256_fingerprint;
+use crate::error::BatchError;
+use crate::job_db::now;
+use crate::subject::{format_subject, from_x509_name};
+
+/// 建表语句，已存在时不重复创建；时间为UTC的RFC 3339格式，可按字符串比较
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS certificates (
+    fingerprint TEXT PRIMARY KEY,
+    serial TEXT NOT NULL,
+    cn TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    issuer TEXT NOT NULL,
+    not_before TEXT NOT NULL,
+    not_after TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    tracked_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS certificates_not_after ON certificates(not_after);
+";
+
+/// 即将到期的证书
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiringCertificate {
+    /// 证书的SHA-256指纹
+    pub fingerprint: String,
+    /// 序列号（十六进制）
+    pub serial: String,
+    /// 通用名称
+    pub cn: String,
+    /// 主题
+    pub subject: String,
+    /// 签发者
+    pub issuer: String,
+    /// 生效时间（UTC的RFC 3339格式）
+    pub not_before: String,
+    /// 到期时间
+    pub not_after: String,
+    /// 剩余天数（不足一天按0计，已过期为负数）
+    pub days_left: i64,
+    /// 来源CSV文件路径
+    pub source_path: String,
+}
+
+/// 证书时间转换为UTC时间
+pub(crate) fn utc_time(time: &Asn1TimeRef) -> Result<DateTime<Utc>> {
+    let diff = Asn1Time::from_unix(0)?.diff(time)?;
+    let seconds = diff.days as i64 * 86400 + diff.secs as i64;
+    Ok(DateTime::from_timestamp(seconds, 0).unwrap_or_default())
+}
+
+pub(crate) fn rfc3339(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 打开数据库（不存在时创建）并确保certificates表存在
+pub(crate) fn open(path: &Path) -> Result<Connection> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| BatchError::io(dir, e))?;
+    }
+    let conn = Connection::open(path).map_err(|e| BatchError::io(path, e))?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+fn track(conn: &Connection, cert: &X509Ref, source_path: &str) -> Result<()> {
+    let subject = from_x509_name(cert.subject_name())?;
+    let cn = subject
+        .iter()
+        .find(|attribute| attribute.attr == "CN")
+        .map(|attribute| attribute.value.clone())
+        .unwrap_or_default();
+    let serial = cert.serial_number().to_bn()?.to_hex_str()?.to_string();
+    conn.execute(
+        "INSERT INTO certificates (fingerprint, serial, cn, subject, issuer, not_before, not_after,
+         source_path, tracked_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(fingerprint) DO UPDATE SET source_path = ?8, tracked_at = ?9",
+        params![
+            sha256_fingerprint(cert)?,
+            serial,
+            cn,
+            format_subject(&subject),
+            format_subject(&from_x509_name(cert.issuer_name())?),
+            rfc3339(utc_time(cert.not_before())?),
+            rfc3339(utc_time(cert.not_after())?),
+            source_path,
+            now(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// 将CSV中certificate列的证书记入数据库（不存在时创建），certificate为空的行被跳过，返回记录的证书数
+pub fn track_certificates(db_path: &str, csv_path: &str) -> Result<usize, BatchError> {
+    track_csv(Path::new(db_path), csv_path).map_err(BatchError::from)
+}
+
+fn track_csv(db_path: &Path, csv_path: &str) -> Result<usize> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(csv_path)
+        .map_err(|e| BatchError::io(csv_path, e))?;
+    let cert_col = reader
+        .headers()?
+        .iter()
+        .position(|h| h == "certificate")
+        .ok_or_else(|| BatchError::header("缺少certificate列"))?;
+    let source_path = std::fs::canonicalize(csv_path)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| csv_path.to_string());
+
+    let mut conn = open(db_path)?;
+    let tx = conn.transaction()?;
+    let mut total = 0;
+    for (index, record) in reader.records().enumerate() {
+        let number = index + 1;
+        let record = record.map_err(|e| BatchError::record(number, format!("格式错误: {}", e)))?;
+        let pem = record.get(cert_col).unwrap_or("").trim();
+        if pem.is_empty() {
+            continue;
+        }
+        let cert = X509::from_pem(pem.as_bytes())
+            .map_err(|e| BatchError::record(number, format!("证书无效: {}", e)))?;
+        track(&tx, &cert, &source_path)?;
+        total += 1;
+    }
+    tx.commit()?;
+    Ok(total)
+}
+
+/// 列出days天内到期（含已过期）的证书，按到期时间排列；数据库不存在时返回空
+pub fn list_expiring(db_path: &str, days: u32) -> Result<Vec<ExpiringCertificate>, BatchError> {
+    expiring(Path::new(db_path), days).map_err(BatchError::from)
+}
+
+fn expiring(path: &Path, days: u32) -> Result<Vec<ExpiringCertificate>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let conn = open(path)?;
+    let now = Utc::now();
+    let deadline = rfc3339(now + Duration::days(days as i64));
+    let mut statement = conn.prepare(
+        "SELECT fingerprint, serial, cn, subject, issuer, not_before, not_after, source_path
+         FROM certificates WHERE not_after <= ?1 ORDER BY not_after, cn",
+    )?;
+    let certs = statement
+        .query_map(params![deadline], |row| {
+            let not_after: String = row.get(6)?;
+            let days_left = DateTime::parse_from_rfc3339(&not_after)
+                .map(|time| (time.with_timezone(&Utc) - now).num_days())
+                .unwrap_or_default();
+            Ok(ExpiringCertificate {
+                fingerprint: row.get(0)?,
+                serial: row.get(1)?,
+                cn: row.get(2)?,
+                subject: row.get(3)?,
+                issuer: row.get(4)?,
+                not_before: row.get(5)?,
+                not_after,
+                days_left,
+                source_path: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::Writer;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+
+    #[test]
+    fn test_list_expiring() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        // 自签名证书，到期时间相对当前时间（天），负数为已过期
+        let cert = |cn: &str, not_after: i64| {
+            let mut name = X509NameBuilder::new().unwrap();
+            name.append_entry_by_text("CN", cn).unwrap();
+            let name = name.build();
+            let mut builder = X509Builder::new().unwrap();
+            builder.set_subject_name(&name).unwrap();
+            builder.set_issuer_name(&name).unwrap();
+            builder.set_pubkey(&key).unwrap();
+            let now = Utc::now().timestamp();
+            let not_before = Asn1Time::from_unix(now - 400 * 86400).unwrap();
+            builder.set_not_before(&not_before).unwrap();
+            let not_after =
+                Asn1Time::from_unix(now + not_after * 86400 + not_after.signum() * 3600);
+            let not_after = not_after.unwrap();
+            builder.set_not_after(&not_after).unwrap();
+            builder.sign(&key, MessageDigest::sha256()).unwrap();
+            String::from_utf8(builder.build().to_pem().unwrap()).unwrap()
+        };
+
+        let dir = std::env::temp_dir().join("csr_batch_expiry_test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("issued.csv");
+        let mut writer = Writer::from_path(&csv_path).unwrap();
+        writer.write_record(["subject", "certificate"]).unwrap();
+        for (cn, days) in [("dev1", 10), ("dev2", 90), ("dev3", -2)] {
+            writer
+                .write_record([format!("CN=[{}]", cn), cert(cn, days)])
+                .unwrap();
+        }
+        // 尚未签发的行
+        writer.write_record(["CN=[dev4]", ""]).unwrap();
+        writer.flush().unwrap();
+
+        let db_path = dir.join("history/job_history.db");
+        let db = db_path.to_string_lossy().to_string();
+        let csv = csv_path.to_string_lossy().to_string();
+        assert!(list_expiring(&db, 30).unwrap().is_empty());
+        assert_eq!(track_certificates(&db, &csv).unwrap(), 3);
+        // 重复记录同一证书不产生重复行
+        assert_eq!(track_certificates(&db, &csv).unwrap(), 3);
+
+        let expiring = list_expiring(&db, 30).unwrap();
+        let summary: Vec<(&str, i64)> = expiring
+            .iter()
+            .map(|cert| (cert.cn.as_str(), cert.days_left))
+            .collect();
+        assert_eq!(summary, [("dev3", -2), ("dev1", 10)]);
+        assert_eq!(expiring[1].subject, "CN=dev1");
+        assert!(expiring[1].source_path.ends_with("issued.csv"));
+        assert_eq!(list_expiring(&db, 365).unwrap().len(), 3);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,402 @@
+//! CSR请求扩展模块
+//! 将密钥用途(Key Usage)、扩展密钥用途(Extended Key Usage)、备用名称和证书模板编码为CSR的请求扩展
+//!
+//! 备用名称的写法见san模块，写入subjectAltName扩展
+//!
+//! 密钥用途和扩展密钥用途均以逗号分隔书写，例如 `digitalSignature,keyEncipherment` 和 `serverAuth,clientAuth`，
+//! 名称不区分大小写；扩展密钥用途还可以直接使用点分OID
+//!
+//! 证书模板用于要求请求中带有模板信息的AD CS模板：填写模板名称（如 `WebServer`）时写入
+//! szOID_ENROLL_CERTTYPE 扩展 (1.3.6.1.4.1.311.20.2，BMPString)；填写模板OID时写入证书模板信息扩展
+//! (1.3.6.1.4.1.311.21.7)，可追加主版本和次版本，如 `1.3.6.1.4.1.311.21.8.1.2:100:3`
+//!
+//! 其他扩展（如CA要求的私有扩展）以 [`CustomExtension`] 按OID追加，书写格式同 `openssl req -addext`：
+//! `1.3.6.1.4.1.99999.1=critical,UTF8:class-a`
+
+use anyhow::Result;
+use openssl::asn1::{Asn1Object, Asn1OctetString};
+use openssl::bn::BigNum;
+use openssl::stack::Stack;
+use openssl::x509::extension::{ExtendedKeyUsage, KeyUsage};
+use openssl::x509::X509Extension;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::error::BatchError;
+use crate::san::san_extension_value;
+use crate::signature::{der, integer, oid, read_tlv, sequence};
+
+/// szOID_ENROLL_CERTTYPE，值为模板名称的BMPString
+const ENROLL_CERTTYPE_OID: &str = "1.3.6.1.4.1.311.20.2";
+/// szOID_CERTIFICATE_TEMPLATE，值为 SEQUENCE { 模板OID, 主版本 OPTIONAL, 次版本 OPTIONAL }
+const CERTIFICATE_TEMPLATE_OID: &str = "1.3.6.1.4.1.311.21.7";
+
+/// CSR请求扩展的参数
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ExtensionOptions<'a> {
+    /// 密钥用途，逗号分隔
+    pub(crate) key_usage: &'a str,
+    /// 扩展密钥用途，逗号分隔
+    pub(crate) extended_key_usage: &'a str,
+    /// 渲染后的备用名称
+    pub(crate) sans: &'a str,
+    /// 证书模板名称或OID
+    pub(crate) certificate_template: &'a str,
+    /// 按OID追加的自定义扩展
+    pub(crate) extensions: &'a [CustomExtension],
+}
+
+/// 按OID追加到CSR中的自定义扩展
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomExtension {
+    /// 扩展OID（点分形式）
+    pub oid: String,
+    /// 是否为关键扩展
+    #[serde(default)]
+    pub critical: bool,
+    /// 扩展值：`DER:` 后接十六进制DER编码（可含空格和冒号，省略前缀时同此），
+    /// `UTF8:` 后接文本编码为UTF8String，`IA5:` 后接ASCII文本编码为IA5String
+    pub value: String,
+}
+
+impl FromStr for CustomExtension {
+    type Err = BatchError;
+
+    /// 解析 `OID=[critical,]值` 格式
+    fn from_str(spec: &str) -> Result<Self, BatchError> {
+        let (oid, value) = spec.split_once('=').ok_or_else(|| {
+            let message = format!("应为 OID=[critical,]值 格式: {}", spec);
+            BatchError::parameter("extensions", message)
+        })?;
+        let value = value.trim();
+        let (critical, value) = match value.strip_prefix("critical,") {
+            Some(value) => (true, value),
+            None => (false, value),
+        };
+        Ok(CustomExtension {
+            oid: oid.trim().to_string(),
+            critical,
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+impl CustomExtension {
+    /// 扩展值（extnValue中的DER内容）
+    fn encoded_value(&self) -> Result<Vec<u8>> {
+        let invalid = |message: &str| {
+            let message = format!("扩展 {} 的值{}", self.oid, message);
+            BatchError::parameter("extensions", message)
+        };
+        // 前缀不区分大小写；十六进制DER可以含冒号，因此只识别已知的前缀
+        let value = self.value.as_str();
+        let prefixed = |prefix: &str| {
+            value
+                .get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| &value[prefix.len()..])
+        };
+        if let Some(text) = prefixed("UTF8:") {
+            return Ok(der(0x0c, text.as_bytes()));
+        }
+        if let Some(text) = prefixed("IA5:") {
+            if !text.is_ascii() {
+                return Err(invalid("只能包含ASCII字符").into());
+            }
+            return Ok(der(0x16, text.as_bytes()));
+        }
+
+        let hex: String = prefixed("DER:")
+            .unwrap_or(value)
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ':')
+            .collect();
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .filter(|bytes| !bytes.is_empty())
+            .ok_or_else(|| invalid("应为十六进制DER，或以UTF8:、IA5:开头的文本"))?;
+        // 值须恰好是一个完整的DER元素
+        match read_tlv(&bytes) {
+            Ok((_, _, [])) => Ok(bytes),
+            _ => Err(invalid("不是单个完整的DER元素").into()),
+        }
+    }
+}
+
+/// 拆分逗号分隔的用途列表，忽略空项
+pub(crate) fn split_usages(spec: &str) -> impl Iterator<Item = &str> {
+    spec.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// 构建密钥用途扩展，按RFC 5280建议标记为关键扩展
+/// 列表为空时返回None
+fn key_usage_extension(spec: &str) -> Result<Option<X509Extension>> {
+    let mut key_usage = KeyUsage::new();
+    key_usage.critical();
+    let mut empty = true;
+    for usage in split_usages(spec) {
+        match usage.to_ascii_lowercase().as_str() {
+            "digitalsignature" => key_usage.digital_signature(),
+            "nonrepudiation" | "contentcommitment" => key_usage.non_repudiation(),
+            "keyencipherment" => key_usage.key_encipherment(),
+            "dataencipherment" => key_usage.data_encipherment(),
+            "keyagreement" => key_usage.key_agreement(),
+            "keycertsign" => key_usage.key_cert_sign(),
+            "crlsign" => key_usage.crl_sign(),
+            "encipheronly" => key_usage.encipher_only(),
+            "decipheronly" => key_usage.decipher_only(),
+            _ => {
+                let message = format!("不支持的密钥用途: {}", usage);
+                return Err(BatchError::parameter("key_usage", message).into());
+            }
+        };
+        empty = false;
+    }
+    if empty {
+        return Ok(None);
+    }
+    Ok(Some(key_usage.build()?))
+}
+
+/// 构建扩展密钥用途扩展，列表为空时返回None
+fn extended_key_usage_extension(spec: &str) -> Result<Option<X509Extension>> {
+    let oid_re = Regex::new(r"^\d+(\.\d+)+$")?;
+    let mut eku = ExtendedKeyUsage::new();
+    let mut empty = true;
+    for usage in split_usages(spec) {
+        match usage.to_ascii_lowercase().as_str() {
+            "serverauth" => eku.server_auth(),
+            "clientauth" => eku.client_auth(),
+            "codesigning" => eku.code_signing(),
+            "emailprotection" => eku.email_protection(),
+            "timestamping" => eku.time_stamping(),
+            "ocspsigning" => eku.other("OCSPSigning"),
+            "smartcardlogon" => eku.other("1.3.6.1.4.1.311.20.2.2"),
+            _ if oid_re.is_match(usage) => eku.other(usage),
+            _ => {
+                let message = format!("不支持的扩展密钥用途: {}", usage);
+                return Err(BatchError::parameter("extended_key_usage", message).into());
+            }
+        };
+        empty = false;
+    }
+    if empty {
+        return Ok(None);
+    }
+    Ok(Some(eku.build()?))
+}
+
+/// 由DER编码的扩展值构造扩展
+pub(crate) fn der_extension(oid: &str, critical: bool, value: &[u8]) -> Result<X509Extension> {
+    let oid = Asn1Object::from_str(oid)?;
+    let value = Asn1OctetString::new_from_bytes(value)?;
+    Ok(X509Extension::new_from_der(&oid, critical, &value)?)
+}
+
+/// 编码证书模板扩展，返回扩展OID和值，未填写时返回None
+fn certificate_template_extension(spec: &str) -> Result<Option<(&'static str, Vec<u8>)>> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return Ok(None);
+    }
+    let invalid = |message: String| BatchError::parameter("certificate_template", message);
+
+    let oid_re = Regex::new(r"^(\d+(?:\.\d+)+)(?::(\d+))?(?::(\d+))?$")?;
+    if let Some(captures) = oid_re.captures(spec) {
+        let template_id =
+            oid(&captures[1]).map_err(|_| invalid(format!("证书模板OID无效: {}", &captures[1])))?;
+        let mut parts = vec![template_id];
+        for version in [captures.get(2), captures.get(3)].into_iter().flatten() {
+            let version: u32 = version
+                .as_str()
+                .parse()
+                .map_err(|_| invalid(format!("证书模板版本无效: {}", version.as_str())))?;
+            parts.push(integer(&BigNum::from_u32(version)?));
+        }
+        let parts: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+        return Ok(Some((CERTIFICATE_TEMPLATE_OID, sequence(&parts))));
+    }
+
+    // BMPString为UCS-2大端编码，不能表示基本多文种平面以外的字符
+    if spec.chars().any(|c| c.is_control() || c as u32 > 0xffff) {
+        return Err(invalid(format!("证书模板名称包含无效字符: {}", spec)).into());
+    }
+    let name: Vec<u8> = spec.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    Ok(Some((ENROLL_CERTTYPE_OID, der(0x1e, &name))))
+}
+
+/// 根据密钥用途、扩展密钥用途、备用名称、证书模板和自定义扩展构建CSR请求扩展列表
+/// 均为空时返回None，此时CSR中不包含extensionRequest属性
+pub(crate) fn requested_extensions(
+    options: &ExtensionOptions,
+) -> Result<Option<Stack<X509Extension>>> {
+    let mut stack = Stack::new()?;
+    // 已写入的扩展OID，同一扩展不能出现两次
+    let mut oids = HashSet::new();
+    if let Some(ext) = key_usage_extension(options.key_usage)? {
+        stack.push(ext)?;
+        oids.insert("2.5.29.15".to_string());
+    }
+    if let Some(ext) = extended_key_usage_extension(options.extended_key_usage)? {
+        stack.push(ext)?;
+        oids.insert("2.5.29.37".to_string());
+    }
+    if let Some(value) = san_extension_value(options.sans)? {
+        stack.push(der_extension("2.5.29.17", false, &value)?)?;
+        oids.insert("2.5.29.17".to_string());
+    }
+    if let Some((extn_id, value)) = certificate_template_extension(options.certificate_template)? {
+        stack.push(der_extension(extn_id, false, &value)?)?;
+        oids.insert(extn_id.to_string());
+    }
+
+    let oid_re = Regex::new(r"^\d+(\.\d+)+$")?;
+    for extension in options.extensions {
+        let extn_id = extension.oid.trim();
+        if !oid_re.is_match(extn_id) || Asn1Object::from_str(extn_id).is_err() {
+            let message = format!("扩展OID无效: {}", extension.oid);
+            return Err(BatchError::parameter("extensions", message).into());
+        }
+        if !oids.insert(extn_id.to_string()) {
+            let message = format!("扩展 {} 重复", extn_id);
+            return Err(BatchError::parameter("extensions", message).into());
+        }
+        let value = extension.encoded_value()?;
+        stack.push(der_extension(extn_id, extension.critical, &value)?)?;
+    }
+    Ok(if stack.is_empty() { None } else { Some(stack) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(
+        key_usage: &str,
+        extended_key_usage: &str,
+    ) -> Result<Option<Stack<X509Extension>>> {
+        requested_extensions(&ExtensionOptions {
+            key_usage,
+            extended_key_usage,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_requested_extensions() {
+        assert!(extensions("", " ").unwrap().is_none());
+
+        let stack = extensions(
+            "digitalSignature, keyEncipherment",
+            "serverAuth,1.3.6.1.5.5.7.3.2",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stack.len(), 2);
+
+        assert!(extensions("signEverything", "").is_err());
+        assert!(extensions("", "anyAuth").is_err());
+    }
+
+    #[test]
+    fn test_certificate_template_extension() {
+        let template = |spec: &str| {
+            let options = ExtensionOptions {
+                certificate_template: spec,
+                ..Default::default()
+            };
+            let stack = requested_extensions(&options)?.unwrap();
+            Ok::<_, anyhow::Error>(stack.get(0).unwrap().to_der()?)
+        };
+        // Extension ::= SEQUENCE { extnID, extnValue OCTET STRING }，非关键扩展不编码critical字段
+        let expected =
+            |extn_id: &str, value: &[u8]| sequence(&[&oid(extn_id).unwrap(), &der(0x04, value)]);
+
+        let mut name = vec![0x1e, 18];
+        name.extend("WebServer".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(
+            template(" WebServer ").unwrap(),
+            expected(ENROLL_CERTTYPE_OID, &name)
+        );
+
+        let template_id = oid("1.3.6.1.4.1.311.21.8.1.2").unwrap();
+        let info = sequence(&[&template_id, &[0x02, 0x01, 100], &[0x02, 0x01, 3]]);
+        assert_eq!(
+            template("1.3.6.1.4.1.311.21.8.1.2:100:3").unwrap(),
+            expected(CERTIFICATE_TEMPLATE_OID, &info)
+        );
+        assert_eq!(
+            template("1.3.6.1.4.1.311.21.8.1.2").unwrap(),
+            expected(CERTIFICATE_TEMPLATE_OID, &sequence(&[&template_id]))
+        );
+
+        assert!(template("Web\u{1F600}").is_err());
+        assert!(template("1.3.6.1:99999999999").is_err());
+    }
+
+    #[test]
+    fn test_custom_extensions() {
+        let custom = |specs: &[&str]| {
+            let extensions: Vec<CustomExtension> =
+                specs.iter().map(|spec| spec.parse().unwrap()).collect();
+            let options = ExtensionOptions {
+                key_usage: "digitalSignature",
+                extensions: &extensions,
+                ..Default::default()
+            };
+            let stack = requested_extensions(&options)?.unwrap();
+            let ders = stack
+                .iter()
+                .map(|ext| ext.to_der())
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, anyhow::Error>(ders)
+        };
+
+        let parsed: CustomExtension = " 1.3.6.1.4.1.99999.1 = critical,UTF8:class-a"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            parsed,
+            CustomExtension {
+                oid: "1.3.6.1.4.1.99999.1".to_string(),
+                critical: true,
+                value: "UTF8:class-a".to_string(),
+            }
+        );
+        assert!("1.3.6.1.4.1.99999.1".parse::<CustomExtension>().is_err());
+
+        let ders = custom(&[
+            "1.3.6.1.4.1.99999.1=critical,UTF8:class-a",
+            "1.3.6.1.4.1.99999.2=ia5:device",
+            "1.3.6.1.4.1.99999.3=DER:02:01:05",
+            "1.3.6.1.4.1.99999.4=3003020101",
+        ])
+        .unwrap();
+        assert_eq!(ders.len(), 5);
+        // 关键扩展编码critical字段 BOOLEAN TRUE
+        let critical = sequence(&[
+            &oid("1.3.6.1.4.1.99999.1").unwrap(),
+            &[0x01, 0x01, 0xff],
+            &der(0x04, &der(0x0c, b"class-a")),
+        ]);
+        assert_eq!(ders[1], critical);
+        assert!(ders[2].ends_with(&der(0x04, &der(0x16, b"device"))));
+        assert!(ders[3].ends_with(&[0x04, 0x03, 0x02, 0x01, 0x05]));
+        assert!(ders[4].ends_with(&[0x04, 0x05, 0x30, 0x03, 0x02, 0x01, 0x01]));
+
+        // 无效OID、值不是完整的DER元素、IA5含非ASCII字符、与密钥用途或其他自定义扩展重复
+        assert!(custom(&["keyUsage=UTF8:x"]).is_err());
+        assert!(custom(&["1.3.6.1.4.1.99999.1=02010500"]).is_err());
+        assert!(custom(&["1.3.6.1.4.1.99999.1=0201"]).is_err());
+        assert!(custom(&["1.3.6.1.4.1.99999.1=IA5:设备"]).is_err());
+        assert!(custom(&["2.5.29.15=DER:03020780"]).is_err());
+        assert!(custom(&["1.2.3.4=UTF8:a", "1.2.3.4=UTF8:b"]).is_err());
+    }
+}
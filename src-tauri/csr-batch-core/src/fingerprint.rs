@@ -0,0 +1,99 @@
+//! 指纹模块
+//! 计算CSR公钥的SHA-256指纹和主题密钥标识符（SKI），签发方可据此去重，
+//! 并在签发后将证书与输出中的行对应，无需重新解析PEM
+//!
+//! SKI按RFC 5280第4.2.1.2节方法1计算（subjectPublicKey位串的SHA-1），与OpenSSL等CA默认写入证书的值相同
+
+use anyhow::{anyhow, Result};
+use openssl::hash::{hash, MessageDigest};
+use openssl::x509::X509Req;
+
+/// 冒号分隔的大写十六进制，如 `AB:CD:01`
+pub(crate) fn colon_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 读取DER元素头部，返回(标签, 内容起始位置, 内容长度)
+fn der_header(der: &[u8]) -> Option<(u8, usize, usize)> {
+    let tag = *der.first()?;
+    let first = *der.get(1)? as usize;
+    if first < 0x80 {
+        return Some((tag, 2, first));
+    }
+    let count = first & 0x7f;
+    if count == 0 || count > 4 {
+        return None;
+    }
+    let mut length = 0usize;
+    for i in 0..count {
+        length = (length << 8) | *der.get(2 + i)? as usize;
+    }
+    Some((tag, 2 + count, length))
+}
+
+/// 从SubjectPublicKeyInfo中取出subjectPublicKey位串的内容（不含未使用位数）
+fn subject_public_key_bits(spki: &[u8]) -> Option<&[u8]> {
+    let (tag, start, length) = der_header(spki)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let body = spki.get(start..start + length)?;
+    let (_, algorithm_start, algorithm_length) = der_header(body)?;
+    let rest = body.get(algorithm_start + algorithm_length..)?;
+    let (tag, start, length) = der_header(rest)?;
+    if tag != 0x03 || length == 0 {
+        return None;
+    }
+    rest.get(start + 1..start + length)
+}
+
+/// CSR公钥的SHA-256指纹（对SubjectPublicKeyInfo计算）和主题密钥标识符，均为冒号分隔的大写十六进制
+pub(crate) fn public_key_ids(csr_pem: &str) -> Result<(String, String)> {
+    let spki = X509Req::from_pem(csr_pem.as_bytes())?
+        .public_key()?
+        .public_key_to_der()?;
+    let fingerprint = hash(MessageDigest::sha256(), &spki)?;
+    let bits = subject_public_key_bits(&spki).ok_or_else(|| anyhow!("无法解析CSR公钥"))?;
+    let ski = hash(MessageDigest::sha1(), bits)?;
+    Ok((colon_hex(&fingerprint), colon_hex(&ski)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::extension::SubjectKeyIdentifier;
+    use openssl::x509::X509;
+
+    #[test]
+    fn test_ski_matches_openssl() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut req = X509Req::builder().unwrap();
+        req.set_pubkey(&key).unwrap();
+        req.sign(&key, MessageDigest::sha256()).unwrap();
+        let csr_pem = String::from_utf8(req.build().to_pem().unwrap()).unwrap();
+
+        let mut cert = X509::builder().unwrap();
+        cert.set_pubkey(&key).unwrap();
+        let extension = SubjectKeyIdentifier::new()
+            .build(&cert.x509v3_context(None, None))
+            .unwrap();
+        cert.append_extension(extension).unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = cert.build();
+
+        let (fingerprint, ski) = public_key_ids(&csr_pem).unwrap();
+        assert_eq!(ski, colon_hex(cert.subject_key_id().unwrap().as_slice()));
+        let spki = key.public_key_to_der().unwrap();
+        let expected = hash(MessageDigest::sha256(), &spki).unwrap();
+        assert_eq!(fingerprint, colon_hex(&expected));
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+    }
+}
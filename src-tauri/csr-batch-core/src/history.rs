@@ -0,0 +1,221 @@
+//! 历史索引模块
+//! 在本地SQLite数据库中记录已生成的通用名称和公钥指纹，新批次与历史批次重叠时警告或拒绝生成，
+//! 避免同一设备序列号先后获得两个互相冲突的身份
+//!
+//! 索引只保存通用名称、公钥SHA-256指纹、生成时间和输出路径，不含CSR和私钥，可与SQLite任务数据库使用同一文件。
+//! 生成结束后才写入本批次的记录，取消时已写入输出的条目同样记录
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use crate::csr_generator::{BatchRequest, CsrResult};
+use crate::error::BatchError;
+use crate::job_db::now;
+
+/// 建表语句，已存在时不重复创建
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS cn_history (
+    id INTEGER PRIMARY KEY,
+    cn TEXT NOT NULL,
+    public_key_sha256 TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    output_path TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS cn_history_cn ON cn_history(cn);
+CREATE INDEX IF NOT EXISTS cn_history_key ON cn_history(public_key_sha256);
+";
+
+/// 与历史批次重叠时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConflictPolicy {
+    /// 照常生成，在报告中警告
+    Warn,
+    /// 拒绝生成
+    Fail,
+}
+
+impl ConflictPolicy {
+    fn parse(value: &str) -> Result<Self, BatchError> {
+        match value {
+            "" | "warn" => Ok(ConflictPolicy::Warn),
+            "fail" => Ok(ConflictPolicy::Fail),
+            other => Err(BatchError::parameter(
+                "history_conflict",
+                format!("{} 无效，须为warn或fail", other),
+            )),
+        }
+    }
+}
+
+/// 重叠的条目：数量和第一个重叠条目的说明
+#[derive(Debug, Default)]
+struct Conflicts {
+    count: usize,
+    example: Option<String>,
+}
+
+impl Conflicts {
+    fn add(&mut self, example: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.example.is_none() {
+            self.example = Some(example());
+        }
+    }
+
+    /// 按处理方式返回警告或错误
+    fn report(&self, policy: ConflictPolicy, what: &str) -> Result<Option<String>, BatchError> {
+        let Some(example) = &self.example else {
+            return Ok(None);
+        };
+        if policy == ConflictPolicy::Fail {
+            return Err(BatchError::HistoryConflict {
+                count: self.count,
+                example: example.clone(),
+            });
+        }
+        Ok(Some(format!(
+            "{}个条目{}（如 {}）",
+            self.count, what, example
+        )))
+    }
+}
+
+/// 单次生成任务使用的历史索引
+pub(crate) struct HistoryIndex {
+    conn: Connection,
+    policy: ConflictPolicy,
+    output_path: String,
+    key_conflicts: Conflicts,
+    /// 本批次已写入的条目：(通用名称, 公钥指纹)
+    written: Vec<(String, String)>,
+}
+
+impl HistoryIndex {
+    /// 打开（或创建）请求中的历史索引，未设置history_db_path时返回None
+    pub(crate) fn open(request: &BatchRequest) -> Result<Option<Self>> {
+        let policy = ConflictPolicy::parse(&request.history_conflict)?;
+        if request.history_db_path.is_empty() {
+            return Ok(None);
+        }
+        let path = Path::new(&request.history_db_path);
+        let conn = Connection::open(path).map_err(|e| BatchError::io(path, e))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Some(Self {
+            conn,
+            policy,
+            output_path: request.output_path.clone(),
+            key_conflicts: Conflicts::default(),
+            written: Vec::new(),
+        }))
+    }
+
+    /// 生成前检查通用名称是否已在历史批次中生成，返回警告（fail时报错）
+    pub(crate) fn check_cns<'a>(
+        &self,
+        cns: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Option<String>> {
+        let mut statement = self.conn.prepare(
+            "SELECT created_at, output_path FROM cn_history WHERE cn = ?1 ORDER BY id LIMIT 1",
+        )?;
+        let mut conflicts = Conflicts::default();
+        for cn in cns {
+            let previous: Option<(String, String)> = statement
+                .query_row(params![cn], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?;
+            if let Some((created_at, output_path)) = previous {
+                conflicts.add(|| format!("{} 已于 {} 生成，输出 {}", cn, created_at, output_path));
+            }
+        }
+        Ok(conflicts.report(self.policy, "的通用名称已在历史批次中生成")?)
+    }
+
+    /// 检查生成结果的公钥是否已在历史批次中用于其他通用名称（fail时报错），并记录该条目
+    pub(crate) fn check(&mut self, result: &CsrResult) -> Result<()> {
+        let fingerprint = &result.public_key_sha256;
+        let previous: Option<String> = self
+            .conn
+            .prepare_cached(
+                "SELECT cn FROM cn_history WHERE public_key_sha256 = ?1 AND cn <> ?2
+                 ORDER BY id LIMIT 1",
+            )?
+            .query_row(params![fingerprint, result.cn], |row| row.get(0))
+            .optional()?;
+        if let Some(previous) = previous {
+            self.key_conflicts
+                .add(|| format!("{} 的公钥已用于 {}", result.cn, previous));
+            if self.policy == ConflictPolicy::Fail {
+                self.key_conflicts.report(self.policy, "")?;
+            }
+        }
+        self.written.push((result.cn.clone(), fingerprint.clone()));
+        Ok(())
+    }
+
+    /// 写入本批次的条目，返回公钥重叠的警告
+    pub(crate) fn finish(self) -> Result<Option<String>> {
+        let warning = self
+            .key_conflicts
+            .report(self.policy, "的公钥已在历史批次中用于其他通用名称")?;
+        let created_at = now();
+        let mut conn = self.conn;
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO cn_history (cn, public_key_sha256, created_at, output_path)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (cn, fingerprint) in &self.written {
+                insert.execute(params![cn, fingerprint, created_at, self.output_path])?;
+            }
+        }
+        tx.commit()?;
+        Ok(warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_conflicts() {
+        let path = std::env::temp_dir().join("csr_batch_history_test.db");
+        std::fs::remove_file(&path).ok();
+        let request = |policy: &str| BatchRequest {
+            history_db_path: path.to_string_lossy().to_string(),
+            history_conflict: policy.to_string(),
+            output_path: "out.csv".to_string(),
+            ..Default::default()
+        };
+        let result = |cn: &str, fingerprint: &str| CsrResult {
+            cn: cn.to_string(),
+            public_key_sha256: fingerprint.to_string(),
+            ..Default::default()
+        };
+
+        let mut history = HistoryIndex::open(&request("")).unwrap().unwrap();
+        assert!(history.check_cns(["YDL0001", "YDL0002"]).unwrap().is_none());
+        history.check(&result("YDL0001", "AA")).unwrap();
+        history.check(&result("YDL0002", "BB")).unwrap();
+        assert!(history.finish().unwrap().is_none());
+
+        let mut history = HistoryIndex::open(&request("warn")).unwrap().unwrap();
+        let warning = history
+            .check_cns(["YDL0002", "YDL0003", "YDL0001"])
+            .unwrap()
+            .unwrap();
+        assert!(warning.starts_with("2个条目的通用名称已在历史批次中生成（如 YDL0002 已于"));
+        // 续期沿用原私钥不算冲突，其他通用名称使用同一公钥时警告
+        history.check(&result("YDL0001", "AA")).unwrap();
+        history.check(&result("YDL0003", "BB")).unwrap();
+        let warning = history.finish().unwrap().unwrap();
+        assert!(warning.contains("（如 YDL0003 的公钥已用于 YDL0002）"));
+
+        let history = HistoryIndex::open(&request("fail")).unwrap().unwrap();
+        let err: BatchError = history.check_cns(["YDL0003"]).unwrap_err().into();
+        assert!(matches!(err, BatchError::HistoryConflict { count: 1, .. }));
+        assert!(HistoryIndex::open(&request("ignore")).is_err());
+        std::fs::remove_file(path).ok();
+    }
+}
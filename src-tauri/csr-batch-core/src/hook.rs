@@ -0,0 +1,183 @@
+//! 完成回调模块
+//! 批量生成结束（完成、取消或失败）后运行配置的命令，或向Webhook地址POST任务结果，
+//! 下游自动化（如提交CA签发、同步到资产系统）可立即开始
+//!
+//! 命令通过系统shell执行（Windows为 `cmd /C`，其他系统为 `sh -c`），任务结果JSON写入标准输入，
+//! 任务ID、结束状态和输出路径另通过环境变量 CSR_JOB_ID、CSR_JOB_STATE、CSR_OUTPUT_PATH 传入；
+//! Webhook以 application/json 发送同样的JSON。回调失败不影响任务结果，只作为警告返回
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::csr_generator::{BatchReport, BatchRequest};
+use crate::error::BatchError;
+use crate::job_log::job_state;
+
+/// 等待回调命令结束的时间，超时后命令在后台继续运行
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Webhook请求超时
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 通过系统shell执行的命令（Windows为 `cmd /C`，其他系统为 `sh -c`）
+pub(crate) fn shell_command(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// 发送给回调的任务结果
+#[derive(Debug, Serialize)]
+struct CompletionEvent<'a> {
+    job_id: &'a str,
+    /// 结束状态：completed、cancelled或failed
+    state: &'a str,
+    /// 生成报告（失败时为空）
+    report: Option<&'a BatchReport>,
+    /// 错误（失败时可用）
+    error: Option<&'a BatchError>,
+}
+
+/// 任务的完成回调，生成开始前由请求创建（请求在生成时被消耗）
+#[derive(Debug, Clone, Default)]
+pub struct CompletionHooks {
+    job_id: String,
+    output_path: String,
+    command: String,
+    webhook_url: String,
+}
+
+impl CompletionHooks {
+    /// 读取请求中的回调命令和Webhook地址
+    pub fn from_request(request: &BatchRequest) -> Self {
+        Self {
+            job_id: request.job_id.clone(),
+            output_path: request.output_path.clone(),
+            command: request.on_complete_command.trim().to_string(),
+            webhook_url: request.webhook_url.trim().to_string(),
+        }
+    }
+
+    /// 依次执行回调命令和Webhook，返回失败的说明（作为警告）
+    pub fn run(&self, result: &Result<BatchReport, BatchError>) -> Vec<String> {
+        if self.command.is_empty() && self.webhook_url.is_empty() {
+            return Vec::new();
+        }
+        let (report, error) = match result {
+            Ok(report) => (Some(report), None),
+            Err(error) => (None, Some(error)),
+        };
+        let event = CompletionEvent {
+            job_id: &self.job_id,
+            state: job_state(result),
+            report,
+            error,
+        };
+        let output_path = report.map_or(self.output_path.as_str(), |r| r.output_path.as_str());
+        let payload = serde_json::to_string(&event).expect("任务结果可序列化为JSON");
+
+        let mut warnings = Vec::new();
+        if !self.command.is_empty() {
+            if let Err(e) = self.run_command(event.state, output_path, &payload) {
+                warnings.push(format!("完成回调命令失败: {}", e));
+            }
+        }
+        if !self.webhook_url.is_empty() {
+            if let Err(e) = self.post_webhook(&payload) {
+                warnings.push(format!("完成回调Webhook失败: {}", e));
+            }
+        }
+        warnings
+    }
+
+    fn run_command(&self, state: &str, output_path: &str, payload: &str) -> Result<()> {
+        let mut child = shell_command(&self.command)
+            .env("CSR_JOB_ID", &self.job_id)
+            .env("CSR_JOB_STATE", state)
+            .env("CSR_OUTPUT_PATH", output_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        // 在单独的线程中写入，命令不读取标准输入时不会阻塞，写入失败也忽略
+        let mut stdin = child.stdin.take().expect("stdin已设置为管道");
+        let payload = payload.to_string();
+        std::thread::spawn(move || stdin.write_all(payload.as_bytes()));
+
+        let started = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                if !status.success() {
+                    return Err(anyhow!("命令退出状态 {}", status));
+                }
+                return Ok(());
+            }
+            if started.elapsed() >= COMMAND_TIMEOUT {
+                return Err(anyhow!(
+                    "命令 {} 秒内未结束，已在后台继续运行",
+                    COMMAND_TIMEOUT.as_secs()
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn post_webhook(&self, payload: &str) -> Result<()> {
+        ureq::post(&self.webhook_url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .send_string(payload)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_completion_command() {
+        let path = std::env::temp_dir().join("csr_batch_hook_test.json");
+        std::fs::remove_file(&path).ok();
+        let request = BatchRequest {
+            job_id: "job-1".to_string(),
+            output_path: "out.csv".to_string(),
+            on_complete_command: format!(
+                "cat > {} && test \"$CSR_JOB_STATE\" = failed",
+                path.display()
+            ),
+            ..Default::default()
+        };
+        let hooks = CompletionHooks::from_request(&request);
+        assert!(hooks.run(&Err(BatchError::NoItems)).is_empty());
+        let event: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(event["job_id"], "job-1");
+        assert_eq!(event["error"]["kind"], "no_items");
+
+        // 命令失败和Webhook无法连接时返回警告
+        let request = BatchRequest {
+            on_complete_command: "exit 3".to_string(),
+            webhook_url: "http://127.0.0.1:1/hook".to_string(),
+            ..Default::default()
+        };
+        let warnings = CompletionHooks::from_request(&request).run(&Err(BatchError::NoItems));
+        assert_eq!(warnings.len(), 2);
+        assert!(CompletionHooks::default()
+            .run(&Err(BatchError::NoItems))
+            .is_empty());
+        std::fs::remove_file(path).ok();
+    }
+}
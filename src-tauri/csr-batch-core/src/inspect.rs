@@ -0,0 +1,345 @@
+//! CSR检查模块
+//! 解析单个CSR（PEM、DER或单行Base64，可为文件路径或CSR文本），返回Subject、备用名称、公钥类型和长度、
+//! 签名算法及请求扩展，用于抽查生成结果或导入外部生成的CSR
+
+use anyhow::{anyhow, Result};
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::asn1::{Asn1ObjectRef, Asn1StringRef};
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::stack::{Stack, StackRef};
+use openssl::x509::{GeneralName, GeneralNameRef, X509Extension, X509ExtensionRef, X509Req};
+use openssl_sys as ffi;
+use serde::Serialize;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::os::raw::{c_char, c_int, c_ulong};
+use std::path::Path;
+
+use crate::ca::parse_csr;
+use crate::csr_generator::KeyType;
+use crate::error::BatchError;
+use crate::fingerprint::{colon_hex, public_key_ids};
+use crate::san::upn_from_der;
+use crate::subject::{format_subject, from_x509_name, oid_text};
+
+extern "C" {
+    // openssl-sys未导出该函数
+    fn X509_REQ_get_signature_nid(req: *const ffi::X509_REQ) -> c_int;
+    // openssl-sys未导出该函数，otherName须从DER编码中读取
+    fn i2d_GENERAL_NAME(name: *mut ffi::GENERAL_NAME, out: *mut *mut u8) -> c_int;
+}
+
+/// X509V3_EXT_print的标志：无法识别的扩展以十六进制输出
+const X509V3_EXT_DUMP_UNKNOWN: c_ulong = 3 << 16;
+
+/// CSR检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CsrInfo {
+    /// Subject（逗号格式，如 `CN=YDL0001,O=Example`）
+    pub subject: String,
+    /// 通用名称（Subject中没有CN时为空）
+    pub cn: String,
+    /// 备用名称，如 `DNS:a.example.com`、`IP:10.0.0.1`
+    pub sans: Vec<String>,
+    /// 公钥类型，如 EC_P-256、RSA_2048；不在支持的密钥类型中时为算法名称
+    pub key_type: String,
+    /// 公钥长度（位）
+    pub key_bits: u32,
+    /// 签名算法，如 ecdsa-with-SHA256、RSASSA-PSS
+    pub signature_algorithm: String,
+    /// CSR自签名是否有效
+    pub signature_valid: bool,
+    /// 请求扩展（按CSR中的顺序）
+    pub extensions: Vec<CsrExtension>,
+    /// 公钥（SubjectPublicKeyInfo）的SHA-256指纹，冒号分隔的大写十六进制
+    pub public_key_sha256: String,
+    /// 主题密钥标识符（RFC 5280方法1），冒号分隔的大写十六进制
+    pub subject_key_id: String,
+}
+
+/// CSR中的单个请求扩展
+#[derive(Debug, Clone, Serialize)]
+pub struct CsrExtension {
+    /// 扩展名称（OpenSSL短名称，无法识别时为点分OID）
+    pub name: String,
+    /// 点分OID
+    pub oid: String,
+    /// 是否为关键扩展
+    pub critical: bool,
+    /// 扩展内容的文本形式，如 `Digital Signature, Key Encipherment`
+    pub value: String,
+}
+
+impl CsrInfo {
+    /// 检查CSR：input为CSR文件路径（PEM或DER），或PEM/单行Base64格式的CSR文本
+    pub fn inspect(input: &str) -> Result<CsrInfo, BatchError> {
+        inspect(input).map_err(BatchError::from)
+    }
+}
+
+/// 读取CSR：以PEM头开头时按文本解析，已存在的文件按PEM或DER读取，其余按单行Base64解析
+fn read_csr(input: &str) -> Result<X509Req> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(BatchError::parameter("csr", "请提供CSR文件路径或CSR内容").into());
+    }
+    if !input.starts_with("-----") && Path::new(input).is_file() {
+        let bytes = fs::read(input).map_err(|e| BatchError::io(input, e))?;
+        return if bytes.trim_ascii_start().starts_with(b"-----") {
+            X509Req::from_pem(&bytes)
+        } else {
+            X509Req::from_der(&bytes)
+        }
+        .map_err(|e| anyhow!("CSR无效: {}", e));
+    }
+    parse_csr(input).map_err(|e| anyhow!("CSR无效: {}", e))
+}
+
+fn inspect(input: &str) -> Result<CsrInfo> {
+    let req = read_csr(input)?;
+    let pubkey = req.public_key()?;
+    let signature_valid = req.verify(&pubkey).unwrap_or(false);
+
+    let attributes = from_x509_name(req.subject_name())?;
+    let cn = attributes
+        .iter()
+        .find(|attribute| attribute.attr == "CN")
+        .map(|attribute| attribute.value.clone())
+        .unwrap_or_default();
+
+    let key_type = match KeyType::of_key(&pubkey) {
+        Ok(key_type) => key_type.display_name().to_string(),
+        Err(_) => Nid::from_raw(pubkey.id().as_raw())
+            .short_name()
+            .unwrap_or("未知")
+            .to_string(),
+    };
+
+    // SAFETY: req为有效的X509_REQ
+    let signature_nid = Nid::from_raw(unsafe { X509_REQ_get_signature_nid(req.as_ptr()) });
+    let signature_algorithm = signature_nid.long_name().unwrap_or("未知").to_string();
+
+    let extensions = request_extensions(&req)?;
+    let mut sans = Vec::new();
+    let mut infos = Vec::new();
+    for extension in &extensions {
+        let info = extension_info(extension)?;
+        if info.name == "subjectAltName" {
+            sans = subject_alt_names(extension);
+        }
+        infos.push(info);
+    }
+
+    let csr_pem = String::from_utf8(req.to_pem()?)?;
+    let (public_key_sha256, subject_key_id) = public_key_ids(&csr_pem)?;
+    Ok(CsrInfo {
+        subject: format_subject(&attributes),
+        cn,
+        sans,
+        key_type,
+        key_bits: pubkey.bits(),
+        signature_algorithm,
+        signature_valid,
+        extensions: infos,
+        public_key_sha256,
+        subject_key_id,
+    })
+}
+
+/// CSR中的请求扩展
+pub(crate) fn request_extensions(req: &X509Req) -> Result<Stack<X509Extension>, ErrorStack> {
+    // CSR中没有请求扩展时返回错误，视为空列表
+    match req.extensions() {
+        Ok(extensions) => Ok(extensions),
+        Err(_) => Stack::new(),
+    }
+}
+
+/// 扩展的对象标识符
+pub(crate) fn extension_object(extension: &X509ExtensionRef) -> &Asn1ObjectRef {
+    // SAFETY: extension为有效的X509_EXTENSION，返回的对象由扩展持有
+    unsafe { Asn1ObjectRef::from_ptr(ffi::X509_EXTENSION_get_object(extension.as_ptr())) }
+}
+
+/// 读取扩展的名称、关键性和内容文本
+fn extension_info(extension: &X509ExtensionRef) -> Result<CsrExtension> {
+    let object = extension_object(extension);
+    // SAFETY: extension为有效的X509_EXTENSION，返回的指针由扩展持有
+    let (critical, data) = unsafe {
+        let ptr = extension.as_ptr();
+        (
+            ffi::X509_EXTENSION_get_critical(ptr) > 0,
+            Asn1StringRef::from_ptr(ffi::X509_EXTENSION_get_data(ptr) as *mut ffi::ASN1_STRING),
+        )
+    };
+    let oid = oid_text(object);
+    let name = match object.nid() {
+        Nid::UNDEF => oid.clone(),
+        nid => nid.short_name().unwrap_or(&oid).to_string(),
+    };
+    let value = match extension_text(extension)? {
+        Some(value) => value,
+        None => colon_hex(data.as_slice()),
+    };
+    Ok(CsrExtension {
+        name,
+        oid,
+        critical,
+        value,
+    })
+}
+
+/// 按OpenSSL的格式输出扩展内容，多行时以 `; ` 连接；无法输出时返回None
+fn extension_text(extension: &X509ExtensionRef) -> Result<Option<String>, ErrorStack> {
+    // SAFETY: BIO在本函数内创建和释放，读取的内存在释放前复制
+    unsafe {
+        let bio = ffi::BIO_new(ffi::BIO_s_mem());
+        if bio.is_null() {
+            return Err(ErrorStack::get());
+        }
+        let ret = ffi::X509V3_EXT_print(bio, extension.as_ptr(), X509V3_EXT_DUMP_UNKNOWN, 0);
+        let mut ptr: *mut c_char = std::ptr::null_mut();
+        let len = ffi::BIO_get_mem_data(bio, &mut ptr);
+        let text = if ret > 0 && !ptr.is_null() && len > 0 {
+            let bytes = std::slice::from_raw_parts(ptr as *const u8, len as usize);
+            let text = String::from_utf8_lossy(bytes);
+            Some(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            )
+        } else {
+            None
+        };
+        ffi::BIO_free_all(bio);
+        Ok(text)
+    }
+}
+
+/// CSR备用名称中的DNS名称
+pub(crate) fn dns_names(req: &X509Req) -> Result<Vec<String>, ErrorStack> {
+    Ok(alt_names(req)?
+        .into_iter()
+        .filter_map(|name| name.strip_prefix("DNS:").map(str::to_string))
+        .collect())
+}
+
+/// CSR的备用名称，文本形式如 `DNS:a.example.com`、`IP:10.0.0.1`
+pub(crate) fn alt_names(req: &X509Req) -> Result<Vec<String>, ErrorStack> {
+    let mut names = Vec::new();
+    for extension in &request_extensions(req)? {
+        if extension_object(extension).nid() == Nid::SUBJECT_ALT_NAME {
+            names.extend(subject_alt_names(extension));
+        }
+    }
+    Ok(names)
+}
+
+/// 解析备用名称扩展中的名称
+fn subject_alt_names(extension: &X509ExtensionRef) -> Vec<String> {
+    // SAFETY: 备用名称扩展解码为GENERAL_NAMES（GENERAL_NAME的栈），所有权转移给Stack
+    let names = unsafe {
+        let ptr = ffi::X509V3_EXT_d2i(extension.as_ptr());
+        if ptr.is_null() {
+            return Vec::new();
+        }
+        Stack::<GeneralName>::from_ptr(ptr as *mut ffi::stack_st_GENERAL_NAME)
+    };
+    general_names(&names)
+}
+
+/// 备用名称的文本形式，如 `DNS:a.example.com`、`IP:10.0.0.1`、`UPN:user@corp.example.com`，不支持的名称类型被忽略
+pub(crate) fn general_names(names: &StackRef<GeneralName>) -> Vec<String> {
+    names.iter().filter_map(general_name).collect()
+}
+
+fn general_name(name: &GeneralNameRef) -> Option<String> {
+    if let Some(dns) = name.dnsname() {
+        Some(format!("DNS:{}", dns))
+    } else if let Some(ip) = name.ipaddress() {
+        let ip = match ip.len() {
+            4 => Ipv4Addr::from(<[u8; 4]>::try_from(ip).ok()?).to_string(),
+            16 => Ipv6Addr::from(<[u8; 16]>::try_from(ip).ok()?).to_string(),
+            _ => return None,
+        };
+        Some(format!("IP:{}", ip))
+    } else if let Some(email) = name.email() {
+        Some(format!("email:{}", email))
+    } else if let Some(uri) = name.uri() {
+        Some(format!("URI:{}", uri))
+    } else {
+        general_name_der(name)
+            .and_then(|der| upn_from_der(&der))
+            .map(|upn| format!("UPN:{}", upn))
+    }
+}
+
+/// GeneralName的DER编码
+fn general_name_der(name: &GeneralNameRef) -> Option<Vec<u8>> {
+    // SAFETY: 第一次调用只计算长度，第二次写入长度相同的缓冲区
+    unsafe {
+        let len = i2d_GENERAL_NAME(name.as_ptr(), std::ptr::null_mut());
+        let mut der = vec![0u8; usize::try_from(len).ok().filter(|len| *len > 0)?];
+        let mut out = der.as_mut_ptr();
+        (i2d_GENERAL_NAME(name.as_ptr(), &mut out) == len).then_some(der)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::{requested_extensions, ExtensionOptions};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::X509NameBuilder;
+
+    #[test]
+    fn test_inspect_csr() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "YDL0001").unwrap();
+        name.append_entry_by_text("O", "Example").unwrap();
+        let mut req = X509Req::builder().unwrap();
+        req.set_pubkey(&key).unwrap();
+        req.set_subject_name(&name.build()).unwrap();
+        let key_usage = ExtensionOptions {
+            key_usage: "digitalSignature",
+            ..Default::default()
+        };
+        let mut extensions = requested_extensions(&key_usage).unwrap().unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("ydl0001.example.com")
+            .ip("10.0.0.1")
+            .build(&req.x509v3_context(None))
+            .unwrap();
+        extensions.push(san).unwrap();
+        req.add_extensions(&extensions).unwrap();
+        req.sign(&key, MessageDigest::sha256()).unwrap();
+        let req = req.build();
+
+        let path = std::env::temp_dir().join("csr_batch_inspect_test.der");
+        fs::write(&path, req.to_der().unwrap()).unwrap();
+        let pem = String::from_utf8(req.to_pem().unwrap()).unwrap();
+        for input in [path.to_string_lossy().to_string(), pem] {
+            let info = CsrInfo::inspect(&input).unwrap();
+            assert_eq!(info.cn, "YDL0001");
+            assert_eq!(info.subject, "CN=YDL0001,O=Example");
+            assert_eq!(info.key_type, "EC_P-256");
+            assert_eq!(info.key_bits, 256);
+            assert_eq!(info.signature_algorithm, "ecdsa-with-SHA256");
+            assert!(info.signature_valid);
+            assert_eq!(info.sans, ["DNS:ydl0001.example.com", "IP:10.0.0.1"]);
+            assert_eq!(info.extensions[0].name, "keyUsage");
+            assert!(info.extensions[0].critical);
+            assert_eq!(info.extensions[0].value, "Digital Signature");
+        }
+        fs::remove_file(path).ok();
+        assert!(CsrInfo::inspect("not a csr").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! 任务数据库模块
+//! 将生成结果写入SQLite数据库：jobs表每次生成一行，csrs表每个CSR一行，
+//! 同一数据库可在多次生成间累积，便于按时间、密钥类型或通用名称查询，而无需解析大型CSV
+//!
+//! 写入在事务中进行，每次刷新时提交；任务未完成（如取消或出错）时已提交的结果仍保留，
+//! jobs表的finished_at为空
+
+use anyhow::Result;
+use chrono::{Local, SecondsFormat};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+use crate::csr_generator::{BatchRequest, CsrResult};
+use crate::error::BatchError;
+
+/// 建表语句，已存在时不重复创建
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    key_type TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    total INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS csrs (
+    id INTEGER PRIMARY KEY,
+    job_id INTEGER NOT NULL REFERENCES jobs(id),
+    created_at TEXT NOT NULL,
+    cn TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    sign_hash_alg TEXT NOT NULL,
+    not_before TEXT NOT NULL,
+    not_after TEXT NOT NULL,
+    unique_id TEXT NOT NULL,
+    sans TEXT NOT NULL,
+    csr TEXT NOT NULL,
+    key_pair_type TEXT NOT NULL,
+    private_key TEXT NOT NULL,
+    private_key_encryption TEXT NOT NULL,
+    pq_csr TEXT,
+    pq_key_pair_type TEXT,
+    pq_private_key TEXT,
+    key_container TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS csrs_cn ON csrs(cn);
+CREATE INDEX IF NOT EXISTS csrs_created_at ON csrs(created_at);
+";
+
+/// 当前时间（本地时区的RFC 3339格式）
+pub(crate) fn now() -> String {
+    Local::now().to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// 单次生成任务在数据库中的记录
+pub(crate) struct JobDatabase {
+    conn: Connection,
+    job_id: i64,
+    total: usize,
+}
+
+impl JobDatabase {
+    /// 打开（或创建）数据库并新增一条任务记录
+    pub(crate) fn open(path: &Path, request: &BatchRequest, output_path: &str) -> Result<Self> {
+        let conn = Connection::open(path).map_err(|e| BatchError::io(path, e))?;
+        conn.execute_batch(SCHEMA)?;
+        conn.execute(
+            "INSERT INTO jobs (started_at, key_type, output_path) VALUES (?1, ?2, ?3)",
+            params![now(), request.key_type, output_path],
+        )?;
+        let job_id = conn.last_insert_rowid();
+        conn.execute_batch("BEGIN")?;
+        Ok(Self {
+            conn,
+            job_id,
+            total: 0,
+        })
+    }
+
+    pub(crate) fn write(&mut self, result: &CsrResult) -> Result<()> {
+        let pq = result.pq.as_ref();
+        self.conn
+            .prepare_cached(
+                "INSERT INTO csrs (job_id, created_at, cn, subject, sign_hash_alg, not_before,
+                 not_after, unique_id, sans, csr, key_pair_type, private_key,
+                 private_key_encryption, pq_csr, pq_key_pair_type, pq_private_key, key_container)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            )?
+            .execute(params![
+                self.job_id,
+                now(),
+                result.cn,
+                result.subject,
+                result.sign_hash_alg,
+                result.not_before,
+                result.not_after,
+                result.unique_id,
+                result.sans,
+                result.csr_pem,
+                result.key_pair_type,
+                result.private_key_pem,
+                result.key_encryption,
+                pq.map(|pq| &pq.csr_pem),
+                pq.map(|pq| &pq.key_pair_type),
+                pq.map(|pq| &pq.private_key_pem),
+                result.key_container,
+            ])?;
+        self.total += 1;
+        Ok(())
+    }
+
+    /// 提交已写入的结果
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        self.update_total()?;
+        self.conn.execute_batch("COMMIT; BEGIN")?;
+        Ok(())
+    }
+
+    /// 记录任务完成时间并提交
+    pub(crate) fn finish(self) -> Result<()> {
+        self.update_total()?;
+        self.conn.execute(
+            "UPDATE jobs SET finished_at = ?1 WHERE id = ?2",
+            params![now(), self.job_id],
+        )?;
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    fn update_total(&self) -> Result<()> {
+        self.conn.execute(
+            "UPDATE jobs SET total = ?1 WHERE id = ?2",
+            params![self.total as i64, self.job_id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs_accumulate() {
+        let path = std::env::temp_dir().join("csr_batch_job_db_test.db");
+        std::fs::remove_file(&path).ok();
+        let request = BatchRequest {
+            key_type: "EC_P256".to_string(),
+            ..Default::default()
+        };
+        let result = |cn: &str| CsrResult {
+            cn: cn.to_string(),
+            key_pair_type: "EC_P-256".to_string(),
+            ..Default::default()
+        };
+
+        for cns in [["YDL0001", "YDL0002"], ["YDL0003", "YDL0004"]] {
+            let mut db = JobDatabase::open(&path, &request, "out.csv").unwrap();
+            for cn in cns {
+                db.write(&result(cn)).unwrap();
+            }
+            db.finish().unwrap();
+        }
+
+        let conn = Connection::open(&path).unwrap();
+        let jobs: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM jobs WHERE finished_at IS NOT NULL AND total = 2",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(jobs, 2);
+        let cn: String = conn
+            .query_row(
+                "SELECT cn FROM csrs WHERE job_id = 2 AND key_pair_type = 'EC_P-256' ORDER BY id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(cn, "YDL0003");
+        std::fs::remove_file(path).ok();
+    }
+}
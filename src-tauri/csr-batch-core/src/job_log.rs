@@ -0,0 +1,561 @@
+//! 任务历史模块
+//! 在本地SQLite数据库中记录每次批量生成：参数（不含机密参数）、开始和结束时间、耗时、执行的操作系统用户、
+//! 生成和失败的数量、输出路径以及每个失败条目的原因，供审计时查询何时由谁生成了哪些CSR
+//!
+//! 与任务数据库（sqlite_path）不同，任务历史不保存CSR和私钥
+//!
+//! 有失败条目的任务可用 [`retry_request`] 重新生成失败的条目并追加到原输出CSV，
+//! 重试结束后原任务的数量、失败条目和错误报告随之更新
+
+use anyhow::Result;
+use chrono::{DateTime, Local, SecondsFormat};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::csr_generator::{BatchReport, BatchRequest, ItemFailure};
+use crate::error::BatchError;
+use crate::job_db::now;
+use crate::output::{derived_csv_path, write_error_report};
+
+/// 建表语句，已存在时不重复创建
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS job_history (
+    id TEXT PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    user TEXT NOT NULL,
+    state TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    failed INTEGER NOT NULL,
+    output_path TEXT NOT NULL,
+    message TEXT NOT NULL,
+    params TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS job_failures (
+    job_id TEXT NOT NULL REFERENCES job_history(id),
+    item_index INTEGER NOT NULL,
+    cn TEXT NOT NULL,
+    error TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS job_failures_job_id ON job_failures(job_id);
+";
+
+/// 查询概要的列
+const SUMMARY_COLUMNS: &str =
+    "id, started_at, finished_at, duration_ms, user, state, total, failed, output_path, message";
+
+/// 任务概要
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    /// 任务ID
+    pub id: String,
+    /// 开始时间（本地时区的RFC 3339格式）
+    pub started_at: String,
+    /// 结束时间
+    pub finished_at: String,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    /// 执行生成的操作系统用户
+    pub user: String,
+    /// 结束状态：completed、cancelled或failed
+    pub state: String,
+    /// 成功生成的CSR数
+    pub total: usize,
+    /// 生成失败的条目数
+    pub failed: usize,
+    /// 输出文件路径
+    pub output_path: String,
+    /// 结果消息（失败时为错误信息）
+    pub message: String,
+}
+
+/// 生成失败的条目
+#[derive(Debug, Clone, Serialize)]
+pub struct JobFailure {
+    /// 条目序号（从0开始）
+    pub index: usize,
+    /// 通用名称
+    pub cn: String,
+    /// 失败原因
+    pub error: String,
+}
+
+/// 任务详情
+#[derive(Debug, Serialize)]
+pub struct JobRecord {
+    #[serde(flatten)]
+    pub summary: JobSummary,
+    /// 生成参数（不含机密参数）
+    pub params: BatchRequest,
+    /// 生成失败的条目
+    pub failures: Vec<JobFailure>,
+}
+
+/// 当前操作系统用户（USER或USERNAME环境变量），无法确定时为空
+pub(crate) fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// 任务的结束状态：completed、cancelled或failed
+pub(crate) fn job_state(result: &Result<BatchReport, BatchError>) -> &'static str {
+    match result {
+        Ok(report) if report.cancelled => "cancelled",
+        Ok(_) => "completed",
+        Err(_) => "failed",
+    }
+}
+
+/// 正在执行的任务，生成开始前创建，结束后写入任务历史
+#[derive(Debug)]
+pub struct JobLogEntry {
+    id: String,
+    started: SystemTime,
+    user: String,
+    params: BatchRequest,
+}
+
+impl JobLogEntry {
+    /// 记录开始时间、当前用户和生成参数（不含机密参数）
+    pub fn start(params: &BatchRequest) -> Self {
+        let started = SystemTime::now();
+        let id = if params.job_id.is_empty() {
+            DateTime::<Local>::from(started)
+                .format("%Y%m%d%H%M%S%f")
+                .to_string()
+        } else {
+            params.job_id.clone()
+        };
+        Self {
+            id,
+            started,
+            user: current_user(),
+            params: params.clone().without_secrets(),
+        }
+    }
+
+    /// 将任务结果写入任务历史数据库（不存在时创建）
+    pub fn finish(
+        self,
+        path: &str,
+        result: &Result<BatchReport, BatchError>,
+    ) -> Result<(), BatchError> {
+        self.write(Path::new(path), result)
+            .map_err(BatchError::from)
+    }
+
+    fn write(self, path: &Path, result: &Result<BatchReport, BatchError>) -> Result<()> {
+        let duration_ms = self.started.elapsed().unwrap_or_default().as_millis() as u64;
+        let started_at =
+            DateTime::<Local>::from(self.started).to_rfc3339_opts(SecondsFormat::Secs, false);
+        let state = job_state(result);
+        let (total, failed, output_path, message) = match result {
+            Ok(report) => (
+                report.total,
+                report.failed,
+                report.output_path.as_str(),
+                report.message.clone(),
+            ),
+            Err(error) => (0, 0, self.params.output_path.as_str(), error.to_string()),
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| BatchError::io(dir, e))?;
+        }
+        let mut conn = Connection::open(path).map_err(|e| BatchError::io(path, e))?;
+        conn.execute_batch(SCHEMA)?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO job_history (id, started_at, finished_at, duration_ms, user,
+             state, total, failed, output_path, message, params)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                self.id,
+                started_at,
+                now(),
+                duration_ms,
+                self.user,
+                state,
+                total,
+                failed,
+                output_path,
+                message,
+                serde_json::to_string(&self.params)?,
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM job_failures WHERE job_id = ?1",
+            params![self.id],
+        )?;
+        if let Ok(report) = result {
+            let mut insert = tx.prepare(
+                "INSERT INTO job_failures (job_id, item_index, cn, error) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for failure in &report.failures {
+                insert.execute(params![
+                    self.id,
+                    failure.index,
+                    failure.cn,
+                    failure.error.to_string()
+                ])?;
+            }
+        }
+        // 重试被取消时未尝试的条目不在报告中，原任务保持不变
+        let retried = match result {
+            Ok(report) if !self.params.retry_of.is_empty() && !report.cancelled => {
+                Some(update_retried(&tx, &self.params.retry_of, report)?)
+            }
+            _ => None,
+        };
+        tx.commit()?;
+        if let Some((params, remaining)) = retried {
+            rewrite_error_report(&params, &remaining)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按重试结果更新原任务：成功数加上重试成功的条目，失败条目只保留重试后仍失败的（沿用原序号），
+/// 返回原任务的参数和仍失败的条目
+fn update_retried(
+    tx: &Transaction,
+    id: &str,
+    report: &BatchReport,
+) -> Result<(BatchRequest, Vec<JobFailure>)> {
+    let (total, params): (usize, String) = tx
+        .query_row(
+            "SELECT total, params FROM job_history WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| BatchError::parameter("retry_of", format!("未找到任务 {}", id)))?;
+    let original = failures(tx, id)?;
+    let remaining: Vec<JobFailure> = report
+        .failures
+        .iter()
+        .map(|failure| JobFailure {
+            index: original
+                .iter()
+                .find(|o| o.cn == failure.cn)
+                .map_or(failure.index, |o| o.index),
+            cn: failure.cn.clone(),
+            error: failure.error.to_string(),
+        })
+        .collect();
+
+    let total = total + report.total;
+    let message = if remaining.is_empty() {
+        format!("成功生成 {} 个CSR（含重试）", total)
+    } else {
+        format!(
+            "成功生成 {} 个CSR，{} 个失败（含重试）",
+            total,
+            remaining.len()
+        )
+    };
+    tx.execute(
+        "UPDATE job_history SET total = ?1, failed = ?2, message = ?3 WHERE id = ?4",
+        params![total, remaining.len(), message, id],
+    )?;
+    tx.execute("DELETE FROM job_failures WHERE job_id = ?1", params![id])?;
+    for failure in &remaining {
+        tx.execute(
+            "INSERT INTO job_failures (job_id, item_index, cn, error) VALUES (?1, ?2, ?3, ?4)",
+            params![id, failure.index, failure.cn, failure.error],
+        )?;
+    }
+    Ok((serde_json::from_str(&params)?, remaining))
+}
+
+/// 原任务开启了错误报告时按仍失败的条目重写，全部重试成功时删除
+fn rewrite_error_report(params: &BatchRequest, remaining: &[JobFailure]) -> Result<()> {
+    if !params.error_report {
+        return Ok(());
+    }
+    let path = derived_csv_path(&params.output_path, "errors");
+    if remaining.is_empty() {
+        if Path::new(&path).exists() {
+            fs::remove_file(&path).map_err(|e| BatchError::io(&path, e))?;
+        }
+        return Ok(());
+    }
+    let failures: Vec<ItemFailure> = remaining
+        .iter()
+        .map(|failure| ItemFailure {
+            index: failure.index,
+            cn: failure.cn.clone(),
+            error: BatchError::Other {
+                message: failure.error.clone(),
+            },
+        })
+        .collect();
+    // 错误报告属于原任务，按仍失败的条目覆盖
+    write_error_report(&path, &failures, true)
+}
+
+/// 读取一行任务概要
+fn summary(row: &Row) -> rusqlite::Result<JobSummary> {
+    Ok(JobSummary {
+        id: row.get(0)?,
+        started_at: row.get(1)?,
+        finished_at: row.get(2)?,
+        duration_ms: row.get(3)?,
+        user: row.get(4)?,
+        state: row.get(5)?,
+        total: row.get(6)?,
+        failed: row.get(7)?,
+        output_path: row.get(8)?,
+        message: row.get(9)?,
+    })
+}
+
+/// 打开已有的任务历史数据库，文件不存在时返回None
+fn open_existing(path: &Path) -> Result<Option<Connection>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let conn = Connection::open(path).map_err(|e| BatchError::io(path, e))?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(Some(conn))
+}
+
+/// 按开始时间倒序列出任务概要，limit为0时返回全部
+pub fn list_jobs(path: &str, limit: usize) -> Result<Vec<JobSummary>, BatchError> {
+    list(Path::new(path), limit).map_err(BatchError::from)
+}
+
+fn list(path: &Path, limit: usize) -> Result<Vec<JobSummary>> {
+    let Some(conn) = open_existing(path)? else {
+        return Ok(Vec::new());
+    };
+    let limit = if limit == 0 { -1 } else { limit as i64 };
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM job_history ORDER BY started_at DESC, rowid DESC LIMIT ?1",
+        SUMMARY_COLUMNS
+    ))?;
+    let jobs = statement
+        .query_map(params![limit], summary)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(jobs)
+}
+
+/// 按任务ID查询任务详情
+pub fn get_job(path: &str, id: &str) -> Result<JobRecord, BatchError> {
+    get(Path::new(path), id)?
+        .ok_or_else(|| BatchError::parameter("id", format!("未找到任务 {}", id)))
+}
+
+fn get(path: &Path, id: &str) -> Result<Option<JobRecord>> {
+    let Some(conn) = open_existing(path)? else {
+        return Ok(None);
+    };
+    let row = conn
+        .query_row(
+            &format!(
+                "SELECT {}, params FROM job_history WHERE id = ?1",
+                SUMMARY_COLUMNS
+            ),
+            params![id],
+            |row| Ok((summary(row)?, row.get::<_, String>(10)?)),
+        )
+        .optional()?;
+    let Some((summary, params)) = row else {
+        return Ok(None);
+    };
+    Ok(Some(JobRecord {
+        summary,
+        params: serde_json::from_str(&params)?,
+        failures: failures(&conn, id)?,
+    }))
+}
+
+/// 任务的失败条目，按序号排列
+fn failures(conn: &Connection, id: &str) -> Result<Vec<JobFailure>> {
+    let failures = conn
+        .prepare(
+            "SELECT item_index, cn, error FROM job_failures WHERE job_id = ?1 ORDER BY item_index",
+        )?
+        .query_map(params![id], |row| {
+            Ok(JobFailure {
+                index: row.get(0)?,
+                cn: row.get(1)?,
+                error: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(failures)
+}
+
+/// 按任务历史准备重新生成任务中失败的条目：以原参数追加到原输出CSV，已生成的通用名称被跳过，
+/// 因此只生成失败的条目（{INDEX}按重试的条目计数）；随机通用名称（uuid/hex）按失败数量重新生成。
+/// 原参数不含机密参数，机密参数和新任务ID取自secrets
+pub fn retry_request(
+    path: &str,
+    id: &str,
+    secrets: &BatchRequest,
+) -> Result<BatchRequest, BatchError> {
+    let job = get_job(path, id)?;
+    let reject = |message: String| Err(BatchError::parameter("id", message));
+    if !job.params.retry_of.is_empty() {
+        return reject(format!(
+            "重试任务不能再次重试，请对原任务 {} 重试",
+            job.params.retry_of
+        ));
+    }
+    if job.summary.state != "completed" {
+        return reject("仅已完成的任务可重试失败条目，取消或暂停的任务请从断点继续".to_string());
+    }
+    if job.failures.is_empty() {
+        return reject(format!("任务 {} 没有失败的条目", id));
+    }
+    let params = &job.params;
+    if !matches!(params.output_mode.as_str(), "" | "csv" | "both") || params.max_rows_per_file > 0 {
+        return reject("仅输出到单个CSV文件（csv/both）的任务可重试失败条目".to_string());
+    }
+    if !Path::new(&params.output_path).exists() {
+        return Err(BatchError::io(&params.output_path, "原输出文件不存在"));
+    }
+
+    let mut request = job.params.with_secrets_of(secrets);
+    if matches!(request.cn_mode.as_str(), "uuid" | "hex") {
+        request.count = job.failures.len();
+    }
+    request.append = true;
+    request.continue_on_error = true;
+    request.error_report = false;
+    request.checkpoint_path.clear();
+    request.retry_of = id.to_string();
+    request.job_id.clone_from(&secrets.job_id);
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::ItemFailure;
+
+    #[test]
+    fn test_job_history() {
+        let path = std::env::temp_dir().join("csr_batch_job_history_test/job_history.db");
+        std::fs::remove_file(&path).ok();
+        let path_str = path.to_string_lossy().to_string();
+        assert!(list_jobs(&path_str, 0).unwrap().is_empty());
+
+        let request = |job_id: &str| BatchRequest {
+            cn_range: "YDL0001-YDL0003".to_string(),
+            key_passphrase: "secret".to_string(),
+            output_path: "out.csv".to_string(),
+            job_id: job_id.to_string(),
+            ..Default::default()
+        };
+        let report = BatchReport {
+            success: true,
+            total: 2,
+            failed: 1,
+            failures: vec![ItemFailure {
+                index: 1,
+                cn: "YDL0002".to_string(),
+                error: BatchError::NoItems,
+            }],
+            output_path: "out.csv".to_string(),
+            message: String::new(),
+            error_report_path: String::new(),
+            manifest_path: String::new(),
+            warnings: Vec::new(),
+            cancelled: false,
+        };
+        JobLogEntry::start(&request("job-1"))
+            .finish(&path_str, &Ok(report))
+            .unwrap();
+        JobLogEntry::start(&request("job-2"))
+            .finish(&path_str, &Err(BatchError::NoItems))
+            .unwrap();
+
+        let jobs = list_jobs(&path_str, 0).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(list_jobs(&path_str, 1).unwrap().len(), 1);
+        let failed = jobs.iter().find(|job| job.id == "job-2").unwrap();
+        assert_eq!(failed.state, "failed");
+        assert_eq!(failed.message, "没有需要生成的条目");
+
+        let job = get_job(&path_str, "job-1").unwrap();
+        assert_eq!(job.summary.state, "completed");
+        assert_eq!((job.summary.total, job.summary.failed), (2, 1));
+        assert_eq!(job.params.cn_range, "YDL0001-YDL0003");
+        assert!(job.params.key_passphrase.is_empty());
+        assert_eq!(job.failures[0].cn, "YDL0002");
+        assert!(get_job(&path_str, "missing").is_err());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_retry_failed() {
+        use crate::csr_generator::{run_batch, CancelToken};
+
+        let dir = std::env::temp_dir().join("csr_batch_retry_test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let history = dir.join("job_history.db").to_string_lossy().to_string();
+        let output = dir.join("out.csv").to_string_lossy().to_string();
+        let errors = derived_csv_path(&output, "errors");
+        let request = BatchRequest {
+            cn_range: "YDL0001-YDL0003".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: output.clone(),
+            continue_on_error: true,
+            error_report: true,
+            job_id: "job-1".to_string(),
+            ..Default::default()
+        };
+
+        // 模拟YDL0002生成失败：排除后生成，并按失败写入任务历史和错误报告
+        let failed = BatchRequest {
+            exclude: "YDL0002".to_string(),
+            ..request.clone()
+        };
+        let mut report = run_batch(failed, &CancelToken::default(), &|_| {}).unwrap();
+        report.failures.push(ItemFailure {
+            index: 1,
+            cn: "YDL0002".to_string(),
+            error: BatchError::NoItems,
+        });
+        report.failed = 1;
+        write_error_report(&errors, &report.failures, false).unwrap();
+        JobLogEntry::start(&request)
+            .finish(&history, &Ok(report))
+            .unwrap();
+
+        let secrets = BatchRequest {
+            job_id: "job-2".to_string(),
+            ..Default::default()
+        };
+        let retry = retry_request(&history, "job-1", &secrets).unwrap();
+        assert!(retry.append && !retry.error_report);
+        assert_eq!(
+            (retry.job_id.as_str(), retry.retry_of.as_str()),
+            ("job-2", "job-1")
+        );
+        let entry = JobLogEntry::start(&retry);
+        let result = run_batch(retry, &CancelToken::default(), &|_| {});
+        assert_eq!(result.as_ref().unwrap().total, 1);
+        entry.finish(&history, &result).unwrap();
+
+        let job = get_job(&history, "job-1").unwrap();
+        assert_eq!((job.summary.total, job.summary.failed), (3, 0));
+        assert!(job.failures.is_empty());
+        assert!(!Path::new(&errors).exists());
+        let rows = csv::Reader::from_path(&output).unwrap().records().count();
+        assert_eq!(rows, 3);
+        // 没有失败条目的任务和重试任务不能再重试
+        assert!(retry_request(&history, "job-1", &secrets).is_err());
+        assert!(retry_request(&history, "job-2", &secrets).is_err());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,178 @@
+//! 密钥池模块
+//! 在用户填写表单时于后台线程中预先生成所配置类型的密钥对，批量生成时优先从池中取用，
+//! 以缩短RSA_4096等耗时密钥类型的生成时间
+//!
+//! 密钥池只保存在内存中，每个密钥最多被取用一次；密钥类型变更时清空已生成的密钥，
+//! 正在运行的生成线程在完成当前密钥后退出
+
+use openssl::pkey::{PKey, Private};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use crate::csr_generator::{check_ml_dsa_enabled, generate_key_pair, KeyType};
+use crate::error::BatchError;
+
+/// 密钥池最多预生成的密钥数量
+const MAX_POOL_SIZE: usize = 1024;
+
+/// 密钥池状态
+#[derive(Debug, Default)]
+struct PoolState {
+    /// 当前预生成的密钥类型
+    key_type: Option<KeyType>,
+    /// 已生成、尚未取用的密钥
+    keys: Vec<PKey<Private>>,
+    /// 还需生成的密钥总数（含已生成的），取用一个密钥时减一
+    target: usize,
+    /// 正在生成中的密钥数量
+    pending: usize,
+    /// 正在运行的生成线程数量
+    workers: usize,
+    /// 密钥类型变更时递增，旧的生成线程据此退出
+    generation: u64,
+}
+
+/// 预生成密钥池，可在多个线程间共享
+#[derive(Debug, Clone, Default)]
+pub struct KeyPool(Arc<Mutex<PoolState>>);
+
+impl KeyPool {
+    fn state(&self) -> MutexGuard<'_, PoolState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 在后台预生成指定类型的密钥，直至池中共有count个（最多1024个），立即返回
+    /// 密钥类型与池中不同时先清空已生成的密钥
+    pub fn prefill(&self, key_type: &str, count: usize) -> Result<(), BatchError> {
+        let key_type = KeyType::from_str(key_type)?;
+        check_ml_dsa_enabled(key_type, "key_type")?;
+
+        let mut state = self.state();
+        if state.key_type != Some(key_type) {
+            state.key_type = Some(key_type);
+            state.keys.clear();
+            state.pending = 0;
+            state.workers = 0;
+            state.generation += 1;
+        }
+        state.target = count.min(MAX_POOL_SIZE);
+
+        // 生成线程数不超过CPU核数和尚需生成的密钥数
+        let parallelism = thread::available_parallelism().map_or(1, |n| n.get());
+        let needed = state
+            .target
+            .saturating_sub(state.keys.len() + state.pending);
+        let spawn = needed.min(parallelism).saturating_sub(state.workers);
+        for _ in 0..spawn {
+            state.workers += 1;
+            let (pool, generation) = (self.clone(), state.generation);
+            thread::spawn(move || pool.work(key_type, generation));
+        }
+        Ok(())
+    }
+
+    /// 生成线程：循环生成密钥，直至达到目标数量或密钥类型变更
+    fn work(&self, key_type: KeyType, generation: u64) {
+        loop {
+            {
+                let mut state = self.state();
+                if state.generation != generation {
+                    return;
+                }
+                if state.keys.len() + state.pending >= state.target {
+                    state.workers -= 1;
+                    return;
+                }
+                state.pending += 1;
+            }
+
+            let key = generate_key_pair(key_type);
+
+            let mut state = self.state();
+            if state.generation != generation {
+                return;
+            }
+            state.pending -= 1;
+            match key {
+                Ok(key) => state.keys.push(key),
+                Err(_) => {
+                    // 生成失败时停止预生成，批量生成会在取用不到时自行生成密钥
+                    state.target = state.keys.len();
+                    state.workers -= 1;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 取出一个指定类型的预生成密钥，池中没有该类型的密钥时返回None
+    pub(crate) fn take(&self, key_type: KeyType) -> Option<PKey<Private>> {
+        let mut state = self.state();
+        if state.key_type != Some(key_type) {
+            return None;
+        }
+        let key = state.keys.pop()?;
+        state.target = state.target.saturating_sub(1);
+        Some(key)
+    }
+
+    /// 池中已生成、尚未取用的密钥数量
+    pub fn available(&self) -> usize {
+        self.state().keys.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    use crate::csr_generator::{BatchRequest, CancelToken};
+
+    /// 等待密钥池生成指定数量的密钥
+    fn wait_for(pool: &KeyPool, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pool.available() < count && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pool.available(), count);
+    }
+
+    #[test]
+    fn test_prefill_and_take() {
+        let pool = KeyPool::default();
+        pool.prefill("EC_P256", 4).unwrap();
+        wait_for(&pool, 4);
+
+        // 类型不同时不取用；取出的密钥不会再次出现
+        assert!(pool.take(KeyType::EcP384).is_none());
+        let key = pool.take(KeyType::EcP256).unwrap();
+        assert_eq!(pool.available(), 3);
+        assert!(std::iter::from_fn(|| pool.take(KeyType::EcP256)).all(|k| !k.public_eq(&key)));
+
+        // 变更类型时清空
+        pool.prefill("ED25519", 0).unwrap();
+        assert_eq!(pool.available(), 0);
+        assert!(pool.prefill("RSA_1024", 1).is_err());
+    }
+
+    #[test]
+    fn test_results_drain_pool() {
+        let pool = KeyPool::default();
+        pool.prefill("EC_P384", 2).unwrap();
+        wait_for(&pool, 2);
+
+        let request = BatchRequest {
+            cn_range: "YDL0001-YDL0003".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P384".to_string(),
+            sign_hash_alg: "SHA256".to_string(),
+            ..Default::default()
+        };
+        let cancel = CancelToken::default();
+        let results = request.results(&cancel).unwrap().key_pool(&pool);
+        // 池中密钥用尽后其余条目自行生成
+        assert_eq!(results.filter(Result::is_ok).count(), 3);
+        assert_eq!(pool.available(), 0);
+    }
+}
@@ -0,0 +1,209 @@
+//! 弱密钥筛查模块
+//! 写出结果前检查CSR中的RSA公钥是否属于已知的弱密钥，命中的条目按生成失败处理：
+//! - ROCA（CVE-2017-15361）：Infineon芯片生成的模数对一组小素数的余数都落在65537生成的子群中，
+//!   TPM、智能卡等外部密钥存储生成的密钥尤其需要检查
+//! - 小素因子：模数能被小于65536的素数整除
+//! - 黑名单：Debian OpenSSL漏洞（CVE-2008-0166）等已泄露的模数，使用openssl-blacklist格式的列表文件，
+//!   主要用于筛查导入的已有私钥
+//!
+//! 非RSA密钥不做检查
+
+use anyhow::{anyhow, Result};
+use openssl::bn::BigNumRef;
+use openssl::hash::{hash, MessageDigest};
+use std::collections::HashSet;
+use std::fs;
+
+use crate::ca::parse_csr;
+use crate::csr_generator::BatchRequest;
+use crate::error::BatchError;
+
+/// ROCA指纹检查使用的素数（与原始检测工具相同）
+const ROCA_PRIMES: [u32; 38] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167,
+];
+
+/// ROCA密钥的生成元
+const ROCA_GENERATOR: u64 = 65537;
+
+/// 小素因子检查的上限
+const SMALL_FACTOR_LIMIT: u32 = 65536;
+
+/// 弱密钥筛查器，每个批次构造一次
+#[derive(Debug)]
+pub(crate) struct KeyScreen {
+    /// 小于SMALL_FACTOR_LIMIT的素数
+    small_primes: Vec<u32>,
+    /// 每个ROCA素数p对应的子群：第r项表示余数r是否在65537生成的子群中
+    roca_subgroups: Vec<(u32, Vec<bool>)>,
+    /// 黑名单中的模数指纹（小写十六进制，SHA-1的后20位）
+    blocklist: HashSet<String>,
+}
+
+impl KeyScreen {
+    /// 按请求构造筛查器，未开启screen_weak_keys时返回None
+    pub(crate) fn from_request(request: &BatchRequest) -> Result<Option<Self>> {
+        if !request.screen_weak_keys {
+            if !request.weak_key_blocklist.is_empty() {
+                let message = "须同时开启screen_weak_keys";
+                return Err(BatchError::parameter("weak_key_blocklist", message).into());
+            }
+            return Ok(None);
+        }
+        let blocklist = if request.weak_key_blocklist.is_empty() {
+            HashSet::new()
+        } else {
+            read_blocklist(&request.weak_key_blocklist)?
+        };
+        Ok(Some(Self::new(blocklist)))
+    }
+
+    fn new(blocklist: HashSet<String>) -> Self {
+        let roca_subgroups = ROCA_PRIMES
+            .iter()
+            .map(|&p| {
+                let mut subgroup = vec![false; p as usize];
+                let mut residue = 1u64;
+                while !subgroup[residue as usize] {
+                    subgroup[residue as usize] = true;
+                    residue = residue * ROCA_GENERATOR % u64::from(p);
+                }
+                (p, subgroup)
+            })
+            .collect();
+        Self {
+            small_primes: small_primes(SMALL_FACTOR_LIMIT),
+            roca_subgroups,
+            blocklist,
+        }
+    }
+
+    /// 检查CSR中的公钥，属于弱密钥时返回错误
+    pub(crate) fn check(&self, csr_pem: &str) -> Result<()> {
+        let pubkey = parse_csr(csr_pem)?.public_key()?;
+        let Ok(rsa) = pubkey.rsa() else {
+            return Ok(());
+        };
+        self.check_modulus(rsa.n())
+    }
+
+    fn check_modulus(&self, modulus: &BigNumRef) -> Result<()> {
+        if self.is_roca(modulus)? {
+            return Err(anyhow!(
+                "弱密钥: RSA模数具有ROCA漏洞（CVE-2017-15361）的特征"
+            ));
+        }
+        for &p in &self.small_primes {
+            if modulus.mod_word(p)? == 0 {
+                return Err(anyhow!("弱密钥: RSA模数含小素因子 {}", p));
+            }
+        }
+        if !self.blocklist.is_empty() && self.blocklist.contains(&modulus_fingerprint(modulus)?) {
+            return Err(anyhow!("弱密钥: RSA模数在已泄露密钥黑名单中"));
+        }
+        Ok(())
+    }
+
+    /// 模数对每个ROCA素数的余数是否都在65537生成的子群中
+    fn is_roca(&self, modulus: &BigNumRef) -> Result<bool> {
+        for (p, subgroup) in &self.roca_subgroups {
+            if !subgroup[modulus.mod_word(*p)? as usize] {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// 小于limit的素数（埃氏筛）
+fn small_primes(limit: u32) -> Vec<u32> {
+    let mut composite = vec![false; limit as usize];
+    let mut primes = Vec::new();
+    for n in 2..limit {
+        if composite[n as usize] {
+            continue;
+        }
+        primes.push(n);
+        for multiple in (n as usize * n as usize..limit as usize).step_by(n as usize) {
+            composite[multiple] = true;
+        }
+    }
+    primes
+}
+
+/// openssl-blacklist格式的模数指纹：`Modulus=<大写十六进制>\n` 的SHA-1的后20位
+fn modulus_fingerprint(modulus: &BigNumRef) -> Result<String> {
+    let line = format!("Modulus={}\n", modulus.to_hex_str()?);
+    let digest = hash(MessageDigest::sha1(), line.as_bytes())?;
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(hex[20..].to_string())
+}
+
+/// 读取黑名单文件：每行一个指纹（20位或完整40位十六进制），`#` 开头为注释
+fn read_blocklist(path: &str) -> Result<HashSet<String>> {
+    let content = fs::read_to_string(path).map_err(|e| BatchError::io(path, e))?;
+    let mut blocklist = HashSet::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !matches!(line.len(), 20 | 40) || !line.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let message = format!("第{}行不是有效的模数指纹: {}", index + 1, line);
+            return Err(BatchError::parameter("weak_key_blocklist", message).into());
+        }
+        blocklist.insert(line[line.len() - 20..].to_ascii_lowercase());
+    }
+    Ok(blocklist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::bn::{BigNum, BigNumContext};
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::{X509NameBuilder, X509Req};
+
+    fn csr() -> (String, BigNum) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let modulus = rsa.n().to_owned().unwrap();
+        let key = PKey::from_rsa(rsa).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "YDL0001").unwrap();
+        let mut req = X509Req::builder().unwrap();
+        req.set_pubkey(&key).unwrap();
+        req.set_subject_name(&name.build()).unwrap();
+        req.sign(&key, MessageDigest::sha256()).unwrap();
+        let pem = String::from_utf8(req.build().to_pem().unwrap()).unwrap();
+        (pem, modulus)
+    }
+
+    #[test]
+    fn test_screen_weak_keys() {
+        let screen = KeyScreen::new(HashSet::new());
+        let (pem, modulus) = csr();
+        screen.check(&pem).unwrap();
+
+        // 65537的幂对任意素数的余数都在65537生成的子群中，具有ROCA特征
+        let mut ctx = BigNumContext::new().unwrap();
+        let mut roca = BigNum::new().unwrap();
+        let generator = BigNum::from_u32(ROCA_GENERATOR as u32).unwrap();
+        roca.exp(&generator, &BigNum::from_u32(128).unwrap(), &mut ctx)
+            .unwrap();
+        let err = screen.check_modulus(&roca).unwrap_err();
+        assert!(err.to_string().contains("ROCA"));
+
+        let mut factored = BigNum::new().unwrap();
+        factored
+            .checked_mul(&modulus, &BigNum::from_u32(65521).unwrap(), &mut ctx)
+            .unwrap();
+        let err = screen.check_modulus(&factored).unwrap_err();
+        assert!(err.to_string().ends_with("小素因子 65521"));
+
+        let blocklisted = KeyScreen::new(HashSet::from([modulus_fingerprint(&modulus).unwrap()]));
+        let err = blocklisted.check(&pem).unwrap_err();
+        assert!(err.to_string().contains("黑名单"));
+    }
+}
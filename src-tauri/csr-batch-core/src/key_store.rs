@@ -0,0 +1,75 @@
+//! 密钥存储模块
+//! 默认私钥以PEM格式输出；也可在操作系统的密钥存储中生成不可导出的密钥，此时CSV中仅包含CSR和密钥引用
+//!
+//! - Windows: CNG密钥存储（见 `cng` 模块）
+//! - macOS: 钥匙串或安全隔区（见 `keychain` 模块）
+//! - Linux/Windows: TPM 2.0（见 `tpm` 模块）
+
+use anyhow::Result;
+
+use crate::error::BatchError;
+
+/// 密钥存储位置
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyStore {
+    /// 私钥以PEM格式输出（默认）
+    #[default]
+    File,
+    /// 当前用户的CNG密钥存储（Windows）
+    CngUser,
+    /// 本地计算机的CNG密钥存储（Windows，需要管理员权限）
+    CngMachine,
+    /// 登录钥匙串（macOS）
+    Keychain,
+    /// 安全隔区（macOS，仅EC_P-256）
+    SecureEnclave,
+    /// TPM 2.0（Linux/Windows，通过tpm2-tools）
+    Tpm,
+}
+
+impl KeyStore {
+    /// 从字符串解析密钥存储位置，空字符串为file；当前平台不支持时返回错误
+    pub(crate) fn from_str(s: &str) -> Result<Self> {
+        let (store, available, platform) = match s {
+            "" | "file" => return Ok(KeyStore::File),
+            "cng_user" => (
+                KeyStore::CngUser,
+                cfg!(windows),
+                "CNG密钥存储仅在Windows上可用",
+            ),
+            "cng_machine" => (
+                KeyStore::CngMachine,
+                cfg!(windows),
+                "CNG密钥存储仅在Windows上可用",
+            ),
+            "keychain" => (
+                KeyStore::Keychain,
+                cfg!(target_os = "macos"),
+                "钥匙串仅在macOS上可用",
+            ),
+            "secure_enclave" => (
+                KeyStore::SecureEnclave,
+                cfg!(target_os = "macos"),
+                "安全隔区仅在macOS上可用",
+            ),
+            "tpm" => (
+                KeyStore::Tpm,
+                cfg!(any(target_os = "linux", windows)),
+                "TPM仅在Linux和Windows上可用",
+            ),
+            _ => {
+                let message = format!("不支持的密钥存储: {}", s);
+                return Err(BatchError::parameter("key_store", message).into());
+            }
+        };
+        if !available {
+            return Err(BatchError::parameter("key_store", platform).into());
+        }
+        Ok(store)
+    }
+
+    /// 私钥是否保存在操作系统的密钥存储中（不输出私钥PEM）
+    pub(crate) fn is_external(&self) -> bool {
+        !matches!(self, KeyStore::File)
+    }
+}
@@ -0,0 +1,194 @@
+//! macOS钥匙串模块
+//! 通过Security.framework在登录钥匙串或安全隔区(Secure Enclave)中生成不可导出的密钥对，
+//! CSR由OpenSSL构建、钥匙串中的私钥签名，私钥始终不落盘；CSV中以钥匙串标签引用密钥
+//!
+//! 安全隔区仅支持EC_P-256，且要求应用已签名并具有钥匙串访问组权限
+
+use anyhow::Result;
+
+use crate::csr_generator::{BatchRequest, CsrOptions, KeyType};
+use crate::error::BatchError;
+use crate::signature::SignatureScheme;
+use crate::subject::DnAttribute;
+
+/// 密钥类型对应的钥匙串密钥参数（是否为RSA、密钥长度），不支持的类型返回None
+fn key_parameters(key_type: KeyType, secure_enclave: bool) -> Option<(bool, u32)> {
+    match key_type {
+        KeyType::EcP256 => Some((false, 256)),
+        _ if secure_enclave => None,
+        KeyType::EcP384 => Some((false, 384)),
+        KeyType::EcP521 => Some((false, 521)),
+        KeyType::Rsa2048 => Some((true, 2048)),
+        KeyType::Rsa3072 => Some((true, 3072)),
+        KeyType::Rsa4096 => Some((true, 4096)),
+        _ => None,
+    }
+}
+
+/// 校验生成参数是否可用于钥匙串
+pub(crate) fn check_request(
+    request: &BatchRequest,
+    key_type: KeyType,
+    secure_enclave: bool,
+    signature_scheme: SignatureScheme,
+) -> Result<()> {
+    let unsupported = if key_parameters(key_type, secure_enclave).is_none() {
+        let store = if secure_enclave {
+            "安全隔区"
+        } else {
+            "钥匙串"
+        };
+        let message = format!("{}不支持密钥类型 {}", store, key_type.display_name());
+        ("key_type", message)
+    } else if !request.pq_key_type.is_empty() {
+        ("pq_key_type", "钥匙串不支持混合模式".to_string())
+    } else if signature_scheme != SignatureScheme::Pkcs1 {
+        (
+            "signature_scheme",
+            "钥匙串仅支持PKCS#1 v1.5签名".to_string(),
+        )
+    } else {
+        return Ok(());
+    };
+    Err(BatchError::parameter(unsupported.0, unsupported.1).into())
+}
+
+/// 在钥匙串（或安全隔区）中生成密钥对和CSR，返回CSR PEM和钥匙串标签
+#[cfg(target_os = "macos")]
+pub(crate) fn generate_csr(
+    subject: &[DnAttribute],
+    cn: &str,
+    key_type: KeyType,
+    secure_enclave: bool,
+    options: &CsrOptions,
+) -> Result<(String, String)> {
+    use anyhow::anyhow;
+    use openssl::rand::rand_bytes;
+    use security_framework::item::Location;
+    use security_framework::key::{Algorithm, GenerateKeyOptions, SecKey, Token};
+
+    use crate::csr_generator::{add_challenge_password, build_req};
+    use crate::output::sanitize_file_name;
+    use crate::signature::sign_req_external;
+
+    let (rsa, bits) = key_parameters(key_type, secure_enclave)
+        .ok_or_else(|| anyhow!("钥匙串不支持密钥类型 {}", key_type.display_name()))?;
+
+    // 钥匙串标签：通用名称加随机后缀，避免与已有密钥重名
+    let mut suffix = [0u8; 8];
+    rand_bytes(&mut suffix)?;
+    let suffix: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+    let label = format!("csr-batch-{}-{}", sanitize_file_name(cn), suffix);
+
+    let mut key_options = GenerateKeyOptions::default();
+    key_options
+        .set_key_type(if rsa {
+            security_framework::key::KeyType::rsa()
+        } else {
+            security_framework::key::KeyType::ec_sec_prime_random()
+        })
+        .set_size_in_bits(bits)
+        .set_label(label.as_str());
+    if secure_enclave {
+        key_options
+            .set_token(Token::SecureEnclave)
+            .set_location(Location::DataProtectionKeychain);
+    } else {
+        key_options.set_location(Location::DefaultFileKeychain);
+    }
+    let key = SecKey::new(&key_options).map_err(|e| anyhow!("钥匙串生成密钥失败: {}", e))?;
+
+    let public_key = key
+        .public_key()
+        .and_then(|public_key| public_key.external_representation())
+        .ok_or_else(|| anyhow!("无法读取钥匙串密钥 {} 的公钥", label))?;
+    let pkey = public_key_from_external(rsa, bits, public_key.bytes())?;
+
+    let req = build_req(subject, &pkey, &options.extensions)?;
+    if !options.challenge_password.is_empty() {
+        add_challenge_password(&req, options.challenge_password)?;
+    }
+    let algorithm = match (rsa, options.sign_hash_alg) {
+        (true, "SHA1") => Algorithm::RSASignatureMessagePKCS1v15SHA1,
+        (true, "SHA384") => Algorithm::RSASignatureMessagePKCS1v15SHA384,
+        (true, "SHA512") => Algorithm::RSASignatureMessagePKCS1v15SHA512,
+        (true, _) => Algorithm::RSASignatureMessagePKCS1v15SHA256,
+        (false, "SHA1") => Algorithm::ECDSASignatureMessageX962SHA1,
+        (false, "SHA384") => Algorithm::ECDSASignatureMessageX962SHA384,
+        (false, "SHA512") => Algorithm::ECDSASignatureMessageX962SHA512,
+        (false, _) => Algorithm::ECDSASignatureMessageX962SHA256,
+    };
+    let req = sign_req_external(&req, rsa, options.sign_hash_alg, |tbs| {
+        key.create_signature(algorithm, tbs)
+            .map_err(|e| anyhow!("钥匙串签名失败: {}", e))
+    })?;
+
+    Ok((String::from_utf8(req.to_pem()?)?, label))
+}
+
+/// 非macOS平台不可用（密钥存储解析时已拒绝）
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn generate_csr(
+    _subject: &[DnAttribute],
+    _cn: &str,
+    _key_type: KeyType,
+    _secure_enclave: bool,
+    _options: &CsrOptions,
+) -> Result<(String, String)> {
+    Err(BatchError::parameter("key_store", "钥匙串仅在macOS上可用").into())
+}
+
+/// 由钥匙串导出的公钥构建OpenSSL公钥：RSA为PKCS#1 DER，EC为未压缩点 04||X||Y
+#[cfg(any(target_os = "macos", test))]
+fn public_key_from_external(
+    rsa: bool,
+    bits: u32,
+    bytes: &[u8],
+) -> Result<openssl::pkey::PKey<openssl::pkey::Public>> {
+    use openssl::bn::BigNumContext;
+    use openssl::ec::{EcGroup, EcKey, EcPoint};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+
+    if rsa {
+        return Ok(PKey::from_rsa(Rsa::public_key_from_der_pkcs1(bytes)?)?);
+    }
+    let nid = match bits {
+        384 => Nid::SECP384R1,
+        521 => Nid::SECP521R1,
+        _ => Nid::X9_62_PRIME256V1,
+    };
+    let group = EcGroup::from_curve_name(nid)?;
+    let mut ctx = BigNumContext::new()?;
+    let point = EcPoint::from_bytes(&group, bytes, &mut ctx)?;
+    Ok(PKey::from_ec_key(EcKey::from_public_key(&group, &point)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::bn::BigNumContext;
+    use openssl::ec::{EcGroup, EcKey, PointConversionForm};
+    use openssl::nid::Nid;
+
+    #[test]
+    fn test_public_key_from_external() {
+        let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let bytes = ec_key
+            .public_key()
+            .to_bytes(
+                &group,
+                PointConversionForm::UNCOMPRESSED,
+                &mut BigNumContext::new().unwrap(),
+            )
+            .unwrap();
+        let pkey = public_key_from_external(false, 384, &bytes).unwrap();
+        assert!(pkey.public_eq(&openssl::pkey::PKey::from_ec_key(ec_key).unwrap()));
+
+        assert_eq!(key_parameters(KeyType::EcP256, true), Some((false, 256)));
+        assert_eq!(key_parameters(KeyType::EcP384, true), None);
+        assert_eq!(key_parameters(KeyType::Rsa2048, false), Some((true, 2048)));
+    }
+}
@@ -0,0 +1,121 @@
+//! 批量CSR生成核心库
+//! 密钥对生成（可由密钥池预生成）、CSR创建、批量输出、证书续期、内置CA签发和PKCS#12导出，不依赖Tauri，
+//! 可被桌面应用、命令行工具或其他服务直接使用
+//!
+//! 公共接口返回可序列化的 [`BatchError`]，错误中带有出错的通用名称或记录序号
+//!
+//! 支持的密钥类型：RSA_2048/3072/4096, EC_P-256/384/521, EC_secp256k1, EC_brainpoolP256r1/384r1/512r1,
+//! ED25519, ED448, SM2；启用 `ml-dsa` 特性后支持后量子签名 ML_DSA_65/87（需要OpenSSL 3.5及以上）
+//!
+//! ```no_run
+//! use csr_batch_core::{BatchRequest, CancelToken};
+//!
+//! let request = BatchRequest {
+//!     cn_range: "YDL0001-YDL0010".to_string(),
+//!     subject_template: "CN=[{CN}]".to_string(),
+//!     key_type: "EC_P256".to_string(),
+//!     ..Default::default()
+//! };
+//! let cancel = CancelToken::default();
+//! for result in request.results(&cancel)? {
+//!     let result = result?;
+//!     println!("{}\n{}", result.cn, result.csr_pem);
+//! }
+//! # Ok::<(), csr_batch_core::BatchError>(())
+//! ```
+
+mod acme;
+mod adcs;
+mod audit_log;
+mod bundle;
+mod ca;
+mod cert_import;
+mod checkpoint;
+mod cmp;
+mod cng;
+mod crl;
+mod csr_generator;
+mod deterministic;
+mod ejbca;
+mod error;
+mod est;
+mod expiry;
+mod extensions;
+mod fingerprint;
+mod history;
+mod hook;
+mod input;
+mod inspect;
+mod job_db;
+mod job_log;
+mod key_pool;
+mod key_screen;
+mod key_store;
+mod keychain;
+mod lint;
+mod mac_range;
+mod manifest;
+#[cfg(feature = "ml-dsa")]
+mod ml_dsa;
+#[cfg(feature = "server")]
+mod ocsp;
+mod output;
+mod pca;
+mod piv;
+mod policy;
+mod preset;
+mod preview;
+mod profile;
+mod random_cn;
+mod renew;
+mod san;
+mod scep;
+#[cfg(feature = "server")]
+mod server;
+mod signature;
+mod subject;
+mod template;
+mod tpm;
+mod validity;
+mod vault;
+mod verify;
+mod webhook;
+
+pub use acme::{issue_acme, issue_acme_with, AcmeParams, AcmeResult, DnsProvider};
+pub use adcs::{adcs_enroll, AdcsParams, AdcsResult};
+pub use audit_log::{verify_audit_log, AuditLogReport};
+pub use bundle::{export_pkcs12_bundles, Pkcs12Params, Pkcs12Result};
+pub use ca::{sign_batch, SignParams, SignResult};
+pub use cert_import::{import_certificates, ImportParams, ImportResult};
+pub use checkpoint::{list_checkpoints, load_checkpoint, resume_checkpoint, Checkpoint};
+pub use cmp::{cmp_enroll, CmpParams, CmpResult};
+pub use crl::{generate_crl, list_revocations, revoke, CrlParams, CrlResult, Revocation};
+pub use csr_generator::{
+    run_batch, run_batch_with_key_pool, BatchReport, BatchRequest, CancelToken, CsrResult,
+    CsrResults, ItemFailure, PqCsr, ProgressEvent,
+};
+pub use ejbca::{ejbca_enroll, EjbcaParams, EjbcaResult};
+pub use error::BatchError;
+pub use est::{est_enroll, EstParams, EstResult};
+pub use expiry::{list_expiring, track_certificates, ExpiringCertificate};
+pub use extensions::CustomExtension;
+pub use hook::CompletionHooks;
+pub use inspect::{CsrExtension, CsrInfo};
+pub use job_log::{
+    get_job, list_jobs, retry_request, JobFailure, JobLogEntry, JobRecord, JobSummary,
+};
+pub use key_pool::KeyPool;
+#[cfg(feature = "server")]
+pub use ocsp::{OcspConfig, OcspResponder};
+pub use pca::{issue_with_pca, PcaParams, PcaResult};
+pub use piv::{provision_piv_tokens, PivParams, PivResult};
+pub use preset::{list_presets, load_preset, save_preset, PresetInfo};
+pub use preview::CnPreview;
+pub use profile::Profile;
+pub use renew::{renew_certificates, RenewParams};
+pub use scep::{scep_enroll, ScepParams, ScepResult};
+#[cfg(feature = "server")]
+pub use server::{CsrServer, ServerConfig};
+pub use vault::{sign_with_vault, VaultParams, VaultResult};
+pub use verify::{verify_batch, VerifyFailure, VerifyParams, VerifyReport};
+pub use webhook::{webhook_enroll, WebhookParams, WebhookResult};
@@ -0,0 +1,276 @@
+//! 规则检查模块
+//! 参照RFC 5280和CA/B Forum基线要求，对生成的每个CSR做zlint式的规则检查，结果汇总为报告中的警告，不影响生成：
+//! - tls_missing_san: 请求了serverAuth（TLS服务器用途）但CSR中没有备用名称扩展
+//! - tls_key_algorithm: TLS服务器用途的密钥不是基线要求允许的RSA或NIST P曲线
+//! - weak_key: RSA小于2048位或EC小于256位
+//! - subject_nonprintable: Subject属性值含控制字符或首尾空白，或国家代码不是两位大写字母
+//!
+//! 有效期超过TLS服务器证书上限的检查见 [`TlsValidityCheck`]，同样汇总在规则检查的警告中
+
+use anyhow::Result;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::asn1::Asn1Object;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKeyRef, Public};
+use openssl::stack::Stack;
+use openssl::x509::X509Req;
+use openssl_sys as ffi;
+
+use crate::ca::parse_csr;
+use crate::csr_generator::CsrResult;
+use crate::inspect::{extension_object, request_extensions};
+use crate::subject::{from_x509_name, DnAttribute};
+use crate::validity::TlsValidityCheck;
+
+/// 检查规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    TlsMissingSan,
+    TlsKeyAlgorithm,
+    WeakKey,
+    SubjectNonPrintable,
+}
+
+impl Rule {
+    const ALL: [Rule; 4] = [
+        Rule::TlsMissingSan,
+        Rule::TlsKeyAlgorithm,
+        Rule::WeakKey,
+        Rule::SubjectNonPrintable,
+    ];
+
+    /// 规则标识
+    fn id(self) -> &'static str {
+        match self {
+            Rule::TlsMissingSan => "tls_missing_san",
+            Rule::TlsKeyAlgorithm => "tls_key_algorithm",
+            Rule::WeakKey => "weak_key",
+            Rule::SubjectNonPrintable => "subject_nonprintable",
+        }
+    }
+
+    /// 规则说明，用于警告文本
+    fn description(self) -> &'static str {
+        match self {
+            Rule::TlsMissingSan => "请求了TLS服务器用途但没有备用名称扩展，公共CA将拒绝签发",
+            Rule::TlsKeyAlgorithm => {
+                "TLS服务器用途的密钥须为RSA或EC P-256/384/521，公共CA将拒绝签发"
+            }
+            Rule::WeakKey => "密钥强度不足（RSA须至少2048位，EC须至少256位）",
+            Rule::SubjectNonPrintable => "Subject含控制字符、首尾空白或无效的国家代码",
+        }
+    }
+}
+
+/// 单条规则的命中情况
+#[derive(Debug, Default)]
+struct RuleHits {
+    count: usize,
+    /// 第一个命中的条目：(通用名称, 详情)
+    first: Option<(String, String)>,
+}
+
+/// 逐条检查生成结果并汇总命中的规则
+#[derive(Debug, Default)]
+pub(crate) struct Linter {
+    tls_validity: TlsValidityCheck,
+    hits: [RuleHits; Rule::ALL.len()],
+}
+
+impl Linter {
+    /// 检查单条结果；CSR无法解析时只检查有效期
+    pub(crate) fn check(&mut self, result: &CsrResult) {
+        self.tls_validity
+            .check(&result.cn, &result.not_before, &result.not_after);
+        let Ok(req) = parse_csr(&result.csr_pem) else {
+            return;
+        };
+        for (rule, detail) in lint_csr(&req) {
+            let hits = &mut self.hits[rule as usize];
+            hits.count += 1;
+            if hits.first.is_none() {
+                hits.first = Some((result.cn.clone(), detail));
+            }
+        }
+    }
+
+    /// 命中规则的警告，每条规则一项
+    pub(crate) fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self.tls_validity.warning().into_iter().collect();
+        for rule in Rule::ALL {
+            let hits = &self.hits[rule as usize];
+            if let Some((cn, detail)) = &hits.first {
+                warnings.push(format!(
+                    "[{}] {}个条目{}（如 {}: {}）",
+                    rule.id(),
+                    hits.count,
+                    rule.description(),
+                    cn,
+                    detail
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// 对单个CSR执行全部规则，返回命中的规则和详情
+fn lint_csr(req: &X509Req) -> Vec<(Rule, String)> {
+    let mut findings = Vec::new();
+    let tls_server = has_server_auth(req).unwrap_or(false);
+    if let Ok(pubkey) = req.public_key() {
+        if let Some(detail) = weak_key(&pubkey) {
+            findings.push((Rule::WeakKey, detail));
+        }
+        if tls_server && !is_tls_key_algorithm(&pubkey) {
+            findings.push((Rule::TlsKeyAlgorithm, key_description(&pubkey)));
+        }
+    }
+    if tls_server && !has_subject_alt_name(req).unwrap_or(false) {
+        findings.push((Rule::TlsMissingSan, "缺少subjectAltName".to_string()));
+    }
+    if let Ok(attributes) = from_x509_name(req.subject_name()) {
+        if let Some(detail) = nonprintable_attribute(&attributes) {
+            findings.push((Rule::SubjectNonPrintable, detail));
+        }
+    }
+    findings
+}
+
+/// 扩展密钥用途中是否包含serverAuth
+fn has_server_auth(req: &X509Req) -> Result<bool> {
+    for extension in &request_extensions(req)? {
+        if extension_object(extension).nid() != Nid::EXT_KEY_USAGE {
+            continue;
+        }
+        // SAFETY: 扩展密钥用途解码为ASN1_OBJECT的栈，所有权转移给Stack
+        let usages = unsafe {
+            let ptr = ffi::X509V3_EXT_d2i(extension.as_ptr());
+            if ptr.is_null() {
+                return Ok(false);
+            }
+            Stack::<Asn1Object>::from_ptr(ptr as *mut ffi::stack_st_ASN1_OBJECT)
+        };
+        return Ok(usages.iter().any(|usage| usage.nid() == Nid::SERVER_AUTH));
+    }
+    Ok(false)
+}
+
+/// 是否包含备用名称扩展
+fn has_subject_alt_name(req: &X509Req) -> Result<bool> {
+    Ok(request_extensions(req)?
+        .iter()
+        .any(|extension| extension_object(extension).nid() == Nid::SUBJECT_ALT_NAME))
+}
+
+/// 密钥的简要描述，如 `EC secp256k1`、`RSA 2048位`
+fn key_description(pubkey: &PKeyRef<Public>) -> String {
+    match pubkey.ec_key().ok().and_then(|ec| ec.group().curve_name()) {
+        Some(curve) => format!("EC {}", curve.short_name().unwrap_or("未知曲线")),
+        None => {
+            let name = Nid::from_raw(pubkey.id().as_raw())
+                .short_name()
+                .unwrap_or("未知算法");
+            format!("{} {}位", name, pubkey.bits())
+        }
+    }
+}
+
+/// 强度不足的密钥返回详情
+fn weak_key(pubkey: &PKeyRef<Public>) -> Option<String> {
+    let weak = match pubkey.id() {
+        Id::RSA => pubkey.bits() < 2048,
+        Id::EC => pubkey.bits() < 256,
+        _ => false,
+    };
+    weak.then(|| key_description(pubkey))
+}
+
+/// 基线要求允许的TLS密钥：RSA（模长为8的倍数）或NIST P-256/384/521
+fn is_tls_key_algorithm(pubkey: &PKeyRef<Public>) -> bool {
+    match pubkey.id() {
+        Id::RSA => pubkey.bits().is_multiple_of(8),
+        Id::EC => matches!(
+            pubkey.ec_key().ok().and_then(|ec| ec.group().curve_name()),
+            Some(Nid::X9_62_PRIME256V1 | Nid::SECP384R1 | Nid::SECP521R1)
+        ),
+        _ => false,
+    }
+}
+
+/// 第一个含控制字符、首尾空白或无效国家代码的属性
+fn nonprintable_attribute(attributes: &[DnAttribute]) -> Option<String> {
+    attributes.iter().find_map(|attribute| {
+        let value = &attribute.value;
+        let problem = if value.chars().any(char::is_control) {
+            "含控制字符"
+        } else if value.trim() != value {
+            "含首尾空白"
+        } else if attribute.attr == "C"
+            && !(value.len() == 2 && value.bytes().all(|b| b.is_ascii_uppercase()))
+        {
+            "须为两位大写字母"
+        } else {
+            return None;
+        };
+        Some(format!("{} {}", attribute.attr, problem))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::{requested_extensions, ExtensionOptions};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::x509::X509NameBuilder;
+
+    fn csr(curve: Nid, subject: &[(&str, &str)], extended_key_usage: &str) -> CsrResult {
+        let group = EcGroup::from_curve_name(curve).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        for (attr, value) in subject {
+            name.append_entry_by_text(attr, value).unwrap();
+        }
+        let mut req = X509Req::builder().unwrap();
+        req.set_pubkey(&key).unwrap();
+        req.set_subject_name(&name.build()).unwrap();
+        let options = ExtensionOptions {
+            extended_key_usage,
+            ..Default::default()
+        };
+        if let Some(extensions) = requested_extensions(&options).unwrap() {
+            req.add_extensions(&extensions).unwrap();
+        }
+        req.sign(&key, MessageDigest::sha256()).unwrap();
+        CsrResult {
+            cn: subject[0].1.to_string(),
+            csr_pem: String::from_utf8(req.build().to_pem().unwrap()).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        let mut linter = Linter::default();
+        linter.check(&csr(Nid::X9_62_PRIME256V1, &[("CN", "YDL0001")], ""));
+        assert!(linter.warnings().is_empty());
+
+        linter.check(&csr(
+            Nid::SECP256K1,
+            &[("CN", "YDL0002"), ("C", "cn")],
+            "serverAuth",
+        ));
+        linter.check(&csr(
+            Nid::X9_62_PRIME256V1,
+            &[("CN", "YDL0003 ")],
+            "serverAuth",
+        ));
+        let warnings = linter.warnings();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("[tls_missing_san] 2个条目"));
+        assert!(warnings[1].contains("（如 YDL0002: EC secp256k1）"));
+        assert!(warnings[2].contains("（如 YDL0002: C 须为两位大写字母）"));
+    }
+}
@@ -0,0 +1,140 @@
+//! MAC地址范围模块
+//! 将MAC地址范围（如 `00:1A:2B:00:00:01 - 00:1A:2B:00:01:00`）展开为通用名称，用于按MAC地址标识的IoT设备批量制证
+//!
+//! 输入中的分隔符不限（冒号、短横线、点或无分隔符均可），每个范围须恰好包含两个MAC地址（24位十六进制数字）；
+//! 多个范围或单个MAC地址以逗号或换行分隔，输出按设置的分隔符和大小写格式化
+
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::error::BatchError;
+
+/// 单次最多展开的MAC地址数量，避免误填范围时耗尽内存
+const MAX_MAC_COUNT: u64 = 1_000_000;
+
+/// 解析MAC地址分隔符: colon (默认，00:1A:2B:00:00:01), hyphen (00-1A-2B-00-00-01),
+/// dot (001A.2B00.0001), none (001A2B000001)
+fn separator(s: &str) -> Result<Option<char>> {
+    match s {
+        "" | "colon" => Ok(Some(':')),
+        "hyphen" => Ok(Some('-')),
+        "dot" => Ok(Some('.')),
+        "none" => Ok(None),
+        _ => {
+            let message = format!("不支持的MAC地址分隔符: {}", s);
+            Err(BatchError::parameter("mac_separator", message).into())
+        }
+    }
+}
+
+/// 按分隔符和大小写格式化MAC地址，dot分隔时每4位一组，其余每2位一组
+fn format_mac(value: u64, separator: Option<char>, uppercase: bool) -> String {
+    let hex = if uppercase {
+        format!("{:012X}", value)
+    } else {
+        format!("{:012x}", value)
+    };
+    let Some(separator) = separator else {
+        return hex;
+    };
+    let group = if separator == '.' { 4 } else { 2 };
+    hex.as_bytes()
+        .chunks(group)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
+/// 展开MAC地址范围为通用名称，结果按出现顺序拼接并去重
+/// mac_separator 为 colon/hyphen/dot/none，mac_case 为 upper (默认) 或 lower
+pub(crate) fn parse_mac_range(
+    range: &str,
+    mac_separator: &str,
+    mac_case: &str,
+) -> Result<Vec<String>> {
+    let separator = separator(mac_separator)?;
+    let uppercase = match mac_case {
+        "" | "upper" => true,
+        "lower" => false,
+        _ => {
+            let message = format!("MAC地址大小写须为upper或lower，当前为{}", mac_case);
+            return Err(BatchError::parameter("mac_case", message).into());
+        }
+    };
+
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for segment in range.split([',', '\n']).map(str::trim) {
+        if segment.is_empty() {
+            continue;
+        }
+        let invalid =
+            || BatchError::parameter("mac_range", format!("无效的MAC地址范围: {}", segment));
+        if !segment
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() || c.is_whitespace() || ":-.".contains(c))
+        {
+            return Err(invalid().into());
+        }
+
+        // 去掉分隔符后，单个MAC地址为12位十六进制数字，范围为24位
+        let digits: String = segment.chars().filter(char::is_ascii_hexdigit).collect();
+        let (start, end) = match digits.len() {
+            12 => (&digits[..], &digits[..]),
+            24 => digits.split_at(12),
+            _ => return Err(invalid().into()),
+        };
+        let start = u64::from_str_radix(start, 16)?;
+        let end = u64::from_str_radix(end, 16)?;
+        if start > end {
+            let message = format!("起始MAC地址大于结束地址: {}", segment);
+            return Err(BatchError::parameter("mac_range", message).into());
+        }
+        if end - start >= MAX_MAC_COUNT {
+            let message = format!("MAC地址范围超过{}个: {}", MAX_MAC_COUNT, segment);
+            return Err(BatchError::parameter("mac_range", message).into());
+        }
+
+        for value in start..=end {
+            let cn = format_mac(value, separator, uppercase);
+            if seen.insert(cn.clone()) {
+                result.push(cn);
+            }
+        }
+    }
+
+    if result.is_empty() {
+        return Err(BatchError::parameter("mac_range", "MAC地址范围为空").into());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac_range() {
+        let result = parse_mac_range("00:1A:2B:00:00:FF - 00:1A:2B:00:01:01", "", "").unwrap();
+        assert_eq!(
+            result,
+            [
+                "00:1A:2B:00:00:FF",
+                "00:1A:2B:00:01:00",
+                "00:1A:2B:00:01:01"
+            ]
+        );
+
+        let result = parse_mac_range(
+            "00-1a-2b-00-00-01 - 00-1a-2b-00-00-02\n001A2B000001",
+            "dot",
+            "lower",
+        )
+        .unwrap();
+        assert_eq!(result, ["001a.2b00.0001", "001a.2b00.0002"]);
+
+        assert!(parse_mac_range("00:1A:2B:00:01:00 - 00:1A:2B:00:00:01", "", "").is_err());
+        assert!(parse_mac_range("00:1A:2B:00:00", "", "").is_err());
+        assert!(parse_mac_range("00:1A:2B:00:00:01", "slash", "").is_err());
+    }
+}
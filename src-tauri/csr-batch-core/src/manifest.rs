@@ -0,0 +1,209 @@
+//! 清单模块
+//! 生成结束后写入JSON清单，列出每个输出文件的大小和SHA-256，以及每个CSR（按输出顺序）的SHA-256，
+//! 可选使用提供的私钥对清单签名（分离签名，写入 `.sig` 文件），接收方据此验证CSV在传输中未被篡改
+//!
+//! 签名为原始二进制：RSA/EC私钥使用SHA-256摘要，Ed25519/Ed448不使用独立摘要
+
+use anyhow::Result;
+use chrono::{Local, SecondsFormat};
+use openssl::base64;
+use openssl::hash::{hash, Hasher, MessageDigest};
+use openssl::pkey::{Id, PKey, Private};
+use openssl::sign::Signer;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::csr_generator::{read_private_key, BatchRequest, CsrResult};
+use crate::error::BatchError;
+use crate::output::{check_overwrite, pem_to_base64, write_atomic};
+
+/// 清单文件路径：输出文件同目录下文件名加 _manifest 后缀
+pub(crate) fn manifest_path(output_path: &str) -> PathBuf {
+    let path = Path::new(output_path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    path.with_file_name(format!("{}_manifest.json", stem))
+}
+
+/// 清单签名文件路径
+pub(crate) fn signature_path(manifest_path: &Path) -> PathBuf {
+    let mut path = manifest_path.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// 小写十六进制，与 sha256sum 的输出一致
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestFile {
+    /// 相对清单所在目录的路径，以 / 分隔
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestRow {
+    cn: String,
+    /// CSR（DER编码）的SHA-256，与输出时的CSR编码无关
+    csr_sha256: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestDocument<'a> {
+    version: u32,
+    created_at: String,
+    files: Vec<ManifestFile>,
+    rows: &'a [ManifestRow],
+}
+
+/// 生成过程中收集的清单内容，结束时计算文件哈希并写入
+pub(crate) struct Manifest {
+    path: PathBuf,
+    signing_key: Option<PKey<Private>>,
+    files: Vec<PathBuf>,
+    rows: Vec<ManifestRow>,
+}
+
+impl Manifest {
+    /// 按请求创建清单，未启用时返回None；签名私钥在生成前读取，避免生成结束后才发现口令错误
+    pub(crate) fn from_request(request: &BatchRequest) -> Result<Option<Self>> {
+        if !request.manifest && request.manifest_signing_key.is_empty() {
+            return Ok(None);
+        }
+        let signing_key = if request.manifest_signing_key.is_empty() {
+            None
+        } else {
+            let path = &request.manifest_signing_key;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let key = read_private_key(&pem, &request.manifest_key_password).map_err(|e| {
+                BatchError::parameter("manifest_signing_key", format!("无法读取签名私钥: {}", e))
+            })?;
+            if !matches!(key.id(), Id::RSA | Id::EC | Id::ED25519 | Id::ED448) {
+                let message = "清单签名私钥须为RSA、EC、Ed25519或Ed448密钥";
+                return Err(BatchError::parameter("manifest_signing_key", message).into());
+            }
+            Some(key)
+        };
+        let path = manifest_path(&request.output_path);
+        check_overwrite(&path, request.overwrite)?;
+        Ok(Some(Self {
+            path,
+            signing_key,
+            files: Vec::new(),
+            rows: Vec::new(),
+        }))
+    }
+
+    /// 登记输出文件，结束时计算哈希
+    pub(crate) fn add_file(&mut self, path: impl Into<PathBuf>) {
+        self.files.push(path.into());
+    }
+
+    /// 登记单条结果，csr_pem可为PEM或单行Base64
+    pub(crate) fn add_row(&mut self, result: &CsrResult) -> Result<()> {
+        let der = base64::decode_block(&pem_to_base64(&result.csr_pem))?;
+        self.rows.push(ManifestRow {
+            cn: result.cn.clone(),
+            csr_sha256: hex(&hash(MessageDigest::sha256(), &der)?),
+        });
+        Ok(())
+    }
+
+    /// 计算文件哈希，写入清单和签名文件
+    pub(crate) fn finish(self) -> Result<()> {
+        let base = self.path.parent().unwrap_or(Path::new(""));
+        let mut files = Vec::with_capacity(self.files.len());
+        for path in &self.files {
+            let mut file = File::open(path).map_err(|e| BatchError::io(path, e))?;
+            let mut hasher = Hasher::new(MessageDigest::sha256())?;
+            let size = io::copy(&mut file, &mut hasher)?;
+            let relative = path.strip_prefix(base).unwrap_or(path);
+            files.push(ManifestFile {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                size,
+                sha256: hex(&hasher.finish()?),
+            });
+        }
+
+        let document = ManifestDocument {
+            version: 1,
+            created_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            files,
+            rows: &self.rows,
+        };
+        let content = serde_json::to_vec_pretty(&document)?;
+        write_atomic(&self.path, &content)?;
+
+        if let Some(key) = &self.signing_key {
+            let mut signer = if matches!(key.id(), Id::ED25519 | Id::ED448) {
+                Signer::new_without_digest(key)?
+            } else {
+                Signer::new(MessageDigest::sha256(), key)?
+            };
+            let signature = signer.sign_oneshot_to_vec(&content)?;
+            write_atomic(&signature_path(&self.path), &signature)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{CsvColumns, OutputWriter};
+    use openssl::sign::Verifier;
+    use serde_json::Value;
+
+    #[test]
+    fn test_signed_manifest() {
+        let dir = std::env::temp_dir().join("csr_batch_manifest_test");
+        fs::create_dir_all(&dir).unwrap();
+        let key = PKey::generate_ed25519().unwrap();
+        let key_path = dir.join("signer.key");
+        fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        let csv_path = dir.join("out.csv");
+        let request = BatchRequest {
+            output_path: csv_path.to_string_lossy().to_string(),
+            manifest_signing_key: key_path.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        let mut req = openssl::x509::X509Req::builder().unwrap();
+        req.set_pubkey(&key).unwrap();
+        req.sign(&key, MessageDigest::null()).unwrap();
+        let csr = req.build();
+        let result = CsrResult {
+            cn: "YDL0001".to_string(),
+            csr_pem: String::from_utf8(csr.to_pem().unwrap()).unwrap(),
+            ..Default::default()
+        };
+        let mut writer = OutputWriter::create(&request, CsvColumns::default()).unwrap();
+        writer.write(&result).unwrap();
+        writer.finish().unwrap();
+
+        let manifest = fs::read(manifest_path(&request.output_path)).unwrap();
+        let document: Value = serde_json::from_slice(&manifest).unwrap();
+        assert_eq!(document["files"][0]["path"], "out.csv");
+        let csv = fs::read(&csv_path).unwrap();
+        let expected = hex(&hash(MessageDigest::sha256(), &csv).unwrap());
+        assert_eq!(document["files"][0]["sha256"], expected.as_str());
+        let expected = hex(&hash(MessageDigest::sha256(), &csr.to_der().unwrap()).unwrap());
+        assert_eq!(document["rows"][0]["csrSha256"], expected.as_str());
+
+        let signature = fs::read(signature_path(&manifest_path(&request.output_path))).unwrap();
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        assert!(verifier.verify_oneshot(&signature, &manifest).unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+}
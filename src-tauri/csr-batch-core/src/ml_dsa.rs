@@ -0,0 +1,36 @@
+//! 后量子签名模块（ml-dsa 特性）
+//! 使用OpenSSL 3.5起内置的ML-DSA (FIPS 204) 实现生成密钥对，
+//! openssl crate尚未提供按算法名称生成密钥的接口，此处直接调用OpenSSL
+
+use anyhow::{anyhow, Result};
+use foreign_types::ForeignType;
+use openssl::error::ErrorStack;
+use openssl::pkey::{PKey, Private};
+use openssl_sys as ffi;
+use std::ffi::CString;
+use std::ptr;
+
+/// 按OpenSSL算法名称（如 ML-DSA-65）生成密钥对
+pub(crate) fn generate_key_pair(algorithm: &str) -> Result<PKey<Private>> {
+    let name = CString::new(algorithm)?;
+
+    // SAFETY: ctx在使用后释放；生成成功时pkey的所有权转交给PKey
+    unsafe {
+        let ctx = ffi::EVP_PKEY_CTX_new_from_name(ptr::null_mut(), name.as_ptr(), ptr::null());
+        if ctx.is_null() {
+            return Err(anyhow!(
+                "当前OpenSSL不支持{}，需要OpenSSL 3.5及以上版本: {}",
+                algorithm,
+                ErrorStack::get()
+            ));
+        }
+        let mut pkey = ptr::null_mut();
+        let generated =
+            ffi::EVP_PKEY_keygen_init(ctx) > 0 && ffi::EVP_PKEY_generate(ctx, &mut pkey) > 0;
+        ffi::EVP_PKEY_CTX_free(ctx);
+        if !generated {
+            return Err(anyhow!("{}密钥生成失败: {}", algorithm, ErrorStack::get()));
+        }
+        Ok(PKey::from_ptr(pkey))
+    }
+}
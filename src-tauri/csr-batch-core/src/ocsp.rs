@@ -0,0 +1,519 @@
+//! OCSP响应器模块（需启用 `server` 特性）
+//! 按本地任务历史数据库中的吊销记录回答OCSP查询（RFC 6960），用于在实验环境中端到端测试设备的吊销检查：
+//!
+//! - `POST /`：请求体为DER编码的OCSP请求（application/ocsp-request）
+//! - `GET /{请求}`：路径为Base64编码（可URL编码）的OCSP请求
+//!
+//! 响应由内置CA的证书和私钥直接签名。吊销的序列号返回revoked（含吊销原因和时间），
+//! 到期跟踪中记录过的该CA签发的证书返回good，其他序列号返回unknown；
+//! 请求中的证书都不是该CA签发时返回unauthorized
+
+use anyhow::{anyhow, Result};
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::asn1::{Asn1IntegerRef, Asn1ObjectRef, Asn1Time};
+use openssl::base64;
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::ocsp::{OcspBasicResponse, OcspCertId, OcspRequest, OcspResponse, OcspResponseStatus};
+use openssl_sys as ffi;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::io::Read;
+use std::net::SocketAddr;
+use std::os::raw::{c_int, c_ulong, c_void};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Arc;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::ca::{CertificateAuthority, SignParams};
+use crate::crl::{self, asn1_time, parse_reason, revocation_row, Revocation};
+use crate::error::BatchError;
+use crate::subject::{format_subject, from_x509_name};
+
+extern "C" {
+    // openssl-sys未导出以下OCSP函数，解析请求和构造响应需直接调用OpenSSL
+    fn OCSP_request_onereq_count(req: *mut ffi::OCSP_REQUEST) -> c_int;
+    fn OCSP_request_onereq_get0(req: *mut ffi::OCSP_REQUEST, i: c_int) -> *mut ffi::OCSP_ONEREQ;
+    fn OCSP_onereq_get0_id(one: *mut ffi::OCSP_ONEREQ) -> *mut ffi::OCSP_CERTID;
+    fn OCSP_id_get0_info(
+        name_hash: *mut *mut ffi::ASN1_OCTET_STRING,
+        md: *mut *mut ffi::ASN1_OBJECT,
+        key_hash: *mut *mut ffi::ASN1_OCTET_STRING,
+        serial: *mut *mut ffi::ASN1_INTEGER,
+        cid: *mut ffi::OCSP_CERTID,
+    ) -> c_int;
+    fn OCSP_id_issuer_cmp(a: *const ffi::OCSP_CERTID, b: *const ffi::OCSP_CERTID) -> c_int;
+    fn OCSP_basic_add1_status(
+        rsp: *mut ffi::OCSP_BASICRESP,
+        cid: *mut ffi::OCSP_CERTID,
+        status: c_int,
+        reason: c_int,
+        revtime: *mut ffi::ASN1_TIME,
+        thisupd: *mut ffi::ASN1_TIME,
+        nextupd: *mut ffi::ASN1_TIME,
+    ) -> *mut c_void;
+    fn OCSP_basic_sign(
+        brsp: *mut ffi::OCSP_BASICRESP,
+        signer: *mut ffi::X509,
+        key: *mut ffi::EVP_PKEY,
+        dgst: *const ffi::EVP_MD,
+        certs: *mut ffi::stack_st_X509,
+        flags: c_ulong,
+    ) -> c_int;
+    fn OCSP_copy_nonce(resp: *mut ffi::OCSP_BASICRESP, req: *mut ffi::OCSP_REQUEST) -> c_int;
+}
+
+/// 请求体大小上限
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+/// OCSP响应器配置
+#[derive(Debug, Clone, Default)]
+pub struct OcspConfig {
+    /// 监听地址，如 127.0.0.1:8888
+    pub addr: String,
+    /// 任务历史数据库路径（吊销记录和到期跟踪的证书）
+    pub db_path: String,
+    /// CA证书路径 (PEM格式，或扩展名为 .p12/.pfx 的PKCS#12文件)
+    pub ca_cert_path: String,
+    /// CA私钥路径 (PEM格式，CA证书为PKCS#12文件时不需要)
+    pub ca_key_path: String,
+    /// CA私钥口令或PKCS#12密码
+    pub ca_password: String,
+    /// 响应的有效小时数（nextUpdate），0表示不设置nextUpdate
+    pub next_update_hours: u32,
+}
+
+/// 单个证书的查询结果
+enum CertStatus {
+    Good,
+    Revoked {
+        reason: u8,
+        revoked_at: Asn1Time,
+    },
+    Unknown,
+    /// 不是本CA签发的证书
+    OtherIssuer,
+}
+
+/// 已绑定监听地址的OCSP响应器
+pub struct OcspResponder {
+    server: Server,
+    db_path: PathBuf,
+    ca: CertificateAuthority,
+    /// CA主题，与到期跟踪和吊销记录中的签发者对应
+    issuer: String,
+    next_update_hours: u32,
+}
+
+impl OcspResponder {
+    /// 加载CA证书和私钥并绑定监听地址（端口为0时由系统分配）
+    pub fn bind(config: &OcspConfig) -> Result<Self, BatchError> {
+        if config.db_path.is_empty() {
+            return Err(BatchError::parameter("db_path", "未指定任务历史数据库"));
+        }
+        let ca = CertificateAuthority::load(&SignParams {
+            ca_cert_path: config.ca_cert_path.clone(),
+            ca_key_path: config.ca_key_path.clone(),
+            ca_password: config.ca_password.clone(),
+            ..Default::default()
+        })?;
+        let issuer = format_subject(&from_x509_name(ca.cert.subject_name())?);
+        let server = Server::http(&config.addr).map_err(|e| {
+            BatchError::parameter("addr", format!("无法监听 {}: {}", config.addr, e))
+        })?;
+        Ok(Self {
+            server,
+            db_path: PathBuf::from(&config.db_path),
+            ca,
+            issuer,
+            next_update_hours: config.next_update_hours,
+        })
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// 处理请求直到进程退出，每个请求在单独的线程中处理
+    pub fn run(self) {
+        let responder = Arc::new(self);
+        for request in responder.server.incoming_requests() {
+            let responder = Arc::clone(&responder);
+            std::thread::spawn(move || responder.handle(request));
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let status = match self.read_request(&mut request) {
+            Ok(der) => match OcspRequest::from_der(&der) {
+                Ok(ocsp_request) => match self.respond(&ocsp_request) {
+                    Ok(response) => Ok(response),
+                    Err(_) => Err(OcspResponseStatus::INTERNAL_ERROR),
+                },
+                Err(_) => Err(OcspResponseStatus::MALFORMED_REQUEST),
+            },
+            Err(status) => Err(status),
+        };
+        let response = status.or_else(|status| OcspResponse::create(status, None));
+        let response = match response.and_then(|response| response.to_der()) {
+            Ok(der) => Response::from_data(der)
+                .with_header(header("Content-Type", "application/ocsp-response"))
+                .boxed(),
+            Err(_) => Response::empty(500).boxed(),
+        };
+        let _ = request.respond(response);
+    }
+
+    /// 读取DER编码的OCSP请求：POST为请求体，GET为URL中的Base64
+    fn read_request(&self, request: &mut Request) -> Result<Vec<u8>, OcspResponseStatus> {
+        let malformed = OcspResponseStatus::MALFORMED_REQUEST;
+        match request.method() {
+            Method::Post => {
+                let mut body = Vec::new();
+                request
+                    .as_reader()
+                    .take(MAX_BODY_SIZE)
+                    .read_to_end(&mut body)
+                    .map_err(|_| malformed)?;
+                Ok(body)
+            }
+            Method::Get => {
+                let encoded = request.url().trim_start_matches('/');
+                let decoded = percent_decode(encoded).ok_or(malformed)?;
+                base64::decode_block(&decoded).map_err(|_| malformed)
+            }
+            _ => Err(malformed),
+        }
+    }
+
+    /// 逐个查询请求中的证书状态，构造由CA签名的响应
+    fn respond(&self, request: &OcspRequest) -> Result<OcspResponse> {
+        let conn = crl::open(&self.db_path)?;
+        let this_update = Asn1Time::days_from_now(0)?;
+        let next_update = match self.next_update_hours {
+            0 => None,
+            hours => {
+                let now = chrono::Utc::now().timestamp();
+                Some(Asn1Time::from_unix(now + hours as i64 * 3600)?)
+            }
+        };
+        // SAFETY: OCSP_BASICRESP_new返回的指针非空时由OcspBasicResponse负责释放；
+        // 请求中的证书ID由请求持有，add1_status复制证书ID和时间
+        unsafe {
+            let basic = ffi::OCSP_BASICRESP_new();
+            if basic.is_null() {
+                return Err(ErrorStack::get().into());
+            }
+            let basic = OcspBasicResponse::from_ptr(basic);
+            let count = OCSP_request_onereq_count(request.as_ptr());
+            let mut authorized = false;
+            for index in 0..count {
+                let one = OCSP_request_onereq_get0(request.as_ptr(), index);
+                let cert_id = OCSP_onereq_get0_id(one);
+                let status = self.cert_status(&conn, cert_id)?;
+                authorized |= !matches!(status, CertStatus::OtherIssuer);
+                let (status, reason, revoked_at) = match status {
+                    CertStatus::Good => (ffi::V_OCSP_CERTSTATUS_GOOD, 0, None),
+                    CertStatus::Revoked { reason, revoked_at } => (
+                        ffi::V_OCSP_CERTSTATUS_REVOKED,
+                        reason as c_int,
+                        Some(revoked_at),
+                    ),
+                    CertStatus::Unknown | CertStatus::OtherIssuer => {
+                        (ffi::V_OCSP_CERTSTATUS_UNKNOWN, 0, None)
+                    }
+                };
+                let single = OCSP_basic_add1_status(
+                    basic.as_ptr(),
+                    cert_id,
+                    status,
+                    reason,
+                    revoked_at
+                        .as_ref()
+                        .map_or(ptr::null_mut(), |time| time.as_ptr()),
+                    this_update.as_ptr(),
+                    next_update
+                        .as_ref()
+                        .map_or(ptr::null_mut(), |time| time.as_ptr()),
+                );
+                if single.is_null() {
+                    return Err(anyhow!("无法添加证书状态: {}", ErrorStack::get()));
+                }
+            }
+            // 请求中没有本CA签发的证书时不作回答
+            if !authorized {
+                return Ok(OcspResponse::create(
+                    OcspResponseStatus::UNAUTHORIZED,
+                    None,
+                )?);
+            }
+            if OCSP_copy_nonce(basic.as_ptr(), request.as_ptr()) <= 0 {
+                return Err(anyhow!("无法复制请求的nonce: {}", ErrorStack::get()));
+            }
+            let ret = OCSP_basic_sign(
+                basic.as_ptr(),
+                self.ca.cert.as_ptr(),
+                self.ca.key.as_ptr(),
+                self.ca.digest.as_ptr(),
+                ptr::null_mut(),
+                0,
+            );
+            if ret <= 0 {
+                return Err(anyhow!("OCSP响应签名失败: {}", ErrorStack::get()));
+            }
+            Ok(OcspResponse::create(
+                OcspResponseStatus::SUCCESSFUL,
+                Some(&basic),
+            )?)
+        }
+    }
+
+    /// 查询证书ID对应的证书状态
+    ///
+    /// # Safety
+    /// cert_id须为有效的OCSP_CERTID指针
+    unsafe fn cert_status(
+        &self,
+        conn: &Connection,
+        cert_id: *mut ffi::OCSP_CERTID,
+    ) -> Result<CertStatus> {
+        let mut md = ptr::null_mut();
+        let mut serial = ptr::null_mut();
+        let ret = OCSP_id_get0_info(
+            ptr::null_mut(),
+            &mut md,
+            ptr::null_mut(),
+            &mut serial,
+            cert_id,
+        );
+        if ret <= 0 || md.is_null() || serial.is_null() {
+            return Ok(CertStatus::Unknown);
+        }
+        // 以请求使用的哈希算法计算本CA的名称和公钥哈希，与请求比较
+        let Some(digest) = MessageDigest::from_nid(Asn1ObjectRef::from_ptr(md).nid()) else {
+            return Ok(CertStatus::Unknown);
+        };
+        let ca_id = ffi::OCSP_cert_to_id(digest.as_ptr(), ptr::null_mut(), self.ca.cert.as_ptr());
+        if ca_id.is_null() {
+            return Err(ErrorStack::get().into());
+        }
+        let ca_id = OcspCertId::from_ptr(ca_id);
+        if OCSP_id_issuer_cmp(ca_id.as_ptr(), cert_id) != 0 {
+            return Ok(CertStatus::OtherIssuer);
+        }
+
+        let serial = Asn1IntegerRef::from_ptr(serial)
+            .to_bn()?
+            .to_hex_str()?
+            .to_string();
+        if let Some(revocation) = find_revocation(conn, &serial, &self.issuer)? {
+            let (_, reason) = parse_reason(&revocation.reason)?;
+            let revoked_at = asn1_time(&revocation.revoked_at)?;
+            return Ok(CertStatus::Revoked { reason, revoked_at });
+        }
+        let tracked: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM certificates WHERE serial = ?1 AND issuer = ?2)",
+            params![serial, self.issuer],
+            |row| row.get(0),
+        )?;
+        Ok(if tracked {
+            CertStatus::Good
+        } else {
+            CertStatus::Unknown
+        })
+    }
+}
+
+/// 查询该签发者（或签发者未知）的序列号的吊销记录，未吊销时返回None
+fn find_revocation(conn: &Connection, serial: &str, issuer: &str) -> Result<Option<Revocation>> {
+    Ok(conn
+        .query_row(
+            "SELECT serial, issuer, reason, revoked_at FROM revocations
+             WHERE serial = ?1 AND (issuer = '' OR issuer = ?2)",
+            params![serial, issuer],
+            revocation_row,
+        )
+        .optional()?)
+}
+
+/// URL百分号解码（GET请求中的Base64可能将 +、/、= 编码）
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("响应头有效")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crl::revoke;
+    use crate::expiry::track_certificates;
+    use csv::Writer;
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::ocsp::{OcspCertStatus, OcspFlag, OcspRevokedStatus};
+    use openssl::pkey::{PKey, Private};
+    use openssl::stack::Stack;
+    use openssl::x509::extension::BasicConstraints;
+    use openssl::x509::store::X509StoreBuilder;
+    use openssl::x509::{X509Builder, X509NameBuilder, X509};
+
+    /// 由issuer签发的证书，issuer为None时自签名
+    fn certificate(
+        cn: &str,
+        serial: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+    ) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", cn).unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_hex_str(serial).unwrap();
+        builder
+            .set_serial_number(&serial.to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        let (issuer_name, signing_key) = match issuer {
+            Some((cert, key)) => (cert.subject_name(), key),
+            None => {
+                let ca = BasicConstraints::new().critical().ca().build().unwrap();
+                builder.append_extension(ca).unwrap();
+                (name.as_ref(), key)
+            }
+        };
+        builder.set_issuer_name(issuer_name).unwrap();
+        builder.sign(signing_key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn test_ocsp_responder() {
+        let dir = std::env::temp_dir().join("csr_batch_ocsp_test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let ca = certificate("Lab CA", "1", &key, None);
+        let other_ca = certificate("Other CA", "2", &key, None);
+        let revoked = certificate("dev1", "3F", &key, Some((&ca, &key)));
+        let good = certificate("dev2", "40", &key, Some((&ca, &key)));
+        let untracked = certificate("dev3", "41", &key, Some((&ca, &key)));
+        let foreign = certificate("dev4", "40", &key, Some((&other_ca, &key)));
+
+        let ca_cert_path = dir.join("ca.pem");
+        let ca_key_path = dir.join("ca.key");
+        std::fs::write(&ca_cert_path, ca.to_pem().unwrap()).unwrap();
+        std::fs::write(&ca_key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        let csv_path = dir.join("issued.csv");
+        let mut writer = Writer::from_path(&csv_path).unwrap();
+        writer.write_record(["certificate"]).unwrap();
+        for cert in [&revoked, &good] {
+            writer
+                .write_record([String::from_utf8(cert.to_pem().unwrap()).unwrap()])
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        let db = dir.join("job_history.db").to_string_lossy().to_string();
+        track_certificates(&db, &csv_path.to_string_lossy()).unwrap();
+        revoke(&db, "3F", "keyCompromise").unwrap();
+
+        let config = OcspConfig {
+            addr: "127.0.0.1:0".to_string(),
+            db_path: db,
+            ca_cert_path: ca_cert_path.to_string_lossy().to_string(),
+            ca_key_path: ca_key_path.to_string_lossy().to_string(),
+            next_update_hours: 1,
+            ..Default::default()
+        };
+        let responder = OcspResponder::bind(&config).unwrap();
+        let base = format!("http://{}", responder.local_addr().unwrap());
+        std::thread::spawn(move || responder.run());
+
+        let mut store = X509StoreBuilder::new().unwrap();
+        store.add_cert(ca.clone()).unwrap();
+        let store = store.build();
+        // 查询单个证书，POST或GET，返回状态和吊销原因
+        let query = |cert: &X509, post: bool| {
+            let id = || OcspCertId::from_cert(MessageDigest::sha1(), cert, &ca).unwrap();
+            let mut request = OcspRequest::new().unwrap();
+            request.add_id(id()).unwrap();
+            let der = request.to_der().unwrap();
+            let response = if post {
+                ureq::post(&base)
+                    .set("Content-Type", "application/ocsp-request")
+                    .send_bytes(&der)
+            } else {
+                let encoded = base64::encode_block(&der)
+                    .replace('+', "%2B")
+                    .replace('/', "%2F")
+                    .replace('=', "%3D");
+                ureq::get(&format!("{}/{}", base, encoded)).call()
+            }
+            .unwrap();
+            let mut body = Vec::new();
+            response.into_reader().read_to_end(&mut body).unwrap();
+            let response = OcspResponse::from_der(&body).unwrap();
+            assert_eq!(response.status(), OcspResponseStatus::SUCCESSFUL);
+            let basic = response.basic().unwrap();
+            basic
+                .verify(&Stack::new().unwrap(), &store, OcspFlag::empty())
+                .unwrap();
+            let status = basic.find_status(&id()).unwrap();
+            status.check_validity(60, None).unwrap();
+            assert!(status.next_update().is_some());
+            (status.status, status.reason)
+        };
+
+        // 不是本CA签发的证书
+        let id = OcspCertId::from_cert(MessageDigest::sha1(), &foreign, &other_ca).unwrap();
+        let mut request = OcspRequest::new().unwrap();
+        request.add_id(id).unwrap();
+        let response = ureq::post(&base)
+            .send_bytes(&request.to_der().unwrap())
+            .unwrap();
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body).unwrap();
+        let response = OcspResponse::from_der(&body).unwrap();
+        assert_eq!(response.status(), OcspResponseStatus::UNAUTHORIZED);
+
+        let (status, reason) = query(&revoked, true);
+        assert_eq!(status, OcspCertStatus::REVOKED);
+        assert_eq!(reason, OcspRevokedStatus::KEY_COMPROMISE);
+        assert_eq!(query(&good, false).0, OcspCertStatus::GOOD);
+        assert_eq!(query(&untracked, true).0, OcspCertStatus::UNKNOWN);
+
+        let response = ureq::post(&base).send_bytes(b"not ocsp").unwrap();
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body).unwrap();
+        let response = OcspResponse::from_der(&body).unwrap();
+        assert_eq!(response.status(), OcspResponseStatus::MALFORMED_REQUEST);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
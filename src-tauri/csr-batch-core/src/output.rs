@@ -0,0 +1,1895 @@
+//! 输出模块
+//! 将生成结果逐条写入CSV文件、逐个CN的PEM文件、ZIP压缩包、Excel工作簿、JSON文件或SQLite任务数据库
+//!
+//! CSV、ZIP、Excel和JSON先写入同目录下的临时文件（`.<文件名>.partial`），完成后原子重命名为目标文件，
+//! 崩溃或出错时目标位置不会留下写了一半的文件；目标文件已存在时默认拒绝覆盖
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use csv::{QuoteStyle, ReaderBuilder, StringRecord, Terminator, Writer, WriterBuilder};
+use encoding_rs::{Encoding, UTF_8};
+use openssl::base64;
+use rust_xlsxwriter::{Format, FormatAlign, Workbook};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use zip::write::{FileOptions, SimpleFileOptions};
+use zip::{AesMode, CompressionMethod, ZipWriter};
+
+use crate::csr_generator::{file_name_stem, BatchRequest, CsrResult, ItemFailure, PqCsr};
+use crate::error::BatchError;
+use crate::job_db::JobDatabase;
+use crate::manifest::Manifest;
+use crate::subject::parse_subject;
+
+/// 输出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    /// 仅CSV文件
+    Csv,
+    /// 仅逐个CN的PEM文件
+    Files,
+    /// CSV文件和PEM文件
+    Both,
+    /// 包含PEM文件和清单CSV的ZIP压缩包
+    Zip,
+    /// Excel工作簿（与CSV相同的列，另附汇总工作表）
+    Xlsx,
+    /// JSON数组，每个CSR一个对象
+    Json,
+    /// 每行一个JSON对象（NDJSON）
+    Ndjson,
+    /// 仅写入SQLite任务数据库
+    Sqlite,
+}
+
+impl OutputMode {
+    /// 从字符串解析输出方式，空字符串为CSV
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "" | "csv" => Ok(OutputMode::Csv),
+            "files" => Ok(OutputMode::Files),
+            "both" => Ok(OutputMode::Both),
+            "zip" => Ok(OutputMode::Zip),
+            "xlsx" => Ok(OutputMode::Xlsx),
+            "json" => Ok(OutputMode::Json),
+            "ndjson" => Ok(OutputMode::Ndjson),
+            "sqlite" => Ok(OutputMode::Sqlite),
+            _ => {
+                Err(BatchError::parameter("output_mode", format!("不支持的输出方式: {}", s)).into())
+            }
+        }
+    }
+}
+
+/// CSR编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CsrEncoding {
+    /// PEM格式
+    Pem,
+    /// 单行Base64（DER的Base64编码，不含PEM首尾行和换行）
+    Base64,
+    /// 单独的CSR文件为二进制DER，表格输出中为单行Base64
+    Der,
+}
+
+impl CsrEncoding {
+    /// 从字符串解析CSR编码，空字符串为PEM
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "" | "pem" => Ok(CsrEncoding::Pem),
+            "base64" => Ok(CsrEncoding::Base64),
+            "der" => Ok(CsrEncoding::Der),
+            _ => {
+                let message = format!("不支持的CSR编码: {}", s);
+                Err(BatchError::parameter("csr_encoding", message).into())
+            }
+        }
+    }
+
+    /// 单独的CSR文件内容，csr为PEM或已转换的单行Base64
+    fn file_content(self, csr: &str) -> Result<Vec<u8>> {
+        match self {
+            CsrEncoding::Der => Ok(base64::decode_block(csr)?),
+            CsrEncoding::Pem | CsrEncoding::Base64 => Ok(csr.as_bytes().to_vec()),
+        }
+    }
+}
+
+/// 去掉PEM首尾行和换行，得到单行Base64
+pub(crate) fn pem_to_base64(pem: &str) -> String {
+    pem.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect()
+}
+
+/// ZIP压缩包中清单文件的名称
+const ZIP_MANIFEST_NAME: &str = "manifest.csv";
+
+/// Excel中PEM列的列宽（字符数），PEM每行64个字符
+const XLSX_PEM_COLUMN_WIDTH: f64 = 66.0;
+
+/// Excel中其余列的列宽（字符数）
+const XLSX_COLUMN_WIDTH: f64 = 24.0;
+
+/// CSV中按需输出的可选列（未指定输出列时使用）
+/// 流式写入时表头先于数据写出，因此需在生成前确定
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvColumns {
+    /// 是否输出uniqueId列
+    pub unique_id: bool,
+    /// 是否输出sans列
+    pub sans: bool,
+    /// 是否输出privateKeyEncryption列
+    pub key_encryption: bool,
+    /// 是否输出混合模式的pqCsr、pqKeyPairType和pqPrivateKey列
+    pub pq: bool,
+    /// 是否输出操作系统密钥存储的keyContainer列
+    pub key_container: bool,
+    /// 是否输出publicKeySha256和subjectKeyId列
+    pub fingerprints: bool,
+}
+
+impl CsvColumns {
+    /// 默认输出的列（按参数省略未使用的可选列）
+    fn defaults(&self) -> Vec<Column> {
+        let mut columns = vec![
+            Column::Subject,
+            Column::SignHashAlg,
+            Column::NotBefore,
+            Column::NotAfter,
+        ];
+        if self.unique_id {
+            columns.push(Column::UniqueId);
+        }
+        if self.sans {
+            columns.push(Column::Sans);
+        }
+        columns.extend([Column::Csr, Column::KeyPairType, Column::PrivateKey]);
+        if self.key_encryption {
+            columns.push(Column::PrivateKeyEncryption);
+        }
+        if self.pq {
+            columns.extend([Column::PqCsr, Column::PqKeyPairType, Column::PqPrivateKey]);
+        }
+        if self.key_container {
+            columns.push(Column::KeyContainer);
+        }
+        if self.fingerprints {
+            columns.extend([Column::PublicKeySha256, Column::SubjectKeyId]);
+        }
+        columns
+    }
+}
+
+/// 可输出的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Cn,
+    Subject,
+    SignHashAlg,
+    NotBefore,
+    NotAfter,
+    UniqueId,
+    Sans,
+    Csr,
+    KeyPairType,
+    PrivateKey,
+    PrivateKeyEncryption,
+    PqCsr,
+    PqKeyPairType,
+    PqPrivateKey,
+    KeyContainer,
+    PublicKeySha256,
+    SubjectKeyId,
+}
+
+impl Column {
+    const ALL: [Column; 17] = [
+        Column::Cn,
+        Column::Subject,
+        Column::SignHashAlg,
+        Column::NotBefore,
+        Column::NotAfter,
+        Column::UniqueId,
+        Column::Sans,
+        Column::Csr,
+        Column::KeyPairType,
+        Column::PrivateKey,
+        Column::PrivateKeyEncryption,
+        Column::PqCsr,
+        Column::PqKeyPairType,
+        Column::PqPrivateKey,
+        Column::KeyContainer,
+        Column::PublicKeySha256,
+        Column::SubjectKeyId,
+    ];
+
+    /// 列名，同时为默认表头
+    fn name(self) -> &'static str {
+        match self {
+            Column::Cn => "cn",
+            Column::Subject => "subject",
+            Column::SignHashAlg => "signHashAlg",
+            Column::NotBefore => "notBefore",
+            Column::NotAfter => "notAfter",
+            Column::UniqueId => "uniqueId",
+            Column::Sans => "sans",
+            Column::Csr => "csr",
+            Column::KeyPairType => "keyPairType",
+            Column::PrivateKey => "privateKey",
+            Column::PrivateKeyEncryption => "privateKeyEncryption",
+            Column::PqCsr => "pqCsr",
+            Column::PqKeyPairType => "pqKeyPairType",
+            Column::PqPrivateKey => "pqPrivateKey",
+            Column::KeyContainer => "keyContainer",
+            Column::PublicKeySha256 => "publicKeySha256",
+            Column::SubjectKeyId => "subjectKeyId",
+        }
+    }
+
+    /// 按列名查找，不区分大小写
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|column| column.name().eq_ignore_ascii_case(name))
+    }
+
+    /// 单条结果中该列的值
+    fn value(self, result: &CsrResult) -> &str {
+        let pq = result.pq.as_ref();
+        match self {
+            Column::Cn => &result.cn,
+            Column::Subject => &result.subject,
+            Column::SignHashAlg => &result.sign_hash_alg,
+            Column::NotBefore => &result.not_before,
+            Column::NotAfter => &result.not_after,
+            Column::UniqueId => &result.unique_id,
+            Column::Sans => &result.sans,
+            Column::Csr => &result.csr_pem,
+            Column::KeyPairType => &result.key_pair_type,
+            Column::PrivateKey => &result.private_key_pem,
+            Column::PrivateKeyEncryption => &result.key_encryption,
+            Column::PqCsr => pq.map_or("", |pq| pq.csr_pem.as_str()),
+            Column::PqKeyPairType => pq.map_or("", |pq| pq.key_pair_type.as_str()),
+            Column::PqPrivateKey => pq.map_or("", |pq| pq.private_key_pem.as_str()),
+            Column::KeyContainer => &result.key_container,
+            Column::PublicKeySha256 => &result.public_key_sha256,
+            Column::SubjectKeyId => &result.subject_key_id,
+        }
+    }
+
+    /// 是否为私钥的列
+    fn is_private_key(self) -> bool {
+        matches!(self, Column::PrivateKey | Column::PqPrivateKey)
+    }
+
+    /// 是否为PEM内容的列
+    fn is_pem(self) -> bool {
+        matches!(
+            self,
+            Column::Csr | Column::PrivateKey | Column::PqCsr | Column::PqPrivateKey
+        )
+    }
+}
+
+/// 实际输出的列及表头
+#[derive(Debug, Clone)]
+struct ColumnLayout {
+    columns: Vec<(Column, String)>,
+}
+
+impl ColumnLayout {
+    /// spec为逗号分隔的列名，按顺序输出，可用 `列名=表头` 改写表头；为空时按默认列输出
+    fn new(defaults: CsvColumns, spec: &str) -> Result<Self> {
+        if spec.trim().is_empty() {
+            let columns = defaults
+                .defaults()
+                .into_iter()
+                .map(|column| (column, column.name().to_string()))
+                .collect();
+            return Ok(Self { columns });
+        }
+
+        let mut columns: Vec<(Column, String)> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, header) = match entry.split_once('=') {
+                Some((name, header)) => (name.trim(), header.trim()),
+                None => (entry, ""),
+            };
+            let column = Column::from_name(name)
+                .ok_or_else(|| BatchError::parameter("columns", format!("不支持的列: {}", name)))?;
+            if columns.iter().any(|(c, _)| *c == column) {
+                let message = format!("列重复: {}", column.name());
+                return Err(BatchError::parameter("columns", message).into());
+            }
+            let header = if header.is_empty() {
+                column.name()
+            } else {
+                header
+            };
+            columns.push((column, header.to_string()));
+        }
+        if columns.is_empty() {
+            return Err(BatchError::parameter("columns", "未指定输出的列").into());
+        }
+        Ok(Self { columns })
+    }
+
+    /// 拆分私钥时去掉默认的私钥列，显式指定私钥列则报错
+    fn without_private_keys(mut self, spec: &str) -> Result<Self> {
+        if !spec.trim().is_empty() && self.columns.iter().any(|(c, _)| c.is_private_key()) {
+            let message = "拆分私钥时不能输出私钥列";
+            return Err(BatchError::parameter("columns", message).into());
+        }
+        self.columns.retain(|(column, _)| !column.is_private_key());
+        Ok(self)
+    }
+
+    /// 表头
+    fn headers(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|(_, header)| header.as_str())
+    }
+
+    /// 单条结果对应的数据行
+    fn record<'a>(&self, result: &'a CsrResult) -> Vec<&'a str> {
+        self.columns
+            .iter()
+            .map(|(column, _)| column.value(result))
+            .collect()
+    }
+}
+
+/// CSV格式：分隔符、引号方式、换行符、编码和BOM
+#[derive(Debug, Clone, Copy)]
+struct CsvDialect {
+    delimiter: u8,
+    quote_style: QuoteStyle,
+    terminator: Terminator,
+    encoding: &'static Encoding,
+    bom: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote_style: QuoteStyle::Necessary,
+            terminator: Terminator::Any(b'\n'),
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl CsvDialect {
+    /// 从生成参数解析CSV格式，未设置的项使用默认值
+    fn from_request(request: &BatchRequest) -> Result<Self> {
+        let delimiter = match request.csv_delimiter.as_str() {
+            "" => b',',
+            "tab" | "\t" => b'\t',
+            s if s.len() == 1 && s.is_ascii() && !matches!(s, "\"" | "\r" | "\n") => {
+                s.as_bytes()[0]
+            }
+            s => {
+                let message = format!("分隔符须为单个ASCII字符或tab: {}", s);
+                return Err(BatchError::parameter("csv_delimiter", message).into());
+            }
+        };
+        let quote_style = match request.csv_quote_style.as_str() {
+            "" | "necessary" => QuoteStyle::Necessary,
+            "always" => QuoteStyle::Always,
+            "non_numeric" => QuoteStyle::NonNumeric,
+            "never" => QuoteStyle::Never,
+            s => {
+                let message = format!("不支持的引号方式: {}", s);
+                return Err(BatchError::parameter("csv_quote_style", message).into());
+            }
+        };
+        let terminator = match request.csv_line_ending.as_str() {
+            "" | "lf" => Terminator::Any(b'\n'),
+            "crlf" => Terminator::CRLF,
+            s => {
+                let message = format!("换行符须为lf或crlf: {}", s);
+                return Err(BatchError::parameter("csv_line_ending", message).into());
+            }
+        };
+        // UTF-16等编码无法编码输出（encoding_rs会改用UTF-8），不予支持
+        let encoding = if request.csv_encoding.is_empty() {
+            UTF_8
+        } else {
+            Encoding::for_label(request.csv_encoding.as_bytes())
+                .filter(|encoding| encoding.output_encoding() == *encoding)
+                .ok_or_else(|| {
+                    let message = format!("不支持的编码: {}", request.csv_encoding);
+                    BatchError::parameter("csv_encoding", message)
+                })?
+        };
+        Ok(Self {
+            delimiter,
+            quote_style,
+            terminator,
+            encoding,
+            bom: request.csv_bom,
+        })
+    }
+}
+
+/// 将UTF-8输出转换为指定编码后写入，无法表示的字符按HTML数字字符引用输出
+/// 跨两次写入的多字节字符会暂存到下次写入
+struct EncodingWriter<W: Write> {
+    inner: W,
+    encoding: &'static Encoding,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).unwrap_or_default();
+        let (encoded, _, _) = self.encoding.encode(text);
+        self.inner.write_all(&encoded)?;
+        self.pending.drain(..valid);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 逐行写入的CSV输出
+struct CsvSink {
+    writer: Writer<Box<dyn Write + Send>>,
+    columns: ColumnLayout,
+}
+
+impl CsvSink {
+    /// 按CSV格式创建并写入表头（及BOM）
+    fn new(
+        output: impl Write + Send + 'static,
+        columns: ColumnLayout,
+        dialect: CsvDialect,
+    ) -> Result<Self> {
+        Self::build(output, columns, dialect, true)
+    }
+
+    /// 追加到已有的CSV，不写表头和BOM
+    fn append(
+        output: impl Write + Send + 'static,
+        columns: ColumnLayout,
+        dialect: CsvDialect,
+    ) -> Result<Self> {
+        Self::build(output, columns, dialect, false)
+    }
+
+    fn build(
+        mut output: impl Write + Send + 'static,
+        columns: ColumnLayout,
+        dialect: CsvDialect,
+        header: bool,
+    ) -> Result<Self> {
+        let output: Box<dyn Write + Send> = if dialect.encoding == UTF_8 {
+            if header && dialect.bom {
+                output.write_all(b"\xEF\xBB\xBF")?;
+            }
+            Box::new(output)
+        } else {
+            Box::new(EncodingWriter {
+                inner: output,
+                encoding: dialect.encoding,
+                pending: Vec::new(),
+            })
+        };
+        let mut writer = WriterBuilder::new()
+            .delimiter(dialect.delimiter)
+            .quote_style(dialect.quote_style)
+            .terminator(dialect.terminator)
+            .from_writer(output);
+        if header {
+            writer.write_record(columns.headers())?;
+        }
+        Ok(Self { writer, columns })
+    }
+
+    fn write(&mut self, result: &CsrResult) -> Result<()> {
+        self.writer.write_record(self.columns.record(result))?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 目标文件已存在且不允许覆盖时报错
+pub(crate) fn check_overwrite(path: &Path, overwrite: bool) -> Result<()> {
+    if !overwrite && path.exists() {
+        let message = format!("输出文件已存在: {}（设置overwrite以覆盖）", path.display());
+        return Err(BatchError::parameter("overwrite", message).into());
+    }
+    Ok(())
+}
+
+/// 写入中的输出文件：先写入临时文件，提交时原子重命名为目标文件；未提交即被丢弃时删除临时文件
+pub(crate) struct PendingFile {
+    target: PathBuf,
+    temp: PathBuf,
+}
+
+impl PendingFile {
+    fn new(target: &Path, overwrite: bool) -> Result<Self> {
+        check_overwrite(target, overwrite)?;
+        let file_name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(Self {
+            target: target.to_path_buf(),
+            temp: target.with_file_name(format!(".{}.partial", file_name)),
+        })
+    }
+
+    /// 创建临时文件
+    fn create(&self) -> Result<File> {
+        Ok(File::create(&self.temp).map_err(|e| BatchError::io(&self.target, e))?)
+    }
+
+    /// 创建仅所有者可读写的临时文件（unix上权限为0600），用于私钥文件
+    fn create_private(&self) -> Result<File> {
+        // 已有的临时文件可能权限较宽，删除后重新创建
+        fs::remove_file(&self.temp).ok();
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        Ok(options
+            .open(&self.temp)
+            .map_err(|e| BatchError::io(&self.target, e))?)
+    }
+
+    /// 重命名为目标文件，调用前须关闭临时文件
+    fn commit(self) -> Result<()> {
+        fs::rename(&self.temp, &self.target).map_err(|e| BatchError::io(&self.target, e))?;
+        Ok(())
+    }
+}
+
+impl Drop for PendingFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.temp).ok();
+    }
+}
+
+/// 原子地写入整个文件（覆盖已有文件）
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let pending = PendingFile::new(path, true)?;
+    pending.create()?.write_all(contents)?;
+    pending.commit()
+}
+
+/// 签发结果CSV：各行先在内存中收集，全部处理完后原子写入输出文件；
+/// 中途失败时已处理的行和其余原样的行照常写入，已签发的证书不会丢失
+pub(crate) struct ResultCsv {
+    path: String,
+    width: usize,
+    writer: Writer<Vec<u8>>,
+}
+
+impl ResultCsv {
+    /// 输出文件已存在且不允许覆盖时报错，须在提交任何请求之前创建
+    pub(crate) fn new(path: &str, overwrite: bool, headers: &StringRecord) -> Result<Self> {
+        check_overwrite(Path::new(path), overwrite)?;
+        let mut writer = Writer::from_writer(Vec::new());
+        writer.write_record(headers)?;
+        Ok(Self {
+            path: path.to_string(),
+            width: headers.len(),
+            writer,
+        })
+    }
+
+    pub(crate) fn write_record<I, T>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.writer.write_record(record)?;
+        Ok(())
+    }
+
+    /// 写入输出文件
+    pub(crate) fn finish(self) -> Result<()> {
+        let content = self.writer.into_inner()?;
+        write_atomic(Path::new(&self.path), &content)
+    }
+
+    /// 处理remaining中的第一行时失败：之前已有processed行签发（或提交）时，将remaining原样写入后写入输出文件，
+    /// 并在错误信息中注明输出文件；没有签发任何行或写入失败时返回原错误
+    pub(crate) fn abort<'a>(
+        mut self,
+        error: anyhow::Error,
+        remaining: impl IntoIterator<Item = &'a StringRecord>,
+        processed: usize,
+    ) -> anyhow::Error {
+        if processed == 0 {
+            return error;
+        }
+        let path = self.path.clone();
+        let written = remaining.into_iter().try_for_each(|record| {
+            let mut fields: Vec<&str> = record.iter().collect();
+            fields.resize(self.width, "");
+            self.write_record(&fields)
+        });
+        if written.and_then(|_| self.finish()).is_err() {
+            return error;
+        }
+        match BatchError::from(error) {
+            BatchError::InvalidRecord { index, message } => {
+                let message = format!("{}（此前签发的{}行已写入 {}）", message, processed, path);
+                BatchError::record(index, message).into()
+            }
+            other => other.into(),
+        }
+    }
+}
+
+/// 按最大行数拆分的CSV输出，拆分时文件名为 `<名称>_001.csv`、`<名称>_002.csv` ……
+struct ChunkedCsv {
+    sink: CsvSink,
+    columns: ColumnLayout,
+    dialect: CsvDialect,
+    output_path: String,
+    /// 当前文件，追加到已有文件时为None
+    pending: Option<PendingFile>,
+    overwrite: bool,
+    /// 每个文件的最大行数，0表示不拆分
+    max_rows: usize,
+    rows: usize,
+    index: usize,
+}
+
+impl ChunkedCsv {
+    /// 创建第一个CSV文件，返回输出和文件路径
+    fn new(
+        output_path: &str,
+        columns: ColumnLayout,
+        dialect: CsvDialect,
+        max_rows: usize,
+        overwrite: bool,
+    ) -> Result<(Self, PathBuf)> {
+        let path = if max_rows == 0 {
+            PathBuf::from(output_path)
+        } else {
+            chunk_path(output_path, 1)
+        };
+        let (sink, pending) = Self::open(&path, &columns, dialect, overwrite)?;
+        let csv = Self {
+            sink,
+            columns,
+            dialect,
+            output_path: output_path.to_string(),
+            pending: Some(pending),
+            overwrite,
+            max_rows,
+            rows: 0,
+            index: 1,
+        };
+        Ok((csv, path))
+    }
+
+    /// 追加到已有的CSV：校验表头一致，返回输出和已有的通用名称；文件不存在时新建
+    fn append(
+        output_path: &str,
+        columns: ColumnLayout,
+        dialect: CsvDialect,
+    ) -> Result<(Self, HashSet<String>)> {
+        let path = Path::new(output_path);
+        if !path.exists() {
+            let (csv, _) = Self::new(output_path, columns, dialect, 0, false)?;
+            return Ok((csv, HashSet::new()));
+        }
+
+        let bytes = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+        let existing = existing_cns(&bytes, &columns, dialect)?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| BatchError::io(path, e))?;
+        // 最后一行没有换行符时先补上，避免与新行连在一起
+        if !bytes.is_empty() && !bytes.ends_with(b"\n") {
+            let line_ending: &[u8] = match dialect.terminator {
+                Terminator::CRLF => b"\r\n",
+                _ => b"\n",
+            };
+            file.write_all(line_ending)?;
+        }
+        let csv = Self {
+            sink: CsvSink::append(file, columns.clone(), dialect)?,
+            columns,
+            dialect,
+            output_path: output_path.to_string(),
+            pending: None,
+            overwrite: false,
+            max_rows: 0,
+            rows: 0,
+            index: 1,
+        };
+        Ok((csv, existing))
+    }
+
+    fn open(
+        path: &Path,
+        columns: &ColumnLayout,
+        dialect: CsvDialect,
+        overwrite: bool,
+    ) -> Result<(CsvSink, PendingFile)> {
+        let pending = PendingFile::new(path, overwrite)?;
+        let sink = CsvSink::new(pending.create()?, columns.clone(), dialect)?;
+        Ok((sink, pending))
+    }
+
+    /// 写入单条结果，当前文件已满时先切换到下一个文件并返回其路径
+    fn write(&mut self, result: &CsrResult) -> Result<Option<PathBuf>> {
+        let mut next = None;
+        if self.max_rows > 0 && self.rows == self.max_rows {
+            self.sink.flush()?;
+            self.index += 1;
+            let path = chunk_path(&self.output_path, self.index);
+            let (sink, pending) = Self::open(&path, &self.columns, self.dialect, self.overwrite)?;
+            // 关闭已写满的文件后再提交
+            drop(std::mem::replace(&mut self.sink, sink));
+            if let Some(full) = self.pending.replace(pending) {
+                full.commit()?;
+            }
+            self.rows = 0;
+            next = Some(path);
+        }
+        self.sink.write(result)?;
+        self.rows += 1;
+        Ok(next)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.sink.flush()
+    }
+
+    /// 正在写入的文件
+    fn current_file(&self) -> PathBuf {
+        match &self.pending {
+            Some(pending) => pending.temp.clone(),
+            None => PathBuf::from(&self.output_path),
+        }
+    }
+
+    /// 关闭并提交当前文件
+    fn finish(mut self) -> Result<()> {
+        self.sink.flush()?;
+        let pending = self.pending.take();
+        drop(self);
+        match pending {
+            Some(pending) => pending.commit(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 按参数中的分隔符和编码读取已生成的CSV（BOM自动去除）
+pub(crate) fn read_output_csv(
+    path: &str,
+    request: &BatchRequest,
+) -> Result<csv::Reader<io::Cursor<Vec<u8>>>> {
+    let dialect = CsvDialect::from_request(request)?;
+    let bytes = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+    let (text, _, _) = dialect.encoding.decode(&bytes);
+    Ok(ReaderBuilder::new()
+        .delimiter(dialect.delimiter)
+        .from_reader(io::Cursor::new(text.into_owned().into_bytes())))
+}
+
+/// 读取已有CSV中的通用名称，表头须与当前输出列一致
+/// 有cn列时按cn列匹配，否则按subject列中的CN属性匹配
+fn existing_cns(
+    bytes: &[u8],
+    columns: &ColumnLayout,
+    dialect: CsvDialect,
+) -> Result<HashSet<String>> {
+    let (text, _, _) = dialect.encoding.decode(bytes);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(dialect.delimiter)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    if !headers.iter().map(String::as_str).eq(columns.headers()) {
+        let message = format!("已有CSV的表头与当前输出列不一致: {}", headers.join(","));
+        return Err(BatchError::parameter("append", message).into());
+    }
+
+    let position = |column: Column| columns.columns.iter().position(|(c, _)| *c == column);
+    let mut cns = HashSet::new();
+    if let Some(cn_col) = position(Column::Cn) {
+        for record in reader.records() {
+            cns.insert(record?.get(cn_col).unwrap_or("").to_string());
+        }
+    } else if let Some(subject_col) = position(Column::Subject) {
+        for record in reader.records() {
+            let record = record?;
+            let subject = parse_subject(record.get(subject_col).unwrap_or("")).unwrap_or_default();
+            if let Some(cn) = subject.into_iter().find(|attr| attr.attr == "CN") {
+                cns.insert(cn.value);
+            }
+        }
+    } else {
+        let message = "追加到已有CSV时须输出cn或subject列";
+        return Err(BatchError::parameter("append", message).into());
+    }
+    Ok(cns)
+}
+
+/// 拆分后第index个CSV文件的路径（index从1开始）
+fn chunk_path(output_path: &str, index: usize) -> PathBuf {
+    let path = Path::new(output_path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("csv");
+    path.with_file_name(format!("{}_{:03}.{}", stem, index, extension))
+}
+
+/// 逐行写入的Excel输出
+/// 结果工作表以常量内存模式逐行写入临时文件，结束时追加汇总工作表（条目数、密钥类型和生成时间）
+struct XlsxSink {
+    workbook: Workbook,
+    pending: PendingFile,
+    columns: ColumnLayout,
+    pem_format: Format,
+    row: u32,
+    key_types: Vec<String>,
+    started: DateTime<Local>,
+}
+
+impl XlsxSink {
+    /// 创建工作簿并写入表头，PEM列自动换行
+    fn new(pending: PendingFile, columns: ColumnLayout) -> Result<Self> {
+        let mut workbook = Workbook::new();
+        let header_format = Format::new().set_bold();
+        let pem_format = Format::new().set_text_wrap().set_align(FormatAlign::Top);
+
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        worksheet.set_name("CSR")?;
+        for (col, (column, header)) in columns.columns.iter().enumerate() {
+            let col = col as u16;
+            let width = if column.is_pem() {
+                XLSX_PEM_COLUMN_WIDTH
+            } else {
+                XLSX_COLUMN_WIDTH
+            };
+            worksheet.set_column_width(col, width)?;
+            worksheet.write_string_with_format(0, col, header, &header_format)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+
+        Ok(Self {
+            workbook,
+            pending,
+            columns,
+            pem_format,
+            row: 0,
+            key_types: Vec::new(),
+            started: Local::now(),
+        })
+    }
+
+    fn write(&mut self, result: &CsrResult) -> Result<()> {
+        self.row += 1;
+        let worksheet = self.workbook.worksheet_from_index(0)?;
+        for (col, (column, _)) in self.columns.columns.iter().enumerate() {
+            let value = column.value(result);
+            if column.is_pem() {
+                worksheet.write_string_with_format(
+                    self.row,
+                    col as u16,
+                    value,
+                    &self.pem_format,
+                )?;
+            } else {
+                worksheet.write_string(self.row, col as u16, value)?;
+            }
+        }
+        if !self.key_types.contains(&result.key_pair_type) {
+            self.key_types.push(result.key_pair_type.clone());
+        }
+        Ok(())
+    }
+
+    /// 追加汇总工作表并保存
+    fn finish(mut self) -> Result<()> {
+        let elapsed = (Local::now() - self.started).num_milliseconds() as f64 / 1000.0;
+        let label_format = Format::new().set_bold();
+        let summary = self.workbook.add_worksheet();
+        summary.set_name("汇总")?;
+        summary.set_column_width(0, 12)?;
+        summary.set_column_width(1, 40)?;
+        let rows = [
+            ("条目数", self.row.to_string()),
+            ("密钥类型", self.key_types.join(", ")),
+            (
+                "生成时间",
+                self.started.format("%Y-%m-%d %H:%M:%S").to_string(),
+            ),
+            ("耗时(秒)", format!("{:.1}", elapsed)),
+        ];
+        for (row, (label, value)) in rows.into_iter().enumerate() {
+            summary.write_string_with_format(row as u32, 0, label, &label_format)?;
+            summary.write_string(row as u32, 1, value)?;
+        }
+        self.workbook
+            .save(&self.pending.temp)
+            .map_err(|e| BatchError::io(&self.pending.target, e))?;
+        self.pending.commit()
+    }
+}
+
+/// 逐条写入的JSON输出，对象的字段为cn和与CSV相同的列（表头为字段名）
+/// json为对象数组，ndjson为每行一个对象，便于脚本逐行读取
+struct JsonSink {
+    writer: BufWriter<File>,
+    pending: PendingFile,
+    columns: ColumnLayout,
+    ndjson: bool,
+    count: usize,
+}
+
+impl JsonSink {
+    fn new(pending: PendingFile, columns: ColumnLayout, ndjson: bool) -> Result<Self> {
+        let mut writer = BufWriter::new(pending.create()?);
+        if !ndjson {
+            writer.write_all(b"[")?;
+        }
+        Ok(Self {
+            writer,
+            pending,
+            columns,
+            ndjson,
+            count: 0,
+        })
+    }
+
+    fn write(&mut self, result: &CsrResult) -> Result<()> {
+        let mut object = Map::new();
+        object.insert("cn".to_string(), Value::from(result.cn.as_str()));
+        for (header, value) in self.columns.headers().zip(self.columns.record(result)) {
+            object.insert(header.to_string(), Value::from(value));
+        }
+
+        if self.ndjson {
+            serde_json::to_writer(&mut self.writer, &object)?;
+            self.writer.write_all(b"\n")?;
+        } else {
+            self.writer
+                .write_all(if self.count == 0 { b"\n  " } else { b",\n  " })?;
+            serde_json::to_writer(&mut self.writer, &object)?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if !self.ndjson {
+            self.writer.write_all(b"\n]\n")?;
+        }
+        self.flush()?;
+        drop(self.writer);
+        self.pending.commit()
+    }
+}
+
+/// ZIP条目选项，设置密码时使用AES-256加密
+fn zip_file_options(password: &str) -> FileOptions<'_, ()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    if password.is_empty() {
+        options
+    } else {
+        options.with_aes_encryption(AesMode::Aes256, password)
+    }
+}
+
+/// 临时文件，丢弃时删除
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// 逐条写入的ZIP输出
+/// 清单CSV先写入临时文件，结束时作为最后一个条目加入压缩包；中途失败时临时文件随之删除
+struct ZipSink {
+    zip: ZipWriter<File>,
+    pending: PendingFile,
+    manifest: CsvSink,
+    manifest_path: TempFile,
+    password: String,
+    /// 是否写入 .key 文件（拆分私钥时为否）
+    include_keys: bool,
+    csr_encoding: CsrEncoding,
+}
+
+impl ZipSink {
+    fn new(
+        pending: PendingFile,
+        password: &str,
+        columns: ColumnLayout,
+        dialect: CsvDialect,
+        include_keys: bool,
+        csr_encoding: CsrEncoding,
+    ) -> Result<Self> {
+        let manifest_path = TempFile(pending.target.with_extension("manifest.tmp"));
+        let manifest = CsvSink::new(File::create(&manifest_path.0)?, columns, dialect)?;
+        Ok(Self {
+            zip: ZipWriter::new(pending.create()?),
+            pending,
+            manifest,
+            manifest_path,
+            password: password.to_string(),
+            include_keys,
+            csr_encoding,
+        })
+    }
+
+    fn write(&mut self, name: &str, result: &CsrResult) -> Result<()> {
+        let options = zip_file_options(&self.password);
+        self.zip.start_file(format!("{}.csr", name), options)?;
+        self.zip
+            .write_all(&self.csr_encoding.file_content(&result.csr_pem)?)?;
+        // 私钥保存在密钥存储中时不输出 .key 文件
+        if self.include_keys && !result.private_key_pem.is_empty() {
+            self.zip.start_file(format!("{}.key", name), options)?;
+            self.zip.write_all(result.private_key_pem.as_bytes())?;
+        }
+        if let Some(pq) = &result.pq {
+            self.zip.start_file(format!("{}.pq.csr", name), options)?;
+            self.zip
+                .write_all(&self.csr_encoding.file_content(&pq.csr_pem)?)?;
+            if self.include_keys {
+                self.zip.start_file(format!("{}.pq.key", name), options)?;
+                self.zip.write_all(pq.private_key_pem.as_bytes())?;
+            }
+        }
+        self.manifest.write(result)
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.manifest.flush()?;
+        let options = zip_file_options(&self.password);
+        self.zip.start_file(ZIP_MANIFEST_NAME, options)?;
+        io::copy(&mut File::open(&self.manifest_path.0)?, &mut self.zip)?;
+        drop(self.zip.finish()?);
+        self.pending.commit()
+    }
+}
+
+/// 拆分私钥时单独保存私钥的ZIP压缩包
+struct KeyArchive {
+    zip: ZipWriter<File>,
+    pending: PendingFile,
+    password: String,
+}
+
+impl KeyArchive {
+    fn new(pending: PendingFile, password: &str) -> Result<Self> {
+        Ok(Self {
+            zip: ZipWriter::new(pending.create()?),
+            pending,
+            password: password.to_string(),
+        })
+    }
+
+    fn write(&mut self, name: &str, result: &CsrResult) -> Result<()> {
+        let options = zip_file_options(&self.password);
+        if !result.private_key_pem.is_empty() {
+            self.zip.start_file(format!("{}.key", name), options)?;
+            self.zip.write_all(result.private_key_pem.as_bytes())?;
+        }
+        if let Some(pq) = &result.pq {
+            self.zip.start_file(format!("{}.pq.key", name), options)?;
+            self.zip.write_all(pq.private_key_pem.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        drop(self.zip.finish()?);
+        self.pending.commit()
+    }
+}
+
+/// 拆分私钥时私钥压缩包的路径：输出文件同目录下文件名加 _keys 后缀
+fn key_archive_path(output_path: &str) -> PathBuf {
+    let path = Path::new(output_path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    path.with_file_name(format!("{}_keys.zip", stem))
+}
+
+/// 生成结果的流式输出，按输出方式将每条结果立即写入文件
+pub struct OutputWriter {
+    csv: Option<ChunkedCsv>,
+    files_dir: Option<PathBuf>,
+    zip: Option<ZipSink>,
+    xlsx: Option<XlsxSink>,
+    json: Option<JsonSink>,
+    job_db: Option<JobDatabase>,
+    keys: Option<KeyArchive>,
+    manifest: Option<Manifest>,
+    csr_encoding: CsrEncoding,
+    /// 追加模式下已有CSV中的通用名称
+    existing_cns: HashSet<String>,
+    /// 是否覆盖已有的单CSR文件
+    overwrite: bool,
+    /// 已使用的单CSR文件名（小写），用于检测重名
+    file_names: HashSet<String>,
+    output_path: String,
+}
+
+impl OutputWriter {
+    /// 按生成参数中的输出方式创建输出文件
+    pub fn create(request: &BatchRequest, columns: CsvColumns) -> Result<Self> {
+        let mode = OutputMode::from_str(&request.output_mode)?;
+        let dialect = CsvDialect::from_request(request)?;
+        let mut columns = ColumnLayout::new(columns, &request.columns)?;
+        let csr_encoding = CsrEncoding::from_str(&request.csr_encoding)?;
+        let mut writer = Self {
+            csv: None,
+            files_dir: None,
+            zip: None,
+            xlsx: None,
+            json: None,
+            job_db: None,
+            keys: None,
+            manifest: Manifest::from_request(request)?,
+            csr_encoding,
+            existing_cns: HashSet::new(),
+            overwrite: request.overwrite,
+            file_names: HashSet::new(),
+            output_path: request.output_path.clone(),
+        };
+        // 错误报告在生成结束后才写入，已存在且不允许覆盖时提前报错
+        if request.error_report {
+            let report_path = derived_csv_path(&request.output_path, "errors");
+            check_overwrite(Path::new(&report_path), request.overwrite)?;
+        }
+
+        if request.append {
+            if !matches!(mode, OutputMode::Csv | OutputMode::Both) {
+                let message = "仅CSV输出支持追加到已有文件";
+                return Err(BatchError::parameter("append", message).into());
+            }
+            if request.max_rows_per_file > 0 {
+                let message = "追加到已有文件时不能按行数拆分";
+                return Err(BatchError::parameter("append", message).into());
+            }
+        }
+        if !request.checkpoint_path.is_empty() && !request.supports_checkpoint() {
+            let message = "断点续传仅支持不拆分的CSV输出（csv/both），且不能拆分私钥或同时写入SQLite任务数据库";
+            return Err(BatchError::parameter("checkpoint_path", message).into());
+        }
+
+        if matches!(mode, OutputMode::Files | OutputMode::Both | OutputMode::Zip)
+            || request.split_keys
+        {
+            check_file_name_template(&request.file_name_template)?;
+        }
+
+        if request.split_keys {
+            if mode == OutputMode::Sqlite {
+                let message = "仅写入SQLite任务数据库时不能拆分私钥";
+                return Err(BatchError::parameter("split_keys", message).into());
+            }
+            if request.zip_password.is_empty() && request.key_passphrase.is_empty() {
+                let message = "拆分私钥时须设置ZIP密码或私钥加密口令";
+                return Err(BatchError::parameter("zip_password", message).into());
+            }
+            columns = columns.without_private_keys(&request.columns)?;
+            let keys_path = key_archive_path(&request.output_path);
+            let pending = PendingFile::new(&keys_path, request.overwrite)?;
+            writer.keys = Some(KeyArchive::new(pending, &request.zip_password)?);
+            writer.track(&keys_path);
+        }
+
+        // 任务数据库可与其他输出方式同时使用
+        if mode == OutputMode::Sqlite || !request.sqlite_path.is_empty() {
+            let db_path = if request.sqlite_path.is_empty() {
+                Path::new(&request.output_path).with_extension("db")
+            } else {
+                PathBuf::from(&request.sqlite_path)
+            };
+            if mode == OutputMode::Sqlite {
+                writer.output_path = db_path.to_string_lossy().to_string();
+            }
+            writer.job_db = Some(JobDatabase::open(&db_path, request, &writer.output_path)?);
+            writer.track(&db_path);
+        }
+        if mode == OutputMode::Sqlite {
+            return Ok(writer);
+        }
+
+        if mode == OutputMode::Zip {
+            let zip_path = Path::new(&request.output_path).with_extension("zip");
+            writer.zip = Some(ZipSink::new(
+                PendingFile::new(&zip_path, request.overwrite)?,
+                &request.zip_password,
+                columns,
+                dialect,
+                !request.split_keys,
+                csr_encoding,
+            )?);
+            writer.track(&zip_path);
+            writer.output_path = zip_path.to_string_lossy().to_string();
+            return Ok(writer);
+        }
+        if mode == OutputMode::Xlsx {
+            let xlsx_path = Path::new(&request.output_path).with_extension("xlsx");
+            let pending = PendingFile::new(&xlsx_path, request.overwrite)?;
+            writer.xlsx = Some(XlsxSink::new(pending, columns)?);
+            writer.track(&xlsx_path);
+            writer.output_path = xlsx_path.to_string_lossy().to_string();
+            return Ok(writer);
+        }
+        if matches!(mode, OutputMode::Json | OutputMode::Ndjson) {
+            let extension = if mode == OutputMode::Json {
+                "json"
+            } else {
+                "ndjson"
+            };
+            let json_path = Path::new(&request.output_path).with_extension(extension);
+            let pending = PendingFile::new(&json_path, request.overwrite)?;
+            writer.json = Some(JsonSink::new(pending, columns, mode == OutputMode::Ndjson)?);
+            writer.track(&json_path);
+            writer.output_path = json_path.to_string_lossy().to_string();
+            return Ok(writer);
+        }
+
+        if matches!(mode, OutputMode::Csv | OutputMode::Both) {
+            let (csv, csv_path) = if request.append {
+                let (csv, existing) = ChunkedCsv::append(&request.output_path, columns, dialect)?;
+                writer.existing_cns = existing;
+                (csv, PathBuf::from(&request.output_path))
+            } else {
+                ChunkedCsv::new(
+                    &request.output_path,
+                    columns,
+                    dialect,
+                    request.max_rows_per_file,
+                    request.overwrite,
+                )?
+            };
+            writer.csv = Some(csv);
+            writer.track(&csv_path);
+            if mode == OutputMode::Csv {
+                writer.output_path = csv_path.to_string_lossy().to_string();
+            }
+        }
+        if matches!(mode, OutputMode::Files | OutputMode::Both) {
+            let output_dir = if request.output_dir.is_empty() {
+                Path::new(&request.output_path).with_extension("")
+            } else {
+                PathBuf::from(&request.output_dir)
+            };
+            fs::create_dir_all(&output_dir).map_err(|e| BatchError::io(&output_dir, e))?;
+            if mode == OutputMode::Files {
+                writer.output_path = output_dir.to_string_lossy().to_string();
+            }
+            writer.files_dir = Some(output_dir);
+        }
+
+        Ok(writer)
+    }
+
+    /// 写入单条结果
+    pub fn write(&mut self, result: &CsrResult) -> Result<()> {
+        // 非PEM编码时CSR统一转换为单行Base64，DER文件在写入时再解码
+        let encoded;
+        let result = if self.csr_encoding == CsrEncoding::Pem {
+            result
+        } else {
+            encoded = CsrResult {
+                csr_pem: pem_to_base64(&result.csr_pem),
+                pq: result.pq.as_ref().map(|pq| PqCsr {
+                    csr_pem: pem_to_base64(&pq.csr_pem),
+                    ..pq.clone()
+                }),
+                ..result.clone()
+            };
+            &encoded
+        };
+        if let Some(csv) = &mut self.csv {
+            if let Some(path) = csv.write(result)? {
+                self.track(&path);
+            }
+        }
+        // 按文件名模板命名的文件不区分大小写检测重名，避免在Windows/macOS上互相覆盖
+        let name = sanitize_file_name(&result.file_name);
+        let per_file = self.files_dir.is_some() || self.zip.is_some() || self.keys.is_some();
+        if per_file && !self.file_names.insert(name.to_lowercase()) {
+            let message = format!("通用名称 {} 的文件名 {} 与之前的条目重复", result.cn, name);
+            return Err(BatchError::parameter("file_name_template", message).into());
+        }
+        if let Some(dir) = &self.files_dir {
+            let include_keys = self.keys.is_none();
+            let csr = self.csr_encoding.file_content(&result.csr_pem)?;
+            let mut files = vec![(format!("{}.csr", name), csr)];
+            if include_keys && !result.private_key_pem.is_empty() {
+                let key = result.private_key_pem.as_bytes().to_vec();
+                files.push((format!("{}.key", name), key));
+            }
+            if let Some(pq) = &result.pq {
+                let csr = self.csr_encoding.file_content(&pq.csr_pem)?;
+                files.push((format!("{}.pq.csr", name), csr));
+                if include_keys {
+                    let key = pq.private_key_pem.as_bytes().to_vec();
+                    files.push((format!("{}.pq.key", name), key));
+                }
+            }
+            for (file_name, content) in files {
+                let path = dir.join(&file_name);
+                let pending = PendingFile::new(&path, self.overwrite)?;
+                let mut file = if file_name.ends_with(".key") {
+                    pending.create_private()?
+                } else {
+                    pending.create()?
+                };
+                file.write_all(&content)?;
+                drop(file);
+                pending.commit()?;
+                if let Some(manifest) = &mut self.manifest {
+                    manifest.add_file(path);
+                }
+            }
+        }
+        if let Some(zip) = &mut self.zip {
+            zip.write(&name, result)?;
+        }
+        if let Some(xlsx) = &mut self.xlsx {
+            xlsx.write(result)?;
+        }
+        if let Some(json) = &mut self.json {
+            json.write(result)?;
+        }
+        if let Some(job_db) = &mut self.job_db {
+            job_db.write(result)?;
+        }
+        if let Some(keys) = &mut self.keys {
+            keys.write(&name, result)?;
+        }
+        if let Some(manifest) = &mut self.manifest {
+            manifest.add_row(result)?;
+        }
+        Ok(())
+    }
+
+    /// 正在写入的CSV文件（新建时为临时文件），没有CSV输出时为None
+    pub(crate) fn csv_file(&self) -> Option<PathBuf> {
+        self.csv.as_ref().map(ChunkedCsv::current_file)
+    }
+
+    /// 追加模式下已有CSV中的通用名称，生成时跳过这些条目
+    pub(crate) fn take_existing_cns(&mut self) -> HashSet<String> {
+        std::mem::take(&mut self.existing_cns)
+    }
+
+    /// 启用清单时登记输出文件
+    fn track(&mut self, path: &Path) {
+        if let Some(manifest) = &mut self.manifest {
+            manifest.add_file(path);
+        }
+    }
+
+    /// 将缓冲的数据写入磁盘
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(csv) = &mut self.csv {
+            csv.flush()?;
+        }
+        if let Some(json) = &mut self.json {
+            json.flush()?;
+        }
+        if let Some(job_db) = &mut self.job_db {
+            job_db.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 完成输出，返回最终输出路径
+    pub fn finish(mut self) -> Result<String> {
+        self.flush()?;
+        if let Some(csv) = self.csv.take() {
+            csv.finish()?;
+        }
+        if let Some(zip) = self.zip.take() {
+            zip.finish()?;
+        }
+        if let Some(xlsx) = self.xlsx.take() {
+            xlsx.finish()?;
+        }
+        if let Some(json) = self.json.take() {
+            json.finish()?;
+        }
+        if let Some(job_db) = self.job_db.take() {
+            job_db.finish()?;
+        }
+        if let Some(keys) = self.keys.take() {
+            keys.finish()?;
+        }
+        // 清单须在其他输出全部写完后计算哈希
+        if let Some(manifest) = self.manifest.take() {
+            manifest.finish()?;
+        }
+        Ok(self.output_path)
+    }
+}
+
+/// 由输入CSV路径派生的输出CSV路径：同目录下文件名加 _<suffix> 后缀
+pub(crate) fn derived_csv_path(input_path: &str, suffix: &str) -> String {
+    let path = Path::new(input_path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    path.with_file_name(format!("{}_{}.csv", stem, suffix))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 原子地写入错误报告CSV，每个失败条目一行：序号（从1开始）、通用名称和错误信息
+pub(crate) fn write_error_report(
+    path: &str,
+    failures: &[ItemFailure],
+    overwrite: bool,
+) -> Result<()> {
+    let pending = PendingFile::new(Path::new(path), overwrite)?;
+    let mut writer = Writer::from_writer(pending.create()?);
+    writer.write_record(["index", "cn", "error"])?;
+    for failure in failures {
+        writer.write_record([
+            (failure.index + 1).to_string(),
+            failure.cn.clone(),
+            failure.error.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    drop(writer);
+    pending.commit()
+}
+
+/// 文件名模板须包含能区分条目的变量，否则所有条目的文件名相同
+fn check_file_name_template(template: &str) -> Result<()> {
+    let stem = file_name_stem(template);
+    if !["{CN}", "{INDEX", "{UUID}", "{RAND:"]
+        .iter()
+        .any(|variable| stem.contains(variable))
+    {
+        let message = "须包含{CN}、{INDEX}、{UUID}或{RAND:n}等能区分条目的模板变量";
+        return Err(BatchError::parameter("file_name_template", message).into());
+    }
+    Ok(())
+}
+
+/// 将文件名中不能用于路径的字符替换为下划线
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("YDL0001"), "YDL0001");
+        assert_eq!(sanitize_file_name("a/b\\c:d"), "a_b_c_d");
+    }
+
+    /// 用于输出测试的生成结果
+    fn sample_result() -> CsrResult {
+        CsrResult {
+            cn: "YDL0001".to_string(),
+            subject: "CN=[YDL0001]".to_string(),
+            sign_hash_alg: "SHA256".to_string(),
+            not_before: String::new(),
+            not_after: String::new(),
+            unique_id: String::new(),
+            sans: String::new(),
+            csr_pem: "CSR".to_string(),
+            key_pair_type: "EC_P-256".to_string(),
+            private_key_pem: "KEY".to_string(),
+            key_encryption: String::new(),
+            pq: None,
+            key_container: String::new(),
+            file_name: "YDL0001".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_write_encrypted_zip() {
+        let result = sample_result();
+        let dir = std::env::temp_dir().join("csr_batch_zip_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let params = BatchRequest {
+            output_path: dir.join("out.csv").to_string_lossy().to_string(),
+            output_mode: "zip".to_string(),
+            zip_password: "secret".to_string(),
+            ..Default::default()
+        };
+
+        // 未完成时丢弃不留下压缩包和清单临时文件
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&result).unwrap();
+        drop(writer);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&result).unwrap();
+        let zip_path = writer.finish().unwrap();
+        assert!(zip_path.ends_with(".zip"));
+
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 3);
+        let mut key = String::new();
+        io::Read::read_to_string(
+            &mut archive.by_name_decrypt("YDL0001.key", b"secret").unwrap(),
+            &mut key,
+        )
+        .unwrap();
+        assert_eq!(key, "KEY");
+        assert!(archive.by_name_decrypt("YDL0001.key", b"wrong").is_err());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_write_xlsx() {
+        let dir = std::env::temp_dir().join("csr_batch_xlsx_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let params = BatchRequest {
+            output_path: dir.join("out.csv").to_string_lossy().to_string(),
+            output_mode: "xlsx".to_string(),
+            ..Default::default()
+        };
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&sample_result()).unwrap();
+        let xlsx_path = writer.finish().unwrap();
+        assert!(xlsx_path.ends_with(".xlsx"));
+
+        let mut archive = zip::ZipArchive::new(File::open(&xlsx_path).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut content).unwrap();
+            content
+        };
+        assert!(read("xl/workbook.xml").contains("汇总"));
+        assert!(read("xl/worksheets/sheet1.xml").contains("CN=[YDL0001]"));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_write_json_and_ndjson() {
+        for (mode, extension) in [("json", "json"), ("ndjson", "ndjson")] {
+            let path = std::env::temp_dir().join(format!("csr_batch_{}_test.csv", mode));
+            let params = BatchRequest {
+                output_path: path.to_string_lossy().to_string(),
+                output_mode: mode.to_string(),
+                ..Default::default()
+            };
+            let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+            writer.write(&sample_result()).unwrap();
+            writer.write(&sample_result()).unwrap();
+            let output_path = writer.finish().unwrap();
+            assert!(output_path.ends_with(extension));
+
+            let content = fs::read_to_string(&output_path).unwrap();
+            let objects: Vec<Value> = if mode == "json" {
+                serde_json::from_str(&content).unwrap()
+            } else {
+                content
+                    .lines()
+                    .map(|line| serde_json::from_str(line).unwrap())
+                    .collect()
+            };
+            assert_eq!(objects.len(), 2);
+            assert_eq!(objects[0]["cn"], "YDL0001");
+            assert_eq!(objects[0]["csr"], "CSR");
+            assert_eq!(objects[1]["keyPairType"], "EC_P-256");
+            fs::remove_file(output_path).ok();
+        }
+    }
+
+    #[test]
+    fn test_result_csv() {
+        let path = std::env::temp_dir().join("csr_batch_result_csv_test.csv");
+        fs::remove_file(&path).ok();
+        let output_path = path.to_string_lossy().to_string();
+        let headers = StringRecord::from(vec!["cn", "certificate"]);
+        let records = [
+            StringRecord::from(vec!["dev1"]),
+            StringRecord::from(vec!["dev2"]),
+            StringRecord::from(vec!["dev3"]),
+        ];
+
+        // 第一行即失败时不写入
+        let output = ResultCsv::new(&output_path, false, &headers).unwrap();
+        let error = BatchError::record(1, "签发失败").into();
+        let error = BatchError::from(output.abort(error, &records, 0));
+        assert_eq!(error, BatchError::record(1, "签发失败"));
+        assert!(!path.exists());
+
+        // 中途失败时已签发的行和其余行照常写入
+        let mut output = ResultCsv::new(&output_path, false, &headers).unwrap();
+        output.write_record(["dev1", "CERT"]).unwrap();
+        let error = BatchError::record(2, "签发失败").into();
+        let error = output.abort(error, &records[1..], 1).to_string();
+        assert!(error.contains(&output_path), "{}", error);
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "cn,certificate\ndev1,CERT\ndev2,\ndev3,\n");
+
+        assert!(ResultCsv::new(&output_path, false, &headers).is_err());
+        let mut output = ResultCsv::new(&output_path, true, &headers).unwrap();
+        output.write_record(["dev1", "CERT"]).unwrap();
+        output.finish().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "cn,certificate\ndev1,CERT\n");
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_csv_dialect() {
+        let path = std::env::temp_dir().join("csr_batch_dialect_test.csv");
+        fs::remove_file(&path).ok();
+        let mut params = BatchRequest {
+            output_path: path.to_string_lossy().to_string(),
+            csv_delimiter: ";".to_string(),
+            csv_line_ending: "crlf".to_string(),
+            csv_encoding: "gbk".to_string(),
+            ..Default::default()
+        };
+        let result = CsrResult {
+            subject: "CN=[设备0001]".to_string(),
+            ..sample_result()
+        };
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&result).unwrap();
+        writer.finish().unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let (content, _, had_errors) = encoding_rs::GBK.decode(&bytes);
+        assert!(!had_errors);
+        assert!(content.starts_with("subject;signHashAlg;"));
+        assert!(content.contains("\r\nCN=[设备0001];SHA256;"));
+
+        params.csv_encoding = String::new();
+        params.csv_bom = true;
+        params.overwrite = true;
+        let writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.finish().unwrap();
+        assert!(fs::read(&path)
+            .unwrap()
+            .starts_with(b"\xEF\xBB\xBFsubject;"));
+
+        params.csv_encoding = "utf-16le".to_string();
+        assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_custom_columns() {
+        let dir = std::env::temp_dir().join("csr_batch_columns_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.csv");
+        let mut params = BatchRequest {
+            output_path: path.to_string_lossy().to_string(),
+            columns: "cn=设备编号, CSR, keyPairType".to_string(),
+            ..Default::default()
+        };
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&sample_result()).unwrap();
+        writer.finish().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "设备编号,csr,keyPairType\nYDL0001,CSR,EC_P-256\n");
+
+        for columns in ["cn,cn", "secret", " , "] {
+            params.columns = columns.to_string();
+            assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        }
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_split_keys() {
+        let path = std::env::temp_dir().join("csr_batch_split_test.csv");
+        let mut params = BatchRequest {
+            output_path: path.to_string_lossy().to_string(),
+            split_keys: true,
+            zip_password: "secret".to_string(),
+            ..Default::default()
+        };
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&sample_result()).unwrap();
+        writer.finish().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("privateKey") && !content.contains("KEY"));
+
+        let keys_path = key_archive_path(&params.output_path);
+        assert!(keys_path.ends_with("csr_batch_split_test_keys.zip"));
+        let mut archive = zip::ZipArchive::new(File::open(&keys_path).unwrap()).unwrap();
+        let mut key = String::new();
+        io::Read::read_to_string(
+            &mut archive.by_name_decrypt("YDL0001.key", b"secret").unwrap(),
+            &mut key,
+        )
+        .unwrap();
+        assert_eq!(key, "KEY");
+
+        params.columns = "cn,privateKey".to_string();
+        assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        params.columns = String::new();
+        params.zip_password = String::new();
+        assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        fs::remove_file(path).ok();
+        fs::remove_file(keys_path).ok();
+    }
+
+    #[test]
+    fn test_file_name_collision() {
+        let dir = std::env::temp_dir().join("csr_batch_file_name_test");
+        fs::remove_dir_all(&dir).ok();
+        let mut params = BatchRequest {
+            output_path: dir.join("out.csv").to_string_lossy().to_string(),
+            output_mode: "files".to_string(),
+            file_name_template: "{DATE}_{KEYTYPE}.csr".to_string(),
+            ..Default::default()
+        };
+        assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        assert_eq!(file_name_stem("{CN}_{KEYTYPE}.CSR"), "{CN}_{KEYTYPE}");
+
+        params.file_name_template = "{CN}_{KEYTYPE}.csr".to_string();
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&sample_result()).unwrap();
+        let duplicate = CsrResult {
+            file_name: "ydl0001".to_string(),
+            ..sample_result()
+        };
+        assert!(writer.write(&duplicate).is_err());
+        assert!(dir.join("out").join("YDL0001.csr").exists());
+        // 私钥文件仅所有者可读写
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = fs::metadata(dir.join("out").join("YDL0001.key")).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_csr_encoding() {
+        let key = openssl::pkey::PKey::generate_ed25519().unwrap();
+        let mut builder = openssl::x509::X509Req::builder().unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .sign(&key, openssl::hash::MessageDigest::null())
+            .unwrap();
+        let csr_pem = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+        let path = std::env::temp_dir().join("csr_batch_encoding_test.csv");
+        let mut params = BatchRequest {
+            output_path: path.to_string_lossy().to_string(),
+            output_mode: "both".to_string(),
+            csr_encoding: "der".to_string(),
+            ..Default::default()
+        };
+        let result = CsrResult {
+            csr_pem: csr_pem.clone(),
+            ..sample_result()
+        };
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&result).unwrap();
+        writer.finish().unwrap();
+        let der = fs::read(path.with_extension("").join("YDL0001.csr")).unwrap();
+        let req = openssl::x509::X509Req::from_der(&der).unwrap();
+        assert_eq!(req.to_pem().unwrap(), csr_pem.as_bytes());
+        let content = fs::read_to_string(&path).unwrap();
+        let base64 = pem_to_base64(&csr_pem);
+        assert!(!base64.contains('\n') && content.contains(&format!(",{},", base64)));
+
+        params.csr_encoding = "p7".to_string();
+        assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        fs::remove_dir_all(path.with_extension("")).ok();
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_max_rows_per_file() {
+        let dir = std::env::temp_dir().join("csr_batch_chunk_test");
+        fs::create_dir_all(&dir).unwrap();
+        let params = BatchRequest {
+            output_path: dir.join("out.csv").to_string_lossy().to_string(),
+            max_rows_per_file: 2,
+            ..Default::default()
+        };
+
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        for _ in 0..5 {
+            writer.write(&sample_result()).unwrap();
+        }
+        let output_path = writer.finish().unwrap();
+        assert!(output_path.ends_with("out_001.csv"));
+        for (name, rows) in [("out_001.csv", 2), ("out_002.csv", 2), ("out_003.csv", 1)] {
+            let mut reader = csv::Reader::from_path(dir.join(name)).unwrap();
+            assert_eq!(reader.records().count(), rows);
+        }
+        assert!(!dir.join("out.csv").exists() && !dir.join("out_004.csv").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_overwrite_refused() {
+        let dir = std::env::temp_dir().join("csr_batch_overwrite_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.json");
+        let mut params = BatchRequest {
+            output_path: path.to_string_lossy().to_string(),
+            output_mode: "json".to_string(),
+            ..Default::default()
+        };
+
+        // 未完成时只有临时文件，丢弃后不留下任何文件
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&sample_result()).unwrap();
+        assert!(!path.exists());
+        drop(writer);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.finish().unwrap();
+        let content = fs::read(&path).unwrap();
+        assert!(OutputWriter::create(&params, CsvColumns::default()).is_err());
+        assert_eq!(fs::read(&path).unwrap(), content);
+
+        params.overwrite = true;
+        let mut writer = OutputWriter::create(&params, CsvColumns::default()).unwrap();
+        writer.write(&sample_result()).unwrap();
+        writer.finish().unwrap();
+        assert_ne!(fs::read(&path).unwrap(), content);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).ok();
+    }
+}
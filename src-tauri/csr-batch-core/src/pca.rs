@@ -0,0 +1,791 @@
+//! AWS Private CA签发模块
+//! 将生成结果CSV中的CSR逐个通过AWS Private CA（ACM PCA）的 IssueCertificate 接口签发，再以 GetCertificate
+//! 取回证书，输出在原有列之后增加certificate列（证书）、chain列（证书链）和certificateArn列的CSV文件
+//!
+//! 请求按AWS签名版本4（SigV4）签名。ThrottlingException、RequestInProgressException（证书尚未签发）、
+//! 5xx和连接失败视为暂时性失败，按指数退避重试；IssueCertificate使用由CSR得出的幂等令牌，重试不会重复签发
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::base64;
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::est::retry_after;
+use crate::output::{derived_csv_path, ResultCsv};
+use crate::validity::parse_datetime;
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 未返回 Retry-After 时首次重试的等待时间，之后每次加倍
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// 退避等待的上限
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// SigV4签名使用的服务名称
+const SERVICE: &str = "acm-pca";
+
+/// AWS Private CA签发参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct PcaParams {
+    /// 待签发的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _pca 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// 私有CA的ARN，如 arn:aws:acm-pca:us-east-1:123456789012:certificate-authority/...
+    pub certificate_authority_arn: String,
+    /// 区域 (可选，默认取自CA的ARN)
+    #[serde(default)]
+    pub region: String,
+    /// 访问密钥ID (可选，默认读取环境变量 AWS_ACCESS_KEY_ID)
+    #[serde(default)]
+    pub access_key_id: String,
+    /// 秘密访问密钥 (可选，默认读取环境变量 AWS_SECRET_ACCESS_KEY)
+    #[serde(default)]
+    pub secret_access_key: String,
+    /// 临时凭证的会话令牌 (可选，默认读取环境变量 AWS_SESSION_TOKEN)
+    #[serde(default)]
+    pub session_token: String,
+    /// 服务端点 (可选，默认为 https://acm-pca.<区域>.amazonaws.com，使用VPC端点时设置)
+    #[serde(default)]
+    pub endpoint_url: String,
+    /// CA的签名算法，如 SHA256WITHRSA (默认), SHA384WITHRSA, SHA256WITHECDSA, SHA384WITHECDSA
+    #[serde(default)]
+    pub signing_algorithm: String,
+    /// 证书模板ARN (可选，如 arn:aws:acm-pca:::template/EndEntityCertificate/V1)
+    #[serde(default)]
+    pub template_arn: String,
+    /// 证书有效天数 (可选，0表示使用CSV中的notBefore/notAfter)
+    #[serde(default)]
+    pub validity_days: u32,
+    /// 暂时性失败的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+/// 默认最大重试次数
+fn default_max_retries() -> u32 {
+    5
+}
+
+/// AWS Private CA签发结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct PcaResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 重试的请求次数
+    pub retries: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// AWS访问凭证
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+}
+
+/// AWS的错误响应
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(rename = "__type", default)]
+    error_type: String,
+    #[serde(alias = "Message", default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct IssueResponse {
+    certificate_arn: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetCertificateResponse {
+    certificate: String,
+    #[serde(default)]
+    certificate_chain: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Result<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data.as_bytes())?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// SigV4的签名密钥
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Result<Vec<u8>> {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date)?;
+    let key = hmac_sha256(&key, region)?;
+    let key = hmac_sha256(&key, service)?;
+    hmac_sha256(&key, "aws4_request")
+}
+
+/// 计算SigV4的Authorization头；headers为参与签名的请求头（名称小写），amz_date为 x-amz-date 的值
+#[allow(clippy::too_many_arguments)]
+fn authorization(
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    amz_date: &str,
+) -> Result<String> {
+    let mut headers = headers.to_vec();
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method,
+        path,
+        canonical_headers,
+        signed_headers,
+        hex(&hash(MessageDigest::sha256(), body)?)
+    );
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&hash(
+            MessageDigest::sha256(),
+            canonical_request.as_bytes()
+        )?)
+    );
+    let key = signing_key(&credentials.secret_access_key, date, region, service)?;
+    Ok(format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id,
+        scope,
+        signed_headers,
+        hex(&hmac_sha256(&key, &string_to_sign)?)
+    ))
+}
+
+/// 一次API调用的结果
+enum Attempt {
+    Done(String),
+    /// 暂时性失败，附带服务器要求的等待时间
+    Retry(String, Option<Duration>),
+    Failed(String),
+}
+
+struct PcaClient {
+    agent: ureq::Agent,
+    endpoint: String,
+    /// 端点的主机名（含非默认端口）和路径，参与签名
+    host: String,
+    path: String,
+    region: String,
+    credentials: Credentials,
+    max_retries: u32,
+}
+
+impl PcaClient {
+    fn connect(params: &PcaParams) -> Result<Self> {
+        let arn = params.certificate_authority_arn.trim();
+        if arn.is_empty() {
+            return Err(BatchError::parameter("certificate_authority_arn", "未填写").into());
+        }
+        let region = match params.region.trim() {
+            "" => arn.split(':').nth(3).unwrap_or_default().to_string(),
+            region => region.to_string(),
+        };
+        if region.is_empty() {
+            return Err(BatchError::parameter("region", "未填写，且无法从CA的ARN中得出").into());
+        }
+        let credential = |value: &str, variable: &str| match value.trim() {
+            "" => std::env::var(variable).unwrap_or_default(),
+            value => value.to_string(),
+        };
+        let credentials = Credentials {
+            access_key_id: credential(&params.access_key_id, "AWS_ACCESS_KEY_ID"),
+            secret_access_key: credential(&params.secret_access_key, "AWS_SECRET_ACCESS_KEY"),
+            session_token: credential(&params.session_token, "AWS_SESSION_TOKEN"),
+        };
+        if credentials.access_key_id.is_empty() || credentials.secret_access_key.is_empty() {
+            let message =
+                "未填写访问密钥，也未设置环境变量 AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY";
+            return Err(BatchError::parameter("access_key_id", message).into());
+        }
+
+        let endpoint = match params.endpoint_url.trim() {
+            "" => format!("https://{}.{}.amazonaws.com/", SERVICE, region),
+            endpoint => endpoint.to_string(),
+        };
+        let (_, rest) = endpoint
+            .split_once("://")
+            .ok_or_else(|| BatchError::parameter("endpoint_url", "无效的地址"))?;
+        let (host, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        Ok(Self {
+            agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build(),
+            host: host.to_string(),
+            path: path.to_string(),
+            endpoint,
+            region,
+            credentials,
+            max_retries: params.max_retries,
+        })
+    }
+
+    fn attempt(&self, target: &str, body: &str) -> Result<Attempt> {
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let target = format!("ACMPrivateCA.{}", target);
+        let content_type = "application/x-amz-json-1.1";
+        let mut headers = vec![
+            ("content-type", content_type),
+            ("host", self.host.as_str()),
+            ("x-amz-date", amz_date.as_str()),
+            ("x-amz-target", target.as_str()),
+        ];
+        if !self.credentials.session_token.is_empty() {
+            headers.push(("x-amz-security-token", &self.credentials.session_token));
+        }
+        let authorization = authorization(
+            &self.credentials,
+            &self.region,
+            SERVICE,
+            "POST",
+            &self.path,
+            &headers,
+            body.as_bytes(),
+            &amz_date,
+        )?;
+
+        let mut request = self
+            .agent
+            .post(&self.endpoint)
+            .set("Authorization", &authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+        Ok(match request.send_string(body) {
+            Ok(response) => match response.into_string() {
+                Ok(body) => Attempt::Done(body),
+                Err(e) => Attempt::Retry(format!("读取响应失败: {}", e), None),
+            },
+            Err(ureq::Error::Status(code, response)) => {
+                let delay = retry_after(&response);
+                let text = response.into_string().unwrap_or_default();
+                let error: ErrorResponse = serde_json::from_str(&text).unwrap_or(ErrorResponse {
+                    error_type: String::new(),
+                    message: text,
+                });
+                // __type可能带有命名空间前缀，如 com.amazonaws.acmpca#ThrottlingException
+                let error_type = error.error_type.rsplit('#').next().unwrap_or_default();
+                let message = format!("AWS返回 {} {}: {}", code, error_type, error.message);
+                let transient = matches!(
+                    error_type,
+                    "ThrottlingException" | "RequestInProgressException"
+                );
+                if transient || code >= 500 || code == 429 {
+                    Attempt::Retry(message, delay)
+                } else {
+                    Attempt::Failed(message)
+                }
+            }
+            Err(ureq::Error::Transport(e)) => Attempt::Retry(format!("连接失败: {}", e), None),
+        })
+    }
+
+    /// 调用API，暂时性失败时按指数退避重试；返回响应内容和重试次数
+    fn call(&self, target: &str, body: &serde_json::Value) -> Result<(String, usize)> {
+        let body = body.to_string();
+        let mut delay = RETRY_DELAY;
+        let mut retry = 0;
+        loop {
+            let (message, wait) = match self.attempt(target, &body)? {
+                Attempt::Done(response) => return Ok((response, retry)),
+                Attempt::Failed(message) => return Err(anyhow!(message)),
+                Attempt::Retry(message, wait) => (message, wait),
+            };
+            if retry >= self.max_retries as usize {
+                return Err(anyhow!("{}（已重试{}次）", message, retry));
+            }
+            std::thread::sleep(wait.unwrap_or(delay));
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+            retry += 1;
+        }
+    }
+}
+
+/// 把证书有效期转换为API的Validity（及ValidityNotBefore）
+fn validity(
+    validity_days: u32,
+    not_before: &str,
+    not_after: &str,
+) -> Result<(serde_json::Value, Option<serde_json::Value>)> {
+    if validity_days > 0 {
+        return Ok((json!({ "Value": validity_days, "Type": "DAYS" }), None));
+    }
+    let absolute = |value: &str, name: &str| {
+        parse_datetime(value)
+            .map(|time| json!({ "Value": time.timestamp(), "Type": "ABSOLUTE" }))
+            .ok_or_else(|| anyhow!("{}无效: {}", name, value))
+    };
+    if not_after.trim().is_empty() {
+        return Err(anyhow!("未设置有效天数，CSV中也没有notAfter"));
+    }
+    let not_before = match not_before.trim() {
+        "" => None,
+        value => Some(absolute(value, "notBefore")?),
+    };
+    Ok((absolute(not_after, "notAfter")?, not_before))
+}
+
+/// 通过AWS Private CA为CSV中的每个CSR签发证书
+pub fn issue_with_pca(params: PcaParams) -> Result<PcaResult, BatchError> {
+    issue_records(&params).map_err(BatchError::from)
+}
+
+fn issue_records(params: &PcaParams) -> Result<PcaResult> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let csr_col = column("csr").ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let not_before_col = column("notBefore");
+    let not_after_col = column("notAfter");
+    if params.validity_days == 0 && not_after_col.is_none() {
+        let message = "未设置有效天数，CSV中也没有notAfter列";
+        return Err(BatchError::parameter("validity_days", message).into());
+    }
+    // 重复签发时覆盖已有的certificate、chain和certificateArn列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+    let arn_col = ensure_column(&mut headers, "certificateArn");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "pca")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = PcaClient::connect(params)?;
+    let ca_arn = params.certificate_authority_arn.trim();
+    let signing_algorithm = match params.signing_algorithm.trim() {
+        "" => "SHA256WITHRSA".to_string(),
+        algorithm => algorithm.to_uppercase(),
+    };
+    let issue_record = |record: &StringRecord, number: usize| {
+        let cell = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("");
+        let csr = parse_csr(cell(Some(csr_col)))
+            .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+        issue(
+            &client,
+            params,
+            ca_arn,
+            &signing_algorithm,
+            &csr,
+            cell(not_before_col),
+            cell(not_after_col),
+        )
+        .map_err(|e| BatchError::record(number, format!("签发失败: {}", e)))
+    };
+
+    let mut total = 0;
+    let mut retries = 0;
+    for (index, record) in records.iter().enumerate() {
+        // 中途签发失败时，已签发的证书（按张计费）和其余行照常写入输出文件
+        let (cert_arn, cert_pem, chain_pem, retried) = match issue_record(record, index + 1) {
+            Ok(issued) => issued,
+            Err(error) => return Err(output.abort(error.into(), &records[index..], total)),
+        };
+        retries += retried;
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        fields[arn_col] = &cert_arn;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    let mut message = format!("AWS Private CA签发 {} 个证书", total);
+    if retries > 0 {
+        message.push_str(&format!("，重试{}次", retries));
+    }
+    Ok(PcaResult {
+        success: true,
+        message,
+        total,
+        retries,
+        output_path,
+    })
+}
+
+/// 签发一个CSR并取回证书，返回证书ARN、证书、证书链和重试次数
+fn issue(
+    client: &PcaClient,
+    params: &PcaParams,
+    ca_arn: &str,
+    signing_algorithm: &str,
+    csr: &X509Req,
+    not_before: &str,
+    not_after: &str,
+) -> Result<(String, String, String, usize)> {
+    let csr_pem = csr.to_pem()?;
+    let (validity, validity_not_before) = validity(params.validity_days, not_before, not_after)?;
+    let mut request = json!({
+        "CertificateAuthorityArn": ca_arn,
+        "Csr": base64::encode_block(&csr_pem),
+        "SigningAlgorithm": signing_algorithm,
+        "Validity": validity,
+        // 同一CSR的重试使用相同令牌，AWS在5分钟内返回同一证书
+        "IdempotencyToken": hex(&hash(MessageDigest::sha256(), &csr_pem)?[..16]),
+    });
+    if let Some(not_before) = validity_not_before {
+        request["ValidityNotBefore"] = not_before;
+    }
+    if !params.template_arn.trim().is_empty() {
+        request["TemplateArn"] = json!(params.template_arn.trim());
+    }
+    let (response, issue_retries) = client.call("IssueCertificate", &request)?;
+    let issued: IssueResponse = serde_json::from_str(&response)?;
+
+    // 证书签发是异步的，尚未完成时返回RequestInProgressException
+    let request = json!({
+        "CertificateAuthorityArn": ca_arn,
+        "CertificateArn": issued.certificate_arn,
+    });
+    let (response, get_retries) = client.call("GetCertificate", &request)?;
+    let response: GetCertificateResponse = serde_json::from_str(&response)?;
+    let cert = X509::from_pem(response.certificate.as_bytes())
+        .map_err(|e| anyhow!("无效的证书: {}", e))?;
+    if !cert.public_key()?.public_eq(&*csr.public_key()?) {
+        return Err(anyhow!("签发的证书与CSR的公钥不一致"));
+    }
+    let mut chain = String::new();
+    if !response.certificate_chain.trim().is_empty() {
+        for ca in X509::stack_from_pem(response.certificate_chain.as_bytes())? {
+            chain.push_str(&String::from_utf8(ca.to_pem()?)?);
+        }
+    }
+    Ok((
+        issued.certificate_arn,
+        String::from_utf8(cert.to_pem()?)?,
+        chain,
+        issue_retries + get_retries,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use std::collections::HashMap;
+    use std::fs;
+    use tiny_http::{Header, Response, Server};
+
+    fn credentials() -> Credentials {
+        Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: String::new(),
+        }
+    }
+
+    /// 启动测试服务器：校验SigV4签名，首次签发返回限流，首次取证书返回签发中；CA为ca-busy时始终限流
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap().to_string();
+        let base = format!("http://{}/", address);
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test Private CA").unwrap();
+        let name = name.build();
+        let validity = |builder: &mut X509Builder, issuer: &openssl::x509::X509NameRef| {
+            builder.set_version(2).unwrap();
+            builder.set_issuer_name(issuer).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                .unwrap();
+        };
+        let mut builder = X509Builder::new().unwrap();
+        validity(&mut builder, &name);
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_pem = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+
+        std::thread::spawn(move || {
+            let mut issued: HashMap<String, String> = HashMap::new();
+            let mut throttled = false;
+            let mut pending = false;
+            for mut request in server.incoming_requests() {
+                let header = |name: &'static str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv(name))
+                        .map(|h| h.value.to_string())
+                        .unwrap_or_default()
+                };
+                let (content_type, amz_date, target) = (
+                    header("Content-Type"),
+                    header("X-Amz-Date"),
+                    header("X-Amz-Target"),
+                );
+                let signature = header("Authorization");
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let headers = [
+                    ("content-type", content_type.as_str()),
+                    ("host", address.as_str()),
+                    ("x-amz-date", amz_date.as_str()),
+                    ("x-amz-target", target.as_str()),
+                ];
+                let expected = authorization(
+                    &credentials(),
+                    "us-east-1",
+                    SERVICE,
+                    "POST",
+                    "/",
+                    &headers,
+                    body.as_bytes(),
+                    &amz_date,
+                )
+                .unwrap();
+                let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let error = |kind: &str, message: &str| {
+                    let reply = json!({ "__type": kind, "message": message });
+                    Response::from_string(reply.to_string())
+                        .with_status_code(400)
+                        .with_header(Header::from_bytes("Retry-After", "0").unwrap())
+                };
+                let ca_arn = body["CertificateAuthorityArn"].as_str().unwrap();
+                let response = if signature != expected {
+                    error("InvalidSignatureException", "signature mismatch")
+                } else if ca_arn.ends_with("/ca-busy") {
+                    error("ThrottlingException", "Rate exceeded")
+                } else if !ca_arn.ends_with("/ca-1") {
+                    error("ResourceNotFoundException", "Could not find CA")
+                } else if target == "ACMPrivateCA.IssueCertificate" && !throttled {
+                    throttled = true;
+                    error("ThrottlingException", "Rate exceeded")
+                } else if target == "ACMPrivateCA.IssueCertificate" {
+                    assert_eq!(body["Validity"], json!({ "Value": 90, "Type": "DAYS" }));
+                    assert_eq!(body["SigningAlgorithm"], "SHA256WITHECDSA");
+                    let csr = base64::decode_block(body["Csr"].as_str().unwrap()).unwrap();
+                    let csr = X509Req::from_pem(&csr).unwrap();
+                    let mut builder = X509Builder::new().unwrap();
+                    validity(&mut builder, &name);
+                    builder.set_subject_name(csr.subject_name()).unwrap();
+                    builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                    builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                    let cert = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+                    let arn = format!(
+                        "arn:aws:acm-pca:us-east-1:1:certificate-authority/ca-1/certificate/{}",
+                        issued.len()
+                    );
+                    issued.insert(arn.clone(), cert);
+                    Response::from_string(json!({ "CertificateArn": arn }).to_string())
+                } else if !pending {
+                    pending = true;
+                    error("RequestInProgressException", "The request is in progress")
+                } else {
+                    let cert = &issued[body["CertificateArn"].as_str().unwrap()];
+                    let reply = json!({ "Certificate": cert, "CertificateChain": ca_pem });
+                    Response::from_string(reply.to_string())
+                };
+                let _ = request.respond(response);
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_sigv4_signature() {
+        // AWS签名版本4测试套件中的 get-vanilla 用例
+        let headers = [
+            ("host", "example.amazonaws.com"),
+            ("x-amz-date", "20150830T123600Z"),
+        ];
+        let authorization = authorization(
+            &credentials(),
+            "us-east-1",
+            "service",
+            "GET",
+            "/",
+            &headers,
+            b"",
+            "20150830T123600Z",
+        )
+        .unwrap();
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_issue_with_pca() {
+        let dir = std::env::temp_dir().join("csr_batch_pca_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let endpoint_url = start_mock();
+        let params = |ca: &str, overwrite: bool| PcaParams {
+            input_path: input.to_string_lossy().to_string(),
+            certificate_authority_arn: format!(
+                "arn:aws:acm-pca:us-east-1:1:certificate-authority/{}",
+                ca
+            ),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            endpoint_url: endpoint_url.clone(),
+            signing_algorithm: "sha256withecdsa".to_string(),
+            validity_days: 90,
+            max_retries: 3,
+            overwrite,
+            ..Default::default()
+        };
+        let result = issue_with_pca(params("ca-1", false)).unwrap();
+        assert_eq!(result.total, 2);
+        assert_eq!(result.retries, 2);
+        assert!(result.output_path.ends_with("out_pca.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (key_col, cert_col, chain_col, arn_col) = (
+            column("privateKey"),
+            column("certificate"),
+            column("chain"),
+            column("certificateArn"),
+        );
+        for record in reader.records() {
+            let record = record.unwrap();
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::stack_from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(cert.issuer_name_hash(), chain[0].subject_name_hash());
+            assert!(record[arn_col].contains("/ca-1/certificate/"));
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let error = issue_with_pca(params("ca-2", false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 非暂时性错误不重试，直接返回AWS的错误说明，已有的结果文件保持不变
+        let issued = fs::read_to_string(&result.output_path).unwrap();
+        let error = issue_with_pca(params("ca-2", true)).unwrap_err();
+        assert!(
+            error.to_string().contains("ResourceNotFoundException"),
+            "{}",
+            error
+        );
+        assert_eq!(fs::read_to_string(&result.output_path).unwrap(), issued);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_throttling_retries_exhausted() {
+        let dir = std::env::temp_dir().join("csr_batch_pca_throttling_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        // 持续限流时重试max_retries次后放弃，返回最后一次的错误说明，不写入结果文件
+        let params = PcaParams {
+            input_path: input.to_string_lossy().to_string(),
+            certificate_authority_arn: "arn:aws:acm-pca:us-east-1:1:certificate-authority/ca-busy"
+                .to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            endpoint_url: start_mock(),
+            validity_days: 90,
+            max_retries: 2,
+            ..Default::default()
+        };
+        let error = issue_with_pca(params).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidRecord { index: 1, ref message }
+                if message.contains("ThrottlingException") && message.contains("已重试2次")),
+            "{}",
+            error
+        );
+        assert!(!dir.join("out_pca.csv").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,297 @@
+//! YubiKey PIV模块
+//! 通过 ykman（YubiKey Manager 命令行工具）在每个已连接YubiKey的PIV槽位中生成密钥对，
+//! 并由卡内私钥签名CSR，私钥不离开设备；用于批量制作操作员智能卡
+//!
+//! 已连接的YubiKey按序列号排序后与通用名称范围依次配对，每个设备一个CN，
+//! 输出CSV包含 serial、slot、cn、subject、keyPairType、csr 列，可直接用内置CA签发
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use csv::Writer;
+use openssl::x509::X509Req;
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use crate::csr_generator::{parse_cn_range, KeyType};
+use crate::error::BatchError;
+use crate::subject::{escape_value, parse_subject, to_rfc4514};
+use crate::template::TemplateContext;
+
+/// 默认PIV槽位（9a: PIV身份认证）
+const DEFAULT_SLOT: &str = "9a";
+
+/// PIV批量制卡参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct PivParams {
+    /// 通用名称范围，按序依次分配给已连接的YubiKey
+    pub cn_range: String,
+    /// 通用名称范围计数器的进制: 10 (默认), 16, 36
+    #[serde(default)]
+    pub range_radix: u32,
+    /// Subject主题模板，可使用{CN}、{INDEX}、{DATE}等模板变量
+    pub subject_template: String,
+    /// 密钥类型: RSA_2048, RSA_3072, RSA_4096, EC_P256, EC_P384, ED25519
+    pub key_type: String,
+    /// 签名哈希算法: SHA256 (默认), SHA384, SHA512
+    #[serde(default)]
+    pub sign_hash_alg: String,
+    /// PIV槽位: 9a (默认), 9c, 9d, 9e
+    #[serde(default)]
+    pub slot: String,
+    /// PIV PIN（所有设备相同）
+    pub pin: String,
+    /// PIV管理密钥，十六进制 (可选，为空时使用设备默认管理密钥或由PIN保护的管理密钥)
+    #[serde(default)]
+    pub management_key: String,
+    /// PIN策略: default, never, once, always (可选)
+    #[serde(default)]
+    pub pin_policy: String,
+    /// 触摸策略: default, never, always, cached (可选)
+    #[serde(default)]
+    pub touch_policy: String,
+    /// 输出CSV文件路径
+    pub output_path: String,
+    /// ykman可执行文件路径 (可选，默认从PATH查找)
+    #[serde(default)]
+    pub ykman_path: String,
+}
+
+/// PIV批量制卡结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct PivResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 生成CSR的设备数
+    pub total: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 密钥类型对应的ykman算法名称，PIV不支持的类型返回None
+fn piv_algorithm(key_type: KeyType) -> Option<&'static str> {
+    match key_type {
+        KeyType::Rsa2048 => Some("RSA2048"),
+        KeyType::Rsa3072 => Some("RSA3072"),
+        KeyType::Rsa4096 => Some("RSA4096"),
+        KeyType::EcP256 => Some("ECCP256"),
+        KeyType::EcP384 => Some("ECCP384"),
+        KeyType::Ed25519 => Some("ED25519"),
+        _ => None,
+    }
+}
+
+/// ykman命令行调用
+struct Ykman<'a> {
+    program: &'a str,
+}
+
+impl Ykman<'_> {
+    /// 执行ykman命令并返回标准输出，serial非空时指定设备
+    fn run(&self, serial: Option<&str>, args: &[&str], stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut command = Command::new(self.program);
+        if let Some(serial) = serial {
+            command.args(["--device", serial]);
+        }
+        let mut child = command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => BatchError::parameter(
+                    "ykman_path",
+                    format!("未找到 {}，请安装YubiKey Manager", self.program),
+                ),
+                _ => BatchError::io(self.program, e),
+            })?;
+
+        if let Some(input) = stdin {
+            child
+                .stdin
+                .take()
+                .expect("stdin已设置为管道")
+                .write_all(input)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "ykman {} 执行失败: {}",
+                args.first().copied().unwrap_or_default(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    }
+
+    /// 已连接设备的序列号（升序）
+    fn serials(&self) -> Result<Vec<String>> {
+        let stdout = self.run(None, &["list", "--serials"], None)?;
+        Ok(parse_serials(&String::from_utf8_lossy(&stdout)))
+    }
+}
+
+/// 解析 `ykman list --serials` 的输出，每行一个序列号
+fn parse_serials(output: &str) -> Vec<String> {
+    let mut serials: Vec<String> = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && line.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect();
+    serials.sort_by_key(|serial| serial.parse::<u64>().unwrap_or(u64::MAX));
+    serials
+}
+
+/// 在已连接的每个YubiKey上生成密钥对和CSR
+pub fn provision_piv_tokens(params: PivParams) -> Result<PivResult, BatchError> {
+    provision_tokens(&params).map_err(BatchError::from)
+}
+
+fn provision_tokens(params: &PivParams) -> Result<PivResult> {
+    let key_type = KeyType::from_str(&params.key_type)?;
+    let algorithm = piv_algorithm(key_type).ok_or_else(|| {
+        let message = format!("PIV不支持密钥类型 {}", key_type.display_name());
+        BatchError::parameter("key_type", message)
+    })?;
+    let hash = if params.sign_hash_alg.is_empty() {
+        "SHA256"
+    } else {
+        &params.sign_hash_alg
+    };
+    let slot = if params.slot.is_empty() {
+        DEFAULT_SLOT
+    } else {
+        &params.slot
+    };
+    let cns = parse_cn_range(&params.cn_range, params.range_radix)?;
+
+    let ykman = Ykman {
+        program: if params.ykman_path.is_empty() {
+            "ykman"
+        } else {
+            &params.ykman_path
+        },
+    };
+    let serials = ykman.serials()?;
+    if serials.is_empty() {
+        return Err(BatchError::NoItems.into());
+    }
+
+    let mut writer = Writer::from_path(&params.output_path)
+        .map_err(|e| BatchError::io(&params.output_path, e))?;
+    writer.write_record(["serial", "slot", "cn", "subject", "keyPairType", "csr"])?;
+
+    // 设备与通用名称依次配对，多余的一方不处理
+    let mut total = 0;
+    let batch_date = Local::now();
+    for (index, (serial, cn)) in serials.iter().zip(&cns).enumerate() {
+        let context = TemplateContext::new(cn, index, batch_date);
+        let subject_str =
+            context.render_escaped(&params.subject_template, "subject_template", escape_value)?;
+        let subject = parse_subject(&subject_str).map_err(|e| BatchError::InvalidSubject {
+            cn: cn.clone(),
+            message: e.to_string(),
+        })?;
+
+        let csr_pem = generate_on_token(
+            &ykman,
+            serial,
+            slot,
+            algorithm,
+            hash,
+            &to_rfc4514(&subject),
+            params,
+        )
+        .map_err(|e| BatchError::item(index, cn, e.context(format!("YubiKey {}", serial))))?;
+        writer.write_record([
+            serial.as_str(),
+            slot,
+            cn,
+            &subject_str,
+            key_type.display_name(),
+            &csr_pem,
+        ])?;
+        writer.flush()?;
+        total += 1;
+    }
+
+    let mut message = format!("已为 {} 个YubiKey生成CSR", total);
+    if serials.len() != cns.len() {
+        message.push_str(&format!(
+            "（已连接 {} 个设备，通用名称 {} 个）",
+            serials.len(),
+            cns.len()
+        ));
+    }
+    Ok(PivResult {
+        success: true,
+        message,
+        total,
+        output_path: params.output_path.clone(),
+    })
+}
+
+/// 在指定设备的槽位生成密钥对，并由卡内私钥签名CSR（subject为RFC 4514格式）
+fn generate_on_token(
+    ykman: &Ykman,
+    serial: &str,
+    slot: &str,
+    algorithm: &str,
+    hash: &str,
+    subject: &str,
+    params: &PivParams,
+) -> Result<String> {
+    let mut generate = vec!["piv", "keys", "generate", "--algorithm", algorithm];
+    if !params.pin_policy.is_empty() {
+        generate.extend(["--pin-policy", &params.pin_policy]);
+    }
+    if !params.touch_policy.is_empty() {
+        generate.extend(["--touch-policy", &params.touch_policy]);
+    }
+    if !params.management_key.is_empty() {
+        generate.extend(["--management-key", &params.management_key]);
+    }
+    generate.extend(["--pin", &params.pin, slot, "-"]);
+    let public_key_pem = ykman.run(Some(serial), &generate, None)?;
+
+    let request = [
+        "piv",
+        "certificates",
+        "request",
+        "--subject",
+        subject,
+        "--hash-algorithm",
+        hash,
+        "--pin",
+        &params.pin,
+        slot,
+        "-",
+        "-",
+    ];
+    let csr = ykman.run(Some(serial), &request, Some(&public_key_pem))?;
+
+    // 校验卡内私钥生成的签名
+    let req = X509Req::from_pem(&csr)?;
+    let public_key = req.public_key()?;
+    if !req.verify(&public_key)? {
+        return Err(anyhow!("CSR签名无效"));
+    }
+    Ok(String::from_utf8(req.to_pem()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_serials() {
+        let serials = parse_serials("23456789\n9876543\n\nWARNING: PC/SC not available\n");
+        assert_eq!(serials, vec!["9876543", "23456789"]);
+        assert_eq!(piv_algorithm(KeyType::EcP256), Some("ECCP256"));
+        assert_eq!(piv_algorithm(KeyType::Sm2), None);
+    }
+}
@@ -0,0 +1,46 @@
+//! 算法策略模块
+//! SHA-1等遗留算法已不被公共CA接受，默认拒绝使用；须显式设置 `allow_legacy_algorithms` 才可使用，
+//! 使用时在批次报告中记录，避免误生成不合规的CSR
+
+use crate::error::BatchError;
+
+/// 遗留哈希算法，仅在显式允许时使用
+const LEGACY_HASH_ALGORITHMS: [&str; 1] = ["SHA1"];
+
+/// 按策略校验哈希算法：遗留算法未被允许时报错，被允许时返回用于报告的记录
+pub(crate) fn check_hash_alg(
+    field: &str,
+    hash_alg: &str,
+    allow_legacy: bool,
+) -> Result<Option<String>, BatchError> {
+    if !LEGACY_HASH_ALGORITHMS.contains(&hash_alg) {
+        return Ok(None);
+    }
+    if !allow_legacy {
+        let message = format!(
+            "{}为遗留算法，已不被公共CA接受；确需使用时请设置allow_legacy_algorithms",
+            hash_alg
+        );
+        return Err(BatchError::parameter(field, message));
+    }
+    Ok(Some(format!(
+        "已按allow_legacy_algorithms允许使用遗留算法{}（{}）",
+        hash_alg, field
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_hash_gated() {
+        assert!(check_hash_alg("sign_hash_alg", "SHA256", false)
+            .unwrap()
+            .is_none());
+        let err = check_hash_alg("sign_hash_alg", "SHA1", false).unwrap_err();
+        assert!(matches!(err, BatchError::InvalidParameter { .. }));
+        let record = check_hash_alg("pss_mgf1_hash", "SHA1", true).unwrap();
+        assert!(record.unwrap().contains("pss_mgf1_hash"));
+    }
+}
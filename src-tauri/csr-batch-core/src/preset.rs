@@ -0,0 +1,160 @@
+//! 任务配置模块
+//! 将生成参数按名称保存到配置文件（JSON），每月等定期批次可直接载入后重新生成，无需逐项重新填写
+//!
+//! 保存时去掉私钥口令、ZIP密码、质询密码、清单签名私钥口令和确定性种子等机密参数以及任务ID，载入后需重新填写
+
+use anyhow::Result;
+use chrono::{Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::csr_generator::BatchRequest;
+use crate::error::BatchError;
+
+/// 已保存的任务配置
+#[derive(Debug, Serialize, Deserialize)]
+struct Preset {
+    name: String,
+    saved_at: String,
+    params: BatchRequest,
+}
+
+/// 任务配置概要
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetInfo {
+    /// 配置名称
+    pub name: String,
+    /// 保存时间（本地时区的RFC 3339格式）
+    pub saved_at: String,
+}
+
+impl BatchRequest {
+    /// 机密参数
+    fn secrets_mut(&mut self) -> [&mut String; 5] {
+        [
+            &mut self.key_passphrase,
+            &mut self.zip_password,
+            &mut self.challenge_password,
+            &mut self.manifest_key_password,
+            &mut self.deterministic_seed,
+        ]
+    }
+
+    /// 去掉机密参数和任务ID后的副本
+    pub(crate) fn without_secrets(mut self) -> Self {
+        for secret in self.secrets_mut() {
+            secret.clear();
+        }
+        self.job_id.clear();
+        self
+    }
+
+    /// 机密参数取自other的副本，用于由不含机密参数的记录重新生成
+    pub(crate) fn with_secrets_of(mut self, other: &BatchRequest) -> Self {
+        let mut other = other.clone();
+        for (secret, value) in self.secrets_mut().into_iter().zip(other.secrets_mut()) {
+            std::mem::swap(secret, value);
+        }
+        self
+    }
+}
+
+/// 读取配置文件，文件不存在时为空
+fn read_presets(path: &Path) -> Result<Vec<Preset>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| BatchError::io(path, e))?;
+    let presets = serde_json::from_str(&content)
+        .map_err(|e| BatchError::parameter("presets", format!("配置文件格式错误: {}", e)))?;
+    Ok(presets)
+}
+
+/// 按名称保存生成参数（不含机密参数），同名配置被覆盖
+pub fn save_preset(path: &str, name: &str, params: BatchRequest) -> Result<(), BatchError> {
+    save(Path::new(path), name, params).map_err(BatchError::from)
+}
+
+fn save(path: &Path, name: &str, params: BatchRequest) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(BatchError::parameter("name", "配置名称不能为空").into());
+    }
+    let mut presets = read_presets(path)?;
+    presets.retain(|preset| preset.name != name);
+    presets.push(Preset {
+        name: name.to_string(),
+        saved_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+        params: params.without_secrets(),
+    });
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| BatchError::io(dir, e))?;
+    }
+    let content = serde_json::to_string_pretty(&presets)?;
+    fs::write(path, content).map_err(|e| BatchError::io(path, e))?;
+    Ok(())
+}
+
+/// 列出已保存的配置（按名称排序）
+pub fn list_presets(path: &str) -> Result<Vec<PresetInfo>, BatchError> {
+    let presets = read_presets(Path::new(path))?;
+    Ok(presets
+        .into_iter()
+        .map(|preset| PresetInfo {
+            name: preset.name,
+            saved_at: preset.saved_at,
+        })
+        .collect())
+}
+
+/// 按名称载入生成参数
+pub fn load_preset(path: &str, name: &str) -> Result<BatchRequest, BatchError> {
+    read_presets(Path::new(path))?
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .map(|preset| preset.params)
+        .ok_or_else(|| BatchError::parameter("name", format!("未找到任务配置 {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_strip_secrets() {
+        let path = std::env::temp_dir().join("csr_batch_presets_test/presets.json");
+        fs::remove_file(&path).ok();
+        let path_str = path.to_string_lossy().to_string();
+        let request = |key_type: &str| BatchRequest {
+            cn_range: "YDL0001-YDL0100".to_string(),
+            key_type: key_type.to_string(),
+            key_passphrase: "secret".to_string(),
+            zip_password: "zip-secret".to_string(),
+            job_id: "job-1".to_string(),
+            ..Default::default()
+        };
+        assert!(list_presets(&path_str).unwrap().is_empty());
+
+        save_preset(&path_str, "月度网关", request("RSA_2048")).unwrap();
+        save_preset(&path_str, "IoT", request("EC_P256")).unwrap();
+        save_preset(&path_str, " 月度网关 ", request("EC_P384")).unwrap();
+        let names: Vec<String> = list_presets(&path_str)
+            .unwrap()
+            .into_iter()
+            .map(|preset| preset.name)
+            .collect();
+        assert_eq!(names, ["IoT", "月度网关"]);
+
+        let loaded = load_preset(&path_str, "月度网关").unwrap();
+        assert_eq!(loaded.key_type, "EC_P384");
+        assert_eq!(loaded.cn_range, "YDL0001-YDL0100");
+        assert!(loaded.key_passphrase.is_empty() && loaded.zip_password.is_empty());
+        assert!(!fs::read_to_string(&path).unwrap().contains("secret"));
+        assert!(load_preset(&path_str, "missing").is_err());
+        assert!(save_preset(&path_str, " ", request("EC_P256")).is_err());
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,87 @@
+//! 范围预览模块
+//! 在生成密钥前展开通用名称（范围、MAC地址范围、随机CN或输入CSV，已跳过排除列表），
+//! 返回总数、首尾若干个通用名称和第一个条目渲染后的Subject，供用户确认写法后再开始耗时的密钥生成
+
+use anyhow::Result;
+use chrono::Local;
+use serde::Serialize;
+
+use crate::csr_generator::{batch_items, render_subject, BatchRequest};
+use crate::error::BatchError;
+use crate::template::TemplateContext;
+
+/// 通用名称预览结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CnPreview {
+    /// 通用名称总数
+    pub total: usize,
+    /// 开头的通用名称（总数不超过 2×limit 或 limit 为0时为全部）
+    pub head: Vec<String>,
+    /// 末尾的通用名称（已全部包含在head中时为空）
+    pub tail: Vec<String>,
+    /// 第一个条目渲染后的Subject
+    pub first_subject: String,
+}
+
+impl BatchRequest {
+    /// 预览按请求展开的通用名称，不生成密钥和CSR
+    /// limit为首尾各返回的数量，0表示返回全部
+    pub fn preview(&self, limit: usize) -> Result<CnPreview, BatchError> {
+        preview(self, limit).map_err(BatchError::from)
+    }
+}
+
+fn preview(request: &BatchRequest, limit: usize) -> Result<CnPreview> {
+    let items = batch_items(request)?;
+    let first = items.first().ok_or(BatchError::NoItems)?;
+    let context = TemplateContext::new(&first.cn, 0, Local::now());
+    let unique_id = first.unique_id.as_ref().unwrap_or(&request.unique_id);
+    let unique_id = context.render(unique_id, "unique_id")?;
+    let (first_subject, _) = render_subject(first, &context, request, &unique_id)?;
+
+    let total = items.len();
+    let mut cns: Vec<String> = items.into_iter().map(|item| item.cn).collect();
+    let tail = if limit == 0 || total <= limit * 2 {
+        Vec::new()
+    } else {
+        let tail = cns.split_off(total - limit);
+        cns.truncate(limit);
+        tail
+    };
+
+    Ok(CnPreview {
+        total,
+        head: cns,
+        tail,
+        first_subject,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subject::parse_subject;
+
+    #[test]
+    fn test_preview_head_and_tail() {
+        let request = BatchRequest {
+            cn_range: "YDL0001-YDL0100".to_string(),
+            subject_template: "CN=[{CN}];O=[Example]".to_string(),
+            exclude: "YDL0001".to_string(),
+            ..Default::default()
+        };
+        let preview = request.preview(3).unwrap();
+        assert_eq!(preview.total, 99);
+        assert_eq!(preview.head, ["YDL0002", "YDL0003", "YDL0004"]);
+        assert_eq!(preview.tail, ["YDL0098", "YDL0099", "YDL0100"]);
+        assert_eq!(preview.first_subject, "CN=[YDL0002];O=[Example]");
+        let subject = parse_subject(&preview.first_subject).unwrap();
+        let pairs: Vec<(&str, &str)> = subject
+            .iter()
+            .map(|a| (a.attr.as_str(), a.value.as_str()))
+            .collect();
+        assert_eq!(pairs, [("CN", "YDL0002"), ("O", "Example")]);
+
+        assert_eq!(request.preview(0).unwrap().head.len(), 99);
+    }
+}
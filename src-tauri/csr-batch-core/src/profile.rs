@@ -0,0 +1,183 @@
+//! 配置模板模块
+//! 按常见证书类型（TLS服务器、TLS客户端、S/MIME、代码签名、IoT设备身份、智能卡登录、国密双证书）预设密钥类型、
+//! 签名哈希算法、密钥用途、扩展密钥用途和备用名称模板
+//!
+//! 模板保存在可编辑的JSON文件中（对象数组，字段同 [`Profile`]），文件不存在时写入内置模板，
+//! 之后以文件内容为准，可修改内置模板或新增自定义模板
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::csr_generator::BatchRequest;
+use crate::error::BatchError;
+use crate::extensions::CustomExtension;
+
+/// 内置模板
+const BUILTIN_PROFILES: &str = include_str!("profiles.json");
+
+/// 证书配置模板，为空的字段不预设
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// 模板ID，如 tls_server
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 说明
+    #[serde(default)]
+    pub description: String,
+    /// 密钥类型
+    #[serde(default)]
+    pub key_type: String,
+    /// 签名哈希算法
+    #[serde(default)]
+    pub sign_hash_alg: String,
+    /// 密钥用途，逗号分隔
+    #[serde(default)]
+    pub key_usage: String,
+    /// 扩展密钥用途，逗号分隔
+    #[serde(default)]
+    pub extended_key_usage: String,
+    /// AD CS证书模板名称或OID
+    #[serde(default)]
+    pub certificate_template: String,
+    /// 按OID追加的自定义扩展
+    #[serde(default)]
+    pub extensions: Vec<CustomExtension>,
+    /// 备用名称模板，可使用模板变量
+    #[serde(default)]
+    pub sans: String,
+}
+
+impl Profile {
+    /// 内置模板
+    pub fn builtin() -> Vec<Profile> {
+        serde_json::from_str(BUILTIN_PROFILES).expect("内置配置模板格式错误")
+    }
+
+    /// 读取模板文件；path为空时返回内置模板，文件不存在时先写入内置模板
+    pub fn load(path: &str) -> Result<Vec<Profile>, BatchError> {
+        load(path).map_err(BatchError::from)
+    }
+
+    /// 按ID查找模板
+    pub fn find(path: &str, id: &str) -> Result<Profile, BatchError> {
+        Self::load(path)?
+            .into_iter()
+            .find(|profile| profile.id == id)
+            .ok_or_else(|| BatchError::parameter("profile", format!("未找到配置模板 {}", id)))
+    }
+
+    /// 将模板中的值写入请求中未设置（为空）的字段，已设置的字段保持不变
+    pub fn apply(&self, request: &mut BatchRequest) {
+        let fields = [
+            (&mut request.key_type, &self.key_type),
+            (&mut request.sign_hash_alg, &self.sign_hash_alg),
+            (&mut request.key_usage, &self.key_usage),
+            (&mut request.extended_key_usage, &self.extended_key_usage),
+            (
+                &mut request.certificate_template,
+                &self.certificate_template,
+            ),
+            (&mut request.sans, &self.sans),
+        ];
+        for (field, value) in fields {
+            if field.trim().is_empty() {
+                field.clone_from(value);
+            }
+        }
+        if request.extensions.is_empty() {
+            request.extensions.clone_from(&self.extensions);
+        }
+    }
+}
+
+fn load(path: &str) -> Result<Vec<Profile>> {
+    if path.is_empty() {
+        return Ok(Profile::builtin());
+    }
+    let path = Path::new(path);
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| BatchError::io(dir, e))?;
+        }
+        fs::write(path, BUILTIN_PROFILES).map_err(|e| BatchError::io(path, e))?;
+        return Ok(Profile::builtin());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| BatchError::io(path, e))?;
+    let profiles: Vec<Profile> = serde_json::from_str(&content)
+        .map_err(|e| BatchError::parameter("profiles", format!("模板文件格式错误: {}", e)))?;
+    let mut ids = HashSet::new();
+    for profile in &profiles {
+        if profile.id.trim().is_empty() || !ids.insert(profile.id.as_str()) {
+            let message = format!("模板ID为空或重复: {:?}", profile.id);
+            return Err(BatchError::parameter("profiles", message).into());
+        }
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let path = std::env::temp_dir().join("csr_batch_profiles_test/profiles.json");
+        fs::remove_file(&path).ok();
+        let path_str = path.to_string_lossy().to_string();
+
+        // 文件不存在时写入内置模板
+        let profiles = Profile::load(&path_str).unwrap();
+        assert_eq!(profiles, Profile::builtin());
+        assert!(path.exists());
+
+        let mut request = BatchRequest {
+            key_type: "RSA_4096".to_string(),
+            ..Default::default()
+        };
+        Profile::find(&path_str, "tls_server")
+            .unwrap()
+            .apply(&mut request);
+        assert_eq!(request.key_type, "RSA_4096");
+        assert_eq!(request.extended_key_usage, "serverAuth");
+        assert_eq!(request.sans, "dNSName=[{CN}]");
+
+        // 文件中的模板可修改
+        let mut edited = profiles;
+        edited[0].key_type = "EC_P384".to_string();
+        fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+        assert_eq!(
+            Profile::find(&path_str, "tls_server").unwrap().key_type,
+            "EC_P384"
+        );
+        assert!(Profile::find(&path_str, "missing").is_err());
+        edited.push(edited[0].clone());
+        fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+        assert!(Profile::load(&path_str).is_err());
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_builtin_profiles_generate() {
+        let cancel = crate::csr_generator::CancelToken::default();
+        for profile in Profile::builtin() {
+            // S/MIME配置以邮箱地址作为通用名称
+            let cn = match profile.id.as_str() {
+                "smime" => "jdoe@example.com",
+                _ => "YDL0001",
+            };
+            let mut request = BatchRequest {
+                cn_range: cn.to_string(),
+                subject_template: "CN=[{CN}]".to_string(),
+                ..Default::default()
+            };
+            profile.apply(&mut request);
+            let result = request.results(&cancel).unwrap().next().unwrap();
+            assert!(result.is_ok(), "{}: {:?}", profile.id, result);
+        }
+    }
+}
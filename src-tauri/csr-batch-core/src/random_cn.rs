@@ -0,0 +1,113 @@
+//! 随机通用名称模块
+//! 在签发时才分配设备标识的场景下，按数量生成UUIDv4或指定长度的随机十六进制字符串作为通用名称，
+//! 随机数来自OpenSSL的CSPRNG，同一批次内保证不重复
+
+use anyhow::Result;
+use openssl::rand::rand_bytes;
+use std::collections::HashSet;
+
+use crate::error::BatchError;
+
+/// 随机十六进制通用名称的默认长度
+const DEFAULT_HEX_LENGTH: usize = 16;
+
+/// 随机十六进制通用名称的最大长度
+const MAX_HEX_LENGTH: usize = 64;
+
+/// 单次最多生成的随机通用名称数量
+const MAX_RANDOM_COUNT: usize = 1_000_000;
+
+/// 生成UUIDv4（RFC 9562，小写带连字符）
+pub(crate) fn uuid_v4() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    rand_bytes(&mut bytes)?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+/// 生成指定长度的随机十六进制字符串（大写）
+pub(crate) fn random_hex(length: usize) -> Result<String> {
+    let mut bytes = vec![0u8; length.div_ceil(2)];
+    rand_bytes(&mut bytes)?;
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    Ok(hex[..length].to_string())
+}
+
+/// 按模式生成count个互不相同的随机通用名称
+/// mode 为 uuid 或 hex，hex_length 为十六进制字符串长度（0按16处理）
+pub(crate) fn generate_random_cns(
+    mode: &str,
+    count: usize,
+    hex_length: usize,
+) -> Result<Vec<String>> {
+    if count == 0 || count > MAX_RANDOM_COUNT {
+        let message = format!("随机通用名称数量须为1到{}", MAX_RANDOM_COUNT);
+        return Err(BatchError::parameter("count", message).into());
+    }
+    let hex_length = if hex_length == 0 {
+        DEFAULT_HEX_LENGTH
+    } else {
+        hex_length
+    };
+    let generate: Box<dyn Fn() -> Result<String>> = match mode {
+        "uuid" => Box::new(uuid_v4),
+        "hex" => {
+            // 取值空间须远大于数量，否则难以生成不重复的名称
+            if hex_length > MAX_HEX_LENGTH
+                || (hex_length < 16 && 16usize.pow(hex_length as u32) < count * 2)
+            {
+                let message = format!(
+                    "随机十六进制长度{}不足以生成{}个不重复的通用名称",
+                    hex_length, count
+                );
+                return Err(BatchError::parameter("hex_length", message).into());
+            }
+            Box::new(move || random_hex(hex_length))
+        }
+        _ => {
+            let message = format!("不支持的通用名称模式: {}", mode);
+            return Err(BatchError::parameter("cn_mode", message).into());
+        }
+    };
+
+    let mut seen = HashSet::new();
+    let mut result = Vec::with_capacity(count);
+    while result.len() < count {
+        let cn = generate()?;
+        if seen.insert(cn.clone()) {
+            result.push(cn);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_random_cns() {
+        let uuids = generate_random_cns("uuid", 3, 0).unwrap();
+        assert_eq!(uuids.len(), 3);
+        let uuid = &uuids[0];
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!("89ab".contains(&uuid[19..20]));
+
+        let hex = generate_random_cns("hex", 10, 7).unwrap();
+        assert!(hex
+            .iter()
+            .all(|cn| cn.len() == 7 && cn.chars().all(|c| c.is_ascii_hexdigit())));
+        assert!(generate_random_cns("hex", 100, 1).is_err());
+        assert!(generate_random_cns("uuid", 0, 0).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! 证书续期模块
+//! 从已有证书读取Subject和备用名称，为每个证书生成续期CSR；默认沿用原私钥，也可轮换为新密钥
+//!
+//! 证书来源为目录（其中的 .pem/.crt/.cer/.der 文件）或包含certificate列的CSV文件（如内置CA签发的输出），
+//! 续期CSR的有效期从当前时间开始、长度与原证书相同，输出CSV与批量生成一致，可直接用内置CA签发
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Local, SecondsFormat};
+use csv::ReaderBuilder;
+use openssl::x509::{X509Ref, X509};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::csr_generator::{
+    read_private_key, write_results, BatchReport, BatchRequest, CancelToken, CsrResults,
+};
+use crate::error::BatchError;
+use crate::input::{read_existing_key, BatchItem};
+use crate::inspect::general_names;
+use crate::subject::{format_subject, from_x509_name};
+
+/// 轮换密钥时的默认密钥类型
+const DEFAULT_KEY_TYPE: &str = "EC_P256";
+
+/// 证书目录中读取的文件扩展名
+const CERT_EXTENSIONS: [&str; 4] = ["pem", "crt", "cer", "der"];
+
+/// 证书续期参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct RenewParams {
+    /// 已有证书路径：证书目录，或包含certificate列的CSV文件（可选privateKey列）
+    pub cert_path: String,
+    /// 原私钥目录 (可选，按 `<CN>.key` 或 `<CN>.pem` 读取；CSV中的privateKey列优先)
+    #[serde(default)]
+    pub key_dir: String,
+    /// 是否轮换密钥 (默认沿用原私钥，设置后为每个证书生成新密钥)
+    #[serde(default)]
+    pub rotate_key: bool,
+    /// 轮换密钥时的密钥类型 (可选，默认EC_P256)
+    #[serde(default)]
+    pub key_type: String,
+    /// 签名哈希算法: SHA256 (默认), SHA384, SHA512
+    #[serde(default)]
+    pub sign_hash_alg: String,
+    /// 私钥加密口令 (可选，用于读取加密的原私钥，输出的私钥按同一口令加密)
+    #[serde(default)]
+    pub key_passphrase: String,
+    /// 输出CSV文件路径
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// 已有证书及其私钥PEM（来自CSV的privateKey列）
+struct ExistingCert {
+    cert: X509,
+    private_key: Option<String>,
+}
+
+/// 读取证书目录中的证书，按文件名排序
+fn read_cert_dir(dir: &Path) -> Result<Vec<ExistingCert>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| BatchError::io(dir, e))? {
+        let path = entry.map_err(|e| BatchError::io(dir, e))?.path();
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        if path.is_file() && extension.is_some_and(|e| CERT_EXTENSIONS.contains(&e.as_str())) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let bytes = fs::read(&path).map_err(|e| BatchError::io(&path, e))?;
+            // 文件中有多个证书时取第一个（终端实体证书）
+            let cert = X509::from_pem(&bytes)
+                .or_else(|_| X509::from_der(&bytes))
+                .map_err(|e| BatchError::io(&path, format!("证书无效: {}", e)))?;
+            Ok(ExistingCert {
+                cert,
+                private_key: None,
+            })
+        })
+        .collect()
+}
+
+/// 读取CSV文件中的证书和私钥
+fn read_cert_csv(path: &str) -> Result<Vec<ExistingCert>> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(path)
+        .map_err(|e| BatchError::io(path, e))?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let cert_col = column("certificate").ok_or_else(|| BatchError::header("缺少certificate列"))?;
+    let key_col = column("privateKey");
+
+    let mut certs = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let number = index + 1;
+        let record = record.map_err(|e| BatchError::record(number, format!("格式错误: {}", e)))?;
+        let cell = |col: usize| record.get(col).unwrap_or("").trim();
+
+        let cert = X509::from_pem(cell(cert_col).as_bytes())
+            .map_err(|e| BatchError::record(number, format!("证书无效: {}", e)))?;
+        let private_key = key_col
+            .map(cell)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string);
+        certs.push(ExistingCert { cert, private_key });
+    }
+    Ok(certs)
+}
+
+/// 证书的备用名称，格式如 `DNS:a.example.com,IP:10.0.0.1`
+fn format_sans(cert: &X509Ref) -> String {
+    cert.subject_alt_names()
+        .map(|names| general_names(&names).join(","))
+        .unwrap_or_default()
+}
+
+/// 由已有证书构建续期条目
+fn renewal_item(existing: ExistingCert, params: &RenewParams) -> Result<BatchItem> {
+    let cert = &existing.cert;
+    let subject = from_x509_name(cert.subject_name())?;
+    let cn = subject
+        .iter()
+        .find(|attr| attr.attr == "CN")
+        .map(|attr| attr.value.clone())
+        .ok_or_else(|| anyhow!("证书Subject中没有CN"))?;
+
+    // 沿用原私钥时须与证书公钥一致
+    let private_key = if params.rotate_key {
+        None
+    } else {
+        let pem = match existing.private_key {
+            Some(pem) => pem,
+            None if !params.key_dir.is_empty() => {
+                String::from_utf8(read_existing_key(&params.key_dir, &cn)?)?
+            }
+            None => return Err(anyhow!("未找到原私钥，请设置私钥目录或选择轮换密钥")),
+        };
+        let key = read_private_key(pem.as_bytes(), &params.key_passphrase)?;
+        if !cert.public_key()?.public_eq(&key) {
+            return Err(anyhow!("私钥与证书不匹配"));
+        }
+        Some(pem)
+    };
+
+    // 有效期长度与原证书相同
+    let validity = cert.not_before().diff(cert.not_after())?;
+    let not_before = Local::now();
+    let not_after =
+        not_before + Duration::days(validity.days.into()) + Duration::seconds(validity.secs.into());
+    let sans = format_sans(cert);
+
+    Ok(BatchItem {
+        cn,
+        subject: Some(format_subject(&subject)),
+        sans: (!sans.is_empty()).then_some(sans),
+        unique_id: None,
+        not_before: Some(not_before.to_rfc3339_opts(SecondsFormat::Secs, false)),
+        not_after: Some(not_after.to_rfc3339_opts(SecondsFormat::Secs, false)),
+        private_key,
+        key_type: None,
+    })
+}
+
+/// 为已有证书生成续期CSR
+pub fn renew_certificates(params: RenewParams) -> Result<BatchReport, BatchError> {
+    renew(&params).map_err(BatchError::from)
+}
+
+fn renew(params: &RenewParams) -> Result<BatchReport> {
+    let certs = if Path::new(&params.cert_path).is_dir() {
+        read_cert_dir(Path::new(&params.cert_path))?
+    } else {
+        read_cert_csv(&params.cert_path)?
+    };
+    let items = certs
+        .into_iter()
+        .enumerate()
+        .map(|(index, existing)| {
+            renewal_item(existing, params).map_err(|e| BatchError::record(index + 1, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let request = BatchRequest {
+        key_type: if params.key_type.is_empty() {
+            DEFAULT_KEY_TYPE.to_string()
+        } else {
+            params.key_type.clone()
+        },
+        sign_hash_alg: if params.sign_hash_alg.is_empty() {
+            "SHA256".to_string()
+        } else {
+            params.sign_hash_alg.clone()
+        },
+        key_passphrase: params.key_passphrase.clone(),
+        output_path: params.output_path.clone(),
+        overwrite: params.overwrite,
+        ..Default::default()
+    };
+    let cancel = CancelToken::default();
+    let results = CsrResults::with_items(&request, items, &cancel)?;
+    write_results(&request, results, &|_| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509Builder, X509Req};
+
+    use crate::subject::{build_x509_name, parse_subject};
+
+    #[test]
+    fn test_renew_from_cert_dir() {
+        let dir = std::env::temp_dir().join("csr_batch_renew_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        // 自签名的已有证书，有效期30天，Subject含多值RDN
+        let subject = parse_subject(r"CN=dev01,OU=Dev+OU=Ops,O=Example\, Inc.").unwrap();
+        let name = build_x509_name(&subject).unwrap();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("dev01.example.com")
+            .ip("10.0.0.1")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        std::fs::write(dir.join("dev01.crt"), builder.build().to_pem().unwrap()).unwrap();
+        std::fs::write(
+            dir.join("dev01.key"),
+            key.private_key_to_pem_pkcs8().unwrap(),
+        )
+        .unwrap();
+
+        let output = dir.join("renewed.csv");
+        let params = |rotate_key| RenewParams {
+            cert_path: dir.to_string_lossy().to_string(),
+            key_dir: dir.to_string_lossy().to_string(),
+            rotate_key,
+            output_path: output.to_string_lossy().to_string(),
+            overwrite: true,
+            ..Default::default()
+        };
+        let csr_of = || {
+            let mut reader = csv::Reader::from_path(&output).unwrap();
+            let headers = reader.headers().unwrap().clone();
+            let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+            let record = reader.records().next().unwrap().unwrap();
+            assert_eq!(
+                &record[column("subject")],
+                r"CN=dev01,OU=Dev+OU=Ops,O=Example\, Inc."
+            );
+            assert_eq!(&record[column("sans")], "DNS:dev01.example.com,IP:10.0.0.1");
+            let req = X509Req::from_pem(record[column("csr")].as_bytes()).unwrap();
+            // 续期CSR的Subject与原证书逐字节相同
+            let subject_der = req.subject_name().to_der().unwrap();
+            assert_eq!(subject_der, name.to_der().unwrap());
+            req
+        };
+
+        // 沿用原私钥
+        assert_eq!(renew_certificates(params(false)).unwrap().total, 1);
+        assert!(csr_of().public_key().unwrap().public_eq(&key));
+
+        // 轮换密钥
+        renew_certificates(params(true)).unwrap();
+        assert!(!csr_of().public_key().unwrap().public_eq(&key));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,421 @@
+//! 备用名称模块
+//! 将渲染后的备用名称(sans)解析为GeneralNames，编码为CSR的subjectAltName请求扩展
+//!
+//! 支持两种写法，可混用，各项以分号或逗号分隔：
+//! - `类型=[值1,值2]`，如 `dNSName=[a.example.com,b.example.com];iPAddress=[10.0.0.1]`
+//! - `类型:值`，如 `DNS:a.example.com,IP:10.0.0.1`（与续期读取的证书备用名称格式相同）
+//!
+//! 类型名称不区分大小写：dNSName/DNS、iPAddress/IP、rfc822Name/email、uniformResourceIdentifier/URI，
+//! 以及智能卡登录使用的Microsoft UPN（userPrincipalName/UPN，编码为otherName 1.3.6.1.4.1.311.20.2.3）
+//!
+//! 每个值按类型校验：DNS名称须符合主机名语法（通配符只能是最左侧的整个标签），IP地址编码为4或16字节，
+//! URI须带有方案且只含可打印ASCII字符，无效的值作为该条目的错误返回，不会写入格式错误的扩展
+//!
+//! 含中文等非ASCII标签的DNS名称和邮箱域名按IDNA（UTS #46）转换为punycode后写入，如 `设备.example.com`
+//! 写为 `xn--srs139i.example.com`；主题CN中的国际化域名同样默认转换（见 [`idn_common_name`]）
+
+use anyhow::Result;
+use regex::Regex;
+use std::net::IpAddr;
+
+use crate::error::BatchError;
+use crate::signature::{children, der, oid, read_tlv, sequence};
+
+/// Microsoft UPN的otherName类型OID (szOID_NT_PRINCIPAL_NAME)
+const UPN_OID: &str = "1.3.6.1.4.1.311.20.2.3";
+
+/// 备用名称
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SubjectAltName {
+    /// DNS名称
+    Dns(String),
+    /// IP地址
+    Ip(IpAddr),
+    /// 邮箱地址
+    Email(String),
+    /// URI
+    Uri(String),
+    /// Microsoft UPN，如 user@corp.example.com
+    Upn(String),
+}
+
+impl SubjectAltName {
+    /// 按类型名称和值构造备用名称并校验值，国际化域名转换为punycode
+    fn parse(kind: &str, value: &str) -> Result<Self> {
+        let kind = kind.trim().to_ascii_lowercase();
+        let (name, parsed) = match kind.as_str() {
+            "dnsname" | "dns" => ("DNS名称", dns_name(value, true).map(SubjectAltName::Dns)),
+            "ipaddress" | "ip" => {
+                let ip = value
+                    .parse()
+                    .map_err(|_| "应为IPv4或IPv6地址，不能带前缀长度");
+                ("IP地址", ip.map(SubjectAltName::Ip))
+            }
+            "rfc822name" | "email" => ("邮箱地址", email_address(value).map(SubjectAltName::Email)),
+            "uniformresourceidentifier" | "uri" => (
+                "URI",
+                check_uri(value).map(|_| SubjectAltName::Uri(value.to_string())),
+            ),
+            "userprincipalname" | "upn" => (
+                "UPN",
+                check_upn(value).map(|_| SubjectAltName::Upn(value.to_string())),
+            ),
+            _ => {
+                let message = format!("不支持的备用名称类型: {}", kind);
+                return Err(BatchError::parameter("sans", message).into());
+            }
+        };
+        parsed.map_err(|reason| {
+            let message = format!("{}无效: {}（{}）", name, value, reason);
+            BatchError::parameter("sans", message).into()
+        })
+    }
+
+    /// GeneralName的DER编码
+    fn to_der(&self) -> Result<Vec<u8>> {
+        Ok(match self {
+            SubjectAltName::Email(email) => der(0x81, email.as_bytes()),
+            SubjectAltName::Dns(dns) => der(0x82, dns.as_bytes()),
+            SubjectAltName::Uri(uri) => der(0x86, uri.as_bytes()),
+            SubjectAltName::Ip(IpAddr::V4(ip)) => der(0x87, &ip.octets()),
+            SubjectAltName::Ip(IpAddr::V6(ip)) => der(0x87, &ip.octets()),
+            // otherName ::= [0] { type-id OID, value [0] EXPLICIT UTF8String }
+            SubjectAltName::Upn(upn) => {
+                let value = der(0xa0, &der(0x0c, upn.as_bytes()));
+                der(0xa0, &[oid(UPN_OID)?, value].concat())
+            }
+        })
+    }
+}
+
+/// 将含非ASCII字符的域名按IDNA转换为punycode，最左侧的通配符和末尾的点保持不变；ASCII域名原样返回
+fn to_ascii_domain(name: &str) -> Result<String, &'static str> {
+    if name.is_ascii() {
+        return Ok(name.to_string());
+    }
+    let (wildcard, rest) = match name.strip_prefix("*.") {
+        Some(rest) => ("*.", rest),
+        None => ("", name),
+    };
+    let (rest, root) = match rest.strip_suffix('.') {
+        Some(rest) => (rest, "."),
+        None => (rest, ""),
+    };
+    let ascii = idna::domain_to_ascii_strict(rest).map_err(|_| "国际化域名无法转换为punycode")?;
+    Ok(format!("{}{}{}", wildcard, ascii, root))
+}
+
+/// 转换并校验DNS名称，返回ASCII形式
+fn dns_name(name: &str, wildcard: bool) -> Result<String, &'static str> {
+    let name = to_ascii_domain(name)?;
+    check_dns_name(&name, wildcard)?;
+    Ok(name)
+}
+
+/// 主题CN为国际化域名（含非ASCII字符和点，且可转换为有效的DNS名称）时返回其punycode形式，
+/// 人名等其他CN返回None
+pub(crate) fn idn_common_name(cn: &str) -> Option<String> {
+    if cn.is_ascii() || !cn.contains(['.', '。']) {
+        return None;
+    }
+    dns_name(cn, true).ok()
+}
+
+/// 校验DNS名称：各标签为1-63个字母、数字或连字符且不以连字符开头或结尾，总长不超过253；
+/// wildcard为true时最左侧标签可以是通配符 `*`
+fn check_dns_name(name: &str, wildcard: bool) -> Result<(), &'static str> {
+    if name.len() > 253 {
+        return Err("总长度超过253个字符");
+    }
+    let name = name.strip_suffix('.').unwrap_or(name);
+    for (i, label) in name.split('.').enumerate() {
+        if i == 0 && label == "*" {
+            if !wildcard {
+                return Err("不能使用通配符");
+            }
+            continue;
+        }
+        if label.is_empty() || label.len() > 63 {
+            return Err("标签为空或超过63个字符");
+        }
+        if !label
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        {
+            return Err("只能包含字母、数字、连字符和点，通配符只能是最左侧的整个标签");
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err("标签不能以连字符开头或结尾");
+        }
+    }
+    if name == "*" {
+        return Err("通配符之后须有域名");
+    }
+    Ok(())
+}
+
+/// 校验邮箱地址：本地部分为不含空白的可打印ASCII字符，域名部分为DNS名称（国际化域名转换为punycode）
+fn email_address(email: &str) -> Result<String, &'static str> {
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return Err("缺少@");
+    };
+    if local.is_empty() || !local.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err("@之前须为不含空白的ASCII字符");
+    }
+    Ok(format!("{}@{}", local, dns_name(domain, false)?))
+}
+
+/// 校验URI（RFC 3986）：须以方案开头，只含可打印ASCII字符，百分号后须为两位十六进制，
+/// 带有 `//` 时主机部分不能为空
+fn check_uri(uri: &str) -> Result<(), &'static str> {
+    let scheme = Regex::new(r"^[A-Za-z][A-Za-z0-9+.\-]*:").expect("URI方案正则表达式无效");
+    let Some(scheme) = scheme.find(uri) else {
+        return Err("缺少方案，如 https: 或 urn:");
+    };
+    let rest = &uri[scheme.end()..];
+    if rest.is_empty() {
+        return Err("方案之后没有内容");
+    }
+    if !rest.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err("只能包含可打印ASCII字符，空格等须百分号编码");
+    }
+    let bytes = rest.as_bytes();
+    for (i, _) in rest.match_indices('%') {
+        if !bytes
+            .get(i + 1..i + 3)
+            .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+        {
+            return Err("百分号之后须为两位十六进制");
+        }
+    }
+    if let Some(authority) = rest.strip_prefix("//") {
+        let host = authority
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or("")
+            .rsplit('@')
+            .next()
+            .unwrap_or("");
+        if host.is_empty() || host.starts_with(':') {
+            return Err("主机部分为空");
+        }
+    }
+    Ok(())
+}
+
+/// 校验UPN：形如 user@corp.example.com，不含控制字符
+fn check_upn(upn: &str) -> Result<(), &'static str> {
+    match upn.rsplit_once('@') {
+        Some((user, suffix)) if !user.is_empty() && !suffix.is_empty() => {}
+        _ => return Err("应为 用户名@UPN后缀 格式"),
+    }
+    if upn.chars().any(char::is_control) {
+        return Err("不能包含控制字符");
+    }
+    Ok(())
+}
+
+/// 从GeneralName的DER编码中读取Microsoft UPN，不是UPN时返回None
+pub(crate) fn upn_from_der(name: &[u8]) -> Option<String> {
+    let (0xa0, other_name, _) = read_tlv(name).ok()? else {
+        return None;
+    };
+    let [(0x06, type_id), (0xa0, value)] = children(other_name).ok()?[..] else {
+        return None;
+    };
+    if oid(UPN_OID).ok()?.get(2..)? != type_id {
+        return None;
+    }
+    let (0x0c, upn, _) = read_tlv(value).ok()? else {
+        return None;
+    };
+    String::from_utf8(upn.to_vec()).ok()
+}
+
+/// 按顶层的分号和逗号拆分，方括号内的逗号不拆分
+fn split_items(spec: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in spec.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ';' | ',' if depth == 0 => {
+                items.push(&spec[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&spec[start..]);
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// 解析备用名称，空值被忽略
+pub(crate) fn parse_sans(spec: &str) -> Result<Vec<SubjectAltName>> {
+    let mut names = Vec::new();
+    for item in split_items(spec) {
+        let list = item
+            .split_once('=')
+            .and_then(|(kind, values)| Some((kind, values.trim().strip_prefix('[')?)));
+        if let Some((kind, values)) = list {
+            let values = values
+                .strip_suffix(']')
+                .ok_or_else(|| BatchError::parameter("sans", format!("缺少右方括号: {}", item)))?;
+            for value in values.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                names.push(SubjectAltName::parse(kind, value)?);
+            }
+        } else if let Some((kind, value)) = item.split_once(':') {
+            if !value.trim().is_empty() {
+                names.push(SubjectAltName::parse(kind, value.trim())?);
+            }
+        } else {
+            let message = format!("应为 类型=[值,...] 或 类型:值 格式: {}", item);
+            return Err(BatchError::parameter("sans", message).into());
+        }
+    }
+    Ok(names)
+}
+
+/// subjectAltName扩展的值（GeneralNames），没有备用名称时返回None
+pub(crate) fn san_extension_value(spec: &str) -> Result<Option<Vec<u8>>> {
+    let names = parse_sans(spec)?
+        .iter()
+        .map(SubjectAltName::to_der)
+        .collect::<Result<Vec<_>>>()?;
+    if names.is_empty() {
+        return Ok(None);
+    }
+    let names: Vec<&[u8]> = names.iter().map(Vec::as_slice).collect();
+    Ok(Some(sequence(&names)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_parse_sans() {
+        let names = parse_sans(
+            "dNSName=[a.example.com, b.example.com];iPAddress=[10.0.0.1],UPN:dev1@corp.example.com",
+        )
+        .unwrap();
+        assert_eq!(
+            names,
+            [
+                SubjectAltName::Dns("a.example.com".to_string()),
+                SubjectAltName::Dns("b.example.com".to_string()),
+                SubjectAltName::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+                SubjectAltName::Upn("dev1@corp.example.com".to_string()),
+            ]
+        );
+        assert!(parse_sans(" ; dNSName=[]").unwrap().is_empty());
+        assert_eq!(
+            parse_sans("DNS:a.example.com,email:a@example.com,URI:urn:dev:1").unwrap(),
+            [
+                SubjectAltName::Dns("a.example.com".to_string()),
+                SubjectAltName::Email("a@example.com".to_string()),
+                SubjectAltName::Uri("urn:dev:1".to_string()),
+            ]
+        );
+
+        // UPN编码为otherName，IP地址编码为4字节
+        let value = san_extension_value("UPN:dev1@corp,IP:10.0.0.1")
+            .unwrap()
+            .unwrap();
+        let upn = [
+            &[0xa0, 0x19][..],
+            &oid(UPN_OID).unwrap(),
+            &[0xa0, 0x0b, 0x0c, 0x09],
+            b"dev1@corp",
+        ]
+        .concat();
+        assert_eq!(value, sequence(&[&upn, &[0x87, 0x04, 10, 0, 0, 1]]));
+        assert!(san_extension_value("").unwrap().is_none());
+        assert_eq!(upn_from_der(&upn).as_deref(), Some("dev1@corp"));
+        assert_eq!(upn_from_der(&[0x82, 0x01, b'a']), None);
+
+        assert!(parse_sans("dNSName=[a.example.com").is_err());
+        assert!(parse_sans("x400Address=[a]").is_err());
+        assert!(parse_sans("a.example.com").is_err());
+    }
+
+    #[test]
+    fn test_validate_sans() {
+        let error = |spec: &str| parse_sans(spec).unwrap_err().to_string();
+
+        // IPv6同样编码为地址字节
+        let value = san_extension_value("IP:2001:db8::1").unwrap().unwrap();
+        let mut ipv6 = vec![0x87, 0x10, 0x20, 0x01, 0x0d, 0xb8];
+        ipv6.extend([0; 11]);
+        ipv6.push(1);
+        assert_eq!(value, sequence(&[&ipv6]));
+        for spec in [
+            "IP:10.0.0.256",
+            "IP:10.0.0.0/8",
+            "iPAddress=[dev1.example.com]",
+        ] {
+            assert!(error(spec).contains("IP地址无效"), "{}", spec);
+        }
+
+        assert!(parse_sans("DNS:*.dev-1.example.com.,DNS:localhost").is_ok());
+        for spec in [
+            "DNS:dev_1.example.com",
+            "DNS:-dev.example.com",
+            "DNS:a..example.com",
+            "DNS:a.*.example.com",
+            "DNS:*",
+            "DNS:设备_1.example.com",
+        ] {
+            assert!(error(spec).contains("DNS名称无效"), "{}", spec);
+        }
+
+        assert!(parse_sans("URI:https://dev1.example.com/a%20b?x=1,URI:urn:uuid:1234").is_ok());
+        assert!(parse_sans("URI:spiffe://corp/ns/dev1").is_ok());
+        for spec in [
+            "URI:dev1.example.com",
+            "URI:https://dev1.example.com/a b",
+            "URI:https://dev1.example.com/%zz",
+            "URI:https:///path",
+            "URI:1https://dev1",
+        ] {
+            assert!(error(spec).contains("URI无效"), "{}", spec);
+        }
+
+        assert!(error("email:dev1.example.com").contains("邮箱地址无效"));
+        assert!(error("email:a@*.example.com").contains("邮箱地址无效"));
+        assert!(error("UPN:dev1").contains("UPN无效"));
+        assert!(error("UPN:@corp").contains("UPN无效"));
+    }
+
+    #[test]
+    fn test_idn_to_punycode() {
+        // 非ASCII的DNS名称和邮箱域名转换为punycode，全角句号视为点，通配符保持不变
+        let names =
+            parse_sans("DNS:设备.example.com;DNS:*.测试。example.com;email:ops@中文.example.com")
+                .unwrap();
+        assert_eq!(
+            names,
+            [
+                SubjectAltName::Dns("xn--srs139i.example.com".to_string()),
+                SubjectAltName::Dns("*.xn--0zwm56d.example.com".to_string()),
+                SubjectAltName::Email("ops@xn--fiq228c.example.com".to_string()),
+            ]
+        );
+        assert!(parse_sans("email:运维@example.com").is_err());
+
+        assert_eq!(
+            idn_common_name("服务器.example.com").as_deref(),
+            Some("xn--zfru1ggxt.example.com")
+        );
+        // 人名、ASCII域名等不转换
+        assert_eq!(idn_common_name("张三"), None);
+        assert_eq!(idn_common_name("dev1.example.com"), None);
+        assert_eq!(idn_common_name("张三 (研发部.北京)"), None);
+    }
+}
@@ -0,0 +1,857 @@
+//! SCEP注册模块
+//! 将生成结果CSV中的CSR（须为RSA密钥）通过SCEP（RFC 8894）提交给CA：CSR（含challengePassword）以CA证书加密为
+//! PKCS#7 EnvelopedData，再以CSR私钥和临时自签名证书签名为PKIMessage（PKCSReq）。CA返回PENDING时
+//! 按间隔发送CertPoll查询，GRANTED（SUCCESS）后解密出签发的证书，写入在原有列之后增加的certificate列
+//! 和chain列，输出新的CSV文件
+//!
+//! openssl crate不支持PKCS#7签名属性（messageType、transactionID等），PKIMessage的SignedData
+//! 在本模块中直接按DER编码和解析，加密、解密和签名验证仍由OpenSSL完成
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkcs7::{Pkcs7, Pkcs7Flags};
+use openssl::pkey::{Id, PKey, Private};
+use openssl::rand::rand_bytes;
+use openssl::sign::Signer;
+use openssl::stack::Stack;
+use openssl::symm::Cipher;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509Builder, X509Req, X509ReqBuilder, X509};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::csr_generator::{add_challenge_password, read_private_key};
+use crate::error::BatchError;
+use crate::output::{derived_csv_path, ResultCsv};
+use crate::signature::{
+    children, der, integer, oid, read_tlv, sequence, sign_req, SignatureScheme,
+};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// PKCS#7和SCEP属性的OID
+const OID_DATA: &str = "1.2.840.113549.1.7.1";
+const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_CONTENT_TYPE: &str = "1.2.840.113549.1.9.3";
+const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const OID_RSA_ENCRYPTION: &str = "1.2.840.113549.1.1.1";
+const OID_MESSAGE_TYPE: &str = "2.16.840.1.113733.1.9.2";
+const OID_PKI_STATUS: &str = "2.16.840.1.113733.1.9.3";
+const OID_FAIL_INFO: &str = "2.16.840.1.113733.1.9.4";
+const OID_SENDER_NONCE: &str = "2.16.840.1.113733.1.9.5";
+const OID_RECIPIENT_NONCE: &str = "2.16.840.1.113733.1.9.6";
+const OID_TRANSACTION_ID: &str = "2.16.840.1.113733.1.9.7";
+
+/// messageType：PKCSReq、CertPoll（GetCertInitial）和CertRep
+const MESSAGE_PKCS_REQ: &str = "19";
+const MESSAGE_CERT_POLL: &str = "20";
+const MESSAGE_CERT_REP: &str = "3";
+
+/// SCEP注册参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct ScepParams {
+    /// 待注册的CSV文件路径（批量生成的输出，需包含csr和privateKey列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _scep 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// SCEP服务地址，如 http://ca.example.com/certsrv/mscep/mscep.dll 或 http://ca.example.com/cgi-bin/pkiclient.exe
+    pub server_url: String,
+    /// CA标识，作为GetCACert的message参数 (可选，同一服务托管多个CA时填写)
+    #[serde(default)]
+    pub ca_identifier: String,
+    /// 质询密码 (可选，填写时以该密码重新签名CSR；留空时CSR须在生成时已包含challengePassword)
+    #[serde(default)]
+    pub challenge_password: String,
+    /// 私钥加密口令 (CSV中的私钥已加密时填写)
+    #[serde(default)]
+    pub key_passphrase: String,
+    /// HTTPS服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+    /// CA返回PENDING时查询的间隔秒数
+    #[serde(default = "default_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// CA返回PENDING时的最大查询次数
+    #[serde(default = "default_max_polls")]
+    pub max_polls: u32,
+}
+
+/// 默认查询间隔
+fn default_poll_interval_seconds() -> u64 {
+    10
+}
+
+/// 默认最大查询次数
+fn default_max_polls() -> u32 {
+    30
+}
+
+/// SCEP注册结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct ScepResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 单值属性 Attribute ::= SEQUENCE { type OID, values SET OF ANY }
+fn attribute(attr_oid: &str, value: &[u8]) -> Result<Vec<u8>> {
+    Ok(sequence(&[&oid(attr_oid)?, &der(0x31, value)]))
+}
+
+/// 以签名者证书和RSA私钥签名内容，生成带签名属性的PKCS#7 SignedData（DER）；content为空时不含内容
+fn signed_message(
+    content: Option<&[u8]>,
+    signer: &X509,
+    key: &PKey<Private>,
+    mut attributes: Vec<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let digest = hash(MessageDigest::sha256(), content.unwrap_or_default())?;
+    attributes.push(attribute(OID_CONTENT_TYPE, &oid(OID_DATA)?)?);
+    attributes.push(attribute(OID_MESSAGE_DIGEST, &der(0x04, &digest))?);
+    // DER的SET OF按编码排序，签名计算在SET标签下的属性编码上
+    attributes.sort();
+    let attributes = attributes.concat();
+    let mut signer_ctx = Signer::new(MessageDigest::sha256(), key)?;
+    signer_ctx.update(&der(0x31, &attributes))?;
+    let signature = signer_ctx.sign_to_vec()?;
+
+    let null = [0x05, 0x00];
+    let sha256 = sequence(&[&oid(OID_SHA256)?, &null]);
+    let signature_algorithm = sequence(&[&oid(OID_RSA_ENCRYPTION)?, &null]);
+    let issuer_and_serial = sequence(&[
+        &signer.issuer_name().to_der()?,
+        &integer(&signer.serial_number().to_bn()?),
+    ]);
+    let signer_info = sequence(&[
+        &der(0x02, &[1]),
+        &issuer_and_serial,
+        &sha256,
+        &der(0xA0, &attributes),
+        &signature_algorithm,
+        &der(0x04, &signature),
+    ]);
+    let content_info = match content {
+        Some(content) => sequence(&[&oid(OID_DATA)?, &der(0xA0, &der(0x04, content))]),
+        None => sequence(&[&oid(OID_DATA)?]),
+    };
+    let signed_data = sequence(&[
+        &der(0x02, &[1]),
+        &der(0x31, &sha256),
+        &content_info,
+        &der(0xA0, &signer.to_der()?),
+        &der(0x31, &signer_info),
+    ]);
+    Ok(sequence(&[
+        &oid(OID_SIGNED_DATA)?,
+        &der(0xA0, &signed_data),
+    ]))
+}
+
+/// 解析出的PKIMessage：内容和第一个签名者的签名属性
+struct SignedMessage {
+    content: Option<Vec<u8>>,
+    /// 属性OID的DER编码和第一个值的内容
+    attributes: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl SignedMessage {
+    fn parse(message: &[u8]) -> Result<Self> {
+        let malformed = || anyhow!("PKCS#7格式错误");
+        let (_, content_info, _) = read_tlv(message)?;
+        let content_info = children(content_info)?;
+        let explicit = content_info.get(1).ok_or_else(malformed)?.1;
+        let (_, signed_data, _) = read_tlv(explicit)?;
+        let signed_data = children(signed_data)?;
+
+        let encapsulated = children(signed_data.get(2).ok_or_else(malformed)?.1)?;
+        let content = match encapsulated.get(1) {
+            Some((_, explicit)) => Some(read_tlv(explicit)?.1.to_vec()),
+            None => None,
+        };
+
+        let (_, signer_infos) = signed_data.last().ok_or_else(malformed)?;
+        let (_, signer_info, _) = read_tlv(signer_infos)?;
+        let mut attributes = Vec::new();
+        for (tag, value) in children(signer_info)? {
+            if tag != 0xA0 {
+                continue;
+            }
+            for (_, attribute) in children(value)? {
+                let (tag, attr_oid, rest) = read_tlv(attribute)?;
+                let (_, values, _) = read_tlv(rest)?;
+                let (_, first, _) = read_tlv(values)?;
+                attributes.push((der(tag, attr_oid), first.to_vec()));
+            }
+        }
+        Ok(Self {
+            content,
+            attributes,
+        })
+    }
+
+    /// 读取签名属性的值
+    fn attribute(&self, attr_oid: &str) -> Result<Option<&[u8]>> {
+        let attr_oid = oid(attr_oid)?;
+        Ok(self
+            .attributes
+            .iter()
+            .find(|(o, _)| *o == attr_oid)
+            .map(|(_, value)| value.as_slice()))
+    }
+
+    /// 读取字符串类型的签名属性
+    fn text(&self, attr_oid: &str) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.attribute(attr_oid)?.unwrap_or_default()).into_owned())
+    }
+}
+
+/// failInfo的说明
+fn fail_info(code: &str) -> &'static str {
+    match code {
+        "0" => "badAlg（不支持的算法）",
+        "1" => "badMessageCheck（签名验证失败）",
+        "2" => "badRequest（请求被拒绝，如质询密码错误）",
+        "3" => "badTime（时间不符）",
+        "4" => "badCertId（找不到对应的证书）",
+        _ => "未知原因",
+    }
+}
+
+struct ScepClient {
+    agent: ureq::Agent,
+    url: String,
+    /// 签名CertRep的CA证书及RA证书
+    ca_certs: Stack<X509>,
+    /// CA证书，CertPoll中的签发者名称
+    ca_cert: X509,
+    /// PKCSReq内容的加密对象（有RA时为RA证书）
+    recipients: Stack<X509>,
+    cipher: Cipher,
+    /// 服务器支持POST方式的PKIOperation
+    post: bool,
+    poll_interval: Duration,
+    max_polls: u32,
+}
+
+impl ScepClient {
+    fn connect(params: &ScepParams) -> Result<Self> {
+        if params.server_url.trim().is_empty() {
+            return Err(BatchError::parameter("server_url", "未填写").into());
+        }
+        let mut builder = ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT);
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            let connector = native_tls::TlsConnector::builder()
+                .add_root_certificate(cert)
+                .build()?;
+            builder = builder.tls_connector(Arc::new(connector));
+        }
+        let agent = builder.build();
+        let url = params.server_url.trim().to_string();
+        let get = |operation: &str| {
+            let mut request = agent.get(&url).query("operation", operation);
+            if !params.ca_identifier.is_empty() {
+                request = request.query("message", &params.ca_identifier);
+            }
+            request
+                .call()
+                .map_err(|e| anyhow!("SCEP {} 失败: {}", operation, e))
+        };
+
+        // GetCACaps失败时按最低能力处理（GET方式、DES3加密）
+        let caps = get("GetCACaps")
+            .and_then(|response| Ok(response.into_string()?))
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let caps: Vec<&str> = caps.lines().map(str::trim).collect();
+
+        let response = get("GetCACert")?;
+        let ra = response.content_type() == "application/x-x509-ca-ra-cert";
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body)?;
+        let certs: Vec<X509> = if ra {
+            Pkcs7::from_der(&body)?
+                .signed()
+                .and_then(|signed| signed.certificates())
+                .map(|stack| stack.iter().map(|cert| cert.to_owned()).collect())
+                .unwrap_or_default()
+        } else {
+            vec![X509::from_der(&body).or_else(|_| X509::from_pem(&body))?]
+        };
+        // CA证书自签名，RA证书由CA签发；有RA时请求加密给RA
+        let self_signed =
+            |cert: &X509| cert.issuer_name().to_der().ok() == cert.subject_name().to_der().ok();
+        let ca_cert = certs
+            .iter()
+            .find(|cert| self_signed(cert))
+            .or(certs.first())
+            .cloned()
+            .ok_or_else(|| anyhow!("GetCACert未返回证书"))?;
+        let recipient = certs
+            .iter()
+            .find(|cert| !self_signed(cert))
+            .unwrap_or(&ca_cert);
+        let mut recipients = Stack::new()?;
+        recipients.push(recipient.clone())?;
+        let mut ca_certs = Stack::new()?;
+        for cert in &certs {
+            ca_certs.push(cert.clone())?;
+        }
+
+        Ok(Self {
+            agent,
+            url,
+            ca_certs,
+            ca_cert,
+            recipients,
+            cipher: if caps.contains(&"AES") {
+                Cipher::aes_128_cbc()
+            } else {
+                Cipher::des_ede3_cbc()
+            },
+            post: caps.contains(&"POSTPKIOPERATION"),
+            poll_interval: Duration::from_secs(params.poll_interval_seconds),
+            max_polls: params.max_polls,
+        })
+    }
+
+    /// 发送PKIMessage，验证CertRep的签名和nonce，返回解析后的响应
+    fn operation(
+        &self,
+        message_type: &str,
+        content: &[u8],
+        transaction_id: &str,
+        signer: &X509,
+        key: &PKey<Private>,
+    ) -> Result<SignedMessage> {
+        let envelope = Pkcs7::encrypt(&self.recipients, content, self.cipher, Pkcs7Flags::BINARY)?;
+        let mut nonce = [0u8; 16];
+        rand_bytes(&mut nonce)?;
+        let attributes = vec![
+            attribute(OID_MESSAGE_TYPE, &der(0x13, message_type.as_bytes()))?,
+            attribute(OID_TRANSACTION_ID, &der(0x13, transaction_id.as_bytes()))?,
+            attribute(OID_SENDER_NONCE, &der(0x04, &nonce))?,
+        ];
+        let message = signed_message(Some(&envelope.to_der()?), signer, key, attributes)?;
+
+        let response = if self.post {
+            self.agent
+                .post(&self.url)
+                .query("operation", "PKIOperation")
+                .set("Content-Type", "application/x-pki-message")
+                .send_bytes(&message)
+        } else {
+            let encoded = openssl::base64::encode_block(&message).replace('\n', "");
+            self.agent
+                .get(&self.url)
+                .query("operation", "PKIOperation")
+                .query("message", &encoded)
+                .call()
+        }
+        .map_err(|e| anyhow!("SCEP PKIOperation 失败: {}", e))?;
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body)?;
+
+        let reply = SignedMessage::parse(&body)?;
+        // 验证CA签名（不验证证书链，CA证书来自GetCACert）
+        let store = X509StoreBuilder::new()?.build();
+        let indata = reply.content.is_none().then_some(&[][..]);
+        Pkcs7::from_der(&body)?
+            .verify(&self.ca_certs, &store, indata, None, Pkcs7Flags::NOVERIFY)
+            .map_err(|e| anyhow!("CertRep签名验证失败: {}", e))?;
+        if reply.text(OID_MESSAGE_TYPE)? != MESSAGE_CERT_REP {
+            return Err(anyhow!("响应不是CertRep消息"));
+        }
+        if reply.attribute(OID_RECIPIENT_NONCE)? != Some(&nonce[..])
+            || reply.text(OID_TRANSACTION_ID)? != transaction_id
+        {
+            return Err(anyhow!(
+                "CertRep的recipientNonce或transactionID与请求不一致"
+            ));
+        }
+        Ok(reply)
+    }
+
+    /// 提交CSR并等待签发，返回签发的证书和其余证书（PEM）
+    fn enroll(&self, csr: &X509Req, key: &PKey<Private>) -> Result<(String, String)> {
+        let public_key = csr.public_key()?.public_key_to_der()?;
+        let transaction_id: String = hash(MessageDigest::sha256(), &public_key)?
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+        let signer = self_signed_cert(csr, key)?;
+
+        let mut reply = self.operation(
+            MESSAGE_PKCS_REQ,
+            &csr.to_der()?,
+            &transaction_id,
+            &signer,
+            key,
+        )?;
+        let issuer_and_subject = sequence(&[
+            &self.ca_cert.subject_name().to_der()?,
+            &csr.subject_name().to_der()?,
+        ]);
+        let mut polls = 0;
+        loop {
+            match reply.text(OID_PKI_STATUS)?.as_str() {
+                "0" => break,
+                "2" => {
+                    let code = reply.text(OID_FAIL_INFO)?;
+                    return Err(anyhow!("CA拒绝签发: {}", fail_info(&code)));
+                }
+                "3" if polls < self.max_polls => {
+                    std::thread::sleep(self.poll_interval);
+                    polls += 1;
+                    reply = self.operation(
+                        MESSAGE_CERT_POLL,
+                        &issuer_and_subject,
+                        &transaction_id,
+                        &signer,
+                        key,
+                    )?;
+                }
+                "3" => return Err(anyhow!("查询{}次后仍未签发（PENDING）", polls)),
+                other => return Err(anyhow!("未知的pkiStatus: {}", other)),
+            }
+        }
+
+        let envelope = reply.content.ok_or_else(|| anyhow!("CertRep中没有证书"))?;
+        let degenerate = Pkcs7::from_der(&envelope)?
+            .decrypt(key, &signer, Pkcs7Flags::BINARY)
+            .map_err(|e| anyhow!("无法解密CertRep: {}", e))?;
+        let pkcs7 = Pkcs7::from_der(&degenerate)?;
+        let certs = pkcs7
+            .signed()
+            .and_then(|signed| signed.certificates())
+            .ok_or_else(|| anyhow!("CertRep中没有证书"))?;
+        let csr_key = csr.public_key()?;
+        let mut leaf = None;
+        let mut chain = String::new();
+        for cert in certs {
+            if leaf.is_none() && cert.public_key()?.public_eq(&csr_key) {
+                leaf = Some(String::from_utf8(cert.to_pem()?)?);
+            } else {
+                chain.push_str(&String::from_utf8(cert.to_pem()?)?);
+            }
+        }
+        let leaf = leaf.ok_or_else(|| anyhow!("CertRep中没有与CSR公钥匹配的证书"))?;
+        Ok((leaf, chain))
+    }
+}
+
+/// 签名PKIMessage使用的临时自签名证书（RFC 8894 2.3）
+fn self_signed_cert(csr: &X509Req, key: &PKey<Private>) -> Result<X509> {
+    let mut serial = BigNum::new()?;
+    serial.rand(63, MsbOption::MAYBE_ZERO, false)?;
+    let mut builder = X509Builder::new()?;
+    builder.set_version(2)?;
+    let serial = serial.to_asn1_integer()?;
+    builder.set_serial_number(&serial)?;
+    builder.set_subject_name(csr.subject_name())?;
+    builder.set_issuer_name(csr.subject_name())?;
+    builder.set_pubkey(key)?;
+    let not_before = Asn1Time::days_from_now(0)?;
+    let not_after = Asn1Time::days_from_now(1)?;
+    builder.set_not_before(&not_before)?;
+    builder.set_not_after(&not_after)?;
+    builder.sign(key, MessageDigest::sha256())?;
+    Ok(builder.build())
+}
+
+/// 以质询密码重新签名CSR，Subject、公钥和请求扩展不变
+fn with_challenge_password(csr: &X509Req, key: &PKey<Private>, password: &str) -> Result<X509Req> {
+    let mut builder = X509ReqBuilder::new()?;
+    builder.set_subject_name(csr.subject_name())?;
+    builder.set_pubkey(key)?;
+    if let Ok(extensions) = csr.extensions() {
+        builder.add_extensions(&extensions)?;
+    }
+    let req = builder.build();
+    add_challenge_password(&req, password)?;
+    sign_req(
+        &req,
+        key,
+        MessageDigest::sha256(),
+        SignatureScheme::default(),
+    )?;
+    Ok(req)
+}
+
+/// 通过SCEP为CSV中的每个CSR签发证书
+pub fn scep_enroll(params: ScepParams) -> Result<ScepResult, BatchError> {
+    enroll_records(&params).map_err(BatchError::from)
+}
+
+/// 注册一行的CSR，返回写回的CSR、证书和证书链
+fn enroll_record(
+    client: &ScepClient,
+    params: &ScepParams,
+    record: &StringRecord,
+    csr_col: usize,
+    key_col: usize,
+    number: usize,
+) -> Result<(String, String, String)> {
+    let cell = |col: usize| record.get(col).unwrap_or("");
+    let mut csr = parse_csr(cell(csr_col))
+        .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+    let key = read_private_key(cell(key_col).as_bytes(), &params.key_passphrase)
+        .map_err(|e| BatchError::record(number, e))?;
+    if !csr.public_key()?.public_eq(&key) {
+        return Err(BatchError::record(number, "私钥与CSR的公钥不一致").into());
+    }
+    // PKCS#7加密只支持RSA密钥传输，CertRep须能以请求者的私钥解密
+    if key.id() != Id::RSA {
+        return Err(BatchError::record(number, "SCEP仅支持RSA密钥").into());
+    }
+    // 重新签名的CSR写回csr列，与签发的证书对应
+    let mut csr_pem = cell(csr_col).to_string();
+    if !params.challenge_password.is_empty() {
+        csr = with_challenge_password(&csr, &key, &params.challenge_password)?;
+        csr_pem = String::from_utf8(csr.to_pem()?)?;
+    }
+    let (cert_pem, chain_pem) = client
+        .enroll(&csr, &key)
+        .map_err(|e| BatchError::record(number, format!("注册失败: {}", e)))?;
+    Ok((csr_pem, cert_pem, chain_pem))
+}
+
+fn enroll_records(params: &ScepParams) -> Result<ScepResult> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let csr_col = column("csr").ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let key_col = column("privateKey")
+        .ok_or_else(|| BatchError::header("缺少privateKey列，SCEP请求须使用CSR的私钥签名"))?;
+    // 重复注册时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "scep")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = ScepClient::connect(params)?;
+    let mut total = 0;
+    for (index, record) in records.iter().enumerate() {
+        // 中途注册失败时，已签发的证书和其余行照常写入输出文件
+        let (csr_pem, cert_pem, chain_pem) =
+            match enroll_record(&client, params, record, csr_col, key_col, index + 1) {
+                Ok(enrolled) => enrolled,
+                Err(error) => return Err(output.abort(error, &records[index..], total)),
+            };
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[csr_col] = &csr_pem;
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    Ok(ScepResult {
+        success: true,
+        message: format!("SCEP签发 {} 个证书", total),
+        total,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use crate::subject::from_x509_name;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509NameBuilder;
+    use std::collections::HashMap;
+    use tiny_http::{Header, Method, Response, Server};
+
+    /// 仅含证书的PKCS#7（degenerate SignedData）
+    fn certs_only(certs: &[&X509]) -> Vec<u8> {
+        let certs: Vec<Vec<u8>> = certs.iter().map(|cert| cert.to_der().unwrap()).collect();
+        let signed_data = sequence(&[
+            &der(0x02, &[1]),
+            &der(0x31, &[]),
+            &sequence(&[&oid(OID_DATA).unwrap()]),
+            &der(0xA0, &certs.concat()),
+            &der(0x31, &[]),
+        ]);
+        sequence(&[&oid(OID_SIGNED_DATA).unwrap(), &der(0xA0, &signed_data)])
+    }
+
+    /// 启动测试SCEP服务器：PKCSReq返回PENDING，CertPoll时签发；CSR不含质询密码secret时拒绝。
+    /// stale_nonce时recipientNonce与请求不一致，模拟重放的旧响应
+    fn start_mock(stale_nonce: bool) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/cgi-bin/pkiclient.exe",
+            server.server_addr().to_ip().unwrap()
+        );
+        let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test SCEP CA").unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+
+        std::thread::spawn(move || {
+            let mut pending: HashMap<String, X509Req> = HashMap::new();
+            for mut request in server.incoming_requests() {
+                let url = request.url().to_string();
+                let response = match (request.method(), url.split_once("operation=")) {
+                    (Method::Get, Some((_, "GetCACaps"))) => {
+                        Response::from_data("POSTPKIOperation\nAES\nSHA-256\n")
+                    }
+                    (Method::Get, Some((_, "GetCACert"))) => {
+                        Response::from_data(ca_cert.to_der().unwrap()).with_header(
+                            Header::from_bytes("Content-Type", "application/x-x509-ca-cert")
+                                .unwrap(),
+                        )
+                    }
+                    (Method::Post, Some((_, "PKIOperation"))) => {
+                        let mut body = Vec::new();
+                        request.as_reader().read_to_end(&mut body).unwrap();
+                        let message = SignedMessage::parse(&body).unwrap();
+                        let pkcs7 = Pkcs7::from_der(&body).unwrap();
+                        let mut envelope = Vec::new();
+                        let store = X509StoreBuilder::new().unwrap().build();
+                        pkcs7
+                            .verify(
+                                &Stack::new().unwrap(),
+                                &store,
+                                None,
+                                Some(&mut envelope),
+                                Pkcs7Flags::NOVERIFY,
+                            )
+                            .unwrap();
+                        let signer = pkcs7.signed().unwrap().certificates().unwrap()[0].to_owned();
+                        let inner = Pkcs7::from_der(&envelope)
+                            .unwrap()
+                            .decrypt(&ca_key, &ca_cert, Pkcs7Flags::BINARY)
+                            .unwrap();
+
+                        let transaction_id = message.text(OID_TRANSACTION_ID).unwrap();
+                        let nonce = if stale_nonce {
+                            &[0u8; 16][..]
+                        } else {
+                            message.attribute(OID_SENDER_NONCE).unwrap().unwrap()
+                        };
+                        let mut attributes = vec![
+                            attribute(OID_MESSAGE_TYPE, &der(0x13, MESSAGE_CERT_REP.as_bytes())),
+                            attribute(OID_TRANSACTION_ID, &der(0x13, transaction_id.as_bytes())),
+                            attribute(OID_RECIPIENT_NONCE, &der(0x04, nonce)),
+                        ];
+                        let mut content = None;
+                        match message.text(OID_MESSAGE_TYPE).unwrap().as_str() {
+                            MESSAGE_PKCS_REQ => {
+                                let csr = X509Req::from_der(&inner).unwrap();
+                                let status = if inner.windows(6).any(|w| w == b"secret") {
+                                    pending.insert(transaction_id, csr);
+                                    "3"
+                                } else {
+                                    attributes.push(attribute(OID_FAIL_INFO, &der(0x13, b"2")));
+                                    "2"
+                                };
+                                attributes
+                                    .push(attribute(OID_PKI_STATUS, &der(0x13, status.as_bytes())));
+                            }
+                            MESSAGE_CERT_POLL => {
+                                let csr = pending.remove(&transaction_id).unwrap();
+                                let mut builder = X509Builder::new().unwrap();
+                                builder.set_version(2).unwrap();
+                                builder.set_subject_name(csr.subject_name()).unwrap();
+                                builder.set_issuer_name(&name).unwrap();
+                                builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                                builder
+                                    .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                                    .unwrap();
+                                builder
+                                    .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                                    .unwrap();
+                                builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                                let degenerate = certs_only(&[&builder.build(), &ca_cert]);
+                                let mut recipients = Stack::new().unwrap();
+                                recipients.push(signer).unwrap();
+                                let enveloped = Pkcs7::encrypt(
+                                    &recipients,
+                                    &degenerate,
+                                    Cipher::aes_128_cbc(),
+                                    Pkcs7Flags::BINARY,
+                                )
+                                .unwrap();
+                                content = Some(enveloped.to_der().unwrap());
+                                attributes.push(attribute(OID_PKI_STATUS, &der(0x13, b"0")));
+                            }
+                            other => panic!("unexpected messageType {}", other),
+                        }
+                        let attributes = attributes.into_iter().map(Result::unwrap).collect();
+                        let reply =
+                            signed_message(content.as_deref(), &ca_cert, &ca_key, attributes);
+                        Response::from_data(reply.unwrap())
+                    }
+                    _ => Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_scep_enroll() {
+        let dir = std::env::temp_dir().join("csr_batch_scep_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "router1,router2".to_string(),
+            subject_template: "CN=[{CN}];O=[Example]".to_string(),
+            key_type: "RSA_2048".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let params = ScepParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: start_mock(false),
+            challenge_password: "secret".to_string(),
+            max_polls: 1,
+            ..Default::default()
+        };
+        let result = scep_enroll(params).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.output_path.ends_with("out_scep.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (csr_col, key_col, cert_col, chain_col) = (
+            column("csr"),
+            column("privateKey"),
+            column("certificate"),
+            column("chain"),
+        );
+        for record in reader.records() {
+            let record = record.unwrap();
+            // 写回的CSR包含质询密码
+            let csr = parse_csr(&record[csr_col]).unwrap();
+            assert!(csr.to_der().unwrap().windows(6).any(|w| w == b"secret"));
+            let subject = from_x509_name(csr.subject_name()).unwrap();
+            assert_eq!(subject.len(), 2);
+            assert_eq!(subject[1].attr, "O");
+            assert_eq!(subject[1].value, "Example");
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            let chain = X509::from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(cert.issuer_name_hash(), chain.subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let server_url = start_mock(false);
+        let params = |overwrite: bool| ScepParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            challenge_password: "wrong".to_string(),
+            overwrite,
+            ..Default::default()
+        };
+        let error = scep_enroll(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 质询密码错误时CA返回FAILURE，已有的结果文件保持不变
+        let issued = fs::read_to_string(&result.output_path).unwrap();
+        let error = scep_enroll(params(true)).unwrap_err();
+        assert!(error.to_string().contains("badRequest"), "{}", error);
+        assert_eq!(fs::read_to_string(&result.output_path).unwrap(), issued);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_scep_nonce_mismatch() {
+        let dir = std::env::temp_dir().join("csr_batch_scep_nonce_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "router1".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "RSA_2048".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        // CertRep的recipientNonce与请求的senderNonce不一致时拒绝，不写入结果文件
+        let params = ScepParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: start_mock(true),
+            challenge_password: "secret".to_string(),
+            max_polls: 1,
+            ..Default::default()
+        };
+        let error = scep_enroll(params).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidRecord { index: 1, ref message }
+                if message.contains("recipientNonce")),
+            "{}",
+            error
+        );
+        assert!(!dir.join("out_scep.csv").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,645 @@
+//! HTTP服务模块（需启用 `server` 特性）
+//! 以本地REST服务的形式提供批量生成，其他内部工具无需Tauri IPC即可提交任务、查询状态和下载结果：
+//!
+//! - `POST /jobs`：提交任务，请求体为生成参数（JSON），返回 `{"job_id": ...}`
+//! - `GET /jobs`、`GET /jobs/{id}`：查询所有任务或单个任务的状态、进度和结果
+//! - `DELETE /jobs/{id}`：取消执行中的任务
+//! - `GET /jobs/{id}/files`、`GET /jobs/{id}/files/{name}`：列出和下载任务的输出文件
+//!
+//! 每个任务输出到输出目录下以任务ID命名的子目录，请求中的输出路径只取文件名；
+//! 读写其他文件的参数（输入CSV、已有私钥目录、审计日志等）、系统密钥存储、回调命令和Webhook一律拒绝，
+//! 见 `check_restricted`。监听非回环地址时须设置访问令牌；未设置令牌时只接受Host为本机地址、
+//! 不带Origin头的请求，防止网页借DNS重绑定或跨站请求提交任务和下载私钥。
+//!
+//! 每个任务最多 [`MAX_JOB_ITEMS`] 个条目，同时执行的任务最多 [`MAX_RUNNING_JOBS`] 个，
+//! 已结束的任务超过 [`MAX_FINISHED_JOBS`] 个时删除最早结束的任务及其输出。任务状态只保存在内存中
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use openssl::memcmp;
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server, SslConfig};
+
+use crate::csr_generator::{
+    batch_items, run_batch, BatchReport, BatchRequest, CancelToken, ProgressEvent,
+};
+use crate::error::BatchError;
+use crate::hook::CompletionHooks;
+use crate::job_log::job_state;
+use crate::random_cn::uuid_v4;
+
+/// 请求体大小上限
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// 处理请求的线程数
+const REQUEST_THREADS: usize = 8;
+
+/// 单个任务的最大条目数
+pub const MAX_JOB_ITEMS: usize = 10_000;
+
+/// 同时执行的最大任务数
+pub const MAX_RUNNING_JOBS: usize = 2;
+
+/// 保留的已结束任务数
+pub const MAX_FINISHED_JOBS: usize = 100;
+
+/// 服务配置
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    /// 监听地址，如 127.0.0.1:8443
+    pub addr: String,
+    /// 输出目录，每个任务写入以任务ID命名的子目录
+    pub output_dir: String,
+    /// 访问令牌，设置后请求须带 `Authorization: Bearer <令牌>` 头；监听非回环地址时必须设置
+    pub token: String,
+    /// TLS证书（PEM）路径，与tls_key都设置时使用HTTPS
+    pub tls_cert: String,
+    /// TLS私钥（PEM）路径
+    pub tls_key: String,
+}
+
+/// 任务状态
+#[derive(Debug, Clone, Serialize)]
+struct JobStatus {
+    job_id: String,
+    /// running、completed、cancelled或failed
+    state: &'static str,
+    /// 最近一次进度
+    progress: Option<ProgressEvent>,
+    /// 生成结果（完成或取消后可用）
+    result: Option<BatchReport>,
+    /// 错误信息（失败时可用）
+    error: Option<BatchError>,
+}
+
+struct ServerJob {
+    status: JobStatus,
+    cancel: CancelToken,
+    /// 结束时间，执行中为None
+    finished: Option<Instant>,
+}
+
+type Jobs = Arc<Mutex<BTreeMap<String, ServerJob>>>;
+
+/// 请求处理失败时的HTTP状态码和错误
+struct ApiError(u16, BatchError);
+
+impl From<BatchError> for ApiError {
+    fn from(error: BatchError) -> Self {
+        let status = match error {
+            BatchError::Io { .. } | BatchError::Openssl { .. } | BatchError::Other { .. } => 500,
+            _ => 400,
+        };
+        ApiError(status, error)
+    }
+}
+
+fn not_found(field: &str, message: impl std::fmt::Display) -> ApiError {
+    ApiError(404, BatchError::parameter(field, message))
+}
+
+/// 已绑定监听地址的服务
+pub struct CsrServer {
+    server: Server,
+    output_dir: PathBuf,
+    token: String,
+    jobs: Jobs,
+}
+
+impl CsrServer {
+    /// 创建输出目录并绑定监听地址（端口为0时由系统分配），监听非回环地址且未设置访问令牌时报错
+    pub fn bind(config: &ServerConfig) -> Result<Self, BatchError> {
+        let output_dir = PathBuf::from(&config.output_dir);
+        fs::create_dir_all(&output_dir).map_err(|e| BatchError::io(&output_dir, e))?;
+        let server = match (config.tls_cert.is_empty(), config.tls_key.is_empty()) {
+            (true, true) => Server::http(&config.addr),
+            (false, false) => {
+                let read = |path: &str| fs::read(path).map_err(|e| BatchError::io(path, e));
+                let ssl = SslConfig {
+                    certificate: read(&config.tls_cert)?,
+                    private_key: read(&config.tls_key)?,
+                };
+                Server::https(&config.addr, ssl)
+            }
+            _ => {
+                let message = "TLS证书和私钥须同时设置";
+                return Err(BatchError::parameter("tls_cert", message));
+            }
+        }
+        .map_err(|e| BatchError::parameter("addr", format!("无法监听 {}: {}", config.addr, e)))?;
+        let loopback = server
+            .server_addr()
+            .to_ip()
+            .is_some_and(|addr| addr.ip().is_loopback());
+        if config.token.is_empty() && !loopback {
+            let message = format!("监听非回环地址 {} 时须设置访问令牌", config.addr);
+            return Err(BatchError::parameter("token", message));
+        }
+        Ok(Self {
+            server,
+            output_dir,
+            token: config.token.clone(),
+            jobs: Jobs::default(),
+        })
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// 处理请求直到进程退出，请求由固定数量的线程处理
+    pub fn run(self) {
+        let service = Arc::new(self);
+        let workers: Vec<_> = (0..REQUEST_THREADS)
+            .map(|_| {
+                let service = Arc::clone(&service);
+                thread::spawn(move || {
+                    while let Ok(request) = service.server.recv() {
+                        service.handle(request);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let response = if !self.authorized(&request) {
+            let error = BatchError::parameter("token", "缺少或错误的访问令牌");
+            json_response(401, &error)
+        } else if self.token.is_empty() && !local_request(&request) {
+            let error = BatchError::parameter("host", "未设置访问令牌时只接受本机地址的请求");
+            json_response(403, &error)
+        } else {
+            match self.route(&mut request) {
+                Ok(response) => response,
+                Err(ApiError(status, error)) => json_response(status, &error),
+            }
+        };
+        let _ = request.respond(response);
+    }
+
+    /// 未设置令牌时（仅回环地址）不检查；令牌按常量时间比较
+    fn authorized(&self, request: &Request) -> bool {
+        if self.token.is_empty() {
+            return true;
+        }
+        let expected = self.token.as_bytes();
+        request.headers().iter().any(|h| {
+            h.field.equiv("Authorization")
+                && h.value
+                    .as_str()
+                    .strip_prefix("Bearer ")
+                    .is_some_and(|token| {
+                        token.len() == expected.len() && memcmp::eq(token.as_bytes(), expected)
+                    })
+        })
+    }
+
+    fn route(&self, request: &mut Request) -> Result<ResponseBox, ApiError> {
+        let path = request.url().split('?').next().unwrap_or("").to_string();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        match (request.method(), segments.as_slice()) {
+            (Method::Get, ["jobs"]) => {
+                let jobs = self.jobs.lock().unwrap();
+                let statuses: Vec<&JobStatus> = jobs.values().map(|job| &job.status).collect();
+                Ok(json_response(200, &statuses))
+            }
+            (Method::Post, ["jobs"]) => {
+                let job_id = self.submit(read_request(request)?)?;
+                Ok(json_response(202, &serde_json::json!({ "job_id": job_id })))
+            }
+            (Method::Get, ["jobs", job_id]) => Ok(json_response(200, &self.status(job_id)?)),
+            (Method::Delete, ["jobs", job_id]) => {
+                if let Some(job) = self.jobs.lock().unwrap().get(*job_id) {
+                    job.cancel.cancel();
+                }
+                Ok(json_response(202, &self.status(job_id)?))
+            }
+            (Method::Get, ["jobs", job_id, "files"]) => {
+                Ok(json_response(200, &self.list_files(job_id)?))
+            }
+            (Method::Get, ["jobs", job_id, "files", name]) => self.download(job_id, name),
+            _ => Err(not_found(
+                "path",
+                format!("不支持的请求: {} {}", request.method(), path),
+            )),
+        }
+    }
+
+    fn status(&self, job_id: &str) -> Result<JobStatus, ApiError> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get(job_id)
+            .ok_or_else(|| not_found("job_id", format!("任务不存在: {}", job_id)))?;
+        Ok(job.status.clone())
+    }
+
+    /// 登记任务并在后台线程中生成，输出写入任务目录
+    fn submit(&self, mut request: BatchRequest) -> Result<String, ApiError> {
+        check_restricted(&request)?;
+        let items = batch_items(&request).map_err(BatchError::from)?.len();
+        if items > MAX_JOB_ITEMS {
+            let message = format!(
+                "服务模式每个任务最多{}个条目，当前为{}",
+                MAX_JOB_ITEMS, items
+            );
+            return Err(BatchError::parameter("cn_range", message).into());
+        }
+        let parallelism = thread::available_parallelism().map_or(1, |n| n.get());
+        request.thread_count = match request.thread_count {
+            0 => parallelism,
+            count => count.min(parallelism),
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.values().filter(|job| job.finished.is_none()).count() >= MAX_RUNNING_JOBS {
+            let message = format!("同时执行的任务已达上限{}个，请稍后重试", MAX_RUNNING_JOBS);
+            return Err(ApiError(429, BatchError::parameter("jobs", message)));
+        }
+        self.evict_finished(&mut jobs);
+        let job_id = uuid_v4().map_err(BatchError::from)?;
+        let dir = self.output_dir.join(&job_id);
+        fs::create_dir_all(&dir).map_err(|e| BatchError::io(&dir, e))?;
+        let file_name = Path::new(&request.output_path)
+            .file_name()
+            .map_or("csr.csv".into(), |name| name.to_string_lossy().to_string());
+        request.output_path = dir.join(file_name).to_string_lossy().to_string();
+        request.job_id = job_id.clone();
+        request.append = false;
+        request.checkpoint_path.clear();
+
+        let cancel = CancelToken::default();
+        let status = JobStatus {
+            job_id: job_id.clone(),
+            state: "running",
+            progress: None,
+            result: None,
+            error: None,
+        };
+        jobs.insert(
+            job_id.clone(),
+            ServerJob {
+                status,
+                cancel: cancel.clone(),
+                finished: None,
+            },
+        );
+        drop(jobs);
+
+        let jobs = Arc::clone(&self.jobs);
+        let task_job_id = job_id.clone();
+        thread::spawn(move || {
+            let on_progress = |progress: ProgressEvent| {
+                if let Some(job) = jobs.lock().unwrap().get_mut(&task_job_id) {
+                    job.status.progress = Some(progress);
+                }
+            };
+            let hooks = CompletionHooks::from_request(&request);
+            let mut result = run_batch(request, &cancel, &on_progress);
+            let hook_warnings = hooks.run(&result);
+            if let Ok(report) = &mut result {
+                report.warnings.extend(hook_warnings);
+            }
+            if let Some(job) = jobs.lock().unwrap().get_mut(&task_job_id) {
+                job.finished = Some(Instant::now());
+                job.status.state = job_state(&result);
+                match result {
+                    Ok(report) => job.status.result = Some(report),
+                    Err(error) => job.status.error = Some(error),
+                }
+            }
+        });
+        Ok(job_id)
+    }
+
+    /// 已结束的任务超过上限时删除最早结束的任务及其输出目录
+    fn evict_finished(&self, jobs: &mut BTreeMap<String, ServerJob>) {
+        let mut finished: Vec<(Instant, String)> = jobs
+            .iter()
+            .filter_map(|(id, job)| job.finished.map(|at| (at, id.clone())))
+            .collect();
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort();
+        for (_, job_id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            jobs.remove(job_id);
+            let _ = fs::remove_dir_all(self.output_dir.join(job_id));
+        }
+    }
+
+    /// 任务目录，任务不存在时返回404
+    fn job_dir(&self, job_id: &str) -> Result<PathBuf, ApiError> {
+        self.status(job_id)?;
+        Ok(self.output_dir.join(job_id))
+    }
+
+    /// 任务目录中的文件名（不含子目录和生成中的临时文件）
+    fn list_files(&self, job_id: &str) -> Result<Vec<String>, ApiError> {
+        let dir = self.job_dir(job_id)?;
+        let entries = fs::read_dir(&dir).map_err(|e| BatchError::io(&dir, e))?;
+        let mut files: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    fn download(&self, job_id: &str, name: &str) -> Result<ResponseBox, ApiError> {
+        if !self.list_files(job_id)?.iter().any(|file| file == name) {
+            return Err(not_found("name", format!("文件不存在: {}", name)));
+        }
+        let path = self.job_dir(job_id)?.join(name);
+        let file = File::open(&path).map_err(|e| BatchError::io(&path, e))?;
+        let disposition = format!("attachment; filename=\"{}\"", name.replace('"', ""));
+        Ok(Response::from_file(file)
+            .with_header(header("Content-Type", "application/octet-stream"))
+            .with_header(header("Content-Disposition", &disposition))
+            .boxed())
+    }
+}
+
+/// 请求的Host是否为本机地址且不带Origin头（未设置令牌时防止DNS重绑定和跨站请求）
+fn local_request(request: &Request) -> bool {
+    let mut host = None;
+    for header in request.headers() {
+        if header.field.equiv("Origin") {
+            return false;
+        }
+        if header.field.equiv("Host") {
+            host = Some(header.value.as_str());
+        }
+    }
+    let Some(host) = host else {
+        return false;
+    };
+    // 去掉端口，IPv6地址写在方括号中
+    let name = match host.rsplit_once(':') {
+        Some((name, port))
+            if port.bytes().all(|b| b.is_ascii_digit())
+                && (name.starts_with('[') || !name.contains(':')) =>
+        {
+            name
+        }
+        _ => host,
+    };
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// 拒绝读写任务目录以外的文件、执行命令、向其他地址发送请求或使用系统密钥存储的请求
+fn check_restricted(request: &BatchRequest) -> Result<(), BatchError> {
+    let restricted = [
+        ("input_csv_path", &request.input_csv_path),
+        ("exclude_path", &request.exclude_path),
+        ("issuer_cert_path", &request.issuer_cert_path),
+        ("sqlite_path", &request.sqlite_path),
+        ("output_dir", &request.output_dir),
+        ("manifest_signing_key", &request.manifest_signing_key),
+        ("existing_key_dir", &request.existing_key_dir),
+        ("weak_key_blocklist", &request.weak_key_blocklist),
+        ("history_db_path", &request.history_db_path),
+        ("audit_log_path", &request.audit_log_path),
+        ("on_complete_command", &request.on_complete_command),
+        ("webhook_url", &request.webhook_url),
+    ];
+    for (field, value) in restricted {
+        if !value.trim().is_empty() {
+            let message = format!("服务模式不支持参数{}", field);
+            return Err(BatchError::parameter(field, message));
+        }
+    }
+    if !matches!(request.key_store.as_str(), "" | "file") {
+        let message = "服务模式只支持文件密钥存储";
+        return Err(BatchError::parameter("key_store", message));
+    }
+    Ok(())
+}
+
+/// 读取并解析JSON请求体
+fn read_request(request: &mut Request) -> Result<BatchRequest, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE)
+        .read_to_string(&mut body)
+        .map_err(|e| BatchError::parameter("body", format!("无法读取请求体: {}", e)))?;
+    serde_json::from_str(&body)
+        .map_err(|e| BatchError::parameter("body", format!("生成参数格式错误: {}", e)).into())
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("响应头有效")
+}
+
+fn json_response(status: u16, body: &impl Serialize) -> ResponseBox {
+    let content = serde_json::to_vec(body).expect("响应可序列化为JSON");
+    Response::from_data(content)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json; charset=utf-8"))
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    /// 发送请求，返回状态码和响应体
+    fn call(request: ureq::Request, body: Option<&Value>) -> (u16, String) {
+        let result = match body {
+            Some(body) => request.send_string(&body.to_string()),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => {
+                (response.status(), response.into_string().unwrap())
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn test_server_jobs() {
+        let dir = std::env::temp_dir().join("csr_batch_server_test");
+        fs::remove_dir_all(&dir).ok();
+        let config = ServerConfig {
+            addr: "127.0.0.1:0".to_string(),
+            output_dir: dir.to_string_lossy().to_string(),
+            token: "secret".to_string(),
+            ..Default::default()
+        };
+        let server = CsrServer::bind(&config).unwrap();
+        let base = format!("http://{}", server.local_addr().unwrap());
+        std::thread::spawn(move || server.run());
+        let get = |path: &str| {
+            let request = ureq::get(&format!("{}{}", base, path));
+            call(request.set("Authorization", "Bearer secret"), None)
+        };
+        let get_json = |path: &str| -> Value { serde_json::from_str(&get(path).1).unwrap() };
+
+        assert_eq!(call(ureq::get(&format!("{}/jobs", base)), None).0, 401);
+        let body = json!({
+            "cn_range": "YDL0001-YDL0003",
+            "subject_template": "CN=[{CN}]",
+            "key_type": "EC_P256",
+            "sign_hash_alg": "SHA256",
+            "not_before": "2025-01-01T00:00:00Z",
+            "not_after": "2026-01-01T00:00:00Z",
+            "unique_id": "",
+            "sans": "",
+            "output_path": "/etc/out.csv",
+        });
+        let request = ureq::post(&format!("{}/jobs", base)).set("Authorization", "Bearer secret");
+        let (status, submitted) = call(request, Some(&body));
+        assert_eq!(status, 202, "{}", submitted);
+        let submitted: Value = serde_json::from_str(&submitted).unwrap();
+        let job_id = submitted["job_id"].as_str().unwrap();
+
+        let mut status = Value::Null;
+        for _ in 0..100 {
+            status = get_json(&format!("/jobs/{}", job_id));
+            if status["state"] != "running" {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(status["state"], "completed", "{}", status);
+        assert_eq!(status["result"]["total"], 3);
+
+        // 输出只写入任务目录
+        assert_eq!(
+            get_json(&format!("/jobs/{}/files", job_id)),
+            json!(["out.csv"])
+        );
+        let (_, csv) = get(&format!("/jobs/{}/files/out.csv", job_id));
+        assert!(csv.contains("CN=[YDL0003]"));
+        let escaped = format!("/jobs/{}/files/..%2F..%2Fout.csv", job_id);
+        assert_eq!(get(&escaped).0, 404);
+        assert_eq!(get("/jobs/unknown").0, 404);
+
+        // 读写任务目录以外文件的参数和系统密钥存储被拒绝
+        for (field, value) in [
+            ("input_csv_path", "/etc/passwd"),
+            ("existing_key_dir", "/etc/ssl/private"),
+            ("audit_log_path", "/tmp/audit.jsonl"),
+            ("webhook_url", "http://169.254.169.254/"),
+            ("key_store", "tpm"),
+        ] {
+            let mut body = body.clone();
+            body[field] = json!(value);
+            let request =
+                ureq::post(&format!("{}/jobs", base)).set("Authorization", "Bearer secret");
+            let (status, error) = call(request, Some(&body));
+            assert_eq!(status, 400, "{}", field);
+            assert!(error.contains(field), "{}", error);
+        }
+        let request = ureq::get(&format!("{}/jobs", base)).set("Authorization", "Bearer secreT");
+        assert_eq!(call(request, None).0, 401);
+
+        // 条目数超过上限的任务被拒绝
+        let mut large = body.clone();
+        large["cn_range"] = json!("A00001-A20000");
+        let request = ureq::post(&format!("{}/jobs", base)).set("Authorization", "Bearer secret");
+        let (status, error) = call(request, Some(&large));
+        assert_eq!(status, 400);
+        assert!(error.contains("cn_range"), "{}", error);
+
+        // 未设置令牌时只接受Host为本机地址、不带Origin头的请求
+        let local = ServerConfig {
+            output_dir: dir.join("local").to_string_lossy().to_string(),
+            token: String::new(),
+            ..config.clone()
+        };
+        let server = CsrServer::bind(&local).unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+        let jobs = format!("http://{}/jobs", addr);
+        assert_eq!(call(ureq::get(&jobs), None).0, 200);
+        let port = addr.port();
+        for host in [format!("localhost:{}", port), "[::1]".to_string()] {
+            assert_eq!(
+                call(ureq::get(&jobs).set("Host", &host), None).0,
+                200,
+                "{}",
+                host
+            );
+        }
+        for host in [
+            format!("evil.example:{}", port),
+            "127.0.0.1.evil.example".to_string(),
+        ] {
+            assert_eq!(
+                call(ureq::get(&jobs).set("Host", &host), None).0,
+                403,
+                "{}",
+                host
+            );
+        }
+        let request = ureq::post(&jobs).set("Origin", "http://evil.example");
+        assert_eq!(call(request, Some(&body)).0, 403);
+
+        // 监听非回环地址时须设置令牌
+        let open = ServerConfig {
+            addr: "0.0.0.0:0".to_string(),
+            token: String::new(),
+            ..config
+        };
+        assert!(CsrServer::bind(&open).is_err());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_evict_finished_jobs() {
+        let dir = std::env::temp_dir().join("csr_batch_server_evict_test");
+        fs::remove_dir_all(&dir).ok();
+        let config = ServerConfig {
+            addr: "127.0.0.1:0".to_string(),
+            output_dir: dir.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let server = CsrServer::bind(&config).unwrap();
+        let job = |job_id: String, finished: Option<Instant>| ServerJob {
+            status: JobStatus {
+                job_id,
+                state: "completed",
+                progress: None,
+                result: None,
+                error: None,
+            },
+            cancel: CancelToken::default(),
+            finished,
+        };
+        let mut jobs = server.jobs.lock().unwrap();
+        jobs.insert("running".to_string(), job("running".to_string(), None));
+        for index in 0..=MAX_FINISHED_JOBS {
+            let job_id = format!("job{:03}", index);
+            fs::create_dir_all(dir.join(&job_id)).unwrap();
+            jobs.insert(job_id.clone(), job(job_id, Some(Instant::now())));
+        }
+
+        // 只删除最早结束的任务及其输出，执行中的任务保留
+        server.evict_finished(&mut jobs);
+        assert_eq!(jobs.len(), MAX_FINISHED_JOBS + 1);
+        assert!(!jobs.contains_key("job000") && !dir.join("job000").exists());
+        assert!(jobs.contains_key("job001") && jobs.contains_key("running"));
+        drop(jobs);
+        fs::remove_dir_all(dir).ok();
+    }
+}
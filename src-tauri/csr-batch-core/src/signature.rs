@@ -0,0 +1,369 @@
+//! 签名方案模块
+//! RSA密钥默认使用PKCS#1 v1.5签名CSR，可选RSASSA-PSS（可配置盐长度和MGF1哈希）；
+//! 其他密钥类型使用各自算法的标准签名
+
+use anyhow::{anyhow, Result};
+use foreign_types::ForeignTypeRef;
+use openssl::asn1::Asn1Object;
+use openssl::bn::BigNum;
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::md::MdRef;
+use openssl::md_ctx::MdCtx;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKeyRef, Private};
+use openssl::rsa::Padding;
+use openssl::sign::RsaPssSaltlen;
+use openssl::x509::{X509Ref, X509Req, X509ReqRef, X509};
+use openssl_sys as ffi;
+use std::fs;
+use std::os::raw::{c_int, c_uint};
+
+use crate::csr_generator::message_digest;
+use crate::error::BatchError;
+
+extern "C" {
+    // openssl-sys未导出该函数，签名上下文需预先设置PSS参数
+    fn X509_REQ_sign_ctx(req: *mut ffi::X509_REQ, ctx: *mut ffi::EVP_MD_CTX) -> c_int;
+    // openssl-sys未导出该函数，外部签名器需要待签名的CertificationRequestInfo
+    fn i2d_re_X509_REQ_tbs(req: *mut ffi::X509_REQ, pp: *mut *mut u8) -> c_int;
+}
+
+/// CSR签名方案
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignatureScheme {
+    /// PKCS#1 v1.5（默认）
+    #[default]
+    Pkcs1,
+    /// RSASSA-PSS
+    Pss {
+        /// 盐长度，None表示与摘要长度相同
+        salt_length: Option<c_int>,
+        /// MGF1哈希算法，None表示与签名哈希算法相同
+        mgf1_hash: Option<&'static str>,
+    },
+}
+
+impl SignatureScheme {
+    /// 从参数解析签名方案：空字符串或pkcs1为PKCS#1 v1.5，pss为RSASSA-PSS
+    pub(crate) fn parse(scheme: &str, salt_length: Option<u32>, mgf1_hash: &str) -> Result<Self> {
+        match scheme.to_ascii_lowercase().as_str() {
+            "" | "pkcs1" => Ok(SignatureScheme::Pkcs1),
+            "pss" => {
+                let salt_length = salt_length
+                    .map(c_int::try_from)
+                    .transpose()
+                    .map_err(|e| BatchError::parameter("pss_salt_length", e))?;
+                let mgf1_hash = match mgf1_hash {
+                    "" => None,
+                    "SHA256" => Some("SHA256"),
+                    "SHA384" => Some("SHA384"),
+                    "SHA512" => Some("SHA512"),
+                    "SHA1" => Some("SHA1"),
+                    _ => {
+                        let message = format!("不支持的MGF1哈希算法: {}", mgf1_hash);
+                        return Err(BatchError::parameter("pss_mgf1_hash", message).into());
+                    }
+                };
+                Ok(SignatureScheme::Pss {
+                    salt_length,
+                    mgf1_hash,
+                })
+            }
+            _ => {
+                let message = format!("不支持的签名方案: {}", scheme);
+                Err(BatchError::parameter("signature_scheme", message).into())
+            }
+        }
+    }
+}
+
+/// 使用私钥签名CSR（须在添加全部属性之后调用）
+pub(crate) fn sign_req(
+    req: &X509ReqRef,
+    pkey: &PKeyRef<Private>,
+    digest: MessageDigest,
+    scheme: SignatureScheme,
+) -> Result<()> {
+    let SignatureScheme::Pss {
+        salt_length,
+        mgf1_hash,
+    } = scheme
+    else {
+        // SAFETY: 三个指针均来自有效的OpenSSL对象
+        let ret = unsafe { ffi::X509_REQ_sign(req.as_ptr(), pkey.as_ptr(), digest.as_ptr()) };
+        if ret <= 0 {
+            return Err(anyhow!("CSR签名失败: {}", ErrorStack::get()));
+        }
+        return Ok(());
+    };
+
+    let mgf1_digest = mgf1_hash.map(message_digest).unwrap_or(digest);
+    // SAFETY: MessageDigest指向OpenSSL内置的静态EVP_MD，在进程生命周期内有效
+    let (md, mgf1_md) = unsafe {
+        (
+            MdRef::from_ptr(digest.as_ptr() as *mut ffi::EVP_MD),
+            MdRef::from_ptr(mgf1_digest.as_ptr() as *mut ffi::EVP_MD),
+        )
+    };
+
+    let mut ctx = MdCtx::new()?;
+    let pkey_ctx = ctx.digest_sign_init(Some(md), pkey)?;
+    pkey_ctx.set_rsa_padding(Padding::PKCS1_PSS)?;
+    pkey_ctx.set_rsa_pss_saltlen(match salt_length {
+        Some(len) => RsaPssSaltlen::custom(len),
+        None => RsaPssSaltlen::DIGEST_LENGTH,
+    })?;
+    pkey_ctx.set_rsa_mgf1_md(mgf1_md)?;
+
+    // SAFETY: req和ctx均为有效的OpenSSL对象，ctx已用私钥初始化
+    let ret = unsafe { X509_REQ_sign_ctx(req.as_ptr(), ctx.as_ptr()) };
+    if ret <= 0 {
+        return Err(anyhow!("CSR签名失败: {}", ErrorStack::get()));
+    }
+    Ok(())
+}
+
+/// 使用RFC 6979确定性随机数签名ECDSA的CSR（确定性模式，需要OpenSSL 3.2及以上），
+/// 相同私钥和请求内容总是得到相同的签名
+pub(crate) fn sign_req_deterministic(
+    req: &X509ReqRef,
+    pkey: &PKeyRef<Private>,
+    digest: MessageDigest,
+) -> Result<()> {
+    // SAFETY: MessageDigest指向OpenSSL内置的静态EVP_MD，在进程生命周期内有效
+    let md = unsafe { MdRef::from_ptr(digest.as_ptr() as *mut ffi::EVP_MD) };
+    let mut ctx = MdCtx::new()?;
+    let pkey_ctx = ctx.digest_sign_init(Some(md), pkey)?;
+
+    // openssl crate仅在以OpenSSL 3.2构建时提供set_nonce_type，此处直接设置参数
+    let mut nonce_type: c_uint = 1;
+    // SAFETY: 参数数组以END结尾，nonce_type在调用期间有效
+    let ret = unsafe {
+        let params = [
+            ffi::OSSL_PARAM_construct_uint(c"nonce-type".as_ptr(), &mut nonce_type),
+            ffi::OSSL_PARAM_construct_end(),
+        ];
+        ffi::EVP_PKEY_CTX_set_params(pkey_ctx.as_ptr(), params.as_ptr())
+    };
+    if ret <= 0 {
+        return Err(anyhow!(
+            "确定性ECDSA签名需要OpenSSL 3.2及以上版本: {}",
+            ErrorStack::get()
+        ));
+    }
+
+    // SAFETY: req和ctx均为有效的OpenSSL对象，ctx已用私钥初始化
+    let ret = unsafe { X509_REQ_sign_ctx(req.as_ptr(), ctx.as_ptr()) };
+    if ret <= 0 {
+        return Err(anyhow!("CSR签名失败: {}", ErrorStack::get()));
+    }
+    Ok(())
+}
+
+/// 读取签发者证书（PEM或DER格式）
+pub(crate) fn read_issuer_cert(path: &str) -> Result<X509> {
+    let bytes = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+    X509::from_pem(&bytes)
+        .or_else(|_| X509::from_der(&bytes))
+        .map_err(|e| BatchError::io(path, format!("证书无效: {}", e)).into())
+}
+
+/// MatchIssuer：与签发者证书的签名算法使用相同的哈希算法；签名算法不直接对应摘要时
+/// （如EdDSA、RSASSA-PSS）按签发者公钥强度选择：P-384取SHA384，P-521取SHA512，其余取SHA256
+pub(crate) fn issuer_hash_alg(issuer: &X509Ref) -> Result<&'static str> {
+    let digest = issuer
+        .signature_algorithm()
+        .object()
+        .nid()
+        .signature_algorithms()
+        .map(|algorithms| algorithms.digest);
+    let hash_alg = match digest {
+        Some(Nid::SHA1) => Some("SHA1"),
+        Some(Nid::SHA256) => Some("SHA256"),
+        Some(Nid::SHA384) => Some("SHA384"),
+        Some(Nid::SHA512) => Some("SHA512"),
+        _ => None,
+    };
+    if let Some(hash_alg) = hash_alg {
+        return Ok(hash_alg);
+    }
+    let key = issuer.public_key()?;
+    Ok(match (key.id(), key.bits()) {
+        (Id::EC, bits) if bits > 384 => "SHA512",
+        (Id::EC, bits) if bits > 256 => "SHA384",
+        _ => "SHA256",
+    })
+}
+
+/// 外部签名算法的OID，RSA为PKCS#1 v1.5，EC为ECDSA
+pub(crate) fn external_signature_oid(rsa: bool, sign_hash_alg: &str) -> &'static str {
+    match (rsa, sign_hash_alg) {
+        (true, "SHA1") => "1.2.840.113549.1.1.5",
+        (true, "SHA384") => "1.2.840.113549.1.1.12",
+        (true, "SHA512") => "1.2.840.113549.1.1.13",
+        (true, _) => "1.2.840.113549.1.1.11",
+        (false, "SHA1") => "1.2.840.10045.4.1",
+        (false, "SHA384") => "1.2.840.10045.4.3.3",
+        (false, "SHA512") => "1.2.840.10045.4.3.4",
+        (false, _) => "1.2.840.10045.4.3.2",
+    }
+}
+
+/// DER编码的TLV
+pub(crate) fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if content.len() < 0x80 {
+        out.push(content.len() as u8);
+    } else {
+        let len = content.len().to_be_bytes();
+        let skip = len.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (len.len() - skip) as u8);
+        out.extend_from_slice(&len[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// OID的DER编码
+pub(crate) fn oid(dotted: &str) -> Result<Vec<u8>> {
+    Ok(der(0x06, Asn1Object::from_str(dotted)?.as_slice()))
+}
+
+/// SEQUENCE的DER编码
+pub(crate) fn sequence(parts: &[&[u8]]) -> Vec<u8> {
+    der(0x30, &parts.concat())
+}
+
+/// 正整数的DER编码
+pub(crate) fn integer(value: &BigNum) -> Vec<u8> {
+    let mut bytes = value.to_vec();
+    if bytes.first().is_none_or(|b| b & 0x80 != 0) {
+        bytes.insert(0, 0);
+    }
+    der(0x02, &bytes)
+}
+
+/// 读取一个DER元素，返回标签、内容和剩余部分
+pub(crate) fn read_tlv(input: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let malformed = || anyhow!("DER格式错误");
+    let (&tag, rest) = input.split_first().ok_or_else(malformed)?;
+    let (&first, mut rest) = rest.split_first().ok_or_else(malformed)?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err(malformed());
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | *b as usize);
+        rest = &rest[count..];
+        len
+    };
+    if rest.len() < len {
+        return Err(malformed());
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+/// 构造类型内容中的各个元素
+pub(crate) fn children(mut content: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+    let mut items = Vec::new();
+    while !content.is_empty() {
+        let (tag, value, rest) = read_tlv(content)?;
+        items.push((tag, value));
+        content = rest;
+    }
+    Ok(items)
+}
+
+/// 使用外部签名器签名CSR（私钥保存在钥匙串、TPM等OpenSSL之外的位置）
+/// sign接收待签名的CertificationRequestInfo DER，返回DER编码的签名值
+pub(crate) fn sign_req_external(
+    req: &X509ReqRef,
+    rsa: bool,
+    sign_hash_alg: &str,
+    sign: impl FnOnce(&[u8]) -> Result<Vec<u8>>,
+) -> Result<X509Req> {
+    // SAFETY: req为有效的X509_REQ指针，第二次调用写入按第一次返回的长度分配的缓冲区
+    let tbs = unsafe {
+        let len = i2d_re_X509_REQ_tbs(req.as_ptr(), std::ptr::null_mut());
+        if len <= 0 {
+            return Err(anyhow!("无法编码CSR: {}", ErrorStack::get()));
+        }
+        let mut tbs = vec![0u8; len as usize];
+        let mut out = tbs.as_mut_ptr();
+        i2d_re_X509_REQ_tbs(req.as_ptr(), &mut out);
+        tbs
+    };
+    let signature = sign(&tbs)?;
+
+    // CertificationRequest ::= SEQUENCE { certificationRequestInfo, signatureAlgorithm, signature }
+    let oid = Asn1Object::from_str(external_signature_oid(rsa, sign_hash_alg))?;
+    let mut algorithm = der(0x06, oid.as_slice());
+    if rsa {
+        // RSA签名算法的参数为NULL，ECDSA省略参数
+        algorithm.extend_from_slice(&[0x05, 0x00]);
+    }
+    let mut bit_string = vec![0u8];
+    bit_string.extend_from_slice(&signature);
+    let mut content = tbs;
+    content.extend(der(0x30, &algorithm));
+    content.extend(der(0x03, &bit_string));
+    Ok(X509Req::from_der(&der(0x30, &content))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::build_req;
+    use crate::extensions::ExtensionOptions;
+    use crate::subject::parse_subject;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::sign::Signer;
+
+    #[test]
+    fn test_issuer_hash_alg() {
+        let issuer = |key: &PKey<Private>, digest| {
+            let mut builder = X509::builder().unwrap();
+            builder.set_pubkey(key).unwrap();
+            builder.sign(key, digest).unwrap();
+            builder.build()
+        };
+        let p384 = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let p384 = PKey::from_ec_key(EcKey::generate(&p384).unwrap()).unwrap();
+        let cert = issuer(&p384, MessageDigest::sha384());
+        assert_eq!(issuer_hash_alg(&cert).unwrap(), "SHA384");
+        let cert = issuer(&p384, MessageDigest::sha1());
+        assert_eq!(issuer_hash_alg(&cert).unwrap(), "SHA1");
+
+        let ed25519 = PKey::generate_ed25519().unwrap();
+        let cert = issuer(&ed25519, MessageDigest::null());
+        assert_eq!(issuer_hash_alg(&cert).unwrap(), "SHA256");
+    }
+
+    #[test]
+    fn test_sign_req_external() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let pkey = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let subject = parse_subject("CN=YDL0001,O=Example").unwrap();
+        let key_usage = ExtensionOptions {
+            key_usage: "digitalSignature",
+            ..Default::default()
+        };
+        let req = build_req(&subject, &pkey, &key_usage).unwrap();
+
+        let signed = sign_req_external(&req, false, "SHA384", |tbs| {
+            let mut signer = Signer::new(MessageDigest::sha384(), &pkey)?;
+            Ok(signer.sign_oneshot_to_vec(tbs)?)
+        })
+        .unwrap();
+        // 验证时按CSR中的签名算法选择摘要，算法标识错误时验证失败
+        assert!(signed.verify(&pkey).unwrap());
+        assert_eq!(signed.extensions().unwrap().len(), 1);
+    }
+}
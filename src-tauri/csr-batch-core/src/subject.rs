@@ -0,0 +1,769 @@
+//! Subject主题解析模块
+//! 将Subject模板解析为DN组件，并据此构建CSR使用的X509Name
+//!
+//! 支持两种书写格式：
+//! - 方括号格式: `CN=[{CN}]; O=[TrustAsia Technologies\, Inc.]; OU=[部门1,部门2]`
+//!   属性之间用分号分隔，方括号内多值用逗号分隔
+//! - 逗号格式: `CN={CN},O=Example\, Inc.,OU=Dev`
+//!
+//! 值中的特殊字符（`,` `;` `[` `]` `+`）用反斜杠转义
+//!
+//! RDN按书写顺序编码，不做重排；用 `+` 连接的属性组成一个多值RDN，如 `OU=[Dev]+OU=[Ops]` 或 `OU=Dev+OU=Ops`。
+//! `+` 之后不是 `属性=` 时视为值的一部分（如 `O=A+B Corp`）。多值RDN是DER的SET OF，其中各属性按DER规则排序编码
+//!
+//! 属性值默认由OpenSSL选择字符串类型（C为PrintableString，emailAddress为IA5String，其余多为UTF8String），
+//! 可按属性强制为PrintableString或UTF8String，如 `C=printable,O=utf8`（见 [`parse_string_types`]）
+
+use anyhow::{anyhow, Result};
+use foreign_types::ForeignTypeRef;
+use openssl::asn1::{Asn1Object, Asn1ObjectRef, Asn1Type};
+use openssl::error::ErrorStack;
+use openssl::x509::{X509Name, X509NameBuilder, X509NameRef};
+use openssl_sys as ffi;
+use regex::Regex;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+
+use crate::error::BatchError;
+
+extern "C" {
+    // openssl-sys未导出该函数，返回条目所在RDN的序号
+    fn X509_NAME_ENTRY_set(entry: *const ffi::X509_NAME_ENTRY) -> c_int;
+}
+
+/// DN属性
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnAttribute {
+    /// 属性名称（OpenSSL短名称或点分OID）
+    pub attr: String,
+    /// 属性值
+    pub value: String,
+    /// 强制使用的字符串类型，None时由OpenSSL按属性选择
+    pub string_type: Option<DnStringType>,
+    /// 与前一个属性同属一个RDN（多值RDN）
+    pub multi_valued: bool,
+}
+
+/// DN属性值的字符串类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnStringType {
+    /// PrintableString，仅限字母、数字、空格和 `'()+,-./:=?`
+    Printable,
+    /// UTF8String
+    Utf8,
+}
+
+impl DnStringType {
+    fn asn1_type(self) -> Asn1Type {
+        match self {
+            DnStringType::Printable => Asn1Type::PRINTABLESTRING,
+            DnStringType::Utf8 => Asn1Type::UTF8STRING,
+        }
+    }
+
+    /// 校验属性值能否以该类型编码，返回第一个不允许的字符
+    fn invalid_char(self, value: &str) -> Option<char> {
+        match self {
+            DnStringType::Printable => value
+                .chars()
+                .find(|c| !(c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(*c))),
+            DnStringType::Utf8 => None,
+        }
+    }
+}
+
+/// 解析按属性指定的字符串类型，如 `C=printable,O=utf8`（逗号、分号或换行分隔），
+/// 类型不区分大小写，可写作 printable/PrintableString、utf8/UTF8String
+pub(crate) fn parse_string_types(spec: &str) -> Result<Vec<(String, DnStringType)>> {
+    let mut types = Vec::new();
+    for entry in spec.split([',', ';', '\n']).map(str::trim) {
+        if entry.is_empty() {
+            continue;
+        }
+        let invalid = |message: String| BatchError::parameter("dn_string_types", message);
+        let (attr, string_type) = entry
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("格式须为 属性=类型: {}", entry)))?;
+        let attr = normalize_attr(attr.trim()).map_err(|e| invalid(e.to_string()))?;
+        let string_type = match string_type.trim().to_ascii_lowercase().as_str() {
+            "printable" | "printablestring" => DnStringType::Printable,
+            "utf8" | "utf8string" => DnStringType::Utf8,
+            other => {
+                let message = format!("不支持的字符串类型: {}，可选 printable、utf8", other);
+                return Err(invalid(message).into());
+            }
+        };
+        types.retain(|(existing, _)| *existing != attr);
+        types.push((attr, string_type));
+    }
+    Ok(types)
+}
+
+/// 按属性设置字符串类型，并校验属性值中是否有该类型不允许的字符
+pub(crate) fn apply_string_types(
+    attributes: &mut [DnAttribute],
+    types: &[(String, DnStringType)],
+) -> Result<()> {
+    for attribute in attributes.iter_mut() {
+        let Some((_, string_type)) = types.iter().find(|(attr, _)| *attr == attribute.attr) else {
+            continue;
+        };
+        if let Some(c) = string_type.invalid_char(&attribute.value) {
+            return Err(anyhow!(
+                "{}={} 含有PrintableString不允许的字符 '{}'",
+                attribute.attr,
+                attribute.value,
+                c
+            ));
+        }
+        attribute.string_type = Some(*string_type);
+    }
+    Ok(())
+}
+
+/// 将属性名称（不区分大小写）规范化为OpenSSL短名称，其他属性的点分OID原样返回
+/// 注意 `SN` 为姓氏(surname)，序列号须写作 `serialNumber`
+fn normalize_attr(key: &str) -> Result<String> {
+    let name = match key.to_ascii_uppercase().as_str() {
+        "CN" => "CN",
+        "O" => "O",
+        "OU" => "OU",
+        "C" => "C",
+        "ST" | "S" => "ST",
+        "L" => "L",
+        "E" | "EMAIL" | "EMAILADDRESS" => "emailAddress",
+        "SERIALNUMBER" => "serialNumber",
+        "DC" | "DOMAINCOMPONENT" => "DC",
+        "UID" | "USERID" => "UID",
+        "STREET" | "STREETADDRESS" => "street",
+        "T" | "TITLE" => "title",
+        "GN" | "G" | "GIVENNAME" => "GN",
+        "SN" | "SURNAME" => "SN",
+        "I" | "INITIALS" => "initials",
+        "POSTALCODE" => "postalCode",
+        "DNQUALIFIER" => "dnQualifier",
+        "PSEUDONYM" => "pseudonym",
+        "DESCRIPTION" => "description",
+        "BUSINESSCATEGORY" => "businessCategory",
+        "ORGANIZATIONIDENTIFIER" => "organizationIdentifier",
+        "JURISDICTIONC" | "JURISDICTIONCOUNTRYNAME" => "jurisdictionC",
+        "JURISDICTIONST" | "JURISDICTIONSTATEORPROVINCENAME" => "jurisdictionST",
+        "JURISDICTIONL" | "JURISDICTIONLOCALITYNAME" => "jurisdictionL",
+        _ => {
+            let oid_re = Regex::new(r"^\d+(\.\d+)+$")?;
+            if !oid_re.is_match(key) {
+                return Err(anyhow!("不支持的DN属性: {}", key));
+            }
+            // 支持的属性写作OID时换为短名称，如 2.5.4.5 即 serialNumber
+            let short_name = Asn1Object::from_str(key)
+                .ok()
+                .and_then(|object| object.nid().short_name().ok())
+                .filter(|name| normalize_attr(name).ok().as_deref() == Some(*name));
+            return Ok(short_name.unwrap_or(key).to_string());
+        }
+    };
+    Ok(name.to_string())
+}
+
+/// 按分隔符切分字符串，忽略被转义或位于方括号内的分隔符
+pub(crate) fn split_unescaped(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut chars = s.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                // 跳过被转义的字符（包括 `\\`）
+                chars.next();
+            }
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            _ if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// 检查方括号是否配对（跳过被转义的字符）
+fn check_brackets(s: &str) -> Result<()> {
+    let mut depth = 0usize;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("Subject格式错误，多余的']': {}", s))?;
+            }
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return Err(anyhow!("Subject格式错误，方括号未闭合: {}", s));
+    }
+    Ok(())
+}
+
+/// 取出以 `[` 开头的属性值中与之配对的方括号内的内容，`]` 之后只能是空白
+fn bracket_inner(raw_value: &str) -> Option<&str> {
+    let rest = raw_value.strip_prefix('[')?;
+    let mut depth = 1usize;
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return rest[i + 1..].trim().is_empty().then_some(&rest[..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 去除转义反斜杠，`\x` 表示字面字符x（`\\` 为反斜杠本身），末尾单独的反斜杠原样保留
+pub(crate) fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) => out.push(next),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// 按未转义的 `+` 切分多值RDN，`+` 之后不是 `属性=` 时并入前一段
+fn split_multi_valued(part: &str) -> Result<Vec<String>> {
+    let attr_re = Regex::new(r"^\s*([A-Za-z][A-Za-z0-9]*|\d+(\.\d+)+)\s*=")?;
+    let mut segments: Vec<String> = Vec::new();
+    for segment in split_unescaped(part, '+') {
+        match segments.last_mut() {
+            Some(last) if !attr_re.is_match(segment) => {
+                last.push('+');
+                last.push_str(segment);
+            }
+            _ => segments.push(segment.to_string()),
+        }
+    }
+    Ok(segments)
+}
+
+/// Subject字符串是否为方括号格式
+fn is_bracketed(subject: &str) -> bool {
+    subject.contains("=[")
+}
+
+/// 解析Subject字符串为DN属性列表（保持书写顺序）
+pub fn parse_subject(subject: &str) -> Result<Vec<DnAttribute>> {
+    let bracketed = is_bracketed(subject);
+    let separator = if bracketed { ';' } else { ',' };
+    if bracketed {
+        check_brackets(subject)?;
+    }
+
+    let mut attributes = Vec::new();
+    for part in split_unescaped(subject, separator) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        let segments = split_multi_valued(part)?;
+        let multi_valued = segments.len() > 1;
+        let mut rdn_started = false;
+        for segment in &segments {
+            let segment = segment.trim();
+            let (key, raw_value) = segment
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Subject格式错误，缺少'=': {}", part))?;
+            let attr = normalize_attr(key.trim())?;
+            let raw_value = raw_value.trim();
+
+            let values = if bracketed {
+                let inner = bracket_inner(raw_value).ok_or_else(|| {
+                    anyhow!(
+                        "Subject格式错误，属性值需用方括号包裹，属性之间用分号分隔: {}",
+                        part
+                    )
+                })?;
+                split_unescaped(inner, ',')
+            } else {
+                vec![raw_value]
+            };
+            if multi_valued && values.len() > 1 {
+                return Err(anyhow!("多值RDN中的每个属性只能有一个值: {}", part));
+            }
+
+            for value in values {
+                let value = unescape(value.trim());
+                if value.is_empty() {
+                    continue;
+                }
+                attributes.push(DnAttribute {
+                    attr: attr.clone(),
+                    value,
+                    string_type: None,
+                    multi_valued: rdn_started,
+                });
+                rdn_started = multi_valued;
+            }
+        }
+    }
+
+    if attributes.is_empty() {
+        return Err(anyhow!("Subject模板中未包含任何DN属性"));
+    }
+
+    Ok(attributes)
+}
+
+/// 根据DN属性列表构建X509Name，RDN保持列表顺序
+pub fn build_x509_name(attributes: &[DnAttribute]) -> Result<X509Name> {
+    let name = X509NameBuilder::new()?.build();
+    for attribute in attributes {
+        let invalid = |e: &dyn std::fmt::Display| {
+            anyhow!("无效的DN属性 {}={}: {}", attribute.attr, attribute.value, e)
+        };
+        let field = CString::new(attribute.attr.as_str()).map_err(|e| invalid(&e))?;
+        let len = c_int::try_from(attribute.value.len()).map_err(|e| invalid(&e))?;
+        let string_type = match attribute.string_type {
+            Some(string_type) => string_type.asn1_type().as_raw(),
+            None => ffi::MBSTRING_UTF8,
+        };
+        // 多值RDN的属性加入最后一个RDN（set=-1），其余属性追加为新的RDN（set=0）
+        let set = if attribute.multi_valued { -1 } else { 0 };
+        // SAFETY: name为有效的X509_NAME，field和value在调用期间有效，OpenSSL复制属性值
+        let ret = unsafe {
+            ffi::X509_NAME_add_entry_by_txt(
+                name.as_ptr(),
+                field.as_ptr(),
+                string_type,
+                attribute.value.as_ptr(),
+                len,
+                -1,
+                set,
+            )
+        };
+        if ret <= 0 {
+            return Err(invalid(&ErrorStack::get()));
+        }
+    }
+    Ok(name)
+}
+
+/// 从已有证书的X509Name读取DN属性列表（保持证书中的顺序和多值RDN）
+/// 模板支持的属性使用短名称，其余属性使用点分OID
+pub(crate) fn from_x509_name(name: &X509NameRef) -> Result<Vec<DnAttribute>> {
+    let mut previous_set = None;
+    name.entries()
+        .map(|entry| {
+            // SAFETY: entry为name中的有效条目
+            let set = unsafe { X509_NAME_ENTRY_set(entry.as_ptr()) };
+            let multi_valued = previous_set.replace(set) == Some(set);
+            // 模板支持的属性使用其短名称
+            let short_name = entry.object().nid().short_name().ok();
+            let attr = match short_name
+                .filter(|name| normalize_attr(name).ok().as_deref() == Some(name))
+            {
+                Some(name) => name.to_string(),
+                None => oid_text(entry.object()),
+            };
+            Ok(DnAttribute {
+                attr,
+                value: entry.data().as_utf8()?.to_string(),
+                string_type: None,
+                multi_valued,
+            })
+        })
+        .collect()
+}
+
+/// 对象标识符的点分形式
+pub(crate) fn oid_text(object: &Asn1ObjectRef) -> String {
+    let mut buf = [0u8; 128];
+    // SAFETY: 缓冲区长度与传入的长度一致，no_name=1 时始终输出点分形式
+    let len = unsafe {
+        ffi::OBJ_obj2txt(
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len() as i32,
+            object.as_ptr(),
+            1,
+        )
+    };
+    String::from_utf8_lossy(&buf[..len.clamp(0, buf.len() as i32 - 1) as usize]).into_owned()
+}
+
+/// 将DN属性列表转换为逗号格式的Subject字符串（多值RDN以 `+` 连接），可再由 [`parse_subject`] 解析
+pub(crate) fn format_subject(attributes: &[DnAttribute]) -> String {
+    let mut subject = String::new();
+    for (i, attribute) in attributes.iter().enumerate() {
+        if i > 0 {
+            subject.push(if attribute.multi_valued { '+' } else { ',' });
+        }
+        subject.push_str(&attribute.attr);
+        subject.push('=');
+        push_escaped(&mut subject, &attribute.value);
+    }
+    subject
+}
+
+/// 按模板的书写格式将DN属性列表转换为Subject字符串：方括号格式沿用模板中分号后是否有空格，
+/// 逗号格式同 [`format_subject`]
+pub(crate) fn format_subject_as(template: &str, attributes: &[DnAttribute]) -> String {
+    if !is_bracketed(template) {
+        return format_subject(attributes);
+    }
+    let separator = separator(template);
+    let mut subject = String::new();
+    for (i, attribute) in attributes.iter().enumerate() {
+        if i > 0 {
+            subject.push_str(if attribute.multi_valued {
+                "+"
+            } else {
+                separator
+            });
+        }
+        subject.push_str(&attribute.attr);
+        subject.push_str("=[");
+        push_escaped(&mut subject, &attribute.value);
+        subject.push(']');
+    }
+    subject
+}
+
+/// 按Subject字符串的书写格式在末尾追加一个属性（单独的RDN），原有部分保持不变
+pub(crate) fn append_attribute(subject: &str, attribute: &DnAttribute) -> String {
+    let subject = subject.trim_end();
+    let attribute = format_subject_as(subject, std::slice::from_ref(attribute));
+    format!("{}{}{}", subject, separator(subject), attribute)
+}
+
+/// RDN之间的分隔符：方括号格式沿用模板中分号后是否有空格
+fn separator(template: &str) -> &'static str {
+    if !is_bracketed(template) {
+        ","
+    } else if template.contains("]; ") {
+        "; "
+    } else {
+        ";"
+    }
+}
+
+/// 转义两种格式中的特殊字符，使任意文本（如输入的CN）代入Subject模板后仍为单个属性值
+pub(crate) fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    push_escaped(&mut escaped, value);
+    escaped
+}
+
+/// 追加属性值，转义两种格式中的特殊字符
+fn push_escaped(subject: &mut String, value: &str) {
+    for c in value.chars() {
+        if matches!(c, ',' | ';' | '[' | ']' | '+' | '\\') {
+            subject.push('\\');
+        }
+        subject.push(c);
+    }
+}
+
+/// 将DN属性列表转换为RFC 4514字符串（供外部工具使用）
+/// RFC 4514从最后一个RDN开始书写，因此按模板的逆序输出RDN，多值RDN内部保持顺序以 `+` 连接；
+/// emailAddress写为点分OID
+pub(crate) fn to_rfc4514(attributes: &[DnAttribute]) -> String {
+    let mut rdns: Vec<Vec<String>> = Vec::new();
+    for attribute in attributes {
+        let attr = match attribute.attr.as_str() {
+            "emailAddress" => "1.2.840.113549.1.9.1",
+            attr => attr,
+        };
+        let text = format!("{}={}", attr, escape_rfc4514(&attribute.value));
+        match rdns.last_mut() {
+            Some(rdn) if attribute.multi_valued => rdn.push(text),
+            _ => rdns.push(vec![text]),
+        }
+    }
+    rdns.iter()
+        .rev()
+        .map(|rdn| rdn.join("+"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 按RFC 4514转义属性值中的特殊字符
+fn escape_rfc4514(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let last = value.chars().count().saturating_sub(1);
+    for (i, c) in value.chars().enumerate() {
+        let special = matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';' | '=')
+            || (i == 0 && matches!(c, '#' | ' '))
+            || (i == last && c == ' ');
+        if special {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::nid::Nid;
+
+    #[test]
+    fn test_parse_bracketed_subject() {
+        let attrs =
+            parse_subject(r"CN=[YDL0001]; O=[TrustAsia Technologies\, Inc.]; OU=[部门1,部门2\\]")
+                .unwrap();
+        let pairs: Vec<(&str, &str)> = attrs
+            .iter()
+            .map(|a| (a.attr.as_str(), a.value.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("CN", "YDL0001"),
+                ("O", "TrustAsia Technologies, Inc."),
+                ("OU", "部门1"),
+                ("OU", "部门2\\"),
+            ]
+        );
+
+        // 方括号不配对或 `]` 之后还有内容（如用逗号分隔属性）时报错
+        for subject in [
+            "CN=[X],O=[Example]",
+            "CN=[X]]; O=[Example]",
+            "CN=[X; O=[Example]",
+            "CN=[X]; O=[Example]]",
+            "CN=[X] Y; O=[Example]",
+            "CN=[X]+O; O=[Example]",
+        ] {
+            assert!(parse_subject(subject).is_err(), "{}", subject);
+        }
+        let attrs = parse_subject(r"CN=[X\]]; OU=[[lab]] ; O=[Example]").unwrap();
+        assert_eq!(attrs[0].value, "X]");
+        assert_eq!(attrs[1].value, "[lab]");
+    }
+
+    #[test]
+    fn test_parse_plain_subject_with_oid() {
+        let attrs =
+            parse_subject("CN=dev01,C=CN,E=ops@example.com,2.5.4.5=SN001,2.5.4.45=X1").unwrap();
+        assert_eq!(attrs[1].attr, "C");
+        assert_eq!(attrs[2].attr, "emailAddress");
+        // 支持的属性写作OID时换为短名称
+        assert_eq!(attrs[3].attr, "serialNumber");
+        assert_eq!(attrs[4].attr, "2.5.4.45");
+
+        let name = build_x509_name(&attrs).unwrap();
+        assert_eq!(name.entries().count(), 5);
+        assert!(parse_subject("XYZ=1").is_err());
+    }
+
+    #[test]
+    fn test_less_common_attributes() {
+        let attrs = parse_subject(
+            "CN=jdoe,serialnumber=SN-001,UID=jdoe,DC=corp,DC=example,DC=com,title=Engineer,GN=John,\
+             SN=Doe,street=1 Main St,postalCode=200000,organizationIdentifier=VATCN-123,\
+             jurisdictionC=CN,businessCategory=Private Organization",
+        )
+        .unwrap();
+        let names: Vec<&str> = attrs.iter().map(|a| a.attr.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "CN",
+                "serialNumber",
+                "UID",
+                "DC",
+                "DC",
+                "DC",
+                "title",
+                "GN",
+                "SN",
+                "street",
+                "postalCode",
+                "organizationIdentifier",
+                "jurisdictionC",
+                "businessCategory"
+            ]
+        );
+
+        // SN为姓氏，序列号为serialNumber
+        let name = build_x509_name(&attrs).unwrap();
+        let nids: Vec<Nid> = name.entries().map(|e| e.object().nid()).collect();
+        assert_eq!(
+            nids[..4],
+            [
+                Nid::COMMONNAME,
+                Nid::SERIALNUMBER,
+                Nid::USERID,
+                Nid::DOMAINCOMPONENT
+            ]
+        );
+        assert_eq!(nids[8], Nid::SURNAME);
+        assert_eq!(from_x509_name(&name).unwrap(), attrs);
+    }
+
+    #[test]
+    fn test_format_subject_round_trip() {
+        let attrs = parse_subject(
+            r"CN=[dev01]; O=[Example\, Inc.]; OU=[[lab]]; OU=[R\\D]; 2.5.4.5=[SN001]",
+        )
+        .unwrap();
+        assert_eq!(attrs[1].value, "Example, Inc.");
+        assert_eq!(attrs[3].value, r"R\D");
+        let name = build_x509_name(&attrs).unwrap();
+        let read = from_x509_name(&name).unwrap();
+        assert_eq!(read, attrs);
+        let formatted = format_subject(&read);
+        assert_eq!(
+            formatted,
+            r"CN=dev01,O=Example\, Inc.,OU=\[lab\],OU=R\\D,serialNumber=SN001"
+        );
+        assert_eq!(parse_subject(&formatted).unwrap(), attrs);
+
+        // 按模板格式输出，多值RDN以 `+` 连接
+        let template = r"CN=[dev01]; O=[Example\, Inc.]+OU=[R\\D]";
+        let attrs = parse_subject(template).unwrap();
+        assert_eq!(format_subject_as(template, &attrs), template);
+        let formatted = format_subject_as("CN=[dev01];O=[x]", &attrs);
+        assert_eq!(formatted, r"CN=[dev01];O=[Example\, Inc.]+OU=[R\\D]");
+        assert_eq!(parse_subject(&formatted).unwrap(), attrs);
+        assert_eq!(
+            format_subject_as("CN=dev01", &attrs),
+            r"CN=dev01,O=Example\, Inc.+OU=R\\D"
+        );
+    }
+
+    #[test]
+    fn test_string_types() {
+        let types = parse_string_types("C=printable; o=UTF8String\nC=utf8").unwrap();
+        assert_eq!(
+            types,
+            [
+                ("O".to_string(), DnStringType::Utf8),
+                ("C".to_string(), DnStringType::Utf8),
+            ]
+        );
+        for spec in ["C", "C=ia5", "XYZ=utf8"] {
+            assert!(parse_string_types(spec).is_err(), "{}", spec);
+        }
+
+        // DER中的标签：0x13为PrintableString，0x0c为UTF8String
+        let encoded = |subject: &str, spec: &str| {
+            let mut attrs = parse_subject(subject).unwrap();
+            apply_string_types(&mut attrs, &parse_string_types(spec).unwrap())?;
+            anyhow::Ok(build_x509_name(&attrs)?.to_der()?)
+        };
+        let contains = |der: &[u8], tag: u8, value: &str| {
+            let field = [&[tag, value.len() as u8], value.as_bytes()].concat();
+            der.windows(field.len()).any(|window| window == field)
+        };
+        let der = encoded("CN=dev01,O=Example,C=CN", "").unwrap();
+        assert!(contains(&der, 0x13, "CN") && contains(&der, 0x0c, "Example"));
+        let der = encoded("CN=dev01,O=Example,C=CN", "C=utf8,O=printable").unwrap();
+        assert!(contains(&der, 0x0c, "CN") && contains(&der, 0x13, "Example"));
+
+        // PrintableString不允许的字符
+        for subject in ["C=中国", "O=A&B", "CN=dev@example.com"] {
+            let error = encoded(subject, "C=printable,O=printable,CN=printable").unwrap_err();
+            assert!(error.to_string().contains("PrintableString"), "{}", subject);
+        }
+    }
+
+    #[test]
+    fn test_multi_valued_rdn() {
+        let summary = |attrs: &[DnAttribute]| -> Vec<(String, String, bool)> {
+            attrs
+                .iter()
+                .map(|a| (a.attr.clone(), a.value.clone(), a.multi_valued))
+                .collect()
+        };
+        let entry = |attr: &str, value: &str, multi_valued| {
+            (attr.to_string(), value.to_string(), multi_valued)
+        };
+
+        // `+` 之后不是属性时为值的一部分
+        let attrs = parse_subject(r"CN=dev01,OU=Dev+OU=Ops+2.5.4.45=SN1,O=A+B Corp,C=CN").unwrap();
+        assert_eq!(
+            summary(&attrs),
+            [
+                entry("CN", "dev01", false),
+                entry("OU", "Dev", false),
+                entry("OU", "Ops", true),
+                entry("2.5.4.45", "SN1", true),
+                entry("O", "A+B Corp", false),
+                entry("C", "CN", false),
+            ]
+        );
+        let bracketed = parse_subject("CN=[dev01]; OU=[Dev]+OU=[Ops]; OU=[Lab,QA]").unwrap();
+        assert_eq!(
+            summary(&bracketed),
+            [
+                entry("CN", "dev01", false),
+                entry("OU", "Dev", false),
+                entry("OU", "Ops", true),
+                entry("OU", "Lab", false),
+                entry("OU", "QA", false),
+            ]
+        );
+        assert!(parse_subject("CN=[dev01]; OU=[Dev,Lab]+OU=[Ops]").is_err());
+
+        // RDN按书写顺序编码，读取后还原为相同的主题
+        let name = build_x509_name(&attrs).unwrap();
+        assert_eq!(name.entries().count(), 6);
+        let read = from_x509_name(&name).unwrap();
+        assert_eq!(read, attrs);
+        let formatted = format_subject(&read);
+        assert_eq!(
+            formatted,
+            r"CN=dev01,OU=Dev+OU=Ops+2.5.4.45=SN1,O=A\+B Corp,C=CN"
+        );
+        assert_eq!(parse_subject(&formatted).unwrap(), attrs);
+        assert_eq!(
+            to_rfc4514(&attrs),
+            r"C=CN,O=A\+B Corp,OU=Dev+OU=Ops+2.5.4.45=SN1,CN=dev01"
+        );
+
+        // DER中共4个RDN
+        let der = name.to_der().unwrap();
+        let (_, content, _) = crate::signature::read_tlv(&der).unwrap();
+        assert_eq!(crate::signature::children(content).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_to_rfc4514() {
+        let attrs =
+            parse_subject(r"CN=[YDL0001]; O=[TrustAsia Technologies\, Inc.]; E=[ops@example.com]")
+                .unwrap();
+        assert_eq!(
+            to_rfc4514(&attrs),
+            r"1.2.840.113549.1.9.1=ops@example.com,O=TrustAsia Technologies\, Inc.,CN=YDL0001"
+        );
+    }
+}
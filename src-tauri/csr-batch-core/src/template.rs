@@ -0,0 +1,183 @@
+//! 模板变量模块
+//! 渲染Subject主题模板、备用名称、唯一ID、challengePassword、有效期偏移和PEM文件名模板中的占位符：
+//!
+//! - `{CN}`: 通用名称
+//! - `{INDEX}` / `{INDEX:width}`: 条目序号（从1开始），可指定补零宽度
+//! - `{DATE}` / `{DATE:format}`: 批次开始日期，格式为chrono格式串，默认 `%Y%m%d`
+//! - `{UUID}`: UUIDv4，同一条目内各处取值相同
+//! - `{RAND:n}`: n位随机十六进制字符串（大写），每处单独生成
+//! - `{KEYTYPE}`: 密钥类型（如 `EC_P-256`），仅在生成条目时可用
+//!
+//! 不认识的花括号内容原样保留
+
+use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use std::cell::OnceCell;
+
+use crate::error::BatchError;
+use crate::random_cn::{random_hex, uuid_v4};
+
+/// 默认日期格式
+const DEFAULT_DATE_FORMAT: &str = "%Y%m%d";
+
+/// {INDEX:width} 的最大补零宽度
+const MAX_INDEX_WIDTH: usize = 32;
+
+/// {RAND:n} 的最大长度
+const MAX_RAND_LENGTH: usize = 64;
+
+/// 单个条目的模板渲染上下文
+pub(crate) struct TemplateContext<'a> {
+    cn: &'a str,
+    /// 条目序号（从0开始）
+    index: usize,
+    date: DateTime<Local>,
+    key_type: Option<&'a str>,
+    uuid: OnceCell<String>,
+}
+
+impl<'a> TemplateContext<'a> {
+    /// index为条目序号（从0开始），date为批次开始时间
+    pub(crate) fn new(cn: &'a str, index: usize, date: DateTime<Local>) -> Self {
+        Self {
+            cn,
+            index,
+            date,
+            key_type: None,
+            uuid: OnceCell::new(),
+        }
+    }
+
+    /// 设置 {KEYTYPE} 的取值
+    pub(crate) fn with_key_type(mut self, key_type: &'a str) -> Self {
+        self.key_type = Some(key_type);
+        self
+    }
+
+    /// 替换模板中的占位符，field用于参数错误提示
+    pub(crate) fn render(&self, template: &str, field: &str) -> Result<String> {
+        self.render_escaped(template, field, str::to_string)
+    }
+
+    /// 替换模板中的占位符，每个值先经escape转换（如Subject模板中转义DN的特殊字符）
+    pub(crate) fn render_escaped(
+        &self,
+        template: &str,
+        field: &str,
+        escape: impl Fn(&str) -> String,
+    ) -> Result<String> {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = match rest.find('}') {
+                Some(end) => self.placeholder(&rest[1..end], field)?.map(|v| (v, end)),
+                None => None,
+            };
+            match value {
+                Some((value, end)) => {
+                    result.push_str(&escape(&value));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// 渲染单个占位符（不含花括号），不认识的占位符返回None
+    fn placeholder(&self, placeholder: &str, field: &str) -> Result<Option<String>> {
+        let (name, arg) = match placeholder.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (placeholder, None),
+        };
+        let invalid = || {
+            let message = format!("无效的模板变量: {{{}}}", placeholder);
+            BatchError::parameter(field, message)
+        };
+        let value = match (name, arg) {
+            ("CN", None) => self.cn.to_string(),
+            ("KEYTYPE", None) => match self.key_type {
+                Some(key_type) => key_type.to_string(),
+                None => return Ok(None),
+            },
+            ("INDEX", None) => (self.index + 1).to_string(),
+            ("INDEX", Some(width)) => {
+                let width: usize = width.parse().map_err(|_| invalid())?;
+                if width > MAX_INDEX_WIDTH {
+                    return Err(invalid().into());
+                }
+                format!("{:0width$}", self.index + 1, width = width)
+            }
+            ("DATE", format) => {
+                let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+                let items: Vec<Item> = StrftimeItems::new(format).collect();
+                if items.iter().any(|item| matches!(item, Item::Error)) {
+                    return Err(invalid().into());
+                }
+                self.date.format_with_items(items.into_iter()).to_string()
+            }
+            ("UUID", None) => match self.uuid.get() {
+                Some(uuid) => uuid.clone(),
+                None => {
+                    let uuid = uuid_v4()?;
+                    self.uuid.set(uuid.clone()).ok();
+                    uuid
+                }
+            },
+            ("RAND", Some(length)) => {
+                let length: usize = length.parse().map_err(|_| invalid())?;
+                if length == 0 || length > MAX_RAND_LENGTH {
+                    return Err(invalid().into());
+                }
+                random_hex(length)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_template() {
+        let date = Local.with_ymd_and_hms(2024, 3, 5, 8, 0, 0).unwrap();
+        let context = TemplateContext::new("YDL0001", 6, date);
+        let render = |template| context.render(template, "subject_template").unwrap();
+
+        assert_eq!(
+            render("CN=[{CN}];SERIALNUMBER=[{INDEX:4}]"),
+            "CN=[YDL0001];SERIALNUMBER=[0007]"
+        );
+        assert_eq!(render("{INDEX}-{DATE}-{DATE:%Y-%m}"), "7-20240305-2024-03");
+        assert_eq!(render("{OTHER} {{CN}} {CN"), "{OTHER} {YDL0001} {CN");
+        assert_eq!(render("{KEYTYPE}"), "{KEYTYPE}");
+        let keyed = TemplateContext::new("YDL0001", 6, date).with_key_type("EC_P-256");
+        assert_eq!(
+            keyed
+                .render("{CN}_{KEYTYPE}", "file_name_template")
+                .unwrap(),
+            "YDL0001_EC_P-256"
+        );
+
+        let uuid = render("{UUID}");
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(render("{UUID}"), uuid);
+        let rand = render("{RAND:8}");
+        assert!(rand.len() == 8 && rand.chars().all(|c| c.is_ascii_hexdigit()));
+
+        assert!(context.render("{RAND:0}", "sans").is_err());
+        assert!(context.render("{INDEX:x}", "sans").is_err());
+        assert!(context.render("{DATE:%Q}", "sans").is_err());
+    }
+}
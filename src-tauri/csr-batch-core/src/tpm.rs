@@ -0,0 +1,199 @@
+//! TPM 2.0模块
+//! 通过 tpm2-tools 在TPM的存储层级下生成签名密钥并持久化，CSR由OpenSSL构建、TPM中的私钥签名，
+//! CSV中记录持久句柄（如 0x81000001）代替私钥PEM，用于引导与TPM绑定的设备身份
+//!
+//! TCTI（TPM访问方式）沿用 tpm2-tools 的配置，例如环境变量 `TPM2TOOLS_TCTI`；
+//! 所有者层级授权须为空。TPM同一时间只处理一个条目的命令
+
+use anyhow::{anyhow, Result};
+use openssl::pkey::PKey;
+use openssl::rand::rand_bytes;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::csr_generator::{add_challenge_password, build_req, BatchRequest, CsrOptions, KeyType};
+use crate::error::BatchError;
+use crate::signature::{sign_req_external, SignatureScheme};
+use crate::subject::DnAttribute;
+
+/// 串行化TPM命令，持久句柄由TPM按顺序分配
+static TPM_LOCK: Mutex<()> = Mutex::new(());
+
+/// 密钥类型对应的tpm2-tools算法和签名方案，TPM不支持的类型返回None
+fn key_algorithm(key_type: KeyType) -> Option<(&'static str, &'static str)> {
+    match key_type {
+        KeyType::Rsa2048 => Some(("rsa2048", "rsassa")),
+        KeyType::EcP256 => Some(("ecc256", "ecdsa")),
+        KeyType::EcP384 => Some(("ecc384", "ecdsa")),
+        _ => None,
+    }
+}
+
+/// 校验生成参数是否可用于TPM
+pub(crate) fn check_request(
+    request: &BatchRequest,
+    key_type: KeyType,
+    signature_scheme: SignatureScheme,
+) -> Result<()> {
+    let unsupported = if key_algorithm(key_type).is_none() {
+        let message = format!("TPM不支持密钥类型 {}", key_type.display_name());
+        ("key_type", message)
+    } else if !request.pq_key_type.is_empty() {
+        ("pq_key_type", "TPM不支持混合模式".to_string())
+    } else if signature_scheme != SignatureScheme::Pkcs1 {
+        ("signature_scheme", "TPM仅支持PKCS#1 v1.5签名".to_string())
+    } else {
+        return Ok(());
+    };
+    Err(BatchError::parameter(unsupported.0, unsupported.1).into())
+}
+
+/// 执行tpm2-tools命令并返回标准输出
+fn tpm2(tool: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(tool)
+        .args(args)
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => {
+                BatchError::parameter("key_store", format!("未找到 {}，请安装 tpm2-tools", tool))
+            }
+            _ => BatchError::io(tool, e),
+        })?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} 执行失败: {}",
+            tool,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 从 `tpm2_evictcontrol` 的输出中读取分配的持久句柄
+fn parse_persistent_handle(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "persistent-handle").then(|| value.trim().to_string())
+    })
+}
+
+/// 单个条目的临时工作目录，结束时删除
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    fn new() -> Result<Self> {
+        let mut suffix = [0u8; 8];
+        rand_bytes(&mut suffix)?;
+        let name: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+        let dir = std::env::temp_dir().join(format!("csr-batch-tpm-{}", name));
+        fs::create_dir(&dir).map_err(|e| BatchError::io(&dir, e))?;
+        Ok(Self(dir))
+    }
+
+    fn file(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// 在TPM中生成持久化的签名密钥和CSR，返回CSR PEM和持久句柄
+pub(crate) fn generate_csr(
+    subject: &[DnAttribute],
+    key_type: KeyType,
+    options: &CsrOptions,
+) -> Result<(String, String)> {
+    let (algorithm, scheme) = key_algorithm(key_type)
+        .ok_or_else(|| anyhow!("TPM不支持密钥类型 {}", key_type.display_name()))?;
+    let hash = options.sign_hash_alg.to_ascii_lowercase();
+    let work = WorkDir::new()?;
+    let (primary, public, private, key) = (
+        work.file("primary.ctx"),
+        work.file("key.pub"),
+        work.file("key.priv"),
+        work.file("key.ctx"),
+    );
+
+    let _guard = TPM_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    // 在所有者层级下创建主密钥，生成不可导出的签名密钥并持久化
+    tpm2(
+        "tpm2_createprimary",
+        &["-C", "o", "-G", "ecc", "-c", &primary],
+    )?;
+    tpm2(
+        "tpm2_create",
+        &[
+            "-C",
+            &primary,
+            "-G",
+            algorithm,
+            "-a",
+            "fixedtpm|fixedparent|sensitivedataorigin|userwithauth|sign",
+            "-u",
+            &public,
+            "-r",
+            &private,
+        ],
+    )?;
+    tpm2(
+        "tpm2_load",
+        &["-C", &primary, "-u", &public, "-r", &private, "-c", &key],
+    )?;
+    let evicted = tpm2("tpm2_evictcontrol", &["-C", "o", "-c", &key])?;
+    let handle = parse_persistent_handle(&evicted)
+        .ok_or_else(|| anyhow!("无法读取TPM持久句柄: {}", evicted.trim()))?;
+
+    // 读取公钥，构建CSR后由TPM签名
+    let public_pem = work.file("key.pem");
+    tpm2(
+        "tpm2_readpublic",
+        &["-c", &handle, "-f", "pem", "-o", &public_pem],
+    )?;
+    let pkey = PKey::public_key_from_pem(&fs::read(&public_pem)?)?;
+    let req = build_req(subject, &pkey, &options.extensions)?;
+    if !options.challenge_password.is_empty() {
+        add_challenge_password(&req, options.challenge_password)?;
+    }
+    let req = sign_req_external(&req, key_type.is_rsa(), options.sign_hash_alg, |tbs| {
+        let (message, signature) = (work.file("tbs.der"), work.file("tbs.sig"));
+        fs::write(&message, tbs).map_err(|e| BatchError::io(work.path(), e))?;
+        tpm2(
+            "tpm2_sign",
+            &[
+                "-c", &handle, "-g", &hash, "-s", scheme, "-f", "plain", "-o", &signature, &message,
+            ],
+        )?;
+        Ok(fs::read(&signature)?)
+    })
+    .map_err(|e| e.context(format!("TPM持久句柄 {}", handle)))?;
+
+    Ok((String::from_utf8(req.to_pem()?)?, handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_persistent_handle() {
+        let output = "persistent-handle: 0x81000001\naction: persisted\n";
+        assert_eq!(
+            parse_persistent_handle(output).as_deref(),
+            Some("0x81000001")
+        );
+        assert_eq!(parse_persistent_handle("action: persisted"), None);
+        assert_eq!(key_algorithm(KeyType::Ed25519), None);
+    }
+}
@@ -0,0 +1,410 @@
+//! 有效期模块
+//! 有效期可使用ISO8601起止时间，也可使用时长（如 `397d`、`2y`、`1y6m`）：开始时间为批次开始时间
+//! （设置了notBefore时从notBefore起算），结束时间按开始时间所在时区的日历计算，
+//! 月末按目标月份的最后一天处理（如1月31日加1个月为2月28日或29日）
+//!
+//! 起止时间除ISO8601（RFC 3339）外，还接受RFC 2822和常见的 `2025-01-01 08:00:00`、`2025/01/01`、`20250101` 等写法，
+//! 不带时区的时间按本地时区处理，输出时统一转换为带时区的RFC 3339格式（精确到秒）
+//!
+//! 可按条目设置偏移（如 `{INDEX}h`），错开同一批次证书的生效和到期时间，避免同时到期时续期系统负载过高
+//!
+//! 两种写法都校验notAfter晚于notBefore；有效期超过CA/B Forum对TLS服务器证书的上限时生成结束后给出警告
+
+use anyhow::Result;
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, SecondsFormat,
+    TimeZone,
+};
+
+use crate::csr_generator::BatchRequest;
+use crate::error::BatchError;
+
+/// 有效期时长，年和月按日历计算，其余按固定长度计算
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ValidityDuration {
+    months: u32,
+    days: u32,
+    hours: u32,
+}
+
+impl ValidityDuration {
+    /// 解析由若干 `<数字><单位>` 组成的时长，单位: y (年), m (月), w (周), d (天), h (小时)
+    pub(crate) fn parse(value: &str, field: &str) -> Result<Self> {
+        let invalid = || {
+            let message = format!("无法解析时长 {}，正确格式示例: 397d、2y、1y6m", value);
+            BatchError::parameter(field, message)
+        };
+        let mut duration = Self::default();
+        let mut number = String::new();
+        let mut empty = true;
+        for c in value.trim().chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let n: u32 = number.parse().map_err(|_| invalid())?;
+            number.clear();
+            let (field, n) = match c.to_ascii_lowercase() {
+                'y' => (&mut duration.months, n.checked_mul(12)),
+                'm' => (&mut duration.months, Some(n)),
+                'w' => (&mut duration.days, n.checked_mul(7)),
+                'd' => (&mut duration.days, Some(n)),
+                'h' => (&mut duration.hours, Some(n)),
+                _ => return Err(invalid().into()),
+            };
+            *field = n.and_then(|n| field.checked_add(n)).ok_or_else(invalid)?;
+            empty = false;
+        }
+        if empty || !number.is_empty() {
+            return Err(invalid().into());
+        }
+        Ok(duration)
+    }
+
+    /// 从开始时间起算的结束时间，先加年月再加天数和小时
+    fn add_to<Tz: TimeZone>(self, start: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        start
+            .checked_add_months(Months::new(self.months))?
+            .checked_add_signed(Duration::days(self.days.into()))?
+            .checked_add_signed(Duration::hours(self.hours.into()))
+    }
+}
+
+/// 解析请求中的有效期时长，未设置时返回None
+pub(crate) fn parse_validity(request: &BatchRequest) -> Result<Option<ValidityDuration>> {
+    if request.validity.trim().is_empty() {
+        return Ok(None);
+    }
+    if !request.not_after.is_empty() {
+        let message = "设置有效期时长时不能同时设置notAfter";
+        return Err(BatchError::parameter("validity", message).into());
+    }
+    ValidityDuration::parse(&request.validity, "validity").map(Some)
+}
+
+/// 不带时区的日期时间写法，按本地时区处理
+const NAIVE_DATETIME_FORMATS: [&str; 6] = [
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+];
+
+/// 只有日期的写法，按本地时区当天0点处理
+const NAIVE_DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d"];
+
+/// CA/B Forum基线要求（SC-081）规定的TLS服务器证书最长有效天数，按签发日期逐步缩短：(生效日期, 天数)
+const TLS_MAX_VALIDITY_DAYS: [((i32, u32, u32), i64); 3] = [
+    ((2026, 3, 15), 200),
+    ((2027, 3, 15), 100),
+    ((2029, 3, 15), 47),
+];
+
+/// SC-081生效前的TLS服务器证书最长有效天数
+const TLS_DEFAULT_MAX_VALIDITY_DAYS: i64 = 398;
+
+/// 解析时间，带时区时保留其中的时区，不带时区时按本地时区处理；无法解析时返回None
+pub(crate) fn parse_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(value) {
+        return Some(time);
+    }
+    let naive = NAIVE_DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NAIVE_DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    // 夏令时切换时重复的本地时间取较早的一个，不存在的本地时间视为无效
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.fixed_offset())
+}
+
+fn parse_time(value: &str, field: &str) -> Result<DateTime<FixedOffset>> {
+    parse_datetime(value).ok_or_else(|| {
+        let message = format!(
+            "无效的时间 {}，正确格式示例: 2025-01-01T00:00:00+08:00、2025-01-01 08:00:00",
+            value
+        );
+        BatchError::parameter(field, message).into()
+    })
+}
+
+fn format_time<Tz: TimeZone>(time: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    time.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// 条目的有效期偏移，由偏移模板按条目渲染后解析，用于错开同一批次证书的生效和到期时间
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ValidityOffsets {
+    /// 加到notBefore上（按有效期时长计算notAfter时notAfter随之后移）
+    pub(crate) not_before: Option<ValidityDuration>,
+    /// 加到notAfter上
+    pub(crate) not_after: Option<ValidityDuration>,
+}
+
+impl ValidityOffsets {
+    /// 解析渲染后的偏移，为空表示不偏移
+    pub(crate) fn parse(not_before: &str, not_after: &str) -> Result<Self> {
+        let parse = |value: &str, field| {
+            if value.trim().is_empty() {
+                Ok(None)
+            } else {
+                ValidityDuration::parse(value, field).map(Some)
+            }
+        };
+        Ok(Self {
+            not_before: parse(not_before, "not_before_offset")?,
+            not_after: parse(not_after, "not_after_offset")?,
+        })
+    }
+}
+
+/// 按有效期时长和偏移得出条目最终的notBefore和notAfter（RFC 3339格式），并校验notAfter晚于notBefore
+/// 未设置时长时为空的起止时间保持为空；设置时长时未设置notBefore则从批次开始时间起算，
+/// 条目自带的notAfter（如输入CSV中的not_after列）优先于时长
+pub(crate) fn resolve_validity(
+    not_before: &str,
+    not_after: &str,
+    duration: Option<ValidityDuration>,
+    offsets: ValidityOffsets,
+    batch_date: DateTime<Local>,
+) -> Result<(String, String)> {
+    let parse = |value: &str, field| {
+        if value.is_empty() {
+            Ok(None)
+        } else {
+            parse_time(value, field).map(Some)
+        }
+    };
+    let shift = |time: DateTime<FixedOffset>, offset: Option<ValidityDuration>, field| match offset
+    {
+        Some(offset) => offset.add_to(time).ok_or_else(|| out_of_range(field)),
+        None => Ok(time),
+    };
+    let start = parse(not_before, "not_before")?;
+    let end = parse(not_after, "not_after")?;
+
+    let (start, end) = match (duration, start) {
+        // 从批次开始时间起算时按本地时区的日历计算，跨夏令时切换时仍为同一本地时刻
+        (Some(duration), None) if end.is_none() => {
+            let start = match offsets.not_before {
+                Some(offset) => offset.add_to(batch_date),
+                None => Some(batch_date),
+            }
+            .ok_or_else(|| out_of_range("not_before_offset"))?;
+            let end = duration
+                .add_to(start)
+                .ok_or_else(|| out_of_range("validity"))?;
+            (Some(start.fixed_offset()), Some(end.fixed_offset()))
+        }
+        (Some(duration), start) => {
+            let start = start.unwrap_or_else(|| batch_date.fixed_offset());
+            let start = shift(start, offsets.not_before, "not_before_offset")?;
+            let end = match end {
+                Some(end) => end,
+                None => duration
+                    .add_to(start)
+                    .ok_or_else(|| out_of_range("validity"))?,
+            };
+            (Some(start), Some(end))
+        }
+        (None, start) => (
+            start
+                .map(|start| shift(start, offsets.not_before, "not_before_offset"))
+                .transpose()?,
+            end,
+        ),
+    };
+    let end = end
+        .map(|end| shift(end, offsets.not_after, "not_after_offset"))
+        .transpose()?;
+
+    for (offset, time, field) in [
+        (offsets.not_before, start, "not_before_offset"),
+        (offsets.not_after, end, "not_after_offset"),
+    ] {
+        if offset.is_some() && time.is_none() {
+            let message = "设置偏移时须设置对应的有效期时间或有效期时长";
+            return Err(BatchError::parameter(field, message).into());
+        }
+    }
+    if let (Some(start), Some(end)) = (start, end) {
+        if end <= start {
+            let message = format!(
+                "有效期结束时间 {} 须晚于开始时间 {}",
+                format_time(&end),
+                format_time(&start)
+            );
+            return Err(BatchError::parameter("not_after", message).into());
+        }
+    }
+    let format = |time: Option<DateTime<FixedOffset>>| {
+        time.map(|time| format_time(&time)).unwrap_or_default()
+    };
+    Ok((format(start), format(end)))
+}
+
+fn out_of_range(field: &str) -> BatchError {
+    BatchError::parameter(field, "有效期时间超出范围")
+}
+
+/// TLS服务器证书在给定签发时间的最长有效天数
+fn tls_max_validity_days(issued: DateTime<FixedOffset>) -> i64 {
+    TLS_MAX_VALIDITY_DAYS
+        .iter()
+        .rev()
+        .find(|((year, month, day), _)| {
+            NaiveDate::from_ymd_opt(*year, *month, *day)
+                .is_some_and(|date| issued.date_naive() >= date)
+        })
+        .map_or(TLS_DEFAULT_MAX_VALIDITY_DAYS, |(_, days)| *days)
+}
+
+/// 统计有效期超过CA/B Forum TLS服务器证书上限的条目（以notBefore作为签发时间），
+/// 非TLS用途的证书不受此限制，因此只给出警告
+#[derive(Debug, Default)]
+pub(crate) struct TlsValidityCheck {
+    count: usize,
+    /// 第一个超出的条目：(通用名称, 有效天数, 上限天数)
+    first: Option<(String, i64, i64)>,
+}
+
+impl TlsValidityCheck {
+    /// 检查单条结果的有效期，起止时间为空或无法解析时跳过
+    pub(crate) fn check(&mut self, cn: &str, not_before: &str, not_after: &str) {
+        let (Some(start), Some(end)) = (parse_datetime(not_before), parse_datetime(not_after))
+        else {
+            return;
+        };
+        let max_days = tls_max_validity_days(start);
+        // 基线要求中的有效期包含起止两个时刻，恰好等于上限天数时仍超出1秒
+        if end - start < Duration::days(max_days) {
+            return;
+        }
+        self.count += 1;
+        if self.first.is_none() {
+            self.first = Some((cn.to_string(), (end - start).num_days(), max_days));
+        }
+    }
+
+    /// 有超出的条目时返回警告
+    pub(crate) fn warning(&self) -> Option<String> {
+        let (cn, days, max_days) = self.first.as_ref()?;
+        Some(format!(
+            "{}个条目的有效期超过CA/B Forum对TLS服务器证书的上限（如 {}: {}天，上限{}天），公共CA将拒绝签发TLS证书",
+            self.count, cn, days, max_days
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_validity() {
+        let now = Local::now();
+        let parse = |value| ValidityDuration::parse(value, "validity").unwrap();
+        let resolve = |not_before, not_after, duration| {
+            resolve_validity(
+                not_before,
+                not_after,
+                duration,
+                ValidityOffsets::default(),
+                now,
+            )
+        };
+        assert_eq!(parse("2y"), parse("24M"));
+        assert_eq!(parse("1w3d"), parse("10d"));
+        for invalid in ["", "397", "d", "1x", "5000000000y"] {
+            assert!(ValidityDuration::parse(invalid, "validity").is_err());
+        }
+
+        let (not_before, not_after) =
+            resolve("2024-01-31T08:00:00+09:00", "", Some(parse("1m"))).unwrap();
+        assert_eq!(not_before, "2024-01-31T08:00:00+09:00");
+        assert_eq!(not_after, "2024-02-29T08:00:00+09:00");
+        let (_, not_after) = resolve("2024-01-01T00:00:00Z", "", Some(parse("397d"))).unwrap();
+        assert_eq!(not_after, "2025-02-01T00:00:00+00:00");
+        let (not_before, _) = resolve("", "", Some(parse("1d"))).unwrap();
+        assert_eq!(not_before, format_time(&now));
+
+        // 绝对时间原样返回，结束时间须晚于开始时间
+        let start = "2025-01-01T00:00:00+08:00";
+        assert_eq!(
+            resolve(start, "2026-01-01T00:00:00+08:00", None).unwrap(),
+            (start.to_string(), "2026-01-01T00:00:00+08:00".to_string())
+        );
+        assert!(resolve(start, "2024-12-31T17:00:00+01:00", None).is_err());
+        assert!(resolve(start, "2025-13-01", None).is_err());
+        assert!(resolve("", "", None).is_ok());
+    }
+
+    #[test]
+    fn test_validity_offsets() {
+        let now = Local::now();
+        let offsets =
+            |not_before, not_after| ValidityOffsets::parse(not_before, not_after).unwrap();
+        let duration = ValidityDuration::parse("1y", "validity").ok();
+        let start = "2025-01-01T00:00:00+08:00";
+
+        // notBefore偏移时按时长计算的notAfter随之后移
+        let resolved = resolve_validity(start, "", duration, offsets("3h", ""), now).unwrap();
+        assert_eq!(
+            resolved,
+            (
+                "2025-01-01T03:00:00+08:00".to_string(),
+                "2026-01-01T03:00:00+08:00".to_string()
+            )
+        );
+        let end = "2026-01-01T00:00:00+08:00";
+        let resolved = resolve_validity(start, end, None, offsets("", "2d"), now).unwrap();
+        assert_eq!(resolved.1, "2026-01-03T00:00:00+08:00");
+        assert!(ValidityOffsets::parse("", "10x").is_err());
+        assert!(resolve_validity("", "", None, offsets("1h", ""), now).is_err());
+        assert!(ValidityOffsets::parse(" ", "")
+            .unwrap()
+            .not_before
+            .is_none());
+    }
+
+    #[test]
+    fn test_normalize_and_tls_limit() {
+        let now = Local::now();
+        let resolve = |not_before, not_after| {
+            resolve_validity(not_before, not_after, None, ValidityOffsets::default(), now)
+        };
+        let local = |value: &str| format_time(&parse_datetime(value).unwrap());
+        let (not_before, not_after) =
+            resolve("2026/04/01 08:00", "Wed, 1 Apr 2026 12:00:00 +0000").unwrap();
+        assert_eq!(not_before, local("2026-04-01T08:00:00"));
+        assert_eq!(not_after, "2026-04-01T12:00:00+00:00");
+        assert_eq!(
+            resolve("20260401", "").unwrap().0,
+            local("2026-04-01 00:00:00")
+        );
+
+        let mut check = TlsValidityCheck::default();
+        check.check("A", "2026-01-01T00:00:00Z", "2027-01-01T00:00:00Z");
+        check.check("B", "2026-04-01T00:00:00Z", "2026-10-17T23:59:59Z");
+        check.check("C", "2026-04-01T00:00:00Z", "2026-10-19T00:00:00Z");
+        check.check("D", "", "2030-01-01T00:00:00Z");
+        assert_eq!(check.count, 1);
+        assert!(check.warning().unwrap().contains("C: 201天，上限200天"));
+        assert!(TlsValidityCheck::default().warning().is_none());
+    }
+}
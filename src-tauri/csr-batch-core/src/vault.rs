@@ -0,0 +1,509 @@
+//! HashiCorp Vault PKI签发模块
+//! 将生成结果CSV中的CSR逐个提交到Vault PKI密钥引擎的 `pki/sign/<角色>` 接口，输出在原有列之后
+//! 增加certificate列（证书）和chain列（证书链）的CSV文件
+//!
+//! 认证支持Vault令牌和AppRole（先登录换取令牌），Vault企业版的命名空间通过 X-Vault-Namespace 头传递
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::output::{derived_csv_path, ResultCsv};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Vault签发参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct VaultParams {
+    /// 待签发的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _vault 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// Vault地址，如 https://vault.example.com:8200
+    pub server_url: String,
+    /// PKI密钥引擎的挂载路径 (默认 pki)
+    #[serde(default)]
+    pub mount: String,
+    /// PKI角色名称
+    pub role: String,
+    /// 认证方式: token (默认), approle
+    #[serde(default)]
+    pub auth_method: String,
+    /// Vault令牌 (token认证)
+    #[serde(default)]
+    pub token: String,
+    /// AppRole的role_id
+    #[serde(default)]
+    pub role_id: String,
+    /// AppRole的secret_id
+    #[serde(default)]
+    pub secret_id: String,
+    /// AppRole认证的挂载路径 (默认 approle)
+    #[serde(default)]
+    pub approle_mount: String,
+    /// Vault企业版命名空间 (可选)
+    #[serde(default)]
+    pub namespace: String,
+    /// 证书有效期，如 720h (可选，默认使用角色的配置)
+    #[serde(default)]
+    pub ttl: String,
+    /// Vault服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+}
+
+/// Vault签发结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// pki/sign的请求体
+#[derive(Serialize)]
+struct SignRequest<'a> {
+    csr: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    ttl: &'a str,
+    format: &'a str,
+}
+
+/// pki/sign响应中的data
+#[derive(Deserialize)]
+struct SignData {
+    certificate: String,
+    #[serde(default)]
+    issuing_ca: String,
+    #[serde(default)]
+    ca_chain: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    data: SignData,
+}
+
+/// AppRole登录响应
+#[derive(Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize)]
+struct LoginAuth {
+    client_token: String,
+}
+
+/// Vault的错误响应
+#[derive(Deserialize)]
+struct ErrorResponse {
+    errors: Vec<String>,
+}
+
+/// 去掉路径首尾的斜杠，为空时使用默认值
+fn mount_path<'a>(value: &'a str, default: &'a str) -> &'a str {
+    match value.trim().trim_matches('/') {
+        "" => default,
+        path => path,
+    }
+}
+
+struct VaultClient {
+    agent: ureq::Agent,
+    base_url: String,
+    namespace: String,
+    token: String,
+}
+
+impl VaultClient {
+    fn connect(params: &VaultParams) -> Result<Self> {
+        if params.server_url.trim().is_empty() {
+            return Err(BatchError::parameter("server_url", "未填写").into());
+        }
+        if params.role.trim().is_empty() {
+            return Err(BatchError::parameter("role", "未填写PKI角色").into());
+        }
+        let mut builder = ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT);
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            let connector = native_tls::TlsConnector::builder()
+                .add_root_certificate(cert)
+                .build()?;
+            builder = builder.tls_connector(Arc::new(connector));
+        }
+        let mut client = Self {
+            agent: builder.build(),
+            base_url: params.server_url.trim().trim_end_matches('/').to_string(),
+            namespace: params.namespace.trim().to_string(),
+            token: String::new(),
+        };
+
+        client.token = match params.auth_method.as_str() {
+            "" | "token" => {
+                if params.token.is_empty() {
+                    return Err(BatchError::parameter("token", "未填写Vault令牌").into());
+                }
+                params.token.clone()
+            }
+            "approle" => {
+                if params.role_id.is_empty() {
+                    return Err(BatchError::parameter("role_id", "未填写").into());
+                }
+                let body = serde_json::json!({
+                    "role_id": params.role_id,
+                    "secret_id": params.secret_id,
+                });
+                let path = format!(
+                    "auth/{}/login",
+                    mount_path(&params.approle_mount, "approle")
+                );
+                let response: LoginResponse = serde_json::from_str(
+                    &client
+                        .post(&path, &body.to_string())
+                        .map_err(|e| anyhow!("AppRole登录失败: {}", e))?,
+                )?;
+                response.auth.client_token
+            }
+            other => {
+                return Err(BatchError::parameter(
+                    "auth_method",
+                    format!("不支持的认证方式: {}", other),
+                )
+                .into())
+            }
+        };
+        Ok(client)
+    }
+
+    /// 向 /v1/<path> 发送JSON请求，返回响应内容
+    fn post(&self, path: &str, body: &str) -> Result<String> {
+        let mut request = self
+            .agent
+            .post(&format!("{}/v1/{}", self.base_url, path))
+            .set("Content-Type", "application/json");
+        if !self.token.is_empty() {
+            request = request.set("X-Vault-Token", &self.token);
+        }
+        if !self.namespace.is_empty() {
+            request = request.set("X-Vault-Namespace", &self.namespace);
+        }
+        match request.send_string(body) {
+            Ok(response) => Ok(response.into_string()?),
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                let text = serde_json::from_str::<ErrorResponse>(&text)
+                    .map(|error| error.errors.join("; "))
+                    .unwrap_or(text);
+                Err(anyhow!("Vault返回 {}: {}", code, text.trim()))
+            }
+            Err(e) => Err(anyhow!("连接失败: {}", e)),
+        }
+    }
+
+    /// 签发CSR，返回证书和证书链 (PEM)
+    fn sign(&self, path: &str, ttl: &str, csr: &X509Req) -> Result<(String, String)> {
+        let request = SignRequest {
+            csr: String::from_utf8(csr.to_pem()?)?,
+            ttl,
+            format: "pem",
+        };
+        let response = self.post(path, &serde_json::to_string(&request)?)?;
+        let response: SignResponse =
+            serde_json::from_str(&response).map_err(|e| anyhow!("无法解析Vault响应: {}", e))?;
+        let cert = X509::from_pem(response.data.certificate.as_bytes())
+            .map_err(|e| anyhow!("无效的证书: {}", e))?;
+        if !cert.public_key()?.public_eq(&*csr.public_key()?) {
+            return Err(anyhow!("签发的证书与CSR的公钥不一致"));
+        }
+
+        // ca_chain包含签发CA及其上级CA，旧版本只返回issuing_ca
+        let mut chain_pems = response.data.ca_chain;
+        if chain_pems.is_empty() && !response.data.issuing_ca.is_empty() {
+            chain_pems.push(response.data.issuing_ca);
+        }
+        let mut seen = vec![cert.to_der()?];
+        let mut chain = String::new();
+        for pem in &chain_pems {
+            for ca in X509::stack_from_pem(pem.as_bytes())? {
+                let encoded = ca.to_der()?;
+                if !seen.contains(&encoded) {
+                    chain.push_str(&String::from_utf8(ca.to_pem()?)?);
+                    seen.push(encoded);
+                }
+            }
+        }
+        Ok((String::from_utf8(cert.to_pem()?)?, chain))
+    }
+}
+
+/// 通过Vault PKI为CSV中的每个CSR签发证书
+pub fn sign_with_vault(params: VaultParams) -> Result<VaultResult, BatchError> {
+    sign_records(&params).map_err(BatchError::from)
+}
+
+/// 签发一行的CSR，返回证书和证书链
+fn sign_record(
+    client: &VaultClient,
+    sign_path: &str,
+    ttl: &str,
+    record: &StringRecord,
+    csr_col: usize,
+    number: usize,
+) -> Result<(String, String)> {
+    let csr = parse_csr(record.get(csr_col).unwrap_or(""))
+        .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+    Ok(client
+        .sign(sign_path, ttl, &csr)
+        .map_err(|e| BatchError::record(number, format!("签发失败: {}", e)))?)
+}
+
+fn sign_records(params: &VaultParams) -> Result<VaultResult> {
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let csr_col = headers
+        .iter()
+        .position(|h| h == "csr")
+        .ok_or_else(|| BatchError::header("缺少csr列"))?;
+    // 重复签发时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "vault")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = VaultClient::connect(params)?;
+    let sign_path = format!(
+        "{}/sign/{}",
+        mount_path(&params.mount, "pki"),
+        params.role.trim()
+    );
+    let mut total = 0;
+    for (index, record) in records.iter().enumerate() {
+        // 中途签发失败时，已签发的证书和其余行照常写入输出文件
+        let signed = sign_record(
+            &client,
+            &sign_path,
+            params.ttl.trim(),
+            record,
+            csr_col,
+            index + 1,
+        );
+        let (cert_pem, chain_pem) = match signed {
+            Ok(signed) => signed,
+            Err(error) => return Err(output.abort(error, &records[index..], total)),
+        };
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    Ok(VaultResult {
+        success: true,
+        message: format!("Vault签发 {} 个证书", total),
+        total,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use tiny_http::{Response, Server};
+
+    /// 启动测试Vault服务器：AppRole登录后以令牌调用 pki/sign/devices，校验命名空间
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test Vault CA").unwrap();
+        let name = name.build();
+        let issuer = name.to_owned().unwrap();
+        let validity = move |builder: &mut X509Builder| {
+            builder.set_version(2).unwrap();
+            builder.set_issuer_name(&issuer).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                .unwrap();
+        };
+        let mut builder = X509Builder::new().unwrap();
+        validity(&mut builder);
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_pem = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let header = |name: &'static str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv(name))
+                        .map(|h| h.value.to_string())
+                        .unwrap_or_default()
+                };
+                let (token, namespace) = (header("X-Vault-Token"), header("X-Vault-Namespace"));
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let (status, reply) = match request.url() {
+                    _ if namespace != "team-a" => (404, serde_json::json!({"errors": []})),
+                    "/v1/auth/approle/login" if body["secret_id"] == "s3cret" => (
+                        200,
+                        serde_json::json!({"auth": {"client_token": "hvs.test"}}),
+                    ),
+                    "/v1/pki_int/sign/devices" if token == "hvs.test" => {
+                        assert_eq!(body["ttl"], "720h");
+                        let csr = parse_csr(body["csr"].as_str().unwrap()).unwrap();
+                        let mut builder = X509Builder::new().unwrap();
+                        validity(&mut builder);
+                        builder.set_subject_name(csr.subject_name()).unwrap();
+                        builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                        let cert = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+                        let data = serde_json::json!({
+                            "certificate": cert,
+                            "issuing_ca": ca_pem,
+                            "ca_chain": [ca_pem],
+                            "serial_number": "01:02",
+                        });
+                        (200, serde_json::json!({ "data": data }))
+                    }
+                    "/v1/pki_int/sign/devices" => {
+                        (403, serde_json::json!({"errors": ["permission denied"]}))
+                    }
+                    _ => (
+                        400,
+                        serde_json::json!({"errors": ["invalid role ID or secret ID"]}),
+                    ),
+                };
+                let response = Response::from_string(reply.to_string()).with_status_code(status);
+                let _ = request.respond(response);
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_sign_with_vault() {
+        let dir = std::env::temp_dir().join("csr_batch_vault_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let server_url = start_mock();
+        let params = VaultParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            mount: "/pki_int/".to_string(),
+            role: "devices".to_string(),
+            auth_method: "approle".to_string(),
+            role_id: "batch".to_string(),
+            secret_id: "s3cret".to_string(),
+            namespace: "team-a".to_string(),
+            ttl: "720h".to_string(),
+            ..Default::default()
+        };
+        let result = sign_with_vault(params).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.output_path.ends_with("out_vault.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (key_col, cert_col, chain_col) =
+            (column("privateKey"), column("certificate"), column("chain"));
+        for record in reader.records() {
+            let record = record.unwrap();
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            // ca_chain和issuing_ca中的同一CA证书只写入一次
+            let chain = X509::stack_from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(chain.len(), 1);
+            assert_eq!(cert.issuer_name_hash(), chain[0].subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let params = |overwrite: bool| VaultParams {
+            input_path: input.to_string_lossy().to_string(),
+            server_url: server_url.clone(),
+            mount: "pki_int".to_string(),
+            role: "devices".to_string(),
+            token: "hvs.other".to_string(),
+            namespace: "team-a".to_string(),
+            overwrite,
+            ..Default::default()
+        };
+        let error = sign_with_vault(params(false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 令牌无权限时返回Vault的错误说明，已有的结果文件保持不变
+        let issued = fs::read_to_string(&result.output_path).unwrap();
+        let error = sign_with_vault(params(true)).unwrap_err();
+        assert!(error.to_string().contains("permission denied"), "{}", error);
+        assert_eq!(fs::read_to_string(&result.output_path).unwrap(), issued);
+        fs::remove_dir_all(dir).ok();
+    }
+}
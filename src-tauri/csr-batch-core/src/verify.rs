@@ -0,0 +1,230 @@
+//! 批量校验模块
+//! 重新读取生成的CSV，逐条验证CSR自签名、私钥与CSR公钥是否匹配，并检查通用名称是否重复，
+//! 代替人工用openssl脚本逐条核对
+//!
+//! 私钥列为空的条目（如密钥在系统密钥存储中生成）只验证CSR；混合模式的后量子列不在校验范围内
+
+use anyhow::{anyhow, Result};
+use openssl::x509::X509Req;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::ca::parse_csr;
+use crate::csr_generator::{read_private_key, BatchRequest};
+use crate::error::BatchError;
+use crate::output::read_output_csv;
+use crate::subject::from_x509_name;
+
+/// 校验参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct VerifyParams {
+    /// 待校验的CSV文件路径（批量生成的输出，需包含csr列）
+    pub output_path: String,
+    /// 私钥加密口令 (可选，私钥加密输出时必填)
+    #[serde(default)]
+    pub key_passphrase: String,
+    /// CSV分隔符 (可选，与生成时的设置一致，默认逗号)
+    #[serde(default)]
+    pub csv_delimiter: String,
+    /// CSV编码 (可选，与生成时的设置一致，默认UTF-8)
+    #[serde(default)]
+    pub csv_encoding: String,
+}
+
+/// 校验报告
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    /// 是否全部通过
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 校验的条目总数
+    pub total: usize,
+    /// 已核对私钥的条目数
+    pub keys_checked: usize,
+    /// 未通过的检查项（同一条目可有多项）
+    pub failures: Vec<VerifyFailure>,
+}
+
+/// 未通过的检查项
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyFailure {
+    /// 条目序号（从1开始，PEM字段跨多行，不按行号计）
+    pub record: usize,
+    /// 通用名称
+    pub cn: String,
+    /// 问题描述
+    pub message: String,
+}
+
+/// 校验生成的CSV中的每个CSR和私钥
+pub fn verify_batch(params: VerifyParams) -> Result<VerifyReport, BatchError> {
+    verify(&params).map_err(BatchError::from)
+}
+
+/// CSR中的通用名称，没有CN时为空
+fn csr_cn(req: &X509Req) -> String {
+    from_x509_name(req.subject_name())
+        .unwrap_or_default()
+        .into_iter()
+        .find(|attribute| attribute.attr == "CN")
+        .map(|attribute| attribute.value)
+        .unwrap_or_default()
+}
+
+/// 验证CSR自签名，以及私钥（如有）与CSR公钥是否匹配；返回是否核对了私钥
+fn verify_record(req: &X509Req, private_key: &str, passphrase: &str) -> Result<bool> {
+    let pubkey = req.public_key()?;
+    if !req.verify(&pubkey).unwrap_or(false) {
+        return Err(anyhow!("CSR自签名无效"));
+    }
+    if private_key.is_empty() {
+        return Ok(false);
+    }
+    let key = read_private_key(private_key.as_bytes(), passphrase)?;
+    if !key.public_eq(&pubkey) {
+        return Err(anyhow!("私钥与CSR公钥不匹配"));
+    }
+    Ok(true)
+}
+
+fn verify(params: &VerifyParams) -> Result<VerifyReport> {
+    let dialect = BatchRequest {
+        csv_delimiter: params.csv_delimiter.clone(),
+        csv_encoding: params.csv_encoding.clone(),
+        ..Default::default()
+    };
+    let mut reader = read_output_csv(&params.output_path, &dialect)?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let csr_col = column("csr").ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let cn_col = column("cn");
+    let key_col = column("privateKey");
+
+    let mut total = 0;
+    let mut keys_checked = 0;
+    let mut failures = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (index, record) in reader.records().enumerate() {
+        let number = index + 1;
+        let record = record.map_err(|e| BatchError::record(number, format!("格式错误: {}", e)))?;
+        let cell = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim();
+        total += 1;
+
+        let req = parse_csr(cell(Some(csr_col)));
+        // 没有cn列时按CSR中的CN检查重复
+        let cn = match (cn_col, &req) {
+            (Some(_), _) => cell(cn_col).to_string(),
+            (None, Ok(req)) => csr_cn(req),
+            (None, Err(_)) => String::new(),
+        };
+        let mut fail = |message: String| {
+            failures.push(VerifyFailure {
+                record: number,
+                cn: cn.clone(),
+                message,
+            })
+        };
+
+        match req {
+            Ok(req) => match verify_record(&req, cell(key_col), &params.key_passphrase) {
+                Ok(checked) => keys_checked += usize::from(checked),
+                Err(e) => fail(e.to_string()),
+            },
+            Err(e) => fail(format!("CSR无效: {}", e)),
+        }
+        if !cn.is_empty() {
+            match seen.get(&cn) {
+                Some(first) => fail(format!("通用名称重复（与第 {} 条相同）", first)),
+                None => {
+                    seen.insert(cn.clone(), number);
+                }
+            }
+        }
+    }
+
+    let message = if failures.is_empty() {
+        format!(
+            "{} 个CSR全部通过校验，已核对 {} 个私钥",
+            total, keys_checked
+        )
+    } else {
+        format!("{} 个CSR中发现 {} 个问题", total, failures.len())
+    };
+    Ok(VerifyReport {
+        success: failures.is_empty(),
+        message,
+        total,
+        keys_checked,
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, CancelToken};
+    use std::fs;
+
+    #[test]
+    fn test_verify_batch() {
+        let path = std::env::temp_dir().join("csr_batch_verify_test.csv");
+        fs::remove_file(&path).ok();
+        let request = BatchRequest {
+            cn_range: "YDL0001-YDL0003".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            key_type: "EC_P256".to_string(),
+            key_passphrase: "secret".to_string(),
+            output_path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(request, &CancelToken::default(), &|_| {}).unwrap();
+        let params = |key_passphrase: &str| VerifyParams {
+            output_path: path.to_string_lossy().to_string(),
+            key_passphrase: key_passphrase.to_string(),
+            ..Default::default()
+        };
+
+        let report = verify_batch(params("secret")).unwrap();
+        assert!(report.success, "{:?}", report.failures);
+        assert_eq!((report.total, report.keys_checked), (3, 3));
+
+        // 交换两行的私钥并复制一行，应报告私钥不匹配和通用名称重复
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let key_col = headers.iter().position(|h| h == "privateKey").unwrap();
+        let mut rows: Vec<Vec<String>> = reader
+            .records()
+            .map(|r| r.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        let key = rows[0][key_col].clone();
+        rows[0][key_col] = rows[1][key_col].clone();
+        rows[1][key_col] = key;
+        rows.push(rows[2].clone());
+        let mut writer = csv::Writer::from_path(&path).unwrap();
+        writer.write_record(&headers).unwrap();
+        for row in &rows {
+            writer.write_record(row).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let report = verify_batch(params("secret")).unwrap();
+        assert!(!report.success);
+        assert_eq!(report.total, 4);
+        let messages: Vec<(usize, &str)> = report
+            .failures
+            .iter()
+            .map(|f| (f.record, f.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (1, "私钥与CSR公钥不匹配"),
+                (2, "私钥与CSR公钥不匹配"),
+                (4, "通用名称重复（与第 3 条相同）"),
+            ]
+        );
+        assert!(!verify_batch(params("wrong")).unwrap().success);
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,695 @@
+//! 通用REST CA适配模块
+//! 面向自建HTTP接口的内部CA：按可配置的地址、请求头、认证方式和请求体模板逐个提交生成结果CSV中的CSR，
+//! 用JSONPath从响应中取出证书（及证书链），输出在原有列之后增加certificate列和chain列的CSV文件
+//!
+//! 地址、请求头和请求体模板中的 {CSR_PEM}、{CSR_B64}（DER的base64）、{CN}、{SANS} 替换为每个CSR的值；
+//! {SANS} 取CSR中的备用名称（逗号分隔，如 DNS:a.example.com,IP:10.0.0.1），CSR中没有时取该行的sans列。内容类型为JSON时替换值按JSON字符串转义，
+//! 模板写作 `{"csr": "{CSR_PEM}"}`；地址中的替换值按URL编码。429、5xx和连接失败按指数退避重试
+
+use anyhow::{anyhow, Result};
+use csv::{ReaderBuilder, StringRecord};
+use openssl::base64;
+use openssl::nid::Nid;
+use openssl::x509::{X509Req, X509};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bundle::ensure_column;
+use crate::ca::parse_csr;
+use crate::error::BatchError;
+use crate::est::{client_identity, retry_after};
+use crate::inspect::alt_names;
+use crate::output::{derived_csv_path, ResultCsv};
+
+/// HTTP请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 未返回 Retry-After 时首次重试的等待时间，之后每次加倍
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// 通用REST CA签发参数结构体
+#[derive(Debug, Default, Deserialize)]
+pub struct WebhookParams {
+    /// 待签发的CSV文件路径（批量生成的输出，需包含csr列）
+    pub input_path: String,
+    /// 输出CSV文件路径 (可选，默认为输入文件名加 _webhook 后缀)
+    #[serde(default)]
+    pub output_path: String,
+    /// 是否覆盖已存在的输出文件 (默认拒绝覆盖)
+    #[serde(default)]
+    pub overwrite: bool,
+    /// 接口地址，可使用占位符，如 https://ca.example.com/api/certs/{CN}
+    pub url: String,
+    /// 请求方法：POST (默认) 或 PUT
+    #[serde(default)]
+    pub method: String,
+    /// 请求体的内容类型 (默认 application/json)
+    #[serde(default)]
+    pub content_type: String,
+    /// 请求体模板，可使用占位符
+    pub body_template: String,
+    /// 附加请求头，每行一个 `名称: 值`，值可使用占位符
+    #[serde(default)]
+    pub headers: String,
+    /// 认证方式：none (默认), basic, bearer
+    #[serde(default)]
+    pub auth_method: String,
+    /// HTTP Basic认证用户名
+    #[serde(default)]
+    pub username: String,
+    /// HTTP Basic认证密码
+    #[serde(default)]
+    pub password: String,
+    /// Bearer令牌
+    #[serde(default)]
+    pub token: String,
+    /// 证书在JSON响应中的JSONPath，如 $.data.certificate (为空表示响应本身即为证书)
+    #[serde(default)]
+    pub certificate_path: String,
+    /// 证书链在JSON响应中的JSONPath，如 $.data.chain[*] (可选)
+    #[serde(default)]
+    pub chain_path: String,
+    /// CA服务器的CA证书 (PEM，可选，服务器证书不受系统信任时设置)
+    #[serde(default)]
+    pub server_ca_cert_path: String,
+    /// TLS客户端证书 (PEM，或包含证书和私钥的 .p12/.pfx 文件，可选)
+    #[serde(default)]
+    pub client_cert_path: String,
+    /// TLS客户端私钥 (PEM，客户端证书为PKCS#12文件时无需填写)
+    #[serde(default)]
+    pub client_key_path: String,
+    /// 客户端私钥口令或PKCS#12密码
+    #[serde(default)]
+    pub client_key_password: String,
+    /// 暂时性失败的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+/// 默认最大重试次数
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// 通用REST CA签发结果结构体
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookResult {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+    /// 签发的证书总数
+    pub total: usize,
+    /// 重试的请求次数
+    pub retries: usize,
+    /// 输出文件路径
+    pub output_path: String,
+}
+
+/// 一次请求的结果
+enum Attempt {
+    Issued(String),
+    /// 暂时性失败，附带服务器要求的等待时间
+    Retry(String, Option<Duration>),
+    Failed(String),
+}
+
+/// JSONPath的一段
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// 解析JSONPath，支持 `$.a.b`、`$['a']`、`$.a[0]` 和 `$.a[*]`
+fn parse_json_path(path: &str) -> Result<Vec<Segment>> {
+    let invalid = || anyhow!("无效的JSONPath: {}", path);
+    let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let name = &after[..end];
+            segments.push(match name {
+                "" => return Err(invalid()),
+                "*" => Segment::Wildcard,
+                name => Segment::Field(name.to_string()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = after[..end].trim();
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            segments.push(match (inner, quoted) {
+                (_, Some(name)) => Segment::Field(name.to_string()),
+                ("*", None) => Segment::Wildcard,
+                (index, None) => Segment::Index(index.parse().map_err(|_| invalid())?),
+            });
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+/// 按JSONPath选出所有匹配的值
+fn select<'a>(value: &'a Value, path: &[Segment]) -> Vec<&'a Value> {
+    let mut current = vec![value];
+    for segment in path {
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (segment, value) {
+                    (Segment::Field(name), _) => value.get(name).into_iter().collect(),
+                    (Segment::Index(index), _) => value.get(index).into_iter().collect(),
+                    (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    (Segment::Wildcard, _) => Vec::new(),
+                }
+            })
+            .collect();
+    }
+    current
+}
+
+/// 解析响应中的证书文本：PEM（可含多个证书）或base64编码的DER
+fn parse_certificates(text: &str) -> Result<Vec<X509>> {
+    if text.contains("-----BEGIN") {
+        return X509::stack_from_pem(text.as_bytes()).map_err(|e| anyhow!("无效的证书: {}", e));
+    }
+    let compact: String = text.split_whitespace().collect();
+    let der = base64::decode_block(&compact).map_err(|_| anyhow!("证书既不是PEM也不是base64"))?;
+    Ok(vec![
+        X509::from_der(&der).map_err(|e| anyhow!("无效的证书: {}", e))?
+    ])
+}
+
+/// 取出JSONPath匹配的所有证书，匹配的值为字符串或字符串数组
+fn certificates_at(response: &Value, path: &str) -> Result<Vec<X509>> {
+    let segments = parse_json_path(path)?;
+    let mut certs = Vec::new();
+    for value in select(response, &segments) {
+        let texts: Vec<&Value> = match value {
+            Value::Array(items) => items.iter().collect(),
+            value => vec![value],
+        };
+        for text in texts {
+            let text = text
+                .as_str()
+                .ok_or_else(|| anyhow!("{} 匹配的值不是字符串", path))?;
+            certs.extend(parse_certificates(text)?);
+        }
+    }
+    Ok(certs)
+}
+
+/// 单个CSR的占位符取值
+struct Placeholders {
+    csr_pem: String,
+    csr_b64: String,
+    cn: String,
+    sans: String,
+}
+
+impl Placeholders {
+    fn of(csr: &X509Req, row_sans: &str) -> Result<Self> {
+        let cn = csr
+            .subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .next()
+            .and_then(|entry| entry.data().as_utf8().ok())
+            .map(|cn| cn.to_string())
+            .unwrap_or_default();
+        Ok(Self {
+            csr_pem: String::from_utf8(csr.to_pem()?)?,
+            csr_b64: base64::encode_block(&csr.to_der()?),
+            cn,
+            sans: match alt_names(csr)?.join(",") {
+                sans if sans.is_empty() => row_sans.to_string(),
+                sans => sans,
+            },
+        })
+    }
+
+    /// 替换模板中的占位符，每个值先经escape转换
+    fn render(&self, template: &str, escape: impl Fn(&str) -> String) -> String {
+        template
+            .replace("{CSR_PEM}", &escape(&self.csr_pem))
+            .replace("{CSR_B64}", &escape(&self.csr_b64))
+            .replace("{CN}", &escape(&self.cn))
+            .replace("{SANS}", &escape(&self.sans))
+    }
+}
+
+/// 按JSON字符串转义（不含两端引号）
+fn json_escape(value: &str) -> String {
+    let quoted = Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// 按URL编码，保留非保留字符
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+struct WebhookClient<'a> {
+    agent: ureq::Agent,
+    params: &'a WebhookParams,
+    method: String,
+    content_type: String,
+    /// 已解析的附加请求头 (名称, 值模板)
+    headers: Vec<(String, String)>,
+    authorization: Option<String>,
+}
+
+impl<'a> WebhookClient<'a> {
+    fn connect(params: &'a WebhookParams) -> Result<Self> {
+        for (field, value) in [
+            ("url", &params.url),
+            ("body_template", &params.body_template),
+        ] {
+            if value.trim().is_empty() {
+                return Err(BatchError::parameter(field, "未填写").into());
+            }
+        }
+        let method = match params.method.trim().to_uppercase().as_str() {
+            "" | "POST" => "POST".to_string(),
+            "PUT" => "PUT".to_string(),
+            other => {
+                let message = format!("不支持的请求方法: {}", other);
+                return Err(BatchError::parameter("method", message).into());
+            }
+        };
+        let mut headers = Vec::new();
+        for line in params.headers.lines().filter(|l| !l.trim().is_empty()) {
+            let (name, value) = line.split_once(':').ok_or_else(|| {
+                BatchError::parameter("headers", format!("请求头应为 名称: 值 格式: {}", line))
+            })?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        let authorization = match params.auth_method.trim() {
+            "" | "none" => None,
+            "basic" => {
+                let credentials = format!("{}:{}", params.username, params.password);
+                Some(format!(
+                    "Basic {}",
+                    base64::encode_block(credentials.as_bytes())
+                ))
+            }
+            "bearer" if params.token.is_empty() => {
+                return Err(BatchError::parameter("token", "未填写").into());
+            }
+            "bearer" => Some(format!("Bearer {}", params.token)),
+            other => {
+                let message = format!("不支持的认证方式: {}", other);
+                return Err(BatchError::parameter("auth_method", message).into());
+            }
+        };
+
+        let mut tls = native_tls::TlsConnector::builder();
+        if !params.server_ca_cert_path.is_empty() {
+            let path = &params.server_ca_cert_path;
+            let pem = fs::read(path).map_err(|e| BatchError::io(path, e))?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| BatchError::parameter("server_ca_cert_path", e))?;
+            tls.add_root_certificate(cert);
+        }
+        if !params.client_cert_path.is_empty() {
+            tls.identity(client_identity(
+                &params.client_cert_path,
+                &params.client_key_path,
+                &params.client_key_password,
+            )?);
+        }
+        let agent = ureq::AgentBuilder::new()
+            .timeout(HTTP_TIMEOUT)
+            .tls_connector(Arc::new(tls.build()?))
+            .build();
+        Ok(Self {
+            agent,
+            params,
+            method,
+            content_type: match params.content_type.trim() {
+                "" => "application/json".to_string(),
+                content_type => content_type.to_string(),
+            },
+            headers,
+            authorization,
+        })
+    }
+
+    fn attempt(&self, url: &str, headers: &[(String, String)], body: &str) -> Attempt {
+        let mut request = self
+            .agent
+            .request(&self.method, url)
+            .set("Content-Type", &self.content_type);
+        if let Some(authorization) = &self.authorization {
+            request = request.set("Authorization", authorization);
+        }
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        match request.send_string(body) {
+            Ok(response) => match response.into_string() {
+                Ok(body) => Attempt::Issued(body),
+                Err(e) => Attempt::Retry(format!("读取响应失败: {}", e), None),
+            },
+            Err(ureq::Error::Status(code, response)) => {
+                let delay = retry_after(&response);
+                let text = response.into_string().unwrap_or_default();
+                let message = format!("CA返回 {}: {}", code, text.trim());
+                if code >= 500 || code == 429 {
+                    Attempt::Retry(message, delay)
+                } else {
+                    Attempt::Failed(message)
+                }
+            }
+            Err(ureq::Error::Transport(e)) => Attempt::Retry(format!("连接失败: {}", e), None),
+        }
+    }
+
+    /// 提交CSR，暂时性失败时重试；返回证书、证书链和重试次数
+    fn enroll(&self, csr: &X509Req, row_sans: &str) -> Result<(String, String, usize)> {
+        let values = Placeholders::of(csr, row_sans)?;
+        let url = values.render(self.params.url.trim(), url_encode);
+        let headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), values.render(value, str::to_string)))
+            .collect();
+        let body = if self.content_type.contains("json") {
+            values.render(&self.params.body_template, json_escape)
+        } else {
+            values.render(&self.params.body_template, str::to_string)
+        };
+
+        let mut delay = RETRY_DELAY;
+        let mut retry = 0;
+        let response = loop {
+            let (message, wait) = match self.attempt(&url, &headers, &body) {
+                Attempt::Issued(response) => break response,
+                Attempt::Failed(message) => return Err(anyhow!(message)),
+                Attempt::Retry(message, wait) => (message, wait),
+            };
+            if retry >= self.params.max_retries as usize {
+                return Err(anyhow!("{}（已重试{}次）", message, retry));
+            }
+            std::thread::sleep(wait.unwrap_or(delay));
+            delay *= 2;
+            retry += 1;
+        };
+
+        // 证书所在的响应可能同时带有完整证书链，按公钥找出签发的证书，其余作为证书链
+        let mut certs = if self.params.certificate_path.trim().is_empty() {
+            parse_certificates(&response)?
+        } else {
+            let json: Value =
+                serde_json::from_str(&response).map_err(|e| anyhow!("响应不是JSON: {}", e))?;
+            let mut certs = certificates_at(&json, &self.params.certificate_path)?;
+            if !self.params.chain_path.trim().is_empty() {
+                certs.extend(certificates_at(&json, &self.params.chain_path)?);
+            }
+            certs
+        };
+        let public_key = csr.public_key()?;
+        let leaf = certs
+            .iter()
+            .position(|cert| {
+                cert.public_key()
+                    .is_ok_and(|key| key.public_eq(&*public_key))
+            })
+            .ok_or_else(|| anyhow!("响应中没有与CSR公钥一致的证书"))?;
+        let cert = certs.remove(leaf);
+        let mut chain = String::new();
+        let mut seen = vec![cert.to_der()?];
+        for ca in certs {
+            let der = ca.to_der()?;
+            if !seen.contains(&der) {
+                chain.push_str(&String::from_utf8(ca.to_pem()?)?);
+                seen.push(der);
+            }
+        }
+        Ok((String::from_utf8(cert.to_pem()?)?, chain, retry))
+    }
+}
+
+/// 通过自定义REST接口为CSV中的每个CSR签发证书
+pub fn webhook_enroll(params: WebhookParams) -> Result<WebhookResult, BatchError> {
+    enroll_records(&params).map_err(BatchError::from)
+}
+
+fn enroll_records(params: &WebhookParams) -> Result<WebhookResult> {
+    // 先校验JSONPath，避免提交后才发现无法解析响应
+    for (field, path) in [
+        ("certificate_path", &params.certificate_path),
+        ("chain_path", &params.chain_path),
+    ] {
+        if !path.trim().is_empty() {
+            parse_json_path(path).map_err(|e| BatchError::parameter(field, e))?;
+        }
+    }
+
+    let mut reader = ReaderBuilder::new()
+        .from_path(&params.input_path)
+        .map_err(|e| BatchError::io(&params.input_path, e))?;
+    let mut headers = reader.headers()?.clone();
+    let csr_col = headers
+        .iter()
+        .position(|h| h == "csr")
+        .ok_or_else(|| BatchError::header("缺少csr列"))?;
+    let sans_col = headers.iter().position(|h| h == "sans");
+    // 重复签发时覆盖已有的certificate和chain列
+    let cert_col = ensure_column(&mut headers, "certificate");
+    let chain_col = ensure_column(&mut headers, "chain");
+
+    let output_path = if params.output_path.is_empty() {
+        derived_csv_path(&params.input_path, "webhook")
+    } else {
+        params.output_path.clone()
+    };
+    let mut output = ResultCsv::new(&output_path, params.overwrite, &headers)?;
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| BatchError::record(index + 1, format!("格式错误: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = WebhookClient::connect(params)?;
+    let enroll_record = |record: &StringRecord, number: usize| {
+        let csr = parse_csr(record.get(csr_col).unwrap_or(""))
+            .map_err(|e| BatchError::record(number, format!("CSR无效: {}", e)))?;
+        client
+            .enroll(&csr, sans_col.and_then(|c| record.get(c)).unwrap_or(""))
+            .map_err(|e| BatchError::record(number, format!("签发失败: {}", e)))
+    };
+
+    let mut total = 0;
+    let mut retries = 0;
+    for (index, record) in records.iter().enumerate() {
+        // 中途签发失败时，已签发的证书和其余行照常写入输出文件
+        let (cert_pem, chain_pem, retried) = match enroll_record(record, index + 1) {
+            Ok(issued) => issued,
+            Err(error) => return Err(output.abort(error.into(), &records[index..], total)),
+        };
+        retries += retried;
+
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.resize(headers.len(), "");
+        fields[cert_col] = &cert_pem;
+        fields[chain_col] = &chain_pem;
+        output.write_record(&fields)?;
+        total += 1;
+    }
+    output.finish()?;
+
+    let mut message = format!("REST CA签发 {} 个证书", total);
+    if retries > 0 {
+        message.push_str(&format!("，重试{}次", retries));
+    }
+    Ok(WebhookResult {
+        success: true,
+        message,
+        total,
+        retries,
+        output_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csr_generator::{run_batch, BatchRequest, CancelToken};
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use tiny_http::{Response, Server};
+
+    /// 启动测试CA：PUT /api/certs/<CN>，校验令牌和请求头，证书和证书链位于响应的不同字段
+    fn start_mock() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Test Internal CA").unwrap();
+        let name = name.build();
+        let validity = |builder: &mut X509Builder, issuer: &openssl::x509::X509NameRef| {
+            builder.set_version(2).unwrap();
+            builder.set_issuer_name(issuer).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+                .unwrap();
+        };
+        let mut builder = X509Builder::new().unwrap();
+        validity(&mut builder, &name);
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(&ca_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca = builder.build();
+        let ca_pem = String::from_utf8(ca.to_pem().unwrap()).unwrap();
+        let ca_b64 = base64::encode_block(&ca.to_der().unwrap());
+
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let header = |name: &'static str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv(name))
+                        .map(|h| h.value.to_string())
+                        .unwrap_or_default()
+                };
+                let (authorization, api_key) = (header("Authorization"), header("X-Api-Key"));
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let response = if authorization != "Bearer t0ken" {
+                    Response::from_string("unauthorized").with_status_code(401)
+                } else {
+                    assert_eq!(request.method().as_str(), "PUT");
+                    let body: Value = serde_json::from_str(&body).unwrap();
+                    let cn = body["name"].as_str().unwrap();
+                    assert_eq!(request.url(), format!("/api/certs/{}", cn));
+                    assert_eq!(api_key, format!("batch-{}", cn));
+                    // 备用名称取自CSR
+                    assert_eq!(body["sans"], format!("DNS:{}.example.com", cn));
+                    let csr = parse_csr(body["csr"].as_str().unwrap()).unwrap();
+                    let mut builder = X509Builder::new().unwrap();
+                    validity(&mut builder, &name);
+                    builder.set_subject_name(csr.subject_name()).unwrap();
+                    builder.set_pubkey(&csr.public_key().unwrap()).unwrap();
+                    builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+                    let cert = String::from_utf8(builder.build().to_pem().unwrap()).unwrap();
+                    // 证书字段为完整证书链，issuer字段再带一份DER形式的CA证书
+                    let reply = serde_json::json!({
+                        "data": {
+                            "certs": [{ "pem": format!("{}{}", ca_pem, cert) }],
+                            "issuer": [ca_b64],
+                        }
+                    });
+                    Response::from_string(reply.to_string())
+                };
+                let _ = request.respond(response);
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_webhook_enroll() {
+        assert_eq!(
+            parse_json_path("$['data'].certs[0].*").unwrap(),
+            vec![
+                Segment::Field("data".to_string()),
+                Segment::Field("certs".to_string()),
+                Segment::Index(0),
+                Segment::Wildcard,
+            ]
+        );
+        assert!(parse_json_path("data.certs").is_err());
+        assert_eq!(json_escape("a\"b\n"), "a\\\"b\\n");
+
+        let dir = std::env::temp_dir().join("csr_batch_webhook_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("out.csv");
+        let generate = BatchRequest {
+            cn_range: "dev1,dev2".to_string(),
+            subject_template: "CN=[{CN}]".to_string(),
+            sans: "dNSName=[{CN}.example.com]".to_string(),
+            key_type: "EC_P256".to_string(),
+            output_path: input.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        run_batch(generate, &CancelToken::default(), &|_| {}).unwrap();
+
+        let base = start_mock();
+        let params = |token: &str, overwrite: bool| WebhookParams {
+            input_path: input.to_string_lossy().to_string(),
+            url: format!("{}/api/certs/{{CN}}", base),
+            method: "put".to_string(),
+            body_template: r#"{"csr": "{CSR_PEM}", "name": "{CN}", "sans": "{SANS}"}"#.to_string(),
+            headers: "X-Api-Key: batch-{CN}\n".to_string(),
+            auth_method: "bearer".to_string(),
+            token: token.to_string(),
+            certificate_path: "$.data.certs[0].pem".to_string(),
+            chain_path: "$['data'].issuer[*]".to_string(),
+            overwrite,
+            ..Default::default()
+        };
+        let result = webhook_enroll(params("t0ken", false)).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.output_path.ends_with("out_webhook.csv"));
+
+        let mut reader = csv::Reader::from_path(&result.output_path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let (key_col, cert_col, chain_col) =
+            (column("privateKey"), column("certificate"), column("chain"));
+        for record in reader.records() {
+            let record = record.unwrap();
+            let key = PKey::private_key_from_pem(record[key_col].as_bytes()).unwrap();
+            let cert = X509::from_pem(record[cert_col].as_bytes()).unwrap();
+            assert!(cert.public_key().unwrap().public_eq(&key));
+            // 两个字段中的同一CA证书只写入一次
+            let chain = X509::stack_from_pem(record[chain_col].as_bytes()).unwrap();
+            assert_eq!(chain.len(), 1);
+            assert_eq!(cert.issuer_name_hash(), chain[0].subject_name_hash());
+        }
+
+        // 结果文件已存在时默认拒绝覆盖
+        let error = webhook_enroll(params("wrong", false)).unwrap_err();
+        assert!(
+            matches!(error, BatchError::InvalidParameter { ref field, .. } if field == "overwrite")
+        );
+
+        // 签发失败时已有的结果文件保持不变
+        let issued = fs::read_to_string(&result.output_path).unwrap();
+        let error = webhook_enroll(params("wrong", true)).unwrap_err();
+        assert!(error.to_string().contains("401"), "{}", error);
+        assert_eq!(fs::read_to_string(&result.output_path).unwrap(), issued);
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,211 @@
+//! 任务管理模块
+//! 登记后台执行的批量生成任务，提供取消、暂停与状态查询
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use csr_batch_core::{BatchError, BatchReport, CancelToken, ProgressEvent};
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// 执行中
+    Running,
+    /// 已完成
+    Completed,
+    /// 已取消
+    Cancelled,
+    /// 已暂停，可从断点继续
+    Paused,
+    /// 执行失败
+    Failed,
+}
+
+/// 任务状态信息
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    /// 任务ID
+    pub job_id: String,
+    /// 当前状态
+    pub state: JobState,
+    /// 最近一次进度
+    pub progress: Option<ProgressEvent>,
+    /// 生成结果（完成或取消后可用）
+    pub result: Option<BatchReport>,
+    /// 错误信息（失败时可用）
+    pub error: Option<BatchError>,
+}
+
+/// 登记的任务
+struct JobEntry {
+    cancel: CancelToken,
+    /// 是否由暂停请求停止
+    paused: bool,
+    status: JobStatus,
+}
+
+/// 任务登记表，作为Tauri状态在命令之间共享
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+}
+
+/// 生成新的任务ID
+pub fn new_job_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+impl JobRegistry {
+    /// 登记任务并返回其取消令牌，同ID任务仍在执行时返回错误
+    pub fn register(&self, job_id: &str) -> Result<CancelToken, BatchError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if matches!(jobs.get(job_id), Some(entry) if entry.status.state == JobState::Running) {
+            return Err(BatchError::Other {
+                message: format!("任务正在执行中: {}", job_id),
+            });
+        }
+
+        let cancel = CancelToken::default();
+        jobs.insert(
+            job_id.to_string(),
+            JobEntry {
+                cancel: cancel.clone(),
+                paused: false,
+                status: JobStatus {
+                    job_id: job_id.to_string(),
+                    state: JobState::Running,
+                    progress: None,
+                    result: None,
+                    error: None,
+                },
+            },
+        );
+        Ok(cancel)
+    }
+
+    /// 记录任务进度
+    pub fn update_progress(&self, progress: &ProgressEvent) {
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(&progress.job_id) {
+            entry.status.progress = Some(progress.clone());
+        }
+    }
+
+    /// 记录任务结束，返回最终状态
+    pub fn finish(&self, job_id: &str, result: Result<BatchReport, BatchError>) -> JobStatus {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(entry) = jobs.get_mut(job_id) else {
+            return JobStatus {
+                job_id: job_id.to_string(),
+                state: JobState::Failed,
+                progress: None,
+                result: None,
+                error: Some(BatchError::Other {
+                    message: "任务未登记".to_string(),
+                }),
+            };
+        };
+
+        match result {
+            Ok(result) => {
+                entry.status.state = if result.cancelled && entry.paused {
+                    JobState::Paused
+                } else if result.cancelled {
+                    JobState::Cancelled
+                } else {
+                    JobState::Completed
+                };
+                entry.status.result = Some(result);
+            }
+            Err(error) => {
+                entry.status.state = JobState::Failed;
+                entry.status.error = Some(error);
+            }
+        }
+        entry.status.clone()
+    }
+
+    /// 取消指定任务，任务不存在或已结束时返回false
+    pub fn cancel(&self, job_id: &str) -> bool {
+        match self.jobs.lock().unwrap().get(job_id) {
+            Some(entry) if entry.status.state == JobState::Running => {
+                entry.cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 暂停指定任务：停止生成，已写入的结果保留，结束后状态为已暂停；任务不存在或已结束时返回false
+    pub fn pause(&self, job_id: &str) -> bool {
+        match self.jobs.lock().unwrap().get_mut(job_id) {
+            Some(entry) if entry.status.state == JobState::Running => {
+                entry.paused = true;
+                entry.cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 查询任务状态
+    pub fn status(&self, job_id: &str) -> Option<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .map(|entry| entry.status.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let registry = JobRegistry::default();
+        let cancel = registry.register("job-1").unwrap();
+        assert!(registry.register("job-1").is_err());
+
+        assert!(registry.cancel("job-1"));
+        assert!(cancel.is_cancelled());
+
+        let status = registry.finish("job-1", Err(BatchError::NoItems));
+        assert_eq!(status.state, JobState::Failed);
+        assert_eq!(registry.status("job-1").unwrap().state, JobState::Failed);
+        assert!(!registry.cancel("job-1"));
+        assert!(registry.status("job-2").is_none());
+        assert!(!registry.pause("job-2"));
+    }
+
+    #[test]
+    fn test_job_pause() {
+        let registry = JobRegistry::default();
+        let cancel = registry.register("job-1").unwrap();
+        assert!(registry.pause("job-1"));
+        assert!(cancel.is_cancelled());
+
+        let report = BatchReport {
+            success: true,
+            message: String::new(),
+            total: 1,
+            failed: 0,
+            failures: Vec::new(),
+            output_path: String::new(),
+            error_report_path: String::new(),
+            manifest_path: String::new(),
+            warnings: Vec::new(),
+            cancelled: true,
+        };
+        let status = registry.finish("job-1", Ok(report.clone()));
+        assert_eq!(status.state, JobState::Paused);
+
+        // 重新登记（续传）后不再处于暂停状态
+        registry.register("job-1").unwrap();
+        let status = registry.finish("job-1", Ok(report));
+        assert_eq!(status.state, JobState::Cancelled);
+    }
+}
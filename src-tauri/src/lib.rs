@@ -1,15 +1,646 @@
 //! 批量CSR生成器 - Rust后端
 //! 功能：根据用户输入的通用名称范围批量生成CSR，并输出到CSV文件
-//! 支持多种密钥类型：RSA_2048/3072/4096, EC_P-256/384/521
+//! 支持多种密钥类型：RSA_2048/3072/4096, EC_P-256/384/521, EC_secp256k1, EC_brainpoolP256r1/384r1/512r1,
+//! ED25519, ED448, SM2
+//!
+//! 生成逻辑位于 `csr-batch-core` 库，本模块仅将其包装为Tauri命令
 
-mod csr_generator;
+mod jobs;
+mod queue;
 
-use csr_generator::{generate_csr_batch_internal, GenerateParams, GenerateResult};
+use csr_batch_core::{
+    adcs_enroll, cmp_enroll, ejbca_enroll, est_enroll, export_pkcs12_bundles, issue_acme,
+    issue_with_pca, provision_piv_tokens, renew_certificates, resume_checkpoint, retry_request,
+    revoke, run_batch_with_key_pool, scep_enroll, sign_batch, webhook_enroll, AcmeParams,
+    AcmeResult, AdcsParams, AdcsResult, AuditLogReport, BatchError, BatchReport, BatchRequest,
+    Checkpoint, CmpParams, CmpResult, CnPreview, CompletionHooks, CrlParams, CrlResult, CsrInfo,
+    EjbcaParams, EjbcaResult, EstParams, EstResult, ExpiringCertificate, ImportParams,
+    ImportResult, JobLogEntry, JobRecord, JobSummary, KeyPool, PcaParams, PcaResult, PivParams,
+    PivResult, Pkcs12Params, Pkcs12Result, PresetInfo, Profile, RenewParams, Revocation,
+    ScepParams, ScepResult, SignParams, SignResult, VaultParams, VaultResult, VerifyParams,
+    VerifyReport, WebhookParams, WebhookResult,
+};
+use jobs::{new_job_id, JobRegistry, JobState, JobStatus};
+use queue::{JobQueue, QueueStatus};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+/// 阻塞任务异常结束（如发生panic）时的错误
+fn join_error(error: tauri::Error) -> BatchError {
+    BatchError::Other {
+        message: format!("后台任务异常结束: {}", error),
+    }
+}
 
 /// 批量生成CSR的Tauri命令
+/// 登记任务后立即返回任务ID，生成在阻塞任务线程中执行：
+/// 过程中通过 `csr-progress` 事件推送进度，结束时通过 `csr-complete` 事件推送最终状态
+/// 命令和任务状态中的错误均为结构化的 `BatchError`，前端按 kind 字段显示提示
+/// 新密钥优先从 `prefill_key_pool` 预生成的密钥池中取用
+#[tauri::command]
+async fn generate_csr_batch(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    params: BatchRequest,
+) -> Result<String, BatchError> {
+    start_batch(app, &jobs, params)
+}
+
+/// 按配置模板批量生成CSR：模板中的密钥类型、哈希算法、密钥用途和备用名称写入参数中为空的字段，
+/// 其余同 `generate_csr_batch`
+#[tauri::command]
+async fn generate_with_profile(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    profile: String,
+    mut params: BatchRequest,
+) -> Result<String, BatchError> {
+    Profile::find(&config_path(&app, "profiles.json")?, &profile)?.apply(&mut params);
+    start_batch(app, &jobs, params)
+}
+
+/// 登记任务并在阻塞任务线程中开始生成，返回任务ID
+/// 未指定审计日志时写入应用配置目录下的审计日志；结束后写入任务历史，写入失败时在报告中警告
+/// 输出方式支持时在应用配置目录下记录断点，任务被取消或失败时删除，暂停或程序中途退出时保留以便续传
+fn start_batch(
+    app: AppHandle,
+    jobs: &JobRegistry,
+    mut params: BatchRequest,
+) -> Result<String, BatchError> {
+    if params.job_id.is_empty() {
+        params.job_id = new_job_id();
+    }
+    if params.audit_log_path.is_empty() {
+        params.audit_log_path = config_path(&app, AUDIT_LOG_FILE)?;
+    }
+    if params.checkpoint_path.is_empty() && params.supports_checkpoint() {
+        params.checkpoint_path = checkpoint_path(&app, &params.job_id)?;
+    }
+    let job_id = params.job_id.clone();
+    let cancel = jobs.register(&job_id)?;
+
+    let task_job_id = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = app.state::<JobRegistry>();
+        let key_pool = app.state::<KeyPool>();
+        let on_progress = |progress| {
+            jobs.update_progress(&progress);
+            let _ = app.emit("csr-progress", progress);
+        };
+        let entry = JobLogEntry::start(&params);
+        let hooks = CompletionHooks::from_request(&params);
+        let checkpoint_path = params.checkpoint_path.clone();
+        let mut result = run_batch_with_key_pool(params, &cancel, &on_progress, &key_pool);
+        let logged =
+            config_path(&app, JOB_HISTORY_FILE).and_then(|path| entry.finish(&path, &result));
+        if let (Err(error), Ok(report)) = (logged, &mut result) {
+            report.warnings.push(format!("写入任务历史失败: {}", error));
+        }
+        // 生成失败时回调的警告无处附加，只以失败事件通知前端
+        let hook_warnings = hooks.run(&result);
+        if let Ok(report) = &mut result {
+            report.warnings.extend(hook_warnings);
+        }
+        let status = jobs.finish(&task_job_id, result);
+        if matches!(status.state, JobState::Cancelled | JobState::Failed) {
+            let _ = std::fs::remove_file(&checkpoint_path);
+        }
+        notify_finished(&app, &status);
+        let _ = app.emit("csr-complete", status);
+        app.state::<JobQueue>().finished(&task_job_id);
+        dispatch_queue(&app);
+    });
+
+    Ok(job_id)
+}
+
+/// 任务结束时发送系统通知，窗口最小化或在后台时也能及时得知，通知发送失败时忽略
+fn notify_finished(app: &AppHandle, status: &JobStatus) {
+    let (title, body) = match (&status.state, &status.result, &status.error) {
+        (JobState::Paused, _, _) => ("批量生成已暂停", "可在任务历史中从断点继续".to_string()),
+        (JobState::Cancelled, _, _) => ("批量生成已取消", status.job_id.clone()),
+        (_, Some(report), _) => ("批量生成完成", report.message.clone()),
+        (_, _, Some(error)) => ("批量生成失败", error.to_string()),
+        _ => return,
+    };
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+/// 按队列顺序启动等待中的任务，直到达到并发数，并通过 `csr-queue` 事件推送队列状态
+/// 任务无法启动时推送失败的 `csr-complete` 事件
+fn dispatch_queue(app: &AppHandle) {
+    let queue = app.state::<JobQueue>();
+    while let Some(params) = queue.next_ready() {
+        let job_id = params.job_id.clone();
+        if let Err(error) = start_batch(app.clone(), &app.state::<JobRegistry>(), params) {
+            queue.finished(&job_id);
+            let status = JobStatus {
+                job_id,
+                state: JobState::Failed,
+                progress: None,
+                result: None,
+                error: Some(error),
+            };
+            let _ = app.emit("csr-complete", status);
+        }
+    }
+    let _ = app.emit("csr-queue", queue.status());
+}
+
+/// 将批量生成任务加入队列，返回任务ID；name为队列中显示的名称（如设备型号），为空时使用通用名称范围
+/// 队列中的任务按顺序执行，进度和结束事件与 `generate_csr_batch` 相同
+#[tauri::command]
+fn enqueue_batch(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    name: String,
+    params: BatchRequest,
+) -> String {
+    let job_id = queue.push(&name, params);
+    dispatch_queue(&app);
+    job_id
+}
+
+/// 查询队列状态：并发数、由队列启动的执行中任务和等待中的任务
+#[tauri::command]
+fn list_queue(queue: State<'_, JobQueue>) -> QueueStatus {
+    queue.status()
+}
+
+/// 设置队列同时执行的任务数
+#[tauri::command]
+fn set_queue_concurrency(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    concurrency: usize,
+) -> Result<(), BatchError> {
+    queue.set_concurrency(concurrency)?;
+    dispatch_queue(&app);
+    Ok(())
+}
+
+/// 将等待中的任务移动到指定位置（从0开始）
+#[tauri::command]
+fn move_queued_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    job_id: String,
+    position: usize,
+) -> Result<(), BatchError> {
+    queue.move_to(&job_id, position)?;
+    dispatch_queue(&app);
+    Ok(())
+}
+
+/// 暂停等待中的任务，暂停的任务保留在队列中但不被调度
+#[tauri::command]
+fn pause_queued_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    job_id: String,
+) -> Result<(), BatchError> {
+    queue.set_paused(&job_id, true)?;
+    dispatch_queue(&app);
+    Ok(())
+}
+
+/// 恢复已暂停的任务
+#[tauri::command]
+fn resume_queued_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    job_id: String,
+) -> Result<(), BatchError> {
+    queue.set_paused(&job_id, false)?;
+    dispatch_queue(&app);
+    Ok(())
+}
+
+/// 取消队列中的任务：等待中的任务直接移出队列，执行中的任务同 `cancel_generation`
+#[tauri::command]
+fn cancel_queued_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    jobs: State<'_, JobRegistry>,
+    job_id: String,
+) -> bool {
+    if queue.remove(&job_id) {
+        dispatch_queue(&app);
+        return true;
+    }
+    jobs.cancel(&job_id)
+}
+
+/// 任务历史数据库的文件名（位于应用配置目录）
+const JOB_HISTORY_FILE: &str = "job_history.db";
+
+/// 审计日志的文件名（位于应用配置目录）
+const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// 断点文件所在的目录（位于应用配置目录）
+const CHECKPOINT_DIR: &str = "checkpoints";
+
+/// 应用配置目录下的文件路径
+fn config_path(app: &AppHandle, file_name: &str) -> Result<String, BatchError> {
+    let dir = app.path().app_config_dir().map_err(|e| BatchError::Other {
+        message: format!("无法确定应用配置目录: {}", e),
+    })?;
+    Ok(dir.join(file_name).to_string_lossy().to_string())
+}
+
+/// 任务的断点文件路径
+fn checkpoint_path(app: &AppHandle, job_id: &str) -> Result<String, BatchError> {
+    config_path(app, &format!("{}/{}.json", CHECKPOINT_DIR, job_id))
+}
+
+/// 列出配置模板（应用配置目录下的 profiles.json，首次读取时写入内置模板）
+#[tauri::command]
+fn list_profiles(app: AppHandle) -> Result<Vec<Profile>, BatchError> {
+    Profile::load(&config_path(&app, "profiles.json")?)
+}
+
+/// 按名称保存生成参数到应用配置目录下的 presets.json，不含私钥口令等机密参数
+#[tauri::command]
+fn save_preset(app: AppHandle, name: String, params: BatchRequest) -> Result<(), BatchError> {
+    csr_batch_core::save_preset(&config_path(&app, "presets.json")?, &name, params)
+}
+
+/// 列出已保存的任务配置
+#[tauri::command]
+fn list_presets(app: AppHandle) -> Result<Vec<PresetInfo>, BatchError> {
+    csr_batch_core::list_presets(&config_path(&app, "presets.json")?)
+}
+
+/// 按名称载入已保存的生成参数
+#[tauri::command]
+fn load_preset(app: AppHandle, name: String) -> Result<BatchRequest, BatchError> {
+    csr_batch_core::load_preset(&config_path(&app, "presets.json")?, &name)
+}
+
+/// 按开始时间倒序列出任务历史，limit为0时返回全部
+#[tauri::command]
+fn list_jobs(app: AppHandle, limit: usize) -> Result<Vec<JobSummary>, BatchError> {
+    csr_batch_core::list_jobs(&config_path(&app, JOB_HISTORY_FILE)?, limit)
+}
+
+/// 查询任务历史中的单个任务：参数（不含机密参数）、数量、耗时、输出路径和失败条目
+#[tauri::command]
+fn get_job(app: AppHandle, id: String) -> Result<JobRecord, BatchError> {
+    csr_batch_core::get_job(&config_path(&app, JOB_HISTORY_FILE)?, &id)
+}
+
+/// 重新生成任务历史中某个任务的失败条目并追加到原输出CSV，返回新任务ID；
+/// 重试结束后原任务的数量、失败条目和错误报告随之更新
+/// 私钥口令等机密参数和新任务ID取自secrets，进度和结束事件与 `generate_csr_batch` 相同
+#[tauri::command]
+async fn retry_failed(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    job_id: String,
+    secrets: BatchRequest,
+) -> Result<String, BatchError> {
+    let params = retry_request(&config_path(&app, JOB_HISTORY_FILE)?, &job_id, &secrets)?;
+    start_batch(app, &jobs, params)
+}
+
+/// 校验审计日志的哈希链，path为空时校验应用配置目录下的审计日志
+#[tauri::command]
+async fn verify_audit_log(app: AppHandle, path: String) -> Result<AuditLogReport, BatchError> {
+    let path = if path.is_empty() {
+        config_path(&app, AUDIT_LOG_FILE)?
+    } else {
+        path
+    };
+    tauri::async_runtime::spawn_blocking(move || csr_batch_core::verify_audit_log(&path))
+        .await
+        .map_err(join_error)?
+}
+
+/// 预览展开后的通用名称和第一个条目的Subject，不生成密钥
+/// limit为首尾各返回的数量，0表示返回全部
+#[tauri::command]
+async fn preview_cn_range(params: BatchRequest, limit: usize) -> Result<CnPreview, BatchError> {
+    tauri::async_runtime::spawn_blocking(move || params.preview(limit))
+        .await
+        .map_err(join_error)?
+}
+
+/// 按表单中的密钥类型和条目数在后台预生成密钥，立即返回
+#[tauri::command]
+fn prefill_key_pool(
+    key_pool: State<'_, KeyPool>,
+    key_type: String,
+    count: usize,
+) -> Result<(), BatchError> {
+    key_pool.prefill(&key_type, count)
+}
+
+/// 取消正在执行的批量生成任务
+#[tauri::command]
+fn cancel_generation(jobs: State<'_, JobRegistry>, job_id: String) -> bool {
+    jobs.cancel(&job_id)
+}
+
+/// 暂停正在执行的批量生成任务：已写入的结果和断点保留，之后可用 `resume_job` 继续
+/// 输出方式不支持断点续传时返回错误，任务不存在或已结束时返回false
+#[tauri::command]
+fn pause_job(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<bool, BatchError> {
+    if !std::path::Path::new(&checkpoint_path(&app, &job_id)?).exists() {
+        if jobs
+            .status(&job_id)
+            .is_some_and(|status| status.state == JobState::Running)
+        {
+            return Err(BatchError::InvalidParameter {
+                field: "output_mode".to_string(),
+                message: "该任务的输出方式不支持暂停，仅不拆分的CSV输出可暂停后继续".to_string(),
+            });
+        }
+        return Ok(false);
+    }
+    Ok(jobs.pause(&job_id))
+}
+
+/// 从断点继续已暂停或因程序退出而中断的任务，返回任务ID（与原任务相同）
+/// 断点不保存机密参数，私钥口令等取自secrets（通常为当前表单的参数）；进度和结束事件与 `generate_csr_batch` 相同
+#[tauri::command]
+async fn resume_job(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    job_id: String,
+    secrets: BatchRequest,
+) -> Result<String, BatchError> {
+    let path = checkpoint_path(&app, &job_id)?;
+    let params = resume_checkpoint(&path, &secrets)?;
+    start_batch(app, &jobs, params)
+}
+
+/// 列出可继续的任务断点（按更新时间倒序）
+#[tauri::command]
+fn list_checkpoints(app: AppHandle) -> Result<Vec<Checkpoint>, BatchError> {
+    csr_batch_core::list_checkpoints(&config_path(&app, CHECKPOINT_DIR)?)
+}
+
+/// 放弃任务断点，已写入的输出文件保留
+#[tauri::command]
+fn discard_checkpoint(app: AppHandle, job_id: String) -> Result<(), BatchError> {
+    let path = checkpoint_path(&app, &job_id)?;
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(BatchError::Io {
+            path,
+            message: e.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// 查询批量生成任务的状态
+#[tauri::command]
+fn get_job_status(jobs: State<'_, JobRegistry>, job_id: String) -> Option<JobStatus> {
+    jobs.status(&job_id)
+}
+
+/// 将签发结果CSV中的证书记入任务历史数据库用于到期跟踪，记录失败不影响签发结果
+fn track_issued(db_path: &str, output_path: &str) {
+    let _ = csr_batch_core::track_certificates(db_path, output_path);
+}
+
+/// 使用内置CA为生成结果CSV中的CSR签发证书
+#[tauri::command]
+async fn sign_csr_batch(app: AppHandle, params: SignParams) -> Result<SignResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = sign_batch(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR提交到ACME CA，完成DNS-01验证后写回签发的证书
+#[tauri::command]
+async fn acme_issue(app: AppHandle, params: AcmeParams) -> Result<AcmeResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = issue_acme(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR逐个提交到EST服务器注册，签发的证书写入私钥所在的行
+#[tauri::command]
+async fn enroll_est(app: AppHandle, params: EstParams) -> Result<EstResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = est_enroll(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR通过SCEP提交给CA，等待签发后写入证书
+#[tauri::command]
+async fn enroll_scep(app: AppHandle, params: ScepParams) -> Result<ScepResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = scep_enroll(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR通过CMP提交给CA，确认签发后写入证书
+#[tauri::command]
+async fn enroll_cmp(app: AppHandle, params: CmpParams) -> Result<CmpResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = cmp_enroll(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR提交到EJBCA REST API，签发后写入证书
+#[tauri::command]
+async fn enroll_ejbca(app: AppHandle, params: EjbcaParams) -> Result<EjbcaResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = ejbca_enroll(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR按证书模板提交给AD CS，或取回之前挂起的请求
+#[tauri::command]
+async fn enroll_adcs(app: AppHandle, params: AdcsParams) -> Result<AdcsResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = adcs_enroll(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR提交到Vault PKI的 sign 接口，签发后写入证书和证书链
+#[tauri::command]
+async fn sign_with_vault(app: AppHandle, params: VaultParams) -> Result<VaultResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = csr_batch_core::sign_with_vault(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR通过AWS Private CA签发，写入证书、证书链和证书ARN
+#[tauri::command]
+async fn issue_pca(app: AppHandle, params: PcaParams) -> Result<PcaResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = issue_with_pca(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将生成结果CSV中的CSR按模板提交到自定义REST接口，从响应中取出证书写入CSV
+#[tauri::command]
+async fn enroll_webhook(
+    app: AppHandle,
+    params: WebhookParams,
+) -> Result<WebhookResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = webhook_enroll(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 将离线签发的证书（目录或ZIP）按公钥或CN对应回生成结果CSV，校验证书链后写入，可选导出PKCS#12
+#[tauri::command]
+async fn import_certificates(
+    app: AppHandle,
+    params: ImportParams,
+) -> Result<ImportResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = csr_batch_core::import_certificates(params)?;
+        track_issued(&db_path, &result.output_path);
+        Ok(result)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 在任务历史数据库中吊销证书，reason为RFC 5280的吊销原因（空为unspecified），下次签发CRL时列入
+#[tauri::command]
+fn revoke_certificate(
+    app: AppHandle,
+    serial: String,
+    reason: String,
+) -> Result<Revocation, BatchError> {
+    revoke(&config_path(&app, JOB_HISTORY_FILE)?, &serial, &reason)
+}
+
+/// 按吊销时间列出任务历史数据库中的吊销记录
+#[tauri::command]
+fn list_revocations(app: AppHandle) -> Result<Vec<Revocation>, BatchError> {
+    csr_batch_core::list_revocations(&config_path(&app, JOB_HISTORY_FILE)?, "")
+}
+
+/// 使用内置CA签发包含任务历史数据库中吊销记录的CRL
+#[tauri::command]
+async fn generate_crl(app: AppHandle, params: CrlParams) -> Result<CrlResult, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || csr_batch_core::generate_crl(&db_path, params))
+        .await
+        .map_err(join_error)?
+}
+
+/// 将CSV中已签发的证书记入任务历史数据库用于到期跟踪，返回记录的证书数
+#[tauri::command]
+async fn track_certificates(app: AppHandle, path: String) -> Result<usize, BatchError> {
+    let db_path = config_path(&app, JOB_HISTORY_FILE)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        csr_batch_core::track_certificates(&db_path, &path)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// 列出任务历史数据库中days天内到期（含已过期）的证书
+#[tauri::command]
+fn list_expiring(app: AppHandle, days: u32) -> Result<Vec<ExpiringCertificate>, BatchError> {
+    csr_batch_core::list_expiring(&config_path(&app, JOB_HISTORY_FILE)?, days)
+}
+
+/// 将已签发CSV中的私钥和证书逐条导出为PKCS#12文件，未指定审计日志时记入应用配置目录下的审计日志
+#[tauri::command]
+async fn export_pkcs12(
+    app: AppHandle,
+    mut params: Pkcs12Params,
+) -> Result<Pkcs12Result, BatchError> {
+    if params.audit_log_path.is_empty() {
+        params.audit_log_path = config_path(&app, AUDIT_LOG_FILE)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || export_pkcs12_bundles(params))
+        .await
+        .map_err(join_error)?
+}
+
+/// 在已连接的YubiKey的PIV槽位中生成密钥对和CSR
+#[tauri::command]
+async fn provision_piv(params: PivParams) -> Result<PivResult, BatchError> {
+    tauri::async_runtime::spawn_blocking(move || provision_piv_tokens(params))
+        .await
+        .map_err(join_error)?
+}
+
+/// 为已有证书生成续期CSR，沿用或轮换原私钥
+#[tauri::command]
+async fn renew_from_certs(params: RenewParams) -> Result<BatchReport, BatchError> {
+    tauri::async_runtime::spawn_blocking(move || renew_certificates(params))
+        .await
+        .map_err(join_error)?
+}
+
+/// 解析单个CSR（文件路径，或PEM/单行Base64文本），返回Subject、备用名称、公钥、签名算法和请求扩展
+#[tauri::command]
+fn inspect_csr(csr: String) -> Result<CsrInfo, BatchError> {
+    CsrInfo::inspect(&csr)
+}
+
+/// 校验生成的CSV：CSR自签名、私钥与CSR公钥是否匹配及通用名称是否重复
 #[tauri::command]
-fn generate_csr_batch(params: GenerateParams) -> Result<GenerateResult, String> {
-    generate_csr_batch_internal(params).map_err(|e| e.to_string())
+async fn verify_batch(params: VerifyParams) -> Result<VerifyReport, BatchError> {
+    tauri::async_runtime::spawn_blocking(move || csr_batch_core::verify_batch(params))
+        .await
+        .map_err(join_error)?
 }
 
 /// 运行Tauri应用
@@ -18,7 +649,58 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![generate_csr_batch])
+        .plugin(tauri_plugin_notification::init())
+        .manage(JobRegistry::default())
+        .manage(KeyPool::default())
+        .manage(JobQueue::default())
+        .invoke_handler(tauri::generate_handler![
+            generate_csr_batch,
+            generate_with_profile,
+            enqueue_batch,
+            list_queue,
+            set_queue_concurrency,
+            move_queued_job,
+            pause_queued_job,
+            resume_queued_job,
+            cancel_queued_job,
+            list_profiles,
+            save_preset,
+            list_presets,
+            load_preset,
+            preview_cn_range,
+            prefill_key_pool,
+            cancel_generation,
+            pause_job,
+            resume_job,
+            list_checkpoints,
+            discard_checkpoint,
+            get_job_status,
+            list_jobs,
+            get_job,
+            retry_failed,
+            verify_audit_log,
+            sign_csr_batch,
+            acme_issue,
+            enroll_est,
+            enroll_scep,
+            enroll_cmp,
+            enroll_ejbca,
+            enroll_adcs,
+            sign_with_vault,
+            issue_pca,
+            enroll_webhook,
+            import_certificates,
+            track_certificates,
+            list_expiring,
+            revoke_certificate,
+            list_revocations,
+            generate_crl,
+            export_pkcs12,
+            provision_piv,
+            renew_from_certs,
+            inspect_csr,
+            verify_batch
+        ])
         .run(tauri::generate_context!())
         .expect("运行Tauri应用时发生错误");
 }
@@ -0,0 +1,228 @@
+//! 任务队列模块
+//! 排队等待执行的批量生成任务，按队列顺序调度，同时执行的任务数不超过设置的并发数，
+//! 便于为多个设备型号依次准备批次
+//!
+//! 队列只记录等待中和由队列启动的任务，直接开始的生成任务不占用队列的并发数
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use csr_batch_core::{BatchError, BatchRequest};
+
+use crate::jobs::new_job_id;
+
+/// 队列中的任务
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJob {
+    /// 任务ID
+    pub job_id: String,
+    /// 任务名称，如设备型号
+    pub name: String,
+    /// 输出文件路径
+    pub output_path: String,
+    /// 是否已暂停（暂停的任务保留在队列中，不被调度）
+    pub paused: bool,
+}
+
+/// 队列状态
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    /// 同时执行的任务数上限
+    pub concurrency: usize,
+    /// 由队列启动、正在执行的任务
+    pub running: Vec<QueuedJob>,
+    /// 等待中的任务，按执行顺序排列
+    pub pending: Vec<QueuedJob>,
+}
+
+/// 等待中的任务及其生成参数
+struct PendingJob {
+    job: QueuedJob,
+    params: BatchRequest,
+}
+
+struct QueueInner {
+    concurrency: usize,
+    running: Vec<QueuedJob>,
+    pending: VecDeque<PendingJob>,
+}
+
+/// 任务队列，作为Tauri状态在命令之间共享
+pub struct JobQueue {
+    inner: Mutex<QueueInner>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(QueueInner {
+                concurrency: 1,
+                running: Vec::new(),
+                pending: VecDeque::new(),
+            }),
+        }
+    }
+}
+
+/// 队列中不存在该任务时的错误
+fn not_queued(job_id: &str) -> BatchError {
+    BatchError::InvalidParameter {
+        field: "job_id".to_string(),
+        message: format!("任务不在等待队列中: {}", job_id),
+    }
+}
+
+impl JobQueue {
+    /// 将任务加入队尾，返回任务ID；name为空时使用通用名称范围
+    pub fn push(&self, name: &str, mut params: BatchRequest) -> String {
+        if params.job_id.is_empty() {
+            params.job_id = new_job_id();
+        }
+        let name = if name.trim().is_empty() {
+            params.cn_range.clone()
+        } else {
+            name.trim().to_string()
+        };
+        let job = QueuedJob {
+            job_id: params.job_id.clone(),
+            name,
+            output_path: params.output_path.clone(),
+            paused: false,
+        };
+        let job_id = job.job_id.clone();
+        self.inner
+            .lock()
+            .unwrap()
+            .pending
+            .push_back(PendingJob { job, params });
+        job_id
+    }
+
+    /// 未达到并发数时取出第一个未暂停的任务并记为执行中
+    pub fn next_ready(&self) -> Option<BatchRequest> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.running.len() >= inner.concurrency {
+            return None;
+        }
+        let index = inner
+            .pending
+            .iter()
+            .position(|pending| !pending.job.paused)?;
+        let pending = inner.pending.remove(index)?;
+        inner.running.push(pending.job);
+        Some(pending.params)
+    }
+
+    /// 记录由队列启动的任务已结束，不是由队列启动的任务忽略
+    pub fn finished(&self, job_id: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .running
+            .retain(|job| job.job_id != job_id);
+    }
+
+    /// 设置并发数（至少为1）
+    pub fn set_concurrency(&self, concurrency: usize) -> Result<(), BatchError> {
+        if concurrency == 0 {
+            return Err(BatchError::InvalidParameter {
+                field: "concurrency".to_string(),
+                message: "并发数至少为1".to_string(),
+            });
+        }
+        self.inner.lock().unwrap().concurrency = concurrency;
+        Ok(())
+    }
+
+    /// 将等待中的任务移动到指定位置（从0开始，超出时移到队尾）
+    pub fn move_to(&self, job_id: &str, position: usize) -> Result<(), BatchError> {
+        let mut inner = self.inner.lock().unwrap();
+        let index = inner
+            .pending
+            .iter()
+            .position(|pending| pending.job.job_id == job_id)
+            .ok_or_else(|| not_queued(job_id))?;
+        let pending = inner.pending.remove(index).expect("索引有效");
+        let position = position.min(inner.pending.len());
+        inner.pending.insert(position, pending);
+        Ok(())
+    }
+
+    /// 暂停或恢复等待中的任务
+    pub fn set_paused(&self, job_id: &str, paused: bool) -> Result<(), BatchError> {
+        let mut inner = self.inner.lock().unwrap();
+        let pending = inner
+            .pending
+            .iter_mut()
+            .find(|pending| pending.job.job_id == job_id)
+            .ok_or_else(|| not_queued(job_id))?;
+        pending.job.paused = paused;
+        Ok(())
+    }
+
+    /// 从队列中移除等待中的任务，任务不在等待队列中时返回false
+    pub fn remove(&self, job_id: &str) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let before = inner.pending.len();
+        inner.pending.retain(|pending| pending.job.job_id != job_id);
+        inner.pending.len() < before
+    }
+
+    /// 查询队列状态
+    pub fn status(&self) -> QueueStatus {
+        let inner = self.inner.lock().unwrap();
+        QueueStatus {
+            concurrency: inner.concurrency,
+            running: inner.running.clone(),
+            pending: inner
+                .pending
+                .iter()
+                .map(|pending| pending.job.clone())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(cn_range: &str) -> BatchRequest {
+        BatchRequest {
+            cn_range: cn_range.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_queue_scheduling() {
+        let queue = JobQueue::default();
+        let a = queue.push("型号A", request("A001-A010"));
+        let b = queue.push("", request("B001-B010"));
+        let c = queue.push("型号C", request("C001-C010"));
+        assert_eq!(queue.status().pending[1].name, "B001-B010");
+
+        // 调整顺序并暂停，暂停的任务被跳过
+        queue.move_to(&c, 0).unwrap();
+        queue.set_paused(&c, true).unwrap();
+        assert_eq!(queue.next_ready().unwrap().job_id, a);
+        // 并发数为1时须等待前一个任务结束
+        assert!(queue.next_ready().is_none());
+        queue.set_concurrency(2).unwrap();
+        assert_eq!(queue.next_ready().unwrap().job_id, b);
+        assert!(queue.next_ready().is_none());
+
+        queue.finished(&a);
+        queue.set_paused(&c, false).unwrap();
+        assert_eq!(queue.next_ready().unwrap().job_id, c);
+        assert_eq!(queue.status().running.len(), 2);
+        assert!(queue.status().pending.is_empty());
+
+        let d = queue.push("型号D", request("D001"));
+        assert!(queue.remove(&d));
+        assert!(!queue.remove(&d));
+        assert!(queue.move_to(&d, 0).is_err());
+        assert!(queue.set_concurrency(0).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 import { useState, useRef, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open as openDialog } from "@tauri-apps/plugin-dialog";
 import {
   Card,
@@ -13,13 +14,25 @@ import {
   Row,
   Col,
   Space,
+  InputNumber,
+  Checkbox,
   message,
 } from "antd";
 import {
   PlayCircleOutlined,
+  StopOutlined,
   FolderOpenOutlined,
   SettingOutlined,
   FileTextOutlined,
+  SafetyCertificateOutlined,
+  EyeOutlined,
+  HistoryOutlined,
+  UnorderedListOutlined,
+  ArrowUpOutlined,
+  ArrowDownOutlined,
+  PauseOutlined,
+  CaretRightOutlined,
+  RedoOutlined,
 } from "@ant-design/icons";
 import dayjs from "dayjs";
 
@@ -31,22 +44,191 @@ const KEY_TYPES = [
   { value: "EC_P256", label: "EC_P-256" },
   { value: "EC_P384", label: "EC_P-384" },
   { value: "EC_P521", label: "EC_P-521" },
+  { value: "EC_SECP256K1", label: "EC_secp256k1" },
+  { value: "EC_BRAINPOOL_P256R1", label: "EC_brainpoolP256r1" },
+  { value: "EC_BRAINPOOL_P384R1", label: "EC_brainpoolP384r1" },
+  { value: "EC_BRAINPOOL_P512R1", label: "EC_brainpoolP512r1" },
+  { value: "ED25519", label: "ED25519" },
+  { value: "ED448", label: "ED448" },
+  { value: "SM2", label: "SM2" },
+  { value: "ML_DSA_65", label: "ML-DSA-65 (后量子)" },
+  { value: "ML_DSA_87", label: "ML-DSA-87 (后量子)" },
 ];
 
 // 签名哈希算法
 const SIGN_HASH_ALGORITHMS = ["SHA256", "SHA384", "SHA512", "SHA1", "MatchIssuer"];
 
+// RSA签名方案
+const SIGNATURE_SCHEMES = [
+  { value: "pkcs1", label: "PKCS#1 v1.5" },
+  { value: "pss", label: "RSASSA-PSS" },
+];
+
+// 输出方式
+const OUTPUT_MODES = [
+  { value: "csv", label: "CSV文件" },
+  { value: "files", label: "单独的PEM文件(.csr/.key)" },
+  { value: "both", label: "CSV文件和单独的PEM文件" },
+  { value: "zip", label: "ZIP压缩包(PEM文件+清单CSV)" },
+  { value: "xlsx", label: "Excel工作簿(.xlsx)" },
+  { value: "json", label: "JSON(.json)" },
+  { value: "ndjson", label: "NDJSON(每行一个JSON对象)" },
+  { value: "sqlite", label: "仅SQLite任务数据库(.db)" },
+];
+
+// 输出PEM文件的输出方式
+const PEM_OUTPUT_MODES = ["files", "both", "zip"];
+
+// 输出CSV文件的输出方式（ZIP中含清单CSV）
+const CSV_OUTPUT_MODES = ["csv", "both", "zip"];
+
+// CSR编码
+const CSR_ENCODINGS = [
+  { value: "pem", label: "PEM" },
+  { value: "base64", label: "单行Base64" },
+  { value: "der", label: "DER(单独的CSR文件)" },
+];
+
+// CSV分隔符
+const CSV_DELIMITERS = [
+  { value: ",", label: "逗号 ," },
+  { value: ";", label: "分号 ;" },
+  { value: "tab", label: "制表符 Tab" },
+  { value: "|", label: "竖线 |" },
+];
+
+// CSV引号方式
+const CSV_QUOTE_STYLES = [
+  { value: "necessary", label: "仅在需要时" },
+  { value: "always", label: "全部加引号" },
+  { value: "non_numeric", label: "非数字加引号" },
+  { value: "never", label: "不加引号" },
+];
+
+// CSV文件编码
+const CSV_ENCODINGS = [
+  { value: "utf-8", label: "UTF-8" },
+  { value: "gbk", label: "GBK" },
+  { value: "gb18030", label: "GB18030" },
+];
+
+// 通用名称范围计数器的进制
+const RANGE_RADIXES = [
+  { value: 10, label: "十进制" },
+  { value: 16, label: "十六进制" },
+  { value: 36, label: "三十六进制" },
+];
+
+// 通用名称模式
+const CN_MODES = [
+  { value: "range", label: "CN范围 / MAC地址范围" },
+  { value: "uuid", label: "随机UUID (UUIDv4)" },
+  { value: "hex", label: "随机十六进制字符串" },
+];
+
+// MAC地址分隔符
+const MAC_SEPARATORS = [
+  { value: "colon", label: "冒号 00:1A:2B:00:00:01" },
+  { value: "hyphen", label: "短横线 00-1A-2B-00-00-01" },
+  { value: "dot", label: "点 001A.2B00.0001" },
+  { value: "none", label: "无 001A2B000001" },
+];
+
+// 密钥存储位置
+const KEY_STORES = [
+  { value: "file", label: "输出私钥PEM" },
+  { value: "cng_user", label: "CNG密钥存储-当前用户(仅Windows)" },
+  { value: "cng_machine", label: "CNG密钥存储-本地计算机(仅Windows)" },
+  { value: "keychain", label: "登录钥匙串(仅macOS)" },
+  { value: "secure_enclave", label: "安全隔区(仅macOS，EC_P-256)" },
+  { value: "tpm", label: "TPM 2.0(Linux/Windows，需安装tpm2-tools)" },
+];
+
 // 生成参数接口
 interface GenerateParams {
   cn_range: string;
+  range_radix: number;
+  cn_mode?: string;
+  count?: number;
+  hex_length?: number;
+  mac_range?: string;
+  mac_separator?: string;
+  mac_case?: string;
+  input_csv_path?: string;
+  exclude?: string;
+  exclude_path?: string;
   subject_template: string;
+  dn_string_types?: string;
   key_type: string;
+  key_type_map?: string;
   sign_hash_alg: string;
+  pq_key_type?: string;
+  signature_scheme?: string;
+  pss_salt_length?: number | null;
+  pss_mgf1_hash?: string;
+  allow_legacy_algorithms?: boolean;
   not_before: string;
   not_after: string;
+  validity?: string;
+  not_before_offset?: string;
+  not_after_offset?: string;
   unique_id: string;
+  unique_id_serial_number?: boolean;
   sans: string;
+  idn_keep_utf8?: boolean;
   output_path: string;
+  output_mode?: string;
+  file_name_template?: string;
+  zip_password?: string;
+  split_keys?: boolean;
+  sqlite_path?: string;
+  screen_weak_keys?: boolean;
+  weak_key_blocklist?: string;
+  history_db_path?: string;
+  history_conflict?: string;
+  csv_delimiter?: string;
+  csv_quote_style?: string;
+  csv_line_ending?: string;
+  csv_encoding?: string;
+  csv_bom?: boolean;
+  max_rows_per_file?: number;
+  append?: boolean;
+  columns?: string;
+  csr_encoding?: string;
+  fingerprint_columns?: boolean;
+  manifest?: boolean;
+  manifest_signing_key?: string;
+  issuer_cert_path?: string;
+  manifest_key_password?: string;
+  key_passphrase?: string;
+  challenge_password?: string;
+  key_usage?: string;
+  extended_key_usage?: string;
+  certificate_template?: string;
+  extensions?: CustomExtension[];
+  key_store?: string;
+  existing_key_dir?: string;
+  continue_on_error?: boolean;
+  error_report?: boolean;
+  on_complete_command?: string;
+  webhook_url?: string;
+  job_id?: string;
+}
+
+// 进度事件接口
+interface ProgressEvent {
+  job_id: string;
+  done: number;
+  total: number;
+  current_cn: string;
+  eta_ms: number;
+}
+
+// 生成失败的条目
+interface ItemFailure {
+  index: number;
+  cn: string;
+  error: BatchError;
 }
 
 // 生成结果接口
@@ -54,24 +236,946 @@ interface GenerateResult {
   success: boolean;
   message: string;
   total: number;
+  failed: number;
+  failures: ItemFailure[];
+  output_path: string;
+  error_report_path: string;
+  manifest_path: string;
+  warnings: string[];
+  cancelled: boolean;
+}
+
+// 通用名称预览结果接口
+interface CnPreview {
+  total: number;
+  head: string[];
+  tail: string[];
+  first_subject: string;
+}
+
+// 按OID追加的自定义扩展接口
+interface CustomExtension {
+  oid: string;
+  critical: boolean;
+  value: string;
+}
+
+// 证书配置模板接口
+interface Profile {
+  id: string;
+  name: string;
+  description: string;
+  key_type: string;
+  sign_hash_alg: string;
+  key_usage: string;
+  extended_key_usage: string;
+  certificate_template?: string;
+  extensions?: CustomExtension[];
+  sans: string;
+}
+
+// 已保存的任务配置接口
+interface PresetInfo {
+  name: string;
+  saved_at: string;
+}
+
+// CSR检查结果接口
+interface CsrInfo {
+  subject: string;
+  cn: string;
+  sans: string[];
+  key_type: string;
+  key_bits: number;
+  signature_algorithm: string;
+  signature_valid: boolean;
+  extensions: { name: string; oid: string; critical: boolean; value: string }[];
+  public_key_sha256: string;
+  subject_key_id: string;
+}
+
+// 批量校验参数接口
+interface VerifyParams {
+  output_path: string;
+  key_passphrase?: string;
+  csv_delimiter?: string;
+  csv_encoding?: string;
+}
+
+// 任务历史概要接口
+interface JobSummary {
+  id: string;
+  started_at: string;
+  finished_at: string;
+  duration_ms: number;
+  user: string;
+  state: "completed" | "cancelled" | "failed";
+  total: number;
+  failed: number;
+  output_path: string;
+  message: string;
+}
+
+// 任务历史详情接口
+interface JobRecord extends JobSummary {
+  params: GenerateParams;
+  failures: { index: number; cn: string; error: string }[];
+}
+
+// 即将到期的证书接口
+interface ExpiringCertificate {
+  fingerprint: string;
+  serial: string;
+  cn: string;
+  subject: string;
+  issuer: string;
+  not_before: string;
+  not_after: string;
+  days_left: number;
+  source_path: string;
+}
+
+// 审计日志校验报告接口
+interface AuditLogReport {
+  success: boolean;
+  message: string;
+  entries: number;
+  last_hash: string;
+  broken_line: number;
+}
+
+// 批量校验报告接口
+interface VerifyReport {
+  success: boolean;
+  message: string;
+  total: number;
+  keys_checked: number;
+  failures: { record: number; cn: string; message: string }[];
+}
+
+// 预览时首尾各显示的CN数量
+const PREVIEW_LIMIT = 5;
+
+// 任务状态接口
+interface JobStatus {
+  job_id: string;
+  state: "running" | "completed" | "cancelled" | "paused" | "failed";
+  progress: ProgressEvent | null;
+  result: GenerateResult | null;
+  error: BatchError | null;
+}
+
+// 任务断点接口
+interface Checkpoint {
+  job_id: string;
+  saved_at: string;
+  params: GenerateParams;
+  csv_path: string;
+  offset: number;
+  written: number;
+  total: number;
+}
+
+// 队列中的任务接口
+interface QueuedJob {
+  job_id: string;
+  name: string;
+  output_path: string;
+  paused: boolean;
+}
+
+// 任务队列状态接口
+interface QueueStatus {
+  concurrency: number;
+  running: QueuedJob[];
+  pending: QueuedJob[];
+}
+
+// 后端结构化错误，按 kind 区分
+type BatchError =
+  | { kind: "invalid_cn_range"; input: string }
+  | { kind: "unsupported_key_type"; key_type: string }
+  | { kind: "invalid_parameter"; field: string; message: string }
+  | { kind: "no_items" }
+  | { kind: "invalid_input"; row: number; message: string }
+  | { kind: "invalid_record"; index: number; message: string }
+  | { kind: "invalid_subject"; cn: string; message: string }
+  | { kind: "io"; path: string; message: string }
+  | { kind: "openssl"; context: string; message: string }
+  | { kind: "item"; index: number; cn: string; source: BatchError }
+  | { kind: "history_conflict"; count: number; example: string }
+  | { kind: "other"; message: string };
+
+// 内置CA签发参数接口
+interface SignParams {
+  input_path: string;
+  output_path?: string;
+  ca_cert_path: string;
+  ca_key_path?: string;
+  ca_password?: string;
+  validity_days?: number;
+  serial_policy?: string;
+  serial_start?: number;
+  sign_hash_alg?: string;
+  allow_legacy_algorithms?: boolean;
+}
+
+// 内置CA签发结果接口
+interface SignResult {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+}
+
+// ACME签发参数接口
+interface AcmeParams {
+  input_path: string;
+  output_path?: string;
+  overwrite?: boolean;
+  directory_url: string;
+  server_ca_cert_path?: string;
+  account_key_path: string;
+  contact_email?: string;
+  eab_kid?: string;
+  eab_hmac_key?: string;
+  dns_provider: string;
+  dns_command?: string;
+  dns_webhook_url?: string;
+  propagation_seconds?: number;
+}
+
+// ACME签发结果接口
+interface AcmeResult {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+}
+
+// EST注册参数接口
+interface EstParams {
+  input_path: string;
+  output_path?: string;
+  overwrite?: boolean;
+  server_url: string;
+  server_ca_cert_path?: string;
+  client_cert_path?: string;
+  client_key_path?: string;
+  client_key_password?: string;
+  username?: string;
+  password?: string;
+  max_retries?: number;
+}
+
+// EST注册结果接口
+interface EstResult {
+  success: boolean;
+  message: string;
+  total: number;
+  retries: number;
+  output_path: string;
+}
+
+// SCEP注册参数接口
+interface ScepParams {
+  input_path: string;
+  output_path?: string;
+  server_url: string;
+  ca_identifier?: string;
+  challenge_password?: string;
+  key_passphrase?: string;
+  server_ca_cert_path?: string;
+  poll_interval_seconds?: number;
+  max_polls?: number;
+  overwrite?: boolean;
+}
+
+// SCEP注册结果接口
+interface ScepResult {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+}
+
+// CMP注册参数接口
+interface CmpParams {
+  input_path: string;
+  output_path?: string;
+  overwrite?: boolean;
+  server_url: string;
+  request_type?: string;
+  protection?: string;
+  reference?: string;
+  shared_secret?: string;
+  client_cert_path?: string;
+  client_key_path?: string;
+  client_key_password?: string;
+  ca_cert_path?: string;
+  key_passphrase?: string;
+  server_ca_cert_path?: string;
+  poll_interval_seconds?: number;
+  max_polls?: number;
+}
+
+// CMP注册结果接口
+interface CmpResult {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+}
+
+// EJBCA注册参数接口
+interface EjbcaParams {
+  input_path: string;
+  output_path?: string;
+  overwrite?: boolean;
+  server_url: string;
+  certificate_profile_name: string;
+  end_entity_profile_name: string;
+  certificate_authority_name: string;
+  username?: string;
+  enrollment_code?: string;
+  email?: string;
+  server_ca_cert_path?: string;
+  client_cert_path?: string;
+  client_key_path?: string;
+  client_key_password?: string;
+  request_interval_ms?: number;
+  max_retries?: number;
+}
+
+// EJBCA注册结果接口
+interface EjbcaResult {
+  success: boolean;
+  message: string;
+  total: number;
+  retries: number;
+  output_path: string;
+}
+
+// AD CS注册参数接口
+interface AdcsParams {
+  input_path: string;
+  output_path?: string;
+  overwrite?: boolean;
+  method?: string;
+  server_url?: string;
+  config?: string;
+  template: string;
+  username?: string;
+  password?: string;
+  server_ca_cert_path?: string;
+}
+
+// AD CS注册结果接口
+interface AdcsResult {
+  success: boolean;
+  message: string;
+  total: number;
+  pending: number;
+  output_path: string;
+}
+
+// Vault签发参数接口
+interface VaultParams {
+  input_path: string;
+  output_path?: string;
+  server_url: string;
+  mount?: string;
+  role: string;
+  auth_method?: string;
+  token?: string;
+  role_id?: string;
+  secret_id?: string;
+  approle_mount?: string;
+  namespace?: string;
+  ttl?: string;
+  server_ca_cert_path?: string;
+  overwrite?: boolean;
+}
+
+// Vault签发结果接口
+interface VaultResult {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+}
+
+// AWS Private CA签发参数接口
+interface PcaParams {
+  input_path: string;
+  output_path?: string;
+  certificate_authority_arn: string;
+  region?: string;
+  access_key_id?: string;
+  secret_access_key?: string;
+  session_token?: string;
+  endpoint_url?: string;
+  signing_algorithm?: string;
+  template_arn?: string;
+  validity_days?: number;
+  max_retries?: number;
+  overwrite?: boolean;
+}
+
+// AWS Private CA签发结果接口
+interface PcaResult {
+  success: boolean;
+  message: string;
+  total: number;
+  retries: number;
+  output_path: string;
+}
+
+// 通用REST CA签发参数接口
+interface WebhookParams {
+  input_path: string;
+  output_path?: string;
+  url: string;
+  method?: string;
+  content_type?: string;
+  body_template: string;
+  headers?: string;
+  auth_method?: string;
+  username?: string;
+  password?: string;
+  token?: string;
+  certificate_path?: string;
+  chain_path?: string;
+  server_ca_cert_path?: string;
+  client_cert_path?: string;
+  client_key_path?: string;
+  client_key_password?: string;
+  max_retries?: number;
+  overwrite?: boolean;
+}
+
+// 通用REST CA签发结果接口
+interface WebhookResult {
+  success: boolean;
+  message: string;
+  total: number;
+  retries: number;
+  output_path: string;
+}
+
+// 证书导入参数接口
+interface ImportParams {
+  input_path: string;
+  source_path: string;
+  output_path?: string;
+  match_by?: string;
+  ca_bundle_path?: string;
+  export_pkcs12?: boolean;
+  pkcs12_password?: string;
+  key_passphrase?: string;
+  overwrite?: boolean;
+}
+
+// 证书导入结果接口
+interface ImportResult {
+  success: boolean;
+  message: string;
+  total: number;
+  matched: number;
+  unmatched: number;
+  certificates: number;
+  output_path: string;
+  pkcs12_dir: string;
+}
+
+// 吊销记录接口
+interface Revocation {
+  serial: string;
+  issuer: string;
+  reason: string;
+  revoked_at: string;
+}
+
+// CRL签发参数接口
+interface CrlParams {
+  ca_cert_path: string;
+  ca_key_path?: string;
+  ca_password?: string;
+  sign_hash_alg?: string;
+  next_update_days?: number;
+  format?: string;
+  output_path: string;
+}
+
+// CRL签发结果接口
+interface CrlResult {
+  success: boolean;
+  message: string;
+  total: number;
+  crl_number: number;
+  next_update: string;
+  output_path: string;
+}
+
+// PKCS#12导出参数接口
+interface Pkcs12Params {
+  input_path: string;
+  password: string;
+  key_passphrase?: string;
+  chain_path?: string;
+}
+
+// PKCS#12导出结果接口
+interface Pkcs12Result {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+  output_dir: string;
+}
+
+// YubiKey PIV制卡参数接口
+interface PivParams {
+  cn_range: string;
+  range_radix: number;
+  subject_template: string;
+  key_type: string;
+  sign_hash_alg: string;
+  slot: string;
+  pin: string;
+  management_key?: string;
+  touch_policy?: string;
+  output_path: string;
+}
+
+// YubiKey PIV制卡结果接口
+interface PivResult {
+  success: boolean;
+  message: string;
+  total: number;
+  output_path: string;
+}
+
+// 证书续期参数接口
+interface RenewParams {
+  cert_path: string;
+  key_dir?: string;
+  rotate_key?: boolean;
+  key_type?: string;
+  sign_hash_alg?: string;
+  key_passphrase?: string;
   output_path: string;
 }
 
+// PIV支持的密钥类型
+const PIV_KEY_TYPES = ["RSA_2048", "RSA_3072", "RSA_4096", "EC_P256", "EC_P384", "ED25519"];
+
+// PIV槽位
+const PIV_SLOTS = [
+  { value: "9a", label: "9a - 身份认证" },
+  { value: "9c", label: "9c - 数字签名" },
+  { value: "9d", label: "9d - 密钥管理" },
+  { value: "9e", label: "9e - 卡认证" },
+];
+
+// PIV触摸策略
+const PIV_TOUCH_POLICIES = [
+  { value: "", label: "设备默认" },
+  { value: "never", label: "从不" },
+  { value: "always", label: "每次" },
+  { value: "cached", label: "15秒内缓存" },
+];
+
+// 证书序列号策略
+const SERIAL_POLICIES = [
+  { value: "random", label: "随机" },
+  { value: "sequential", label: "顺序递增" },
+];
+
+// 证书吊销原因（RFC 5280）
+const REVOKE_REASONS = [
+  { value: "unspecified", label: "未指定" },
+  { value: "keyCompromise", label: "私钥泄露" },
+  { value: "cACompromise", label: "CA私钥泄露" },
+  { value: "affiliationChanged", label: "隶属关系变更" },
+  { value: "superseded", label: "已被取代" },
+  { value: "cessationOfOperation", label: "停止使用" },
+  { value: "certificateHold", label: "暂时冻结" },
+  { value: "privilegeWithdrawn", label: "权限撤销" },
+];
+
+// ACME DNS-01验证的DNS服务商
+const DNS_PROVIDERS = [
+  { value: "command", label: "执行命令" },
+  { value: "webhook", label: "Webhook" },
+];
+
+// 表单停止编辑后多久开始预生成密钥（毫秒）
+const KEY_POOL_DEBOUNCE_MS = 800;
+
+// 估算通用名称数量：逗号或换行分隔（方括号列表内的逗号除外），YDL0001-YDL0010 格式的片段按范围计数，
+// ABC-{0001..0500}-CN 和 [SHA, PEK]0001-0100 格式的片段按各计数器长度（均按 step 步长）和列表项数相乘，其余片段各计一个
+function countCnRange(range: string, radix: number): number {
+  const digits = radix === 16 ? "[0-9A-Fa-f]*" : radix === 36 ? "[0-9A-Za-z]*" : "\\d*";
+  const rangePattern = new RegExp(`^([A-Za-z]+)(\\d${digits})-([A-Za-z]+)(\\d${digits})$`);
+  const counterPattern = new RegExp(`\\{(\\d${digits})\\.\\.(\\d${digits})\\}|\\[([^\\[\\]]*)\\]`, "g");
+  const prefixesPattern = new RegExp(`^(\\[[^\\[\\]]*\\])(\\d${digits})-(\\d${digits})$`);
+  return range
+    .split(/[,\n](?![^[]*\])/)
+    .map((segment) => segment.trim())
+    .filter((segment) => segment)
+    .reduce((count, segment) => {
+      const step = parseInt(segment.match(/\sstep\s+(\d+)/)?.[1] ?? "1") || 1;
+      const span = (start: string, end: string) =>
+        Math.floor(Math.abs(parseInt(end, radix) - parseInt(start, radix)) / step) + 1;
+      const body = segment
+        .replace(/(\s+(step|pad)\s+\d+)+$/, "")
+        .replace(prefixesPattern, "$1{$2..$3}");
+      const counters = [...body.matchAll(counterPattern)];
+      if (counters.length > 0) {
+        return count + counters.reduce((product, match) => {
+          if (match[3] === undefined) return product * span(match[1], match[2]);
+          return product * match[3].split(",").filter((value) => value.trim()).length;
+        }, 1);
+      }
+      const match = body.match(rangePattern);
+      return count + (match ? span(match[2], match[4]) : 1);
+    }, 0);
+}
+
+// 估算MAC地址数量：每个范围去掉分隔符后为24位十六进制数字，单个地址为12位
+function countMacRange(range: string): number {
+  return range
+    .split(/[,\n]/)
+    .map((segment) => segment.replace(/[^0-9A-Fa-f]/g, ""))
+    .filter((digits) => digits)
+    .reduce((count, digits) => {
+      if (digits.length !== 24) return count + 1;
+      return count + Math.max(parseInt(digits.slice(12), 16) - parseInt(digits.slice(0, 12), 16) + 1, 0);
+    }, 0);
+}
+
+// 解析自定义扩展：每行一个，格式同 openssl req -addext，即 OID=[critical,]值；值的格式由后端校验
+function parseCustomExtensions(text: string): CustomExtension[] {
+  return text
+    .split("\n")
+    .map((line) => line.trim())
+    .filter((line) => line)
+    .map((line) => {
+      const [oid, ...rest] = line.split("=");
+      const value = rest.join("=").trim();
+      const critical = value.startsWith("critical,");
+      return { oid: oid.trim(), critical, value: critical ? value.slice("critical,".length).trim() : value };
+    });
+}
+
+// 自定义扩展转换为每行一个的文本
+function formatCustomExtensions(extensions: CustomExtension[]): string {
+  return extensions.map((ext) => `${ext.oid}=${ext.critical ? "critical," : ""}${ext.value}`).join("\n");
+}
+
+// 将后端错误转换为可读的提示信息
+function formatBatchError(error: BatchError): string {
+  switch (error.kind) {
+    case "invalid_cn_range":
+      return `通用名称范围格式错误: ${error.input}，请按 YDL0001-YDL0010 的格式填写，多个范围以逗号或换行分隔`;
+    case "unsupported_key_type":
+      return `不支持的密钥类型: ${error.key_type}`;
+    case "invalid_parameter":
+      return `参数 ${error.field} 无效: ${error.message}`;
+    case "no_items":
+      return "没有需要生成的条目，请检查通用名称范围或输入CSV";
+    case "invalid_input":
+      return `输入CSV第 ${error.row} 行有误: ${error.message}`;
+    case "invalid_record":
+      return `第 ${error.index} 条记录有误: ${error.message}`;
+    case "invalid_subject":
+      return `${error.cn} 的Subject无效，请检查Subject模板: ${error.message}`;
+    case "io":
+      return `无法读写文件 ${error.path}: ${error.message}`;
+    case "openssl":
+      return `${error.context}: ${error.message}`;
+    case "item":
+      return `第 ${error.index + 1} 个条目（${error.cn}）生成失败: ${formatBatchError(error.source)}`;
+    case "history_conflict":
+      return `${error.count} 个条目与历史批次重叠（如 ${error.example}），已拒绝生成`;
+    case "other":
+      return error.message;
+  }
+}
+
+// 提取异常中的错误信息，兼容后端结构化错误
+function errorMessage(error: unknown): string {
+  if (error && typeof error === "object" && "kind" in error) {
+    return formatBatchError(error as BatchError);
+  }
+  return error instanceof Error ? error.message : String(error);
+}
+
 
 function App() {
   // 表单状态
   const [form] = Form.useForm();
   const [cnRange, setCnRange] = useState("YDL0001-YDL0010");
+  const [rangeRadix, setRangeRadix] = useState(10);
+  const [cnMode, setCnMode] = useState("range");
+  const [randomCount, setRandomCount] = useState(100);
+  const [hexLength, setHexLength] = useState(16);
+  const [macRange, setMacRange] = useState("");
+  const [macSeparator, setMacSeparator] = useState("colon");
+  const [macCase, setMacCase] = useState("upper");
+  const [inputCsvPath, setInputCsvPath] = useState("");
+  const [exclude, setExclude] = useState("");
+  const [excludePath, setExcludePath] = useState("");
   const [subjectTemplate, setSubjectTemplate] = useState(
-    "CN=[{CN}]; O=[TrustAsia Technologies\\\\, Inc.]; OU=[部门1]"
+    "CN=[{CN}]; O=[TrustAsia Technologies\\, Inc.]; OU=[部门1]"
   );
+  const [dnStringTypes, setDnStringTypes] = useState("");
+  const [profiles, setProfiles] = useState<Profile[]>([]);
+  const [profileId, setProfileId] = useState("");
+  const [presets, setPresets] = useState<PresetInfo[]>([]);
+  const [presetName, setPresetName] = useState("");
   const [keyType, setKeyType] = useState("RSA_2048");
+  const [keyTypeMap, setKeyTypeMap] = useState("");
   const [signHashAlg, setSignHashAlg] = useState("SHA256");
+  const [issuerCertPath, setIssuerCertPath] = useState("");
+  const [allowLegacyAlgorithms, setAllowLegacyAlgorithms] = useState(false);
+  const [pqKeyType, setPqKeyType] = useState("");
+  const [signatureScheme, setSignatureScheme] = useState("pkcs1");
+  const [pssSaltLength, setPssSaltLength] = useState<number | null>(null);
+  const [pssMgf1Hash, setPssMgf1Hash] = useState("");
   const [notBefore, setNotBefore] = useState(dayjs());
   const [notAfter, setNotAfter] = useState(dayjs().add(10, 'year'));
+  const [validity, setValidity] = useState("");
+  const [notAfterOffset, setNotAfterOffset] = useState("");
   const [uniqueId, setUniqueId] = useState("");
+  const [uniqueIdSerialNumber, setUniqueIdSerialNumber] = useState(false);
   const [sans, setSans] = useState("");
+  const [idnKeepUtf8, setIdnKeepUtf8] = useState(false);
+  const [keyPassphrase, setKeyPassphrase] = useState("");
+  const [challengePassword, setChallengePassword] = useState("");
+  const [keyUsage, setKeyUsage] = useState("");
+  const [extendedKeyUsage, setExtendedKeyUsage] = useState("");
+  const [certificateTemplate, setCertificateTemplate] = useState("");
+  const [customExtensions, setCustomExtensions] = useState("");
+  const [keyStore, setKeyStore] = useState("file");
+  const [existingKeyDir, setExistingKeyDir] = useState("");
+  const [continueOnError, setContinueOnError] = useState(false);
+  const [errorReport, setErrorReport] = useState(true);
+  const [onCompleteCommand, setOnCompleteCommand] = useState("");
+  const [webhookUrl, setWebhookUrl] = useState("");
+  const [screenWeakKeys, setScreenWeakKeys] = useState(false);
+  const [weakKeyBlocklist, setWeakKeyBlocklist] = useState("");
   const [outputDir, setOutputDir] = useState("");
+  const [outputMode, setOutputMode] = useState("csv");
+  const [fileNameTemplate, setFileNameTemplate] = useState("{CN}");
+  const [zipPassword, setZipPassword] = useState("");
+  const [splitKeys, setSplitKeys] = useState(false);
+  const [sqlitePath, setSqlitePath] = useState("");
+  const [historyDbPath, setHistoryDbPath] = useState("");
+  const [historyConflict, setHistoryConflict] = useState("warn");
+  const [csvDelimiter, setCsvDelimiter] = useState(",");
+  const [csvQuoteStyle, setCsvQuoteStyle] = useState("necessary");
+  const [csvLineEnding, setCsvLineEnding] = useState("lf");
+  const [csvEncoding, setCsvEncoding] = useState("utf-8");
+  const [csvBom, setCsvBom] = useState(false);
+  const [maxRowsPerFile, setMaxRowsPerFile] = useState(0);
+  const [appendPath, setAppendPath] = useState("");
+  const [columns, setColumns] = useState("");
+  const [csrEncoding, setCsrEncoding] = useState("pem");
+  const [fingerprintColumns, setFingerprintColumns] = useState(false);
+  const [writeManifest, setWriteManifest] = useState(false);
+  const [manifestSigningKey, setManifestSigningKey] = useState("");
+  const [manifestKeyPassword, setManifestKeyPassword] = useState("");
+
+  // 内置CA签发状态
+  const [signInputPath, setSignInputPath] = useState("");
+  const [caCertPath, setCaCertPath] = useState("");
+  const [caKeyPath, setCaKeyPath] = useState("");
+  const [caPassword, setCaPassword] = useState("");
+  const [validityDays, setValidityDays] = useState(0);
+  const [serialPolicy, setSerialPolicy] = useState("random");
+  const [serialStart, setSerialStart] = useState(1);
+  const [isSigning, setIsSigning] = useState(false);
+  const [revokeSerial, setRevokeSerial] = useState("");
+  const [revokeReason, setRevokeReason] = useState("unspecified");
+  const [crlNextUpdateDays, setCrlNextUpdateDays] = useState(7);
+  const [crlFormat, setCrlFormat] = useState("PEM");
+  const [isIssuingCrl, setIsIssuingCrl] = useState(false);
+
+  // ACME签发状态
+  const [acmeInputPath, setAcmeInputPath] = useState("");
+  const [acmeDirectoryUrl, setAcmeDirectoryUrl] = useState("");
+  const [acmeAccountKeyPath, setAcmeAccountKeyPath] = useState("");
+  const [acmeEmail, setAcmeEmail] = useState("");
+  const [acmeEabKid, setAcmeEabKid] = useState("");
+  const [acmeEabHmacKey, setAcmeEabHmacKey] = useState("");
+  const [acmeServerCaPath, setAcmeServerCaPath] = useState("");
+  const [dnsProvider, setDnsProvider] = useState("command");
+  const [dnsCommand, setDnsCommand] = useState("");
+  const [dnsWebhookUrl, setDnsWebhookUrl] = useState("");
+  const [propagationSeconds, setPropagationSeconds] = useState(60);
+  const [acmeOverwrite, setAcmeOverwrite] = useState(false);
+  const [isAcmeIssuing, setIsAcmeIssuing] = useState(false);
+
+  // EST注册状态
+  const [estInputPath, setEstInputPath] = useState("");
+  const [estServerUrl, setEstServerUrl] = useState("");
+  const [estServerCaPath, setEstServerCaPath] = useState("");
+  const [estClientCertPath, setEstClientCertPath] = useState("");
+  const [estClientKeyPath, setEstClientKeyPath] = useState("");
+  const [estClientKeyPassword, setEstClientKeyPassword] = useState("");
+  const [estUsername, setEstUsername] = useState("");
+  const [estPassword, setEstPassword] = useState("");
+  const [estMaxRetries, setEstMaxRetries] = useState(3);
+  const [estOverwrite, setEstOverwrite] = useState(false);
+  const [isEnrolling, setIsEnrolling] = useState(false);
+
+  // SCEP注册状态
+  const [scepInputPath, setScepInputPath] = useState("");
+  const [scepServerUrl, setScepServerUrl] = useState("");
+  const [scepCaIdentifier, setScepCaIdentifier] = useState("");
+  const [scepChallengePassword, setScepChallengePassword] = useState("");
+  const [scepServerCaPath, setScepServerCaPath] = useState("");
+  const [scepPollInterval, setScepPollInterval] = useState(10);
+  const [scepMaxPolls, setScepMaxPolls] = useState(30);
+  const [scepOverwrite, setScepOverwrite] = useState(false);
+  const [isScepEnrolling, setIsScepEnrolling] = useState(false);
+  // CMP注册状态
+  const [cmpInputPath, setCmpInputPath] = useState("");
+  const [cmpServerUrl, setCmpServerUrl] = useState("");
+  const [cmpRequestType, setCmpRequestType] = useState("p10cr");
+  const [cmpProtection, setCmpProtection] = useState("mac");
+  const [cmpReference, setCmpReference] = useState("");
+  const [cmpSharedSecret, setCmpSharedSecret] = useState("");
+  const [cmpClientCertPath, setCmpClientCertPath] = useState("");
+  const [cmpClientKeyPath, setCmpClientKeyPath] = useState("");
+  const [cmpClientKeyPassword, setCmpClientKeyPassword] = useState("");
+  const [cmpCaCertPath, setCmpCaCertPath] = useState("");
+  const [cmpServerCaPath, setCmpServerCaPath] = useState("");
+  const [cmpPollInterval, setCmpPollInterval] = useState(10);
+  const [cmpMaxPolls, setCmpMaxPolls] = useState(30);
+  const [cmpOverwrite, setCmpOverwrite] = useState(false);
+  const [isCmpEnrolling, setIsCmpEnrolling] = useState(false);
+  // EJBCA注册状态
+  const [ejbcaInputPath, setEjbcaInputPath] = useState("");
+  const [ejbcaServerUrl, setEjbcaServerUrl] = useState("");
+  const [ejbcaCaName, setEjbcaCaName] = useState("");
+  const [ejbcaCertificateProfile, setEjbcaCertificateProfile] = useState("");
+  const [ejbcaEndEntityProfile, setEjbcaEndEntityProfile] = useState("");
+  const [ejbcaUsername, setEjbcaUsername] = useState("{CN}");
+  const [ejbcaEnrollmentCode, setEjbcaEnrollmentCode] = useState("");
+  const [ejbcaEmail, setEjbcaEmail] = useState("");
+  const [ejbcaClientCertPath, setEjbcaClientCertPath] = useState("");
+  const [ejbcaClientKeyPath, setEjbcaClientKeyPath] = useState("");
+  const [ejbcaClientKeyPassword, setEjbcaClientKeyPassword] = useState("");
+  const [ejbcaServerCaPath, setEjbcaServerCaPath] = useState("");
+  const [ejbcaRequestInterval, setEjbcaRequestInterval] = useState(0);
+  const [ejbcaMaxRetries, setEjbcaMaxRetries] = useState(3);
+  const [ejbcaOverwrite, setEjbcaOverwrite] = useState(false);
+  const [isEjbcaEnrolling, setIsEjbcaEnrolling] = useState(false);
+  // AD CS注册状态
+  const [adcsInputPath, setAdcsInputPath] = useState("");
+  const [adcsMethod, setAdcsMethod] = useState("web");
+  const [adcsServerUrl, setAdcsServerUrl] = useState("");
+  const [adcsConfig, setAdcsConfig] = useState("");
+  const [adcsTemplate, setAdcsTemplate] = useState("");
+  const [adcsUsername, setAdcsUsername] = useState("");
+  const [adcsPassword, setAdcsPassword] = useState("");
+  const [adcsServerCaPath, setAdcsServerCaPath] = useState("");
+  const [adcsOverwrite, setAdcsOverwrite] = useState(false);
+  const [isAdcsEnrolling, setIsAdcsEnrolling] = useState(false);
+  // Vault签发状态
+  const [vaultInputPath, setVaultInputPath] = useState("");
+  const [vaultServerUrl, setVaultServerUrl] = useState("");
+  const [vaultMount, setVaultMount] = useState("pki");
+  const [vaultRole, setVaultRole] = useState("");
+  const [vaultTtl, setVaultTtl] = useState("");
+  const [vaultAuthMethod, setVaultAuthMethod] = useState("token");
+  const [vaultToken, setVaultToken] = useState("");
+  const [vaultRoleId, setVaultRoleId] = useState("");
+  const [vaultSecretId, setVaultSecretId] = useState("");
+  const [vaultNamespace, setVaultNamespace] = useState("");
+  const [vaultServerCaPath, setVaultServerCaPath] = useState("");
+  const [vaultOverwrite, setVaultOverwrite] = useState(false);
+  const [isVaultSigning, setIsVaultSigning] = useState(false);
+  // AWS Private CA签发状态
+  const [pcaInputPath, setPcaInputPath] = useState("");
+  const [pcaCaArn, setPcaCaArn] = useState("");
+  const [pcaRegion, setPcaRegion] = useState("");
+  const [pcaAccessKeyId, setPcaAccessKeyId] = useState("");
+  const [pcaSecretAccessKey, setPcaSecretAccessKey] = useState("");
+  const [pcaSessionToken, setPcaSessionToken] = useState("");
+  const [pcaSigningAlgorithm, setPcaSigningAlgorithm] = useState("SHA256WITHRSA");
+  const [pcaValidityDays, setPcaValidityDays] = useState(365);
+  const [pcaTemplateArn, setPcaTemplateArn] = useState("");
+  const [pcaEndpointUrl, setPcaEndpointUrl] = useState("");
+  const [pcaOverwrite, setPcaOverwrite] = useState(false);
+  const [isPcaIssuing, setIsPcaIssuing] = useState(false);
+  // 通用REST CA签发状态
+  const [webhookInputPath, setWebhookInputPath] = useState("");
+  const [webhookUrl, setWebhookUrl] = useState("");
+  const [webhookMethod, setWebhookMethod] = useState("POST");
+  const [webhookContentType, setWebhookContentType] = useState("application/json");
+  const [webhookBodyTemplate, setWebhookBodyTemplate] = useState("");
+  const [webhookHeaders, setWebhookHeaders] = useState("");
+  const [webhookAuthMethod, setWebhookAuthMethod] = useState("none");
+  const [webhookUsername, setWebhookUsername] = useState("");
+  const [webhookPassword, setWebhookPassword] = useState("");
+  const [webhookToken, setWebhookToken] = useState("");
+  const [webhookCertificatePath, setWebhookCertificatePath] = useState("");
+  const [webhookChainPath, setWebhookChainPath] = useState("");
+  const [webhookServerCaPath, setWebhookServerCaPath] = useState("");
+  const [webhookOverwrite, setWebhookOverwrite] = useState(false);
+  const [isWebhookEnrolling, setIsWebhookEnrolling] = useState(false);
+  // 证书导入状态
+  const [importInputPath, setImportInputPath] = useState("");
+  const [importSourcePath, setImportSourcePath] = useState("");
+  const [importMatchBy, setImportMatchBy] = useState("auto");
+  const [importCaBundlePath, setImportCaBundlePath] = useState("");
+  const [importExportPkcs12, setImportExportPkcs12] = useState(false);
+  const [importPkcs12Password, setImportPkcs12Password] = useState("");
+  const [importOverwrite, setImportOverwrite] = useState(false);
+  const [isImporting, setIsImporting] = useState(false);
+
+  // PKCS#12导出状态
+  const [p12InputPath, setP12InputPath] = useState("");
+  const [p12Password, setP12Password] = useState("");
+  const [chainPath, setChainPath] = useState("");
+  const [isExporting, setIsExporting] = useState(false);
+
+  // YubiKey PIV制卡状态
+  const [pivSlot, setPivSlot] = useState("9a");
+  const [pivPin, setPivPin] = useState("");
+  const [pivManagementKey, setPivManagementKey] = useState("");
+  const [pivTouchPolicy, setPivTouchPolicy] = useState("");
+  const [isProvisioning, setIsProvisioning] = useState(false);
+
+  // 证书续期状态
+  const [renewCertPath, setRenewCertPath] = useState("");
+  const [renewKeyDir, setRenewKeyDir] = useState("");
+  const [rotateKey, setRotateKey] = useState(false);
+  const [isRenewing, setIsRenewing] = useState(false);
+
+  // 证书到期跟踪
+  const [expiryDays, setExpiryDays] = useState(30);
+  const [expiringCerts, setExpiringCerts] = useState<ExpiringCertificate[]>([]);
+  const [selectedCert, setSelectedCert] = useState<string | undefined>(undefined);
+  const [inspectInput, setInspectInput] = useState("");
+  const [verifyPath, setVerifyPath] = useState("");
+
+  // 任务历史
+  const [jobHistory, setJobHistory] = useState<JobSummary[]>([]);
+  const [selectedJobId, setSelectedJobId] = useState<string | undefined>(undefined);
+  const [checkpoints, setCheckpoints] = useState<Checkpoint[]>([]);
+  const [checkpointJobId, setCheckpointJobId] = useState<string | undefined>(undefined);
+
+  // 任务队列
+  const [queueStatus, setQueueStatus] = useState<QueueStatus>({ concurrency: 1, running: [], pending: [] });
+  const [queueName, setQueueName] = useState("");
+  const [queueProgress, setQueueProgress] = useState<Record<string, string>>({});
+  const queuedJobsRef = useRef<Record<string, string>>({});
+  const [isVerifying, setIsVerifying] = useState(false);
 
   // UI状态
   const [isGenerating, setIsGenerating] = useState(false);
@@ -82,6 +1186,8 @@ function App() {
 
   // 日志区域引用
   const logAreaRef = useRef<HTMLDivElement>(null);
+  // 当前任务ID，用于取消
+  const jobIdRef = useRef<string>("");
 
   /*// 格式化日期时间为本地输入格式
   function formatDateTimeLocal(date: Date): string {
@@ -126,44 +1232,381 @@ function App() {
     }
   }, [logs]);
 
-  // 浏览输出目录
-  async function browseOutputDir() {
-    try {
-      const dirPath = await openDialog({
-        directory: true,
-        multiple: false,
-      });
-      if (dirPath) {
-        setOutputDir(dirPath as string);
-        // 同时更新表单字段的值
-        form.setFieldsValue({ outputDir: dirPath as string });
-      }
-    } catch (error) {
-      console.error("选择目录失败:", error);
-    }
-  }
+  // 监听任务队列：队列状态，以及由本页面加入队列的任务的进度和结束
+  useEffect(() => {
+    invoke<QueueStatus>("list_queue")
+      .then(setQueueStatus)
+      .catch((error) => addLog(`读取任务队列失败: ${errorMessage(error)}`, "error"));
+    const unlisteners = [
+      listen<QueueStatus>("csr-queue", (event) => setQueueStatus(event.payload)),
+      listen<ProgressEvent>("csr-progress", (event) => {
+        const { job_id, done, total } = event.payload;
+        if (!(job_id in queuedJobsRef.current)) return;
+        setQueueProgress((prev) => ({ ...prev, [job_id]: `${done}/${total}` }));
+      }),
+      listen<JobStatus>("csr-complete", (event) => {
+        const status = event.payload;
+        const name = queuedJobsRef.current[status.job_id];
+        if (name === undefined) return;
+        delete queuedJobsRef.current[status.job_id];
+        if (status.state === "failed" || !status.result) {
+          addLog(`队列任务 ${name} 失败: ${errorMessage(status.error)}`, "error");
+        } else {
+          const type = status.state === "completed" ? "success" : "warning";
+          addLog(`队列任务 ${name}: ${status.result.message}，输出文件 ${status.result.output_path}`, type);
+        }
+      }),
+    ];
+    return () => unlisteners.forEach((unlisten) => unlisten.then((f) => f()));
+  }, []);
 
-  // 开始生成
-  async function startGeneration() {
+  // 读取配置模板（应用配置目录下的profiles.json，首次读取时写入内置模板）
+  useEffect(() => {
+    invoke<Profile[]>("list_profiles")
+      .then(setProfiles)
+      .catch((error) => addLog(`读取配置模板失败: ${errorMessage(error)}`, "error"));
+  }, []);
+
+  // 读取已保存的任务配置（应用配置目录下的presets.json）
+  function refreshPresets() {
+    invoke<PresetInfo[]>("list_presets")
+      .then(setPresets)
+      .catch((error) => addLog(`读取任务配置失败: ${errorMessage(error)}`, "error"));
+  }
+
+  useEffect(refreshPresets, []);
+
+  // 读取可继续的任务断点（暂停或程序退出时中断的任务）
+  function refreshCheckpoints() {
+    invoke<Checkpoint[]>("list_checkpoints")
+      .then((list) => {
+        setCheckpoints(list);
+        setCheckpointJobId((current) => list.some((c) => c.job_id === current) ? current : list[0]?.job_id);
+      })
+      .catch((error) => addLog(`读取任务断点失败: ${errorMessage(error)}`, "error"));
+  }
+
+  useEffect(refreshCheckpoints, []);
+
+  // 读取到期天数内（含已过期）的已签发证书
+  function refreshExpiring() {
+    invoke<ExpiringCertificate[]>("list_expiring", { days: expiryDays })
+      .then((list) => {
+        setExpiringCerts(list);
+        setSelectedCert((current) => list.some((c) => c.fingerprint === current) ? current : list[0]?.fingerprint);
+      })
+      .catch((error) => addLog(`读取证书到期信息失败: ${errorMessage(error)}`, "error"));
+  }
+
+  useEffect(refreshExpiring, [expiryDays]);
+
+  // 按名称保存当前表单，私钥口令、ZIP密码等机密参数不保存
+  async function savePreset() {
+    const name = presetName.trim();
+    if (!name) {
+      message.error("请输入任务配置名称！");
+      return;
+    }
+    const append = appendPath.trim() !== "" && ["csv", "both"].includes(outputMode);
+    const outputPath = append ? appendPath.trim() : `${outputDir}/csr_batch.csv`;
     try {
-      // 使用 Ant Design 表单验证
-      await form.validateFields();
+      await invoke("save_preset", { name, params: buildParams(outputPath, append) });
+      addLog(`已保存任务配置: ${name}（不含私钥口令、ZIP密码等机密参数）`, "success");
+      refreshPresets();
     } catch (error) {
-      message.error("请检查表单输入！");
-      return;
+      addLog(`保存任务配置失败: ${errorMessage(error)}`, "error");
     }
+  }
 
-    // 验证输入
-    if (!cnRange.trim()) {
-      message.error("请输入通用名称范围！");
+  // 载入任务配置并填入表单；有效期开始时间改为当前时间，未设置有效期时长时保持原有的时长
+  async function loadPreset(name: string) {
+    try {
+      const params = await invoke<GenerateParams>("load_preset", { name });
+      applyParams(params);
+      setPresetName(name);
+      addLog(`已载入任务配置: ${name}，私钥口令等机密参数需重新填写`, "success");
+    } catch (error) {
+      addLog(`载入任务配置失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 按生成参数设置表单
+  function applyParams(params: GenerateParams) {
+    const start = dayjs();
+    const savedStart = dayjs(params.not_before);
+    const savedEnd = dayjs(params.not_after);
+    const end = params.not_after && savedStart.isValid() && savedEnd.isValid()
+      ? start.add(savedEnd.diff(savedStart), 'millisecond')
+      : notAfter;
+    const dir = params.output_path.replace(/[\\/][^\\/]*$/, "");
+
+    setCnRange(params.cn_range);
+    setRangeRadix(params.range_radix || 10);
+    setCnMode(params.cn_mode || "range");
+    setRandomCount(params.count || 100);
+    setHexLength(params.hex_length || 16);
+    setMacRange(params.mac_range ?? "");
+    setMacSeparator(params.mac_separator || "colon");
+    setMacCase(params.mac_case || "upper");
+    setInputCsvPath(params.input_csv_path ?? "");
+    setExclude(params.exclude ?? "");
+    setExcludePath(params.exclude_path ?? "");
+    setSubjectTemplate(params.subject_template);
+    setDnStringTypes(params.dn_string_types ?? "");
+    setProfileId("");
+    setKeyType(params.key_type || "RSA_2048");
+    setKeyTypeMap(params.key_type_map ?? "");
+    setSignHashAlg(params.sign_hash_alg || "SHA256");
+    setIssuerCertPath(params.issuer_cert_path ?? "");
+    setPqKeyType(params.pq_key_type ?? "");
+    setSignatureScheme(params.signature_scheme || "pkcs1");
+    setPssSaltLength(params.pss_salt_length ?? null);
+    setPssMgf1Hash(params.pss_mgf1_hash ?? "");
+    setAllowLegacyAlgorithms(!!params.allow_legacy_algorithms);
+    setNotBefore(start);
+    setNotAfter(end);
+    setValidity(params.validity ?? "");
+    setNotAfterOffset(params.not_after_offset ?? "");
+    setUniqueId(params.unique_id);
+    setUniqueIdSerialNumber(!!params.unique_id_serial_number);
+    setSans(params.sans);
+    setIdnKeepUtf8(!!params.idn_keep_utf8);
+    setKeyUsage(params.key_usage ?? "");
+    setExtendedKeyUsage(params.extended_key_usage ?? "");
+    setCertificateTemplate(params.certificate_template ?? "");
+    setCustomExtensions(formatCustomExtensions(params.extensions ?? []));
+    setKeyStore(params.key_store || "file");
+    setExistingKeyDir(params.existing_key_dir ?? "");
+    setContinueOnError(!!params.continue_on_error);
+    setErrorReport(params.continue_on_error ? !!params.error_report : true);
+    setOnCompleteCommand(params.on_complete_command ?? "");
+    setWebhookUrl(params.webhook_url ?? "");
+    setScreenWeakKeys(!!params.screen_weak_keys);
+    setWeakKeyBlocklist(params.weak_key_blocklist ?? "");
+    setOutputMode(params.output_mode || "csv");
+    setFileNameTemplate(params.file_name_template || "{CN}");
+    setSplitKeys(!!params.split_keys);
+    setSqlitePath(params.sqlite_path ?? "");
+    setHistoryDbPath(params.history_db_path ?? "");
+    setHistoryConflict(params.history_conflict || "warn");
+    setCsvDelimiter(params.csv_delimiter || ",");
+    setCsvQuoteStyle(params.csv_quote_style || "necessary");
+    setCsvLineEnding(params.csv_line_ending || "lf");
+    setCsvEncoding(params.csv_encoding || "utf-8");
+    setCsvBom(!!params.csv_bom);
+    setMaxRowsPerFile(params.max_rows_per_file ?? 0);
+    setAppendPath(params.append ? params.output_path : "");
+    setColumns(params.columns ?? "");
+    setCsrEncoding(params.csr_encoding || "pem");
+    setFingerprintColumns(!!params.fingerprint_columns);
+    setWriteManifest(!!params.manifest);
+    setManifestSigningKey(params.manifest_signing_key ?? "");
+    if (!params.append) setOutputDir(dir);
+    form.setFieldsValue({
+      cnRange: params.cn_range,
+      subjectTemplate: params.subject_template,
+      keyType: params.key_type || "RSA_2048",
+      signHashAlg: params.sign_hash_alg || "SHA256",
+      notBefore: start,
+      notAfter: end,
+      uniqueId: params.unique_id,
+      sans: params.sans,
+      ...(params.append ? {} : { outputDir: dir }),
+    });
+  }
+
+  // 按配置模板预填密钥类型、签名哈希算法、密钥用途和备用名称，模板中为空的字段保持不变
+  function applyProfile(id?: string) {
+    setProfileId(id ?? "");
+    const profile = profiles.find((p) => p.id === id);
+    if (!profile) return;
+    if (profile.key_type) setKeyType(profile.key_type);
+    if (profile.sign_hash_alg) setSignHashAlg(profile.sign_hash_alg);
+    if (profile.key_usage) setKeyUsage(profile.key_usage);
+    if (profile.extended_key_usage) setExtendedKeyUsage(profile.extended_key_usage);
+    if (profile.certificate_template) setCertificateTemplate(profile.certificate_template);
+    if (profile.extensions?.length) setCustomExtensions(formatCustomExtensions(profile.extensions));
+    if (profile.sans) setSans(profile.sans);
+    form.setFieldsValue({
+      keyType: profile.key_type || keyType,
+      signHashAlg: profile.sign_hash_alg || signHashAlg,
+      sans: profile.sans || sans,
+    });
+    addLog(`已应用配置模板: ${profile.name}`);
+  }
+
+  // 填写表单时在后台按密钥类型和条目数预生成密钥，开始生成时优先取用
+  useEffect(() => {
+    const count = inputCsvPath.trim()
+      ? 0
+      : cnMode !== "range"
+        ? randomCount
+        : macRange.trim()
+        ? countMacRange(macRange)
+        : countCnRange(cnRange, rangeRadix);
+    if (keyStore !== "file" || existingKeyDir.trim() || count === 0) return;
+    const timer = setTimeout(() => {
+      invoke("prefill_key_pool", { keyType, count }).catch((error) => {
+        console.error("预生成密钥失败:", error);
+      });
+    }, KEY_POOL_DEBOUNCE_MS);
+    return () => clearTimeout(timer);
+  }, [keyType, cnMode, randomCount, cnRange, rangeRadix, macRange, inputCsvPath, keyStore, existingKeyDir]);
+
+  // 浏览输出目录
+  async function browseOutputDir() {
+    try {
+      const dirPath = await openDialog({
+        directory: true,
+        multiple: false,
+      });
+      if (dirPath) {
+        setOutputDir(dirPath as string);
+        // 同时更新表单字段的值
+        form.setFieldsValue({ outputDir: dirPath as string });
+      }
+    } catch (error) {
+      console.error("选择目录失败:", error);
+    }
+  }
+
+  // 浏览已有私钥目录
+  async function browseExistingKeyDir() {
+    try {
+      const dirPath = await openDialog({
+        directory: true,
+        multiple: false,
+      });
+      if (dirPath) {
+        setExistingKeyDir(dirPath as string);
+      }
+    } catch (error) {
+      console.error("选择目录失败:", error);
+    }
+  }
+
+  // 浏览排除列表文件
+  async function browseExcludeFile() {
+    try {
+      const filePath = await openDialog({
+        directory: false,
+        multiple: false,
+        filters: [{ name: "文本文件", extensions: ["txt", "csv"] }],
+      });
+      if (filePath) {
+        setExcludePath(filePath as string);
+      }
+    } catch (error) {
+      console.error("选择文件失败:", error);
+    }
+  }
+
+  // 浏览输入CSV文件
+  async function browseInputCsv() {
+    try {
+      const filePath = await openDialog({
+        directory: false,
+        multiple: false,
+        filters: [{ name: "CSV / Excel", extensions: ["csv", "xlsx", "xlsm", "xls", "ods"] }],
+      });
+      if (filePath) {
+        setInputCsvPath(filePath as string);
+      }
+    } catch (error) {
+      console.error("选择文件失败:", error);
+    }
+  }
+
+  // 浏览文件，返回选择的路径
+  async function browseFile(name: string, extensions: string[]): Promise<string | null> {
+    try {
+      const filePath = await openDialog({
+        directory: false,
+        multiple: false,
+        filters: [{ name, extensions }],
+      });
+      return filePath ? (filePath as string) : null;
+    } catch (error) {
+      console.error("选择文件失败:", error);
+      return null;
+    }
+  }
+
+  // 使用内置CA签发证书
+  async function signCertificates() {
+    if (!signInputPath.trim() || !caCertPath.trim()) {
+      message.error("请选择待签发的CSV文件和CA证书！");
       return;
     }
-    if (!subjectTemplate.trim()) {
-      message.error("请输入Subject主题模板！");
+
+    setIsSigning(true);
+    addLog("");
+    addLog("开始签发证书...");
+    addLog(`待签发CSV: ${signInputPath}`);
+    addLog(`CA证书: ${caCertPath}`);
+    try {
+      const params: SignParams = {
+        input_path: signInputPath.trim(),
+        ca_cert_path: caCertPath.trim(),
+        ca_key_path: caKeyPath.trim(),
+        ca_password: caPassword,
+        validity_days: validityDays,
+        serial_policy: serialPolicy,
+        serial_start: serialStart,
+        sign_hash_alg: signHashAlg,
+        allow_legacy_algorithms: allowLegacyAlgorithms,
+      };
+      const result = await invoke<SignResult>("sign_csr_batch", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`签发失败: ${errorMsg}`, "error");
+      message.error(`签发证书时发生错误: ${errorMsg}`);
+    } finally {
+      setIsSigning(false);
+    }
+  }
+
+  // 吊销证书，记入任务历史数据库，下次生成CRL时列入
+  async function revokeCertificate() {
+    if (!revokeSerial.trim()) {
+      message.error("请填写要吊销的证书序列号！");
       return;
     }
-    if (notBefore.isAfter(notAfter)) {
-      message.error("有效期开始时间不能晚于结束时间！");
+    try {
+      const revocation = await invoke<Revocation>("revoke_certificate", {
+        serial: revokeSerial.trim(),
+        reason: revokeReason,
+      });
+      addLog(`已吊销证书 ${revocation.serial}（${revocation.reason}），吊销时间 ${revocation.revoked_at}`, "success");
+      setRevokeSerial("");
+    } catch (error) {
+      addLog(`吊销证书失败: ${errorMessage(error)}`, "error");
+      message.error(`吊销证书失败: ${errorMessage(error)}`);
+    }
+  }
+
+  // 将吊销记录写入日志
+  async function showRevocations() {
+    try {
+      const revocations = await invoke<Revocation[]>("list_revocations");
+      addLog("");
+      addLog(`吊销记录: 共 ${revocations.length} 条`);
+      for (const revocation of revocations) {
+        addLog(`  ${revocation.serial} ${revocation.reason} ${revocation.revoked_at}${revocation.issuer ? ` 签发者: ${revocation.issuer}` : ""}`);
+      }
+    } catch (error) {
+      addLog(`读取吊销记录失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 使用内置CA签发CRL，写入输出目录
+  async function generateCrl() {
+    if (!caCertPath.trim()) {
+      message.error("请选择CA证书！");
       return;
     }
     if (!outputDir.trim()) {
@@ -171,349 +1614,4820 @@ function App() {
       return;
     }
 
-    // 在选定目录中生成带时间戳的CSV文件
-    const timestamp = getFileTimestamp();
-    const fileName = `csr_batch_${timestamp}.csv`;
-    const finalOutputPath = `${outputDir}/${fileName}`;
+    setIsIssuingCrl(true);
+    addLog("");
+    addLog("开始签发CRL...");
+    addLog(`CA证书: ${caCertPath}`);
+    try {
+      const params: CrlParams = {
+        ca_cert_path: caCertPath.trim(),
+        ca_key_path: caKeyPath.trim(),
+        ca_password: caPassword,
+        sign_hash_alg: signHashAlg,
+        next_update_days: crlNextUpdateDays,
+        format: crlFormat,
+        output_path: `${outputDir}/crl_${getFileTimestamp()}.${crlFormat === "DER" ? "crl" : "pem"}`,
+      };
+      const result = await invoke<CrlResult>("generate_crl", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`签发CRL失败: ${errorMsg}`, "error");
+      message.error(`签发CRL时发生错误: ${errorMsg}`);
+    } finally {
+      setIsIssuingCrl(false);
+    }
+  }
 
-    // 开始生成
-    setIsGenerating(true);
-    setLogs([]);
-    setProgress(0);
-    setProgressText("生成中...");
-    setStatusText("正在生成CSR，请稍候...");
+  // 通过ACME CA签发证书
+  async function issueAcme() {
+    if (!acmeInputPath.trim() || !acmeDirectoryUrl.trim() || !acmeAccountKeyPath.trim()) {
+      message.error("请填写待签发的CSV文件、ACME目录地址和账户私钥！");
+      return;
+    }
 
-    addLog("========================================");
-    addLog("开始批量生成CSR");
-    addLog("========================================");
-    addLog(`通用名称范围: ${cnRange}`);
-    addLog(`密钥类型: ${keyType}`);
-    addLog(`签名哈希算法: ${signHashAlg}`);
-    addLog(`Subject模板: ${subjectTemplate}`);
-    addLog(`notBefore: ${notBefore.format('YYYY-MM-DDTHH:mm:ss+08:00')}`);
-    addLog(`notAfter: ${notAfter.format('YYYY-MM-DDTHH:mm:ss+08:00')}`);
-    if (uniqueId) addLog(`uniqueId: ${uniqueId}`);
-    if (sans) addLog(`sans: ${sans}`);
-    addLog(`输出文件: ${finalOutputPath}`);
+    setIsAcmeIssuing(true);
     addLog("");
+    addLog("开始通过ACME签发证书...");
+    addLog(`待签发CSV: ${acmeInputPath}`);
+    addLog(`ACME目录: ${acmeDirectoryUrl}`);
+    addLog(`DNS服务商: ${dnsProvider}，等待生效 ${propagationSeconds} 秒`);
+    try {
+      const params: AcmeParams = {
+        input_path: acmeInputPath.trim(),
+        directory_url: acmeDirectoryUrl.trim(),
+        server_ca_cert_path: acmeServerCaPath.trim(),
+        account_key_path: acmeAccountKeyPath.trim(),
+        contact_email: acmeEmail.trim(),
+        eab_kid: acmeEabKid.trim(),
+        eab_hmac_key: acmeEabHmacKey.trim(),
+        dns_provider: dnsProvider,
+        dns_command: dnsCommand.trim(),
+        dns_webhook_url: dnsWebhookUrl.trim(),
+        propagation_seconds: propagationSeconds,
+        overwrite: acmeOverwrite,
+      };
+      const result = await invoke<AcmeResult>("acme_issue", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`ACME签发失败: ${errorMsg}`, "error");
+      message.error(`ACME签发证书时发生错误: ${errorMsg}`);
+    } finally {
+      setIsAcmeIssuing(false);
+    }
+  }
 
-    // 使用 setTimeout 让UI有时间更新，避免卡顿
-    setTimeout(async () => {
-      try {
-        // 调用Rust后端生成CSR
-        const params: GenerateParams = {
-          cn_range: cnRange.trim(),
-          subject_template: subjectTemplate.trim(),
-          key_type: keyType,
-          sign_hash_alg: signHashAlg,
-          not_before: notBefore.format('YYYY-MM-DDTHH:mm:ss+08:00'),
-          not_after: notAfter.format('YYYY-MM-DDTHH:mm:ss+08:00'),
-          unique_id: uniqueId.trim(),
-          sans: sans.trim(),
-          output_path: finalOutputPath,
-        };
-
-        // 解析CN范围以计算总数
-        /*const cnRangeMatch = cnRange.match(/(\w+)(\d+)-(\w+)(\d+)/);
-        if (cnRangeMatch) {
-          const startNum = parseInt(cnRangeMatch[2]);
-          const endNum = parseInt(cnRangeMatch[4]);
-        }*/
-
-        // 启动进度模拟
-        let currentProgress = 5; // 从5%开始
-        setProgress(5);
-        setProgressText("初始化...");
-        
-        const progressInterval = setInterval(() => {
-          currentProgress += Math.random() * 5 + 3; // 每次增加3-8%
-          if (currentProgress >= 90) {
-            currentProgress = 90; // 在90%停止，等待实际完成
-            clearInterval(progressInterval);
-            setProgressText("即将完成...");
-          }
-          
-          const roundedProgress = Math.floor(currentProgress);
-          setProgress(roundedProgress);
-          
-          // 更新进度文本
-          if (currentProgress < 25) {
-            setProgressText("初始化...");
-          } else if (currentProgress < 50) {
-            setProgressText("生成密钥对...");
-          } else if (currentProgress < 75) {
-            setProgressText("生成CSR...");
-          } else if (currentProgress < 90) {
-            setProgressText("保存文件...");
-          } else {
-            setProgressText("即将完成...");
-          }
-          
-          console.log(`进度更新: ${roundedProgress}%`); // 调试日志
-        }, 500);
-
-        // 监听进度事件
-        const unlisten = await invoke<GenerateResult>("generate_csr_batch", { params });
-
-        // 清除进度模拟
-        clearInterval(progressInterval);
-
-        // 处理结果
-        if (unlisten.success) {
-          addLog("");
-          addLog("========================================", "success");
-          addLog("生成完成！", "success");
-          addLog(`共生成 ${unlisten.total} 个CSR`, "success");
-          addLog(`输出文件: ${unlisten.output_path}`, "success");
-          addLog("========================================", "success");
-
-          setProgress(100);
-          setProgressText("完成");
-          setStatusText(`生成完成！共 ${unlisten.total} 个CSR`);
-
-          // 显示生成完成消息
-          message.success(`CSR生成完成！共生成 ${unlisten.total} 个，文件已保存到：${unlisten.output_path}`);
-        } else {
-          throw new Error(unlisten.message);
-        }
-      } catch (error) {
-        const errorMsg = error instanceof Error ? error.message : String(error);
-        addLog(`发生错误: ${errorMsg}`, "error");
-        setProgress(0);
-        setProgressText("错误");
-        setStatusText("生成失败");
-        message.error(`生成过程中发生错误: ${errorMsg}`);
-      } finally {
-        setIsGenerating(false);
-      }
-    }, 100); // 100ms 延迟让UI有时间更新
+  // 通过EST服务器注册证书
+  async function enrollEst() {
+    if (!estInputPath.trim() || !estServerUrl.trim()) {
+      message.error("请填写待注册的CSV文件和EST服务地址！");
+      return;
+    }
+
+    setIsEnrolling(true);
+    addLog("");
+    addLog("开始EST注册...");
+    addLog(`待注册CSV: ${estInputPath}`);
+    addLog(`EST服务: ${estServerUrl}`);
+    try {
+      const params: EstParams = {
+        input_path: estInputPath.trim(),
+        server_url: estServerUrl.trim(),
+        server_ca_cert_path: estServerCaPath.trim(),
+        client_cert_path: estClientCertPath.trim(),
+        client_key_path: estClientKeyPath.trim(),
+        client_key_password: estClientKeyPassword,
+        username: estUsername.trim(),
+        password: estPassword,
+        max_retries: estMaxRetries,
+        overwrite: estOverwrite,
+      };
+      const result = await invoke<EstResult>("enroll_est", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`EST注册失败: ${errorMsg}`, "error");
+      message.error(`EST注册时发生错误: ${errorMsg}`);
+    } finally {
+      setIsEnrolling(false);
+    }
   }
 
-  return (
-    <div style={{ padding: '24px', maxWidth: '1200px', margin: '0 auto' }}>
-      <Typography.Title level={1} style={{ textAlign: 'center', marginBottom: '32px' }}>
-        <SettingOutlined style={{ marginRight: '12px' }} />
-        批量CSR生成器 v1.0
-      </Typography.Title>
+  // 通过SCEP注册证书
+  async function enrollScep() {
+    if (!scepInputPath.trim() || !scepServerUrl.trim()) {
+      message.error("请填写待注册的CSV文件和SCEP服务地址！");
+      return;
+    }
 
-      {/* 参数设置卡片 */}
-      <Card 
-        title={
-          <Space>
-            <SettingOutlined />
-            参数设置
-          </Space>
-        }
-        style={{ marginBottom: '24px' }}
-      >
-        <Form
-          form={form}
-          layout="vertical"
-          initialValues={{
-            cnRange,
-            subjectTemplate,
-            keyType,
-            signHashAlg,
-            notBefore,
-            notAfter,
-            uniqueId,
-            sans,
-            outputDir,
-          }}
-        >
-          {/* 通用名称范围 */}
-          <Form.Item
-            label="通用名称(CN)范围"
-            name="cnRange"
-            rules={[{ required: true, message: '请输入通用名称范围!' }]}
-            help="格式示例: YDL0001-YDL0010"
-          >
-            <Input
-              value={cnRange}
-              onChange={(e) => setCnRange(e.target.value)}
-              placeholder="格式示例: YDL0001-YDL0010"
-              disabled={isGenerating}
-            />
-          </Form.Item>
+    setIsScepEnrolling(true);
+    addLog("");
+    addLog("开始SCEP注册...");
+    addLog(`待注册CSV: ${scepInputPath}`);
+    addLog(`SCEP服务: ${scepServerUrl}`);
+    if (scepChallengePassword) {
+      addLog("将以填写的质询密码重新签名CSR");
+    }
+    try {
+      const params: ScepParams = {
+        input_path: scepInputPath.trim(),
+        server_url: scepServerUrl.trim(),
+        ca_identifier: scepCaIdentifier.trim(),
+        challenge_password: scepChallengePassword,
+        key_passphrase: keyPassphrase,
+        server_ca_cert_path: scepServerCaPath.trim(),
+        poll_interval_seconds: scepPollInterval,
+        max_polls: scepMaxPolls,
+        overwrite: scepOverwrite,
+      };
+      const result = await invoke<ScepResult>("enroll_scep", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`SCEP注册失败: ${errorMsg}`, "error");
+      message.error(`SCEP注册时发生错误: ${errorMsg}`);
+    } finally {
+      setIsScepEnrolling(false);
+    }
+  }
 
-          {/* Subject主题模板 */}
-          <Form.Item
-            label="Subject主题模板"
-            name="subjectTemplate"
-            rules={[{ required: true, message: '请输入Subject主题模板!' }]}
-            help='使用{CN}作为通用名称占位符，多值用逗号分隔，值中逗号用\,转义'
-          >
-            <Input
-              value={subjectTemplate}
-              onChange={(e) => setSubjectTemplate(e.target.value)}
-              placeholder="使用{CN}作为通用名称占位符"
-              disabled={isGenerating}
-            />
+  // 通过CMP注册证书
+  async function enrollCmp() {
+    if (!cmpInputPath.trim() || !cmpServerUrl.trim()) {
+      message.error("请填写待注册的CSV文件和CMP服务地址！");
+      return;
+    }
+    if (cmpProtection === "mac" && !cmpSharedSecret) {
+      message.error("共享密钥保护须填写共享密钥！");
+      return;
+    }
+    if (cmpProtection === "signature" && (!cmpClientCertPath.trim() || !cmpClientKeyPath.trim())) {
+      message.error("签名保护须选择客户端证书和私钥！");
+      return;
+    }
+
+    setIsCmpEnrolling(true);
+    addLog("");
+    addLog("开始CMP注册...");
+    addLog(`待注册CSV: ${cmpInputPath}`);
+    addLog(`CMP服务: ${cmpServerUrl}`);
+    addLog(`请求类型: ${cmpRequestType}，消息保护: ${cmpProtection === "mac" ? "共享密钥" : "客户端证书签名"}`);
+    try {
+      const params: CmpParams = {
+        input_path: cmpInputPath.trim(),
+        server_url: cmpServerUrl.trim(),
+        request_type: cmpRequestType,
+        protection: cmpProtection,
+        reference: cmpReference.trim(),
+        shared_secret: cmpSharedSecret,
+        client_cert_path: cmpClientCertPath.trim(),
+        client_key_path: cmpClientKeyPath.trim(),
+        client_key_password: cmpClientKeyPassword,
+        ca_cert_path: cmpCaCertPath.trim(),
+        key_passphrase: keyPassphrase,
+        server_ca_cert_path: cmpServerCaPath.trim(),
+        poll_interval_seconds: cmpPollInterval,
+        max_polls: cmpMaxPolls,
+        overwrite: cmpOverwrite,
+      };
+      const result = await invoke<CmpResult>("enroll_cmp", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`CMP注册失败: ${errorMsg}`, "error");
+      message.error(`CMP注册时发生错误: ${errorMsg}`);
+    } finally {
+      setIsCmpEnrolling(false);
+    }
+  }
+
+  // 通过EJBCA REST API注册证书
+  async function enrollEjbca() {
+    if (!ejbcaInputPath.trim() || !ejbcaServerUrl.trim()) {
+      message.error("请填写待注册的CSV文件和EJBCA REST API地址！");
+      return;
+    }
+    if (!ejbcaCaName.trim() || !ejbcaCertificateProfile.trim() || !ejbcaEndEntityProfile.trim()) {
+      message.error("请填写CA名称、证书配置和终端实体配置！");
+      return;
+    }
+
+    setIsEjbcaEnrolling(true);
+    addLog("");
+    addLog("开始EJBCA注册...");
+    addLog(`待注册CSV: ${ejbcaInputPath}`);
+    addLog(`EJBCA: ${ejbcaServerUrl}，CA: ${ejbcaCaName}`);
+    addLog(`证书配置: ${ejbcaCertificateProfile}，终端实体配置: ${ejbcaEndEntityProfile}`);
+    try {
+      const params: EjbcaParams = {
+        input_path: ejbcaInputPath.trim(),
+        server_url: ejbcaServerUrl.trim(),
+        certificate_profile_name: ejbcaCertificateProfile.trim(),
+        end_entity_profile_name: ejbcaEndEntityProfile.trim(),
+        certificate_authority_name: ejbcaCaName.trim(),
+        username: ejbcaUsername.trim(),
+        enrollment_code: ejbcaEnrollmentCode,
+        email: ejbcaEmail.trim(),
+        server_ca_cert_path: ejbcaServerCaPath.trim(),
+        client_cert_path: ejbcaClientCertPath.trim(),
+        client_key_path: ejbcaClientKeyPath.trim(),
+        client_key_password: ejbcaClientKeyPassword,
+        request_interval_ms: ejbcaRequestInterval,
+        max_retries: ejbcaMaxRetries,
+        overwrite: ejbcaOverwrite,
+      };
+      const result = await invoke<EjbcaResult>("enroll_ejbca", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`EJBCA注册失败: ${errorMsg}`, "error");
+      message.error(`EJBCA注册时发生错误: ${errorMsg}`);
+    } finally {
+      setIsEjbcaEnrolling(false);
+    }
+  }
+
+  // 通过AD CS注册证书
+  async function enrollAdcs() {
+    if (!adcsInputPath.trim() || !adcsTemplate.trim()) {
+      message.error("请填写待注册的CSV文件和证书模板！");
+      return;
+    }
+    if (adcsMethod === "web" ? !adcsServerUrl.trim() : !adcsConfig.trim()) {
+      message.error(adcsMethod === "web" ? "请填写证书服务地址！" : "请填写CA配置！");
+      return;
+    }
+
+    setIsAdcsEnrolling(true);
+    addLog("");
+    addLog("开始AD CS注册...");
+    addLog(`待注册CSV: ${adcsInputPath}`);
+    addLog(`CA: ${adcsMethod === "web" ? adcsServerUrl : adcsConfig}，证书模板: ${adcsTemplate}`);
+    try {
+      const params: AdcsParams = {
+        input_path: adcsInputPath.trim(),
+        method: adcsMethod,
+        server_url: adcsServerUrl.trim(),
+        config: adcsConfig.trim(),
+        template: adcsTemplate.trim(),
+        username: adcsUsername.trim(),
+        password: adcsPassword,
+        server_ca_cert_path: adcsServerCaPath.trim(),
+        overwrite: adcsOverwrite,
+      };
+      const result = await invoke<AdcsResult>("enroll_adcs", { params });
+      addLog(result.message, result.pending > 0 ? "warning" : "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`AD CS注册失败: ${errorMsg}`, "error");
+      message.error(`AD CS注册时发生错误: ${errorMsg}`);
+    } finally {
+      setIsAdcsEnrolling(false);
+    }
+  }
+
+  // 通过Vault PKI签发证书
+  async function signWithVault() {
+    if (!vaultInputPath.trim() || !vaultServerUrl.trim() || !vaultRole.trim()) {
+      message.error("请填写待签发的CSV文件、Vault地址和角色！");
+      return;
+    }
+    if (vaultAuthMethod === "token" ? !vaultToken : !vaultRoleId.trim()) {
+      message.error(vaultAuthMethod === "token" ? "请填写Vault令牌！" : "请填写AppRole的role_id！");
+      return;
+    }
+
+    setIsVaultSigning(true);
+    addLog("");
+    addLog("开始Vault签发...");
+    addLog(`待签发CSV: ${vaultInputPath}`);
+    addLog(`Vault: ${vaultServerUrl}，接口: ${vaultMount.trim() || "pki"}/sign/${vaultRole.trim()}`);
+    try {
+      const params: VaultParams = {
+        input_path: vaultInputPath.trim(),
+        server_url: vaultServerUrl.trim(),
+        mount: vaultMount.trim(),
+        role: vaultRole.trim(),
+        auth_method: vaultAuthMethod,
+        token: vaultToken,
+        role_id: vaultRoleId.trim(),
+        secret_id: vaultSecretId,
+        namespace: vaultNamespace.trim(),
+        ttl: vaultTtl.trim(),
+        server_ca_cert_path: vaultServerCaPath.trim(),
+        overwrite: vaultOverwrite,
+      };
+      const result = await invoke<VaultResult>("sign_with_vault", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`Vault签发失败: ${errorMsg}`, "error");
+      message.error(`Vault签发时发生错误: ${errorMsg}`);
+    } finally {
+      setIsVaultSigning(false);
+    }
+  }
+
+  // 通过AWS Private CA签发证书
+  async function issueWithPca() {
+    if (!pcaInputPath.trim() || !pcaCaArn.trim()) {
+      message.error("请填写待签发的CSV文件和CA的ARN！");
+      return;
+    }
+
+    setIsPcaIssuing(true);
+    addLog("");
+    addLog("开始AWS Private CA签发...");
+    addLog(`待签发CSV: ${pcaInputPath}`);
+    addLog(`CA: ${pcaCaArn.trim()}，签名算法: ${pcaSigningAlgorithm}`);
+    try {
+      const params: PcaParams = {
+        input_path: pcaInputPath.trim(),
+        certificate_authority_arn: pcaCaArn.trim(),
+        region: pcaRegion.trim(),
+        access_key_id: pcaAccessKeyId.trim(),
+        secret_access_key: pcaSecretAccessKey,
+        session_token: pcaSessionToken,
+        endpoint_url: pcaEndpointUrl.trim(),
+        signing_algorithm: pcaSigningAlgorithm,
+        template_arn: pcaTemplateArn.trim(),
+        validity_days: pcaValidityDays,
+        overwrite: pcaOverwrite,
+      };
+      const result = await invoke<PcaResult>("issue_pca", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`AWS Private CA签发失败: ${errorMsg}`, "error");
+      message.error(`AWS Private CA签发时发生错误: ${errorMsg}`);
+    } finally {
+      setIsPcaIssuing(false);
+    }
+  }
+
+  // 通过自定义REST接口签发证书
+  async function enrollWebhook() {
+    if (!webhookInputPath.trim() || !webhookUrl.trim() || !webhookBodyTemplate.trim()) {
+      message.error("请填写待签发的CSV文件、接口地址和请求体模板！");
+      return;
+    }
+
+    setIsWebhookEnrolling(true);
+    addLog("");
+    addLog("开始REST接口签发...");
+    addLog(`待签发CSV: ${webhookInputPath}`);
+    addLog(`接口: ${webhookMethod} ${webhookUrl.trim()}`);
+    try {
+      const params: WebhookParams = {
+        input_path: webhookInputPath.trim(),
+        url: webhookUrl.trim(),
+        method: webhookMethod,
+        content_type: webhookContentType.trim(),
+        body_template: webhookBodyTemplate,
+        headers: webhookHeaders,
+        auth_method: webhookAuthMethod,
+        username: webhookUsername.trim(),
+        password: webhookPassword,
+        token: webhookToken,
+        certificate_path: webhookCertificatePath.trim(),
+        chain_path: webhookChainPath.trim(),
+        server_ca_cert_path: webhookServerCaPath.trim(),
+        overwrite: webhookOverwrite,
+      };
+      const result = await invoke<WebhookResult>("enroll_webhook", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`REST接口签发失败: ${errorMsg}`, "error");
+      message.error(`REST接口签发时发生错误: ${errorMsg}`);
+    } finally {
+      setIsWebhookEnrolling(false);
+    }
+  }
+
+  // 导入离线签发的证书
+  async function importCertificates() {
+    if (!importInputPath.trim() || !importSourcePath.trim()) {
+      message.error("请选择生成结果CSV文件和证书目录或ZIP文件！");
+      return;
+    }
+
+    setIsImporting(true);
+    addLog("");
+    addLog("开始导入证书...");
+    addLog(`生成结果CSV: ${importInputPath}`);
+    addLog(`证书来源: ${importSourcePath}`);
+    try {
+      const params: ImportParams = {
+        input_path: importInputPath.trim(),
+        source_path: importSourcePath.trim(),
+        match_by: importMatchBy,
+        ca_bundle_path: importCaBundlePath.trim(),
+        export_pkcs12: importExportPkcs12,
+        pkcs12_password: importPkcs12Password,
+        key_passphrase: keyPassphrase,
+        overwrite: importOverwrite,
+      };
+      const result = await invoke<ImportResult>("import_certificates", { params });
+      addLog(result.message, result.unmatched > 0 ? "warning" : "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      if (result.pkcs12_dir) {
+        addLog(`PKCS#12目录: ${result.pkcs12_dir}`, "success");
+      }
+      setP12InputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`导入证书失败: ${errorMsg}`, "error");
+      message.error(`导入证书时发生错误: ${errorMsg}`);
+    } finally {
+      setIsImporting(false);
+    }
+  }
+
+  // 导出PKCS#12文件
+  async function exportPkcs12() {
+    if (!p12InputPath.trim()) {
+      message.error("请选择已签发的CSV文件！");
+      return;
+    }
+
+    setIsExporting(true);
+    addLog("");
+    addLog("开始导出PKCS#12文件...");
+    addLog(`已签发CSV: ${p12InputPath}`);
+    try {
+      const params: Pkcs12Params = {
+        input_path: p12InputPath.trim(),
+        password: p12Password,
+        key_passphrase: keyPassphrase,
+        chain_path: chainPath.trim(),
+      };
+      const result = await invoke<Pkcs12Result>("export_pkcs12", { params });
+      addLog(result.message, "success");
+      addLog(`PKCS#12目录: ${result.output_dir}`, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      message.success(`${result.message}，文件已保存到：${result.output_dir}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`导出失败: ${errorMsg}`, "error");
+      message.error(`导出PKCS#12时发生错误: ${errorMsg}`);
+    } finally {
+      setIsExporting(false);
+    }
+  }
+
+  // 在已连接的YubiKey上生成密钥对和CSR
+  async function provisionPiv() {
+    if (!PIV_KEY_TYPES.includes(keyType)) {
+      message.error("PIV仅支持 RSA_2048/3072/4096、EC_P-256/384 和 ED25519 密钥！");
+      return;
+    }
+    if (!pivPin) {
+      message.error("请输入PIV PIN！");
+      return;
+    }
+    if (!outputDir.trim()) {
+      message.error("请选择输出目录！");
+      return;
+    }
+
+    setIsProvisioning(true);
+    addLog("");
+    addLog("开始为已连接的YubiKey生成CSR...");
+    addLog(`PIV槽位: ${pivSlot}`);
+    addLog(`密钥类型: ${keyType}`);
+    try {
+      const params: PivParams = {
+        cn_range: cnRange.trim(),
+        range_radix: rangeRadix,
+        subject_template: subjectTemplate.trim(),
+        key_type: keyType,
+        sign_hash_alg: signHashAlg === "MatchIssuer" ? "SHA256" : signHashAlg,
+        slot: pivSlot,
+        pin: pivPin,
+        management_key: pivManagementKey.trim(),
+        touch_policy: pivTouchPolicy,
+        output_path: `${outputDir}/piv_${getFileTimestamp()}.csv`,
+      };
+      const result = await invoke<PivResult>("provision_piv", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setSignInputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`PIV制卡失败: ${errorMsg}`, "error");
+      message.error(`YubiKey生成CSR时发生错误: ${errorMsg}`);
+    } finally {
+      setIsProvisioning(false);
+    }
+  }
+
+  // 浏览目录，返回选择的路径
+  async function browseDirectory(): Promise<string | null> {
+    try {
+      const dirPath = await openDialog({
+        directory: true,
+        multiple: false,
+      });
+      return dirPath ? (dirPath as string) : null;
+    } catch (error) {
+      console.error("选择目录失败:", error);
+      return null;
+    }
+  }
+
+  // 为已有证书生成续期CSR
+  // 将已签发的CSV中的证书记入到期跟踪（在其他工具中签发或导入的证书）
+  async function trackCertificates() {
+    const path = await browseFile("CSV", ["csv"]);
+    if (!path) return;
+    try {
+      const count = await invoke<number>("track_certificates", { path });
+      addLog(`已记录 ${count} 个证书的到期时间: ${path}`, "success");
+      refreshExpiring();
+    } catch (error) {
+      addLog(`记录证书失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 以选中证书的来源CSV作为证书续期的输入，沿用原私钥
+  function renewSelectedCert() {
+    const cert = expiringCerts.find((c) => c.fingerprint === selectedCert);
+    if (!cert) return;
+    setRenewCertPath(cert.source_path);
+    addLog(`续期输入已设为 ${cert.cn} 的来源文件: ${cert.source_path}，确认参数后点击“生成续期CSR”`);
+  }
+
+  async function renewCertificates() {
+    if (!renewCertPath.trim()) {
+      message.error("请选择证书目录或CSV文件！");
+      return;
+    }
+    if (!outputDir.trim()) {
+      message.error("请选择输出目录！");
+      return;
+    }
+
+    setIsRenewing(true);
+    addLog("");
+    addLog("开始生成续期CSR...");
+    addLog(`已有证书: ${renewCertPath}`);
+    addLog(rotateKey ? `轮换密钥: ${keyType}` : "沿用原私钥");
+    try {
+      const params: RenewParams = {
+        cert_path: renewCertPath.trim(),
+        key_dir: renewKeyDir.trim(),
+        rotate_key: rotateKey,
+        key_type: keyType,
+        sign_hash_alg: signHashAlg === "MatchIssuer" ? "SHA256" : signHashAlg,
+        key_passphrase: keyPassphrase,
+        output_path: `${outputDir}/renew_${getFileTimestamp()}.csv`,
+      };
+      const result = await invoke<GenerateResult>("renew_from_certs", { params });
+      addLog(result.message, "success");
+      addLog(`输出文件: ${result.output_path}`, "success");
+      setSignInputPath(result.output_path);
+      message.success(`${result.message}，文件已保存到：${result.output_path}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`续期失败: ${errorMsg}`, "error");
+      message.error(`生成续期CSR时发生错误: ${errorMsg}`);
+    } finally {
+      setIsRenewing(false);
+    }
+  }
+
+  // 预览展开后的CN和第一个条目的Subject，不生成密钥
+  async function previewCnRange() {
+    try {
+      const params = {
+        cn_range: cnRange.trim(),
+        range_radix: rangeRadix,
+        cn_mode: cnMode,
+        count: randomCount,
+        hex_length: hexLength,
+        mac_range: macRange.trim(),
+        mac_separator: macSeparator,
+        mac_case: macCase,
+        input_csv_path: inputCsvPath.trim(),
+        exclude: exclude.trim(),
+        exclude_path: excludePath.trim(),
+        subject_template: subjectTemplate.trim(),
+        dn_string_types: dnStringTypes.trim(),
+        idn_keep_utf8: idnKeepUtf8,
+        unique_id: uniqueId.trim(),
+        unique_id_serial_number: uniqueIdSerialNumber,
+        key_type: keyType,
+        sign_hash_alg: signHashAlg,
+      };
+      const preview = await invoke<CnPreview>("preview_cn_range", { params, limit: PREVIEW_LIMIT });
+      addLog("");
+      addLog(`预览: 共 ${preview.total} 个CN`);
+      addLog(`开头: ${preview.head.join(", ")}`);
+      if (preview.tail.length > 0) addLog(`末尾: ${preview.tail.join(", ")}`);
+      addLog(`第一个Subject: ${preview.first_subject}`);
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`预览失败: ${errorMsg}`, "error");
+      message.error(`预览时发生错误: ${errorMsg}`);
+    }
+  }
+
+  // 检查单个CSR，结果写入日志
+  async function inspectCsr() {
+    if (!inspectInput.trim()) {
+      message.error("请选择CSR文件或粘贴CSR内容！");
+      return;
+    }
+    try {
+      const info = await invoke<CsrInfo>("inspect_csr", { csr: inspectInput.trim() });
+      addLog("");
+      addLog(`CSR检查: ${info.subject}`);
+      addLog(`公钥: ${info.key_type}（${info.key_bits} 位），SHA-256 ${info.public_key_sha256}`);
+      addLog(`签名算法: ${info.signature_algorithm}`, info.signature_valid ? "info" : "error");
+      if (!info.signature_valid) addLog("CSR自签名无效", "error");
+      if (info.sans.length > 0) addLog(`备用名称: ${info.sans.join(", ")}`);
+      for (const extension of info.extensions) {
+        addLog(`扩展 ${extension.name}${extension.critical ? "（关键）" : ""}: ${extension.value}`);
+      }
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`检查失败: ${errorMsg}`, "error");
+      message.error(`检查CSR时发生错误: ${errorMsg}`);
+    }
+  }
+
+  // 校验生成的CSV：CSR自签名、私钥匹配和通用名称重复
+  async function verifyOutput() {
+    if (!verifyPath.trim()) {
+      message.error("请选择生成的CSV文件！");
+      return;
+    }
+
+    setIsVerifying(true);
+    addLog("");
+    addLog(`开始校验: ${verifyPath}`);
+    try {
+      const params: VerifyParams = {
+        output_path: verifyPath.trim(),
+        key_passphrase: keyPassphrase,
+        csv_delimiter: csvDelimiter,
+        csv_encoding: csvEncoding,
+      };
+      const report = await invoke<VerifyReport>("verify_batch", { params });
+      for (const failure of report.failures) {
+        addLog(`第 ${failure.record} 条 ${failure.cn}: ${failure.message}`, "error");
+      }
+      if (report.success) {
+        addLog(report.message, "success");
+        message.success(report.message);
+      } else {
+        addLog(report.message, "error");
+        message.warning(`${report.message}，详情见日志`);
+      }
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`校验失败: ${errorMsg}`, "error");
+      message.error(`校验时发生错误: ${errorMsg}`);
+    } finally {
+      setIsVerifying(false);
+    }
+  }
+
+  // 读取最近的任务历史
+  async function loadJobHistory() {
+    try {
+      const jobs = await invoke<JobSummary[]>("list_jobs", { limit: 100 });
+      setJobHistory(jobs);
+      addLog(`任务历史: 共 ${jobs.length} 条${jobs.length === 100 ? "（仅显示最近100条）" : ""}`);
+    } catch (error) {
+      addLog(`读取任务历史失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 将任务历史中单个任务的详情写入日志
+  async function showJob(id: string) {
+    setSelectedJobId(id);
+    try {
+      const job = await invoke<JobRecord>("get_job", { id });
+      const state = { completed: "已完成", cancelled: "已取消或暂停", failed: "失败" }[job.state];
+      addLog("");
+      addLog(`任务 ${job.id}（${state}）`);
+      addLog(`执行用户: ${job.user || "未知"}`);
+      addLog(`开始时间: ${job.started_at}，耗时 ${(job.duration_ms / 1000).toFixed(1)} 秒`);
+      addLog(`生成 ${job.total} 个，失败 ${job.failed} 个`);
+      addLog(`输出文件: ${job.output_path}`);
+      addLog(`通用名称: ${job.params.input_csv_path || job.params.mac_range || job.params.cn_range}`);
+      addLog(`Subject模板: ${job.params.subject_template}`);
+      addLog(`密钥类型: ${job.params.key_type}，签名哈希算法: ${job.params.sign_hash_alg}`);
+      for (const failure of job.failures) {
+        addLog(`第 ${failure.index + 1} 个条目 ${failure.cn}: ${failure.error}`, "error");
+      }
+      addLog(job.message, job.state === "failed" ? "error" : "info");
+    } catch (error) {
+      addLog(`读取任务详情失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 重新生成所选任务中失败的条目并追加到原输出文件，私钥口令等机密参数取自当前表单
+  function retryFailed() {
+    const job = jobHistory.find((j) => j.id === selectedJobId);
+    if (!job) return;
+    setIsGenerating(true);
+    setLogs([]);
+    setProgress(0);
+    setProgressText("生成中...");
+    setStatusText("正在重试失败条目，请稍候...");
+    addLog("========================================");
+    addLog(`重试任务 ${job.id} 中失败的 ${job.failed} 个条目`);
+    addLog("========================================");
+    addLog(`输出文件: ${job.output_path}（追加，跳过已有的CN）`);
+    if (keyPassphrase) addLog("私钥加密: 使用当前表单中的口令");
+    addLog("");
+    const jobId = crypto.randomUUID();
+    const secrets = { ...buildParams("", false), job_id: jobId };
+    runJob(jobId, () => invoke<string>("retry_failed", { jobId: job.id, secrets })).then(loadJobHistory);
+  }
+
+  // 校验应用配置目录下审计日志的哈希链
+  async function verifyAuditLog() {
+    try {
+      const report = await invoke<AuditLogReport>("verify_audit_log", { path: "" });
+      addLog("");
+      addLog(report.message, report.success ? "success" : "error");
+      addLog(`最后一条有效记录的哈希: ${report.last_hash}`);
+      if (!report.success) message.warning("审计日志已被修改，详情见日志");
+    } catch (error) {
+      addLog(`校验审计日志失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 检查表单输入，有误时提示并返回false
+  async function validateInputs(): Promise<boolean> {
+    try {
+      // 使用 Ant Design 表单验证
+      await form.validateFields();
+    } catch (error) {
+      message.error("请检查表单输入！");
+      return false;
+    }
+
+    // 验证输入
+    if (!inputCsvPath.trim() && cnMode === "range" && !macRange.trim() && !cnRange.trim()) {
+      message.error("请输入通用名称范围！");
+      return false;
+    }
+    if (!subjectTemplate.trim()) {
+      message.error("请输入Subject主题模板！");
+      return false;
+    }
+    if (!validity.trim() && notBefore.isAfter(notAfter)) {
+      message.error("有效期开始时间不能晚于结束时间！");
+      return false;
+    }
+    if (signHashAlg === "MatchIssuer" && !issuerCertPath.trim()) {
+      message.error("签名哈希算法为MatchIssuer时请选择签发者证书！");
+      return false;
+    }
+    if (!outputDir.trim()) {
+      message.error("请选择输出目录！");
+      return false;
+    }
+    return true;
+  }
+
+  // 开始生成
+  async function startGeneration() {
+    if (!(await validateInputs())) return;
+
+    // 在选定目录中生成带时间戳的CSV文件，追加时写入指定的已有CSV
+    const timestamp = getFileTimestamp();
+    const fileName = `csr_batch_${timestamp}.csv`;
+    const append = appendPath.trim() !== "" && ["csv", "both"].includes(outputMode);
+    const finalOutputPath = append ? appendPath.trim() : `${outputDir}/${fileName}`;
+    const usePss = keyType.startsWith("RSA") && signatureScheme === "pss";
+
+    // 开始生成
+    setIsGenerating(true);
+    setLogs([]);
+    setProgress(0);
+    setProgressText("生成中...");
+    setStatusText("正在生成CSR，请稍候...");
+
+    addLog("========================================");
+    addLog("开始批量生成CSR");
+    addLog("========================================");
+    if (inputCsvPath.trim()) {
+      addLog(`输入CSV文件: ${inputCsvPath}`);
+    } else if (cnMode !== "range") {
+      addLog(`随机通用名称: ${cnMode === "uuid" ? "UUIDv4" : `${hexLength}位十六进制`}，共 ${randomCount} 个`);
+    } else if (macRange.trim()) {
+      addLog(`MAC地址范围: ${macRange}（分隔符: ${macSeparator}，${macCase === "lower" ? "小写" : "大写"}）`);
+    } else {
+      addLog(`通用名称范围: ${cnRange}`);
+      if (rangeRadix !== 10) addLog(`范围进制: ${rangeRadix}`);
+    }
+    if (exclude.trim()) addLog(`排除: ${exclude}`);
+    if (excludePath.trim()) addLog(`排除列表文件: ${excludePath}`);
+    if (profileId) addLog(`配置模板: ${profileId}`);
+    addLog(`密钥类型: ${keyType}`);
+    if (keyTypeMap.trim()) addLog(`按前缀指定密钥类型: ${keyTypeMap}`);
+    addLog(`签名哈希算法: ${signHashAlg}`);
+    if (signHashAlg === "MatchIssuer") addLog(`签发者证书: ${issuerCertPath}`);
+    if (allowLegacyAlgorithms) addLog("已允许SHA1等遗留算法，使用时将记录在结果警告中", "warning");
+    if (pqKeyType) addLog(`混合模式: 同时生成 ${pqKeyType} 后量子密钥和CSR`);
+    if (usePss) addLog(`签名方案: RSASSA-PSS（盐长度 ${pssSaltLength ?? "同摘要长度"}，MGF1 ${pssMgf1Hash || "同签名哈希"}）`);
+    addLog(`Subject模板: ${subjectTemplate}`);
+    if (dnStringTypes.trim()) addLog(`DN字符串类型: ${dnStringTypes.trim()}`);
+    addLog(`notBefore: ${notBefore.format('YYYY-MM-DDTHH:mm:ssZ')}`);
+    addLog(validity.trim() ? `有效期时长: ${validity.trim()}` : `notAfter: ${notAfter.format('YYYY-MM-DDTHH:mm:ssZ')}`);
+    if (uniqueId) addLog(`uniqueId: ${uniqueId}`);
+    if (uniqueIdSerialNumber) addLog("uniqueId写入Subject的serialNumber");
+    if (sans) addLog(`sans: ${sans}`);
+    if (idnKeepUtf8) addLog("国际化域名: 主题保留UTF-8");
+    if (keyPassphrase) addLog("私钥加密: 已启用");
+    if (challengePassword) addLog("challengePassword: 已设置");
+    if (keyUsage) addLog(`keyUsage: ${keyUsage}`);
+    if (extendedKeyUsage) addLog(`extendedKeyUsage: ${extendedKeyUsage}`);
+    if (certificateTemplate.trim()) addLog(`证书模板: ${certificateTemplate.trim()}`);
+    for (const ext of parseCustomExtensions(customExtensions)) {
+      addLog(`自定义扩展: ${ext.oid}${ext.critical ? "（关键）" : ""} = ${ext.value}`);
+    }
+    if (keyStore !== "file") addLog(`密钥存储: ${keyStore}（私钥不可导出，CSV中不含私钥）`);
+    if (existingKeyDir.trim()) addLog(`已有私钥目录: ${existingKeyDir}（不生成新密钥）`);
+    if (continueOnError) addLog("失败条目: 跳过并继续");
+    if (onCompleteCommand.trim()) addLog(`完成回调命令: ${onCompleteCommand.trim()}`);
+    if (webhookUrl.trim()) addLog(`完成回调Webhook: ${webhookUrl.trim()}`);
+    if (screenWeakKeys) addLog(`弱密钥筛查: ROCA、小素因子${weakKeyBlocklist.trim() ? `、黑名单 ${weakKeyBlocklist}` : ""}`);
+    addLog(`输出文件: ${finalOutputPath}${append ? "（追加，跳过已有的CN）" : ""}`);
+    if (columns.trim()) addLog(`输出列: ${columns}`);
+    if (csrEncoding !== "pem") addLog(`CSR编码: ${csrEncoding}`);
+    if (writeManifest) addLog(`清单: 写入各输出文件的SHA-256${manifestSigningKey.trim() ? "，并使用私钥签名" : ""}`);
+    if (splitKeys) addLog("拆分私钥: 私钥另存为 <输出文件名>_keys.zip");
+    if (sqlitePath.trim()) addLog(`SQLite任务数据库: ${sqlitePath}`);
+    if (historyDbPath.trim()) addLog(`历史索引: ${historyDbPath}（重叠时${historyConflict === "fail" ? "拒绝生成" : "警告"}）`);
+    if (CSV_OUTPUT_MODES.includes(outputMode)) {
+      if (maxRowsPerFile > 0) addLog(`每个CSV文件最多 ${maxRowsPerFile} 行，超过时拆分为多个文件`);
+      addLog(`CSV格式: 分隔符 ${csvDelimiter}, 编码 ${csvEncoding}${csvBom && csvEncoding === "utf-8" ? " (BOM)" : ""}, 换行 ${csvLineEnding.toUpperCase()}`);
+    }
+    addLog("");
+
+    // 使用 setTimeout 让UI有时间更新，避免卡顿
+    setTimeout(() => {
+      // 调用Rust后端生成CSR
+      const params: GenerateParams = {
+        ...buildParams(finalOutputPath, append),
+        job_id: crypto.randomUUID(),
+      };
+      runJob(params.job_id!, () => invoke<string>("generate_csr_batch", { params }));
+    }, 100); // 100ms 延迟让UI有时间更新
+  }
+
+  // 从断点继续已暂停或中断的任务，私钥口令等机密参数取自当前表单
+  function resumeCheckpoint() {
+    const checkpoint = checkpoints.find((c) => c.job_id === checkpointJobId);
+    if (!checkpoint) return;
+    setIsGenerating(true);
+    setLogs([]);
+    setProgress(0);
+    setProgressText("生成中...");
+    setStatusText("正在从断点继续，请稍候...");
+    addLog("========================================");
+    addLog(`从断点继续任务 ${checkpoint.job_id}`);
+    addLog("========================================");
+    addLog(`输出文件: ${checkpoint.params.output_path}`);
+    addLog(`上次已写入 ${checkpoint.written}/${checkpoint.total} 个（${dayjs(checkpoint.saved_at).format('YYYY-MM-DD HH:mm:ss')}），已写入的条目不再生成`);
+    if (keyPassphrase) addLog("私钥加密: 使用当前表单中的口令");
+    addLog("");
+    const secrets = buildParams("", false);
+    runJob(checkpoint.job_id, () => invoke<string>("resume_job", { jobId: checkpoint.job_id, secrets }));
+  }
+
+  // 执行已登记的生成任务并跟踪进度直到结束，start启动任务（新建或续传）
+  async function runJob(jobId: string, start: () => Promise<string>) {
+    jobIdRef.current = jobId;
+    try {
+      // 解析CN范围以计算总数
+      /*const cnRangeMatch = cnRange.match(/(\w+)(\d+)-(\w+)(\d+)/);
+      if (cnRangeMatch) {
+        const startNum = parseInt(cnRangeMatch[2]);
+        const endNum = parseInt(cnRangeMatch[4]);
+      }*/
+
+      // 监听后端推送的进度事件
+      setProgress(0);
+      setProgressText("初始化...");
+      const unlistenProgress = await listen<ProgressEvent>("csr-progress", (event) => {
+        if (event.payload.job_id !== jobId) return;
+        const { done, total, current_cn, eta_ms } = event.payload;
+        setProgress(Math.floor((done / total) * 100));
+        setProgressText(`生成中 ${done}/${total}`);
+        setStatusText(`最近完成: ${current_cn}，预计剩余 ${Math.ceil(eta_ms / 1000)} 秒`);
+      });
+
+      // 后端立即返回任务ID，任务结束时推送 csr-complete 事件
+      let unlistenComplete: (() => void) | undefined;
+      let result: GenerateResult;
+      let paused = false;
+      try {
+        const completion = new Promise<JobStatus>((resolve) => {
+          listen<JobStatus>("csr-complete", (event) => {
+            if (event.payload.job_id === jobId) resolve(event.payload);
+          }).then((unlisten) => (unlistenComplete = unlisten));
+        });
+        await start();
+        const status = await completion;
+        if (status.state === "failed" || !status.result) {
+          throw status.error ?? new Error("未知错误");
+        }
+        result = status.result;
+        paused = status.state === "paused";
+      } finally {
+        unlistenProgress();
+        unlistenComplete?.();
+      }
+
+      // 列出被跳过的失败条目
+      if (result.failed > 0) {
+        addLog("");
+        addLog(`${result.failed} 个条目生成失败，已跳过:`, "warning");
+        result.failures.forEach((failure) => {
+          addLog(`${failure.cn}: ${formatBatchError(failure.error)}`, "warning");
+        });
+        if (result.error_report_path) {
+          addLog(`错误报告: ${result.error_report_path}`, "warning");
+        }
+      }
+      for (const warning of result.warnings) {
+        addLog(`警告: ${warning}`, "warning");
+      }
+
+      // 处理结果
+      if (result.success && paused) {
+        addLog("");
+        addLog(`任务已暂停，已生成 ${result.total} 个CSR，可在"任务历史"中从断点继续`, "warning");
+        addLog(`输出文件: ${result.output_path}`, "warning");
+
+        setProgressText("已暂停");
+        setStatusText(`已暂停，已生成 ${result.total} 个CSR`);
+        message.warning("任务已暂停，可从断点继续");
+      } else if (result.success && result.cancelled) {
+        addLog("");
+        addLog(result.message, "warning");
+        addLog(`输出文件: ${result.output_path}`, "warning");
+
+        setProgressText("已取消");
+        setStatusText(result.message);
+        message.warning(result.message);
+      } else if (result.success) {
+        addLog("");
+        addLog("========================================", "success");
+        addLog("生成完成！", "success");
+        addLog(`共生成 ${result.total} 个CSR`, "success");
+        addLog(`输出文件: ${result.output_path}`, "success");
+        if (result.manifest_path) {
+          addLog(`清单: ${result.manifest_path}`, "success");
+        }
+        addLog("========================================", "success");
+        if (result.output_path.endsWith(".csv")) {
+          setSignInputPath(result.output_path);
+          setVerifyPath(result.output_path);
+        }
+
+        setProgress(100);
+        setProgressText("完成");
+        setStatusText(`生成完成！共 ${result.total} 个CSR`);
+
+        // 显示生成完成消息
+        if (result.failed > 0) {
+          message.warning(`${result.message}，失败原因见日志`);
+        } else {
+          message.success(`CSR生成完成！共生成 ${result.total} 个，文件已保存到：${result.output_path}`);
+        }
+      } else {
+        throw new Error(result.message);
+      }
+    } catch (error) {
+      const errorMsg = errorMessage(error);
+      addLog(`发生错误: ${errorMsg}`, "error");
+      setProgress(0);
+      setProgressText("错误");
+      setStatusText("生成失败");
+      message.error(`生成过程中发生错误: ${errorMsg}`);
+    } finally {
+      jobIdRef.current = "";
+      setIsGenerating(false);
+      refreshCheckpoints();
+    }
+  }
+
+  // 按当前表单参数将任务加入队列，每个任务输出到单独的文件
+  async function enqueueBatch() {
+    if (!(await validateInputs())) return;
+
+    const jobId = crypto.randomUUID();
+    const append = appendPath.trim() !== "" && ["csv", "both"].includes(outputMode);
+    const outputPath = append
+      ? appendPath.trim()
+      : `${outputDir}/csr_batch_${getFileTimestamp()}_${jobId.slice(0, 8)}.csv`;
+    const name = queueName.trim() || cnRange.trim();
+    queuedJobsRef.current[jobId] = name;
+    try {
+      const params: GenerateParams = { ...buildParams(outputPath, append), job_id: jobId };
+      await invoke<string>("enqueue_batch", { name: queueName.trim(), params });
+      addLog(`已加入队列: ${name}，输出文件 ${outputPath}`);
+      setQueueName("");
+    } catch (error) {
+      delete queuedJobsRef.current[jobId];
+      addLog(`加入队列失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 调整队列（并发数、顺序、暂停、取消），队列状态由 csr-queue 事件更新
+  async function queueCommand(command: string, args: Record<string, unknown>) {
+    try {
+      await invoke(command, args);
+    } catch (error) {
+      message.error(errorMessage(error));
+    }
+  }
+
+  // 按当前表单组装生成参数
+  function buildParams(outputPath: string, append: boolean): GenerateParams {
+    const usePss = keyType.startsWith("RSA") && signatureScheme === "pss";
+    return {
+      cn_range: cnRange.trim(),
+      range_radix: rangeRadix,
+      cn_mode: cnMode,
+      count: randomCount,
+      hex_length: hexLength,
+      mac_range: macRange.trim(),
+      mac_separator: macSeparator,
+      mac_case: macCase,
+      input_csv_path: inputCsvPath.trim(),
+      exclude: exclude.trim(),
+      exclude_path: excludePath.trim(),
+      subject_template: subjectTemplate.trim(),
+      dn_string_types: dnStringTypes.trim(),
+      key_type: keyType,
+      key_type_map: keyTypeMap.trim(),
+      sign_hash_alg: signHashAlg,
+      issuer_cert_path: signHashAlg === "MatchIssuer" ? issuerCertPath.trim() : "",
+      pq_key_type: pqKeyType,
+      signature_scheme: usePss ? "pss" : "pkcs1",
+      pss_salt_length: usePss ? pssSaltLength : null,
+      pss_mgf1_hash: usePss ? pssMgf1Hash : "",
+      allow_legacy_algorithms: allowLegacyAlgorithms,
+      not_before: notBefore.format('YYYY-MM-DDTHH:mm:ssZ'),
+      not_after: validity.trim() ? "" : notAfter.format('YYYY-MM-DDTHH:mm:ssZ'),
+      validity: validity.trim(),
+      not_after_offset: notAfterOffset.trim(),
+      unique_id: uniqueId.trim(),
+      unique_id_serial_number: uniqueIdSerialNumber,
+      sans: sans.trim(),
+      idn_keep_utf8: idnKeepUtf8,
+      output_path: outputPath,
+      output_mode: outputMode,
+      file_name_template: fileNameTemplate.trim(),
+      zip_password: outputMode === "zip" || splitKeys ? zipPassword : "",
+      split_keys: splitKeys,
+      sqlite_path: sqlitePath.trim(),
+      screen_weak_keys: screenWeakKeys,
+      weak_key_blocklist: screenWeakKeys ? weakKeyBlocklist.trim() : "",
+      history_db_path: historyDbPath.trim(),
+      history_conflict: historyConflict,
+      csv_delimiter: csvDelimiter,
+      csv_quote_style: csvQuoteStyle,
+      csv_line_ending: csvLineEnding,
+      csv_encoding: csvEncoding,
+      csv_bom: csvBom,
+      max_rows_per_file: append ? 0 : maxRowsPerFile,
+      append,
+      columns: columns.trim(),
+      csr_encoding: csrEncoding,
+      fingerprint_columns: fingerprintColumns,
+      manifest: writeManifest,
+      manifest_signing_key: writeManifest ? manifestSigningKey.trim() : "",
+      manifest_key_password: writeManifest ? manifestKeyPassword : "",
+      key_passphrase: keyPassphrase,
+      challenge_password: challengePassword,
+      key_usage: keyUsage.trim(),
+      extended_key_usage: extendedKeyUsage.trim(),
+      certificate_template: certificateTemplate.trim(),
+      extensions: parseCustomExtensions(customExtensions),
+      key_store: keyStore,
+      existing_key_dir: existingKeyDir.trim(),
+      continue_on_error: continueOnError,
+      error_report: continueOnError && errorReport,
+      on_complete_command: onCompleteCommand.trim(),
+      webhook_url: webhookUrl.trim(),
+    };
+  }
+
+  // 暂停生成，已写入的结果和断点保留，之后可从断点继续
+  async function pauseGeneration() {
+    if (!jobIdRef.current) {
+      return;
+    }
+    try {
+      await invoke<boolean>("pause_job", { jobId: jobIdRef.current });
+      addLog("已请求暂停，正在等待进行中的条目完成...", "warning");
+    } catch (error) {
+      addLog(`暂停失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 放弃选中的任务断点，已写入的输出文件保留
+  async function discardCheckpoint() {
+    if (!checkpointJobId) return;
+    try {
+      await invoke("discard_checkpoint", { jobId: checkpointJobId });
+      addLog(`已放弃任务 ${checkpointJobId} 的断点`);
+      refreshCheckpoints();
+    } catch (error) {
+      addLog(`放弃断点失败: ${errorMessage(error)}`, "error");
+    }
+  }
+
+  // 取消生成
+  async function cancelGeneration() {
+    if (!jobIdRef.current) {
+      return;
+    }
+    try {
+      await invoke<boolean>("cancel_generation", { jobId: jobIdRef.current });
+      addLog("已请求取消，正在等待进行中的条目完成...", "warning");
+    } catch (error) {
+      console.error("取消任务失败:", error);
+    }
+  }
+
+  return (
+    <div style={{ padding: '24px', maxWidth: '1200px', margin: '0 auto' }}>
+      <Typography.Title level={1} style={{ textAlign: 'center', marginBottom: '32px' }}>
+        <SettingOutlined style={{ marginRight: '12px' }} />
+        批量CSR生成器 v1.0
+      </Typography.Title>
+
+      {/* 参数设置卡片 */}
+      <Card 
+        title={
+          <Space>
+            <SettingOutlined />
+            参数设置
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form
+          form={form}
+          layout="vertical"
+          initialValues={{
+            cnRange,
+            subjectTemplate,
+            keyType,
+            signHashAlg,
+            notBefore,
+            notAfter,
+            uniqueId,
+            sans,
+            outputDir,
+          }}
+        >
+          {/* 任务配置 */}
+          <Form.Item
+            label="任务配置(可选)"
+            help="按名称保存当前表单，定期批次可直接载入后生成；不保存私钥口令、ZIP密码、质询密码等机密参数，载入后有效期从当前时间开始"
+          >
+            <Row gutter={16}>
+              <Col span={12}>
+                <Select
+                  value={undefined}
+                  onChange={loadPreset}
+                  placeholder="载入已保存的任务配置"
+                  disabled={isGenerating || presets.length === 0}
+                >
+                  {presets.map((preset) => (
+                    <Select.Option key={preset.name} value={preset.name}>
+                      {preset.name}（{dayjs(preset.saved_at).format('YYYY-MM-DD HH:mm')}）
+                    </Select.Option>
+                  ))}
+                </Select>
+              </Col>
+              <Col span={12}>
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={presetName}
+                    onChange={(e) => setPresetName(e.target.value)}
+                    placeholder="配置名称，同名覆盖"
+                    disabled={isGenerating}
+                  />
+                  <Button style={{ width: '100px' }} onClick={savePreset} disabled={isGenerating}>
+                    保存配置
+                  </Button>
+                </Input.Group>
+              </Col>
+            </Row>
+          </Form.Item>
+
+          {/* 通用名称模式 */}
+          <Form.Item
+            label="通用名称模式"
+            help="设备标识在签发时才分配时，可按数量生成随机UUID或随机十六进制字符串作为CN，同一批次内不重复"
+          >
+            <Input.Group compact>
+              <Select
+                style={{ width: '240px' }}
+                value={cnMode}
+                onChange={setCnMode}
+                disabled={isGenerating || !!inputCsvPath}
+              >
+                {CN_MODES.map((mode) => (
+                  <Select.Option key={mode.value} value={mode.value}>
+                    {mode.label}
+                  </Select.Option>
+                ))}
+              </Select>
+              <InputNumber
+                style={{ width: '160px' }}
+                min={1}
+                max={1000000}
+                value={randomCount}
+                onChange={(value) => setRandomCount(value ?? 1)}
+                addonBefore="数量"
+                disabled={isGenerating || !!inputCsvPath || cnMode === "range"}
+              />
+              <InputNumber
+                style={{ width: '160px' }}
+                min={1}
+                max={64}
+                value={hexLength}
+                onChange={(value) => setHexLength(value ?? 16)}
+                addonBefore="长度"
+                disabled={isGenerating || !!inputCsvPath || cnMode !== "hex"}
+              />
+            </Input.Group>
+          </Form.Item>
+
+          {/* 通用名称范围 */}
+          <Form.Item
+            label="通用名称(CN)范围"
+            name="cnRange"
+            rules={[{ required: !inputCsvPath && cnMode === "range" && !macRange, message: '请输入通用名称范围!' }]}
+            help="格式示例: YDL0001-YDL0010；计数器在中间时写作 ABC-{0001..0500}-CN；多个前缀写作 [SHA, PEK, CAN]0001-0100，按前缀×范围生成；末尾可加 step N（步长）和 pad N（补零宽度），起始值大于结束值时降序生成；多个范围或单个CN以逗号或换行分隔，如 YDL0001-YDL0010, ZTE0100-ZTE0105, SPECIAL-A1，重复的CN只生成一次"
+          >
+            <Input.TextArea
+              value={cnRange}
+              onChange={(e) => setCnRange(e.target.value)}
+              placeholder="格式示例: YDL0001-YDL0010"
+              autoSize={{ minRows: 1, maxRows: 6 }}
+              disabled={isGenerating || !!inputCsvPath || cnMode !== "range" || !!macRange}
+            />
+          </Form.Item>
+
+          {/* 通用名称范围进制 */}
+          <Form.Item
+            label="范围进制"
+            help="范围计数器的进制，例如十六进制范围 DEV00FA-DEV0110；十六进制和三十六进制的计数器须以数字开头"
+          >
+            <Select value={rangeRadix} onChange={setRangeRadix} disabled={isGenerating || !!inputCsvPath || cnMode !== "range"}>
+              {RANGE_RADIXES.map((radix) => (
+                <Select.Option key={radix.value} value={radix.value}>
+                  {radix.label}
+                </Select.Option>
+              ))}
+            </Select>
+          </Form.Item>
+
+          {/* MAC地址范围 */}
+          <Form.Item
+            label="MAC地址范围(可选)"
+            help="可选，按MAC地址生成CN，如 00:1A:2B:00:00:01 - 00:1A:2B:00:01:00，输入的分隔符不限；多个范围以逗号或换行分隔；设置后忽略CN范围"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 320px)' }}
+                value={macRange}
+                onChange={(e) => setMacRange(e.target.value)}
+                disabled={isGenerating || !!inputCsvPath || cnMode !== "range"}
+                placeholder="留空则使用通用名称范围"
+              />
+              <Select
+                style={{ width: '220px' }}
+                value={macSeparator}
+                onChange={setMacSeparator}
+                disabled={isGenerating || !!inputCsvPath || cnMode !== "range"}
+              >
+                {MAC_SEPARATORS.map((separator) => (
+                  <Select.Option key={separator.value} value={separator.value}>
+                    {separator.label}
+                  </Select.Option>
+                ))}
+              </Select>
+              <Select
+                style={{ width: '100px' }}
+                value={macCase}
+                onChange={setMacCase}
+                disabled={isGenerating || !!inputCsvPath || cnMode !== "range"}
+              >
+                <Select.Option value="upper">大写</Select.Option>
+                <Select.Option value="lower">小写</Select.Option>
+              </Select>
+            </Input.Group>
+          </Form.Item>
+
+          {/* 输入CSV文件 */}
+          <Form.Item
+            label="输入CSV/Excel文件(可选)"
+            help="可选，按文件逐行生成CSR（Excel读取第一个工作表），必须包含cn列，可选subject、sans、unique_id、not_before、not_after、privateKey、key_type列；设置后忽略CN范围和MAC地址范围。含privateKey列时使用已有私钥，可直接用上次生成的CSV续期"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={inputCsvPath}
+                onChange={(e) => setInputCsvPath(e.target.value)}
+                disabled={isGenerating}
+                placeholder="留空则使用通用名称范围"
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={browseInputCsv}
+                disabled={isGenerating}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          {/* 排除列表 */}
+          <Form.Item
+            label="排除的CN(可选)"
+            help="跳过已预留或已注册的CN，逗号分隔，可使用与CN范围相同的范围写法；排除列表文件每行一个CN或范围，#开头为注释"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(50% - 50px)' }}
+                value={exclude}
+                onChange={(e) => setExclude(e.target.value)}
+                disabled={isGenerating}
+                placeholder="如 YDL0005, YDL0100-YDL0120"
+              />
+              <Input
+                style={{ width: 'calc(50% - 50px)' }}
+                value={excludePath}
+                onChange={(e) => setExcludePath(e.target.value)}
+                disabled={isGenerating}
+                placeholder="排除列表文件"
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={browseExcludeFile}
+                disabled={isGenerating}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          {/* Subject主题模板 */}
+          <Form.Item
+            label="Subject主题模板"
+            name="subjectTemplate"
+            rules={[{ required: true, message: '请输入Subject主题模板!' }]}
+            help='使用{CN}作为通用名称占位符，另可使用{INDEX:4}、{DATE:%Y%m%d}、{UUID}、{RAND:8}，多值用逗号分隔，值中逗号用\,转义；RDN按书写顺序编码，用+连接的属性组成多值RDN，如 OU=[Dev]+OU=[Ops]'
+          >
+            <Input
+              value={subjectTemplate}
+              onChange={(e) => setSubjectTemplate(e.target.value)}
+              placeholder="使用{CN}作为通用名称占位符"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* DN字符串类型 */}
+          <Form.Item
+            label="DN字符串类型(可选)"
+            help="按属性强制PrintableString或UTF8String，如 C=printable,O=utf8；PrintableString仅允许字母、数字、空格和 '()+,-./:=?，含其他字符的条目生成失败；留空由OpenSSL选择（C为PrintableString，其余多为UTF8String）"
+          >
+            <Input
+              value={dnStringTypes}
+              onChange={(e) => setDnStringTypes(e.target.value)}
+              placeholder="如 C=printable,O=utf8"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 配置模板 */}
+          <Form.Item
+            label="配置模板(可选)"
+            help={
+              profiles.find((p) => p.id === profileId)?.description ??
+              "按证书类型预填密钥类型、签名哈希算法、密钥用途和备用名称，预填后仍可修改；模板保存在应用配置目录的profiles.json中，可编辑或新增"
+            }
+          >
+            <Select
+              value={profileId || undefined}
+              onChange={applyProfile}
+              placeholder="不使用模板"
+              allowClear
+              disabled={isGenerating}
+            >
+              {profiles.map((profile) => (
+                <Select.Option key={profile.id} value={profile.id}>
+                  {profile.name}
+                </Select.Option>
+              ))}
+            </Select>
+          </Form.Item>
+
+          {/* 密钥类型和签名哈希算法 */}
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item
+                label="密钥类型"
+                name="keyType"
+                rules={[{ required: true, message: '请选择密钥类型!' }]}
+              >
+                <Select
+                  value={keyType}
+                  onChange={setKeyType}
+                  disabled={isGenerating}
+                >
+                  {KEY_TYPES.map((type) => (
+                    <Select.Option key={type.value} value={type.value}>
+                      {type.label}
+                    </Select.Option>
+                  ))}
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item
+                label="签名哈希算法"
+                name="signHashAlg"
+                rules={[{ required: true, message: '请选择签名哈希算法!' }]}
+              >
+                <Select
+                  value={signHashAlg}
+                  onChange={setSignHashAlg}
+                  disabled={isGenerating}
+                >
+                  {SIGN_HASH_ALGORITHMS.map((alg) => (
+                    <Select.Option key={alg} value={alg}>
+                      {alg}
+                    </Select.Option>
+                  ))}
+                </Select>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {signHashAlg === "MatchIssuer" && (
+            <Form.Item
+              label="签发者证书"
+              help="PEM或DER格式，签名哈希算法取自签发者证书的签名算法（Ed25519/PSS等则按其密钥强度选择）；CA签发时使用CA证书，无需填写"
+            >
+              <Input
+                value={issuerCertPath}
+                onChange={(e) => setIssuerCertPath(e.target.value)}
+                placeholder="签发者证书路径"
+                disabled={isGenerating}
+              />
+            </Form.Item>
+          )}
+
+          {/* 遗留算法须显式允许 */}
+          <Form.Item help="SHA1已不被公共CA接受，默认拒绝使用；勾选后允许签名哈希或MGF1哈希为SHA1，实际使用时记录在结果警告中">
+            <Checkbox
+              checked={allowLegacyAlgorithms}
+              onChange={(e) => setAllowLegacyAlgorithms(e.target.checked)}
+              disabled={isGenerating}
+            >
+              允许SHA1等遗留算法
+            </Checkbox>
+          </Form.Item>
+
+          {/* 按前缀指定密钥类型 */}
+          <Form.Item
+            label="按前缀指定密钥类型(可选)"
+            help="格式如: GW=EC_P256, LEGACY=RSA_2048，多个前缀匹配时取最长的；输入CSV的key_type列优先，均未匹配时使用上方的密钥类型"
+          >
+            <Input
+              value={keyTypeMap}
+              onChange={(e) => setKeyTypeMap(e.target.value)}
+              placeholder="GW=EC_P256, LEGACY=RSA_2048"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 混合模式 */}
+          <Form.Item
+            label="混合模式(后量子)"
+            help="可选，每个CN额外生成一对后量子密钥和CSR，写入pqCsr/pqKeyPairType/pqPrivateKey列"
+          >
+            <Select value={pqKeyType} onChange={setPqKeyType} disabled={isGenerating}>
+              <Select.Option value="">不启用</Select.Option>
+              {KEY_TYPES.filter((type) => type.value.startsWith("ML_DSA")).map((type) => (
+                <Select.Option key={type.value} value={type.value}>
+                  {type.label}
+                </Select.Option>
+              ))}
+            </Select>
+          </Form.Item>
+
+          {/* 密钥存储 */}
+          <Form.Item
+            label="密钥存储"
+            help="在系统密钥存储中生成不可导出的密钥（支持RSA和EC_P-256/384/521，安全隔区仅EC_P-256），CSV中仅包含CSR和keyContainer列"
+          >
+            <Select value={keyStore} onChange={setKeyStore} disabled={isGenerating}>
+              {KEY_STORES.map((store) => (
+                <Select.Option key={store.value} value={store.value}>
+                  {store.label}
+                </Select.Option>
+              ))}
+            </Select>
+          </Form.Item>
+
+          {/* 已有私钥目录 */}
+          <Form.Item
+            label="已有私钥目录(可选)"
+            help="续期时使用已有私钥签名CSR，按<CN>.key或<CN>.pem读取，密钥类型按私钥识别；加密私钥使用下方的私钥加密口令"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={existingKeyDir}
+                onChange={(e) => setExistingKeyDir(e.target.value)}
+                disabled={isGenerating || keyStore !== "file"}
+                placeholder="留空则生成新密钥"
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={browseExistingKeyDir}
+                disabled={isGenerating || keyStore !== "file"}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          {/* RSA签名方案 */}
+          {keyType.startsWith("RSA") && (
+            <Row gutter={16}>
+              <Col span={8}>
+                <Form.Item label="签名方案" help="部分CA要求新申请使用RSASSA-PSS">
+                  <Select value={signatureScheme} onChange={setSignatureScheme} disabled={isGenerating}>
+                    {SIGNATURE_SCHEMES.map((scheme) => (
+                      <Select.Option key={scheme.value} value={scheme.value}>
+                        {scheme.label}
+                      </Select.Option>
+                    ))}
+                  </Select>
+                </Form.Item>
+              </Col>
+              <Col span={8}>
+                <Form.Item label="PSS盐长度(字节)" help="留空则与摘要长度相同">
+                  <InputNumber
+                    min={0}
+                    value={pssSaltLength}
+                    onChange={(value) => setPssSaltLength(value)}
+                    disabled={isGenerating || signatureScheme !== "pss"}
+                    style={{ width: "100%" }}
+                  />
+                </Form.Item>
+              </Col>
+              <Col span={8}>
+                <Form.Item label="MGF1哈希算法">
+                  <Select value={pssMgf1Hash} onChange={setPssMgf1Hash} disabled={isGenerating || signatureScheme !== "pss"}>
+                    <Select.Option value="">同签名哈希算法</Select.Option>
+                    {SIGN_HASH_ALGORITHMS.filter((alg) => alg !== "MatchIssuer").map((alg) => (
+                      <Select.Option key={alg} value={alg}>
+                        {alg}
+                      </Select.Option>
+                    ))}
+                  </Select>
+                </Form.Item>
+              </Col>
+            </Row>
+          )}
+
+          {/* 有效期 */}
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item
+                label="notBefore(有效期开始)"
+                name="notBefore"
+                rules={[{ required: true, message: '请选择有效期开始时间!' }]}
+              >
+                <DatePicker
+                  showTime
+                  value={notBefore}
+                  onChange={(date) => setNotBefore(date || dayjs())}
+                  disabled={isGenerating}
+                  style={{ width: '100%' }}
+                  format="YYYY-MM-DD HH:mm:ss"
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item
+                label="notAfter(有效期结束)"
+                name="notAfter"
+                rules={[{ required: true, message: '请选择有效期结束时间!' }]}
+              >
+                <DatePicker
+                  showTime
+                  value={notAfter}
+                  onChange={(date) => setNotAfter(date || dayjs().add(10, 'year'))}
+                  disabled={isGenerating || validity.trim() !== ""}
+                  style={{ width: '100%' }}
+                  format="YYYY-MM-DD HH:mm:ss"
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {/* 有效期时长 */}
+          <Form.Item
+            label="有效期时长(可选)"
+            help="如 397d、2y、1y6m（y年、m月、w周、d天、h小时），设置后notAfter由notBefore加时长计算，按本地时区的日历处理月末"
+          >
+            <Input
+              value={validity}
+              onChange={(e) => setValidity(e.target.value)}
+              placeholder="留空则使用notAfter"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 到期时间偏移 */}
+          <Form.Item
+            label="到期时间偏移(可选)"
+            help="按条目渲染后加到notAfter上，如 {INDEX}h 使每个证书依次晚1小时到期，避免整批证书同时到期"
+          >
+            <Input
+              value={notAfterOffset}
+              onChange={(e) => setNotAfterOffset(e.target.value)}
+              placeholder="留空则不偏移"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* uniqueId */}
+          <Form.Item
+            label="uniqueId(可选)"
+            name="uniqueId"
+            help="可选，下载证书时以该id作为文件夹名称，否则以证书序列号作为文件夹名称；可使用{CN}、{INDEX}、{UUID}等模板变量"
+          >
+            <Input
+              value={uniqueId}
+              onChange={(e) => setUniqueId(e.target.value)}
+              placeholder="可选，下载证书时以该id作为文件夹名称"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+          <Form.Item help="勾选后唯一ID同时写入Subject末尾的serialNumber属性（Subject模板中不能再有serialNumber）；serialNumber为PrintableString，只能使用字母、数字、空格和 '()+,-./:=?">
+            <Checkbox
+              checked={uniqueIdSerialNumber}
+              onChange={(e) => setUniqueIdSerialNumber(e.target.checked)}
+              disabled={isGenerating || !uniqueId.trim()}
+            >
+              写入Subject的serialNumber
+            </Checkbox>
+          </Form.Item>
+
+          {/* sans备用名称 */}
+          <Form.Item
+            label="sans备用名称(可选)"
+            name="sans"
+            help="格式如: dNSName=[domain.com,domain1.com];iPAddress=[127.0.0.1]，也可写作 DNS:a.com,IP:127.0.0.1；智能卡登录的UPN写作 UPN:{CN}@corp.example.com；写入CSR的备用名称扩展，可使用{CN}等模板变量"
+          >
+            <Input
+              value={sans}
+              onChange={(e) => setSans(e.target.value)}
+              placeholder="格式如: dNSName=[domain.com,domain1.com];iPAddress=[127.0.0.1]"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 国际化域名 */}
+          <Form.Item
+            label="国际化域名"
+            help="含中文标签的域名（如 服务器.example.com）在备用名称中始终转换为punycode（xn--...）；默认主题CN同样转换，勾选后CN保留UTF-8"
+          >
+            <Checkbox
+              checked={idnKeepUtf8}
+              onChange={(e) => setIdnKeepUtf8(e.target.checked)}
+              disabled={isGenerating}
+            >
+              主题CN保留UTF-8
+            </Checkbox>
+          </Form.Item>
+
+          {/* 私钥加密口令 */}
+          <Form.Item
+            label="私钥加密口令(可选)"
+            name="keyPassphrase"
+            help="可选，设置后私钥以加密PKCS#8格式(AES-256-CBC)写入CSV"
+          >
+            <Input.Password
+              value={keyPassphrase}
+              onChange={(e) => setKeyPassphrase(e.target.value)}
+              placeholder="留空则输出未加密私钥"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* CSR质询密码 */}
+          <Form.Item
+            label="质询密码(可选)"
+            name="challengePassword"
+            help="可选，写入CSR的challengePassword属性，可使用{CN}等模板变量"
+          >
+            <Input.Password
+              value={challengePassword}
+              onChange={(e) => setChallengePassword(e.target.value)}
+              placeholder="留空则不添加challengePassword属性"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 密钥用途 */}
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="密钥用途(可选)" help="逗号分隔，写入CSR请求扩展">
+                <Input
+                  value={keyUsage}
+                  onChange={(e) => setKeyUsage(e.target.value)}
+                  placeholder="如: digitalSignature,keyEncipherment"
+                  disabled={isGenerating}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="扩展密钥用途(可选)" help="逗号分隔，支持点分OID">
+                <Input
+                  value={extendedKeyUsage}
+                  onChange={(e) => setExtendedKeyUsage(e.target.value)}
+                  placeholder="如: serverAuth,clientAuth"
+                  disabled={isGenerating}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {/* 证书模板 */}
+          <Form.Item
+            label="AD CS证书模板(可选)"
+            help="填写模板名称时写入szOID_ENROLL_CERTTYPE扩展；填写模板OID时写入证书模板信息扩展，OID后可追加 :主版本[:次版本]"
+          >
+            <Input
+              value={certificateTemplate}
+              onChange={(e) => setCertificateTemplate(e.target.value)}
+              placeholder="如: WebServer 或 1.3.6.1.4.1.311.21.8.1.2:100:3"
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 自定义扩展 */}
+          <Form.Item
+            label="自定义扩展(可选)"
+            help="每行一个，格式为 OID=[critical,]值；值为十六进制DER（如 DER:30:03:02:01:01），或 UTF8:、IA5: 开头的文本；OID不能与密钥用途等已写入的扩展重复"
+          >
+            <Input.TextArea
+              value={customExtensions}
+              onChange={(e) => setCustomExtensions(e.target.value)}
+              placeholder="如: 1.3.6.1.4.1.99999.1=critical,UTF8:class-a"
+              autoSize={{ minRows: 1, maxRows: 6 }}
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 弱密钥筛查 */}
+          <Form.Item
+            label="弱密钥筛查"
+            help="开启后检查RSA公钥是否具有ROCA特征、含小素因子，或在黑名单（openssl-blacklist格式，如Debian的blacklist.RSA-2048）中，命中的条目按生成失败处理"
+          >
+            <Space>
+              <Checkbox
+                checked={screenWeakKeys}
+                onChange={(e) => setScreenWeakKeys(e.target.checked)}
+                disabled={isGenerating}
+              >
+                筛查弱密钥
+              </Checkbox>
+              <Input
+                style={{ width: '360px' }}
+                value={weakKeyBlocklist}
+                onChange={(e) => setWeakKeyBlocklist(e.target.value)}
+                placeholder="黑名单文件路径（可选）"
+                disabled={isGenerating || !screenWeakKeys}
+              />
+            </Space>
+          </Form.Item>
+
+          {/* 失败处理 */}
+          <Form.Item label="失败处理" help="开启后跳过生成失败的条目，结束时汇总失败原因">
+            <Space>
+              <Checkbox
+                checked={continueOnError}
+                onChange={(e) => setContinueOnError(e.target.checked)}
+                disabled={isGenerating}
+              >
+                跳过失败的条目继续生成
+              </Checkbox>
+              <Checkbox
+                checked={errorReport}
+                onChange={(e) => setErrorReport(e.target.checked)}
+                disabled={isGenerating || !continueOnError}
+              >
+                另存错误报告CSV
+              </Checkbox>
+            </Space>
+          </Form.Item>
+
+          {/* 完成回调 */}
+          <Form.Item
+            label="完成回调(可选)"
+            help="任务结束（完成、取消或失败）后执行命令或POST到Webhook，任务结果JSON写入命令的标准输入或作为请求体；命令可读取环境变量 CSR_JOB_ID、CSR_JOB_STATE、CSR_OUTPUT_PATH"
+          >
+            <Space>
+              <Input
+                style={{ width: '360px' }}
+                value={onCompleteCommand}
+                onChange={(e) => setOnCompleteCommand(e.target.value)}
+                placeholder="命令，如 ./submit-to-ca.sh"
+                disabled={isGenerating}
+              />
+              <Input
+                style={{ width: '360px' }}
+                value={webhookUrl}
+                onChange={(e) => setWebhookUrl(e.target.value)}
+                placeholder="Webhook地址，如 https://ci.example.com/hooks/csr"
+                disabled={isGenerating}
+              />
+            </Space>
+          </Form.Item>
+
+          {/* 输出方式 */}
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="输出方式">
+                <Select value={outputMode} onChange={setOutputMode} disabled={isGenerating}>
+                  {OUTPUT_MODES.map((mode) => (
+                    <Select.Option key={mode.value} value={mode.value}>
+                      {mode.label}
+                    </Select.Option>
+                  ))}
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="PEM文件名模板" help="可使用{CN}、{INDEX:4}、{DATE}、{KEYTYPE}等模板变量，如 {CN}_{KEYTYPE}_{DATE}.csr，生成<名称>.csr和<名称>.key，文件名重复时报错">
+                <Input
+                  value={fileNameTemplate}
+                  onChange={(e) => setFileNameTemplate(e.target.value)}
+                  disabled={isGenerating || !PEM_OUTPUT_MODES.includes(outputMode)}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {/* 拆分私钥 */}
+          <Form.Item help="输出中不含私钥，私钥另存为与输出文件同目录的 <名称>_keys.zip，CSV可直接交给CA；须设置ZIP密码或私钥加密口令">
+            <Checkbox
+              checked={splitKeys}
+              onChange={(e) => setSplitKeys(e.target.checked)}
+              disabled={isGenerating || outputMode === "sqlite" || keyStore !== "file"}
+            >
+              拆分私钥，单独保存到加密压缩包
+            </Checkbox>
+          </Form.Item>
+
+          {/* ZIP密码 */}
+          {(outputMode === "zip" || splitKeys) && (
+            <Form.Item
+              label="ZIP密码(可选)"
+              help="可选，设置后使用AES-256加密ZIP中的每个文件（拆分私钥时同样用于私钥压缩包）"
+            >
+              <Input.Password
+                value={zipPassword}
+                onChange={(e) => setZipPassword(e.target.value)}
+                placeholder="留空则不加密"
+                disabled={isGenerating}
+              />
+            </Form.Item>
+          )}
+
+          {/* 追加到已有CSV */}
+          <Form.Item
+            label="追加到已有CSV(可选)"
+            help="设置后不新建带时间戳的文件，而是校验该CSV的表头后在末尾追加，并跳过其中已有的CN（按cn列或subject中的CN），便于多次增量生成到同一文件"
+          >
+            <Input
+              value={appendPath}
+              onChange={(e) => setAppendPath(e.target.value)}
+              placeholder="留空则新建CSV文件"
+              disabled={isGenerating || !["csv", "both"].includes(outputMode)}
+            />
+          </Form.Item>
+
+          {/* CSR编码 */}
+          <Form.Item
+            label="CSR编码"
+            help="部分旧的注册系统要求不带PEM首尾行的单行Base64或二进制DER；选择DER时单独的CSR文件为DER，CSV等表格中的csr列为单行Base64"
+          >
+            <Select value={csrEncoding} onChange={setCsrEncoding} disabled={isGenerating}>
+              {CSR_ENCODINGS.map((item) => (
+                <Select.Option key={item.value} value={item.value}>
+                  {item.label}
+                </Select.Option>
+              ))}
+            </Select>
+          </Form.Item>
+
+          {/* 指纹列 */}
+          <Form.Item help="CA可据此去重，并在签发后按证书的SKI将证书与输出中的行对应">
+            <Checkbox
+              checked={fingerprintColumns}
+              onChange={(e) => setFingerprintColumns(e.target.checked)}
+              disabled={isGenerating}
+            >
+              输出公钥SHA-256指纹和主题密钥标识符(SKI)列
+            </Checkbox>
+          </Form.Item>
+
+          {/* 清单 */}
+          <Form.Item help="生成结束后写入 <名称>_manifest.json，列出各输出文件和每个CSR的SHA-256；设置签名私钥后另写分离签名 .sig 文件，接收方可验证文件未被篡改">
+            <Checkbox
+              checked={writeManifest}
+              onChange={(e) => setWriteManifest(e.target.checked)}
+              disabled={isGenerating}
+            >
+              写入带哈希的清单文件
+            </Checkbox>
+          </Form.Item>
+          {writeManifest && (
+            <Row gutter={16}>
+              <Col span={12}>
+                <Form.Item label="清单签名私钥(可选)">
+                  <Input
+                    value={manifestSigningKey}
+                    onChange={(e) => setManifestSigningKey(e.target.value)}
+                    placeholder="PEM格式的RSA/EC/Ed25519私钥路径，留空则不签名"
+                    disabled={isGenerating}
+                  />
+                </Form.Item>
+              </Col>
+              <Col span={12}>
+                <Form.Item label="签名私钥口令(可选)">
+                  <Input.Password
+                    value={manifestKeyPassword}
+                    onChange={(e) => setManifestKeyPassword(e.target.value)}
+                    disabled={isGenerating || !manifestSigningKey.trim()}
+                  />
+                </Form.Item>
+              </Col>
+            </Row>
+          )}
+
+          {/* 输出列 */}
+          <Form.Item
+            label="输出列(可选)"
+            help="逗号分隔的列名及顺序，可用“列名=表头”改写表头；可选列: cn, subject, signHashAlg, notBefore, notAfter, uniqueId, sans, csr, keyPairType, privateKey, privateKeyEncryption, pqCsr, pqKeyPairType, pqPrivateKey, keyContainer, publicKeySha256, subjectKeyId"
+          >
+            <Input
+              value={columns}
+              onChange={(e) => setColumns(e.target.value)}
+              placeholder="留空则输出全部列，如 cn=设备编号,subject,csr"
+              disabled={isGenerating || outputMode === "files" || outputMode === "sqlite"}
+            />
+          </Form.Item>
+
+          {/* CSV格式 */}
+          <Form.Item
+            label="CSV格式"
+            help="按导入系统的要求设置，如部分CA门户要求分号分隔的GBK文件；Excel直接打开UTF-8文件时需要BOM；每文件行数大于0时按行数拆分为 <名称>_001.csv、<名称>_002.csv ……，0为不拆分"
+          >
+            <Space wrap>
+              <Select
+                style={{ width: 130 }}
+                value={csvDelimiter}
+                onChange={setCsvDelimiter}
+                disabled={isGenerating || !CSV_OUTPUT_MODES.includes(outputMode)}
+              >
+                {CSV_DELIMITERS.map((item) => (
+                  <Select.Option key={item.value} value={item.value}>
+                    {item.label}
+                  </Select.Option>
+                ))}
+              </Select>
+              <Select
+                style={{ width: 130 }}
+                value={csvQuoteStyle}
+                onChange={setCsvQuoteStyle}
+                disabled={isGenerating || !CSV_OUTPUT_MODES.includes(outputMode)}
+              >
+                {CSV_QUOTE_STYLES.map((item) => (
+                  <Select.Option key={item.value} value={item.value}>
+                    {item.label}
+                  </Select.Option>
+                ))}
+              </Select>
+              <Select
+                style={{ width: 100 }}
+                value={csvLineEnding}
+                onChange={setCsvLineEnding}
+                disabled={isGenerating || !CSV_OUTPUT_MODES.includes(outputMode)}
+              >
+                <Select.Option value="lf">LF</Select.Option>
+                <Select.Option value="crlf">CRLF</Select.Option>
+              </Select>
+              <Select
+                style={{ width: 110 }}
+                value={csvEncoding}
+                onChange={setCsvEncoding}
+                disabled={isGenerating || !CSV_OUTPUT_MODES.includes(outputMode)}
+              >
+                {CSV_ENCODINGS.map((item) => (
+                  <Select.Option key={item.value} value={item.value}>
+                    {item.label}
+                  </Select.Option>
+                ))}
+              </Select>
+              <InputNumber
+                style={{ width: 190 }}
+                min={0}
+                value={maxRowsPerFile}
+                onChange={(value) => setMaxRowsPerFile(value ?? 0)}
+                addonBefore="每文件行数"
+                placeholder="0为不拆分"
+                disabled={isGenerating || !["csv", "both"].includes(outputMode)}
+              />
+              <Checkbox
+                checked={csvBom}
+                onChange={(e) => setCsvBom(e.target.checked)}
+                disabled={isGenerating || !CSV_OUTPUT_MODES.includes(outputMode) || csvEncoding !== "utf-8"}
+              >
+                写入BOM
+              </Checkbox>
+            </Space>
+          </Form.Item>
+
+          {/* SQLite任务数据库 */}
+          <Form.Item
+            label="SQLite任务数据库(可选)"
+            help="可选，设置后在其他输出之外同时写入该数据库（jobs表和csrs表），多次生成累积在同一数据库中，可按时间、密钥类型或CN查询"
+          >
+            <Input
+              value={sqlitePath}
+              onChange={(e) => setSqlitePath(e.target.value)}
+              placeholder={outputMode === "sqlite" ? "留空则为与输出文件同名的.db文件" : "留空则不写入数据库"}
+              disabled={isGenerating}
+            />
+          </Form.Item>
+
+          {/* 历史索引 */}
+          <Form.Item
+            label="历史索引(可选)"
+            help="可选，本地SQLite文件，记录每次生成的CN和公钥指纹；新批次的CN已生成过，或公钥已用于其他CN时警告或拒绝生成，避免同一设备获得两个冲突的身份"
+          >
+            <Space>
+              <Input
+                style={{ width: '360px' }}
+                value={historyDbPath}
+                onChange={(e) => setHistoryDbPath(e.target.value)}
+                placeholder="留空则不检查"
+                disabled={isGenerating}
+              />
+              <Select
+                style={{ width: '140px' }}
+                value={historyConflict}
+                onChange={setHistoryConflict}
+                disabled={isGenerating || !historyDbPath.trim()}
+              >
+                <Select.Option value="warn">重叠时警告</Select.Option>
+                <Select.Option value="fail">重叠时拒绝</Select.Option>
+              </Select>
+            </Space>
+          </Form.Item>
+
+          {/* 输出目录 */}
+          <Form.Item
+            label="输出目录"
+            name="outputDir"
+            rules={[{ required: true, message: '请选择输出目录!' }]}
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={outputDir}
+                onChange={(e) => setOutputDir(e.target.value)}
+                disabled={isGenerating}
+                placeholder="请选择CSV文件输出目录"
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={browseOutputDir}
+                disabled={isGenerating}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          {/* 生成按钮 */}
+          <Form.Item style={{ textAlign: 'center', marginTop: '32px', marginBottom: '16px' }}>
+            <Button
+              type="primary"
+              size="large"
+              icon={<PlayCircleOutlined />}
+              onClick={startGeneration}
+              disabled={isGenerating}
+              loading={isGenerating}
+              style={{ minWidth: '200px', height: '48px', fontSize: '16px' }}
+            >
+              {isGenerating ? "生成中..." : "开始生成CSV"}
+            </Button>
+            <Button
+              size="large"
+              icon={<EyeOutlined />}
+              onClick={previewCnRange}
+              disabled={isGenerating}
+              style={{ marginLeft: '16px', height: '48px' }}
+            >
+              预览CN
+            </Button>
+            {isGenerating && (
+              <Button
+                size="large"
+                icon={<PauseOutlined />}
+                onClick={pauseGeneration}
+                style={{ marginLeft: '16px', height: '48px' }}
+              >
+                暂停
+              </Button>
+            )}
+            {isGenerating && (
+              <Button
+                danger
+                size="large"
+                icon={<StopOutlined />}
+                onClick={cancelGeneration}
+                style={{ marginLeft: '16px', height: '48px' }}
+              >
+                取消
+              </Button>
+            )}
+          </Form.Item>
+
+          {/* 进度条 */}
+          {(isGenerating || progress > 0) && (
+            <Form.Item style={{ marginBottom: '24px' }}>
+              <Space direction="vertical" style={{ width: '100%' }}>
+                <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center' }}>
+                  <Typography.Text strong>进度状态: {progressText}</Typography.Text>
+                  <Typography.Text>{progress}%</Typography.Text>
+                </div>
+                <Progress 
+                  percent={progress} 
+                  status={progress === 100 ? 'success' : progress === 0 && progressText === '错误' ? 'exception' : 'active'}
+                  strokeColor={progress === 100 ? '#52c41a' : undefined}
+                />
+                <Typography.Text type="secondary">{statusText}</Typography.Text>
+              </Space>
+            </Form.Item>
+          )}
+        </Form>
+      </Card>
+
+      {/* 任务队列卡片 */}
+      <Card
+        title={
+          <Space>
+            <UnorderedListOutlined />
+            任务队列
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Row gutter={16}>
+            <Col span={16}>
+              <Form.Item
+                label="任务名称(可选)"
+                help="按上方表单的当前参数加入队列，可依次为多个设备型号准备批次；为空时使用通用名称范围，每个任务输出到单独的CSV文件"
+              >
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 120px)' }}
+                    value={queueName}
+                    onChange={(e) => setQueueName(e.target.value)}
+                    placeholder="如设备型号"
+                  />
+                  <Button style={{ width: '120px' }} icon={<UnorderedListOutlined />} onClick={enqueueBatch}>
+                    加入队列
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="并发数" help="同时执行的队列任务数，直接开始的生成不计入">
+                <InputNumber
+                  min={1}
+                  max={8}
+                  value={queueStatus.concurrency}
+                  onChange={(value) => value && queueCommand("set_queue_concurrency", { concurrency: value })}
+                  style={{ width: '100%' }}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {queueStatus.running.map((job) => (
+            <Row key={job.job_id} justify="space-between" align="middle" style={{ marginTop: '8px' }}>
+              <Typography.Text>
+                {job.name} <Typography.Text type="success">执行中 {queueProgress[job.job_id] ?? ""}</Typography.Text>
+              </Typography.Text>
+              <Button size="small" danger icon={<StopOutlined />} onClick={() => queueCommand("cancel_queued_job", { jobId: job.job_id })}>
+                取消
+              </Button>
+            </Row>
+          ))}
+          {queueStatus.pending.map((job, index) => (
+            <Row key={job.job_id} justify="space-between" align="middle" style={{ marginTop: '8px' }}>
+              <Typography.Text type={job.paused ? "secondary" : undefined}>
+                {index + 1}. {job.name}{job.paused ? "（已暂停）" : "（等待中）"}
+              </Typography.Text>
+              <Space>
+                <Button
+                  size="small"
+                  icon={<ArrowUpOutlined />}
+                  disabled={index === 0}
+                  onClick={() => queueCommand("move_queued_job", { jobId: job.job_id, position: index - 1 })}
+                />
+                <Button
+                  size="small"
+                  icon={<ArrowDownOutlined />}
+                  disabled={index === queueStatus.pending.length - 1}
+                  onClick={() => queueCommand("move_queued_job", { jobId: job.job_id, position: index + 1 })}
+                />
+                <Button
+                  size="small"
+                  icon={job.paused ? <CaretRightOutlined /> : <PauseOutlined />}
+                  onClick={() => queueCommand(job.paused ? "resume_queued_job" : "pause_queued_job", { jobId: job.job_id })}
+                >
+                  {job.paused ? "恢复" : "暂停"}
+                </Button>
+                <Button size="small" danger onClick={() => queueCommand("cancel_queued_job", { jobId: job.job_id })}>
+                  移出
+                </Button>
+              </Space>
+            </Row>
+          ))}
+        </Form>
+      </Card>
+
+      {/* 内置CA签发卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(内置CA)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="待签发的CSV文件" help="批量生成输出的CSV文件，签发后另存为 _signed.csv 并增加certificate列">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={signInputPath}
+                onChange={(e) => setSignInputPath(e.target.value)}
+                disabled={isSigning}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setSignInputPath(path);
+                }}
+                disabled={isSigning}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="CA证书" help="PEM证书，或包含证书和私钥的 .p12/.pfx 文件">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={caCertPath}
+                    onChange={(e) => setCaCertPath(e.target.value)}
+                    disabled={isSigning}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer", "p12", "pfx"]);
+                      if (path) setCaCertPath(path);
+                    }}
+                    disabled={isSigning}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="CA私钥" help="PEM私钥，CA证书为PKCS#12文件时无需填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={caKeyPath}
+                    onChange={(e) => setCaKeyPath(e.target.value)}
+                    disabled={isSigning}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA私钥", ["pem", "key"]);
+                      if (path) setCaKeyPath(path);
+                    }}
+                    disabled={isSigning}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="CA私钥口令/PKCS#12密码">
+                <Input.Password
+                  value={caPassword}
+                  onChange={(e) => setCaPassword(e.target.value)}
+                  disabled={isSigning}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="有效天数" help="0表示使用CSV中的notBefore/notAfter">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={validityDays}
+                  onChange={(value) => setValidityDays(value ?? 0)}
+                  disabled={isSigning}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="序列号">
+                <Select value={serialPolicy} onChange={setSerialPolicy} disabled={isSigning}>
+                  {SERIAL_POLICIES.map((policy) => (
+                    <Select.Option key={policy.value} value={policy.value}>
+                      {policy.label}
+                    </Select.Option>
+                  ))}
+                </Select>
+              </Form.Item>
+            </Col>
+            {serialPolicy === "sequential" && (
+              <Col span={6}>
+                <Form.Item label="起始序列号">
+                  <InputNumber
+                    style={{ width: '100%' }}
+                    min={1}
+                    value={serialStart}
+                    onChange={(value) => setSerialStart(value ?? 1)}
+                    disabled={isSigning}
+                  />
+                </Form.Item>
+              </Col>
+            )}
+          </Row>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={signCertificates}
+              disabled={isGenerating || isSigning}
+              loading={isSigning}
+            >
+              签发证书
+            </Button>
+          </Form.Item>
+
+          {/* PKCS#12导出 */}
+          <Form.Item
+            label="已签发的CSV文件"
+            help="导出为每个CN单独的 .p12 文件，私钥加密时使用上方填写的私钥加密口令解密"
+            style={{ marginTop: '24px' }}
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={p12InputPath}
+                onChange={(e) => setP12InputPath(e.target.value)}
+                disabled={isExporting}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setP12InputPath(path);
+                }}
+                disabled={isExporting}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="PKCS#12密码">
+                <Input.Password
+                  value={p12Password}
+                  onChange={(e) => setP12Password(e.target.value)}
+                  disabled={isExporting}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="证书链文件(可选)" help="PEM格式，可包含多个CA证书">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={chainPath}
+                    onChange={(e) => setChainPath(e.target.value)}
+                    disabled={isExporting}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("证书链", ["pem", "crt", "cer"]);
+                      if (path) setChainPath(path);
+                    }}
+                    disabled={isExporting}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              icon={<SafetyCertificateOutlined />}
+              onClick={exportPkcs12}
+              disabled={isGenerating || isSigning || isExporting}
+              loading={isExporting}
+            >
+              导出PKCS#12
+            </Button>
+          </Form.Item>
+
+          {/* 证书吊销和CRL */}
+          <Row gutter={16} style={{ marginTop: '24px' }}>
+            <Col span={12}>
+              <Form.Item label="吊销证书" help="十六进制序列号（可含冒号），吊销记录保存在应用配置目录的job_history.db中">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 260px)' }}
+                    value={revokeSerial}
+                    onChange={(e) => setRevokeSerial(e.target.value)}
+                    placeholder="如 3F:A2:01"
+                  />
+                  <Select style={{ width: '160px' }} value={revokeReason} onChange={setRevokeReason}>
+                    {REVOKE_REASONS.map((reason) => (
+                      <Select.Option key={reason.value} value={reason.value}>
+                        {reason.label}
+                      </Select.Option>
+                    ))}
+                  </Select>
+                  <Button style={{ width: '100px' }} danger onClick={revokeCertificate}>
+                    吊销
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="CRL下次更新(天)">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={1}
+                  value={crlNextUpdateDays}
+                  onChange={(value) => setCrlNextUpdateDays(value ?? 7)}
+                  disabled={isIssuingCrl}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="CRL格式">
+                <Select value={crlFormat} onChange={setCrlFormat} disabled={isIssuingCrl}>
+                  <Select.Option value="PEM">PEM</Select.Option>
+                  <Select.Option value="DER">DER</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Space>
+              <Button icon={<UnorderedListOutlined />} onClick={showRevocations}>
+                查看吊销记录
+              </Button>
+              <Button
+                icon={<SafetyCertificateOutlined />}
+                onClick={generateCrl}
+                disabled={isGenerating || isSigning || isIssuingCrl}
+                loading={isIssuingCrl}
+              >
+                生成CRL
+              </Button>
+            </Space>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* ACME签发卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(ACME)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item
+            label="待签发的CSV文件"
+            help="批量生成输出的CSV文件，按SAN中的DNS名称（无SAN时为CN）申请证书，签发后另存为 _acme.csv 并增加certificate和chain列"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={acmeInputPath}
+                onChange={(e) => setAcmeInputPath(e.target.value)}
+                disabled={isAcmeIssuing}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setAcmeInputPath(path);
+                }}
+                disabled={isAcmeIssuing}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="ACME目录地址" help="如 https://acme.example.com/directory">
+                <Input
+                  value={acmeDirectoryUrl}
+                  onChange={(e) => setAcmeDirectoryUrl(e.target.value)}
+                  disabled={isAcmeIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="账户私钥" help="EC P-256 PEM私钥，文件不存在时自动生成并保存">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={acmeAccountKeyPath}
+                    onChange={(e) => setAcmeAccountKeyPath(e.target.value)}
+                    disabled={isAcmeIssuing}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("账户私钥", ["pem", "key"]);
+                      if (path) setAcmeAccountKeyPath(path);
+                    }}
+                    disabled={isAcmeIssuing}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={8}>
+              <Form.Item label="联系邮箱(可选)">
+                <Input
+                  value={acmeEmail}
+                  onChange={(e) => setAcmeEmail(e.target.value)}
+                  disabled={isAcmeIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="EAB密钥ID(可选)" help="CA要求外部账户绑定时填写">
+                <Input
+                  value={acmeEabKid}
+                  onChange={(e) => setAcmeEabKid(e.target.value)}
+                  disabled={isAcmeIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="EAB HMAC密钥(可选)" help="base64url编码">
+                <Input.Password
+                  value={acmeEabHmacKey}
+                  onChange={(e) => setAcmeEabHmacKey(e.target.value)}
+                  disabled={isAcmeIssuing}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="DNS服务商" help="用于添加和删除DNS-01验证的TXT记录">
+                <Select value={dnsProvider} onChange={setDnsProvider} disabled={isAcmeIssuing}>
+                  {DNS_PROVIDERS.map((provider) => (
+                    <Select.Option key={provider.value} value={provider.value}>
+                      {provider.label}
+                    </Select.Option>
+                  ))}
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              {dnsProvider === "command" ? (
+                <Form.Item
+                  label="DNS命令"
+                  help="环境变量 CSR_DNS_ACTION (add/remove)、CSR_DNS_NAME、CSR_DNS_VALUE 为操作、记录名和记录值"
+                >
+                  <Input
+                    value={dnsCommand}
+                    onChange={(e) => setDnsCommand(e.target.value)}
+                    disabled={isAcmeIssuing}
+                  />
+                </Form.Item>
+              ) : (
+                <Form.Item label="DNS Webhook地址" help='以POST发送 {"action", "name", "value"} JSON'>
+                  <Input
+                    value={dnsWebhookUrl}
+                    onChange={(e) => setDnsWebhookUrl(e.target.value)}
+                    disabled={isAcmeIssuing}
+                  />
+                </Form.Item>
+              )}
+            </Col>
+            <Col span={6}>
+              <Form.Item label="等待生效(秒)" help="添加TXT记录后等待DNS生效">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={propagationSeconds}
+                  onChange={(value) => setPropagationSeconds(value ?? 0)}
+                  disabled={isAcmeIssuing}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item label="ACME服务器CA证书(可选)" help="服务器证书不受系统信任时（如内网CA）填写">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={acmeServerCaPath}
+                onChange={(e) => setAcmeServerCaPath(e.target.value)}
+                disabled={isAcmeIssuing}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                  if (path) setAcmeServerCaPath(path);
+                }}
+                disabled={isAcmeIssuing}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item help="默认拒绝覆盖已有的 _acme.csv；中途签发失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={acmeOverwrite}
+              onChange={(e) => setAcmeOverwrite(e.target.checked)}
+              disabled={isAcmeIssuing}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={issueAcme}
+              disabled={isGenerating || isAcmeIssuing}
+              loading={isAcmeIssuing}
+            >
+              ACME签发
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* EST注册卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(EST)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item
+            label="待注册的CSV文件"
+            help="批量生成输出的CSV文件，逐个提交到 /simpleenroll，签发后另存为 _est.csv 并增加certificate和chain列"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={estInputPath}
+                onChange={(e) => setEstInputPath(e.target.value)}
+                disabled={isEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setEstInputPath(path);
+                }}
+                disabled={isEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item
+                label="EST服务地址"
+                help="如 https://est.example.com/.well-known/est，使用CA标签时在末尾加上标签"
+              >
+                <Input
+                  value={estServerUrl}
+                  onChange={(e) => setEstServerUrl(e.target.value)}
+                  disabled={isEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="EST服务器CA证书(可选)" help="服务器证书不受系统信任时填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={estServerCaPath}
+                    onChange={(e) => setEstServerCaPath(e.target.value)}
+                    disabled={isEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                      if (path) setEstServerCaPath(path);
+                    }}
+                    disabled={isEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="客户端证书(可选)" help="TLS客户端认证，PEM证书或包含证书和私钥的 .p12/.pfx 文件">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={estClientCertPath}
+                    onChange={(e) => setEstClientCertPath(e.target.value)}
+                    disabled={isEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("客户端证书", ["pem", "crt", "cer", "p12", "pfx"]);
+                      if (path) setEstClientCertPath(path);
+                    }}
+                    disabled={isEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="客户端私钥" help="PEM私钥，客户端证书为PKCS#12文件时无需填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={estClientKeyPath}
+                    onChange={(e) => setEstClientKeyPath(e.target.value)}
+                    disabled={isEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("客户端私钥", ["pem", "key"]);
+                      if (path) setEstClientKeyPath(path);
+                    }}
+                    disabled={isEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="私钥口令/PKCS#12密码">
+                <Input.Password
+                  value={estClientKeyPassword}
+                  onChange={(e) => setEstClientKeyPassword(e.target.value)}
+                  disabled={isEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="Basic认证用户名(可选)">
+                <Input
+                  value={estUsername}
+                  onChange={(e) => setEstUsername(e.target.value)}
+                  disabled={isEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="Basic认证密码">
+                <Input.Password
+                  value={estPassword}
+                  onChange={(e) => setEstPassword(e.target.value)}
+                  disabled={isEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="最大重试次数" help="连接失败、5xx或请求待处理时重试">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={estMaxRetries}
+                  onChange={(value) => setEstMaxRetries(value ?? 0)}
+                  disabled={isEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item help="默认拒绝覆盖已有的 _est.csv；中途注册失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={estOverwrite}
+              onChange={(e) => setEstOverwrite(e.target.checked)}
+              disabled={isEnrolling}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={enrollEst}
+              disabled={isGenerating || isEnrolling}
+              loading={isEnrolling}
+            >
+              EST注册
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* SCEP注册卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(SCEP)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item
+            label="待注册的CSV文件"
+            help="批量生成输出的CSV文件（RSA密钥，私钥加密时使用上方填写的私钥加密口令），签发后另存为 _scep.csv 并增加certificate和chain列"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={scepInputPath}
+                onChange={(e) => setScepInputPath(e.target.value)}
+                disabled={isScepEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setScepInputPath(path);
+                }}
+                disabled={isScepEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="SCEP服务地址" help="如 http://ca.example.com/certsrv/mscep/mscep.dll">
+                <Input
+                  value={scepServerUrl}
+                  onChange={(e) => setScepServerUrl(e.target.value)}
+                  disabled={isScepEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="CA标识(可选)" help="同一服务托管多个CA时填写">
+                <Input
+                  value={scepCaIdentifier}
+                  onChange={(e) => setScepCaIdentifier(e.target.value)}
+                  disabled={isScepEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="质询密码" help="填写时以该密码重新签名CSR；留空则使用CSR中已有的">
+                <Input.Password
+                  value={scepChallengePassword}
+                  onChange={(e) => setScepChallengePassword(e.target.value)}
+                  disabled={isScepEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="HTTPS服务器CA证书(可选)" help="服务地址为HTTPS且服务器证书不受系统信任时填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={scepServerCaPath}
+                    onChange={(e) => setScepServerCaPath(e.target.value)}
+                    disabled={isScepEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                      if (path) setScepServerCaPath(path);
+                    }}
+                    disabled={isScepEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="查询间隔(秒)" help="CA返回PENDING时">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={scepPollInterval}
+                  onChange={(value) => setScepPollInterval(value ?? 0)}
+                  disabled={isScepEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="最大查询次数">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={scepMaxPolls}
+                  onChange={(value) => setScepMaxPolls(value ?? 0)}
+                  disabled={isScepEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item help="默认拒绝覆盖已有的 _scep.csv；中途注册失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={scepOverwrite}
+              onChange={(e) => setScepOverwrite(e.target.checked)}
+              disabled={isScepEnrolling}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={enrollScep}
+              disabled={isGenerating || isScepEnrolling}
+              loading={isScepEnrolling}
+            >
+              SCEP注册
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* CMP注册卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(CMP)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item
+            label="待注册的CSV文件"
+            help="批量生成输出的CSV文件（cr/ir须包含privateKey列，私钥加密时使用上方填写的私钥加密口令），签发后另存为 _cmp.csv 并增加certificate和chain列"
+          >
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={cmpInputPath}
+                onChange={(e) => setCmpInputPath(e.target.value)}
+                disabled={isCmpEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setCmpInputPath(path);
+                }}
+                disabled={isCmpEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="CMP服务地址" help="如 http://ejbca.example.com/ejbca/publicweb/cmp/<别名>">
+                <Input
+                  value={cmpServerUrl}
+                  onChange={(e) => setCmpServerUrl(e.target.value)}
+                  disabled={isCmpEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="请求类型" help="p10cr提交CSR；cr/ir转为CRMF并签名POP">
+                <Select value={cmpRequestType} onChange={setCmpRequestType} disabled={isCmpEnrolling}>
+                  <Select.Option value="p10cr">p10cr (PKCS#10)</Select.Option>
+                  <Select.Option value="cr">cr (证书请求)</Select.Option>
+                  <Select.Option value="ir">ir (初始化请求)</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="消息保护">
+                <Select value={cmpProtection} onChange={setCmpProtection} disabled={isCmpEnrolling}>
+                  <Select.Option value="mac">共享密钥 (PBM)</Select.Option>
+                  <Select.Option value="signature">客户端证书签名</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {cmpProtection === "mac" ? (
+            <Row gutter={16}>
+              <Col span={12}>
+                <Form.Item label="引用值(senderKID)" help="EJBCA中为终端实体用户名或CMP别名配置的引用">
+                  <Input
+                    value={cmpReference}
+                    onChange={(e) => setCmpReference(e.target.value)}
+                    disabled={isCmpEnrolling}
+                  />
+                </Form.Item>
+              </Col>
+              <Col span={12}>
+                <Form.Item label="共享密钥">
+                  <Input.Password
+                    value={cmpSharedSecret}
+                    onChange={(e) => setCmpSharedSecret(e.target.value)}
+                    disabled={isCmpEnrolling}
+                  />
+                </Form.Item>
+              </Col>
+            </Row>
+          ) : (
+            <Row gutter={16}>
+              <Col span={9}>
+                <Form.Item label="客户端证书" help="PEM格式，签名保护消息">
+                  <Input.Group compact>
+                    <Input
+                      style={{ width: 'calc(100% - 100px)' }}
+                      value={cmpClientCertPath}
+                      onChange={(e) => setCmpClientCertPath(e.target.value)}
+                      disabled={isCmpEnrolling}
+                    />
+                    <Button
+                      style={{ width: '100px' }}
+                      icon={<FolderOpenOutlined />}
+                      onClick={async () => {
+                        const path = await browseFile("客户端证书", ["pem", "crt", "cer"]);
+                        if (path) setCmpClientCertPath(path);
+                      }}
+                      disabled={isCmpEnrolling}
+                    >
+                      浏览
+                    </Button>
+                  </Input.Group>
+                </Form.Item>
+              </Col>
+              <Col span={9}>
+                <Form.Item label="客户端私钥" help="PEM格式">
+                  <Input.Group compact>
+                    <Input
+                      style={{ width: 'calc(100% - 100px)' }}
+                      value={cmpClientKeyPath}
+                      onChange={(e) => setCmpClientKeyPath(e.target.value)}
+                      disabled={isCmpEnrolling}
+                    />
+                    <Button
+                      style={{ width: '100px' }}
+                      icon={<FolderOpenOutlined />}
+                      onClick={async () => {
+                        const path = await browseFile("私钥", ["pem", "key"]);
+                        if (path) setCmpClientKeyPath(path);
+                      }}
+                      disabled={isCmpEnrolling}
+                    >
+                      浏览
+                    </Button>
+                  </Input.Group>
+                </Form.Item>
+              </Col>
+              <Col span={6}>
+                <Form.Item label="私钥口令(可选)">
+                  <Input.Password
+                    value={cmpClientKeyPassword}
+                    onChange={(e) => setCmpClientKeyPassword(e.target.value)}
+                    disabled={isCmpEnrolling}
+                  />
+                </Form.Item>
+              </Col>
+            </Row>
+          )}
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="CA证书(可选)" help="作为消息接收方，并验证CA签名保护的响应">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={cmpCaCertPath}
+                    onChange={(e) => setCmpCaCertPath(e.target.value)}
+                    disabled={isCmpEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                      if (path) setCmpCaCertPath(path);
+                    }}
+                    disabled={isCmpEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="HTTPS服务器CA证书(可选)" help="服务地址为HTTPS且服务器证书不受系统信任时填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={cmpServerCaPath}
+                    onChange={(e) => setCmpServerCaPath(e.target.value)}
+                    disabled={isCmpEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                      if (path) setCmpServerCaPath(path);
+                    }}
+                    disabled={isCmpEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="查询间隔(秒)" help="CA返回waiting时">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={cmpPollInterval}
+                  onChange={(value) => setCmpPollInterval(value ?? 0)}
+                  disabled={isCmpEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="最大查询次数">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={cmpMaxPolls}
+                  onChange={(value) => setCmpMaxPolls(value ?? 0)}
+                  disabled={isCmpEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item help="默认拒绝覆盖已有的 _cmp.csv；中途注册失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={cmpOverwrite}
+              onChange={(e) => setCmpOverwrite(e.target.checked)}
+              disabled={isCmpEnrolling}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={enrollCmp}
+              disabled={isGenerating || isCmpEnrolling}
+              loading={isCmpEnrolling}
+            >
+              CMP注册
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* EJBCA注册卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(EJBCA)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="待注册的CSV文件" help="批量生成输出的CSV文件，签发后另存为 _ejbca.csv 并增加certificate和chain列">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={ejbcaInputPath}
+                onChange={(e) => setEjbcaInputPath(e.target.value)}
+                disabled={isEjbcaEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setEjbcaInputPath(path);
+                }}
+                disabled={isEjbcaEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="REST API地址" help="如 https://ejbca.example.com/ejbca/ejbca-rest-api">
+                <Input
+                  value={ejbcaServerUrl}
+                  onChange={(e) => setEjbcaServerUrl(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="CA名称">
+                <Input
+                  value={ejbcaCaName}
+                  onChange={(e) => setEjbcaCaName(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="证书配置" help="Certificate Profile">
+                <Input
+                  value={ejbcaCertificateProfile}
+                  onChange={(e) => setEjbcaCertificateProfile(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="终端实体配置" help="End Entity Profile">
+                <Input
+                  value={ejbcaEndEntityProfile}
+                  onChange={(e) => setEjbcaEndEntityProfile(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={8}>
+              <Form.Item label="用户名" help="{CN} 替换为CSR的通用名称，留空为 {CN}">
+                <Input
+                  value={ejbcaUsername}
+                  onChange={(e) => setEjbcaUsername(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="注册码" help="终端实体的密码">
+                <Input.Password
+                  value={ejbcaEnrollmentCode}
+                  onChange={(e) => setEjbcaEnrollmentCode(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="电子邮件(可选)">
+                <Input
+                  value={ejbcaEmail}
+                  onChange={(e) => setEjbcaEmail(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={9}>
+              <Form.Item label="TLS客户端证书" help="PEM，或包含证书和私钥的 .p12/.pfx 文件">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={ejbcaClientCertPath}
+                    onChange={(e) => setEjbcaClientCertPath(e.target.value)}
+                    disabled={isEjbcaEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("客户端证书", ["pem", "crt", "cer", "p12", "pfx"]);
+                      if (path) setEjbcaClientCertPath(path);
+                    }}
+                    disabled={isEjbcaEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={9}>
+              <Form.Item label="TLS客户端私钥" help="客户端证书为PEM时填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={ejbcaClientKeyPath}
+                    onChange={(e) => setEjbcaClientKeyPath(e.target.value)}
+                    disabled={isEjbcaEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("私钥", ["pem", "key"]);
+                      if (path) setEjbcaClientKeyPath(path);
+                    }}
+                    disabled={isEjbcaEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="私钥口令/PKCS#12密码">
+                <Input.Password
+                  value={ejbcaClientKeyPassword}
+                  onChange={(e) => setEjbcaClientKeyPassword(e.target.value)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="HTTPS服务器CA证书(可选)" help="EJBCA服务器证书不受系统信任时填写">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={ejbcaServerCaPath}
+                    onChange={(e) => setEjbcaServerCaPath(e.target.value)}
+                    disabled={isEjbcaEnrolling}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                      if (path) setEjbcaServerCaPath(path);
+                    }}
+                    disabled={isEjbcaEnrolling}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="请求间隔(毫秒)" help="主动限速，0为不限">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={ejbcaRequestInterval}
+                  onChange={(value) => setEjbcaRequestInterval(value ?? 0)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="最大重试次数" help="超出速率限制(429)或暂时性失败时">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={ejbcaMaxRetries}
+                  onChange={(value) => setEjbcaMaxRetries(value ?? 0)}
+                  disabled={isEjbcaEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item help="默认拒绝覆盖已有的 _ejbca.csv；中途注册失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={ejbcaOverwrite}
+              onChange={(e) => setEjbcaOverwrite(e.target.checked)}
+              disabled={isEjbcaEnrolling}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={enrollEjbca}
+              disabled={isGenerating || isEjbcaEnrolling}
+              loading={isEjbcaEnrolling}
+            >
+              EJBCA注册
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* AD CS注册卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(AD CS)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="待注册的CSV文件" help="批量生成输出的CSV文件，另存为 _adcs.csv 并增加certificate、chain和requestId列；选择之前的输出文件则取回挂起的请求">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={adcsInputPath}
+                onChange={(e) => setAdcsInputPath(e.target.value)}
+                disabled={isAdcsEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setAdcsInputPath(path);
+                }}
+                disabled={isAdcsEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="提交方式">
+                <Select value={adcsMethod} onChange={setAdcsMethod} disabled={isAdcsEnrolling}>
+                  <Select.Option value="web">Web注册 (certsrv)</Select.Option>
+                  <Select.Option value="certreq">certreq (Windows)</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              {adcsMethod === "web" ? (
+                <Form.Item label="证书服务地址" help="如 https://ca.example.com/certsrv">
+                  <Input
+                    value={adcsServerUrl}
+                    onChange={(e) => setAdcsServerUrl(e.target.value)}
+                    disabled={isAdcsEnrolling}
+                  />
+                </Form.Item>
+              ) : (
+                <Form.Item label="CA配置" help="如 ca.example.com\Example Issuing CA">
+                  <Input
+                    value={adcsConfig}
+                    onChange={(e) => setAdcsConfig(e.target.value)}
+                    disabled={isAdcsEnrolling}
+                  />
+                </Form.Item>
+              )}
+            </Col>
+            <Col span={6}>
+              <Form.Item label="证书模板" help="模板名称（非显示名称），如 WebServer">
+                <Input
+                  value={adcsTemplate}
+                  onChange={(e) => setAdcsTemplate(e.target.value)}
+                  disabled={isAdcsEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          {adcsMethod === "web" && (
+            <Row gutter={16}>
+              <Col span={6}>
+                <Form.Item label="用户名" help="如 EXAMPLE\user，IIS须启用Basic认证">
+                  <Input
+                    value={adcsUsername}
+                    onChange={(e) => setAdcsUsername(e.target.value)}
+                    disabled={isAdcsEnrolling}
+                  />
+                </Form.Item>
+              </Col>
+              <Col span={6}>
+                <Form.Item label="密码">
+                  <Input.Password
+                    value={adcsPassword}
+                    onChange={(e) => setAdcsPassword(e.target.value)}
+                    disabled={isAdcsEnrolling}
+                  />
+                </Form.Item>
+              </Col>
+              <Col span={12}>
+                <Form.Item label="HTTPS服务器CA证书(可选)" help="证书服务的服务器证书不受系统信任时填写">
+                  <Input.Group compact>
+                    <Input
+                      style={{ width: 'calc(100% - 100px)' }}
+                      value={adcsServerCaPath}
+                      onChange={(e) => setAdcsServerCaPath(e.target.value)}
+                      disabled={isAdcsEnrolling}
+                    />
+                    <Button
+                      style={{ width: '100px' }}
+                      icon={<FolderOpenOutlined />}
+                      onClick={async () => {
+                        const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                        if (path) setAdcsServerCaPath(path);
+                      }}
+                      disabled={isAdcsEnrolling}
+                    >
+                      浏览
+                    </Button>
+                  </Input.Group>
+                </Form.Item>
+              </Col>
+            </Row>
+          )}
+
+          <Form.Item help="默认拒绝覆盖已有的 _adcs.csv；中途失败时已签发的证书和挂起请求的requestId照常写入该文件">
+            <Checkbox
+              checked={adcsOverwrite}
+              onChange={(e) => setAdcsOverwrite(e.target.checked)}
+              disabled={isAdcsEnrolling}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={enrollAdcs}
+              disabled={isGenerating || isAdcsEnrolling}
+              loading={isAdcsEnrolling}
+            >
+              AD CS注册
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* Vault签发卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(Vault PKI)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="待签发的CSV文件" help="批量生成输出的CSV文件，签发后另存为 _vault.csv 并增加certificate和chain列">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={vaultInputPath}
+                onChange={(e) => setVaultInputPath(e.target.value)}
+                disabled={isVaultSigning}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setVaultInputPath(path);
+                }}
+                disabled={isVaultSigning}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="Vault地址" help="如 https://vault.example.com:8200">
+                <Input
+                  value={vaultServerUrl}
+                  onChange={(e) => setVaultServerUrl(e.target.value)}
+                  disabled={isVaultSigning}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={4}>
+              <Form.Item label="PKI挂载路径" help="默认 pki">
+                <Input
+                  value={vaultMount}
+                  onChange={(e) => setVaultMount(e.target.value)}
+                  disabled={isVaultSigning}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={4}>
+              <Form.Item label="角色" help="pki/sign/<角色>">
+                <Input
+                  value={vaultRole}
+                  onChange={(e) => setVaultRole(e.target.value)}
+                  disabled={isVaultSigning}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={4}>
+              <Form.Item label="有效期(可选)" help="如 720h，默认按角色">
+                <Input
+                  value={vaultTtl}
+                  onChange={(e) => setVaultTtl(e.target.value)}
+                  disabled={isVaultSigning}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="认证方式">
+                <Select value={vaultAuthMethod} onChange={setVaultAuthMethod} disabled={isVaultSigning}>
+                  <Select.Option value="token">令牌</Select.Option>
+                  <Select.Option value="approle">AppRole</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            {vaultAuthMethod === "token" ? (
+              <Col span={12}>
+                <Form.Item label="令牌">
+                  <Input.Password
+                    value={vaultToken}
+                    onChange={(e) => setVaultToken(e.target.value)}
+                    disabled={isVaultSigning}
+                  />
+                </Form.Item>
+              </Col>
+            ) : (
+              <>
+                <Col span={6}>
+                  <Form.Item label="role_id">
+                    <Input
+                      value={vaultRoleId}
+                      onChange={(e) => setVaultRoleId(e.target.value)}
+                      disabled={isVaultSigning}
+                    />
+                  </Form.Item>
+                </Col>
+                <Col span={6}>
+                  <Form.Item label="secret_id">
+                    <Input.Password
+                      value={vaultSecretId}
+                      onChange={(e) => setVaultSecretId(e.target.value)}
+                      disabled={isVaultSigning}
+                    />
+                  </Form.Item>
+                </Col>
+              </>
+            )}
+            <Col span={6}>
+              <Form.Item label="命名空间(可选)" help="Vault企业版">
+                <Input
+                  value={vaultNamespace}
+                  onChange={(e) => setVaultNamespace(e.target.value)}
+                  disabled={isVaultSigning}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item label="Vault服务器CA证书(可选)" help="Vault服务器证书不受系统信任时填写">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={vaultServerCaPath}
+                onChange={(e) => setVaultServerCaPath(e.target.value)}
+                disabled={isVaultSigning}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                  if (path) setVaultServerCaPath(path);
+                }}
+                disabled={isVaultSigning}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item help="默认拒绝覆盖已有的 _vault.csv；中途签发失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={vaultOverwrite}
+              onChange={(e) => setVaultOverwrite(e.target.checked)}
+              disabled={isVaultSigning}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={signWithVault}
+              disabled={isGenerating || isVaultSigning}
+              loading={isVaultSigning}
+            >
+              Vault签发
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* AWS Private CA签发卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(AWS Private CA)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="待签发的CSV文件" help="批量生成输出的CSV文件，签发后另存为 _pca.csv 并增加certificate、chain和certificateArn列">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={pcaInputPath}
+                onChange={(e) => setPcaInputPath(e.target.value)}
+                disabled={isPcaIssuing}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setPcaInputPath(path);
+                }}
+                disabled={isPcaIssuing}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={16}>
+              <Form.Item label="CA的ARN" help="如 arn:aws:acm-pca:us-east-1:123456789012:certificate-authority/...">
+                <Input
+                  value={pcaCaArn}
+                  onChange={(e) => setPcaCaArn(e.target.value)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="区域(可选)" help="默认取自CA的ARN">
+                <Input
+                  value={pcaRegion}
+                  onChange={(e) => setPcaRegion(e.target.value)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={8}>
+              <Form.Item label="访问密钥ID(可选)" help="留空时读取环境变量 AWS_ACCESS_KEY_ID">
+                <Input
+                  value={pcaAccessKeyId}
+                  onChange={(e) => setPcaAccessKeyId(e.target.value)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="秘密访问密钥(可选)" help="留空时读取环境变量 AWS_SECRET_ACCESS_KEY">
+                <Input.Password
+                  value={pcaSecretAccessKey}
+                  onChange={(e) => setPcaSecretAccessKey(e.target.value)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={8}>
+              <Form.Item label="会话令牌(可选)" help="使用临时凭证时填写">
+                <Input.Password
+                  value={pcaSessionToken}
+                  onChange={(e) => setPcaSessionToken(e.target.value)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="CA签名算法" help="须与CA密钥类型一致">
+                <Select value={pcaSigningAlgorithm} onChange={setPcaSigningAlgorithm} disabled={isPcaIssuing}>
+                  <Select.Option value="SHA256WITHRSA">SHA256WITHRSA</Select.Option>
+                  <Select.Option value="SHA384WITHRSA">SHA384WITHRSA</Select.Option>
+                  <Select.Option value="SHA512WITHRSA">SHA512WITHRSA</Select.Option>
+                  <Select.Option value="SHA256WITHECDSA">SHA256WITHECDSA</Select.Option>
+                  <Select.Option value="SHA384WITHECDSA">SHA384WITHECDSA</Select.Option>
+                  <Select.Option value="SHA512WITHECDSA">SHA512WITHECDSA</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={4}>
+              <Form.Item label="有效天数" help="0表示使用CSV中的notBefore/notAfter">
+                <InputNumber
+                  style={{ width: '100%' }}
+                  min={0}
+                  value={pcaValidityDays}
+                  onChange={(value) => setPcaValidityDays(value ?? 0)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={14}>
+              <Form.Item label="证书模板ARN(可选)" help="如 arn:aws:acm-pca:::template/EndEntityCertificate/V1">
+                <Input
+                  value={pcaTemplateArn}
+                  onChange={(e) => setPcaTemplateArn(e.target.value)}
+                  disabled={isPcaIssuing}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item label="服务端点(可选)" help="默认 https://acm-pca.<区域>.amazonaws.com，使用VPC端点时填写">
+            <Input
+              value={pcaEndpointUrl}
+              onChange={(e) => setPcaEndpointUrl(e.target.value)}
+              disabled={isPcaIssuing}
+            />
+          </Form.Item>
+
+          <Form.Item help="默认拒绝覆盖已有的 _pca.csv；中途签发失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={pcaOverwrite}
+              onChange={(e) => setPcaOverwrite(e.target.checked)}
+              disabled={isPcaIssuing}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={issueWithPca}
+              disabled={isGenerating || isPcaIssuing}
+              loading={isPcaIssuing}
+            >
+              AWS Private CA签发
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* 通用REST CA签发卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            签发证书(通用REST接口)
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="待签发的CSV文件" help="批量生成输出的CSV文件，签发后另存为 _webhook.csv 并增加certificate和chain列">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={webhookInputPath}
+                onChange={(e) => setWebhookInputPath(e.target.value)}
+                disabled={isWebhookEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setWebhookInputPath(path);
+                }}
+                disabled={isWebhookEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={4}>
+              <Form.Item label="请求方法">
+                <Select value={webhookMethod} onChange={setWebhookMethod} disabled={isWebhookEnrolling}>
+                  <Select.Option value="POST">POST</Select.Option>
+                  <Select.Option value="PUT">PUT</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={14}>
+              <Form.Item label="接口地址" help="可使用 {CN} 等占位符，如 https://ca.example.com/api/certs/{CN}">
+                <Input
+                  value={webhookUrl}
+                  onChange={(e) => setWebhookUrl(e.target.value)}
+                  disabled={isWebhookEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={6}>
+              <Form.Item label="内容类型" help="默认 application/json，JSON时占位符按字符串转义">
+                <Input
+                  value={webhookContentType}
+                  onChange={(e) => setWebhookContentType(e.target.value)}
+                  disabled={isWebhookEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item label="请求体模板" help="占位符: {CSR_PEM}、{CSR_B64}（DER的base64）、{CN}、{SANS}">
+            <Input.TextArea
+              value={webhookBodyTemplate}
+              onChange={(e) => setWebhookBodyTemplate(e.target.value)}
+              placeholder={'{"csr": "{CSR_PEM}", "name": "{CN}"}'}
+              autoSize={{ minRows: 3, maxRows: 8 }}
+              disabled={isWebhookEnrolling}
+            />
+          </Form.Item>
+
+          <Form.Item label="附加请求头(可选)" help="每行一个 名称: 值，值可使用占位符">
+            <Input.TextArea
+              value={webhookHeaders}
+              onChange={(e) => setWebhookHeaders(e.target.value)}
+              placeholder="X-Api-Key: ..."
+              autoSize={{ minRows: 1, maxRows: 8 }}
+              disabled={isWebhookEnrolling}
+            />
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="认证方式">
+                <Select value={webhookAuthMethod} onChange={setWebhookAuthMethod} disabled={isWebhookEnrolling}>
+                  <Select.Option value="none">无</Select.Option>
+                  <Select.Option value="basic">Basic</Select.Option>
+                  <Select.Option value="bearer">Bearer令牌</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            {webhookAuthMethod === "basic" && (
+              <>
+                <Col span={9}>
+                  <Form.Item label="用户名">
+                    <Input
+                      value={webhookUsername}
+                      onChange={(e) => setWebhookUsername(e.target.value)}
+                      disabled={isWebhookEnrolling}
+                    />
+                  </Form.Item>
+                </Col>
+                <Col span={9}>
+                  <Form.Item label="密码">
+                    <Input.Password
+                      value={webhookPassword}
+                      onChange={(e) => setWebhookPassword(e.target.value)}
+                      disabled={isWebhookEnrolling}
+                    />
+                  </Form.Item>
+                </Col>
+              </>
+            )}
+            {webhookAuthMethod === "bearer" && (
+              <Col span={18}>
+                <Form.Item label="令牌">
+                  <Input.Password
+                    value={webhookToken}
+                    onChange={(e) => setWebhookToken(e.target.value)}
+                    disabled={isWebhookEnrolling}
+                  />
+                </Form.Item>
+              </Col>
+            )}
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={12}>
+              <Form.Item label="证书的JSONPath" help="如 $.data.certificate，留空表示响应本身即为证书（PEM或base64）">
+                <Input
+                  value={webhookCertificatePath}
+                  onChange={(e) => setWebhookCertificatePath(e.target.value)}
+                  disabled={isWebhookEnrolling}
+                />
+              </Form.Item>
+            </Col>
+            <Col span={12}>
+              <Form.Item label="证书链的JSONPath(可选)" help="如 $.data.chain[*]">
+                <Input
+                  value={webhookChainPath}
+                  onChange={(e) => setWebhookChainPath(e.target.value)}
+                  disabled={isWebhookEnrolling}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item label="CA服务器的CA证书(可选)" help="服务器证书不受系统信任时填写">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={webhookServerCaPath}
+                onChange={(e) => setWebhookServerCaPath(e.target.value)}
+                disabled={isWebhookEnrolling}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                  if (path) setWebhookServerCaPath(path);
+                }}
+                disabled={isWebhookEnrolling}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item help="默认拒绝覆盖已有的 _webhook.csv；中途签发失败时已签发的证书照常写入该文件">
+            <Checkbox
+              checked={webhookOverwrite}
+              onChange={(e) => setWebhookOverwrite(e.target.checked)}
+              disabled={isWebhookEnrolling}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={enrollWebhook}
+              disabled={isGenerating || isWebhookEnrolling}
+              loading={isWebhookEnrolling}
+            >
+              REST接口签发
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* 证书导入卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            导入证书
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item label="生成结果CSV文件" help="批量生成输出的CSV文件，导入后另存为 _imported.csv 并增加certificate和chain列">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={importInputPath}
+                onChange={(e) => setImportInputPath(e.target.value)}
+                disabled={isImporting}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setImportInputPath(path);
+                }}
+                disabled={isImporting}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item label="证书目录或ZIP文件" help="CA返回的证书文件，支持PEM、DER和PKCS#7（.p7b），包括子目录；其中的CA证书用于构建证书链">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 200px)' }}
+                value={importSourcePath}
+                onChange={(e) => setImportSourcePath(e.target.value)}
+                disabled={isImporting}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await openDialog({ directory: true, multiple: false });
+                  if (path) setImportSourcePath(path as string);
+                }}
+                disabled={isImporting}
+              >
+                目录
+              </Button>
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("ZIP", ["zip"]);
+                  if (path) setImportSourcePath(path);
+                }}
+                disabled={isImporting}
+              >
+                ZIP
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Row gutter={16}>
+            <Col span={8}>
+              <Form.Item label="匹配方式">
+                <Select value={importMatchBy} onChange={setImportMatchBy} disabled={isImporting}>
+                  <Select.Option value="auto">自动（有公钥时按公钥）</Select.Option>
+                  <Select.Option value="public_key">公钥指纹</Select.Option>
+                  <Select.Option value="cn">通用名称(CN)</Select.Option>
+                </Select>
+              </Form.Item>
+            </Col>
+            <Col span={16}>
+              <Form.Item label="受信任的CA证书(可选)" help="PEM格式，设置后按其完整校验证书链（含有效期），否则只校验各级签名">
+                <Input.Group compact>
+                  <Input
+                    style={{ width: 'calc(100% - 100px)' }}
+                    value={importCaBundlePath}
+                    onChange={(e) => setImportCaBundlePath(e.target.value)}
+                    disabled={isImporting}
+                  />
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<FolderOpenOutlined />}
+                    onClick={async () => {
+                      const path = await browseFile("CA证书", ["pem", "crt", "cer"]);
+                      if (path) setImportCaBundlePath(path);
+                    }}
+                    disabled={isImporting}
+                  >
+                    浏览
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Row gutter={16}>
+            <Col span={8}>
+              <Form.Item help="所有行都须找到证书，私钥加密时使用上方填写的私钥加密口令解密">
+                <Checkbox
+                  checked={importExportPkcs12}
+                  onChange={(e) => setImportExportPkcs12(e.target.checked)}
+                  disabled={isImporting}
+                >
+                  同时导出PKCS#12
+                </Checkbox>
+              </Form.Item>
+            </Col>
+            {importExportPkcs12 && (
+              <Col span={16}>
+                <Form.Item label="PKCS#12密码">
+                  <Input.Password
+                    value={importPkcs12Password}
+                    onChange={(e) => setImportPkcs12Password(e.target.value)}
+                    disabled={isImporting}
+                  />
+                </Form.Item>
+              </Col>
+            )}
+          </Row>
+
+          <Form.Item help="默认拒绝覆盖已有的 _imported.csv">
+            <Checkbox
+              checked={importOverwrite}
+              onChange={(e) => setImportOverwrite(e.target.checked)}
+              disabled={isImporting}
+            >
+              覆盖已有的结果文件
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={importCertificates}
+              disabled={isGenerating || isImporting}
+              loading={isImporting}
+            >
+              导入证书
+            </Button>
           </Form.Item>
+        </Form>
+      </Card>
+
+      {/* YubiKey PIV制卡卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            YubiKey PIV制卡
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Typography.Paragraph type="secondary">
+            使用上方的通用名称范围、Subject模板、密钥类型和输出目录，依次在每个已连接的YubiKey上生成密钥对，
+            CSR由卡内私钥签名，私钥不离开设备。需要安装 YubiKey Manager (ykman)。
+          </Typography.Paragraph>
 
-          {/* 密钥类型和签名哈希算法 */}
           <Row gutter={16}>
             <Col span={12}>
-              <Form.Item
-                label="密钥类型"
-                name="keyType"
-                rules={[{ required: true, message: '请选择密钥类型!' }]}
-              >
+              <Form.Item label="PIV槽位">
                 <Select
-                  value={keyType}
-                  onChange={setKeyType}
-                  disabled={isGenerating}
-                >
-                  {KEY_TYPES.map((type) => (
-                    <Select.Option key={type.value} value={type.value}>
-                      {type.label}
-                    </Select.Option>
-                  ))}
-                </Select>
+                  value={pivSlot}
+                  onChange={setPivSlot}
+                  options={PIV_SLOTS}
+                  disabled={isProvisioning}
+                />
               </Form.Item>
             </Col>
             <Col span={12}>
-              <Form.Item
-                label="签名哈希算法"
-                name="signHashAlg"
-                rules={[{ required: true, message: '请选择签名哈希算法!' }]}
-              >
+              <Form.Item label="触摸策略">
                 <Select
-                  value={signHashAlg}
-                  onChange={setSignHashAlg}
-                  disabled={isGenerating}
-                >
-                  {SIGN_HASH_ALGORITHMS.map((alg) => (
-                    <Select.Option key={alg} value={alg}>
-                      {alg}
-                    </Select.Option>
-                  ))}
-                </Select>
+                  value={pivTouchPolicy}
+                  onChange={setPivTouchPolicy}
+                  options={PIV_TOUCH_POLICIES}
+                  disabled={isProvisioning}
+                />
               </Form.Item>
             </Col>
           </Row>
 
-          {/* 有效期 */}
           <Row gutter={16}>
             <Col span={12}>
-              <Form.Item
-                label="notBefore(有效期开始)"
-                name="notBefore"
-                rules={[{ required: true, message: '请选择有效期开始时间!' }]}
-              >
-                <DatePicker
-                  showTime
-                  value={notBefore}
-                  onChange={(date) => setNotBefore(date || dayjs())}
-                  disabled={isGenerating}
-                  style={{ width: '100%' }}
-                  format="YYYY-MM-DD HH:mm:ss"
+              <Form.Item label="PIV PIN" help="所有设备使用相同的PIN">
+                <Input.Password
+                  value={pivPin}
+                  onChange={(e) => setPivPin(e.target.value)}
+                  disabled={isProvisioning}
                 />
               </Form.Item>
             </Col>
             <Col span={12}>
-              <Form.Item
-                label="notAfter(有效期结束)"
-                name="notAfter"
-                rules={[{ required: true, message: '请选择有效期结束时间!' }]}
-              >
-                <DatePicker
-                  showTime
-                  value={notAfter}
-                  onChange={(date) => setNotAfter(date || dayjs().add(10, 'year'))}
-                  disabled={isGenerating}
+              <Form.Item label="管理密钥(可选)" help="十六进制，为空时使用设备默认或PIN保护的管理密钥">
+                <Input.Password
+                  value={pivManagementKey}
+                  onChange={(e) => setPivManagementKey(e.target.value)}
+                  disabled={isProvisioning}
+                />
+              </Form.Item>
+            </Col>
+          </Row>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={provisionPiv}
+              disabled={isGenerating || isProvisioning}
+              loading={isProvisioning}
+            >
+              生成YubiKey CSR
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* 证书到期卡片 */}
+      <Card
+        title={
+          <Space>
+            <HistoryOutlined />
+            证书到期
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Typography.Paragraph type="secondary">
+            内置CA、ACME、EST等签发和证书导入完成后，自动将输出CSV中的证书记入应用配置目录的job_history.db。
+            选择证书后点击“续期”，以其来源CSV作为下方证书续期的输入。
+          </Typography.Paragraph>
+
+          <Row gutter={16}>
+            <Col span={6}>
+              <Form.Item label="到期天数" help="列出该天数内到期的证书，已过期的证书始终列出">
+                <InputNumber
                   style={{ width: '100%' }}
-                  format="YYYY-MM-DD HH:mm:ss"
+                  min={0}
+                  max={3650}
+                  value={expiryDays}
+                  onChange={(value) => setExpiryDays(value ?? 30)}
                 />
               </Form.Item>
             </Col>
+            <Col span={18}>
+              <Form.Item
+                label="即将到期的证书"
+                help={expiringCerts.length > 0 ? `共 ${expiringCerts.length} 个证书需要续期` : undefined}
+              >
+                <Input.Group compact>
+                  <Select
+                    style={{ width: 'calc(100% - 320px)' }}
+                    value={selectedCert}
+                    onChange={setSelectedCert}
+                    placeholder="暂无即将到期的证书"
+                    notFoundContent="暂无即将到期的证书"
+                  >
+                    {expiringCerts.map((cert) => (
+                      <Select.Option key={cert.fingerprint} value={cert.fingerprint}>
+                        {cert.days_left < 0 ? `已过期${-cert.days_left}天` : `剩余${cert.days_left}天`}
+                        {" "}{cert.cn || cert.subject} 到期 {dayjs(cert.not_after).format('YYYY-MM-DD HH:mm')}，序列号 {cert.serial}
+                      </Select.Option>
+                    ))}
+                  </Select>
+                  <Button style={{ width: '100px' }} icon={<HistoryOutlined />} onClick={refreshExpiring}>
+                    刷新
+                  </Button>
+                  <Button style={{ width: '120px' }} icon={<FolderOpenOutlined />} onClick={trackCertificates}>
+                    记录证书
+                  </Button>
+                  <Button
+                    style={{ width: '100px' }}
+                    icon={<RedoOutlined />}
+                    onClick={renewSelectedCert}
+                    disabled={isRenewing || !selectedCert}
+                  >
+                    续期
+                  </Button>
+                </Input.Group>
+              </Form.Item>
+            </Col>
           </Row>
+        </Form>
+      </Card>
+
+      {/* 证书续期卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            证书续期
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Typography.Paragraph type="secondary">
+            从已有证书读取Subject和备用名称，为每个证书生成续期CSR，有效期长度与原证书相同。
+            默认沿用原私钥，轮换密钥时使用上方的密钥类型；私钥加密口令和输出目录沿用上方设置。
+          </Typography.Paragraph>
 
-          {/* uniqueId */}
           <Form.Item
-            label="uniqueId(可选)"
-            name="uniqueId"
-            help="可选，下载证书时以该id作为文件夹名称，否则以证书序列号作为文件夹名称"
+            label="已有证书"
+            help="证书目录（.pem/.crt/.cer/.der），或包含certificate列的CSV文件（如签发输出，其中的privateKey列作为原私钥）"
           >
-            <Input
-              value={uniqueId}
-              onChange={(e) => setUniqueId(e.target.value)}
-              placeholder="可选，下载证书时以该id作为文件夹名称"
-              disabled={isGenerating}
-            />
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 200px)' }}
+                value={renewCertPath}
+                onChange={(e) => setRenewCertPath(e.target.value)}
+                disabled={isRenewing}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseDirectory();
+                  if (path) setRenewCertPath(path);
+                }}
+                disabled={isRenewing}
+              >
+                目录
+              </Button>
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setRenewCertPath(path);
+                }}
+                disabled={isRenewing}
+              >
+                CSV
+              </Button>
+            </Input.Group>
           </Form.Item>
 
-          {/* sans备用名称 */}
+          <Form.Item label="原私钥目录(可选)" help="按<CN>.key或<CN>.pem读取原私钥，CSV中的privateKey列优先">
+            <Input.Group compact>
+              <Input
+                style={{ width: 'calc(100% - 100px)' }}
+                value={renewKeyDir}
+                onChange={(e) => setRenewKeyDir(e.target.value)}
+                disabled={isRenewing || rotateKey}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseDirectory();
+                  if (path) setRenewKeyDir(path);
+                }}
+                disabled={isRenewing || rotateKey}
+              >
+                浏览
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item>
+            <Checkbox
+              checked={rotateKey}
+              onChange={(e) => setRotateKey(e.target.checked)}
+              disabled={isRenewing}
+            >
+              轮换密钥（为每个证书生成新密钥）
+            </Checkbox>
+          </Form.Item>
+
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button
+              type="primary"
+              icon={<SafetyCertificateOutlined />}
+              onClick={renewCertificates}
+              disabled={isGenerating || isRenewing}
+              loading={isRenewing}
+            >
+              生成续期CSR
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* CSR检查卡片 */}
+      <Card
+        title={
+          <Space>
+            <EyeOutlined />
+            检查CSR
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
           <Form.Item
-            label="sans备用名称(可选)"
-            name="sans"
-            help="格式如: dNSName=[domain.com,domain1.com];iPAddress=[127.0.0.1]"
+            label="CSR"
+            help="CSR文件路径（PEM或DER），或直接粘贴PEM/单行Base64格式的CSR；Subject、公钥、签名算法和请求扩展写入下方日志"
           >
-            <Input
-              value={sans}
-              onChange={(e) => setSans(e.target.value)}
-              placeholder="格式如: dNSName=[domain.com,domain1.com];iPAddress=[127.0.0.1]"
-              disabled={isGenerating}
-            />
+            <Input.Group compact>
+              <Input.TextArea
+                style={{ width: 'calc(100% - 100px)' }}
+                autoSize={{ minRows: 1, maxRows: 6 }}
+                value={inspectInput}
+                onChange={(e) => setInspectInput(e.target.value)}
+              />
+              <Button
+                style={{ width: '100px' }}
+                icon={<FolderOpenOutlined />}
+                onClick={async () => {
+                  const path = await browseFile("CSR", ["csr", "pem", "der", "req"]);
+                  if (path) setInspectInput(path);
+                }}
+              >
+                浏览
+              </Button>
+            </Input.Group>
           </Form.Item>
 
-          {/* 输出目录 */}
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
+            <Button icon={<EyeOutlined />} onClick={inspectCsr}>
+              检查
+            </Button>
+          </Form.Item>
+        </Form>
+      </Card>
+
+      {/* 批量校验卡片 */}
+      <Card
+        title={
+          <Space>
+            <SafetyCertificateOutlined />
+            校验生成结果
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
           <Form.Item
-            label="输出目录"
-            name="outputDir"
-            rules={[{ required: true, message: '请选择输出目录!' }]}
+            label="生成的CSV"
+            help="逐条验证CSR自签名和私钥与CSR公钥是否匹配，并检查通用名称是否重复；私钥加密口令、分隔符和编码沿用上方设置"
           >
             <Input.Group compact>
               <Input
                 style={{ width: 'calc(100% - 100px)' }}
-                value={outputDir}
-                onChange={(e) => setOutputDir(e.target.value)}
-                disabled={isGenerating}
-                placeholder="请选择CSV文件输出目录"
+                value={verifyPath}
+                onChange={(e) => setVerifyPath(e.target.value)}
+                disabled={isVerifying}
               />
               <Button
                 style={{ width: '100px' }}
                 icon={<FolderOpenOutlined />}
-                onClick={browseOutputDir}
-                disabled={isGenerating}
+                onClick={async () => {
+                  const path = await browseFile("CSV", ["csv"]);
+                  if (path) setVerifyPath(path);
+                }}
+                disabled={isVerifying}
               >
                 浏览
               </Button>
             </Input.Group>
           </Form.Item>
 
-          {/* 生成按钮 */}
-          <Form.Item style={{ textAlign: 'center', marginTop: '32px', marginBottom: '16px' }}>
+          <Form.Item style={{ textAlign: 'center', marginBottom: 0 }}>
             <Button
-              type="primary"
-              size="large"
-              icon={<PlayCircleOutlined />}
-              onClick={startGeneration}
-              disabled={isGenerating}
-              loading={isGenerating}
-              style={{ minWidth: '200px', height: '48px', fontSize: '16px' }}
+              icon={<SafetyCertificateOutlined />}
+              onClick={verifyOutput}
+              disabled={isGenerating || isVerifying}
+              loading={isVerifying}
             >
-              {isGenerating ? "生成中..." : "开始生成CSV"}
+              校验
             </Button>
           </Form.Item>
+        </Form>
+      </Card>
 
-          {/* 进度条 */}
-          {(isGenerating || progress > 0) && (
-            <Form.Item style={{ marginBottom: '24px' }}>
-              <Space direction="vertical" style={{ width: '100%' }}>
-                <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center' }}>
-                  <Typography.Text strong>进度状态: {progressText}</Typography.Text>
-                  <Typography.Text>{progress}%</Typography.Text>
-                </div>
-                <Progress 
-                  percent={progress} 
-                  status={progress === 100 ? 'success' : progress === 0 && progressText === '错误' ? 'exception' : 'active'}
-                  strokeColor={progress === 100 ? '#52c41a' : undefined}
-                />
-                <Typography.Text type="secondary">{statusText}</Typography.Text>
-              </Space>
-            </Form.Item>
-          )}
+      {/* 任务历史卡片 */}
+      <Card
+        title={
+          <Space>
+            <HistoryOutlined />
+            任务历史
+          </Space>
+        }
+        style={{ marginBottom: '24px' }}
+      >
+        <Form layout="vertical">
+          <Form.Item
+            label="已执行的任务"
+            help="每次批量生成结束后记录参数（不含口令等机密参数）、执行用户、开始时间、耗时、数量、输出路径和失败条目，保存在应用配置目录的job_history.db中；选择任务后详情写入下方日志。有失败条目的CSV任务可点击“重试失败”只重新生成失败的条目并追加到原输出文件"
+          >
+            <Input.Group compact>
+              <Select
+                style={{ width: 'calc(100% - 220px)' }}
+                value={selectedJobId}
+                onChange={showJob}
+                placeholder="点击刷新读取任务历史"
+                notFoundContent="暂无任务"
+              >
+                {jobHistory.map((job) => (
+                  <Select.Option key={job.id} value={job.id}>
+                    {dayjs(job.started_at).format('YYYY-MM-DD HH:mm:ss')} {job.user} 生成{job.total}个
+                    {job.failed > 0 ? `，失败${job.failed}个` : ""}
+                    {job.state === "completed" ? "" : job.state === "cancelled" ? "（已取消）" : "（失败）"}
+                  </Select.Option>
+                ))}
+              </Select>
+              <Button style={{ width: '100px' }} icon={<HistoryOutlined />} onClick={loadJobHistory}>
+                刷新
+              </Button>
+              <Button
+                style={{ width: '120px' }}
+                icon={<RedoOutlined />}
+                onClick={retryFailed}
+                disabled={isGenerating || !jobHistory.some((j) => j.id === selectedJobId && j.state === "completed" && j.failed > 0)}
+              >
+                重试失败
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item
+            label="可继续的任务"
+            help="CSV输出（不按行数拆分、不拆分私钥）的任务在生成过程中记录断点，暂停或程序中途退出后可从最后写入的行继续，已生成的密钥不会重新生成；断点不保存口令等机密参数，继续时使用当前表单中的值"
+          >
+            <Input.Group compact>
+              <Select
+                style={{ width: 'calc(100% - 200px)' }}
+                value={checkpointJobId}
+                onChange={setCheckpointJobId}
+                placeholder="暂无可继续的任务"
+                notFoundContent="暂无可继续的任务"
+              >
+                {checkpoints.map((checkpoint) => (
+                  <Select.Option key={checkpoint.job_id} value={checkpoint.job_id}>
+                    {dayjs(checkpoint.saved_at).format('YYYY-MM-DD HH:mm:ss')} 已写入{checkpoint.written}/{checkpoint.total}个 {checkpoint.params.output_path}
+                  </Select.Option>
+                ))}
+              </Select>
+              <Button
+                style={{ width: '100px' }}
+                icon={<CaretRightOutlined />}
+                onClick={resumeCheckpoint}
+                disabled={isGenerating || !checkpointJobId}
+              >
+                继续
+              </Button>
+              <Button style={{ width: '100px' }} danger onClick={discardCheckpoint} disabled={!checkpointJobId}>
+                放弃
+              </Button>
+            </Input.Group>
+          </Form.Item>
+
+          <Form.Item
+            label="审计日志"
+            help="生成和PKCS#12导出时在应用配置目录的audit.log中追加任务开始、每个生成的条目、写入的文件、导出的私钥和任务结束等事件，每条记录包含上一条记录的哈希，修改或删除任意记录都能被发现"
+            style={{ marginBottom: 0 }}
+          >
+            <Button icon={<SafetyCertificateOutlined />} onClick={verifyAuditLog}>
+              校验审计日志
+            </Button>
+          </Form.Item>
         </Form>
       </Card>
 